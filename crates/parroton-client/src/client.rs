@@ -0,0 +1,201 @@
+use crate::error::ClientError;
+use parroton_core::action::Action;
+use parroton_core::assertion::{Assertion, PutAssertionPayload};
+use parroton_core::case::TestCase;
+use parroton_core::deletion::DeletionJob;
+use parroton_core::page::QueryResult;
+use parroton_core::parameter::{Parameter, ParameterType};
+use parroton_core::run::{Run, RunStatus, RunTestCasePayload, RunVerdict};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Statuses a waited run answers with, all of them carrying a verdict.
+const VERDICT_STATUSES: [StatusCode; 4] = [
+    StatusCode::OK,
+    StatusCode::UNPROCESSABLE_ENTITY,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+#[derive(Clone)]
+pub struct ParrotonClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl ParrotonClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        ParrotonClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    /// Sends every request with `token`, an API token or a session, as its bearer credential.
+    pub fn with_token(self, token: impl Into<String>) -> Self {
+        ParrotonClient { token: Some(token.into()), ..self }
+    }
+
+    /// Creates a test case from a recorded HAR file.
+    pub async fn upload_har(&self, name: &str, description: &str, har: Vec<u8>) -> Result<TestCase, ClientError> {
+        let form = Form::new()
+            .text("name", name.to_string())
+            .text("description", description.to_string())
+            .part("file", Part::bytes(har).file_name("recording.har"));
+        self.send(self.request(Method::POST, "/test-cases").multipart(form)).await
+    }
+
+    /// Appends the requests of a recorded HAR file to the test case, returning the new actions.
+    pub async fn append_har(&self, test_case_id: &str, har: Vec<u8>) -> Result<Vec<Action>, ClientError> {
+        let form = Form::new().part("file", Part::bytes(har).file_name("recording.har"));
+        self.send(self.request(Method::POST, &format!("/test-cases/{}/append-har", test_case_id)).multipart(form)).await
+    }
+
+    pub async fn list_test_cases(&self, next_page_key: Option<&str>) -> Result<QueryResult<TestCase>, ClientError> {
+        self.send(self.request(Method::GET, "/test-cases").query(&page_query(next_page_key))).await
+    }
+
+    pub async fn get_test_case(&self, id: &str) -> Result<TestCase, ClientError> {
+        self.send(self.request(Method::GET, &format!("/test-cases/{}", id))).await
+    }
+
+    /// Starts deleting the test case with everything under it; poll the returned job for completion.
+    pub async fn delete_test_case(&self, id: &str) -> Result<DeletionJob, ClientError> {
+        self.send(self.request(Method::DELETE, &format!("/test-cases/{}", id))).await
+    }
+
+    pub async fn get_deletion_job(&self, id: &str) -> Result<DeletionJob, ClientError> {
+        self.send(self.request(Method::GET, &format!("/deletion-jobs/{}", id))).await
+    }
+
+    pub async fn list_actions(&self, test_case_id: &str, next_page_key: Option<&str>) -> Result<QueryResult<Action>, ClientError> {
+        let path = format!("/test-cases/{}/actions", test_case_id);
+        self.send(self.request(Method::GET, &path).query(&page_query(next_page_key))).await
+    }
+
+    pub async fn list_parameters(&self, test_case_id: &str, action_id: &str, parameter_type: ParameterType) -> Result<QueryResult<Parameter>, ClientError> {
+        let path = format!("/test-cases/{}/actions/{}/parameters", test_case_id, action_id);
+        let parameter_type = match parameter_type {
+            ParameterType::Input => "Input",
+            ParameterType::Output => "Output",
+        };
+        self.send(self.request(Method::GET, &path).query(&[("parameter_type", parameter_type)])).await
+    }
+
+    /// Starts a run and returns right away; follow it with `poll_run`.
+    pub async fn run(&self, test_case_id: &str, payload: &RunTestCasePayload, mock: bool) -> Result<Run, ClientError> {
+        let request = self.request(Method::POST, &format!("/test-cases/{}/run", test_case_id))
+            .query(&[("mock", mock)])
+            .json(payload);
+        self.send(request).await
+    }
+
+    /// Runs the test case and waits for its verdict; failed, errored and timed out runs are
+    /// verdicts too, not errors.
+    pub async fn run_and_wait(&self, test_case_id: &str, payload: &RunTestCasePayload, mock: bool, timeout_secs: Option<u64>) -> Result<RunVerdict, ClientError> {
+        let mut request = self.request(Method::POST, &format!("/test-cases/{}/run", test_case_id))
+            .query(&[("wait", true), ("mock", mock)])
+            .json(payload);
+        if let Some(timeout_secs) = timeout_secs {
+            request = request.query(&[("timeout", timeout_secs)]);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        if VERDICT_STATUSES.contains(&status) {
+            if let Ok(verdict) = serde_json::from_str::<RunVerdict>(&text) {
+                return Ok(verdict);
+            }
+        }
+        Err(ClientError::from_response(status.as_u16(), &text))
+    }
+
+    pub async fn get_run(&self, test_case_id: &str, run_id: &str) -> Result<Run, ClientError> {
+        self.send(self.request(Method::GET, &format!("/test-cases/{}/runs/{}", test_case_id, run_id))).await
+    }
+
+    /// Runs of the test case, newest first.
+    pub async fn list_runs(&self, test_case_id: &str, next_page_key: Option<&str>) -> Result<QueryResult<Run>, ClientError> {
+        let path = format!("/test-cases/{}/runs", test_case_id);
+        self.send(self.request(Method::GET, &path).query(&page_query(next_page_key))).await
+    }
+
+    /// Fetches the run every `interval` until it is finished, giving up with
+    /// `ClientError::Timeout` once `timeout` has passed.
+    pub async fn poll_run(&self, test_case_id: &str, run_id: &str, interval: Duration, timeout: Duration) -> Result<Run, ClientError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let run = self.get_run(test_case_id, run_id).await?;
+            if run.status == RunStatus::Finished {
+                return Ok(run);
+            }
+            if Instant::now() + interval > deadline {
+                return Err(ClientError::Timeout { run_id: run_id.to_string(), waited: timeout });
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Continues a run that was interrupted, e.g. by a restart of the service.
+    pub async fn resume_run(&self, run_id: &str) -> Result<Run, ClientError> {
+        self.send(self.request(Method::POST, &format!("/runs/{}/resume", run_id))).await
+    }
+
+    /// Context of the run as it was after `after_action`, or at its end when `None`.
+    pub async fn get_run_context(&self, run_id: &str, after_action: Option<&str>) -> Result<Value, ClientError> {
+        let query: Vec<(&str, &str)> = after_action.map(|action| ("after_action", action)).into_iter().collect();
+        self.send(self.request(Method::GET, &format!("/runs/{}/context", run_id)).query(&query)).await
+    }
+
+    pub async fn list_assertions(&self, test_case_id: &str) -> Result<QueryResult<Assertion>, ClientError> {
+        self.send(self.request(Method::GET, &format!("/test-cases/{}/assertions", test_case_id))).await
+    }
+
+    pub async fn get_assertion(&self, test_case_id: &str, id: &str) -> Result<Option<Assertion>, ClientError> {
+        self.send(self.request(Method::GET, &format!("/test-cases/{}/assertions/{}", test_case_id, id))).await
+    }
+
+    pub async fn batch_get_assertions(&self, test_case_id: &str, ids: &[String]) -> Result<Vec<Assertion>, ClientError> {
+        self.send(self.request(Method::POST, &format!("/test-cases/{}/assertions/batch-get", test_case_id)).json(ids)).await
+    }
+
+    /// Creates the assertion, or replaces it when the payload carries an existing id.
+    pub async fn put_assertion(&self, test_case_id: &str, payload: &PutAssertionPayload) -> Result<Assertion, ClientError> {
+        self.send(self.request(Method::PUT, &format!("/test-cases/{}/assertions", test_case_id)).json(payload)).await
+    }
+
+    pub async fn delete_assertion(&self, test_case_id: &str, id: &str) -> Result<Option<Assertion>, ClientError> {
+        self.send(self.request(Method::DELETE, &format!("/test-cases/{}/assertions/{}", test_case_id, id))).await
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.http.request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+        read_response(request.send().await?).await
+    }
+}
+
+async fn read_response<T: DeserializeOwned>(response: Response) -> Result<T, ClientError> {
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await?;
+        return Err(ClientError::from_response(status.as_u16(), &text));
+    }
+    Ok(response.json().await?)
+}
+
+fn page_query(next_page_key: Option<&str>) -> Vec<(&'static str, &str)> {
+    next_page_key.map(|key| ("next_page_key", key)).into_iter().collect()
+}
@@ -0,0 +1,52 @@
+use parroton_core::error::ErrorBody;
+use std::time::Duration;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The service answered with an error status; `body` carries its code and field errors.
+    #[error("{status} {}: {}", body.code, body.message)]
+    Api { status: u16, body: ErrorBody },
+    /// The run was still in progress when polling gave up.
+    #[error("run {run_id} did not finish within {waited:?}")]
+    Timeout { run_id: String, waited: Duration },
+}
+
+impl ClientError {
+    pub(crate) fn from_response(status: u16, text: &str) -> Self {
+        let body = serde_json::from_str::<ErrorBody>(text).unwrap_or_else(|_| ErrorBody {
+            code: "unknown".to_string(),
+            message: text.to_string(),
+            errors: vec![],
+            details: None,
+        });
+        ClientError::Api { status, body }
+    }
+
+    /// True for a 404 from the service.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, ClientError::Api { status: 404, .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_error_body() {
+        let error = ClientError::from_response(400, r#"{"code":"validation_failed","message":"Request payload is invalid","errors":[{"field":"name","message":"must not be blank"}]}"#);
+        let ClientError::Api { status, body } = &error else {
+            panic!("expected an api error");
+        };
+        assert_eq!(*status, 400);
+        assert_eq!(body.code, "validation_failed");
+        assert_eq!(body.errors[0].field, "name");
+        assert_eq!(error.to_string(), "400 validation_failed: Request payload is invalid");
+
+        let error = ClientError::from_response(502, "Bad Gateway");
+        assert!(matches!(error, ClientError::Api { body: ErrorBody { ref message, .. }, .. } if message == "Bad Gateway"));
+        assert!(ClientError::from_response(404, "").is_not_found());
+    }
+}
@@ -0,0 +1,13 @@
+//! Typed async client for the parroton API.
+//!
+//! It covers the record-run-assert workflow: test cases and their HAR imports, actions and
+//! parameters, runs, assertions and deletion jobs, all with the types of `parroton-core`. The
+//! configuration endpoints, e.g. auth providers, environments, memberships or notification
+//! settings, are not wrapped; their schemas are in the service's `/openapi.json`.
+
+mod client;
+mod error;
+
+pub use client::ParrotonClient;
+pub use error::ClientError;
+pub use parroton_core as model;
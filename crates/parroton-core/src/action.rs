@@ -0,0 +1,415 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Builder, ToSchema)]
+pub struct Action {
+    pub customer_id: String,
+    pub test_case_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub order: usize,
+    /// Either absolute or a path relative to `base_url`, falling back to the test case's base URL.
+    pub url: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    pub name: String,
+    pub mime_type: Option<String>,
+    pub method: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub action_type: ActionType,
+    pub delay_ms: Option<u64>,
+    #[serde(default)]
+    #[builder(default)]
+    pub redirect_policy: RedirectPolicy,
+    #[serde(default)]
+    pub auth_provider_id: Option<String>,
+    /// Response paths left out of snapshot comparisons, e.g. `$.requestId` or `$.items[].updatedAt`.
+    #[serde(default)]
+    #[builder(default)]
+    pub volatile_paths: Vec<String>,
+    #[serde(default)]
+    pub group_id: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub last_reviewed_at: Option<u64>,
+    /// Cleanup step, e.g. deleting what earlier actions created. Teardown actions run after all
+    /// other actions whatever their order, and their failures do not fail the run.
+    #[serde(default)]
+    #[builder(default)]
+    pub teardown: bool,
+    #[serde(default)]
+    pub budget: Option<ActionBudget>,
+    #[serde(default)]
+    #[builder(default)]
+    pub hooks: ActionHooks,
+    /// Status the response must have, replacing the default of any 2xx or 3xx. A matching 4xx or
+    /// 5xx response is then treated as a success and its body enters the run context.
+    #[serde(default)]
+    pub expected_status: Option<ExpectedStatus>,
+    /// Set for actions creating something upstream, e.g. `POST /orders`, which runs then list in
+    /// their resource manifest.
+    #[serde(default)]
+    pub creates_resource: Option<ResourceTracking>,
+    /// Status of the response recorded on import, answered by mock runs and the mock server.
+    #[serde(default)]
+    pub recorded_status: Option<u16>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Default, ToSchema)]
+pub enum ActionType {
+    #[default]
+    Http,
+    Sleep,
+}
+
+/// How the executor treats 3xx responses of an action.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RedirectPolicy {
+    Follow { max_hops: usize },
+    DontFollow,
+    Record { max_hops: usize },
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Follow { max_hops: 10 }
+    }
+}
+
+impl RedirectPolicy {
+    pub fn records_hops(&self) -> bool {
+        !matches!(self, RedirectPolicy::Follow { .. })
+    }
+}
+
+/// Performance limits of an action. Exceeding them is recorded on the execution as a warning,
+/// and fails the run as well when `fail_run` is set.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Default, ToSchema)]
+pub struct ActionBudget {
+    #[serde(default)]
+    pub max_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub max_response_bytes: Option<u64>,
+    #[serde(default)]
+    pub fail_run: bool,
+}
+
+/// A single status code like `201`, or a class like `4xx`.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, ToSchema)]
+#[serde(try_from = "StatusPattern", into = "StatusPattern")]
+pub enum ExpectedStatus {
+    Code(u16),
+    /// The hundreds digit, e.g. 4 for `4xx`.
+    Class(u16),
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+enum StatusPattern {
+    Code(u16),
+    Text(String),
+}
+
+impl ExpectedStatus {
+    pub fn matches(&self, status_code: u16) -> bool {
+        match self {
+            ExpectedStatus::Code(code) => status_code == *code,
+            ExpectedStatus::Class(class) => status_code / 100 == *class,
+        }
+    }
+
+    /// Parses `201` or `2xx`, the `xx` in any case.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let expected = match text.to_ascii_lowercase().strip_suffix("xx") {
+            Some(class) => class.parse().ok().filter(|class| (1..=5).contains(class)).map(ExpectedStatus::Class),
+            None => text.parse().ok().map(ExpectedStatus::Code),
+        };
+        expected
+            .filter(|expected| !matches!(expected, ExpectedStatus::Code(code) if !(100..=599).contains(code)))
+            .ok_or_else(|| format!("{} is neither a status code nor a class like 2xx", text))
+    }
+}
+
+impl std::fmt::Display for ExpectedStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpectedStatus::Code(code) => write!(f, "{}", code),
+            ExpectedStatus::Class(class) => write!(f, "{}xx", class),
+        }
+    }
+}
+
+impl TryFrom<StatusPattern> for ExpectedStatus {
+    type Error = String;
+
+    fn try_from(pattern: StatusPattern) -> Result<Self, Self::Error> {
+        match pattern {
+            StatusPattern::Code(code) => ExpectedStatus::parse(&code.to_string()),
+            StatusPattern::Text(text) => ExpectedStatus::parse(&text),
+        }
+    }
+}
+
+impl From<ExpectedStatus> for StatusPattern {
+    fn from(expected: ExpectedStatus) -> Self {
+        match expected {
+            ExpectedStatus::Code(code) => StatusPattern::Code(code),
+            ExpectedStatus::Class(_) => StatusPattern::Text(expected.to_string()),
+        }
+    }
+}
+
+/// How the resource an action created is identified and deleted.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct ResourceTracking {
+    /// Reads the id of the created resource from the response body, e.g. `$.id`.
+    pub id_expression: String,
+    /// Listed in the manifest, like `order`; the action name when not set.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Request deleting the resource, e.g. `DELETE /orders/{id}`, `{id}` being replaced with the
+    /// id. Relative URLs are resolved like the action's own.
+    #[serde(default)]
+    pub teardown: Option<ResourceRequest>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct ResourceRequest {
+    pub method: String,
+    pub url: String,
+}
+
+/// Steps that adjust what static parameters cannot express: the request right before it is sent,
+/// e.g. a signature over the body, and the response before it enters the run context.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Default, ToSchema)]
+pub struct ActionHooks {
+    #[serde(default)]
+    pub pre_request: Vec<RequestHook>,
+    #[serde(default)]
+    pub post_response: Vec<ResponseHook>,
+}
+
+impl ActionHooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_request.is_empty() && self.post_response.is_empty()
+    }
+}
+
+/// Values are templates where `{{$.login_0.output.token}}` is replaced with the first match of the
+/// expression. Besides the run context they can read the outgoing request under `$.request`, with
+/// its `method`, `url`, `path`, `body` as sent, `headers` and the unix `timestamp`.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestHook {
+    SetHeader { name: String, value: String },
+    RemoveHeader { name: String },
+    SetQueryParam { name: String, value: String },
+    /// Sends the HMAC of the rendered `message` keyed with the rendered `secret` in the header `name`.
+    HmacHeader {
+        name: String,
+        secret: String,
+        message: String,
+        #[serde(default)]
+        algorithm: HmacAlgorithm,
+        #[serde(default)]
+        encoding: DigestEncoding,
+    },
+}
+
+/// Fields are addressed with JSON pointers like `/data/payload`, the empty pointer being the whole body.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseHook {
+    /// Parses a string field as JSON, e.g. a payload the API encodes twice.
+    ParseJson { pointer: String },
+    /// Replaces a base64 string field with its decoded text, parsed as JSON when it is JSON.
+    DecodeBase64 { pointer: String },
+    /// Replaces the body with the first match of the expression.
+    Select { expression: String },
+    /// Sets a field to the rendered template, which reads the body under `$.response`.
+    SetField { pointer: String, value: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq, Debug, ToSchema)]
+pub enum BudgetKind {
+    Duration,
+    ResponseSize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct BudgetViolation {
+    pub kind: BudgetKind,
+    pub limit: u64,
+    pub actual: u64,
+}
+
+impl ActionBudget {
+    /// Limits the execution went over. The response size is only checked when it is known.
+    pub fn violations(&self, duration_ms: u64, response_bytes: Option<u64>) -> Vec<BudgetViolation> {
+        let duration = self.max_duration_ms.map(|limit| (BudgetKind::Duration, limit, Some(duration_ms)));
+        let response_size = self.max_response_bytes.map(|limit| (BudgetKind::ResponseSize, limit, response_bytes));
+        [duration, response_size].into_iter()
+            .flatten()
+            .filter_map(|(kind, limit, actual)| actual
+                .filter(|actual| *actual > limit)
+                .map(|actual| BudgetViolation { kind, limit, actual }))
+            .collect()
+    }
+}
+
+impl BudgetViolation {
+    pub fn message(&self) -> String {
+        match self.kind {
+            BudgetKind::Duration => format!("took {} ms, over the budget of {} ms", self.actual, self.limit),
+            BudgetKind::ResponseSize => format!("responded with {} bytes, over the budget of {} bytes", self.actual, self.limit),
+        }
+    }
+}
+
+impl Action {
+    /// Whether the response status is the expected one, any 2xx or 3xx unless set otherwise.
+    pub fn status_succeeded(&self, status_code: u16) -> bool {
+        match &self.expected_status {
+            Some(expected) => expected.matches(status_code),
+            None => (200..400).contains(&status_code),
+        }
+    }
+
+    /// Absolute URLs are kept as recorded; a relative one without any base URL is returned as is.
+    pub fn absolute_url(&self, case_base_url: Option<&str>) -> String {
+        self.resolve_url(&self.url, case_base_url)
+    }
+
+    /// Resolves a URL the way the action's own is, against its base URL or the test case's.
+    pub fn resolve_url(&self, url: &str, case_base_url: Option<&str>) -> String {
+        if url.contains("://") {
+            return url.to_string();
+        }
+        match self.base_url.as_deref().or(case_base_url) {
+            Some(base_url) => join_url(base_url, url),
+            None => url.to_string(),
+        }
+    }
+}
+
+pub fn join_url(base_url: &str, path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    if path.is_empty() {
+        return base_url.trim_end_matches('/').to_string();
+    }
+    format!("{}/{}", base_url.trim_end_matches('/'), path)
+}
+
+impl PartialOrd for Action {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Action {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order.cmp(&other.order)
+    }
+}
+/// Everything reading from an action, i.e. what breaks when the action is deleted or renamed.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct ActionImpact {
+    pub action_id: String,
+    pub parameters: Vec<ParameterReference>,
+    pub assertions: Vec<AssertionReference>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct ParameterReference {
+    /// Action the parameter belongs to.
+    pub action_id: String,
+    pub parameter_id: String,
+    pub expression: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct AssertionReference {
+    pub assertion_id: String,
+    pub expressions: Vec<String>,
+    /// True when the assertion compares the action's output against its snapshot.
+    pub snapshot: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_url_prefers_action_base_url() {
+        let action = Action::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .order(0)
+            .url("/orders/{id}".to_string())
+            .name("order_0".to_string())
+            .method("GET".to_string())
+            .build();
+
+        assert_eq!(action.absolute_url(Some("https://staging.abc.xyz/")), "https://staging.abc.xyz/orders/{id}");
+        assert_eq!(action.absolute_url(None), "/orders/{id}");
+        let overridden = Action { base_url: Some("https://payments.abc.xyz".to_string()), ..action.clone() };
+        assert_eq!(overridden.absolute_url(Some("https://staging.abc.xyz")), "https://payments.abc.xyz/orders/{id}");
+        let recorded = Action { url: "https://abc.xyz/orders".to_string(), ..action };
+        assert_eq!(recorded.absolute_url(Some("https://staging.abc.xyz")), "https://abc.xyz/orders");
+        assert_eq!(join_url("https://abc.xyz/api", ""), "https://abc.xyz/api");
+    }
+
+    #[test]
+    fn budget_violations() {
+        let budget = ActionBudget { max_duration_ms: Some(500), max_response_bytes: Some(1024), fail_run: false };
+
+        assert!(budget.violations(500, Some(1024)).is_empty());
+        assert_eq!(budget.violations(750, None), vec![BudgetViolation { kind: BudgetKind::Duration, limit: 500, actual: 750 }]);
+        assert_eq!(budget.violations(100, Some(2048)).iter().map(|violation| violation.message()).collect::<Vec<_>>(),
+                   vec!["responded with 2048 bytes, over the budget of 1024 bytes".to_string()]);
+        assert!(ActionBudget::default().violations(10_000, Some(10_000)).is_empty());
+    }
+
+    #[test]
+    fn expected_status() {
+        let created: ExpectedStatus = serde_json::from_value(serde_json::json!(201)).unwrap();
+        let client_error: ExpectedStatus = serde_json::from_value(serde_json::json!("4XX")).unwrap();
+
+        assert_eq!(created, ExpectedStatus::Code(201));
+        assert_eq!(client_error, ExpectedStatus::Class(4));
+        assert_eq!(serde_json::to_value(client_error).unwrap(), serde_json::json!("4xx"));
+        assert_eq!(serde_json::to_value(created).unwrap(), serde_json::json!(201));
+        assert!(client_error.matches(404) && !client_error.matches(500));
+        assert!(created.matches(201) && !created.matches(200));
+        assert_eq!(ExpectedStatus::parse(" 201 "), Ok(created));
+        assert!(ExpectedStatus::parse("6xx").is_err());
+        assert!(ExpectedStatus::parse("42").is_err());
+        assert!(serde_json::from_value::<ExpectedStatus>(serde_json::json!("ok")).is_err());
+    }
+}
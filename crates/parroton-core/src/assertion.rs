@@ -0,0 +1,235 @@
+use crate::json_path::Expression;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum ComparisonType {
+    EqualTo,
+    Contains,
+    GreaterThan,
+    GreaterThanOrEqualTo,
+    LessThan,
+    LessThanOrEqualTo,
+    /// Compares the output of `snapshot_action_id` against its accepted baseline; left and right are not used.
+    MatchesSnapshot,
+    /// Passes when the two numbers are within the assertion's `tolerance` of each other.
+    ApproximatelyEqualTo,
+    /// Passes when the left point in time is before the right one, by at least `duration_ms` when set.
+    Before,
+    /// Passes when the left point in time is after the right one, by at least `duration_ms` when set.
+    After,
+    /// Passes when the two points in time are at most `duration_ms` apart.
+    WithinDuration,
+    /// Passes when the left expression matches anything; the right side is not used.
+    Exists,
+    /// Passes when the left expression matches nothing; the right side is not used.
+    NotExists,
+    /// Passes when everything the left expression matches is null; the right side is not used.
+    IsNull,
+    /// Passes when the length of the left array, string or object, or else the number of values
+    /// the left expression matches, equals the number on the right.
+    HasLength,
+    /// Compares with the comparison a plugin registered under the name.
+    Custom(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum Operation {
+    Sum,
+    Avg,
+    Count,
+    /// Computes with the function a plugin registered under the name.
+    Custom(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Function {
+    pub operation: Operation,
+    pub parameters: Vec<ValueProvider>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Builder, ToSchema)]
+pub struct ValueProvider {
+    pub expression: Option<Expression>,
+    pub value: Option<Value>,
+}
+
+/// Only critical failures fail a run; warnings and infos are reported alongside the verdict.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, ToSchema)]
+pub enum Severity {
+    #[default]
+    Critical,
+    Warning,
+    Info,
+}
+
+/// The empty item stands for the unused right side of presence comparisons.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Builder, ToSchema)]
+pub struct AssertionItem {
+    pub function: Option<Function>,
+    pub value_provider: Option<ValueProvider>,
+}
+
+impl AssertionItem {
+    pub fn from_function(function: Function) -> Self {
+        AssertionItem {
+            function: Some(function),
+            value_provider: None,
+        }
+    }
+
+    pub fn from_expression(expression: Expression) -> Self {
+        AssertionItem {
+            function: None,
+            value_provider: Some(ValueProvider {
+                expression: Some(expression),
+                value: None,
+            }),
+        }
+    }
+
+    pub fn from_value(value: Value) -> Self {
+        AssertionItem {
+            function: None,
+            value_provider: Some(ValueProvider {
+                expression: None,
+                value: Some(value),
+            }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, ToSchema)]
+pub struct Assertion {
+    pub customer_id: String,
+    pub test_case_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub left: AssertionItem,
+    #[serde(default)]
+    #[builder(default)]
+    pub right: AssertionItem,
+    pub comparison_type: ComparisonType,
+    #[builder(default = false)]
+    pub negate: bool,
+    #[serde(default)]
+    #[builder(default)]
+    pub severity: Severity,
+    #[serde(default)]
+    pub snapshot_action_id: Option<String>,
+    #[serde(default)]
+    pub tolerance: Option<Tolerance>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub last_reviewed_at: Option<u64>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+/// Body of an assertion create or replace; a missing id creates a new assertion.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct PutAssertionPayload {
+    pub id: Option<String>,
+    pub left: AssertionItem,
+    #[serde(default)]
+    pub right: AssertionItem,
+    pub comparison_type: ComparisonType,
+    pub negate: bool,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default)]
+    pub snapshot_action_id: Option<String>,
+    #[serde(default)]
+    pub tolerance: Option<Tolerance>,
+    #[serde(default)]
+    pub duration_ms: Option<u64>,
+}
+
+/// How far apart the numbers of an `ApproximatelyEqualTo` assertion may be; within either bound
+/// is close enough. Without bounds only floating point rounding is tolerated.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, ToSchema)]
+pub struct Tolerance {
+    #[serde(default)]
+    pub absolute: Option<f64>,
+    /// Fraction of the larger magnitude of the two numbers, e.g. `0.01` for 1%.
+    #[serde(default)]
+    pub relative: Option<f64>,
+}
+
+impl ComparisonType {
+    pub fn compares_time(&self) -> bool {
+        matches!(self, ComparisonType::Before | ComparisonType::After | ComparisonType::WithinDuration)
+    }
+
+    /// Presence comparisons only look at the left side.
+    pub fn uses_right(&self) -> bool {
+        !matches!(self, ComparisonType::Exists | ComparisonType::NotExists | ComparisonType::IsNull)
+    }
+}
+
+impl Tolerance {
+    pub fn allows(&self, left: f64, right: f64) -> bool {
+        let difference = (left - right).abs();
+        let magnitude = left.abs().max(right.abs());
+        if self.absolute.is_none() && self.relative.is_none() {
+            return difference <= f64::EPSILON * magnitude;
+        }
+        self.absolute.is_some_and(|absolute| difference <= absolute)
+            || self.relative.is_some_and(|relative| difference <= relative * magnitude)
+    }
+
+    pub fn describe(&self) -> String {
+        let absolute = self.absolute.map(|absolute| format!("{}", absolute));
+        let relative = self.relative.map(|relative| format!("{}%", relative * 100.0));
+        match (absolute, relative) {
+            (Some(absolute), Some(relative)) => format!("{} or {}", absolute, relative),
+            (Some(bound), None) | (None, Some(bound)) => bound,
+            (None, None) => "rounding".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Builder, ToSchema)]
+pub struct AssertionResult {
+    pub assertion_id: String,
+    pub success: bool,
+    pub message: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    pub severity: Severity,
+}
+
+impl AssertionResult {
+    pub fn from_error(id: String, message: String) -> Self {
+        AssertionResult {
+            assertion_id: id.clone(),
+            success: false,
+            message: Some(message),
+            severity: Severity::default(),
+        }
+    }
+
+    pub fn of_success(id: String) -> Self {
+        AssertionResult {
+            assertion_id: id.clone(),
+            success: true,
+            message: None,
+            severity: Severity::default(),
+        }
+    }
+
+    pub fn with_severity(self, severity: Severity) -> Self {
+        AssertionResult { severity, ..self }
+    }
+
+    pub fn is_blocking(&self) -> bool {
+        !self.success && self.severity == Severity::Critical
+    }
+}
\ No newline at end of file
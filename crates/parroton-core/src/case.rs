@@ -0,0 +1,61 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, ToSchema)]
+pub struct TestCase {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub concurrency_policy: ConcurrencyPolicy,
+    #[serde(default)]
+    #[builder(default)]
+    pub archived: bool,
+    /// Relative action URLs are resolved against it, e.g. `https://staging.abc.xyz`.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Test case run first, its context exposed to this one under `$.setup`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup: Option<SetupCase>,
+    /// Fingerprint of the imported action sequence, used to spot duplicate test cases on import.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    pub context_retention: ContextRetention,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct SetupCase {
+    pub test_case_id: String,
+    /// Reuses the latest passed run of the setup case instead of running it again when that run
+    /// started at most this many seconds ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reuse_within_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, ToSchema)]
+pub enum ConcurrencyPolicy {
+    #[default]
+    Allow,
+    Reject,
+    Queue,
+}
+
+/// Which action entries a run keeps in its context. Entries that later actions or assertions
+/// read from are always kept until their last use, so retention only drops what nothing reads.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ContextRetention {
+    #[default]
+    All,
+    /// Drops an action's entry once nothing reads from it anymore.
+    Referenced,
+    /// Keeps the entries of the last `count` executed actions besides the referenced ones.
+    LastActions { count: usize },
+}
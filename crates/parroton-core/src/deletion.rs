@@ -0,0 +1,29 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Builder, ToSchema)]
+pub struct DeletionJob {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub test_case_id: String,
+    pub status: DeletionJobStatus,
+    #[serde(default)]
+    #[builder(default)]
+    pub deleted_counts: HashMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub enum DeletionJobStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+#[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct FieldError {
+    /// Path of the field in the payload, e.g. `name` or `url_rewrites[0].from`.
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError { field: field.into(), message: message.into() }
+    }
+}
+
+/// Body of every error response.
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+pub struct ErrorBody {
+    /// Stable, machine readable counterpart of the status code, e.g. `validation_failed`.
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// What the error is about beyond the message, e.g. the quota, its limit and usage for
+    /// `quota_exceeded`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
@@ -0,0 +1,11 @@
+//! Types shared by the parroton service and its API clients.
+
+pub mod action;
+pub mod assertion;
+pub mod case;
+pub mod deletion;
+pub mod error;
+pub mod json_path;
+pub mod page;
+pub mod parameter;
+pub mod run;
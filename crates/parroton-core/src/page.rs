@@ -0,0 +1,14 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// One page of a listing; pass `next_page_key` back to get the following page.
+#[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
+#[serde(bound(deserialize = ""))]
+pub struct QueryResult<T>
+where
+    T: DeserializeOwned + Serialize + Clone,
+{
+    pub items: Vec<T>,
+    pub next_page_key: Option<String>,
+}
@@ -0,0 +1,185 @@
+use crate::json_path::Expression;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+pub enum ParameterType {
+    Input,
+    Output,
+}
+
+#[derive(Deserialize, Clone, PartialEq, ToSchema)]
+pub enum ParameterIn {
+    Header,
+    Cookie,
+    Query,
+    Body,
+    Path,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum ParameterLocation {
+    Header(String),
+    Cookie(String),
+    Query(String),
+    Body(String),
+    Path(String),
+}
+
+/// Type a body parameter's value is sent as, whatever type its expression produced.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum ValueType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl ValueType {
+    /// Type of a recorded scalar; `None` for nulls, arrays and objects.
+    pub fn of(value: &Value) -> Option<ValueType> {
+        match value {
+            Value::String(_) => Some(ValueType::String),
+            Value::Number(number) if number.is_f64() => Some(ValueType::Number),
+            Value::Number(_) => Some(ValueType::Integer),
+            Value::Bool(_) => Some(ValueType::Boolean),
+            _ => None,
+        }
+    }
+
+    /// Converts the value, element by element for arrays. Nulls are kept as they are.
+    pub fn coerce(&self, value: &Value) -> Result<Value, String> {
+        let coerced = match (self, value) {
+            (_, Value::Null) => Some(Value::Null),
+            (_, Value::Array(items)) => {
+                return items.iter().map(|item| self.coerce(item)).collect::<Result<Vec<Value>, String>>().map(Value::Array);
+            }
+            (ValueType::String, Value::String(_)) => Some(value.clone()),
+            (ValueType::String, Value::Number(_) | Value::Bool(_)) => Some(Value::String(value.to_string())),
+            (ValueType::Integer, Value::Number(number)) => number.as_i64()
+                .or_else(|| number.as_f64().filter(|float| float.fract() == 0.0).map(|float| float as i64))
+                .map(Value::from),
+            (ValueType::Integer, Value::String(text)) => text.trim().parse::<i64>().ok().map(Value::from),
+            (ValueType::Number, Value::Number(_)) => Some(value.clone()),
+            (ValueType::Number, Value::String(text)) => text.trim().parse::<i64>().ok().map(Value::from)
+                .or_else(|| text.trim().parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number)),
+            (ValueType::Boolean, Value::Bool(_)) => Some(value.clone()),
+            (ValueType::Boolean, Value::String(text)) => match text.trim().to_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            _ => None,
+        };
+        coerced.ok_or_else(|| format!("{} is not a valid {:?}", value, self))
+    }
+}
+
+/// How a query parameter is sent when its expression matches several values.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum MultiValueMode {
+    /// `?id=1&id=2`
+    Repeat,
+    /// `?id=1,2` with `,` as the delimiter.
+    Join { delimiter: String },
+    /// `?id=1`
+    First,
+}
+
+impl MultiValueMode {
+    pub fn apply(&self, values: Vec<String>) -> Vec<String> {
+        match self {
+            MultiValueMode::Repeat => values,
+            MultiValueMode::Join { delimiter } if !values.is_empty() => vec![values.join(delimiter)],
+            MultiValueMode::Join { .. } => vec![],
+            MultiValueMode::First => values.into_iter().take(1).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Builder, ToSchema)]
+pub struct Parameter {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub action_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub parameter_type: ParameterType,
+    pub location: ParameterLocation,
+    pub value: Value,
+    pub value_expression: Option<Expression>,
+    #[serde(default)]
+    #[builder(default)]
+    pub expression_accepted: bool,
+    /// Set for body parameters, whose values are coerced to it when the request is built.
+    #[serde(default)]
+    pub value_type: Option<ValueType>,
+    /// Set for query parameters to send every value the expression matches, not only the first.
+    #[serde(default)]
+    pub multi_value_mode: Option<MultiValueMode>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+
+}
+
+impl Parameter {
+    pub fn get_path(&self) -> String {
+        match &self.location {
+            ParameterLocation::Header(name) => { name.clone() }
+            ParameterLocation::Cookie(name) => { name.clone() }
+            ParameterLocation::Query(name) => { name.clone() }
+            ParameterLocation::Body(name) => { name.clone() }
+            ParameterLocation::Path(name) => { name.clone() }
+        }
+    }
+
+    pub fn get_parameter_in(&self) -> ParameterIn {
+        match &self.location {
+            ParameterLocation::Header(_) => { ParameterIn::Header }
+            ParameterLocation::Cookie(_) => { ParameterIn::Cookie }
+            ParameterLocation::Query(_) => { ParameterIn::Query }
+            ParameterLocation::Body(_) => { ParameterIn::Body }
+            ParameterLocation::Path(_) => { ParameterIn::Path }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn coerce_values() {
+        assert_eq!(ValueType::Integer.coerce(&json!("42")), Ok(json!(42)));
+        assert_eq!(ValueType::Integer.coerce(&json!(3.0)), Ok(json!(3)));
+        assert!(ValueType::Integer.coerce(&json!("4.5")).is_err());
+        assert_eq!(ValueType::Number.coerce(&json!(" 4.5")), Ok(json!(4.5)));
+        assert_eq!(ValueType::Boolean.coerce(&json!("TRUE")), Ok(json!(true)));
+        assert_eq!(ValueType::Boolean.coerce(&json!("yes")), Err("\"yes\" is not a valid Boolean".to_string()));
+        assert_eq!(ValueType::String.coerce(&json!(7)), Ok(json!("7")));
+        assert_eq!(ValueType::Integer.coerce(&json!(["1", 2])), Ok(json!([1, 2])));
+        assert_eq!(ValueType::Boolean.coerce(&Value::Null), Ok(Value::Null));
+        assert!(ValueType::String.coerce(&json!({"a": 1})).is_err());
+    }
+
+    #[test]
+    fn apply_multi_value_mode() {
+        let values = || vec!["1".to_string(), "b".to_string()];
+        assert_eq!(MultiValueMode::Repeat.apply(values()), values());
+        assert_eq!(MultiValueMode::Join { delimiter: ",".to_string() }.apply(values()), vec!["1,b".to_string()]);
+        assert_eq!(MultiValueMode::First.apply(values()), vec!["1".to_string()]);
+        assert!(MultiValueMode::Join { delimiter: ",".to_string() }.apply(vec![]).is_empty());
+    }
+
+    #[test]
+    fn infer_value_type() {
+        assert_eq!(ValueType::of(&json!("abc")), Some(ValueType::String));
+        assert_eq!(ValueType::of(&json!(12)), Some(ValueType::Integer));
+        assert_eq!(ValueType::of(&json!(1.5)), Some(ValueType::Number));
+        assert_eq!(ValueType::of(&json!(false)), Some(ValueType::Boolean));
+        assert_eq!(ValueType::of(&json!([1])), None);
+    }
+}
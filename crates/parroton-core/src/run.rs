@@ -0,0 +1,317 @@
+use crate::action::ResourceRequest;
+use crate::assertion::AssertionResult;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Builder, ToSchema)]
+pub struct Run {
+    pub customer_id: String,
+    pub test_case_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub status: RunStatus,
+    pub started_at: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    pub assertion_results: Option<Vec<AssertionResult>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verdict: Option<Verdict>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default)]
+    #[builder(default)]
+    pub mock: bool,
+    /// Auth provider ids keyed by base URL, replacing the stored provider choice for this run only.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[builder(default)]
+    pub auth_provider_overrides: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault_injection: Option<FaultInjection>,
+    /// Environment whose URL rewrites are applied to every request of the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_id: Option<String>,
+    /// Set for partial runs, which only execute the selected actions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selection: Option<ActionSelection>,
+    /// Context the run starts from: entries standing in for actions left out, or values like a tenant id.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    #[builder(default)]
+    pub seed_context: Map<String, Value>,
+    #[serde(default)]
+    #[builder(default)]
+    pub trigger: RunTrigger,
+    /// Free-form tags such as a branch or release name, used to filter the run list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub labels: Vec<String>,
+    /// Run of the setup test case whose context is seeded under `setup`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub setup_run_id: Option<String>,
+    /// What the run's actions created upstream, for actions that track the resources they create.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub resources: Vec<CreatedResource>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+/// A resource an action of the run created, with the request deleting it resolved.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct CreatedResource {
+    pub action_id: String,
+    pub action_name: String,
+    pub kind: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teardown: Option<ResourceRequest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<u64>,
+    /// Why the last attempt to delete the resource failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub teardown_error: Option<String>,
+}
+
+/// Faults injected into the HTTP calls of a single run to check how assertions hold up when
+/// the target misbehaves.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+pub struct FaultInjection {
+    /// Share of requests, 0 to 100, answered with `failure_status` instead of being sent.
+    #[serde(default)]
+    pub failure_rate_percent: u8,
+    #[serde(default = "default_failure_status")]
+    pub failure_status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Response fields replaced with null before they enter the run context, in drift path notation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub corrupt_paths: Vec<String>,
+}
+
+fn default_failure_status() -> u16 {
+    503
+}
+
+impl FaultInjection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.failure_rate_percent > 100 {
+            return Err("failure_rate_percent must be between 0 and 100!".to_string());
+        }
+        if !(400..600).contains(&self.failure_status) {
+            return Err("failure_status must be a 4xx or 5xx status code!".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Actions of a partial run: the range from `start_action` to `end_action` by name, both inclusive
+/// and either open ended, plus any action in `include_action_ids`.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Default, ToSchema)]
+pub struct ActionSelection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_action: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_action: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_action_ids: Vec<String>,
+}
+
+impl ActionSelection {
+    pub fn is_empty(&self) -> bool {
+        self.start_action.is_none() && self.end_action.is_none() && self.include_action_ids.is_empty()
+    }
+}
+
+/// A run summary in the customer wide run list, next to the name of its test case.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct RunOverview {
+    #[serde(flatten)]
+    pub run: Run,
+    /// Missing when the test case was deleted after the run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_case_name: Option<String>,
+}
+
+/// What started a run. Runs stored before triggers were recorded read as manual.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Default, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunTrigger {
+    #[default]
+    Manual,
+    Schedule { schedule_id: String },
+    Ci {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pipeline: Option<String>,
+    },
+    ApiKey { name: String },
+}
+
+impl RunTrigger {
+    /// The `type` tag the trigger is stored under, as accepted by the run list filter.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RunTrigger::Manual => "manual",
+            RunTrigger::Schedule { .. } => "schedule",
+            RunTrigger::Ci { .. } => "ci",
+            RunTrigger::ApiKey { .. } => "api_key",
+        }
+    }
+}
+
+/// Optional body of a run request.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, ToSchema)]
+pub struct RunTestCasePayload {
+    /// Auth provider ids keyed by base URL, replacing the stored provider choice for this run only.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub auth_provider_overrides: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault_injection: Option<FaultInjection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_id: Option<String>,
+    #[serde(flatten)]
+    pub selection: ActionSelection,
+    /// Merged into the context before the first action, e.g. `{"tenant_id": "t1"}` read as `$.tenant_id`,
+    /// or `{"login_0": {"output": {"token": "..."}}}` standing in for an action left out of a partial run.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub seed_context: Map<String, Value>,
+    #[serde(default)]
+    pub trigger: RunTrigger,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct RunVerdict {
+    pub verdict: Verdict,
+    pub failed_assertions: Vec<AssertionResult>,
+    #[serde(default)]
+    pub warnings: Vec<AssertionResult>,
+    pub run: Run,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub enum Verdict {
+    Passed,
+    Failed,
+    Errored,
+    TimedOut,
+}
+
+/// Derives the outcome of a finished run: an action that never got a response errors the run,
+/// while a blocking assertion failure or an unsuccessful status code fails it. Redirect statuses
+/// count as successful since they are only surfaced when the redirect policy does not follow them.
+pub fn compute_verdict(assertion_results: &[AssertionResult], status_codes: &[u16]) -> Verdict {
+    if status_codes.contains(&0) {
+        return Verdict::Errored;
+    }
+    let failed_status = status_codes.iter().any(|status_code| !(200..400).contains(status_code));
+    let failed_assertion = assertion_results.iter().any(|result| result.is_blocking());
+    if failed_status || failed_assertion { Verdict::Failed } else { Verdict::Passed }
+}
+
+impl RunVerdict {
+    pub fn from_finished_run(run: Run) -> Self {
+        let (failed_assertions, warnings): (Vec<AssertionResult>, Vec<AssertionResult>) = run.assertion_results.iter()
+            .flatten()
+            .filter(|result| !result.success)
+            .cloned()
+            .partition(|result| result.is_blocking());
+        RunVerdict {
+            verdict: run.verdict.clone()
+                .unwrap_or(if failed_assertions.is_empty() { Verdict::Passed } else { Verdict::Failed }),
+            failed_assertions,
+            warnings,
+            run,
+        }
+    }
+
+    pub fn timed_out(run: Run) -> Self {
+        RunVerdict {
+            verdict: Verdict::TimedOut,
+            failed_assertions: vec![],
+            warnings: vec![],
+            run,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub enum RunStatus {
+    Queued,
+    InProgress,
+    Finished,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertion::Severity;
+
+    #[test]
+    fn verdict_from_finished_run() {
+        let passed = AssertionResult::of_success("a1".to_string());
+        let failed = AssertionResult::from_error("a2".to_string(), "not equal".to_string());
+        let warning = AssertionResult::from_error("a3".to_string(), "slow".to_string())
+            .with_severity(Severity::Warning);
+        let run = Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .status(RunStatus::Finished)
+            .started_at(0)
+            .assertion_results(vec![passed.clone()])
+            .build();
+
+        assert_eq!(RunVerdict::from_finished_run(run.clone()).verdict, Verdict::Passed);
+
+        let verdict = RunVerdict::from_finished_run(Run {
+            assertion_results: Some(vec![passed.clone(), failed.clone()]),
+            ..run
+        });
+        assert_eq!(verdict.verdict, Verdict::Failed);
+        assert_eq!(verdict.failed_assertions, vec![failed]);
+
+        let verdict = RunVerdict::from_finished_run(Run {
+            assertion_results: Some(vec![passed, warning.clone()]),
+            ..verdict.run
+        });
+        assert_eq!(verdict.verdict, Verdict::Passed);
+        assert!(verdict.failed_assertions.is_empty());
+        assert_eq!(verdict.warnings, vec![warning.clone()]);
+
+        let verdict = RunVerdict::from_finished_run(Run {
+            verdict: Some(Verdict::Errored),
+            ..verdict.run
+        });
+        assert_eq!(verdict.verdict, Verdict::Errored);
+    }
+
+    #[test]
+    fn verdict_from_results_and_status_codes() {
+        let passed = AssertionResult::of_success("a1".to_string());
+        let failed = AssertionResult::from_error("a2".to_string(), "not equal".to_string());
+        let warning = failed.clone().with_severity(Severity::Warning);
+
+        assert_eq!(compute_verdict(&[passed.clone(), warning], &[200, 302]), Verdict::Passed);
+        assert_eq!(compute_verdict(&[passed.clone(), failed], &[200]), Verdict::Failed);
+        assert_eq!(compute_verdict(std::slice::from_ref(&passed), &[200, 500]), Verdict::Failed);
+        assert_eq!(compute_verdict(&[passed], &[404, 0]), Verdict::Errored);
+        assert_eq!(compute_verdict(&[], &[]), Verdict::Passed);
+    }
+
+    #[test]
+    fn trigger_defaults_to_manual() {
+        let payload: RunTestCasePayload = serde_json::from_value(serde_json::json!({
+            "trigger": {"type": "schedule", "schedule_id": "nightly"},
+            "labels": ["release-1.4"]
+        })).unwrap();
+        assert_eq!(payload.trigger, RunTrigger::Schedule { schedule_id: "nightly".to_string() });
+        assert_eq!(payload.trigger.type_name(), "schedule");
+        assert_eq!(serde_json::to_value(RunTrigger::Ci { pipeline: None }).unwrap(), serde_json::json!({"type": "ci"}));
+
+        let payload: RunTestCasePayload = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(payload.trigger, RunTrigger::Manual);
+        assert!(payload.labels.is_empty());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::access::model::{ApiToken, ApiTokenSummary, CreatedApiToken, Membership, Role, TokenScope};
+use crate::access::token::issue;
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::change::middleware::current_user;
+use crate::persistence::repo::Repository;
+use crate::validation::{check_not_blank, AppJson, ValidJson, Validate};
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn list_memberships(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<Membership>>, AppError> {
+    let result = repository.memberships().list_all(&"eren".to_string()).await;
+    ApiResponse::from(result)
+}
+
+pub async fn put_membership(
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<PutMembershipPayload>,
+) -> Result<ApiResponse<Membership>, AppError> {
+    let result = repository.memberships()
+        .put(Membership::builder()
+            .customer_id("eren".to_string())
+            .id(Membership::id_of(&payload.user_id, payload.test_case_id.as_deref()))
+            .user_id(payload.user_id)
+            .maybe_test_case_id(payload.test_case_id)
+            .role(payload.role)
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_membership(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Membership>, AppError> {
+    let result = repository.memberships().delete(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutMembershipPayload {
+    pub user_id: String,
+    pub test_case_id: Option<String>,
+    pub role: Role,
+}
+
+pub async fn list_api_tokens(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<ApiTokenSummary>>, AppError> {
+    let result = repository.api_tokens()
+        .list_all("eren")
+        .await
+        .map(|api_tokens| api_tokens.into_iter().map(ApiTokenSummary::from).collect());
+    ApiResponse::from(result)
+}
+
+pub async fn create_api_token(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<CreateApiTokenPayload>,
+) -> Result<ApiResponse<CreatedApiToken>, AppError> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let (token, secret_hash) = issue(&id);
+    let result = repository.api_tokens()
+        .create(ApiToken::builder()
+            .customer_id("eren".to_string())
+            .id(id)
+            .name(payload.name)
+            .scope(payload.scope)
+            .secret_hash(secret_hash)
+            .maybe_created_by(current_user())
+            .build())
+        .await
+        .map(|api_token| CreatedApiToken {
+            token,
+            api_token: ApiTokenSummary::from(api_token),
+        });
+    ApiResponse::from(result)
+}
+
+pub async fn delete_api_token(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<ApiTokenSummary>, AppError> {
+    let result = repository.api_tokens()
+        .delete("eren", &id)
+        .await
+        .map(|api_token| api_token.map(ApiTokenSummary::from));
+    ApiResponse::from_option(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CreateApiTokenPayload {
+    pub name: String,
+    pub scope: TokenScope,
+}
+
+impl Validate for CreateApiTokenPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter().collect()
+    }
+}
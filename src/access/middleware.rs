@@ -0,0 +1,161 @@
+use crate::access::model::{effective_role, is_operator_path, required_role};
+use crate::access::session::verify_session;
+use crate::access::token::{hash_secret, parse, API_TOKEN_PREFIX};
+use crate::api::{AppError, AppState};
+use crate::config::app_config;
+use crate::persistence::repo::{current_timestamp, Repository};
+use axum::extract::{MatchedPath, RawPathParams, Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::warn;
+
+/// The caller `authorize` authenticated, a session's user or `api-token:<id>`, for the handlers
+/// and layers after it. A value sent by the client is dropped before anything reads it.
+pub const USER_HEADER: &str = "x-user-id";
+/// Uses of a token closer together than this are recorded once.
+const LAST_USED_RESOLUTION_MILLIS: u64 = 60 * 1000;
+
+enum Credential {
+    Session(String),
+    ApiToken(String),
+}
+
+/// Checks the caller's role against the route. Every route but the operational ones needs a
+/// session or an API token. Customers without any membership only admit the configured admin
+/// users, who can then create the first memberships.
+pub async fn authorize(
+    State(app_state): State<AppState>,
+    matched_path: Option<MatchedPath>,
+    path_params: Option<RawPathParams>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    request.headers_mut().remove(USER_HEADER);
+    let path = matched_path.map(|path| path.as_str().to_string()).unwrap_or_default();
+    let required = required_role(request.method().as_str(), &path);
+    if required.is_none() && !is_operator_path(&path) {
+        return Ok(next.run(request).await);
+    }
+    let user_id = match credential_of(request.headers())? {
+        None => return Err(AppError::Unauthorized("A session or API token is required".to_string())),
+        Some(Credential::ApiToken(token)) => {
+            return authorize_api_token(&app_state.repository, &token, &path, request, next).await;
+        }
+        Some(Credential::Session(user_id)) => user_id,
+    };
+    set_caller(&mut request, &user_id);
+    if is_operator_path(&path) {
+        return authorize_operator(&user_id, request, next).await;
+    }
+    let Some(required) = required else {
+        return Ok(next.run(request).await);
+    };
+    let memberships = app_state.repository.memberships().list_all(&"eren".to_string()).await?;
+    if memberships.is_empty() {
+        return match is_admin_user(&user_id) {
+            true => Ok(next.run(request).await),
+            false => Err(AppError::Forbidden("No memberships exist yet, only admin users have access".to_string())),
+        };
+    }
+    let test_case_id = if path.starts_with("/test-cases/:") {
+        let param = |key: &str| path_params.iter()
+            .flat_map(|params| params.iter())
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value.to_string());
+        param("test_case_id").or_else(|| param("id"))
+    } else {
+        None
+    };
+    match effective_role(&memberships, &user_id, test_case_id.as_deref()) {
+        Some(role) if role >= required => Ok(next.run(request).await),
+        _ => Err(AppError::Forbidden(format!("{:?} role is required", required))),
+    }
+}
+
+/// The bearer credential of the request: an API token, or a session verified against the
+/// configured secret. Bearer tokens that are neither are rejected.
+fn credential_of(headers: &HeaderMap) -> Result<Option<Credential>, AppError> {
+    let Some(token) = headers.get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer ")) else {
+        return Ok(None);
+    };
+    if token.starts_with(API_TOKEN_PREFIX) {
+        return Ok(Some(Credential::ApiToken(token.to_string())));
+    }
+    app_config().session_secret.as_ref()
+        .and_then(|secret| verify_session(token, secret.as_bytes(), current_timestamp() / 1000))
+        .map(|user_id| Some(Credential::Session(user_id)))
+        .ok_or(AppError::Unauthorized("Invalid or expired session".to_string()))
+}
+
+fn set_caller(request: &mut Request, caller: &str) {
+    match HeaderValue::from_str(caller) {
+        Ok(value) => {
+            request.headers_mut().insert(USER_HEADER, value);
+        }
+        Err(_) => warn!("caller {} cannot be passed on as a header", caller),
+    }
+}
+
+fn is_admin_user(user_id: &str) -> bool {
+    app_config().admin_user_ids.iter().any(|admin_user_id| admin_user_id == user_id)
+}
+
+/// API tokens carry their own scope instead of memberships. The token stands in as the caller,
+/// `api-token:<id>`, so changes made through it are attributed to it.
+async fn authorize_api_token(repository: &Repository, token: &str, path: &str, mut request: Request, next: Next) -> Result<Response, AppError> {
+    let invalid = || AppError::Unauthorized("Invalid API token".to_string());
+    let (id, secret) = parse(token).ok_or_else(invalid)?;
+    let api_token = repository.api_tokens()
+        .get("eren", id)
+        .await?
+        .filter(|api_token| api_token.secret_hash == hash_secret(secret))
+        .ok_or_else(invalid)?;
+    if is_operator_path(path) || !api_token.scope.allows(request.method().as_str(), path) {
+        return Err(AppError::Forbidden(format!("{:?} API token does not allow this request", api_token.scope)));
+    }
+    let now = current_timestamp();
+    if api_token.last_used_at.is_none_or(|last_used_at| now.saturating_sub(last_used_at) >= LAST_USED_RESOLUTION_MILLIS) {
+        let repository = repository.clone();
+        let (customer_id, id) = (api_token.customer_id.clone(), api_token.id.clone());
+        tokio::spawn(async move {
+            if let Err(err) = repository.api_tokens().set_last_used_at(&customer_id, &id, now).await {
+                warn!("could not record the use of API token {}: {:?}", id, err);
+            }
+        });
+    }
+    set_caller(&mut request, &format!("api-token:{}", api_token.id));
+    Ok(next.run(request).await)
+}
+
+/// Operator endpoints span customers, so memberships do not apply; only the configured admin users
+/// get through, and nobody when none are configured.
+async fn authorize_operator(user_id: &str, request: Request, next: Next) -> Result<Response, AppError> {
+    if !is_admin_user(user_id) {
+        return Err(AppError::Forbidden("Operator access is required".to_string()));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_bearer_credentials_identify_the_caller() {
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            pairs.iter().for_each(|(name, value)| {
+                headers.insert(*name, HeaderValue::from_str(value).unwrap());
+            });
+            headers
+        };
+
+        assert!(matches!(credential_of(&headers(&[(USER_HEADER, "owner")])), Ok(None)));
+        assert!(matches!(credential_of(&headers(&[("authorization", "Bearer prt_a1.secret")])), Ok(Some(Credential::ApiToken(_)))));
+        assert!(credential_of(&headers(&[("authorization", "Bearer not-a-session")])).is_err());
+    }
+}
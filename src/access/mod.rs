@@ -0,0 +1,6 @@
+pub mod model;
+pub mod service;
+pub mod token;
+pub mod session;
+pub mod api;
+pub mod middleware;
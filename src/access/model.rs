@@ -0,0 +1,190 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+pub enum Role {
+    Viewer,
+    Editor,
+    Admin,
+}
+
+/// Grants a user a role on every test case of the customer, or on a single one when `test_case_id` is set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Membership {
+    pub customer_id: String,
+    pub id: String,
+    pub user_id: String,
+    pub test_case_id: Option<String>,
+    pub role: Role,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl Membership {
+    pub fn id_of(user_id: &str, test_case_id: Option<&str>) -> String {
+        match test_case_id {
+            None => user_id.to_string(),
+            Some(test_case_id) => format!("{}#{}", user_id, test_case_id),
+        }
+    }
+}
+
+/// What a customer API token may call. Tokens act on their own and skip memberships.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum TokenScope {
+    /// The GET routes a viewer may call; viewer routes that create something, like share links,
+    /// are left out.
+    ReadOnly,
+    /// Reading, plus starting and resuming runs.
+    RunOnly,
+    /// Whatever an admin may call.
+    Admin,
+}
+
+impl TokenScope {
+    pub fn allows(&self, method: &str, path: &str) -> bool {
+        let reading = ["GET", "HEAD"].contains(&method);
+        match (self, required_role(method, path)) {
+            (_, None) | (TokenScope::Admin, _) => true,
+            (_, Some(Role::Viewer)) if reading => true,
+            (TokenScope::RunOnly, Some(_)) => method == "POST" && ["/test-cases/:id/run", "/runs/:run_id/resume"].contains(&path),
+            (TokenScope::ReadOnly, Some(_)) => false,
+        }
+    }
+}
+
+/// A long-lived credential for integrations like CI. Only the hash of its secret is stored.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct ApiToken {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().simple().to_string())]
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub secret_hash: String,
+    pub created_by: Option<String>,
+    pub last_used_at: Option<u64>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+/// An API token as listed to users, without the secret hash.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ApiTokenSummary {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_by: Option<String>,
+    pub last_used_at: Option<u64>,
+    pub created_at: Option<u64>,
+}
+
+impl From<ApiToken> for ApiTokenSummary {
+    fn from(api_token: ApiToken) -> Self {
+        ApiTokenSummary {
+            id: api_token.id,
+            name: api_token.name,
+            scope: api_token.scope,
+            created_by: api_token.created_by,
+            last_used_at: api_token.last_used_at,
+            created_at: api_token.created_at,
+        }
+    }
+}
+
+/// Response of token creation, the only time the token itself is shown.
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct CreatedApiToken {
+    pub token: String,
+    pub api_token: ApiTokenSummary,
+}
+
+/// The role a request needs, `None` for the unauthenticated operational endpoints.
+pub fn required_role(method: &str, path: &str) -> Option<Role> {
+    if ["/healthz", "/readyz", "/metrics", "/openapi.json", "/docs"].contains(&path) || path.starts_with("/docs/") || path.starts_with("/shared/") {
+        return None;
+    }
+    if path.ends_with("/share") || path == "/json-path/evaluate" {
+        return Some(Role::Viewer);
+    }
+    let admin_only = path.starts_with("/auth-providers") || path.starts_with("/memberships") || path.starts_with("/api-tokens") || path == "/quota" || path == "/sensitive-headers";
+    Some(match method {
+        "GET" | "HEAD" | "OPTIONS" => Role::Viewer,
+        "DELETE" => Role::Admin,
+        _ if admin_only => Role::Admin,
+        _ => Role::Editor,
+    })
+}
+
+/// Operator endpoints report on every customer and are gated by configuration instead of roles.
+pub fn is_operator_path(path: &str) -> bool {
+    path.starts_with("/admin/")
+}
+
+/// Highest role the user holds either customer-wide or on the given test case.
+pub fn effective_role(memberships: &[Membership], user_id: &str, test_case_id: Option<&str>) -> Option<Role> {
+    memberships.iter()
+        .filter(|membership| membership.user_id == user_id)
+        .filter(|membership| membership.test_case_id.is_none() || membership.test_case_id.as_deref() == test_case_id)
+        .map(|membership| membership.role)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn membership(user_id: &str, test_case_id: Option<&str>, role: Role) -> Membership {
+        Membership::builder()
+            .customer_id("cust1".to_string())
+            .id(Membership::id_of(user_id, test_case_id))
+            .user_id(user_id.to_string())
+            .maybe_test_case_id(test_case_id.map(|id| id.to_string()))
+            .role(role)
+            .build()
+    }
+
+    #[test]
+    fn resolve_required_role() {
+        assert_eq!(required_role("GET", "/test-cases/:id/runs"), Some(Role::Viewer));
+        assert_eq!(required_role("POST", "/test-cases/:id/run"), Some(Role::Editor));
+        assert_eq!(required_role("DELETE", "/test-cases/:id"), Some(Role::Admin));
+        assert_eq!(required_role("PATCH", "/auth-providers/:id/value"), Some(Role::Admin));
+        assert_eq!(required_role("GET", "/healthz"), None);
+        assert_eq!(required_role("GET", "/docs/swagger-ui.css"), None);
+        assert_eq!(required_role("GET", "/shared/runs/:token"), None);
+        assert_eq!(required_role("POST", "/runs/:run_id/share"), Some(Role::Viewer));
+        assert_eq!(required_role("POST", "/json-path/evaluate"), Some(Role::Viewer));
+        assert!(is_operator_path("/admin/customers/:id/usage"));
+        assert!(!is_operator_path("/auth-providers"));
+    }
+
+    #[test]
+    fn token_scope_limits_routes() {
+        assert!(TokenScope::ReadOnly.allows("GET", "/test-cases/:id/runs"));
+        assert!(!TokenScope::ReadOnly.allows("POST", "/test-cases/:id/run"));
+        assert!(!TokenScope::ReadOnly.allows("POST", "/runs/:run_id/share"));
+        assert!(!TokenScope::ReadOnly.allows("POST", "/json-path/evaluate"));
+        assert!(!TokenScope::RunOnly.allows("POST", "/runs/:run_id/share"));
+        assert!(TokenScope::RunOnly.allows("POST", "/test-cases/:id/run"));
+        assert!(TokenScope::RunOnly.allows("POST", "/runs/:run_id/resume"));
+        assert!(!TokenScope::RunOnly.allows("PATCH", "/test-cases/:id/name"));
+        assert!(!TokenScope::RunOnly.allows("POST", "/api-tokens"));
+        assert!(TokenScope::Admin.allows("DELETE", "/api-tokens/:id"));
+        assert!(TokenScope::ReadOnly.allows("GET", "/healthz"));
+    }
+
+    #[test]
+    fn test_case_membership_extends_customer_role() {
+        let memberships = vec![
+            membership("ayse", None, Role::Viewer),
+            membership("ayse", Some("tc1"), Role::Editor),
+            membership("mehmet", Some("tc2"), Role::Admin),
+        ];
+        assert_eq!(effective_role(&memberships, "ayse", Some("tc1")), Some(Role::Editor));
+        assert_eq!(effective_role(&memberships, "ayse", Some("tc2")), Some(Role::Viewer));
+        assert_eq!(effective_role(&memberships, "ayse", None), Some(Role::Viewer));
+        assert_eq!(effective_role(&memberships, "mehmet", Some("tc1")), None);
+    }
+}
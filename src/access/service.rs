@@ -0,0 +1,116 @@
+use crate::access::model::{ApiToken, Membership};
+use crate::api::AppError;
+use crate::persistence::repo::Table;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use serde_dynamo::to_attribute_value;
+use std::sync::Arc;
+
+pub struct MembershipOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct MembershipsTable();
+
+impl Table<Membership> for MembershipsTable {
+    fn table_name() -> String {
+        "memberships".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Membership) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &Membership) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl MembershipOperations {
+    pub async fn put(&self, membership: Membership) -> Result<Membership, AppError> {
+        MembershipsTable::put_item(self.client.clone(), membership).await
+    }
+
+    pub async fn list_all(&self, customer_id: &String) -> Result<Vec<Membership>, AppError> {
+        MembershipsTable::list_all_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, id: &String) -> Result<Option<Membership>, AppError> {
+        MembershipsTable::delete_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    /// Deletes the memberships granted on the test case, customer-wide ones are kept.
+    pub async fn delete_all_of_test_case(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Membership>, AppError> {
+        let memberships: Vec<Membership> = self.list_all(customer_id)
+            .await?
+            .into_iter()
+            .filter(|membership| membership.test_case_id.as_ref() == Some(test_case_id))
+            .collect();
+        let keys = memberships.iter().map(|membership| (membership.customer_id.clone(), membership.id.clone())).collect();
+        MembershipsTable::batch_delete_items(self.client.clone(), keys).await?;
+        Ok(memberships)
+    }
+}
+
+pub struct ApiTokenOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct ApiTokensTable();
+
+impl Table<ApiToken> for ApiTokensTable {
+    fn table_name() -> String {
+        "api_tokens".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &ApiToken) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &ApiToken) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl ApiTokenOperations {
+    pub async fn create(&self, api_token: ApiToken) -> Result<ApiToken, AppError> {
+        ApiTokensTable::put_item(self.client.clone(), api_token).await
+    }
+
+    pub async fn get(&self, customer_id: &str, id: &str) -> Result<Option<ApiToken>, AppError> {
+        ApiTokensTable::get_item(self.client.clone(), customer_id.to_string(), id.to_string()).await
+    }
+
+    pub async fn list_all(&self, customer_id: &str) -> Result<Vec<ApiToken>, AppError> {
+        ApiTokensTable::list_all_items(self.client.clone(), customer_id.to_string()).await
+    }
+
+    pub async fn delete(&self, customer_id: &str, id: &str) -> Result<Option<ApiToken>, AppError> {
+        ApiTokensTable::delete_item(self.client.clone(), customer_id.to_string(), id.to_string()).await
+    }
+
+    /// Records the token's use; a token deleted in the meantime is not brought back.
+    pub async fn set_last_used_at(&self, customer_id: &str, id: &str, last_used_at: u64) -> Result<ApiToken, AppError> {
+        ApiTokensTable::update_partial(customer_id.to_string(), id.to_string(),
+                                       self.client.clone()
+                                           .update_item()
+                                           .update_expression("SET last_used_at = :last_used_at")
+                                           .expression_attribute_values(":last_used_at", to_attribute_value(last_used_at).unwrap())).await
+    }
+}
@@ -0,0 +1,41 @@
+use sha2::{Digest, Sha256};
+
+/// Marks bearer tokens that are customer API tokens rather than some other credential.
+pub const API_TOKEN_PREFIX: &str = "prt_";
+
+/// A new token for the given token id as `prt_<id>.<secret>`, with the hash of the secret to store.
+pub fn issue(id: &str) -> (String, String) {
+    let secret = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let secret_hash = hash_secret(&secret);
+    (format!("{}{}.{}", API_TOKEN_PREFIX, id, secret), secret_hash)
+}
+
+/// Splits a token into its id and secret, `None` when it is not an API token.
+pub fn parse(token: &str) -> Option<(&str, &str)> {
+    let (id, secret) = token.strip_prefix(API_TOKEN_PREFIX)?.split_once('.')?;
+    (!id.is_empty() && !secret.is_empty()).then_some((id, secret))
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_token_parses_back() {
+        let (token, secret_hash) = issue("a1b2");
+        let (id, secret) = parse(&token).unwrap();
+
+        assert_eq!(id, "a1b2");
+        assert_eq!(hash_secret(secret), secret_hash);
+        assert_ne!(secret, secret_hash);
+        assert_eq!(parse("a1b2.secret"), None);
+        assert_eq!(parse("prt_a1b2"), None);
+        assert_eq!(parse("prt_.secret"), None);
+    }
+}
@@ -1,32 +1,505 @@
-use crate::action::model::Action;
-use crate::api::{ApiResponse, AppError};
-use crate::persistence::model::QueryResult;
+use crate::action::impact::{action_impact, rename_action};
+use crate::action::model::{Action, ActionBudget, ActionHooks, ActionImpact, ActionType, ExpectedStatus, RedirectPolicy, RequestHook, ResourceTracking, ResponseHook};
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::http::HttpMethod;
+use crate::persistence::model::{QueryResult, SortOrder};
 use crate::persistence::repo::Repository;
+use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+use crate::revision::history::{record_before_change, snapshot_before_change};
+use crate::run::hooks::template_expressions;
+use crate::validation::{AppJson, ValidJson, Validate, check_action_name, check_base_url, check_expression, check_not_blank};
+use crate::change::version::IfMatch;
 use axum::extract::{Path, Query, State};
+use reqwest::header::HeaderName;
 use serde::Deserialize;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
 
 pub async fn list_actions(
     Path(test_case_id): Path<String>,
     params: Query<ActionQueryParams>,
     State(repository): State<Repository>,
 ) -> Result<ApiResponse<QueryResult<Action>>, AppError> {
-    let result = match params.before_order {
-        None => {
+    let result = match (&params.group_id, params.before_order) {
+        (Some(group_id), before_order) => {
             repository
                 .actions()
-                .list("eren".to_string(), test_case_id.to_string(), None)
+                .list_group("eren".to_string(), test_case_id.to_string(), group_id.clone(), before_order, params.next_page_key.clone(), params.order.unwrap_or(SortOrder::Asc))
                 .await
         }
-        Some(order) => {
+        (None, None) => {
             repository
                 .actions()
-                .list_previous("eren".to_string(), test_case_id.to_string(), order, None)
+                .list("eren".to_string(), test_case_id.to_string(), params.next_page_key.clone(), params.order.unwrap_or(SortOrder::Asc))
+                .await
+        }
+        (None, Some(order)) => {
+            repository
+                .actions()
+                .list_previous("eren".to_string(), test_case_id.to_string(), order, params.next_page_key.clone(), params.order.unwrap_or(SortOrder::Asc))
                 .await
         }
     };
     ApiResponse::from(result)
 }
-#[derive(Deserialize)]
+pub async fn update_action_delay(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionDelayPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action delay update").await;
+    let result = repository
+        .actions()
+        .update_delay("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_content_type(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionContentTypePayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action content type update").await;
+    let result = repository
+        .actions()
+        .update_mime_type("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_redirect_policy(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionRedirectPolicyPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action redirect policy update").await;
+    let result = repository
+        .actions()
+        .update_redirect_policy("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_auth_provider(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionAuthProviderPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    if let Some(auth_provider_id) = &payload.value {
+        repository.auth_providers()
+            .get(&"eren".to_string(), auth_provider_id.clone())
+            .await?
+            .ok_or(AppError::NotFound("Auth provider not found!".to_string()))?;
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action auth provider update").await;
+    let result = repository
+        .actions()
+        .update_auth_provider("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_volatile_paths(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionVolatilePathsPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    if let Some(path) = payload.value.iter().find(|path| !path.trim().starts_with('$')) {
+        return Err(AppError::Validation(format!("Volatile path must start with $: {}", path)));
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action volatile paths update").await;
+    let result = repository
+        .actions()
+        .update_volatile_paths("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_name(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<PatchActionNamePayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action rename").await;
+    let result = rename_action(&repository, &"eren".to_string(), &test_case_id, &id, payload.value).await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// What references the action, to be reviewed before deleting it.
+pub async fn get_action_impact(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<ActionImpact>, AppError> {
+    let result = action_impact(&repository, &"eren".to_string(), &test_case_id, &id).await;
+    ApiResponse::from(result)
+}
+
+/// Points a single action at another host than the rest of its test case, none to follow the test case again.
+pub async fn update_action_base_url(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<PatchActionBaseUrlPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action base url update").await;
+    let result = repository
+        .actions()
+        .update_base_url("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Marks the action as a cleanup step that runs at the end of every run.
+pub async fn update_action_teardown(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionTeardownPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action teardown update").await;
+    let result = repository
+        .actions()
+        .update_teardown("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Sets the duration and response size limits of the action, or removes them with `null`.
+pub async fn update_action_budget(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<PatchActionBudgetPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action budget update").await;
+    let result = repository
+        .actions()
+        .update_budget("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Sets the status the response must have, a code like `201` or a class like `4xx`, or goes back
+/// to accepting any 2xx or 3xx with `null`.
+pub async fn update_action_expected_status(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionExpectedStatusPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action expected status update").await;
+    let result = repository
+        .actions()
+        .update_expected_status("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Marks the action as creating a resource runs list in their manifest, or unmarks it with `null`.
+pub async fn update_action_resource_tracking(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<PatchActionResourceTrackingPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action resource tracking update").await;
+    let result = repository
+        .actions()
+        .update_resource_tracking("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Replaces the pre-request and post-response hooks of the action.
+pub async fn update_action_hooks(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<PatchActionHooksPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action hooks update").await;
+    let result = repository
+        .actions()
+        .update_hooks("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_notes(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionAnnotationPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action notes update").await;
+    let result = repository
+        .actions()
+        .update_notes("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_action_owner(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchActionAnnotationPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action owner update").await;
+    let result = repository
+        .actions()
+        .update_owner("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn mark_action_reviewed(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+) -> Result<ApiResponse<Action>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action review").await;
+    let result = repository
+        .actions()
+        .mark_reviewed("eren".to_string(), test_case_id, id, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn create_sleep_action(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<CreateSleepActionPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    let action = Action::builder()
+        .customer_id("eren".to_string())
+        .test_case_id(test_case_id.clone())
+        .order(payload.order)
+        .name(payload.name.unwrap_or(format!("sleep_{}", payload.order)))
+        .url("".to_string())
+        .method("".to_string())
+        .action_type(ActionType::Sleep)
+        .delay_ms(payload.delay_ms)
+        .build();
+    let permit = guard_test_case_write(&repository, &"eren".to_string(), &test_case_id, TestCaseWrite {
+        actions: std::slice::from_ref(&action),
+        ..Default::default()
+    }).await?;
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "sleep action insert").await;
+    let result = repository.actions().insert(action, permit).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionDelayPayload {
+    pub value: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionContentTypePayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionRedirectPolicyPayload {
+    pub value: RedirectPolicy,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionAuthProviderPayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionVolatilePathsPayload {
+    pub value: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionNamePayload {
+    pub value: String,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionAnnotationPayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionBaseUrlPayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionTeardownPayload {
+    pub value: bool,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionBudgetPayload {
+    pub value: Option<ActionBudget>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionExpectedStatusPayload {
+    pub value: Option<ExpectedStatus>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionResourceTrackingPayload {
+    pub value: Option<ResourceTracking>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchActionHooksPayload {
+    pub value: ActionHooks,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CreateSleepActionPayload {
+    pub name: Option<String>,
+    pub order: usize,
+    pub delay_ms: u64,
+}
+
+#[derive(Deserialize, IntoParams)]
 pub struct ActionQueryParams {
     before_order: Option<usize>,
-}
\ No newline at end of file
+    group_id: Option<String>,
+    order: Option<SortOrder>,
+    next_page_key: Option<String>,
+}
+
+impl Validate for PatchActionNamePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_action_name("value", &self.value).into_iter().collect()
+    }
+}
+
+impl Validate for PatchActionBaseUrlPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        self.value.as_ref()
+            .and_then(|value| check_base_url("value", value))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl Validate for PatchActionBudgetPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let Some(budget) = &self.value else {
+            return vec![];
+        };
+        let mut errors = vec![];
+        if budget.max_duration_ms.is_none() && budget.max_response_bytes.is_none() {
+            errors.push(FieldError::new("value", "must set max_duration_ms or max_response_bytes"));
+        }
+        if budget.max_duration_ms == Some(0) {
+            errors.push(FieldError::new("value.max_duration_ms", "must be positive"));
+        }
+        if budget.max_response_bytes == Some(0) {
+            errors.push(FieldError::new("value.max_response_bytes", "must be positive"));
+        }
+        errors
+    }
+}
+
+impl Validate for PatchActionResourceTrackingPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let Some(tracking) = &self.value else {
+            return vec![];
+        };
+        let mut errors: Vec<FieldError> = check_expression("value.id_expression", &tracking.id_expression).into_iter().collect();
+        if let Some(teardown) = &tracking.teardown {
+            if HttpMethod::from_str(&teardown.method.to_uppercase()).is_err() {
+                errors.push(FieldError::new("value.teardown.method", "is not a supported HTTP method"));
+            }
+            if !teardown.url.contains("{id}") {
+                errors.push(FieldError::new("value.teardown.url", "must contain {id}"));
+            }
+        }
+        errors
+    }
+}
+
+impl Validate for PatchActionHooksPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        for (index, hook) in self.value.pre_request.iter().enumerate() {
+            let field = format!("value.pre_request[{}]", index);
+            let (name, templates) = match hook {
+                RequestHook::SetHeader { name, value } => (Some(name), vec![value]),
+                RequestHook::RemoveHeader { name } => (Some(name), vec![]),
+                RequestHook::SetQueryParam { name, value } => {
+                    errors.extend(check_not_blank(&format!("{}.name", field), name));
+                    (None, vec![value])
+                }
+                RequestHook::HmacHeader { name, secret, message, .. } => (Some(name), vec![secret, message]),
+            };
+            if name.is_some_and(|name| HeaderName::from_bytes(name.as_bytes()).is_err()) {
+                errors.push(FieldError::new(format!("{}.name", field), "must be a valid header name"));
+            }
+            templates.into_iter().for_each(|template| errors.extend(check_template(&field, template)));
+        }
+        for (index, hook) in self.value.post_response.iter().enumerate() {
+            let field = format!("value.post_response[{}]", index);
+            match hook {
+                ResponseHook::ParseJson { pointer } | ResponseHook::DecodeBase64 { pointer } => {
+                    errors.extend(check_pointer(&field, pointer));
+                }
+                ResponseHook::Select { expression } => {
+                    errors.extend(check_expression(&format!("{}.expression", field), expression));
+                }
+                ResponseHook::SetField { pointer, value } => {
+                    errors.extend(check_pointer(&field, pointer));
+                    errors.extend(check_template(&field, value));
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn check_template(field: &str, template: &str) -> Vec<FieldError> {
+    match template_expressions(template) {
+        Ok(expressions) => expressions.into_iter()
+            .filter_map(|expression| check_expression(field, expression))
+            .collect(),
+        Err(err) => vec![FieldError::new(field, err)],
+    }
+}
+
+fn check_pointer(field: &str, pointer: &str) -> Option<FieldError> {
+    (!pointer.is_empty() && !pointer.starts_with('/'))
+        .then(|| FieldError::new(format!("{}.pointer", field), "must be a JSON pointer like /data/payload"))
+}
+
+impl Validate for CreateSleepActionPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        self.name.as_ref()
+            .and_then(|name| check_not_blank("name", name))
+            .into_iter()
+            .collect()
+    }
+}
@@ -0,0 +1,15 @@
+use crate::action::model::Action;
+
+/// An action of test case `tc1` of customer `cust1`. Its id is `{name}_id`, so tests tell ids and
+/// names apart; anything else is set with struct update syntax.
+pub fn test_action(name: &str, order: usize, method: &str, url: &str) -> Action {
+    Action::builder()
+        .customer_id("cust1".to_string())
+        .test_case_id("tc1".to_string())
+        .id(format!("{}_id", name))
+        .order(order)
+        .url(url.to_string())
+        .name(name.to_string())
+        .method(method.to_string())
+        .build()
+}
@@ -0,0 +1,199 @@
+use crate::action::model::{Action, ActionImpact, AssertionReference, ParameterReference};
+use crate::action::service::ActionsTable;
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, AssertionItem, ComparisonType, ValueProvider};
+use crate::assertion::service::AssertionsTable;
+use crate::change::model::ChangeKind;
+use crate::lint::analysis::expressions_of;
+use crate::parameter::model::Parameter;
+use crate::parameter::service::ParametersTable;
+use crate::persistence::repo::{current_timestamp, Repository, Table};
+use std::collections::HashMap;
+
+/// Lists the parameters and assertions that would break if the action were deleted.
+pub async fn action_impact(repository: &Repository, customer_id: &String, test_case_id: &String, id: &String) -> Result<ActionImpact, AppError> {
+    let action = repository.actions()
+        .get(customer_id.clone(), test_case_id.clone(), id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Action not found!".to_string()))?;
+    let parameters = repository.parameters().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let assertions = repository.assertions().list_all(customer_id, test_case_id).await?;
+    Ok(find_impact(&action, &parameters, &assertions))
+}
+
+/// Renames the action and rewrites every expression referring to it by name transactionally, so a
+/// failure leaves the old name and the old expressions in place.
+pub async fn rename_action(repository: &Repository, customer_id: &String, test_case_id: &String, id: &String, name: String) -> Result<Action, AppError> {
+    let actions = repository.actions().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let action = actions.iter()
+        .find(|action| &action.id == id)
+        .ok_or(AppError::NotFound("Action not found!".to_string()))?;
+    if action.name == name {
+        return Ok(action.clone());
+    }
+    if actions.iter().any(|other| other.name.eq_ignore_ascii_case(&name)) {
+        return Err(AppError::Conflict(format!("An action named {} already exists!", name)));
+    }
+    let parameters = repository.parameters().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let assertions = repository.assertions().list_all(customer_id, test_case_id).await?;
+    let previous_parameters: HashMap<String, Parameter> = parameters.iter()
+        .map(|parameter| (parameter.id.clone(), parameter.clone()))
+        .collect();
+    let previous_assertions: HashMap<String, Assertion> = assertions.iter()
+        .map(|assertion| (assertion.id.clone(), assertion.clone()))
+        .collect();
+    let version = Some(current_timestamp());
+    let renamed = Action { name: name.clone(), updated_at: version, ..action.clone() };
+    let parameters: Vec<Parameter> = rename_in_parameters(parameters, &action.name, &name).into_iter()
+        .map(|parameter| Parameter { updated_at: version, ..parameter })
+        .collect();
+    let assertions: Vec<Assertion> = rename_in_assertions(assertions, &action.name, &name).into_iter()
+        .map(|assertion| Assertion { updated_at: version, ..assertion })
+        .collect();
+    let mut items = vec![ActionsTable::transact_put(&renamed, Some(action))];
+    items.extend(parameters.iter().map(|parameter| ParametersTable::transact_put(parameter, previous_parameters.get(&parameter.id))));
+    items.extend(assertions.iter().map(|assertion| AssertionsTable::transact_put(assertion, previous_assertions.get(&assertion.id))));
+    repository.transact_write(items).await?;
+    ActionsTable::publish_change(&renamed, ChangeKind::Updated);
+    parameters.iter().for_each(|parameter| ParametersTable::publish_change(parameter, ChangeKind::Updated));
+    assertions.iter().for_each(|assertion| AssertionsTable::publish_change(assertion, ChangeKind::Updated));
+    Ok(renamed)
+}
+
+pub fn find_impact(action: &Action, parameters: &[Parameter], assertions: &[Assertion]) -> ActionImpact {
+    let parameters = parameters.iter()
+        .filter_map(|parameter| {
+            let expression = parameter.value_expression.as_ref()?;
+            strip_reference(&expression.value, &action.name)?;
+            Some(ParameterReference {
+                action_id: parameter.action_id.clone(),
+                parameter_id: parameter.id.clone(),
+                expression: expression.value.clone(),
+            })
+        })
+        .collect();
+    let assertions = assertions.iter()
+        .filter_map(|assertion| {
+            let snapshot = assertion.comparison_type == ComparisonType::MatchesSnapshot
+                && assertion.snapshot_action_id.as_ref() == Some(&action.id);
+            let expressions: Vec<String> = expressions_of(&assertion.left).into_iter()
+                .chain(expressions_of(&assertion.right))
+                .filter(|expression| strip_reference(expression, &action.name).is_some())
+                .cloned()
+                .collect();
+            (snapshot || !expressions.is_empty()).then(|| AssertionReference {
+                assertion_id: assertion.id.clone(),
+                expressions,
+                snapshot,
+            })
+        })
+        .collect();
+    ActionImpact { action_id: action.id.clone(), parameters, assertions }
+}
+
+/// Parameters whose expression changed, the untouched ones are left out.
+fn rename_in_parameters(parameters: Vec<Parameter>, old_name: &str, new_name: &str) -> Vec<Parameter> {
+    parameters.into_iter()
+        .filter_map(|mut parameter| {
+            let expression = parameter.value_expression.as_mut()?;
+            expression.value = rename_reference(&expression.value, old_name, new_name)?;
+            Some(parameter)
+        })
+        .collect()
+}
+
+/// Assertions with at least one expression changed, the untouched ones are left out.
+fn rename_in_assertions(assertions: Vec<Assertion>, old_name: &str, new_name: &str) -> Vec<Assertion> {
+    assertions.into_iter()
+        .filter_map(|mut assertion| {
+            let left = rename_in_item(&mut assertion.left, old_name, new_name);
+            let right = rename_in_item(&mut assertion.right, old_name, new_name);
+            (left || right).then_some(assertion)
+        })
+        .collect()
+}
+
+fn rename_in_item(item: &mut AssertionItem, old_name: &str, new_name: &str) -> bool {
+    let providers = item.value_provider.iter_mut()
+        .chain(item.function.iter_mut().flat_map(|function| function.parameters.iter_mut()));
+    let mut changed = false;
+    for provider in providers {
+        if let ValueProvider { expression: Some(expression), .. } = provider {
+            if let Some(renamed) = rename_reference(&expression.value, old_name, new_name) {
+                expression.value = renamed;
+                changed = true;
+            }
+        }
+    }
+    changed
+}
+
+fn rename_reference(expression: &str, old_name: &str, new_name: &str) -> Option<String> {
+    strip_reference(expression, old_name).map(|rest| format!("$.{}{}", new_name, rest))
+}
+
+/// What follows `$.name` when the expression reads from the action called `name`.
+fn strip_reference<'e>(expression: &'e str, name: &str) -> Option<&'e str> {
+    let rest = expression.trim().strip_prefix("$.")?.strip_prefix(name)?;
+    (rest.is_empty() || rest.starts_with(['.', '['])).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::model::{Function, Operation};
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use serde_json::json;
+
+    fn input(id: &str, expression: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id("search_1".to_string())
+            .id(id.to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Query("q".to_string()))
+            .value(json!("x"))
+            .value_expression(Expression { value: expression.to_string() })
+            .build()
+    }
+
+    fn expression(value: &str) -> ValueProvider {
+        ValueProvider { expression: Some(Expression { value: value.to_string() }), value: None }
+    }
+
+    #[test]
+    fn rewrite_references_to_renamed_action() {
+        let action = test_action("login_0", 0, "POST", "https://abc.xyz");
+        let parameters = vec![
+            input("p1", "$.login_0.output.token"),
+            input("p2", "$.login_0_retry.output.token"),
+            input("p3", "$.login_0[0]"),
+        ];
+        let assertion = Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("a1".to_string())
+            .left(AssertionItem::from_function(Function {
+                operation: Operation::Sum,
+                parameters: vec![expression("$.login_0.output.items[*].price"), expression("$.other.output.price")],
+            }))
+            .right(AssertionItem::from_value(json!(10)))
+            .comparison_type(ComparisonType::EqualTo)
+            .build();
+
+        let impact = find_impact(&action, &parameters, std::slice::from_ref(&assertion));
+        assert_eq!(impact.parameters.iter().map(|reference| reference.parameter_id.as_str()).collect::<Vec<_>>(), vec!["p1", "p3"]);
+        assert_eq!(impact.assertions[0].expressions, vec!["$.login_0.output.items[*].price"]);
+
+        let renamed: Vec<String> = rename_in_parameters(parameters, "login_0", "sign_in")
+            .into_iter()
+            .map(|parameter| parameter.value_expression.unwrap().value)
+            .collect();
+        assert_eq!(renamed, vec!["$.sign_in.output.token", "$.sign_in[0]"]);
+        let renamed = rename_in_assertions(vec![assertion], "login_0", "sign_in");
+        assert_eq!(expressions_of(&renamed[0].left), vec!["$.sign_in.output.items[*].price", "$.other.output.price"]);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod api;
 pub mod model;
-pub mod service;
\ No newline at end of file
+pub mod service;
+pub mod impact;
+#[cfg(test)]
+pub mod fixtures;
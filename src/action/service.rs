@@ -1,13 +1,18 @@
-use crate::action::model::Action;
+use crate::action::model::{Action, ActionBudget, ActionHooks, ExpectedStatus, RedirectPolicy, ResourceTracking};
 use crate::api::AppError;
-use crate::persistence::model::{ListItemsRequest, PageKey, QueryResult};
-use crate::persistence::repo::{build_composite_key, OnDeleteMessage, Table};
+use crate::change::model::{EntityChange, EntityType};
+use crate::persistence::model::{QueryResult, SortOrder};
+use crate::persistence::repo::{build_composite_key, current_timestamp, Table, TransactionItem};
+use crate::quota::guard::QuotaPermit;
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
+use serde_dynamo::to_attribute_value;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+const GROUP_PAGE_SIZE: usize = 50;
+
 pub struct ActionOperations {
     pub(crate) client: Arc<Client>,
 }
@@ -37,7 +42,13 @@ impl Table<Action> for ActionsTable {
         Self::sort_key(build_composite_key(vec![entity.id.clone()]))
     }
 
+    fn versioned() -> bool {
+        true
+    }
 
+    fn change_of(entity: &Action) -> Option<EntityChange> {
+        Some(EntityChange::updated(&entity.customer_id, &entity.test_case_id, EntityType::Action, &entity.id, entity.updated_at))
+    }
 
     fn add_index_key_attributes(entity: &Action, item: &mut HashMap<String, AttributeValue>) {
         item.insert(
@@ -46,31 +57,42 @@ impl Table<Action> for ActionsTable {
         );
     }
 
-    fn build_deleted_event(entity: Action) -> Option<OnDeleteMessage> {
-        Some(OnDeleteMessage::ActionDeleted(entity))
-    }
-
     fn ordering(e1: &Action, e2: &Action) -> Ordering {
         e1.order.cmp(&e2.order)
     }
 }
 
+impl ActionsTable {
+    /// The put of a new action within a transaction, needing a permit like the other action inserts.
+    pub(crate) fn transact_create(action: &Action, _permit: &QuotaPermit) -> TransactionItem {
+        Self::transact_put(action, None)
+    }
+}
+
 impl ActionOperations {
 
+    /// Pages through actions on `order_index` (customer_id#test_case_id, order) so the order holds
+    /// across pages.
     pub async fn list(
         &self,
         customer_id: String,
         test_case_id: String,
         next_page_key: Option<String>,
+        order: SortOrder,
     ) -> Result<QueryResult<Action>, AppError> {
-        ActionsTable::list_items(
-            self.client.clone(),
-            ListItemsRequest::builder()
-                .partition_key(build_composite_key(vec![customer_id, test_case_id]))
-                .maybe_next_page_key(next_page_key)
-                .build()
-        )
-            .await
+        let partition_key =
+            ActionsTable::partition_key(build_composite_key(vec![customer_id, test_case_id]));
+        let result = ActionsTable::query_builder(self.client.clone())
+            .index_name("order_index")
+            .scan_index_forward(order.scan_index_forward())
+            .expression_attribute_names("#pk", partition_key.0)
+            .expression_attribute_values(":pk", partition_key.1)
+            .key_condition_expression("#pk = :pk")
+            .set_exclusive_start_key(ActionsTable::build_exclusion_key(next_page_key))
+            .send()
+            .await;
+
+        ActionsTable::from_ordered_query_result(result)
     }
 
     pub async fn list_previous(
@@ -79,23 +101,64 @@ impl ActionOperations {
         test_case_id: String,
         before_order: usize,
         next_page_key: Option<String>,
+        order: SortOrder,
     ) -> Result<QueryResult<Action>, AppError> {
         let partition_key =
             ActionsTable::partition_key(build_composite_key(vec![customer_id, test_case_id]));
         let result = ActionsTable::query_builder(self.client.clone())
+            .index_name("order_index")
+            .scan_index_forward(order.scan_index_forward())
             .expression_attribute_names("#pk", partition_key.0)
             .expression_attribute_names("#order", "order")
             .expression_attribute_values(":pk", partition_key.1)
             .expression_attribute_values(":order", AttributeValue::N(before_order.to_string()))
-            .key_condition_expression("#pk = :pk")
-            .filter_expression("#order < :order")
-            .set_exclusive_start_key(
-                next_page_key.map(|next| PageKey::from_next_page_key(&next).to_attribute_values()),
-            )
+            .key_condition_expression("#pk = :pk AND #order < :order")
+            .set_exclusive_start_key(ActionsTable::build_exclusion_key(next_page_key))
             .send()
             .await;
 
-        ActionsTable::from_query_result(result)
+        ActionsTable::from_ordered_query_result(result)
+    }
+
+    /// Pages through the actions of a group like `list` and `list_previous` do. The group is
+    /// filtered in the query, which evaluates at most a page of items per call, so queries are
+    /// repeated until the page is full or the test case is exhausted.
+    pub async fn list_group(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        group_id: String,
+        before_order: Option<usize>,
+        next_page_key: Option<String>,
+        order: SortOrder,
+    ) -> Result<QueryResult<Action>, AppError> {
+        let partition_key =
+            ActionsTable::partition_key(build_composite_key(vec![customer_id, test_case_id]));
+        let mut page = QueryResult { items: vec![], next_page_key };
+        loop {
+            let mut query = ActionsTable::query_builder(self.client.clone())
+                .index_name("order_index")
+                .scan_index_forward(order.scan_index_forward())
+                .limit((GROUP_PAGE_SIZE - page.items.len()) as i32)
+                .expression_attribute_names("#pk", partition_key.0.clone())
+                .expression_attribute_names("#group_id", "group_id")
+                .expression_attribute_values(":pk", partition_key.1.clone())
+                .expression_attribute_values(":group_id", AttributeValue::S(group_id.clone()))
+                .key_condition_expression("#pk = :pk")
+                .filter_expression("#group_id = :group_id")
+                .set_exclusive_start_key(ActionsTable::build_exclusion_key(page.next_page_key.take()));
+            if let Some(before_order) = before_order {
+                query = query.expression_attribute_names("#order", "order")
+                    .expression_attribute_values(":order", AttributeValue::N(before_order.to_string()))
+                    .key_condition_expression("#pk = :pk AND #order < :order");
+            }
+            let next = ActionsTable::from_ordered_query_result(query.send().await)?;
+            page.items.extend(next.items);
+            page.next_page_key = next.next_page_key;
+            if page.items.len() >= GROUP_PAGE_SIZE || page.next_page_key.is_none() {
+                return Ok(page);
+            }
+        }
     }
 
     pub async fn get(
@@ -132,7 +195,239 @@ impl ActionOperations {
 
     }
 
-    pub async fn batch_create(&self, actions: Vec<Action>) {
+    pub async fn batch_create(&self, actions: Vec<Action>, _permit: QuotaPermit) {
         ActionsTable::batch_put_item(self.client.clone(), actions).await
     }
+
+    pub async fn update_delay(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        delay_ms: Option<u64>,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #delay = :delay")
+                                         .expression_attribute_names("#delay", "delay_ms")
+                                         .expression_attribute_values(":delay", delay_ms.map_or(AttributeValue::Null(true), |delay| AttributeValue::N(delay.to_string()))))
+            .await
+    }
+
+    pub async fn update_mime_type(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        mime_type: Option<String>,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #mime_type = :mime_type")
+                                         .expression_attribute_names("#mime_type", "mime_type")
+                                         .expression_attribute_values(":mime_type", mime_type.map_or(AttributeValue::Null(true), AttributeValue::S)))
+            .await
+    }
+
+    pub async fn update_redirect_policy(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        redirect_policy: RedirectPolicy,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #redirect_policy = :redirect_policy")
+                                         .expression_attribute_names("#redirect_policy", "redirect_policy")
+                                         .expression_attribute_values(":redirect_policy", to_attribute_value(redirect_policy).unwrap()))
+            .await
+    }
+
+    pub async fn update_volatile_paths(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        volatile_paths: Vec<String>,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #volatile_paths = :volatile_paths")
+                                         .expression_attribute_names("#volatile_paths", "volatile_paths")
+                                         .expression_attribute_values(":volatile_paths", to_attribute_value(volatile_paths).unwrap()))
+            .await
+    }
+
+    pub async fn update_auth_provider(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        auth_provider_id: Option<String>,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #auth_provider_id = :auth_provider_id")
+                                         .expression_attribute_names("#auth_provider_id", "auth_provider_id")
+                                         .expression_attribute_values(":auth_provider_id", auth_provider_id.map_or(AttributeValue::Null(true), AttributeValue::S)))
+            .await
+    }
+
+    pub async fn update_group(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+        group_id: Option<String>,
+        expected_updated_at: Option<u64>,
+    ) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone()
+                                         .update_item()
+                                         .update_expression("SET #group_id = :group_id")
+                                         .expression_attribute_names("#group_id", "group_id")
+                                         .expression_attribute_values(":group_id", group_id.map_or(AttributeValue::Null(true), AttributeValue::S)))
+            .await
+    }
+
+    pub async fn update_base_url(&self, customer_id: String, test_case_id: String, id: String, base_url: Option<String>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #base_url = :value")
+                                         .expression_attribute_names("#base_url", "base_url")
+                                         .expression_attribute_values(":value", base_url.map_or(AttributeValue::Null(true), AttributeValue::S))).await
+    }
+
+    pub async fn update_teardown(&self, customer_id: String, test_case_id: String, id: String, teardown: bool, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #teardown = :value")
+                                         .expression_attribute_names("#teardown", "teardown")
+                                         .expression_attribute_values(":value", AttributeValue::Bool(teardown))).await
+    }
+
+    pub async fn update_budget(&self, customer_id: String, test_case_id: String, id: String, budget: Option<ActionBudget>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #budget = :value")
+                                         .expression_attribute_names("#budget", "budget")
+                                         .expression_attribute_values(":value", to_attribute_value(budget).unwrap())).await
+    }
+
+    pub async fn update_expected_status(&self, customer_id: String, test_case_id: String, id: String, expected_status: Option<ExpectedStatus>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #expected_status = :value")
+                                         .expression_attribute_names("#expected_status", "expected_status")
+                                         .expression_attribute_values(":value", to_attribute_value(expected_status).unwrap())).await
+    }
+
+    pub async fn update_resource_tracking(&self, customer_id: String, test_case_id: String, id: String, tracking: Option<ResourceTracking>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #creates_resource = :value")
+                                         .expression_attribute_names("#creates_resource", "creates_resource")
+                                         .expression_attribute_values(":value", to_attribute_value(tracking).unwrap())).await
+    }
+
+    pub async fn update_hooks(&self, customer_id: String, test_case_id: String, id: String, hooks: ActionHooks, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #hooks = :value")
+                                         .expression_attribute_names("#hooks", "hooks")
+                                         .expression_attribute_values(":value", to_attribute_value(hooks).unwrap())).await
+    }
+
+    pub async fn update_notes(&self, customer_id: String, test_case_id: String, id: String, notes: Option<String>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #notes = :value")
+                                         .expression_attribute_names("#notes", "notes")
+                                         .expression_attribute_values(":value", notes.map_or(AttributeValue::Null(true), AttributeValue::S))).await
+    }
+
+    pub async fn update_owner(&self, customer_id: String, test_case_id: String, id: String, owner: Option<String>, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #owner = :value")
+                                         .expression_attribute_names("#owner", "owner")
+                                         .expression_attribute_values(":value", owner.map_or(AttributeValue::Null(true), AttributeValue::S))).await
+    }
+
+    pub async fn mark_reviewed(&self, customer_id: String, test_case_id: String, id: String, expected_updated_at: Option<u64>) -> Result<Action, AppError> {
+        ActionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                     self.client.clone().update_item()
+                                         .update_expression("SET #last_reviewed_at = :value")
+                                         .expression_attribute_names("#last_reviewed_at", "last_reviewed_at")
+                                         .expression_attribute_values(":value", AttributeValue::N(current_timestamp().to_string()))).await
+    }
+
+    pub async fn delete(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        id: String,
+    ) -> Result<Option<Action>, AppError> {
+        ActionsTable::delete_item(self.client.clone(), build_composite_key(vec![customer_id, test_case_id]), id)
+            .await
+    }
+
+    pub async fn delete_all(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+    ) -> Result<Vec<Action>, AppError> {
+        ActionsTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id, test_case_id]))
+            .await
+    }
+
+    pub async fn insert(&self, action: Action, _permit: QuotaPermit) -> Result<Action, AppError> {
+        self.shift_orders(action.customer_id.clone(), action.test_case_id.clone(), action.order, 1).await?;
+        ActionsTable::put_item(self.client.clone(), action).await
+    }
+
+    pub async fn count(&self, customer_id: &String, test_case_id: &String) -> Result<u64, AppError> {
+        ActionsTable::count_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()])).await
+    }
+
+    pub async fn list_all(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+    ) -> Result<Vec<Action>, AppError> {
+        ActionsTable::list_all_items(self.client.clone(), build_composite_key(vec![customer_id, test_case_id]))
+            .await
+    }
+
+    pub async fn shift_orders(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        from_order: usize,
+        by: usize,
+    ) -> Result<(), AppError> {
+        let partition_key = build_composite_key(vec![customer_id, test_case_id]);
+        let actions = ActionsTable::list_all_items(self.client.clone(), partition_key.clone()).await?;
+        for action in actions.iter().filter(|action| action.order >= from_order) {
+            ActionsTable::update_partial(partition_key.clone(), action.id.clone(),
+                                         self.client.clone()
+                                             .update_item()
+                                             .update_expression("SET #order = :order")
+                                             .expression_attribute_names("#order", "order")
+                                             .expression_attribute_values(":order", AttributeValue::N((action.order + by).to_string())))
+                .await?;
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,15 +1,41 @@
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
+use serde::Deserialize;
 use crate::action_execution::model::ActionExecutionPair;
 use crate::api::{ApiResponse, AppError, AppState};
+use crate::persistence::model::View;
+use utoipa::IntoParams;
 
 pub async fn get_action_executions(
     Path(path_params): Path<(String, String)>,
+    Query(params): Query<ActionExecutionParams>,
     State(app_state): State<AppState>,
 ) -> Result<ApiResponse<Vec<ActionExecutionPair>>, AppError> {
+    let groups = app_state
+        .repository
+        .action_groups()
+        .list_all(&"eren".to_string(), &path_params.0)
+        .await?;
     let result = app_state
         .repository
         .action_executions()
-        .list_with_actions(&"eren".to_string(), &path_params.0, &path_params.1)
-        .await;
+        .list_with_actions(&"eren".to_string(), &path_params.0, &path_params.1, params.view)
+        .await
+        .map(|pairs| pairs.into_iter()
+            .map(|pair| {
+                let group_id = pair.action.as_ref().and_then(|action| action.group_id.clone());
+                ActionExecutionPair {
+                    group: group_id.and_then(|group_id| groups.iter().find(|group| group.id == group_id).cloned()),
+                    ..pair
+                }
+            })
+            .filter(|pair| params.group_id.is_none() || pair.group.as_ref().map(|group| &group.id) == params.group_id.as_ref())
+            .collect());
     ApiResponse::from(result)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ActionExecutionParams {
+    pub group_id: Option<String>,
+    #[serde(default)]
+    pub view: View,
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{Map, Number, Value};
+use sha2::{Digest, Sha256};
+
+/// Same content, same value: object keys are sorted and floats without a fractional part are
+/// written as integers, so `{"b": 1.0, "a": 2}` and `{"a": 2, "b": 1}` serialize identically.
+pub fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(entries.into_iter()
+                .map(|(key, val)| (key.clone(), canonicalize(val)))
+                .collect::<Map<String, Value>>())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        Value::Number(number) => Value::Number(canonical_number(number)),
+        _ => value.clone(),
+    }
+}
+
+fn canonical_number(number: &Number) -> Number {
+    match number.as_f64() {
+        Some(float) if number.is_f64() && float.fract() == 0.0 && float.abs() < i64::MAX as f64 => Number::from(float as i64),
+        _ => number.clone(),
+    }
+}
+
+/// Hash of the canonical form; equal hashes mean equal content whatever the key order.
+pub fn canonical_hash(value: &Value) -> String {
+    let canonical = serde_json::to_vec(&canonicalize(value)).unwrap_or_default();
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn canonical_form_ignores_key_order_and_float_notation() {
+        let recorded = json!({"b": 1.0, "a": {"y": [2.5, -0.0], "x": null}});
+        let live = json!({"a": {"x": null, "y": [2.5, 0]}, "b": 1});
+        assert_eq!(serde_json::to_string(&canonicalize(&recorded)).unwrap(), r#"{"a":{"x":null,"y":[2.5,0]},"b":1}"#);
+        assert_eq!(canonical_hash(&recorded), canonical_hash(&live));
+        assert_ne!(canonical_hash(&recorded), canonical_hash(&json!({"b": 2, "a": {"x": null, "y": [2.5, 0]}})));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod model;
 pub mod api;
-pub mod service;
\ No newline at end of file
+pub mod service;
+pub mod canonical;
\ No newline at end of file
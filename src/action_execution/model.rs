@@ -1,9 +1,14 @@
-use crate::action::model::Action;
+use crate::action::model::{Action, BudgetViolation, ResponseHook};
+use crate::action_execution::canonical::{canonical_hash, canonicalize};
+use crate::artifact::model::ArtifactRef;
+use crate::group::model::ActionGroup;
+use crate::http::RedirectHop;
 use bon::Builder;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Clone, Builder)]
+#[derive(Serialize, Deserialize, Clone, Builder, ToSchema)]
 pub struct ActionExecution {
     pub run_id: String,
     pub customer_id: String,
@@ -13,17 +18,65 @@ pub struct ActionExecution {
     pub id: String,
     pub status_code: u16,
     pub error: Option<String>,
+    /// Stored in canonical form, see `with_canonical_response`.
     pub response_body: Option<Value>,
+    /// Hash of the canonical response body, compared to tell unchanged responses apart without a diff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_hash: Option<String>,
+    /// Where a response body too large to keep inline was moved; `response_body` is empty then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_artifact: Option<ArtifactRef>,
     pub request_body: Option<Value>,
+    /// URL the request went to, query string included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_url: Option<String>,
     pub query_params: Vec<(String, String)>,
+    #[serde(default)]
+    #[builder(default)]
+    pub request_headers: Vec<(String, String)>,
+    #[serde(default)]
+    #[builder(default)]
+    pub redirects: Vec<RedirectHop>,
+    /// Masked with the sensitive header policy like the request headers.
+    #[serde(default)]
+    #[builder(default)]
+    pub response_headers: Vec<(String, String)>,
+    /// Limits of the action's budget this execution went over.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub budget_violations: Vec<BudgetViolation>,
+    /// Errors of the action's hooks that could not be applied; the request went out, or the
+    /// response entered the context, without them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub hook_errors: Vec<String>,
+    /// Post-response hooks the response went through, so the context can be rebuilt the same
+    /// way after the action's hooks changed.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub post_response_hooks: Vec<ResponseHook>,
     pub started_at: Option<u64>,
     pub finished_at: Option<u64>,
     pub created_at: Option<u64>,
     pub updated_at: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Builder)]
+impl ActionExecution {
+    /// Canonicalizes the response body and records its hash.
+    pub fn with_canonical_response(self) -> Self {
+        let response_body = self.response_body.as_ref().map(canonicalize);
+        ActionExecution {
+            response_hash: response_body.as_ref().map(canonical_hash),
+            response_body,
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Builder, ToSchema)]
 pub struct ActionExecutionPair {
     pub action: Option<Action>,
     pub execution: ActionExecution,
+    #[serde(default)]
+    pub group: Option<ActionGroup>,
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use crate::action::service::ActionsTable;
 use crate::action_execution::model::{ActionExecution, ActionExecutionPair};
 use crate::api::AppError;
+use crate::artifact::store::restore_response_bodies;
+use crate::persistence::model::View;
 use crate::persistence::repo::{build_composite_key, Table};
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
@@ -13,6 +15,12 @@ pub struct ActionExecutionsOperations {
 }
 pub(crate) struct ActionExecutionTable();
 
+/// Everything but the stored request and response bodies, headers and redirect hops.
+const SUMMARY_ATTRIBUTES: [&str; 15] = [
+    "run_id", "customer_id", "test_case_id", "action_id", "id", "status_code", "error",
+    "response_hash", "response_artifact", "request_url", "query_params", "started_at", "finished_at", "created_at", "updated_at",
+];
+
 impl Table<ActionExecution> for ActionExecutionTable {
     fn table_name() -> String {
         "action_executions".to_string()
@@ -66,14 +74,20 @@ impl ActionExecutionsOperations {
         customer_id: &String,
         test_case_id: &String,
         run_id: &String,
+        view: View,
     ) -> Result<Vec<ActionExecutionPair>, AppError> {
-        let result = ActionExecutionTable::list_all_items(
+        let projected_attributes = match view {
+            View::Full => None,
+            View::Summary => Some(SUMMARY_ATTRIBUTES.iter().map(|attribute| attribute.to_string()).collect()),
+        };
+        let result = ActionExecutionTable::list_all_items_projected(
             self.client.clone(),
             build_composite_key(vec![
                 customer_id.clone(),
                 test_case_id.clone(),
                 run_id.clone(),
             ]),
+            projected_attributes,
         )
             .await;
         match result {
@@ -98,6 +112,7 @@ impl ActionExecutionsOperations {
                             .map(|exec| ActionExecutionPair {
                                 action: (actions.iter().find(|a| a.id.eq(&exec.action_id))).cloned(),
                                 execution: exec,
+                                group: None,
                             })
                             .collect();
                         pairs
@@ -123,6 +138,47 @@ impl ActionExecutionsOperations {
         ).await
     }
 
+    /// Like `list`, with the response bodies moved to the artifact bucket read back.
+    pub async fn list_with_bodies(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        run_id: &String,
+    ) -> Result<Vec<ActionExecution>, AppError> {
+        let executions = self.list(customer_id, test_case_id, run_id).await?;
+        Ok(restore_response_bodies(executions).await)
+    }
+
+    pub async fn get(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        run_id: &String,
+        id: &String,
+    ) -> Result<Option<ActionExecution>, AppError> {
+        ActionExecutionTable::get_item(
+            self.client.clone(),
+            build_composite_key(vec![customer_id.clone(), test_case_id.clone(), run_id.clone()]),
+            id.clone(),
+        ).await
+    }
+
+    pub async fn delete_all(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        run_id: &String,
+    ) -> Result<Vec<ActionExecution>, AppError> {
+        ActionExecutionTable::delete_all_items(
+            self.client.clone(),
+            build_composite_key(vec![
+                customer_id.clone(),
+                test_case_id.clone(),
+                run_id.clone(),
+            ]),
+        ).await
+    }
+
     pub async fn create(&self, action_execution: ActionExecution) -> ActionExecution {
         ActionExecutionTable::put_item(self.client.clone(), action_execution)
             .await
@@ -0,0 +1,29 @@
+use crate::admin::model::{CustomerOverview, CustomerUsage, SecretScan};
+use crate::admin::service::{get_customer_usage, list_customer_overviews, scan_secrets};
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn list_customers(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<CustomerOverview>>, AppError> {
+    let result = list_customer_overviews(&repository).await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_customer_usage_report(
+    Path(customer_id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<CustomerUsage>, AppError> {
+    let result = get_customer_usage(&repository, &customer_id).await;
+    ApiResponse::from(result)
+}
+
+/// Reports where secret-like values are stored, to clean them up before test cases are shared.
+pub async fn get_customer_secret_scan(
+    Path(customer_id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<SecretScan>, AppError> {
+    let result = scan_secrets(&repository, &customer_id).await;
+    ApiResponse::from(result)
+}
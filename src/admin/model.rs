@@ -0,0 +1,134 @@
+use crate::parameter::model::ParameterLocation;
+use crate::quota::model::QuotaUsage;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Days counted into `runs_last_30_days`, today included.
+pub const RUN_VOLUME_DAYS: u64 = 30;
+
+/// One line of the operator's customer list.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct CustomerOverview {
+    pub customer_id: String,
+    pub test_cases: u64,
+    pub runs_today: u64,
+    pub runs_last_30_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct CustomerUsage {
+    pub customer_id: String,
+    pub runs_today: u64,
+    pub runs_last_30_days: u64,
+    pub runs_in_progress: u64,
+    pub storage: StorageUsage,
+    pub test_cases: Vec<TestCaseUsage>,
+}
+
+/// Stored items of a customer by kind. Action executions are left out since counting them takes a
+/// query per run.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq, ToSchema)]
+pub struct StorageUsage {
+    pub test_cases: u64,
+    pub actions: u64,
+    pub parameters: u64,
+    pub assertions: u64,
+    pub runs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct TestCaseUsage {
+    pub test_case_id: String,
+    pub name: String,
+    pub archived: bool,
+    pub actions: u64,
+    pub parameters: u64,
+    pub assertions: u64,
+    pub runs: u64,
+}
+
+impl StorageUsage {
+    pub fn of(test_cases: &[TestCaseUsage]) -> Self {
+        test_cases.iter().fold(StorageUsage::default(), |total, usage| StorageUsage {
+            test_cases: total.test_cases + 1,
+            actions: total.actions + usage.actions,
+            parameters: total.parameters + usage.parameters,
+            assertions: total.assertions + usage.assertions,
+            runs: total.runs + usage.runs,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretKind {
+    Jwt,
+    AwsAccessKey,
+    BearerToken,
+}
+
+/// Where a secret-like value is stored. Auth providers are where credentials belong, parameters
+/// holding them travel with the test case when it is shared.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SecretLocation {
+    Parameter {
+        test_case_id: String,
+        action_id: String,
+        parameter_id: String,
+        location: ParameterLocation,
+    },
+    AuthProviderHeader {
+        auth_provider_id: String,
+        name: String,
+    },
+    AuthProviderCookie {
+        auth_provider_id: String,
+        name: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SecretFinding {
+    pub kind: SecretKind,
+    pub location: SecretLocation,
+    /// Start of the value with the rest masked, so the report does not leak it once more.
+    pub preview: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SecretScan {
+    pub customer_id: String,
+    pub parameters_scanned: u64,
+    pub auth_providers_scanned: u64,
+    pub findings: Vec<SecretFinding>,
+}
+
+/// Runs started within the last `days` days up to and including `today`, both day numbers as
+/// [`crate::quota::model::day_of`] gives them.
+pub fn runs_within(usages: &[QuotaUsage], today: u64, days: u64) -> u64 {
+    usages.iter()
+        .filter(|usage| usage.id.parse::<u64>().is_ok_and(|day| day <= today && day + days > today))
+        .map(|usage| usage.runs)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_runs_within_days() {
+        let usage = |day: u64, runs: u64| QuotaUsage {
+            customer_id: "cust1".to_string(),
+            id: day.to_string(),
+            runs,
+        };
+        let usages = vec![usage(100, 4), usage(129, 2), usage(130, 1), usage(131, 7)];
+
+        assert_eq!(runs_within(&usages, 130, 30), 3);
+        assert_eq!(runs_within(&usages, 130, 1), 1);
+        assert_eq!(runs_within(&usages, 131, 32), 14);
+        assert_eq!(runs_within(&[], 130, 30), 0);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::admin::model::{runs_within, CustomerOverview, CustomerUsage, SecretFinding, SecretLocation, SecretScan, StorageUsage, TestCaseUsage, RUN_VOLUME_DAYS};
+use crate::admin::secrets::{find_secrets, find_secrets_in_value};
+use crate::api::AppError;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::quota::model::day_of;
+use futures::future::try_join_all;
+
+/// Customers are the owners of test cases, since nothing else registers them.
+pub async fn list_customer_overviews(repository: &Repository) -> Result<Vec<CustomerOverview>, AppError> {
+    let customer_ids = repository.test_cases().list_customer_ids().await?;
+    try_join_all(customer_ids.into_iter().map(|customer_id| async move {
+        let (runs_today, runs_last_30_days) = run_volume(repository, &customer_id).await?;
+        Ok(CustomerOverview {
+            test_cases: repository.test_cases().count(&customer_id).await?,
+            customer_id,
+            runs_today,
+            runs_last_30_days,
+        })
+    })).await
+}
+
+pub async fn get_customer_usage(repository: &Repository, customer_id: &String) -> Result<CustomerUsage, AppError> {
+    let test_cases = repository.test_cases().list_all(customer_id).await?;
+    if test_cases.is_empty() {
+        return Err(AppError::NotFound(format!("Customer {} has no test cases!", customer_id)));
+    }
+    let test_cases = try_join_all(test_cases.into_iter().map(|test_case| async move {
+        Ok::<TestCaseUsage, AppError>(TestCaseUsage {
+            actions: repository.actions().count(customer_id, &test_case.id).await?,
+            parameters: repository.parameters().count(customer_id, &test_case.id).await?,
+            assertions: repository.assertions().count(customer_id, &test_case.id).await?,
+            runs: repository.runs().count(customer_id, &test_case.id).await?,
+            test_case_id: test_case.id,
+            name: test_case.name,
+            archived: test_case.archived,
+        })
+    })).await?;
+    let (runs_today, runs_last_30_days) = run_volume(repository, customer_id).await?;
+    Ok(CustomerUsage {
+        customer_id: customer_id.clone(),
+        runs_today,
+        runs_last_30_days,
+        runs_in_progress: repository.runs().count_in_progress(customer_id).await?,
+        storage: StorageUsage::of(&test_cases),
+        test_cases,
+    })
+}
+
+/// Looks through the parameters of every test case and the auth providers of the customer for
+/// values like JWTs, AWS access keys and bearer tokens.
+pub async fn scan_secrets(repository: &Repository, customer_id: &String) -> Result<SecretScan, AppError> {
+    let test_cases = repository.test_cases().list_all(customer_id).await?;
+    let parameters: Vec<_> = try_join_all(test_cases.iter().map(|test_case| async move {
+        repository.parameters().list_all(customer_id.clone(), test_case.id.clone()).await
+    })).await?.into_iter().flatten().collect();
+    let auth_providers = repository.auth_providers().list_all(customer_id).await?;
+    let in_parameters = parameters.iter().flat_map(|parameter| find_secrets_in_value(&parameter.value)
+        .into_iter()
+        .map(|(kind, preview)| SecretFinding {
+            kind,
+            location: SecretLocation::Parameter {
+                test_case_id: parameter.test_case_id.clone(),
+                action_id: parameter.action_id.clone(),
+                parameter_id: parameter.id.clone(),
+                location: parameter.location.clone(),
+            },
+            preview,
+        }));
+    let in_auth_providers = auth_providers.iter().flat_map(|provider| {
+        let headers = provider.headers_by_name.iter().map(|(name, header)| (SecretLocation::AuthProviderHeader {
+            auth_provider_id: provider.id.clone(),
+            name: name.clone(),
+        }, &header.value));
+        let cookies = provider.cookies_by_name.iter().map(|(name, cookie)| (SecretLocation::AuthProviderCookie {
+            auth_provider_id: provider.id.clone(),
+            name: name.clone(),
+        }, &cookie.value));
+        headers.chain(cookies)
+            .flat_map(|(location, value)| find_secrets(value)
+                .into_iter()
+                .map(move |(kind, preview)| SecretFinding { kind, location: location.clone(), preview }))
+    });
+    Ok(SecretScan {
+        customer_id: customer_id.clone(),
+        parameters_scanned: parameters.len() as u64,
+        auth_providers_scanned: auth_providers.len() as u64,
+        findings: in_parameters.chain(in_auth_providers).collect(),
+    })
+}
+
+async fn run_volume(repository: &Repository, customer_id: &String) -> Result<(u64, u64), AppError> {
+    let usages = repository.quotas().list_usages(customer_id).await?;
+    let today = day_of(current_timestamp()).parse().unwrap_or_default();
+    Ok((runs_within(&usages, today, 1), runs_within(&usages, today, RUN_VOLUME_DAYS)))
+}
@@ -1,26 +1,64 @@
-use crate::action::api::list_actions;
+use crate::access::api::{create_api_token, delete_api_token, delete_membership, list_api_tokens, list_memberships, put_membership};
+use crate::admin::api::{get_customer_secret_scan, get_customer_usage_report, list_customers};
+use crate::access::middleware::authorize;
+use crate::action::api::{create_sleep_action, get_action_impact, list_actions, update_action_name, mark_action_reviewed, update_action_notes, update_action_owner, update_action_base_url, update_action_teardown, update_action_budget, update_action_expected_status, update_action_hooks, update_action_resource_tracking, update_action_auth_provider, update_action_content_type, update_action_delay, update_action_redirect_policy, update_action_volatile_paths};
 use crate::action_execution::api::get_action_executions;
-use crate::assertion::api::{batch_get_assertions, delete_assertion, get_assertion, list_assertions, put_assertion, update_assertion_comparison, update_assertion_expression, update_assertion_negation};
-use crate::auth::api::{add_auth_header_value, create_auth_provider, delete_auth_provider, get_auth_provider, list_auth_providers, list_auth_providers_with_multiple_urls, set_auth_header_enablement, set_auth_header_value};
-use crate::case::api::{delete_test_case, filter_paths, get_test_case, list_test_cases, update_test_case, update_test_case_description, update_test_case_name, upload_test_case};
+use crate::assertion::api::{apply_assertion_template_to_actions, batch_get_assertions, delete_assertion, get_assertion, get_assertion_templates, list_assertions, mark_assertion_reviewed, put_assertion, update_assertion_notes, update_assertion_owner, update_assertion_comparison, update_assertion_expression, update_assertion_negation, update_assertion_severity};
+use crate::auth::api::{add_auth_cookie, add_auth_header_value, clone_auth_provider, set_auth_provider_environment, delete_auth_cookie, set_auth_cookie_enablement, set_auth_cookie_expiry, create_auth_provider, delete_auth_provider, get_auth_provider, list_auth_providers, list_auth_providers_with_multiple_urls, set_auth_header_enablement, set_auth_header_expiry, set_auth_header_value, set_auth_provider_signing};
+use crate::constant::api::{get_constants, get_test_case_constants, put_constants, put_test_case_constants};
+use crate::curl::api::{append_curl, get_action_curl};
+use crate::deletion::api::get_deletion_job;
+use crate::drift::api::get_drift_report;
+use crate::environment::api::{create_environment, delete_environment, get_environment, list_environments, update_environment};
+use crate::config::AppConfig;
+use crate::change::api::stream_changes;
+use crate::change::middleware::track_caller;
+use crate::case::api::{append_har, batch_delete_test_cases, delete_test_case, diff_har, filter_paths, get_test_case, list_test_cases, refresh_from_har, update_test_case, update_test_case_archived, update_test_case_base_url, update_test_case_concurrency_policy, update_test_case_context_retention, update_test_case_description, update_test_case_name, update_test_case_setup, upload_test_case};
+use crate::group::api::{create_action_group, delete_action_group, list_grouped_actions, move_action_to_group, rename_action_group};
+use crate::header_rule::api::{create_header_rule, delete_header_rule, get_header_rule, list_header_rules, update_header_rule};
 use crate::http::ApiClient;
-use crate::json_path::api::auto_complete;
-use crate::parameter::api::{list_parameters, update_parameter_expression};
+use crate::import_report::api::get_import_report;
+use crate::artifact::api::get_response_artifact_url;
+use crate::json_path::api::{auto_complete, evaluate};
+use crate::lint::api::get_lint_report;
+use crate::graph::api::get_test_case_graph;
+use crate::mock::api::serve_mock;
+use crate::openapi::routing::{any, delete, get, patch, post, DocumentedRouter};
+use crate::plugin::api::list_assertion_plugins;
+use crate::plugin::registry::plugins;
+use crate::plugin::wasm::load_plugins;
+use crate::metrics::api::{get_metrics, healthz, readyz};
+use crate::parameter::api::{batch_get_parameters, list_parameters, list_suggested_expressions, review_suggested_expressions, update_parameter_expression, update_parameter_value_type, update_parameter_multi_value_mode};
 use crate::persistence::repo::Repository;
-use crate::run::api::{get_run, list_runs, run_test_case};
+use crate::quota::api::{get_quota, put_quota};
+use crate::quota::model::QuotaViolation;
+pub use parroton_core::error::{ErrorBody, FieldError};
+use crate::run::api::{delete_run_resources, get_run, get_run_context, get_run_har, get_run_resources, list_recent_runs, list_runs, resume, run_test_case};
+use crate::revision::api::{create_revision, diff_revision, get_revision, list_revisions, restore};
+use crate::sensitive_header::api::{get_sensitive_header_policy, put_sensitive_header_policy};
+use crate::notification::api::{delete_digest_subscription, get_digest_subscription, get_notification_settings, put_digest_subscription, put_notification_settings};
+use crate::notification::digest::start_digest_job;
+use crate::share::api::{get_shared_run, share_run};
+use crate::snapshot::api::{accept_snapshot, delete_snapshot, get_snapshot};
+use crate::sync::api::{apply_test_case_yaml, get_test_case_yaml};
+use crate::telemetry::logging::init_logging;
+use crate::template::api::{apply_template_to_test_case, create_template, delete_template, get_template, list_templates};
 use axum::body::Body;
 use axum::extract::{DefaultBodyLimit, FromRef};
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
-use axum::routing::{delete, get, patch, post};
+use axum::middleware;
 use axum::Router;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use serde_json::json;
 use std::ops::Deref;
+use std::path::Path;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::{DefaultMakeSpan, DefaultOnRequest, DefaultOnResponse, TraceLayer};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnRequest, DefaultOnResponse, TraceLayer};
 use tower_http::LatencyUnit;
-use tracing::Level;
+use tracing::{error, info_span, warn, Level, Span};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -35,53 +73,155 @@ impl FromRef<AppState> for Repository {
     }
 }
 
-pub async fn build_api() -> Router {
-    tracing_subscriber::fmt::init();
-    let repository = Repository::new().await;
+pub async fn build_api(config: &AppConfig) -> Router {
+    init_logging(&config.log_format);
+    let repository = Repository::from_config(config).await;
+    if let Some(plugin_dir) = &config.plugin_dir {
+        load_plugins(plugins(), Path::new(plugin_dir), config.plugin_fuel);
+    }
 
     let cors = CorsLayer::new()
-        .allow_origin(Any) // Allow all origins (not recommended for production)
-        .allow_methods(Any) // Allow specific HTTP methods
-        .allow_headers(Any); // Allow specific headers
+        .allow_origin(build_allowed_origins(&config.cors_origins))
+        .allow_methods(Any)
+        .allow_headers(Any);
 
 
     let app_state = AppState {
         repository: Arc::new(repository),
-        api_client: Arc::new(ApiClient::new()),
+        api_client: Arc::new(ApiClient::from_config(config)),
     };
+    start_digest_job(Arc::clone(&app_state.repository));
 
-    Router::new()
+    DocumentedRouter::new()
         .route("/test-cases/:test_case_id/actions/:action_id/parameters/:id/expression", patch(update_parameter_expression))
+        .route("/test-cases/:test_case_id/actions/:action_id/parameters/:id/value-type", patch(update_parameter_value_type))
+        .route("/test-cases/:test_case_id/actions/:action_id/parameters/:id/multi-value-mode", patch(update_parameter_multi_value_mode))
         .route("/test-cases/:test_case_id/actions/:id/parameters", get(list_parameters))
+        .route("/test-cases/:test_case_id/parameters/batch-get", post(batch_get_parameters))
+        .route("/test-cases/:test_case_id/actions/:id/delay", patch(update_action_delay))
+        .route("/test-cases/:test_case_id/actions/:id/content-type", patch(update_action_content_type))
+        .route("/test-cases/:test_case_id/actions/:id/redirect-policy", patch(update_action_redirect_policy))
+        .route("/test-cases/:test_case_id/actions/:id/auth-provider", patch(update_action_auth_provider))
+        .route("/test-cases/:test_case_id/actions/:id/group", patch(move_action_to_group))
+        .route("/test-cases/:test_case_id/actions/:id/volatile-paths", patch(update_action_volatile_paths))
+        .route("/test-cases/:test_case_id/actions/:id/snapshot", get(get_snapshot).post(accept_snapshot).delete(delete_snapshot))
+        .route("/test-cases/:test_case_id/actions/:id/name", patch(update_action_name))
+        .route("/test-cases/:test_case_id/actions/:id/impact", get(get_action_impact))
+        .route("/test-cases/:test_case_id/actions/:id/notes", patch(update_action_notes))
+        .route("/test-cases/:test_case_id/actions/:id/owner", patch(update_action_owner))
+        .route("/test-cases/:test_case_id/actions/:id/base-url", patch(update_action_base_url))
+        .route("/test-cases/:test_case_id/actions/:id/teardown", patch(update_action_teardown))
+        .route("/test-cases/:test_case_id/actions/:id/budget", patch(update_action_budget))
+        .route("/test-cases/:test_case_id/actions/:id/expected-status", patch(update_action_expected_status))
+        .route("/test-cases/:test_case_id/actions/:id/resource-tracking", patch(update_action_resource_tracking))
+        .route("/test-cases/:test_case_id/actions/:id/hooks", patch(update_action_hooks))
+        .route("/test-cases/:test_case_id/actions/:id/reviewed", post(mark_action_reviewed))
+        .route("/test-cases/:test_case_id/actions/:id/curl", get(get_action_curl))
+        .route("/test-cases/:test_case_id/actions/sleep", post(create_sleep_action))
+        .route("/test-cases/:id/actions/from-curl", post(append_curl))
+        .route("/test-cases/:test_case_id/groups/:id", patch(rename_action_group).delete(delete_action_group))
+        .route("/test-cases/:test_case_id/groups", get(list_grouped_actions).post(create_action_group))
         .route("/test-cases/:test_case_id/actions", get(list_actions))
         .route("/test-cases/:id/runs/:run_id/action-executions", get(get_action_executions))
+        .route("/test-cases/:id/runs/:run_id/action-executions/:execution_id/response-url", get(get_response_artifact_url))
+        .route("/test-cases/:id/runs/:run_id/drift", get(get_drift_report))
         .route("/test-cases/:id/runs/:run_id", get(get_run))
         .route("/test-cases/:id/run", post(run_test_case))
         .route("/test-cases/:id/runs", get(list_runs))
+        .route("/runs", get(list_recent_runs))
+        .route("/runs/:run_id/resume", post(resume))
+        .route("/runs/:run_id/context", get(get_run_context))
+        .route("/runs/:run_id/resources", get(get_run_resources).delete(delete_run_resources))
+        .route("/runs/:run_id/har", get(get_run_har))
+        .route("/runs/:run_id/share", post(share_run))
+        .route("/shared/runs/:token", get(get_shared_run))
         .route("/test-cases/:test_case_id/assertions/:id/:location/expression", patch(update_assertion_expression))
         .route("/test-cases/:test_case_id/assertions/:id/comparison-type", patch(update_assertion_comparison))
         .route("/test-cases/:test_case_id/assertions/:id/negate", patch(update_assertion_negation))
+        .route("/test-cases/:test_case_id/assertions/:id/severity", patch(update_assertion_severity))
+        .route("/test-cases/:test_case_id/assertions/:id/notes", patch(update_assertion_notes))
+        .route("/test-cases/:test_case_id/assertions/:id/owner", patch(update_assertion_owner))
+        .route("/test-cases/:test_case_id/assertions/:id/reviewed", post(mark_assertion_reviewed))
         .route("/test-cases/:test_case_id/assertions/:id", get(get_assertion).delete(delete_assertion))
         .route("/test-cases/:id/assertions/batch-get", post(batch_get_assertions))
+        .route("/test-cases/:id/assertions/apply-template", post(apply_assertion_template_to_actions))
         .route("/test-cases/:id/assertions", get(list_assertions).put(put_assertion))
+        .route("/assertion-templates", get(get_assertion_templates))
+        .route("/test-cases/:id/apply-template", post(apply_template_to_test_case))
+        .route("/test-cases/:id/append-har", post(append_har))
+        .route("/test-cases/:id/refresh-from-har", post(refresh_from_har))
+        .route("/test-cases/:id/diff-har", post(diff_har))
+        .route("/test-cases/:id/mock/*path", any(serve_mock))
+        .route("/test-cases/:id/suggested-expressions/review", post(review_suggested_expressions))
+        .route("/test-cases/:id/suggested-expressions", get(list_suggested_expressions))
+        .route("/test-cases/:id/lint", get(get_lint_report))
+        .route("/test-cases/:id/graph", get(get_test_case_graph))
+        .route("/test-cases/:id/changes", get(stream_changes))
+        .route("/test-cases/:id/import-report", get(get_import_report))
+        .route("/test-cases/:id/yaml", get(get_test_case_yaml).put(apply_test_case_yaml))
+        .route("/test-cases/:test_case_id/revisions/:id/diff", get(diff_revision))
+        .route("/test-cases/:test_case_id/revisions/:id/restore", post(restore))
+        .route("/test-cases/:test_case_id/revisions/:id", get(get_revision))
+        .route("/test-cases/:id/revisions", get(list_revisions).post(create_revision))
+        .route("/test-cases/:id/constants", get(get_test_case_constants).put(put_test_case_constants))
         .route("/test-cases/:id/name", patch(update_test_case_name))
         .route("/test-cases/:id/description", patch(update_test_case_description))
+        .route("/test-cases/:id/concurrency-policy", patch(update_test_case_concurrency_policy))
+        .route("/test-cases/:id/context-retention", patch(update_test_case_context_retention))
+        .route("/test-cases/:id/archived", patch(update_test_case_archived))
+        .route("/test-cases/:id/base-url", patch(update_test_case_base_url))
+        .route("/test-cases/:id/setup", patch(update_test_case_setup))
+        .route("/test-cases/batch-delete", post(batch_delete_test_cases))
         .route("/test-cases/:id", get(get_test_case).delete(delete_test_case).patch(update_test_case))
         .route("/auth-providers/:id", delete(delete_auth_provider).get(get_auth_provider))
         .route("/auth-providers/:id/headers", patch(add_auth_header_value))
         .route("/auth-providers/:id/value", patch(set_auth_header_value))
         .route("/auth-providers/:id/disabled", patch(set_auth_header_enablement))
+        .route("/auth-providers/:id/expires-at", patch(set_auth_header_expiry))
+        .route("/auth-providers/:id/signing", patch(set_auth_provider_signing))
+        .route("/auth-providers/:id/environment", patch(set_auth_provider_environment))
+        .route("/auth-providers/:id/clone", post(clone_auth_provider))
+        .route("/auth-providers/:id/cookies", patch(add_auth_cookie))
+        .route("/auth-providers/:id/cookies/disabled", patch(set_auth_cookie_enablement))
+        .route("/auth-providers/:id/cookies/expires-at", patch(set_auth_cookie_expiry))
+        .route("/auth-providers/:id/cookies/:name", delete(delete_auth_cookie))
         .route("/auth-providers", post(create_auth_provider))
         .route("/test-cases", get(list_test_cases).post(upload_test_case))
         .route("/auth-providers/search-by-urls", post(list_auth_providers_with_multiple_urls))
         .route("/auth-providers", get(list_auth_providers))
+        .route("/deletion-jobs/:id", get(get_deletion_job))
+        .route("/header-rules/:id", get(get_header_rule).put(update_header_rule).delete(delete_header_rule))
+        .route("/header-rules", get(list_header_rules).post(create_header_rule))
+        .route("/environments/:id", get(get_environment).put(update_environment).delete(delete_environment))
+        .route("/environments", get(list_environments).post(create_environment))
+        .route("/constants", get(get_constants).put(put_constants))
+        .route("/templates/:id", get(get_template).delete(delete_template))
+        .route("/templates", get(list_templates).post(create_template))
         .route("/auto-complete", post(auto_complete))
+        .route("/json-path/evaluate", post(evaluate))
+        .route("/assertion-plugins", get(list_assertion_plugins))
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(get_metrics))
         .route("/filter-paths", post(filter_paths))
+        .route("/quota", get(get_quota).put(put_quota))
+        .route("/sensitive-headers", get(get_sensitive_header_policy).put(put_sensitive_header_policy))
+        .route("/notification-settings", get(get_notification_settings).put(put_notification_settings))
+        .route("/digest-subscription", get(get_digest_subscription).put(put_digest_subscription).delete(delete_digest_subscription))
+        .route("/memberships/:id", delete(delete_membership))
+        .route("/memberships", get(list_memberships).put(put_membership))
+        .route("/api-tokens/:id", delete(delete_api_token))
+        .route("/api-tokens", get(list_api_tokens).post(create_api_token))
+        .route("/admin/customers/:id/usage", get(get_customer_usage_report))
+        .route("/admin/customers/:id/secrets", get(get_customer_secret_scan))
+        .route("/admin/customers", get(list_customers))
+        .into_router()
+        .route_layer(middleware::from_fn(track_caller))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), authorize))
         .layer(cors)
-        .layer(DefaultBodyLimit::max(90003944))
+        .layer(DefaultBodyLimit::max(config.body_limit_bytes))
         .layer(TraceLayer::new_for_http()
-            .make_span_with(
-                DefaultMakeSpan::new().include_headers(true))
+            .make_span_with(make_request_span)
             .on_request(
                 DefaultOnRequest::new()
                     .level(Level::INFO))
@@ -90,8 +230,29 @@ pub async fn build_api() -> Router {
                     .level(Level::INFO)
                     .latency_unit(LatencyUnit::Micros)
             ))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(app_state)
 }
+
+fn make_request_span(request: &axum::http::Request<Body>) -> Span {
+    let request_id = request.headers().get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    info_span!("request", method = %request.method(), uri = %request.uri(), request_id = %request_id)
+}
+
+/// No origin is allowed when none are configured, so only same-origin callers get through.
+fn build_allowed_origins(origins: &[String]) -> AllowOrigin {
+    AllowOrigin::list(origins.iter().filter_map(|origin| match HeaderValue::from_str(origin) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            warn!("ignoring invalid CORS origin: {}", origin);
+            None
+        }
+    }))
+}
+
 pub struct ApiResponse<T>(pub T);
 
 impl<T> ApiResponse<T> {
@@ -141,54 +302,82 @@ where
 pub enum AppError {
     NotFound(String),
     Validation(String),
+    /// Request payload rejected with one entry per offending field.
+    InvalidFields(Vec<FieldError>),
     Processing(String),
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    QuotaExceeded(QuotaViolation),
     Internal(String),
 }
 
+impl AppError {
+    /// Stable, machine readable counterpart of the status code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) | AppError::InvalidFields(_) => "validation_failed",
+            AppError::Processing(_) => "processing_failed",
+            AppError::Conflict(_) => "conflict",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::QuotaExceeded(_) => "quota_exceeded",
+            AppError::Internal(_) => "internal",
+        }
+    }
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct ErrorBody {
-    pub message: String,
-}
-
-impl Into<Body> for ErrorBody {
-    fn into(self) -> Body {
-        Body::from(serde_json::to_string(&self).unwrap())
+    fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Validation(_) | AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
+            AppError::Processing(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::QuotaExceeded(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
     }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        match self {
-            AppError::NotFound(message) => {
-                Response::builder()
-                    .status(404)
-                    .header("Content-Type", "application/json")
-                    .body(ErrorBody { message }.into())
-                    .unwrap()
-            }
-            AppError::Validation(message) => {
-                Response::builder()
-                    .status(400)
-                    .header("Content-Type", "application/json")
-                    .body(ErrorBody { message }.into())
-                    .unwrap()
-            }
-            AppError::Processing(message) => {
-                Response::builder()
-                    .status(422)
-                    .header("Content-Type", "application/json")
-                    .body(ErrorBody { message }.into())
-                    .unwrap()
-            }
+        let status = self.status();
+        let code = self.code().to_string();
+        let body = match self {
+            AppError::QuotaExceeded(violation) => serde_json::to_string(&ErrorBody {
+                code,
+                message: violation.message.clone(),
+                errors: vec![],
+                details: Some(json!({"quota": violation.quota, "limit": violation.limit, "used": violation.used})),
+            }).unwrap(),
+            AppError::InvalidFields(errors) => serde_json::to_string(&ErrorBody {
+                code,
+                message: "Request payload is invalid".to_string(),
+                errors,
+                details: None,
+            }).unwrap(),
             AppError::Internal(message) => {
-                //tracing::error!("{}", message);
-                Response::builder()
-                    .status(500)
-                    .header("Content-Type", "application/json")
-                    .body(ErrorBody { message: "Internal server error".to_string() }.into())
-                    .unwrap()
+                error!("internal server error: {}", message);
+                serde_json::to_string(&ErrorBody {
+                    code,
+                    message: "Internal server error".to_string(),
+                    errors: vec![],
+                    details: None,
+                }).unwrap()
             }
-        }
+            AppError::NotFound(message)
+            | AppError::Validation(message)
+            | AppError::Processing(message)
+            | AppError::Conflict(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message) => serde_json::to_string(&ErrorBody { code, message, errors: vec![], details: None }).unwrap(),
+        };
+        Response::builder()
+            .status(status)
+            .header("Content-Type", "application/json")
+            .body(body.into())
+            .unwrap()
     }
-}
\ No newline at end of file
+}
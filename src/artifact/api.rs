@@ -0,0 +1,31 @@
+use crate::api::{ApiResponse, AppError};
+use crate::artifact::model::ArtifactUrl;
+use crate::artifact::store::artifact_store;
+use crate::http::BinaryBody;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+/// Presigned download URL of an execution's response body when it was moved to the artifact
+/// bucket, either for its size or because it is a binary download.
+pub async fn get_response_artifact_url(
+    Path((test_case_id, run_id, execution_id)): Path<(String, String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<ArtifactUrl>, AppError> {
+    let store = artifact_store()
+        .ok_or(AppError::NotFound("No artifact bucket is configured!".to_string()))?;
+    let execution = repository.action_executions()
+        .get(&"eren".to_string(), &test_case_id, &run_id, &execution_id)
+        .await?
+        .ok_or(AppError::NotFound("Action execution not found!".to_string()))?;
+    let binary_location = execution.response_body
+        .and_then(|body| serde_json::from_value::<BinaryBody>(body).ok())
+        .and_then(|binary| binary.stored_at);
+    let key = match (&execution.response_artifact, &binary_location) {
+        (Some(artifact), _) => artifact.key.as_str(),
+        (None, Some(location)) => store.key_of(location)
+            .ok_or(AppError::NotFound("The binary response is not stored in the artifact bucket!".to_string()))?,
+        (None, None) => return Err(AppError::NotFound("The response of this execution is stored inline!".to_string())),
+    };
+    let result = store.presigned_get(key).await;
+    ApiResponse::from(result)
+}
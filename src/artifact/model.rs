@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Object in the artifact bucket holding a payload kept out of DynamoDB.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct ArtifactRef {
+    pub key: String,
+    pub size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Presigned URL to download an artifact directly from the bucket.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct ArtifactUrl {
+    pub url: String,
+    pub expires_in_secs: u64,
+}
@@ -0,0 +1,238 @@
+use crate::action_execution::model::ActionExecution;
+use crate::api::AppError;
+use crate::artifact::model::{ArtifactRef, ArtifactUrl};
+use crate::config::AppConfig;
+use crate::http::BinaryBody;
+use aws_config::SdkConfig;
+use aws_sdk_s3::error::DisplayErrorContext;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use aws_sdk_s3::Client;
+use futures::future::join_all;
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+
+static STORE: OnceLock<ArtifactStore> = OnceLock::new();
+
+const S3_SCHEME: &str = "s3://";
+
+/// S3 bucket for payloads kept out of DynamoDB, accessed with the AWS config the repository uses.
+pub struct ArtifactStore {
+    bucket: String,
+    client: Client,
+    threshold_bytes: usize,
+    url_ttl: Duration,
+}
+
+/// Sets the store up when `artifact_bucket` is configured; without it payloads stay inline.
+pub fn init_artifact_store(sdk_config: &SdkConfig, app_config: &AppConfig) {
+    let Some(bucket) = &app_config.artifact_bucket else {
+        return;
+    };
+    if sdk_config.region().is_none() || sdk_config.credentials_provider().is_none() {
+        warn!("artifact bucket {} is configured without an AWS region or credentials, payloads stay inline", bucket);
+        return;
+    }
+    let _ = STORE.set(ArtifactStore {
+        bucket: bucket.clone(),
+        client: Client::new(sdk_config),
+        threshold_bytes: app_config.artifact_threshold_bytes,
+        url_ttl: Duration::from_secs(app_config.artifact_url_ttl_secs),
+    });
+}
+
+pub fn artifact_store() -> Option<&'static ArtifactStore> {
+    STORE.get()
+}
+
+impl ArtifactStore {
+    pub async fn put(&self, key: String, bytes: Vec<u8>, content_type: Option<String>) -> Result<ArtifactRef, AppError> {
+        let size = bytes.len();
+        self.client.put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .body(ByteStream::from(bytes))
+            .set_content_type(content_type.clone())
+            .send()
+            .await
+            .map_err(|err| AppError::Internal(format!("could not store artifact {}: {}", key, DisplayErrorContext(err))))?;
+        Ok(ArtifactRef { key, size, content_type })
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Vec<u8>, AppError> {
+        let object = self.client.get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|err| AppError::Internal(format!("could not read artifact {}: {}", key, DisplayErrorContext(err))))?;
+        object.body.collect().await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|err| AppError::Internal(format!("could not read artifact {}: {}", key, err)))
+    }
+
+    pub async fn presigned_get(&self, key: &str) -> Result<ArtifactUrl, AppError> {
+        let presigning = PresigningConfig::expires_in(self.url_ttl)
+            .map_err(|err| AppError::Internal(format!("could not presign artifact {}: {}", key, err)))?;
+        let request = self.client.get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(presigning)
+            .await
+            .map_err(|err| AppError::Internal(format!("could not presign artifact {}: {}", key, DisplayErrorContext(err))))?;
+        Ok(ArtifactUrl {
+            url: request.uri().to_string(),
+            expires_in_secs: self.url_ttl.as_secs(),
+        })
+    }
+
+    /// Deletes every object whose key starts with `prefix`, returning how many there were.
+    pub async fn delete_prefix(&self, prefix: &str) -> Result<usize, AppError> {
+        let mut deleted = 0;
+        let mut continuation_token = None;
+        loop {
+            let listed = self.client.list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await
+                .map_err(|err| AppError::Internal(format!("could not list artifacts under {}: {}", prefix, DisplayErrorContext(err))))?;
+            let objects: Vec<ObjectIdentifier> = listed.contents()
+                .iter()
+                .filter_map(|object| object.key())
+                .filter_map(|key| ObjectIdentifier::builder().key(key).build().ok())
+                .collect();
+            if !objects.is_empty() {
+                deleted += objects.len();
+                let delete = Delete::builder()
+                    .set_objects(Some(objects))
+                    .quiet(true)
+                    .build()
+                    .map_err(|err| AppError::Internal(format!("could not delete artifacts under {}: {}", prefix, err)))?;
+                self.client.delete_objects()
+                    .bucket(&self.bucket)
+                    .delete(delete)
+                    .send()
+                    .await
+                    .map_err(|err| AppError::Internal(format!("could not delete artifacts under {}: {}", prefix, DisplayErrorContext(err))))?;
+            }
+            match listed.next_continuation_token {
+                Some(token) if listed.is_truncated == Some(true) => continuation_token = Some(token),
+                _ => return Ok(deleted),
+            }
+        }
+    }
+
+    /// Key of an object of this bucket given as `s3://bucket/key`, like `BinaryBody::stored_at`.
+    pub fn key_of<'a>(&self, location: &'a str) -> Option<&'a str> {
+        location.strip_prefix(S3_SCHEME)?
+            .strip_prefix(self.bucket.as_str())?
+            .strip_prefix('/')
+    }
+}
+
+/// Prefix of the keys of what the runs of a test case store, binary bodies being shared by content
+/// hash and kept apart.
+pub fn test_case_prefix(customer_id: &str, test_case_id: &str) -> String {
+    format!("{}/{}/", customer_id, test_case_id)
+}
+
+/// Moves a response body larger than the threshold to the bucket, leaving a reference behind.
+/// When the upload fails the body stays inline.
+pub async fn offload_response_body(execution: ActionExecution) -> ActionExecution {
+    let Some(store) = artifact_store() else {
+        return execution;
+    };
+    let Some(body) = &execution.response_body else {
+        return execution;
+    };
+    let bytes = serde_json::to_vec(body).unwrap_or_default();
+    if bytes.len() <= store.threshold_bytes {
+        return execution;
+    }
+    let key = format!("{}{}/{}/response.json", test_case_prefix(&execution.customer_id, &execution.test_case_id), execution.run_id, execution.id);
+    match store.put(key, bytes, Some("application/json".to_string())).await {
+        Ok(artifact) => ActionExecution {
+            response_body: None,
+            response_artifact: Some(artifact),
+            ..execution
+        },
+        Err(err) => {
+            warn!("keeping the response body of execution {} inline: {:?}", execution.id, err);
+            execution
+        }
+    }
+}
+
+/// Reads offloaded response bodies back, for what replays a run from its executions.
+pub async fn restore_response_bodies(executions: Vec<ActionExecution>) -> Vec<ActionExecution> {
+    let Some(store) = artifact_store() else {
+        return executions;
+    };
+    join_all(executions.into_iter().map(|mut execution| async move {
+        if let (None, Some(artifact)) = (&execution.response_body, &execution.response_artifact) {
+            match store.get(&artifact.key).await.map(|bytes| serde_json::from_slice::<Value>(&bytes)) {
+                Ok(Ok(body)) => execution.response_body = Some(body),
+                Ok(Err(err)) => warn!("artifact {} is not JSON: {}", artifact.key, err),
+                Err(err) => warn!("could not restore the response body of execution {}: {:?}", execution.id, err),
+            }
+        }
+        execution
+    })).await
+}
+
+/// Uploads the bytes behind a binary response descriptor once per content hash.
+pub async fn store_binary_body(store: &ArtifactStore, parsed: Value, bytes: &[u8]) -> Value {
+    let Ok(binary) = serde_json::from_value::<BinaryBody>(parsed.clone()) else {
+        return parsed;
+    };
+    if binary.size != bytes.len() {
+        return parsed;
+    }
+    let key = format!("binary/{}", binary.sha256);
+    match store.put(key.clone(), bytes.to_vec(), binary.content_type.clone()).await {
+        Ok(_) => serde_json::to_value(BinaryBody {
+            stored_at: Some(format!("{}{}/{}", S3_SCHEME, store.bucket, key)),
+            ..binary
+        }).unwrap(),
+        Err(err) => {
+            warn!("could not store binary response body: {:?}", err);
+            parsed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_credential_types::Credentials;
+    use aws_sdk_s3::config::{BehaviorVersion, Region};
+
+    #[tokio::test]
+    async fn presigned_url_carries_the_signature() {
+        let config = aws_sdk_s3::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("eu-west-1"))
+            .credentials_provider(Credentials::new("AKIDEXAMPLE", "secret", None, None, "test"))
+            .build();
+        let store = ArtifactStore {
+            bucket: "artifacts".to_string(),
+            client: Client::from_conf(config),
+            threshold_bytes: 1024,
+            url_ttl: Duration::from_secs(900),
+        };
+
+        let presigned = store.presigned_get("binary/abc").await.unwrap();
+        assert!(presigned.url.starts_with("https://artifacts.s3.eu-west-1.amazonaws.com/binary/abc?"));
+        assert!(presigned.url.contains("X-Amz-Algorithm=AWS4-HMAC-SHA256"));
+        assert!(presigned.url.contains("X-Amz-Credential=AKIDEXAMPLE%2F"));
+        assert!(presigned.url.contains("X-Amz-Expires=900"));
+        assert!(presigned.url.contains("X-Amz-Signature="));
+        assert_eq!(presigned.expires_in_secs, 900);
+        assert_eq!(store.key_of("s3://artifacts/binary/abc"), Some("binary/abc"));
+    }
+}
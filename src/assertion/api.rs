@@ -1,16 +1,22 @@
-use crate::api::{ApiResponse, AppError, AppState};
-use crate::assertion::model::{Assertion, AssertionItem, ComparisonType};
+use crate::api::{ApiResponse, AppError, AppState, FieldError};
+use crate::assertion::model::{Assertion, AssertionItem, ComparisonType, Operation, PutAssertionPayload, Severity, Tolerance};
+use crate::assertion::template::{apply_assertion_template, list_assertion_templates, ApplyAssertionTemplateCommand, AssertionTemplate, AssertionTemplateInfo};
 use crate::persistence::model::QueryResult;
+use crate::plugin::registry::plugins;
 use crate::persistence::repo::Repository;
+use crate::revision::history::{record_before_change, snapshot_before_change};
+use crate::validation::{AppJson, ValidJson, Validate, check_expression, check_not_blank};
+use crate::change::version::IfMatch;
 use axum::extract::{Path, State};
-use axum::Json;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 pub async fn delete_assertion(
     State(repository): State<Repository>,
     Path(params): Path<AssertionsPathParam>,
 ) -> Result<ApiResponse<Option<Assertion>>, AppError>{
+    record_before_change(&repository, &"eren".to_string(), &params.test_case_id, "assertion delete").await;
     let result = repository.assertions()
         .delete("eren".to_string(), params.test_case_id, params.id).await;
     ApiResponse::from(result)
@@ -28,7 +34,7 @@ pub async fn get_assertion(
 pub async fn batch_get_assertions(
     Path(test_case_id): Path<String>,
     State(repository): State<Repository>,
-    Json(ids): Json<Vec<String>>,
+    AppJson(ids): AppJson<Vec<String>>,
 ) -> Result<ApiResponse<Vec<Assertion>>, AppError>{
     let result = repository.assertions()
         .batch_get("eren".to_string(), test_case_id, ids).await;
@@ -38,51 +44,130 @@ pub async fn batch_get_assertions(
 pub async fn update_assertion_comparison(
     Path((test_case_id, id)): Path<(String, String)>,
     State(repository): State<Repository>,
-    Json(payload): Json<PatchAssertionComparisonType>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchAssertionComparisonType>,
 ) -> Result<ApiResponse<Assertion>, AppError>{
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion comparison type update").await;
     let result = repository.assertions()
-        .update_comparison_type("eren".to_string(), test_case_id, id, payload.value)
+        .update_comparison_type("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
         .await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
 pub async fn update_assertion_negation(
     Path((test_case_id, id)): Path<(String, String)>,
     State(repository): State<Repository>,
-    Json(payload): Json<PatchAssertionNegation>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchAssertionNegation>,
 ) -> Result<ApiResponse<Assertion>, AppError>{
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion negation update").await;
     let result = repository.assertions()
-        .update_comparison_negation("eren".to_string(), test_case_id, id, payload.value)
+        .update_comparison_negation("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
         .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_assertion_severity(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchAssertionSeverity>,
+) -> Result<ApiResponse<Assertion>, AppError>{
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion severity update").await;
+    let result = repository.assertions()
+        .update_severity("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
 pub async fn update_assertion_expression(
     Path((test_case_id, id, location)): Path<(String, String, String)>,
     State(repository): State<Repository>,
-    Json(payload): Json<PatchAssertionExpression>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<PatchAssertionExpression>,
 ) -> Result<ApiResponse<Assertion>, AppError>{
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion expression update").await;
     let result = repository.assertions()
         .update_expression("eren".to_string(), test_case_id, id,
-                           if location.eq("left") {true} else {false}, payload.value)
+                           location.eq("left"), payload.value, expected_updated_at)
         .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_assertion_notes(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchAssertionAnnotation>,
+) -> Result<ApiResponse<Assertion>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion notes update").await;
+    let result = repository
+        .assertions()
+        .update_notes("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_assertion_owner(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchAssertionAnnotation>,
+) -> Result<ApiResponse<Assertion>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion owner update").await;
+    let result = repository
+        .assertions()
+        .update_owner("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn mark_assertion_reviewed(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+) -> Result<ApiResponse<Assertion>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion review").await;
+    let result = repository
+        .assertions()
+        .mark_reviewed("eren".to_string(), test_case_id, id, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
 pub async fn put_assertion(
     Path(test_case_id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<PutAssertionPayload>,
+    ValidJson(payload): ValidJson<PutAssertionPayload>,
 ) -> Result<ApiResponse<Assertion>, AppError>{
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion put").await;
+    let id = payload.id.unwrap_or(Uuid::new_v4().to_string());
+    let existing = repository.assertions()
+        .get("eren".to_string(), test_case_id.clone(), id.clone())
+        .await?;
     let result = repository.assertions()
         .put(Assertion::builder()
             .customer_id("eren".to_string())
             .test_case_id(test_case_id)
-            .id(payload.id.unwrap_or(Uuid::new_v4().to_string()))
+            .id(id)
             .left(payload.left)
             .right(payload.right)
             .comparison_type(payload.comparison_type)
             .negate(payload.negate)
+            .severity(payload.severity)
+            .maybe_snapshot_action_id(payload.snapshot_action_id)
+            .maybe_tolerance(payload.tolerance)
+            .maybe_duration_ms(payload.duration_ms)
+            .maybe_notes(existing.as_ref().and_then(|assertion| assertion.notes.clone()))
+            .maybe_owner(existing.as_ref().and_then(|assertion| assertion.owner.clone()))
+            .maybe_last_reviewed_at(existing.as_ref().and_then(|assertion| assertion.last_reviewed_at))
             .build()).await;
     ApiResponse::from(result)
 }
@@ -99,35 +184,160 @@ pub async fn list_assertions(
     ApiResponse::from(result)
 }
 
-#[derive(Deserialize, Clone)]
-pub struct PutAssertionPayload {
-    pub id: Option<String>,
-    pub left: AssertionItem,
-    pub right: AssertionItem,
-    pub comparison_type: ComparisonType,
-    pub negate: bool,
+pub async fn get_assertion_templates() -> Result<ApiResponse<Vec<AssertionTemplateInfo>>, AppError> {
+    ApiResponse::from(Ok(list_assertion_templates()))
+}
+
+pub async fn apply_assertion_template_to_actions(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<ApplyAssertionTemplatePayload>,
+) -> Result<ApiResponse<Vec<Assertion>>, AppError> {
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "assertion template apply").await;
+    let result = apply_assertion_template(&repository, ApplyAssertionTemplateCommand {
+        customer_id: "eren".to_string(),
+        test_case_id,
+        template: payload.template,
+        action_ids: payload.action_ids,
+        field: payload.field,
+        severity: payload.severity,
+    }).await;
+    ApiResponse::from(result)
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct PatchAssertionComparisonType
 {
     pub value: ComparisonType,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct PatchAssertionNegation
 {
     pub value: bool,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchAssertionSeverity
+{
+    pub value: Severity,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct PatchAssertionExpression
 {
     pub value: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct AssertionsPathParam {
     test_case_id: String,
     id: String,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchAssertionAnnotation {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct ApplyAssertionTemplatePayload {
+    pub template: AssertionTemplate,
+    /// Actions to bind the template to; every request action of the test case when empty.
+    #[serde(default)]
+    pub action_ids: Vec<String>,
+    /// Response field checked by `NonEmptyField`, like `id` or `data.id`.
+    pub field: Option<String>,
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+impl Validate for PutAssertionPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.comparison_type == ComparisonType::MatchesSnapshot {
+            return match &self.snapshot_action_id {
+                None => vec![FieldError::new("snapshot_action_id", "is required for snapshot comparisons")],
+                Some(action_id) => check_not_blank("snapshot_action_id", action_id).into_iter().collect(),
+            };
+        }
+        let tolerance_errors = match (&self.comparison_type, &self.tolerance) {
+            (ComparisonType::ApproximatelyEqualTo, None) => vec![FieldError::new("tolerance", "is required for approximate comparisons")],
+            (ComparisonType::ApproximatelyEqualTo, Some(tolerance)) => validate_tolerance(tolerance),
+            (_, Some(_)) => vec![FieldError::new("tolerance", "only applies to approximate comparisons")],
+            (_, None) => vec![],
+        };
+        let duration_errors = match (&self.comparison_type, self.duration_ms) {
+            (ComparisonType::WithinDuration, None) => vec![FieldError::new("duration_ms", "is required for WithinDuration")],
+            (comparison_type, Some(_)) if !comparison_type.compares_time() => vec![FieldError::new("duration_ms", "only applies to date comparisons")],
+            _ => vec![],
+        };
+        let unknown_comparison = match &self.comparison_type {
+            ComparisonType::Custom(name) if plugins().comparison(name).is_none() =>
+                Some(FieldError::new("comparison_type", format!("no plugin provides the comparison {}", name))),
+            _ => None,
+        };
+        let right_errors = if self.comparison_type.uses_right() {
+            validate_item("right", &self.right)
+        } else {
+            vec![]
+        };
+        validate_item("left", &self.left).into_iter()
+            .chain(right_errors)
+            .chain(unknown_comparison)
+            .chain(tolerance_errors)
+            .chain(duration_errors)
+            .collect()
+    }
+}
+
+fn validate_tolerance(tolerance: &Tolerance) -> Vec<FieldError> {
+    if tolerance.absolute.is_none() && tolerance.relative.is_none() {
+        return vec![FieldError::new("tolerance", "must set absolute or relative")];
+    }
+    [("tolerance.absolute", tolerance.absolute), ("tolerance.relative", tolerance.relative)].into_iter()
+        .filter(|(_, bound)| bound.is_some_and(|bound| !bound.is_finite() || bound < 0.0))
+        .map(|(field, _)| FieldError::new(field, "must be a non-negative number"))
+        .collect()
+}
+
+impl Validate for ApplyAssertionTemplatePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        match &self.field {
+            Some(field) => check_not_blank("field", field).into_iter()
+                .chain(check_expression("field", &format!("$.action.output.{}", field)))
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+impl Validate for PatchAssertionExpression {
+    fn validate(&self) -> Vec<FieldError> {
+        self.value.as_ref()
+            .and_then(|expression| check_expression("value", expression))
+            .into_iter()
+            .collect()
+    }
+}
+
+fn validate_item(side: &str, item: &AssertionItem) -> Vec<FieldError> {
+    if item.function.is_none() && item.value_provider.is_none() {
+        return vec![FieldError::new(side, "needs a value, an expression or a function")];
+    }
+    if let Some(Operation::Custom(name)) = item.function.as_ref().map(|function| &function.operation) {
+        if plugins().function(name).is_none() {
+            return vec![FieldError::new(format!("{}.function.operation", side), format!("no plugin provides the function {}", name))];
+        }
+    }
+    let from_provider = item.value_provider.iter()
+        .map(|provider| (format!("{}.value_provider.expression", side), provider));
+    let from_function = item.function.iter()
+        .flat_map(|function| function.parameters.iter().enumerate())
+        .map(|(i, provider)| (format!("{}.function.parameters[{}].expression", side, i), provider));
+    from_provider.chain(from_function)
+        .filter_map(|(field, provider)| {
+            let expression = provider.expression.as_ref()?;
+            check_expression(&field, &expression.value)
+        })
+        .collect()
+}
@@ -1,5 +1,7 @@
 use crate::assertion::model::{Assertion, AssertionItem, AssertionResult, ComparisonType, Function, Operation, ValueProvider};
+use crate::assertion::time::parse_instant;
 use crate::json_path::utils::evaluate_expression;
+use crate::plugin::registry::plugins;
 use serde_json::Value;
 
 trait ValueSupplier {
@@ -57,6 +59,12 @@ impl ValueSupplier for Function {
                 Operation::Count => {
                     Ok(vec![])
                 }
+                Operation::Custom(name) => {
+                    match plugins().function(name) {
+                        Some(function) => function.call(&value_list),
+                        None => Err(format!("no plugin provides the function {}", name)),
+                    }
+                }
             }
         }
     }
@@ -100,10 +108,18 @@ impl ValueSupplier for AssertionItem {
 }
 
 pub fn check_assertion(assertion: &Assertion, context: &Value) -> AssertionResult {
+    evaluate(assertion, context).with_severity(assertion.severity.clone())
+}
+
+fn evaluate(assertion: &Assertion, context: &Value) -> AssertionResult {
     let left_result = assertion.left.supply(context);
     match left_result {
         Ok(left_val) => {
-            let right_result = assertion.right.supply(context);
+            let right_result = if assertion.comparison_type.uses_right() {
+                assertion.right.supply(context)
+            } else {
+                Ok(vec![])
+            };
             match right_result {
                 Ok(right_val) => {
                     check(&assertion, left_val, right_val)
@@ -125,7 +141,7 @@ fn as_string(val: Vec<Value>) -> String {
 }
 
 fn check(assertion: &Assertion, left: Vec<Value>, right: Vec<Value>) -> AssertionResult {
-    match assertion.comparison_type {
+    match &assertion.comparison_type {
         ComparisonType::EqualTo => {
             let equals = left.eq(&right);
             if equals ^ assertion.negate {
@@ -168,6 +184,111 @@ fn check(assertion: &Assertion, left: Vec<Value>, right: Vec<Value>) -> Assertio
         ComparisonType::LessThanOrEqualTo => {
             check_greater_than(assertion, false, true, left, right)
         }
+        ComparisonType::MatchesSnapshot => {
+            AssertionResult::from_error(assertion.id.to_string(), "snapshot assertions are checked against a baseline".to_string())
+        }
+        ComparisonType::ApproximatelyEqualTo => {
+            check_approximately_equal(assertion, left, right)
+        }
+        ComparisonType::Before | ComparisonType::After | ComparisonType::WithinDuration => {
+            check_time(assertion, left, right)
+        }
+        ComparisonType::Exists | ComparisonType::NotExists | ComparisonType::IsNull => {
+            check_presence(assertion, left)
+        }
+        ComparisonType::HasLength => {
+            check_length(assertion, left, right)
+        }
+        ComparisonType::Custom(name) => {
+            check_custom(assertion, name, left, right)
+        }
+    }
+}
+
+fn check_approximately_equal(assertion: &Assertion, left: Vec<Value>, right: Vec<Value>) -> AssertionResult {
+    let tolerance = assertion.tolerance.unwrap_or_default();
+    match (left.as_slice(), right.as_slice()) {
+        ([left_item], [right_item]) => match (left_item.as_f64(), right_item.as_f64()) {
+            (Some(left_number), Some(right_number)) => {
+                if tolerance.allows(left_number, right_number) ^ assertion.negate {
+                    AssertionResult::of_success(assertion.id.to_string())
+                } else {
+                    AssertionResult::from_error(assertion.id.to_string(), format!("{} is{} within {} of {}",
+                                                                                  left_number, if assertion.negate { "" } else { " not" }, tolerance.describe(), right_number))
+                }
+            }
+            _ => AssertionResult::from_error(assertion.id.to_string(), format!("{} and {} cannot be compared as numbers", as_string(left), as_string(right))),
+        },
+        _ => AssertionResult::from_error(assertion.id.to_string(), "Lists cannot be compared as numbers!".to_string()),
+    }
+}
+
+fn check_time(assertion: &Assertion, left: Vec<Value>, right: Vec<Value>) -> AssertionResult {
+    let (left_item, right_item) = match (left.as_slice(), right.as_slice()) {
+        ([left_item], [right_item]) => (left_item, right_item),
+        _ => return AssertionResult::from_error(assertion.id.to_string(), "Lists cannot be compared as dates!".to_string()),
+    };
+    let (Some(left_ms), Some(right_ms)) = (parse_instant(left_item), parse_instant(right_item)) else {
+        return AssertionResult::from_error(assertion.id.to_string(), format!("{} and {} cannot be compared as dates", as_string(left), as_string(right)));
+    };
+    let gap = assertion.duration_ms.unwrap_or(0) as i64;
+    let (success, expectation) = match (&assertion.comparison_type, assertion.duration_ms) {
+        (ComparisonType::Before, None) => (left_ms < right_ms, format!("before {}", as_string(right))),
+        (ComparisonType::Before, Some(_)) => (left_ms < right_ms && right_ms - left_ms >= gap, format!("at least {} ms before {}", gap, as_string(right))),
+        (ComparisonType::After, None) => (left_ms > right_ms, format!("after {}", as_string(right))),
+        (ComparisonType::After, Some(_)) => (left_ms > right_ms && left_ms - right_ms >= gap, format!("at least {} ms after {}", gap, as_string(right))),
+        _ => ((left_ms - right_ms).abs() <= gap, format!("within {} ms of {}", gap, as_string(right))),
+    };
+    if success ^ assertion.negate {
+        AssertionResult::of_success(assertion.id.to_string())
+    } else {
+        AssertionResult::from_error(assertion.id.to_string(), format!("{} is{} {}",
+                                                                      as_string(left), if assertion.negate { "" } else { " not" }, expectation))
+    }
+}
+
+fn check_presence(assertion: &Assertion, left: Vec<Value>) -> AssertionResult {
+    let (success, expectation) = match assertion.comparison_type {
+        ComparisonType::Exists => (!left.is_empty(), "exist"),
+        ComparisonType::NotExists => (left.is_empty(), "be absent"),
+        _ => (!left.is_empty() && left.iter().all(Value::is_null), "be null"),
+    };
+    if success ^ assertion.negate {
+        AssertionResult::of_success(assertion.id.to_string())
+    } else {
+        let found = if left.is_empty() { "nothing".to_string() } else { as_string(left) };
+        AssertionResult::from_error(assertion.id.to_string(), format!("expected value to{} {}, but found: {}",
+                                                                      if assertion.negate { " not" } else { "" }, expectation, found))
+    }
+}
+
+fn check_length(assertion: &Assertion, left: Vec<Value>, right: Vec<Value>) -> AssertionResult {
+    let Some(expected) = right.first().and_then(Value::as_u64).filter(|_| right.len() == 1) else {
+        return AssertionResult::from_error(assertion.id.to_string(), format!("{} is not a length", as_string(right)));
+    };
+    let length = match left.as_slice() {
+        [Value::Array(items)] => items.len(),
+        [Value::String(text)] => text.chars().count(),
+        [Value::Object(fields)] => fields.len(),
+        values => values.len(),
+    } as u64;
+    if (length == expected) ^ assertion.negate {
+        AssertionResult::of_success(assertion.id.to_string())
+    } else {
+        AssertionResult::from_error(assertion.id.to_string(), format!("expected length{} {}, but got: {}",
+                                                                      if assertion.negate { " other than" } else { "" }, expected, length))
+    }
+}
+
+fn check_custom(assertion: &Assertion, name: &str, left: Vec<Value>, right: Vec<Value>) -> AssertionResult {
+    let Some(comparison) = plugins().comparison(name) else {
+        return AssertionResult::from_error(assertion.id.to_string(), format!("no plugin provides the comparison {}", name));
+    };
+    match comparison.compare(&left, &right) {
+        Ok(passed) if passed ^ assertion.negate => AssertionResult::of_success(assertion.id.to_string()),
+        Ok(_) => AssertionResult::from_error(assertion.id.to_string(), format!("{} and {} do{} pass {}",
+                                                                              as_string(left), as_string(right), if assertion.negate { "" } else { " not" }, name)),
+        Err(err) => AssertionResult::from_error(assertion.id.to_string(), format!("{} failed: {}", name, err)),
     }
 }
 
@@ -199,8 +320,12 @@ fn check_greater_than(assertion: &Assertion, greater: bool, or_equal: bool, left
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assertion::model::{Severity, Tolerance};
     use crate::json_path::model::Expression;
+    use crate::plugin::registry::{AssertionFunction, Comparison};
+    use crate::run::model::{compute_verdict, Verdict};
     use serde_json::json;
+    use std::sync::Arc;
 
     #[test]
     fn equality_check() {
@@ -337,4 +462,142 @@ mod tests {
         println!("{:?}", result.message);
         assert_eq!(result.success, false);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn only_critical_failures_fail_the_run() {
+        let assertion = |id: &str, severity: Severity| Assertion::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .id(id.to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.action1.output.count".to_string() }))
+            .right(AssertionItem::from_value(json!(5)))
+            .comparison_type(ComparisonType::LessThanOrEqualTo)
+            .severity(severity)
+            .build();
+        let context = json!({"action1": {"output": {"count": 17}}});
+        let warning = check_assertion(&assertion("a1", Severity::Warning), &context);
+        let info = check_assertion(&assertion("a2", Severity::Info), &context);
+        let critical = check_assertion(&assertion("a3", Severity::Critical), &context);
+
+        assert_eq!((warning.success, warning.severity.clone()), (false, Severity::Warning));
+        assert_eq!((info.success, info.severity.clone()), (false, Severity::Info));
+        assert_eq!(compute_verdict(&[warning.clone(), info.clone()], &[200]), Verdict::Passed);
+        assert_eq!(compute_verdict(&[warning, info, critical], &[200]), Verdict::Failed);
+    }
+
+    struct Max;
+
+    impl AssertionFunction for Max {
+        fn call(&self, arguments: &[Vec<Value>]) -> Result<Vec<Value>, String> {
+            let max = arguments.iter().flatten().filter_map(Value::as_i64).max();
+            Ok(max.map(Value::from).into_iter().collect())
+        }
+    }
+
+    struct SameLength;
+
+    impl Comparison for SameLength {
+        fn compare(&self, left: &[Value], right: &[Value]) -> Result<bool, String> {
+            Ok(left.len() == right.len())
+        }
+    }
+
+    #[test]
+    fn plugin_function_and_comparison() {
+        plugins().register_function("check_test_max", Arc::new(Max));
+        plugins().register_comparison("check_test_same_length", Arc::new(SameLength));
+        let max = Function {
+            operation: Operation::Custom("check_test_max".to_string()),
+            parameters: vec![ValueProvider { expression: Some(Expression { value: "$.list_0.output.items[*].price".to_string() }), value: None }],
+        };
+        let assertion = |left: AssertionItem, comparison_type: ComparisonType| Assertion::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .left(left)
+            .right(AssertionItem::from_value(json!(30)))
+            .comparison_type(comparison_type)
+            .build();
+        let context = json!({"list_0": {"output": {"items": [{"price": 10}, {"price": 30}, {"price": 20}]}}});
+
+        assert!(check_assertion(&assertion(AssertionItem::from_function(max), ComparisonType::EqualTo), &context).success);
+        let items = AssertionItem::from_expression(Expression { value: "$.list_0.output.items[*]".to_string() });
+        let result = check_assertion(&assertion(items, ComparisonType::Custom("check_test_same_length".to_string())), &context);
+        assert_eq!(result.message, Some("{\"price\":10},{\"price\":30},{\"price\":20} and 30 do not pass check_test_same_length".to_string()));
+        let unknown = check_assertion(&assertion(AssertionItem::from_value(json!(1)), ComparisonType::Custom("missing".to_string())), &context);
+        assert_eq!(unknown.message, Some("no plugin provides the comparison missing".to_string()));
+    }
+
+    #[test]
+    fn approximately_equal_within_tolerance() {
+        let assertion = |tolerance: Option<Tolerance>, negate: bool| Assertion::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.cart_0.output.total".to_string() }))
+            .right(AssertionItem::from_value(json!(30.3)))
+            .comparison_type(ComparisonType::ApproximatelyEqualTo)
+            .negate(negate)
+            .maybe_tolerance(tolerance)
+            .build();
+        let context = json!({"cart_0": {"output": {"total": 30.300000000000004}}});
+
+        assert!(check_assertion(&assertion(None, false), &context).success);
+        let absolute = Tolerance { absolute: Some(0.5), relative: None };
+        assert!(check_assertion(&assertion(Some(absolute), false), &json!({"cart_0": {"output": {"total": 30}}})).success);
+        let relative = Tolerance { absolute: None, relative: Some(0.01) };
+        let result = check_assertion(&assertion(Some(relative), false), &json!({"cart_0": {"output": {"total": 31}}}));
+        assert_eq!(result.message, Some("31 is not within 1% of 30.3".to_string()));
+        assert!(check_assertion(&assertion(Some(relative), true), &json!({"cart_0": {"output": {"total": 31}}})).success);
+        assert!(!check_assertion(&assertion(Some(absolute), false), &json!({"cart_0": {"output": {"total": "30"}}})).success);
+    }
+
+    #[test]
+    fn date_comparisons() {
+        let assertion = |comparison_type: ComparisonType, duration_ms: Option<u64>| Assertion::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.login_0.output.expiresAt".to_string() }))
+            .right(AssertionItem::from_expression(Expression { value: "$.login_0.output.createdAt".to_string() }))
+            .comparison_type(comparison_type)
+            .maybe_duration_ms(duration_ms)
+            .build();
+        let context = json!({"login_0": {"output": {"createdAt": "2024-03-01T12:30:00Z", "expiresAt": 1_709_299_800}}});
+
+        assert!(check_assertion(&assertion(ComparisonType::After, Some(3_600_000)), &context).success);
+        assert!(check_assertion(&assertion(ComparisonType::WithinDuration, Some(3_600_000)), &context).success);
+        let result = check_assertion(&assertion(ComparisonType::After, Some(3_600_001)), &context);
+        assert_eq!(result.message, Some("1709299800 is not at least 3600001 ms after 2024-03-01T12:30:00Z".to_string()));
+        let result = check_assertion(&assertion(ComparisonType::Before, None), &context);
+        assert_eq!(result.message, Some("1709299800 is not before 2024-03-01T12:30:00Z".to_string()));
+        let unparsable = json!({"login_0": {"output": {"createdAt": "soon", "expiresAt": 1_709_299_800}}});
+        assert!(!check_assertion(&assertion(ComparisonType::WithinDuration, Some(1)), &unparsable).success);
+    }
+
+    #[test]
+    fn presence_and_length_checks() {
+        let assertion = |expression: &str, comparison_type: ComparisonType, right: AssertionItem| Assertion::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .left(AssertionItem::from_expression(Expression { value: expression.to_string() }))
+            .right(right)
+            .comparison_type(comparison_type)
+            .build();
+        let context = json!({"get_0": {"output": {"id": 7, "deletedAt": null, "tags": ["a", "b"], "name": "abc"}}});
+        let check = |expression: &str, comparison_type: ComparisonType, right: AssertionItem| check_assertion(&assertion(expression, comparison_type, right), &context);
+
+        assert!(check("$.get_0.output.id", ComparisonType::Exists, AssertionItem::default()).success);
+        assert!(check("$.get_0.output.deletedAt", ComparisonType::Exists, AssertionItem::default()).success);
+        assert!(check("$.get_0.output.error", ComparisonType::NotExists, AssertionItem::default()).success);
+        assert!(check("$.get_0.output.deletedAt", ComparisonType::IsNull, AssertionItem::default()).success);
+        assert_eq!(check("$.get_0.output.id", ComparisonType::IsNull, AssertionItem::default()).message,
+                   Some("expected value to be null, but found: 7".to_string()));
+        assert_eq!(check("$.get_0.output.missing", ComparisonType::Exists, AssertionItem::default()).message,
+                   Some("expected value to exist, but found: nothing".to_string()));
+
+        assert!(check("$.get_0.output.tags", ComparisonType::HasLength, AssertionItem::from_value(json!(2))).success);
+        assert!(check("$.get_0.output.name", ComparisonType::HasLength, AssertionItem::from_value(json!(3))).success);
+        assert!(check("$.get_0.output.tags[*]", ComparisonType::HasLength, AssertionItem::from_value(json!(2))).success);
+        assert_eq!(check("$.get_0.output", ComparisonType::HasLength, AssertionItem::from_value(json!(3))).message,
+                   Some("expected length 3, but got: 4".to_string()));
+        assert!(!check("$.get_0.output.tags", ComparisonType::HasLength, AssertionItem::from_value(json!("two"))).success);
+    }
+}
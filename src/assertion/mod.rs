@@ -1,4 +1,6 @@
 pub mod model;
 pub mod service;
 pub mod api;
-pub mod check;
\ No newline at end of file
+pub mod check;
+pub mod template;
+pub mod time;
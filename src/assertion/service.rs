@@ -1,8 +1,9 @@
 use crate::api::AppError;
-use crate::assertion::model::{Assertion, ComparisonType, ValueProvider};
+use crate::assertion::model::{Assertion, ComparisonType, Severity, ValueProvider};
+use crate::change::model::{EntityChange, EntityType};
 use crate::json_path::model::Expression;
 use crate::persistence::model::{ListItemsRequest, QueryResult};
-use crate::persistence::repo::{build_composite_key, Table};
+use crate::persistence::repo::{build_composite_key, current_timestamp, Table};
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 use bon::Builder;
@@ -35,6 +36,14 @@ impl Table<Assertion> for AssertionsTable {
     fn sort_key_from_entity(entity: &Assertion) -> (String, AttributeValue) {
         Self::sort_key(entity.id.clone())
     }
+
+    fn versioned() -> bool {
+        true
+    }
+
+    fn change_of(entity: &Assertion) -> Option<EntityChange> {
+        Some(EntityChange::updated(&entity.customer_id, &entity.test_case_id, EntityType::Assertion, &entity.id, entity.updated_at))
+    }
 }
 
 impl AssertionOperations {
@@ -45,6 +54,20 @@ impl AssertionOperations {
             .await
     }
 
+    pub async fn count(&self, customer_id: &String, test_case_id: &String) -> Result<u64, AppError> {
+        AssertionsTable::count_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()])).await
+    }
+
+    pub async fn list_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Assertion>, AppError> {
+        AssertionsTable::list_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
+    pub async fn delete_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Assertion>, AppError> {
+        AssertionsTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
     pub async fn batch_create(&self, assertions: Vec<Assertion>) {
         AssertionsTable::batch_put_item(self.client.clone(), assertions).await
     }
@@ -58,25 +81,25 @@ impl AssertionOperations {
         AssertionsTable::put_item(self.client.clone(), assertion).await
     }
 
-    pub async fn update_comparison_type(&self, customer_id: String, test_case_id: String, id: String, comparison_type: ComparisonType) -> Result<Assertion, AppError> {
-        AssertionsTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id,
+    pub async fn update_comparison_type(&self, customer_id: String, test_case_id: String, id: String, comparison_type: ComparisonType, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id, expected_updated_at,
                                         self.client.clone().update_item()
                                             .expression_attribute_names("#comparison_type", "comparison_type")
                                             .expression_attribute_values(":value", to_attribute_value(comparison_type).unwrap())
                                             .update_expression("SET #comparison_type = :value")).await
     }
 
-    pub async fn update_comparison_negation(&self, customer_id: String, test_case_id: String, id: String, negate: bool) -> Result<Assertion, AppError> {
-        AssertionsTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id,
+    pub async fn update_comparison_negation(&self, customer_id: String, test_case_id: String, id: String, negate: bool, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id, expected_updated_at,
                                         self.client.clone().update_item()
                                             .expression_attribute_names("#negate", "negate")
                                             .expression_attribute_values(":value", to_attribute_value(negate).unwrap())
                                             .update_expression("SET #negate = :value")).await
     }
 
-    pub async fn update_expression(&self, customer_id: String, test_case_id: String, id: String, left: bool, expression: Option<String>) -> Result<Assertion, AppError> {
+    pub async fn update_expression(&self, customer_id: String, test_case_id: String, id: String, left: bool, expression: Option<String>, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
         let left_or_right = if left { "left" } else { "right" };
-        AssertionsTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id,
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id, expected_updated_at,
                                         self.client.clone().update_item()
                                             .update_expression(format!("SET {} = :newValue, #f = :func", format!("#{}.#value_provider.#expression.#value", left_or_right)))
                                             .expression_attribute_names(format!("#{}", left_or_right), left_or_right)
@@ -88,6 +111,38 @@ impl AssertionOperations {
                                             .expression_attribute_values(":newValue", to_attribute_value(expression).unwrap())).await
     }
 
+    pub async fn update_severity(&self, customer_id: String, test_case_id: String, id: String, severity: Severity, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                        self.client.clone().update_item()
+                                            .expression_attribute_names("#severity", "severity")
+                                            .expression_attribute_values(":value", to_attribute_value(severity).unwrap())
+                                            .update_expression("SET #severity = :value")).await
+    }
+
+    pub async fn update_notes(&self, customer_id: String, test_case_id: String, id: String, notes: Option<String>, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                        self.client.clone().update_item()
+                                            .update_expression("SET #notes = :value")
+                                            .expression_attribute_names("#notes", "notes")
+                                            .expression_attribute_values(":value", notes.map_or(AttributeValue::Null(true), AttributeValue::S))).await
+    }
+
+    pub async fn update_owner(&self, customer_id: String, test_case_id: String, id: String, owner: Option<String>, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                        self.client.clone().update_item()
+                                            .update_expression("SET #owner = :value")
+                                            .expression_attribute_names("#owner", "owner")
+                                            .expression_attribute_values(":value", owner.map_or(AttributeValue::Null(true), AttributeValue::S))).await
+    }
+
+    pub async fn mark_reviewed(&self, customer_id: String, test_case_id: String, id: String, expected_updated_at: Option<u64>) -> Result<Assertion, AppError> {
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]), id, expected_updated_at,
+                                        self.client.clone().update_item()
+                                            .update_expression("SET #last_reviewed_at = :value")
+                                            .expression_attribute_names("#last_reviewed_at", "last_reviewed_at")
+                                            .expression_attribute_values(":value", AttributeValue::N(current_timestamp().to_string()))).await
+    }
+
     pub async fn get(&self, customer_id: String, test_case_id: String, id: String) -> Result<Option<Assertion>, AppError> {
         AssertionsTable::get_item(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id)
             .await
@@ -105,7 +160,7 @@ impl AssertionOperations {
     pub async fn update_function_parameter(&self, request: UpdateFunctionParameterRequest) -> Result<Assertion, AppError> {
         let left_or_right = if request.left { "left" } else { "right" };
         let update_path = format!("#location.#f.#p[{}]", request.parameter_index);
-        AssertionsTable::update_partial(build_composite_key(vec![request.customer_id, request.test_case_id]), request.assertion_id,
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![request.customer_id, request.test_case_id]), request.assertion_id, request.expected_updated_at,
                                         self.client.clone().update_item()
                                             .update_expression(format!("SET {} = :newValue, #vp = :vp", update_path))
                                             .expression_attribute_names("#location", left_or_right)
@@ -119,7 +174,7 @@ impl AssertionOperations {
     pub async fn delete_function_parameter(&self, request: DeleteFunctionParameterRequest) -> Result<Assertion, AppError> {
         let left_or_right = if request.left { "left" } else { "right" };
         let update_path = format!("#location.#f.#p[{}]", request.parameter_index);
-        AssertionsTable::update_partial(build_composite_key(vec![request.customer_id, request.test_case_id]), request.assertion_id,
+        AssertionsTable::update_partial_expecting(build_composite_key(vec![request.customer_id, request.test_case_id]), request.assertion_id, request.expected_updated_at,
                                         self.client.clone().update_item()
                                             .update_expression(format!("REMOVE {}", update_path))
                                             .expression_attribute_names("#location", left_or_right)
@@ -137,6 +192,7 @@ pub struct UpdateFunctionParameterRequest {
     pub value_provider: ValueProvider,
     pub parameter_index: u8,
     pub left: bool,
+    pub expected_updated_at: Option<u64>,
 }
 
 #[derive(Builder)]
@@ -146,6 +202,7 @@ pub struct DeleteFunctionParameterRequest {
     pub assertion_id: String,
     pub parameter_index: u8,
     pub left: bool,
+    pub expected_updated_at: Option<u64>,
 }
 
 #[cfg(test)]
@@ -182,7 +239,7 @@ mod tests {
         info!("{:?}", get_result);
 
         let update_result = repository.assertions()
-            .update_expression("cust1".to_string(), "tc1".to_string(), "a1".to_string(), true, Some(String::from("$m.n"))).await;
+            .update_expression("cust1".to_string(), "tc1".to_string(), "a1".to_string(), true, Some(String::from("$m.n")), None).await;
 
         assert!(update_result.is_ok());
         assert_eq!(update_result.unwrap().left.value_provider.unwrap().expression.unwrap().value, String::from("$m.n"));
@@ -215,6 +272,7 @@ mod tests {
                 },
                 parameter_index: 0,
                 left: true,
+                expected_updated_at: None,
             }).await;
 
         assert!(update_result.is_ok());
@@ -248,10 +306,52 @@ mod tests {
                 assertion_id: "a3".to_string(),
                 parameter_index: 0,
                 left: true,
+                expected_updated_at: None,
             }).await;
 
         assert!(update_result.is_ok());
         assert_eq!(update_result.unwrap().left.function.unwrap().parameters.get(0).unwrap().clone(), ValueProvider { expression: Some(Expression { value: "$.1.2".to_string() }), value: None });
 
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn stale_version_is_a_conflict() {
+        init_logger();
+        let repository = Repository::new().await;
+        repository.assertions()
+            .batch_create(vec![Assertion::builder()
+                .customer_id("cust1".to_string())
+                .test_case_id("tc1".to_string())
+                .id("a4".to_string())
+                .left(AssertionItem::from_function(Function{ operation: Operation::Sum, parameters: vec![ValueProvider { expression: Some(Expression { value: "$.x.y".to_string() }), value: None }] }))
+                .right(AssertionItem::from_expression(Expression{ value: "$a.b.c".to_string() }))
+                .comparison_type(ComparisonType::EqualTo)
+                .build()]).await;
+        let version = repository.assertions()
+            .update_severity("cust1".to_string(), "tc1".to_string(), "a4".to_string(), Severity::Warning, None).await
+            .unwrap()
+            .updated_at;
+
+        let stale_result = repository.assertions()
+            .delete_function_parameter(DeleteFunctionParameterRequest {
+                customer_id: "cust1".to_string(),
+                test_case_id: "tc1".to_string(),
+                assertion_id: "a4".to_string(),
+                parameter_index: 0,
+                left: true,
+                expected_updated_at: version.map(|version| version - 1),
+            }).await;
+        assert!(matches!(stale_result, Err(AppError::Conflict(_))));
+
+        let update_result = repository.assertions()
+            .delete_function_parameter(DeleteFunctionParameterRequest {
+                customer_id: "cust1".to_string(),
+                test_case_id: "tc1".to_string(),
+                assertion_id: "a4".to_string(),
+                parameter_index: 0,
+                left: true,
+                expected_updated_at: version,
+            }).await;
+        assert!(update_result.is_ok());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::action::model::{Action, ActionType};
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, AssertionItem, ComparisonType, Severity, ValueProvider};
+use crate::json_path::model::Expression;
+use crate::persistence::repo::Repository;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+/// Field checked by `NonEmptyField` when none is given.
+pub const DEFAULT_TEMPLATE_FIELD: &str = "id";
+
+/// Named assertion shapes that are bound to one action at a time, checking paths under the
+/// action's entry in the run context.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, ToSchema)]
+pub enum AssertionTemplate {
+    /// The response status is in the 2xx range.
+    SuccessStatus,
+    /// The response has the field, and it is neither null nor an empty string.
+    NonEmptyField,
+}
+
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct AssertionTemplateInfo {
+    pub template: AssertionTemplate,
+    pub description: String,
+}
+
+pub struct ApplyAssertionTemplateCommand {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub template: AssertionTemplate,
+    pub action_ids: Vec<String>,
+    pub field: Option<String>,
+    pub severity: Severity,
+}
+
+impl AssertionTemplate {
+    pub const ALL: [AssertionTemplate; 2] = [AssertionTemplate::SuccessStatus, AssertionTemplate::NonEmptyField];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            AssertionTemplate::SuccessStatus => "status is 2xx",
+            AssertionTemplate::NonEmptyField => "response has a non-empty field, `id` unless another is given",
+        }
+    }
+
+    /// Concrete assertions of the template bound to `action`, with expressions like `$.login_0.status`.
+    pub fn instantiate(&self, action: &Action, field: &str, severity: &Severity) -> Vec<Assertion> {
+        let checks: Vec<(String, ComparisonType, AssertionItem, bool)> = match self {
+            AssertionTemplate::SuccessStatus => {
+                let status = format!("$.{}.status", action.name);
+                vec![
+                    (status.clone(), ComparisonType::GreaterThanOrEqualTo, AssertionItem::from_value(json!(200)), false),
+                    (status, ComparisonType::LessThan, AssertionItem::from_value(json!(300)), false),
+                ]
+            }
+            AssertionTemplate::NonEmptyField => {
+                let path = format!("$.{}.output.{}", action.name, field);
+                vec![
+                    (path.clone(), ComparisonType::EqualTo, no_values(), true),
+                    (path.clone(), ComparisonType::EqualTo, AssertionItem::from_value(Value::Null), true),
+                    (path, ComparisonType::EqualTo, AssertionItem::from_value(json!("")), true),
+                ]
+            }
+        };
+        checks.into_iter()
+            .map(|(path, comparison_type, right, negate)| Assertion::builder()
+                .customer_id(action.customer_id.clone())
+                .test_case_id(action.test_case_id.clone())
+                .left(AssertionItem::from_expression(Expression { value: path }))
+                .right(right)
+                .comparison_type(comparison_type)
+                .negate(negate)
+                .severity(severity.clone())
+                .build())
+            .collect()
+    }
+}
+
+pub fn list_assertion_templates() -> Vec<AssertionTemplateInfo> {
+    AssertionTemplate::ALL.iter()
+        .map(|template| AssertionTemplateInfo {
+            template: *template,
+            description: template.description().to_string(),
+        })
+        .collect()
+}
+
+/// Binds the template to every given action, or to every request action of the test case when
+/// none is given. Assertions the test case already has are not created again.
+pub async fn apply_assertion_template(repository: &Repository, command: ApplyAssertionTemplateCommand) -> Result<Vec<Assertion>, AppError> {
+    repository.test_cases()
+        .get(command.customer_id.clone(), command.test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let mut actions = repository.actions()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?;
+    actions.sort();
+    actions.retain(|action| action.action_type != ActionType::Sleep);
+    if !command.action_ids.is_empty() {
+        let known_ids: HashSet<&String> = actions.iter().map(|action| &action.id).collect();
+        if let Some(unknown_id) = command.action_ids.iter().find(|id| !known_ids.contains(id)) {
+            return Err(AppError::NotFound(format!("Action {} not found!", unknown_id)));
+        }
+        actions.retain(|action| command.action_ids.contains(&action.id));
+    }
+    let existing = repository.assertions()
+        .list_all(&command.customer_id, &command.test_case_id)
+        .await?;
+    let field = command.field.as_deref().unwrap_or(DEFAULT_TEMPLATE_FIELD);
+    let assertions: Vec<Assertion> = actions.iter()
+        .flat_map(|action| command.template.instantiate(action, field, &command.severity))
+        .filter(|assertion| !existing.iter().any(|other| is_same_check(assertion, other)))
+        .collect();
+    repository.assertions().batch_create(assertions.clone()).await;
+    Ok(assertions)
+}
+
+/// An item supplying no values, so a negated equality passes only when the left side matches something.
+fn no_values() -> AssertionItem {
+    AssertionItem::builder()
+        .value_provider(ValueProvider::builder().build())
+        .build()
+}
+
+fn is_same_check(assertion: &Assertion, other: &Assertion) -> bool {
+    assertion.left == other.left
+        && assertion.right == other.right
+        && assertion.comparison_type == other.comparison_type
+        && assertion.negate == other.negate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::check::check_assertion;
+
+    fn passes(assertions: &[Assertion], context: Value) -> bool {
+        assertions.iter().all(|assertion| check_assertion(assertion, &context).success)
+    }
+
+    #[test]
+    fn success_status_checks_the_range() {
+        let assertions = AssertionTemplate::SuccessStatus.instantiate(&test_action("orders_0", 0, "POST", "https://abc.xyz/orders"), DEFAULT_TEMPLATE_FIELD, &Severity::Warning);
+
+        assert_eq!(assertions.len(), 2);
+        assert!(assertions.iter().all(|assertion| assertion.severity == Severity::Warning && assertion.test_case_id == "tc1"));
+        assert!(passes(&assertions, json!({"orders_0": {"status": 201}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"status": 302}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"status": 0}})));
+    }
+
+    #[test]
+    fn non_empty_field_needs_a_value() {
+        let assertions = AssertionTemplate::NonEmptyField.instantiate(&test_action("orders_0", 0, "POST", "https://abc.xyz/orders"), "data.id", &Severity::Critical);
+
+        assert!(passes(&assertions, json!({"orders_0": {"output": {"data": {"id": 42}}}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"output": {"data": {"id": ""}}}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"output": {"data": {"id": null}}}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"output": {"data": {}}}})));
+        assert!(!passes(&assertions, json!({"orders_0": {"output": null}})));
+    }
+}
@@ -0,0 +1,54 @@
+use aws_sdk_dynamodb::primitives::{DateTime, DateTimeFormat};
+use serde_json::Value;
+
+/// Epoch numbers below this are taken as seconds, above as milliseconds; in seconds it is
+/// the year 5138, in milliseconds March 1973.
+const EPOCH_SECONDS_LIMIT: f64 = 1e11;
+
+/// Reads a point in time as epoch milliseconds from an RFC 3339 / ISO-8601 string like
+/// `2024-03-01T12:30:00+02:00`, a plain date like `2024-03-01` (midnight UTC), or an epoch number
+/// in seconds or milliseconds, given as a number or a numeric string.
+pub fn parse_instant(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number.as_f64().map(from_epoch),
+        Value::String(text) => {
+            let text = text.trim();
+            if let Ok(number) = text.parse::<f64>() {
+                return Some(from_epoch(number));
+            }
+            let text = if text.len() == 10 { format!("{}T00:00:00Z", text) } else { text.to_string() };
+            DateTime::from_str(&text, DateTimeFormat::DateTimeWithOffset)
+                .ok()
+                .and_then(|date_time| date_time.to_millis().ok())
+        }
+        _ => None,
+    }
+}
+
+fn from_epoch(number: f64) -> i64 {
+    if number.abs() < EPOCH_SECONDS_LIMIT {
+        (number * 1000.0) as i64
+    } else {
+        number as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_iso_and_epoch_values() {
+        let expected = Some(1_709_296_200_000);
+        assert_eq!(parse_instant(&json!("2024-03-01T12:30:00Z")), expected);
+        assert_eq!(parse_instant(&json!("2024-03-01T14:30:00+02:00")), expected);
+        assert_eq!(parse_instant(&json!("2024-03-01T12:30:00.000Z")), expected);
+        assert_eq!(parse_instant(&json!(1_709_296_200)), expected);
+        assert_eq!(parse_instant(&json!(1_709_296_200_000u64)), expected);
+        assert_eq!(parse_instant(&json!("1709296200")), expected);
+        assert_eq!(parse_instant(&json!("2024-03-01")), Some(1_709_251_200_000));
+        assert_eq!(parse_instant(&json!("yesterday")), None);
+        assert_eq!(parse_instant(&json!(true)), None);
+    }
+}
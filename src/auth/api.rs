@@ -1,23 +1,25 @@
-use crate::api::{ApiResponse, AppError};
-use crate::auth::model::{AuthHeaderValue, AuthenticationProvider, ListAuthProvidersRequest};
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::auth::model::{parse_window, AuthHeaderValue, AuthenticationProvider, ListAuthProvidersRequest, RequestSigning};
 use crate::auth::service::SetHeaderRequest;
 use crate::persistence::model::QueryResult;
-use crate::persistence::repo::Repository;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::validation::{AppJson, ValidJson, Validate, check_not_blank};
 use axum::extract::{Path, Query, State};
-use axum::Json;
 use serde::Deserialize;
 use std::collections::HashSet;
+use utoipa::{IntoParams, ToSchema};
 
 pub async fn set_auth_header_value(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<SetHeaderPayload>,
+    AppJson(payload): AppJson<SetHeaderPayload>,
 ) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
     let result = repository.auth_providers().set_header(SetHeaderRequest {
         customer_id: "eren".to_string(),
         id,
         name: payload.name,
         value: payload.value,
+        expires_at: None,
     }).await;
     ApiResponse::from(result)
 }
@@ -25,21 +27,43 @@ pub async fn set_auth_header_value(
 pub async fn add_auth_header_value(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<SetHeaderPayload>,
+    AppJson(payload): AppJson<SetHeaderPayload>,
 ) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
     let result = repository.auth_providers().add_header(SetHeaderRequest {
         customer_id: "eren".to_string(),
         id,
         name: payload.name,
         value: payload.value,
+        expires_at: payload.expires_at,
     }).await;
     ApiResponse::from(result)
 }
 
+pub async fn set_auth_header_expiry(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<SetHeaderExpiryPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().set_header_expiry("eren".to_string(),
+                                                               id,
+                                                               payload.name,
+                                                               payload.expires_at).await;
+    ApiResponse::from(result)
+}
+
+pub async fn set_auth_provider_signing(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<SetSigningPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().set_signing("eren".to_string(), id, payload.value).await;
+    ApiResponse::from(result)
+}
+
 pub async fn set_auth_header_enablement(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<SetHeaderEnablementPayload>,
+    AppJson(payload): AppJson<SetHeaderEnablementPayload>,
 ) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
     let result = repository.auth_providers().set_header_enablement("eren".to_string(),
                                                                    id,
@@ -47,6 +71,92 @@ pub async fn set_auth_header_enablement(
                                                                    payload.disabled).await;
     ApiResponse::from(result)
 }
+
+/// Adds the cookie sent in the provider's `Cookie` header, or replaces the one with the same name.
+pub async fn add_auth_cookie(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<SetCookiePayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().add_cookie(SetHeaderRequest {
+        customer_id: "eren".to_string(),
+        id,
+        name: payload.name,
+        value: payload.value,
+        expires_at: payload.expires_at,
+    }).await;
+    ApiResponse::from(result)
+}
+
+pub async fn set_auth_cookie_enablement(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<SetHeaderEnablementPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().set_cookie_enablement("eren".to_string(),
+                                                                   id,
+                                                                   payload.name,
+                                                                   payload.disabled).await;
+    ApiResponse::from(result)
+}
+
+pub async fn set_auth_cookie_expiry(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<SetHeaderExpiryPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().set_cookie_expiry("eren".to_string(),
+                                                               id,
+                                                               payload.name,
+                                                               payload.expires_at).await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_auth_cookie(
+    Path((id, name)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    let result = repository.auth_providers().remove_cookie("eren".to_string(), id, name).await;
+    ApiResponse::from(result)
+}
+
+/// Copies the provider, e.g. to hold the staging token of a base URL as a variant bound to the
+/// staging environment.
+pub async fn clone_auth_provider(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<CloneAuthProviderPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    if let Some(environment_id) = &payload.environment_id {
+        check_environment(&repository, environment_id).await?;
+    }
+    let result = repository.auth_providers()
+        .clone_provider(&"eren".to_string(), id, payload.name, payload.environment_id)
+        .await;
+    ApiResponse::from(result)
+}
+
+/// Limits the provider to runs in the environment, or lifts the limit with `null`.
+pub async fn set_auth_provider_environment(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<SetEnvironmentPayload>,
+) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
+    if let Some(environment_id) = &payload.value {
+        check_environment(&repository, environment_id).await?;
+    }
+    let result = repository.auth_providers().set_environment("eren".to_string(), id, payload.value).await;
+    ApiResponse::from(result)
+}
+
+async fn check_environment(repository: &Repository, environment_id: &String) -> Result<(), AppError> {
+    repository.environments()
+        .get(&"eren".to_string(), environment_id)
+        .await?
+        .map(|_| ())
+        .ok_or(AppError::NotFound("Environment not found!".to_string()))
+}
+
 pub async fn delete_auth_provider(
     Path(id): Path<String>,
     State(repository): State<Repository>,
@@ -71,7 +181,7 @@ pub async fn get_auth_provider(
 
 pub async fn create_auth_provider(
     State(repository): State<Repository>,
-    Json(payload): Json<CreateAuthProviderPayload>,
+    ValidJson(payload): ValidJson<CreateAuthProviderPayload>,
 ) -> Result<ApiResponse<AuthenticationProvider>, AppError> {
     let provider = AuthenticationProvider::builder()
         .name(payload.name)
@@ -80,9 +190,11 @@ pub async fn create_auth_provider(
         .headers_by_name(payload.headers.iter()
             .map(|h| (h.name.clone(), AuthHeaderValue::builder()
                 .value(h.value.clone())
+                .maybe_expires_at(h.expires_at)
                 .build()))
             .collect())
         .linked_test_case_ids(HashSet::new())
+        .maybe_signing(payload.signing)
         .build();
     let result = repository
         .auth_providers()
@@ -95,7 +207,11 @@ pub async fn list_auth_providers(
     params: Query<AuthProvidersQueryParams>,
     State(repository): State<Repository>,
 ) -> Result<ApiResponse<QueryResult<AuthenticationProvider>>, AppError> {
-    let result = repository
+    let expiring_within = params.expiring_within.as_ref()
+        .map(|window| parse_window(window)
+            .ok_or(AppError::Validation(format!("Invalid expiring_within: {}, expected e.g. 48h", window))))
+        .transpose()?;
+    let mut result = repository
         .auth_providers()
         .list(ListAuthProvidersRequest::builder()
             .customer_id("eren".to_string())
@@ -104,44 +220,116 @@ pub async fn list_auth_providers(
             .maybe_keyword(params.keyword.clone())
             .build())
         .await;
+    if let (Ok(query_result), Some(window)) = (result.as_mut(), expiring_within) {
+        let now = current_timestamp();
+        query_result.items.retain(|provider| provider.expires_within(now, window));
+    }
     ApiResponse::from(result)
 }
 
-pub async fn list_auth_providers_with_multiple_urls(State(repository): State<Repository>, Json(payload): Json<SearchByMultiBaseUrlPayload>) -> Result<ApiResponse<Vec<AuthenticationProvider>>, AppError> {
+pub async fn list_auth_providers_with_multiple_urls(State(repository): State<Repository>, AppJson(payload): AppJson<SearchByMultiBaseUrlPayload>) -> Result<ApiResponse<Vec<AuthenticationProvider>>, AppError> {
     let result = repository.auth_providers()
         .list_by_multi_base_url(&"eren".to_string(), payload.urls).await;
     ApiResponse::from(result)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
 pub struct AuthProvidersQueryParams {
     test_case_id: Option<String>,
     base_url: Option<String>,
     next_page_key: Option<String>,
     keyword: Option<String>,
+    expiring_within: Option<String>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct SetHeaderPayload {
     pub name: String,
-    pub value: String
+    pub value: String,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+}
+
+/// Only the name and value, without attributes like `Path`, which a request does not send.
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct SetCookiePayload {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct SetHeaderExpiryPayload {
+    pub name: String,
+    pub expires_at: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct SetHeaderEnablementPayload {
     pub name: String,
     pub disabled: bool
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct CreateAuthProviderPayload {
     pub name: String,
     pub url: String,
+    #[serde(default)]
     pub headers: Vec<SetHeaderPayload>,
+    #[serde(default)]
+    pub signing: Option<RequestSigning>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct SetSigningPayload {
+    pub value: Option<RequestSigning>,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CloneAuthProviderPayload {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub environment_id: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct SetEnvironmentPayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct SearchByMultiBaseUrlPayload {
     pub urls: Vec<String>
 }
 
+impl Validate for CreateAuthProviderPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter()
+            .chain(check_not_blank("url", &self.url))
+            .collect()
+    }
+}
+
+impl Validate for SetCookiePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = check_not_blank("name", &self.name).into_iter().collect();
+        if self.name.contains(|c: char| c == '=' || c == ';' || c.is_whitespace() || c.is_control()) {
+            errors.push(FieldError::new("name", "must not contain '=', ';', whitespace or control characters"));
+        }
+        if self.value.contains(|c: char| c == ';' || c.is_control()) {
+            errors.push(FieldError::new("value", "must not contain ';' or control characters"));
+        }
+        errors
+    }
+}
+
+impl Validate for CloneAuthProviderPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        self.name.as_ref()
+            .and_then(|name| check_not_blank("name", name))
+            .into_iter()
+            .collect()
+    }
+}
@@ -1,8 +1,9 @@
 use bon::Builder;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Clone, Builder)]
+#[derive(Serialize, Deserialize, Clone, Builder, ToSchema)]
 pub struct AuthenticationProvider {
     pub customer_id: String,
     #[builder(default = uuid::Uuid::new_v4().to_string())]
@@ -10,17 +11,106 @@ pub struct AuthenticationProvider {
     pub name: String,
     pub base_url: String,
     pub headers_by_name: HashMap<String, AuthHeaderValue>,
+    /// Sent together in one `Cookie` header, so they are kept apart from the headers.
+    #[serde(default)]
+    #[builder(default)]
+    pub cookies_by_name: HashMap<String, AuthHeaderValue>,
     #[serde(skip_serializing_if = "HashSet::is_empty", default = "HashSet::new")]
     pub linked_test_case_ids: HashSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<RequestSigning>,
+    /// Environment the provider is limited to, e.g. a staging token for a base URL that runs in
+    /// other environments reach with other credentials.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_id: Option<String>,
+    /// Provider this one replaces in runs of its environment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant_of: Option<String>,
     pub created_at: Option<u64>,
     pub updated_at: Option<u64>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Builder)]
+impl AuthenticationProvider {
+    /// Whether any enabled header or cookie has expired or expires before `now + window_millis`.
+    pub fn expires_within(&self, now: u64, window_millis: u64) -> bool {
+        self.headers_by_name.values()
+            .chain(self.cookies_by_name.values())
+            .filter(|header| !header.disabled)
+            .filter_map(|header| header.expires_at)
+            .any(|expires_at| expires_at <= now.saturating_add(window_millis))
+    }
+
+    /// Value of the `Cookie` header carrying the enabled cookies, like `session=abc; csrf=xyz`,
+    /// or `None` without any.
+    pub fn cookie_header(&self) -> Option<String> {
+        let mut cookies: Vec<String> = self.cookies_by_name.iter()
+            .filter(|(_, cookie)| !cookie.disabled)
+            .map(|(name, cookie)| format!("{}={}", name, cookie.value))
+            .collect();
+        cookies.sort();
+        Some(cookies.join("; ")).filter(|header| !header.is_empty())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, Builder, ToSchema)]
 pub struct AuthHeaderValue {
     pub value: String,
     #[builder(default = false)]
     pub disabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// Signs every outbound request at send time, for APIs where a recorded signature can never be replayed.
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq, Debug, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestSigning {
+    SigV4 {
+        access_key_id: String,
+        secret_access_key: String,
+        session_token: Option<String>,
+        region: String,
+        service: String,
+    },
+    /// Hex encoded HMAC-SHA256 of `METHOD\npath?query\nbody`, sent in `header`.
+    Hmac {
+        secret: String,
+        #[serde(default = "default_signature_header")]
+        header: String,
+    },
+}
+
+fn default_signature_header() -> String {
+    "X-Signature".to_string()
+}
+
+/// Narrows the providers found for an action to what applies in the run's environment: providers
+/// bound to another environment are left out, and a variant bound to the run's environment
+/// replaces the provider it is a variant of.
+pub fn for_environment(providers: Vec<AuthenticationProvider>, environment_id: Option<&str>) -> Vec<AuthenticationProvider> {
+    let applies = |provider: &AuthenticationProvider| provider.environment_id.is_none() || provider.environment_id.as_deref() == environment_id;
+    let replaced: HashSet<String> = providers.iter()
+        .filter(|provider| provider.environment_id.is_some() && applies(provider))
+        .filter_map(|provider| provider.variant_of.clone())
+        .collect();
+    providers.into_iter()
+        .filter(|provider| applies(provider) && !replaced.contains(&provider.id))
+        .collect()
+}
+
+/// Parses windows like `90s`, `30m`, `48h` or `7d` into milliseconds.
+pub fn parse_window(window: &str) -> Option<u64> {
+    let window = window.trim();
+    let split = window.len().checked_sub(1)?;
+    let (amount, unit) = window.split_at(split);
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.parse::<u64>().ok()?.checked_mul(seconds * 1000)
 }
 
 #[derive(Builder)]
@@ -30,4 +120,73 @@ pub struct ListAuthProvidersRequest {
     pub base_url: Option<String>,
     pub next_page_key: Option<String>,
     pub keyword: Option<String>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_expiring_within_window() {
+        let header = |expires_at: Option<u64>, disabled: bool| AuthHeaderValue::builder()
+            .value("secret".to_string())
+            .disabled(disabled)
+            .maybe_expires_at(expires_at)
+            .build();
+        let provider = |headers: Vec<AuthHeaderValue>| AuthenticationProvider::builder()
+            .customer_id("cust1".to_string())
+            .name("api".to_string())
+            .base_url("https://abc.xyz".to_string())
+            .headers_by_name(headers.into_iter().enumerate().map(|(i, h)| (i.to_string(), h)).collect())
+            .linked_test_case_ids(HashSet::new())
+            .build();
+
+        assert!(provider(vec![header(Some(500), false)]).expires_within(1000, 0));
+        assert!(provider(vec![header(None, false), header(Some(1500), false)]).expires_within(1000, 600));
+        assert!(!provider(vec![header(Some(1500), false)]).expires_within(1000, 400));
+        assert!(!provider(vec![header(Some(500), true), header(None, false)]).expires_within(1000, 0));
+
+        let with_cookies = AuthenticationProvider {
+            cookies_by_name: HashMap::from([
+                ("session".to_string(), header(Some(500), false)),
+                ("csrf".to_string(), header(None, false)),
+                ("tracking".to_string(), header(None, true)),
+            ]),
+            ..provider(vec![])
+        };
+        assert!(with_cookies.expires_within(1000, 0));
+        assert_eq!(with_cookies.cookie_header(), Some("csrf=secret; session=secret".to_string()));
+        assert_eq!(provider(vec![]).cookie_header(), None);
+
+        assert_eq!(parse_window("48h"), Some(48 * 60 * 60 * 1000));
+        assert_eq!(parse_window("7d"), Some(7 * 24 * 60 * 60 * 1000));
+        assert_eq!(parse_window("48"), None);
+        assert_eq!(parse_window(""), None);
+    }
+
+    #[test]
+    fn variants_replace_their_provider_in_their_environment() {
+        let provider = AuthenticationProvider::builder()
+            .customer_id("cust1".to_string())
+            .name("api".to_string())
+            .base_url("https://abc.xyz".to_string())
+            .headers_by_name(HashMap::new())
+            .linked_test_case_ids(HashSet::new())
+            .build();
+        let base = AuthenticationProvider { id: "base".to_string(), ..provider.clone() };
+        let staging = AuthenticationProvider {
+            id: "staging".to_string(),
+            environment_id: Some("env-staging".to_string()),
+            variant_of: Some("base".to_string()),
+            ..provider.clone()
+        };
+        let prod_only = AuthenticationProvider { id: "prod".to_string(), environment_id: Some("env-prod".to_string()), ..provider.clone() };
+        let ids = |environment_id: Option<&str>| for_environment(vec![base.clone(), staging.clone(), prod_only.clone()], environment_id)
+            .into_iter()
+            .map(|provider| provider.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids(Some("env-staging")), vec!["staging"]);
+        assert_eq!(ids(Some("env-prod")), vec!["base", "prod"]);
+        assert_eq!(ids(None), vec!["base"]);
+        assert_eq!(ids(Some("env-qa")), vec!["base"]);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::api::AppError;
-use crate::auth::model::{AuthHeaderValue, AuthenticationProvider, ListAuthProvidersRequest};
+use crate::auth::model::{AuthHeaderValue, AuthenticationProvider, ListAuthProvidersRequest, RequestSigning};
 use crate::persistence::model::QueryResult;
-use crate::persistence::repo::Table;
+use crate::persistence::repo::{current_timestamp, Table};
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 use serde_dynamo::to_attribute_value;
@@ -54,11 +54,16 @@ impl Table<AuthenticationProvider> for AuthenticationProviderTable {
     }
 }
 
+const HEADERS_ATTRIBUTE: &str = "headers_by_name";
+const COOKIES_ATTRIBUTE: &str = "cookies_by_name";
+
+/// Sets a header, or a cookie with the cookie operations.
 pub struct SetHeaderRequest {
     pub customer_id: String,
     pub id: String,
     pub name: String,
     pub value: String,
+    pub expires_at: Option<u64>,
 }
 
 impl AuthProviderOperations {
@@ -71,43 +76,179 @@ impl AuthProviderOperations {
         &self,
         request: SetHeaderRequest,
     ) -> Result<AuthenticationProvider, AppError> {
-        AuthenticationProviderTable::update_partial(request.customer_id, request.id,
+        self.set_credential_field(request.customer_id, request.id, HEADERS_ATTRIBUTE, request.name, "value", AttributeValue::S(request.value)).await
+    }
+
+    pub async fn add_header(
+        &self,
+        request: SetHeaderRequest,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.put_credential(HEADERS_ATTRIBUTE, request).await
+    }
+
+    pub async fn set_header_enablement(
+        &self,
+        customer_id: String,
+        id: String,
+        name: String,
+        disabled: bool,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.set_credential_field(customer_id, id, HEADERS_ATTRIBUTE, name, "disabled", AttributeValue::Bool(disabled)).await
+    }
+
+    pub async fn set_header_expiry(
+        &self,
+        customer_id: String,
+        id: String,
+        name: String,
+        expires_at: Option<u64>,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.set_credential_field(customer_id, id, HEADERS_ATTRIBUTE, name, "expires_at", expiry_value(expires_at)).await
+    }
+
+    /// Adds the cookie or replaces the one with the same name. Providers stored before cookies
+    /// were kept apart get their cookie map first.
+    pub async fn add_cookie(
+        &self,
+        request: SetHeaderRequest,
+    ) -> Result<AuthenticationProvider, AppError> {
+        AuthenticationProviderTable::update_partial(request.customer_id.clone(), request.id.clone(),
                                                     self.client.clone()
                                                         .update_item()
-                                                        .update_expression("SET headers_by_name.#key.#value = :newValue")
-                                                        .expression_attribute_names("#key", request.name)
-                                                        .expression_attribute_names("#value", "value")
-                                                        .expression_attribute_values(":newValue", AttributeValue::S(request.value))).await
+                                                        .update_expression("SET #credentials = if_not_exists(#credentials, :empty)")
+                                                        .expression_attribute_names("#credentials", COOKIES_ATTRIBUTE)
+                                                        .expression_attribute_values(":empty", AttributeValue::M(HashMap::new()))).await?;
+        self.put_credential(COOKIES_ATTRIBUTE, request).await
     }
 
-    pub async fn add_header(
+    pub async fn set_cookie(
         &self,
         request: SetHeaderRequest,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.set_credential_field(request.customer_id, request.id, COOKIES_ATTRIBUTE, request.name, "value", AttributeValue::S(request.value)).await
+    }
+
+    pub async fn set_cookie_enablement(
+        &self,
+        customer_id: String,
+        id: String,
+        name: String,
+        disabled: bool,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.set_credential_field(customer_id, id, COOKIES_ATTRIBUTE, name, "disabled", AttributeValue::Bool(disabled)).await
+    }
+
+    pub async fn set_cookie_expiry(
+        &self,
+        customer_id: String,
+        id: String,
+        name: String,
+        expires_at: Option<u64>,
+    ) -> Result<AuthenticationProvider, AppError> {
+        self.set_credential_field(customer_id, id, COOKIES_ATTRIBUTE, name, "expires_at", expiry_value(expires_at)).await
+    }
+
+    pub async fn remove_cookie(
+        &self,
+        customer_id: String,
+        id: String,
+        name: String,
+    ) -> Result<AuthenticationProvider, AppError> {
+        AuthenticationProviderTable::update_partial(customer_id, id,
+                                                    self.client.clone()
+                                                        .update_item()
+                                                        .update_expression("REMOVE #credentials.#key")
+                                                        .expression_attribute_names("#credentials", COOKIES_ATTRIBUTE)
+                                                        .expression_attribute_names("#key", name)).await
+    }
+
+    async fn put_credential(
+        &self,
+        attribute: &str,
+        request: SetHeaderRequest,
     ) -> Result<AuthenticationProvider, AppError> {
         AuthenticationProviderTable::update_partial(request.customer_id, request.id,
                                                     self.client.clone()
                                                         .update_item()
-                                                        .update_expression("SET headers_by_name.#key = :newValue")
+                                                        .update_expression("SET #credentials.#key = :newValue")
+                                                        .expression_attribute_names("#credentials", attribute)
                                                         .expression_attribute_names("#key", request.name)
                                                         .expression_attribute_values(":newValue", to_attribute_value(AuthHeaderValue::builder()
                                                             .value(request.value)
+                                                            .maybe_expires_at(request.expires_at)
                                                             .build()).unwrap())).await
     }
 
-    pub async fn set_header_enablement(
+    async fn set_credential_field(
         &self,
         customer_id: String,
         id: String,
+        attribute: &str,
         name: String,
-        disabled: bool,
+        field: &str,
+        value: AttributeValue,
     ) -> Result<AuthenticationProvider, AppError> {
         AuthenticationProviderTable::update_partial(customer_id, id,
                                                     self.client.clone()
                                                         .update_item()
-                                                        .update_expression("SET headers_by_name.#key.#disabled = :newValue")
+                                                        .update_expression("SET #credentials.#key.#field = :newValue")
+                                                        .expression_attribute_names("#credentials", attribute)
                                                         .expression_attribute_names("#key", name)
-                                                        .expression_attribute_names("#disabled", "disabled")
-                                                        .expression_attribute_values(":newValue", AttributeValue::Bool(disabled))).await
+                                                        .expression_attribute_names("#field", field)
+                                                        .expression_attribute_values(":newValue", value)).await
+    }
+
+    pub async fn set_signing(
+        &self,
+        customer_id: String,
+        id: String,
+        signing: Option<RequestSigning>,
+    ) -> Result<AuthenticationProvider, AppError> {
+        AuthenticationProviderTable::update_partial(customer_id, id,
+                                                    self.client.clone()
+                                                        .update_item()
+                                                        .update_expression("SET #signing = :newValue")
+                                                        .expression_attribute_names("#signing", "signing")
+                                                        .expression_attribute_values(":newValue", to_attribute_value(signing).unwrap())).await
+    }
+
+    pub async fn set_environment(
+        &self,
+        customer_id: String,
+        id: String,
+        environment_id: Option<String>,
+    ) -> Result<AuthenticationProvider, AppError> {
+        AuthenticationProviderTable::update_partial(customer_id, id,
+                                                    self.client.clone()
+                                                        .update_item()
+                                                        .update_expression("SET #environment_id = :newValue")
+                                                        .expression_attribute_names("#environment_id", "environment_id")
+                                                        .expression_attribute_values(":newValue", to_attribute_value(environment_id).unwrap())).await
+    }
+
+    /// Copies the provider under a new id, linked to the same test cases. Bound to an environment
+    /// the copy becomes a variant of the provider, or of what the provider is itself a variant of.
+    pub async fn clone_provider(
+        &self,
+        customer_id: &String,
+        id: String,
+        name: Option<String>,
+        environment_id: Option<String>,
+    ) -> Result<AuthenticationProvider, AppError> {
+        let source = self.get(customer_id, id)
+            .await?
+            .ok_or(AppError::NotFound("Auth provider not found!".to_string()))?;
+        let variant_of = environment_id.as_ref().map(|_| source.variant_of.clone().unwrap_or(source.id.clone()));
+        let copy = AuthenticationProvider {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.unwrap_or(format!("{} (copy)", source.name)),
+            environment_id,
+            variant_of,
+            created_at: Some(current_timestamp()),
+            updated_at: None,
+            ..source
+        };
+        self.create(copy).await
     }
 
     pub async fn unlink_test_case(&self, customer_id: &String, test_case_id: &String) {
@@ -160,6 +301,10 @@ impl AuthProviderOperations {
         AuthenticationProviderTable::from_query_result(result)
     }
 
+    pub async fn list_all(&self, customer_id: &str) -> Result<Vec<AuthenticationProvider>, AppError> {
+        AuthenticationProviderTable::list_all_items(self.client.clone(), customer_id.to_string()).await
+    }
+
     pub async fn batch_get(
         &self,
         customer_id: &String,
@@ -239,6 +384,10 @@ impl AuthProviderOperations {
     }
 }
 
+fn expiry_value(expires_at: Option<u64>) -> AttributeValue {
+    expires_at.map_or(AttributeValue::Null(true), |expires_at| AttributeValue::N(expires_at.to_string()))
+}
+
 async fn list_by_url(client: Arc<Client>, customer_id: String, url: String) -> Result<QueryResult<AuthenticationProvider>, AppError> {
     let result = AuthenticationProviderTable::query_builder(client)
         .expression_attribute_names("#pk", AuthenticationProviderTable::partition_key_name())
@@ -280,6 +429,7 @@ mod tests {
                 id: "auth1".to_string(),
                 name: "newHeader".to_string(),
                 value: "newVal".to_string(),
+                expires_at: None,
             }).await.unwrap();
 
         let link_result = repository.auth_providers()
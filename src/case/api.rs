@@ -1,15 +1,22 @@
-use crate::api::{ApiResponse, AppError};
-use crate::case::model::TestCase;
-use crate::har_resolver::{build_test_case, filter_entries};
+use crate::action::model::Action;
+use crate::api::{ApiResponse, AppError, AppState, FieldError};
+use crate::case::model::{ConcurrencyPolicy, ContextRetention, SetupCase, TestCase};
+use crate::deletion::cascade;
+use crate::deletion::model::DeletionJob;
+use crate::import_report::model::{HarDiff, RefreshReport};
+use crate::har_resolver::{append_to_test_case, build_test_case, filter_import_entries, diff_test_case, refresh_test_case, HarImportOptions};
 use crate::persistence::model::QueryResult;
 use crate::persistence::repo::Repository;
+use crate::revision::history::{record_before_change, snapshot_before_change};
+use crate::run::setup::validate_setup;
+use crate::validation::{AppJson, ValidJson, Validate, check_base_url, check_not_blank};
+use crate::change::version::IfMatch;
 use axum::extract::{Multipart, Path, Query, State};
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
-use axum::Json;
 use har::{Error, Har};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::io::{Cursor, ErrorKind};
+use utoipa::{IntoParams, ToSchema};
 
 pub async fn get_test_case(
     Path(id): Path<String>,
@@ -23,18 +30,20 @@ pub async fn list_test_cases(
     State(repository): State<Repository>,
     Query(params): Query<ListTestCaseParams>,
 ) -> Result<ApiResponse<QueryResult<TestCase>>, AppError> {
-    let result = repository.test_cases().list("eren".to_string(), params.next_page_key, params.keyword).await;
+    let result = repository.test_cases()
+        .list("eren".to_string(), params.next_page_key, params.keyword, params.archived.unwrap_or(false)).await;
     ApiResponse::from(result)
 }
 
 pub async fn upload_test_case(
     State(repository): State<Repository>,
     mut multipart: Multipart,
-) -> impl IntoResponse {
+) -> Result<ApiResponse<TestCase>, AppError> {
     let mut provided_har: Option<Har> = None;
     let mut provided_name: String = "".to_string();
     let mut provided_description: String = "".to_string();
     let mut provided_excluded_path_parts: Vec<String> = vec![];
+    let mut provided_collapse_polling = false;
     let mut provided_auth_providers: Vec<String> = vec![];
     while let Some(mut field) = multipart.next_field().await.unwrap() {
         let name = field.name().unwrap().to_string();
@@ -51,6 +60,9 @@ pub async fn upload_test_case(
                     .map(|s| s.to_string().trim().to_string())
                     .collect();
             }
+            "collapse_polling" => {
+                provided_collapse_polling = field.text().await.unwrap().trim().eq_ignore_ascii_case("true");
+            }
             "excluded_paths" => {
                 provided_excluded_path_parts = field
                     .text()
@@ -68,21 +80,172 @@ pub async fn upload_test_case(
         }
     }
 
-    match provided_har {
-        Some(har) => {
-            build_test_case(
-                &repository,
-                &har.log,
-                &"eren".to_string(),
-                &provided_name,
-                &provided_description,
-                provided_excluded_path_parts.clone(),
-                provided_auth_providers.clone(),
-            )
-                .await;
+    let mut errors: Vec<FieldError> = check_not_blank("name", &provided_name).into_iter().collect();
+    let Some(har) = provided_har else {
+        errors.push(FieldError::new("file", "a HAR file is required"));
+        return Err(AppError::InvalidFields(errors));
+    };
+    if !errors.is_empty() {
+        return Err(AppError::InvalidFields(errors));
+    }
+    let test_case = build_test_case(
+        &repository,
+        &har.log,
+        &"eren".to_string(),
+        &provided_name,
+        &provided_description,
+        HarImportOptions {
+            excluded_path_parts: provided_excluded_path_parts,
+            auth_providers: provided_auth_providers,
+            collapse_polling: provided_collapse_polling,
+        },
+    )
+        .await?;
+    Ok(ApiResponse(test_case))
+}
+
+pub async fn append_har(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    mut multipart: Multipart,
+) -> Result<ApiResponse<Vec<Action>>, AppError> {
+    let mut provided_har: Option<Har> = None;
+    let mut provided_excluded_path_parts: Vec<String> = vec![];
+    let mut provided_collapse_polling = false;
+    let mut provided_auth_providers: Vec<String> = vec![];
+    while let Some(field) = multipart.next_field().await
+        .map_err(|err| AppError::Validation(err.to_string()))? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "auth_providers" => {
+                provided_auth_providers = field.text().await.unwrap()
+                    .split(",")
+                    .map(|s| s.to_string().trim().to_string())
+                    .collect();
+            }
+            "collapse_polling" => {
+                provided_collapse_polling = field.text().await.unwrap().trim().eq_ignore_ascii_case("true");
+            }
+            "excluded_paths" => {
+                provided_excluded_path_parts = field
+                    .text()
+                    .await
+                    .unwrap()
+                    .split(",")
+                    .map(|s| s.to_string())
+                    .collect();
+            }
+            "file" => {
+                let data = field.bytes().await.unwrap();
+                provided_har = Some(har::from_reader(Cursor::new(data))
+                    .map_err(|err| AppError::Validation(format!("Invalid HAR: {}", err)))?);
+            }
+            _ => {}
         }
-        None => {}
     }
+    let har = provided_har.ok_or(AppError::Validation("No HAR file provided!".to_string()))?;
+    let test_case = repository.test_cases()
+        .get("eren".to_string(), id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    record_before_change(&repository, &"eren".to_string(), &id, "har append").await;
+    let result = append_to_test_case(
+        &repository,
+        &har.log,
+        &test_case,
+        HarImportOptions {
+            excluded_path_parts: provided_excluded_path_parts,
+            auth_providers: provided_auth_providers,
+            collapse_polling: provided_collapse_polling,
+        },
+    ).await;
+    ApiResponse::from(result)
+}
+
+/// A recording of a test case's flow, uploaded to compare the test case with or refresh it from.
+struct Rerecording {
+    har: Har,
+    excluded_path_parts: Vec<String>,
+    collapse_polling: bool,
+}
+
+async fn read_rerecording(mut multipart: Multipart) -> Result<Rerecording, AppError> {
+    let mut provided_har: Option<Har> = None;
+    let mut provided_excluded_path_parts: Vec<String> = vec![];
+    let mut provided_collapse_polling = false;
+    while let Some(field) = multipart.next_field().await
+        .map_err(|err| AppError::Validation(err.to_string()))? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "collapse_polling" => {
+                provided_collapse_polling = field.text().await.unwrap().trim().eq_ignore_ascii_case("true");
+            }
+            "excluded_paths" => {
+                provided_excluded_path_parts = field
+                    .text()
+                    .await
+                    .unwrap()
+                    .split(",")
+                    .map(|s| s.to_string())
+                    .collect();
+            }
+            "file" => {
+                let data = field.bytes().await.unwrap();
+                provided_har = Some(har::from_reader(Cursor::new(data))
+                    .map_err(|err| AppError::Validation(format!("Invalid HAR: {}", err)))?);
+            }
+            _ => {}
+        }
+    }
+    Ok(Rerecording {
+        har: provided_har.ok_or(AppError::Validation("No HAR file provided!".to_string()))?,
+        excluded_path_parts: provided_excluded_path_parts,
+        collapse_polling: provided_collapse_polling,
+    })
+}
+
+/// Updates recorded values, outputs and auth credentials of the test case from a new recording
+/// of the same flow, keeping its actions and everything edited on them.
+pub async fn refresh_from_har(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    multipart: Multipart,
+) -> Result<ApiResponse<RefreshReport>, AppError> {
+    let rerecording = read_rerecording(multipart).await?;
+    let test_case = repository.test_cases()
+        .get("eren".to_string(), id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    record_before_change(&repository, &"eren".to_string(), &id, "har refresh").await;
+    let result = refresh_test_case(
+        &repository,
+        &rerecording.har.log,
+        &test_case,
+        rerecording.excluded_path_parts,
+        rerecording.collapse_polling,
+    ).await;
+    ApiResponse::from(result)
+}
+
+/// Compares the test case with a new recording of its flow, changing nothing.
+pub async fn diff_har(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    multipart: Multipart,
+) -> Result<ApiResponse<HarDiff>, AppError> {
+    let rerecording = read_rerecording(multipart).await?;
+    let test_case = repository.test_cases()
+        .get("eren".to_string(), id)
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let result = diff_test_case(
+        &repository,
+        &rerecording.har.log,
+        &test_case,
+        rerecording.excluded_path_parts,
+        rerecording.collapse_polling,
+    ).await;
+    ApiResponse::from(result)
 }
 
 pub async fn filter_paths(mut multipart: Multipart) -> Result<ApiResponse<Vec<String>>, AppError> {
@@ -91,9 +254,13 @@ pub async fn filter_paths(mut multipart: Multipart) -> Result<ApiResponse<Vec<St
         "No Har found",
     )));
     let mut provided_excluded_path_parts: Vec<String> = vec![];
+    let mut provided_collapse_polling = false;
     while let Some(mut field) = multipart.next_field().await.unwrap() {
         let name = field.name().unwrap().to_string();
         match name.as_str() {
+            "collapse_polling" => {
+                provided_collapse_polling = field.text().await.unwrap().trim().eq_ignore_ascii_case("true");
+            }
             "excluded_paths" => {
                 provided_excluded_path_parts = field
                     .text()
@@ -114,7 +281,7 @@ pub async fn filter_paths(mut multipart: Multipart) -> Result<ApiResponse<Vec<St
 
     match provided_har {
         Ok(har) => {
-            let urls: Vec<String> = filter_entries(provided_excluded_path_parts, &har.log)
+            let urls: Vec<String> = filter_import_entries(provided_excluded_path_parts, &har.log, provided_collapse_polling)
                 .iter()
                 .map(|entry| &entry.request.url)
                 .cloned()
@@ -127,56 +294,216 @@ pub async fn filter_paths(mut multipart: Multipart) -> Result<ApiResponse<Vec<St
 
 pub async fn delete_test_case(
     Path(id): Path<String>,
-    State(repository): State<Repository>,
-) -> impl IntoResponse {
-    repository
-        .test_cases()
-        .delete(&"eren".to_string(), &id)
-        .await;
-    StatusCode::NO_CONTENT
+    State(app_state): State<AppState>,
+) -> Result<ApiResponse<DeletionJob>, AppError> {
+    let result = cascade::delete_test_case(app_state.repository, "eren".to_string(), id).await;
+    ApiResponse::from(result)
+}
+
+/// Deletes several test cases at once. Each one gets its own cascade deletion job;
+/// ids that do not exist are reported back instead of failing the whole batch.
+pub async fn batch_delete_test_cases(
+    State(app_state): State<AppState>,
+    ValidJson(payload): ValidJson<BatchDeleteTestCasesPayload>,
+) -> Result<ApiResponse<BatchDeleteTestCasesResult>, AppError> {
+    let mut result = BatchDeleteTestCasesResult { jobs: vec![], not_found: vec![] };
+    for id in dedup_ids(payload.ids) {
+        match cascade::delete_test_case(app_state.repository.clone(), "eren".to_string(), id.clone()).await {
+            Ok(job) => result.jobs.push(job),
+            Err(AppError::NotFound(_)) => result.not_found.push(id),
+            Err(err) => return Err(err),
+        }
+    }
+    ApiResponse::from(Ok(result))
 }
 
 pub async fn update_test_case(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<UpdateTestCasePayload>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<UpdateTestCasePayload>,
 ) -> Result<ApiResponse<TestCase>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case update").await;
     let result = repository.test_cases()
-        .update("eren".to_string(), id, payload.name, payload.description).await;
+        .update("eren".to_string(), id, payload.name, payload.description, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
 pub async fn update_test_case_name(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<UpdateNamePayload>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<UpdateNamePayload>,
 ) -> Result<ApiResponse<TestCase>, AppError> {
-    let result = repository.test_cases().update_name("eren".to_string(), id, payload.value).await;
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case name update").await;
+    let result = repository.test_cases().update_name("eren".to_string(), id, payload.value, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
 pub async fn update_test_case_description(
     Path(id): Path<String>,
     State(repository): State<Repository>,
-    Json(payload): Json<UpdateNamePayload>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<UpdateNamePayload>,
+) -> Result<ApiResponse<TestCase>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case description update").await;
+    let result = repository.test_cases().update_description("eren".to_string(), id, payload.value, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_test_case_concurrency_policy(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<UpdateConcurrencyPolicyPayload>,
+) -> Result<ApiResponse<TestCase>, AppError> {
+    let result = repository.test_cases().update_concurrency_policy("eren".to_string(), id, payload.value, expected_updated_at).await;
+    ApiResponse::from(result)
+}
+
+/// Sets which action entries long runs keep in their context.
+pub async fn update_test_case_context_retention(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<UpdateContextRetentionPayload>,
+) -> Result<ApiResponse<TestCase>, AppError> {
+    let result = repository.test_cases().update_context_retention("eren".to_string(), id, payload.value, expected_updated_at).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_test_case_archived(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<UpdateArchivedPayload>,
+) -> Result<ApiResponse<TestCase>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case archive update").await;
+    let result = repository.test_cases().update_archived("eren".to_string(), id, payload.value, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Moves every relative action of the test case to another host, e.g. from production to staging.
+pub async fn update_test_case_base_url(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(payload): ValidJson<UpdateBaseUrlPayload>,
+) -> Result<ApiResponse<TestCase>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case base url update").await;
+    let result = repository.test_cases().update_base_url("eren".to_string(), id, payload.value, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Runs another test case before this one and exposes its context under `$.setup`, none to stop.
+pub async fn update_test_case_setup(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<UpdateSetupPayload>,
 ) -> Result<ApiResponse<TestCase>, AppError> {
-    let result = repository.test_cases().update_description("eren".to_string(), id, payload.value).await;
+    if let Some(setup) = &payload.value {
+        validate_setup(&repository, &"eren".to_string(), &id, setup).await?;
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &id, "test case setup update").await;
+    let result = repository.test_cases().update_setup("eren".to_string(), id, payload.value, expected_updated_at).await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
 }
 
-#[derive(Deserialize, Clone)]
+fn dedup_ids(ids: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(id.clone())).collect()
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct UpdateArchivedPayload {
+    pub value: bool,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct UpdateSetupPayload {
+    pub value: Option<SetupCase>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct UpdateBaseUrlPayload {
+    pub value: Option<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct BatchDeleteTestCasesPayload {
+    pub ids: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct BatchDeleteTestCasesResult {
+    pub jobs: Vec<DeletionJob>,
+    pub not_found: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct UpdateConcurrencyPolicyPayload {
+    pub value: ConcurrencyPolicy,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct UpdateContextRetentionPayload {
+    pub value: ContextRetention,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct  UpdateNamePayload {
     pub value: String,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, ToSchema)]
 pub struct  UpdateTestCasePayload {
     pub name: String,
     pub description: String,
 }
 
-#[derive(Deserialize, Clone)]
+#[derive(Deserialize, Clone, IntoParams)]
 pub struct  ListTestCaseParams {
     pub next_page_key: Option<String>,
-    pub keyword: Option<String>
-}
\ No newline at end of file
+    pub keyword: Option<String>,
+    pub archived: Option<bool>,
+}
+
+impl Validate for UpdateNamePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("value", &self.value).into_iter().collect()
+    }
+}
+
+impl Validate for UpdateBaseUrlPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        self.value.as_ref()
+            .and_then(|value| check_base_url("value", value))
+            .into_iter()
+            .collect()
+    }
+}
+
+impl Validate for UpdateTestCasePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter().collect()
+    }
+}
+
+impl Validate for BatchDeleteTestCasesPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.ids.is_empty() {
+            return vec![FieldError::new("ids", "must not be empty")];
+        }
+        self.ids.iter()
+            .enumerate()
+            .filter_map(|(i, id)| check_not_blank(&format!("ids[{}]", i), id))
+            .collect()
+    }
+}
@@ -0,0 +1,57 @@
+use crate::action::model::{Action, ActionType};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// Hash of the method and path template of every HTTP action in order, so two recordings of the
+/// same flow match whatever hosts, ids or query strings they ran with.
+pub fn fingerprint(actions: &[Action]) -> String {
+    let mut actions: Vec<&Action> = actions.iter()
+        .filter(|action| action.action_type == ActionType::Http)
+        .collect();
+    actions.sort_by_key(|action| action.order);
+    let sequence: Vec<String> = actions.iter()
+        .map(|action| format!("{} {}", action.method.to_uppercase(), path_template(&action.url)))
+        .collect();
+    URL_SAFE_NO_PAD.encode(Sha256::digest(sequence.join("\n").as_bytes()))
+}
+
+/// Path of the URL with the host and query left out and every segment carrying a value, i.e. an
+/// expression, a number or an id, replaced with `{}`.
+pub fn path_template(url: &str) -> String {
+    let without_origin = match url.find("://") {
+        Some(scheme_end) => url[scheme_end + 3..].find('/').map_or("", |path_start| &url[scheme_end + 3 + path_start..]),
+        None => url,
+    };
+    let path = without_origin.split(['?', '#']).next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| if is_value_segment(segment) { "{}" } else { segment })
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+fn is_value_segment(segment: &str) -> bool {
+    segment.contains("$.")
+        || segment.chars().all(|c| c.is_ascii_digit())
+        || (segment.len() >= 16 && segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+
+    #[test]
+    fn same_flow_with_other_values_has_the_same_fingerprint() {
+        assert_eq!(path_template("https://abc.xyz/orders/42/items?page=2"), "/orders/{}/items");
+        assert_eq!(path_template("/orders/$.create_0.output.id"), "/orders/{}");
+        assert_eq!(path_template("https://abc.xyz/users/4b7c2a90-1f7e-4c1a-9c3d-7e2f6a1b0c9d/"), "/users/{}");
+        assert_eq!(path_template("https://abc.xyz"), "/");
+
+        let recorded = vec![test_action("create_0", 0, "post", "https://abc.xyz/orders"), test_action("get_1", 1, "GET", "https://abc.xyz/orders/42")];
+        let rerecorded = vec![test_action("get_1", 1, "GET", "/orders/$.create_0.output.id"), test_action("create_0", 0, "POST", "/orders?source=ui")];
+        assert_eq!(fingerprint(&recorded), fingerprint(&rerecorded));
+        assert_ne!(fingerprint(&recorded), fingerprint(&recorded[..1]));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod model;
 pub mod service;
+pub mod fingerprint;
 pub mod api;
\ No newline at end of file
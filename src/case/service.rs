@@ -1,24 +1,13 @@
-use crate::action::service::ActionsTable;
-use crate::action_execution::service::ActionExecutionTable;
 use crate::api::AppError;
-use crate::assertion::service::AssertionsTable;
-use crate::auth::service::AuthProviderOperations;
-use crate::case::model::TestCase;
-use crate::parameter::service::ParametersTable;
+use crate::case::model::{ConcurrencyPolicy, ContextRetention, SetupCase, TestCase};
+use crate::change::model::{EntityChange, EntityType};
 use crate::persistence::model::{ListItemsRequest, QueryResult};
-use crate::persistence::repo::{build_composite_key, OnDeleteMessage, Table};
-use crate::run::service::RunTable;
+use crate::persistence::repo::{build_composite_key, Table};
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
-use std::alloc::System;
+use serde_dynamo::to_attribute_value;
 use std::collections::HashMap;
-use std::fmt::Debug;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::mpsc;
-use tokio::sync::mpsc::Sender;
-use tokio::task::id;
-use tracing::info;
 
 struct TestCaseTable();
 
@@ -40,12 +29,20 @@ impl Table<TestCase> for TestCaseTable {
     }
 
     fn partition_key_from_entity(entity: &TestCase) -> (String, AttributeValue) {
-        Self::sort_key(build_composite_key(vec![entity.customer_id.clone()]))
+        Self::partition_key(entity.customer_id.clone())
     }
 
     fn sort_key_from_entity(entity: &TestCase) -> (String, AttributeValue) {
         Self::sort_key(build_composite_key(vec![entity.id.clone()]))
     }
+
+    fn versioned() -> bool {
+        true
+    }
+
+    fn change_of(entity: &TestCase) -> Option<EntityChange> {
+        Some(EntityChange::updated(&entity.customer_id, &entity.id, EntityType::TestCase, &entity.id, entity.updated_at))
+    }
 }
 
 impl TestCaseOperations {
@@ -60,13 +57,15 @@ impl TestCaseOperations {
         customer_id: String,
         next_page_key: Option<String>,
         keyword: Option<String>,
+        archived: bool,
     ) -> Result<QueryResult<TestCase>, AppError> {
+        let (filter_expression, names, values) = list_filter(keyword, archived);
         TestCaseTable::list_items(self.client.clone(), ListItemsRequest::builder()
             .partition_key(customer_id)
             .maybe_next_page_key(next_page_key)
-            .maybe_filter_expression(keyword.clone().map(|keyword|{"contains(#name, :keyword)".to_string()}))
-            .maybe_expression_attribute_names(keyword.clone().map(|keyword|{HashMap::from([("#name".to_string(), "name".to_string())])}))
-            .maybe_expression_attribute_values(keyword.clone().map(|keyword|{HashMap::from([(":keyword".to_string(), AttributeValue::S(keyword))])}))
+            .filter_expression(filter_expression)
+            .expression_attribute_names(names)
+            .expression_attribute_values(values)
             .build()).await
     }
 
@@ -78,8 +77,15 @@ impl TestCaseOperations {
         TestCaseTable::get_item(self.client.clone(), customer_id, test_case_id).await
     }
 
-    pub async fn update(&self, customer_id: String, test_case_id: String, name: String, desc: String) -> Result<TestCase, AppError> {
-        TestCaseTable::update_partial(customer_id, test_case_id, self.client.clone()
+    pub async fn batch_get(&self, customer_id: &String, ids: Vec<String>) -> Result<Vec<TestCase>, AppError> {
+        let key_pairs = ids.iter()
+            .map(|id| (customer_id.clone(), id.clone()))
+            .collect();
+        TestCaseTable::batch_get_items(self.client.clone(), key_pairs).await
+    }
+
+    pub async fn update(&self, customer_id: String, test_case_id: String, name: String, desc: String, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
             .update_item()
             .expression_attribute_names("#name", "name")
             .expression_attribute_names("#desc", "description")
@@ -89,8 +95,8 @@ impl TestCaseOperations {
         ).await
     }
 
-    pub async fn update_name(&self, customer_id: String, test_case_id: String, name: String) -> Result<TestCase, AppError> {
-        TestCaseTable::update_partial(customer_id, test_case_id, self.client.clone()
+    pub async fn update_name(&self, customer_id: String, test_case_id: String, name: String, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
             .update_item()
             .expression_attribute_names("#name", "name")
             .expression_attribute_values(":val", AttributeValue::S(name))
@@ -98,8 +104,8 @@ impl TestCaseOperations {
         ).await
     }
 
-    pub async fn update_description(&self, customer_id: String, test_case_id: String, description: String) -> Result<TestCase, AppError> {
-        TestCaseTable::update_partial(customer_id, test_case_id, self.client.clone()
+    pub async fn update_description(&self, customer_id: String, test_case_id: String, description: String, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
             .update_item()
             .expression_attribute_names("#desc", "description")
             .expression_attribute_values(":val", AttributeValue::S(description))
@@ -107,123 +113,136 @@ impl TestCaseOperations {
         ).await
     }
 
-    pub async fn delete(&self, customer_id: &String, test_case_id: &String) {
-        let (tx, mut rx) = mpsc::channel(32);
-        let deleted_test_case = TestCaseTable::delete_item(
-            self.client.clone(),
-            customer_id.clone(),
-            test_case_id.clone(),
-        ).await;
-        if let Ok(Some(deleted_case)) = deleted_test_case {
-            tx.send(OnDeleteMessage::TestCaseDeleted(deleted_case))
-                .await
-                .unwrap();
-        }
-        let cloned_client = self.client.clone();
-        tokio::task::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                info!("received deleted message: {:?}", message);
-                match message {
-                    OnDeleteMessage::TestCaseDeleted(test_case) => {
-                        Self::delete_all_actions(&test_case.customer_id, &test_case.id, &tx, cloned_client.clone()).await;
-                        Self::delete_all_runs(&test_case.customer_id, &test_case.id, &tx, cloned_client.clone()).await;
-                        Self::delete_all_assertions(&test_case.customer_id, &test_case.id, &tx, cloned_client.clone()).await;
-                        AuthProviderOperations {
-                            client: cloned_client.clone(),
-                        }.unlink_test_case(&test_case.customer_id, &test_case.id).await;
-                    }
-                    OnDeleteMessage::ActionDeleted(action) => {
-                        Self::delete_all_parameters(&action.customer_id, &action.test_case_id, &tx, cloned_client.clone()).await;
-                    }
-                    OnDeleteMessage::RunDeleted(run) => {
-                        Self::delete_all_action_executions(&run.customer_id, &run.test_case_id, &run.id, &tx, cloned_client.clone()).await;
-                    }
-                }
+    pub async fn update_concurrency_policy(&self, customer_id: String, test_case_id: String, policy: ConcurrencyPolicy, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#cp", "concurrency_policy")
+            .expression_attribute_values(":val", to_attribute_value(policy).unwrap())
+            .update_expression("SET #cp = :val"),
+        ).await
+    }
+
+    pub async fn update_context_retention(&self, customer_id: String, test_case_id: String, retention: ContextRetention, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#context_retention", "context_retention")
+            .expression_attribute_values(":val", to_attribute_value(retention).unwrap())
+            .update_expression("SET #context_retention = :val"),
+        ).await
+    }
+
+    pub async fn update_base_url(&self, customer_id: String, test_case_id: String, base_url: Option<String>, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#base_url", "base_url")
+            .expression_attribute_values(":val", base_url.map_or(AttributeValue::Null(true), AttributeValue::S))
+            .update_expression("SET #base_url = :val"),
+        ).await
+    }
+
+    pub async fn update_setup(&self, customer_id: String, test_case_id: String, setup: Option<SetupCase>, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#setup", "setup")
+            .expression_attribute_values(":val", setup.map_or(AttributeValue::Null(true), |setup| to_attribute_value(setup).unwrap()))
+            .update_expression("SET #setup = :val"),
+        ).await
+    }
+
+    pub async fn update_fingerprint(&self, customer_id: String, test_case_id: String, fingerprint: String) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, None, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#fingerprint", "fingerprint")
+            .expression_attribute_values(":val", AttributeValue::S(fingerprint))
+            .update_expression("SET #fingerprint = :val"),
+        ).await
+    }
+
+    /// Every test case of the customer imported with the given action sequence fingerprint.
+    pub async fn find_by_fingerprint(&self, customer_id: &String, fingerprint: &String) -> Result<Vec<TestCase>, AppError> {
+        let mut test_cases = vec![];
+        let mut next_page_key = None;
+        loop {
+            let page = TestCaseTable::list_items(self.client.clone(), ListItemsRequest::builder()
+                .partition_key(customer_id.clone())
+                .maybe_next_page_key(next_page_key)
+                .filter_expression("#fingerprint = :fingerprint".to_string())
+                .expression_attribute_names(HashMap::from([("#fingerprint".to_string(), "fingerprint".to_string())]))
+                .expression_attribute_values(HashMap::from([(":fingerprint".to_string(), AttributeValue::S(fingerprint.clone()))]))
+                .build()).await?;
+            test_cases.extend(page.items);
+            next_page_key = page.next_page_key;
+            if next_page_key.is_none() {
+                return Ok(test_cases);
             }
-        });
-    }
-
-    async fn delete_all_actions(customer_id: &String, id: &String, tx: &Sender<OnDeleteMessage>, client: Arc<Client>) {
-        let sender = tx.clone();
-        let client_cloned = client.clone();
-        let customer_id_cloned = customer_id.clone();
-        let id_cloned = id.clone();
-        tokio::task::spawn(async move {
-            ActionsTable::delete_all_items(
-                client_cloned,
-                build_composite_key(vec![
-                    customer_id_cloned,
-                    id_cloned,
-                ]),
-                &sender,
-            )
-                .await;
-        });
-    }
-
-    async fn delete_all_parameters(customer_id: &String, id: &String, tx: &Sender<OnDeleteMessage>, client: Arc<Client>) {
-        let sender = tx.clone();
-        let client_cloned = client.clone();
-        let customer_id_cloned = customer_id.clone();
-        let id_cloned = id.clone();
-        tokio::task::spawn(async move {
-            ParametersTable::delete_all_items(
-                client_cloned,
-                build_composite_key(vec![
-                    customer_id_cloned,
-                    id_cloned,
-                ]),
-                &sender,
-            ).await;
-        });
-    }
-
-    async fn delete_all_runs(customer_id: &String, id: &String, tx: &Sender<OnDeleteMessage>, client: Arc<Client>) {
-        let sender = tx.clone();
-        let client_cloned = client.clone();
-        let customer_id_cloned = customer_id.clone();
-        let id_cloned = id.clone();
-        tokio::task::spawn(async move {
-            RunTable::delete_all_items(
-                client_cloned.clone(),
-                build_composite_key(vec![
-                    customer_id_cloned,
-                    id_cloned,
-                ]),
-                &sender,
-            ).await;
-        });
-    }
-
-    async fn delete_all_assertions(customer_id: &String, id: &String, tx: &Sender<OnDeleteMessage>, client: Arc<Client>) {
-        let sender = tx.clone();
-        let client_cloned = client.clone();
-        let customer_id_cloned = customer_id.clone();
-        let id_cloned = id.clone();
-        tokio::task::spawn(async move {
-            AssertionsTable::delete_all_items(client_cloned, build_composite_key(vec![customer_id_cloned, id_cloned]), &sender)
-                .await;
-        });
-    }
-
-    async fn delete_all_action_executions(customer_id: &String, test_case_id: &String, run_id: &String, tx: &Sender<OnDeleteMessage>, client: Arc<Client>) {
-        let sender = tx.clone();
-        let client_cloned = client.clone();
-        let customer_id_cloned = customer_id.clone();
-        let test_case_id_cloned = test_case_id.clone();
-        let run_id_cloned = run_id.clone();
-        tokio::task::spawn(async move {
-            ActionExecutionTable::delete_all_items(client_cloned, build_composite_key(vec![customer_id_cloned, test_case_id_cloned, run_id_cloned]), &sender)
-                .await;
-        });
+        }
+    }
+
+    pub async fn update_archived(&self, customer_id: String, test_case_id: String, archived: bool, expected_updated_at: Option<u64>) -> Result<TestCase, AppError> {
+        TestCaseTable::update_partial_expecting(customer_id, test_case_id, expected_updated_at, self.client.clone()
+            .update_item()
+            .expression_attribute_names("#archived", "archived")
+            .expression_attribute_values(":val", AttributeValue::Bool(archived))
+            .update_expression("SET #archived = :val"),
+        ).await
+    }
+
+    /// Archived test cases included.
+    pub async fn list_all(&self, customer_id: &String) -> Result<Vec<TestCase>, AppError> {
+        TestCaseTable::list_all_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    pub async fn count(&self, customer_id: &String) -> Result<u64, AppError> {
+        TestCaseTable::count_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    /// Every customer owning at least one test case.
+    pub async fn list_customer_ids(&self) -> Result<Vec<String>, AppError> {
+        TestCaseTable::scan_partition_keys(self.client.clone()).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, test_case_id: &String) -> Result<Option<TestCase>, AppError> {
+        TestCaseTable::delete_item(self.client.clone(), customer_id.clone(), test_case_id.clone()).await
     }
 }
 
+/// Test cases stored before archiving existed have no `archived` attribute, so
+/// they are treated as active.
+fn list_filter(keyword: Option<String>, archived: bool) -> (String, HashMap<String, String>, HashMap<String, AttributeValue>) {
+    let mut names = HashMap::from([("#archived".to_string(), "archived".to_string())]);
+    let mut values = HashMap::new();
+    let mut filter_expression = if archived {
+        values.insert(":archived".to_string(), AttributeValue::Bool(true));
+        "#archived = :archived".to_string()
+    } else {
+        values.insert(":archived".to_string(), AttributeValue::Bool(false));
+        "(attribute_not_exists(#archived) OR #archived = :archived)".to_string()
+    };
+    if let Some(keyword) = keyword {
+        names.insert("#name".to_string(), "name".to_string());
+        values.insert(":keyword".to_string(), AttributeValue::S(keyword));
+        filter_expression.push_str(" AND contains(#name, :keyword)");
+    }
+    (filter_expression, names, values)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::persistence::repo::{init_logger, Repository};
 
+    #[test]
+    fn list_filter_hides_archived_by_default() {
+        let (expression, names, values) = list_filter(Some("login".to_string()), false);
+        assert_eq!(expression, "(attribute_not_exists(#archived) OR #archived = :archived) AND contains(#name, :keyword)");
+        assert_eq!(names.get("#name"), Some(&"name".to_string()));
+        assert_eq!(values.get(":archived"), Some(&AttributeValue::Bool(false)));
+
+        let (expression, _, values) = list_filter(None, true);
+        assert_eq!(expression, "#archived = :archived");
+        assert_eq!(values.get(":archived"), Some(&AttributeValue::Bool(true)));
+    }
+
 
     #[tokio::test]
     async fn delete() {
@@ -242,7 +261,7 @@ mod tests {
         println!("{:?}", get_result);
 
         repository.test_cases()
-            .delete(&create_case.customer_id, &create_case.id.to_string()).await;
+            .delete(&create_case.customer_id, &create_case.id.to_string()).await.unwrap();
 
         let result = repository.test_cases()
             .get(create_case.customer_id.clone(), create_case.customer_id).await;
@@ -0,0 +1,46 @@
+use crate::change::feed::change_feed;
+use crate::change::model::{ChangeKind, EntityChange};
+use axum::extract::Path;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures::Stream;
+use std::convert::Infallible;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Server-sent events for every write to the test case and its actions, assertions and parameters.
+/// A `resync` event means changes were missed and the client should reload what it shows.
+pub async fn stream_changes(
+    Path(test_case_id): Path<String>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let customer_id = "eren".to_string();
+    let receiver = change_feed().subscribe();
+    let stream = futures::stream::unfold(receiver, move |mut receiver| {
+        let customer_id = customer_id.clone();
+        let test_case_id = test_case_id.clone();
+        async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(change) if change.customer_id == customer_id && change.test_case_id == test_case_id => to_event(&change),
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(missed)) => Event::default().event("resync").data(missed.to_string()),
+                    Err(RecvError::Closed) => return None,
+                };
+                return Some((Ok(event), receiver));
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn to_event(change: &EntityChange) -> Event {
+    let name = match change.kind {
+        ChangeKind::Created => "created",
+        ChangeKind::Updated => "updated",
+        ChangeKind::Deleted => "deleted",
+    };
+    let event = Event::default().event(name);
+    let event = match change.version {
+        Some(version) => event.id(version.to_string()),
+        None => event,
+    };
+    event.json_data(change).unwrap_or_else(|_| Event::default().event("resync"))
+}
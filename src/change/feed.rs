@@ -0,0 +1,34 @@
+use crate::change::middleware::current_user;
+use crate::change::model::EntityChange;
+use std::sync::OnceLock;
+use tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// Changes a slow subscriber may fall behind by before it is told to resync.
+const FEED_CAPACITY: usize = 1024;
+
+static CHANGE_FEED: OnceLock<ChangeFeed> = OnceLock::new();
+
+pub fn change_feed() -> &'static ChangeFeed {
+    CHANGE_FEED.get_or_init(ChangeFeed::new)
+}
+
+/// In-process fan-out of entity changes; subscribers only see writes made by this instance.
+pub struct ChangeFeed {
+    sender: Sender<EntityChange>,
+}
+
+impl ChangeFeed {
+    fn new() -> Self {
+        ChangeFeed { sender: broadcast::channel(FEED_CAPACITY).0 }
+    }
+
+    pub fn publish(&self, mut change: EntityChange) {
+        change.user_id = change.user_id.or_else(current_user);
+        // no subscribers is not an error, the change is simply dropped
+        let _ = self.sender.send(change);
+    }
+
+    pub fn subscribe(&self) -> Receiver<EntityChange> {
+        self.sender.subscribe()
+    }
+}
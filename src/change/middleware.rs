@@ -0,0 +1,21 @@
+use crate::access::middleware::USER_HEADER;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+tokio::task_local! {
+    static CALLER: Option<String>;
+}
+
+/// Remembers the caller for the duration of the request so published changes name who made them.
+pub async fn track_caller(request: Request, next: Next) -> Response {
+    let user_id = request.headers()
+        .get(USER_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+    CALLER.scope(user_id, next.run(request)).await
+}
+
+pub fn current_user() -> Option<String> {
+    CALLER.try_with(|user_id| user_id.clone()).ok().flatten()
+}
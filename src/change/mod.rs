@@ -0,0 +1,5 @@
+pub mod model;
+pub mod feed;
+pub mod api;
+pub mod middleware;
+pub mod version;
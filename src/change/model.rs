@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum EntityType {
+    TestCase,
+    Action,
+    Assertion,
+    Parameter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// Broadcast to everyone watching the test case after one of its entities was written.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct EntityChange {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    /// Set for parameters, which are addressed through their action.
+    pub action_id: Option<String>,
+    pub kind: ChangeKind,
+    /// `updated_at` of the entity after the write, to be sent back in `If-Match` on the next update.
+    pub version: Option<u64>,
+    /// Caller of the request that made the change, so clients can skip their own edits.
+    pub user_id: Option<String>,
+}
+
+impl EntityChange {
+    pub fn updated(customer_id: &str, test_case_id: &str, entity_type: EntityType, entity_id: &str, version: Option<u64>) -> Self {
+        EntityChange {
+            customer_id: customer_id.to_string(),
+            test_case_id: test_case_id.to_string(),
+            entity_type,
+            entity_id: entity_id.to_string(),
+            action_id: None,
+            kind: ChangeKind::Updated,
+            version,
+            user_id: None,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::api::AppError;
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::header::IF_MATCH;
+use axum::http::request::Parts;
+
+/// `updated_at` the caller last saw, taken from `If-Match`; `None` when absent or `*`.
+pub struct IfMatch(pub Option<u64>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(IF_MATCH) {
+            None => Ok(IfMatch(None)),
+            Some(value) => value.to_str().ok()
+                .and_then(parse_if_match)
+                .map(IfMatch)
+                .ok_or(AppError::Validation("If-Match must be the updated_at of the entity!".to_string())),
+        }
+    }
+}
+
+/// `"1718000000000"`, `W/"1718000000000"` or the bare number.
+fn parse_if_match(value: &str) -> Option<Option<u64>> {
+    let value = value.trim();
+    if value == "*" {
+        return Some(None);
+    }
+    let value = value.strip_prefix("W/").unwrap_or(value).trim_matches('"');
+    value.parse().ok().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version_from_if_match() {
+        assert_eq!(parse_if_match("\"1718000000000\""), Some(Some(1718000000000)));
+        assert_eq!(parse_if_match("W/\"12\""), Some(Some(12)));
+        assert_eq!(parse_if_match("12"), Some(Some(12)));
+        assert_eq!(parse_if_match("*"), Some(None));
+        assert_eq!(parse_if_match("abc"), None);
+    }
+}
@@ -0,0 +1,265 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+static CONFIG: OnceLock<AppConfig> = OnceLock::new();
+
+/// Startup configuration, read from the YAML file in `PARROTON_CONFIG_FILE` when set and then
+/// overridden by `PARROTON_*` environment variables.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct AppConfig {
+    pub bind_address: String,
+    pub port: u16,
+    pub aws_region: Option<String>,
+    pub aws_profile: Option<String>,
+    /// Prepended to every DynamoDB table name, e.g. `staging_` for `staging_actions`.
+    pub table_prefix: String,
+    pub body_limit_bytes: usize,
+    pub default_wait_timeout_secs: u64,
+    pub max_wait_timeout_secs: u64,
+    /// Allowed CORS origins; only same-origin requests are allowed when empty.
+    pub cors_origins: Vec<String>,
+    pub log_format: LogFormat,
+    /// S3 bucket for large response bodies and binary downloads; they stay inline when not set.
+    pub artifact_bucket: Option<String>,
+    /// Response bodies serialized larger than this are moved to the artifact bucket.
+    pub artifact_threshold_bytes: usize,
+    pub artifact_url_ttl_secs: u64,
+    /// Users, by the `sub` of their session, allowed on the operator endpoints under `/admin`
+    /// across customers, and admins of customers that have no memberships yet.
+    pub admin_user_ids: Vec<String>,
+    /// Secret user sessions (HS256 JWTs) are signed with; only API tokens are accepted when not set.
+    pub session_secret: Option<String>,
+    /// Secret share links are signed with; a random one is used when not set, so links stop
+    /// working when the process restarts.
+    pub share_secret: Option<String>,
+    /// Sends the W3C `traceparent` of the run with every request of its actions.
+    pub propagate_traceparent: bool,
+    /// Base URL the API is reached at, used for links in notifications.
+    pub public_url: Option<String>,
+    /// Verified SES sender of notification emails and digests; no email is sent when not set.
+    pub email_sender: Option<String>,
+    /// Idle connections kept per host by the client actions are sent with; unlimited when not set.
+    pub http_pool_max_idle_per_host: Option<usize>,
+    pub http_pool_idle_timeout_secs: u64,
+    /// Lets the client negotiate HTTP/2; requests are sent over HTTP/1.1 only when off.
+    pub http2: bool,
+    pub http_tcp_keepalive_secs: Option<u64>,
+    /// Addresses hosts resolve to instead of asking DNS, e.g. for hostnames not public yet.
+    pub http_dns_overrides: HashMap<String, IpAddr>,
+    /// Directory of WebAssembly modules providing custom assertion functions and comparisons.
+    pub plugin_dir: Option<String>,
+    /// Instructions a plugin may execute in one call before it is stopped.
+    pub plugin_fuel: u64,
+}
+
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            bind_address: "0.0.0.0".to_string(),
+            port: 3000,
+            aws_region: None,
+            aws_profile: None,
+            table_prefix: "".to_string(),
+            body_limit_bytes: 90003944,
+            default_wait_timeout_secs: 300,
+            max_wait_timeout_secs: 3600,
+            cors_origins: vec![],
+            log_format: LogFormat::default(),
+            artifact_bucket: None,
+            artifact_threshold_bytes: 256 * 1024,
+            artifact_url_ttl_secs: 900,
+            admin_user_ids: vec![],
+            session_secret: None,
+            share_secret: None,
+            propagate_traceparent: false,
+            public_url: None,
+            email_sender: None,
+            http_pool_max_idle_per_host: None,
+            http_pool_idle_timeout_secs: 90,
+            http2: true,
+            http_tcp_keepalive_secs: None,
+            http_dns_overrides: HashMap::new(),
+            plugin_dir: None,
+            plugin_fuel: 10_000_000,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn load() -> Result<Self, String> {
+        let base = match env::var("PARROTON_CONFIG_FILE") {
+            Ok(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .map_err(|err| format!("could not read config file {}: {}", path, err))?;
+                serde_yaml::from_str(&content)
+                    .map_err(|err| format!("invalid config file {}: {}", path, err))?
+            }
+            Err(_) => AppConfig::default(),
+        };
+        base.with_overrides(&env::vars().collect())
+    }
+
+    fn with_overrides(mut self, vars: &HashMap<String, String>) -> Result<Self, String> {
+        let var = |name: &str| vars.get(name).filter(|value| !value.trim().is_empty()).map(|value| value.trim().to_string());
+        if let Some(bind_address) = var("PARROTON_BIND_ADDRESS") {
+            self.bind_address = bind_address;
+        }
+        if let Some(port) = var("PARROTON_PORT") {
+            self.port = parse_var("PARROTON_PORT", &port)?;
+        }
+        self.aws_region = var("PARROTON_AWS_REGION").or(self.aws_region);
+        self.aws_profile = var("PARROTON_AWS_PROFILE").or(self.aws_profile);
+        if let Some(table_prefix) = var("PARROTON_TABLE_PREFIX") {
+            self.table_prefix = table_prefix;
+        }
+        if let Some(body_limit_bytes) = var("PARROTON_BODY_LIMIT_BYTES") {
+            self.body_limit_bytes = parse_var("PARROTON_BODY_LIMIT_BYTES", &body_limit_bytes)?;
+        }
+        if let Some(timeout) = var("PARROTON_DEFAULT_WAIT_TIMEOUT_SECS") {
+            self.default_wait_timeout_secs = parse_var("PARROTON_DEFAULT_WAIT_TIMEOUT_SECS", &timeout)?;
+        }
+        if let Some(timeout) = var("PARROTON_MAX_WAIT_TIMEOUT_SECS") {
+            self.max_wait_timeout_secs = parse_var("PARROTON_MAX_WAIT_TIMEOUT_SECS", &timeout)?;
+        }
+        if let Some(origins) = var("PARROTON_CORS_ORIGINS") {
+            self.cors_origins = split_list(&origins);
+        }
+        if let Some(admin_user_ids) = var("PARROTON_ADMIN_USER_IDS") {
+            self.admin_user_ids = split_list(&admin_user_ids);
+        }
+        self.session_secret = var("PARROTON_SESSION_SECRET").or(self.session_secret);
+        self.share_secret = var("PARROTON_SHARE_SECRET").or(self.share_secret);
+        if let Some(propagate) = var("PARROTON_PROPAGATE_TRACEPARENT") {
+            self.propagate_traceparent = parse_var("PARROTON_PROPAGATE_TRACEPARENT", &propagate)?;
+        }
+        self.artifact_bucket = var("PARROTON_ARTIFACT_BUCKET").or(self.artifact_bucket);
+        self.public_url = var("PARROTON_PUBLIC_URL").or(self.public_url);
+        self.email_sender = var("PARROTON_EMAIL_SENDER").or(self.email_sender);
+        if let Some(threshold) = var("PARROTON_ARTIFACT_THRESHOLD_BYTES") {
+            self.artifact_threshold_bytes = parse_var("PARROTON_ARTIFACT_THRESHOLD_BYTES", &threshold)?;
+        }
+        if let Some(ttl) = var("PARROTON_ARTIFACT_URL_TTL_SECS") {
+            self.artifact_url_ttl_secs = parse_var("PARROTON_ARTIFACT_URL_TTL_SECS", &ttl)?;
+        }
+        if let Some(max_idle) = var("PARROTON_HTTP_POOL_MAX_IDLE_PER_HOST") {
+            self.http_pool_max_idle_per_host = Some(parse_var("PARROTON_HTTP_POOL_MAX_IDLE_PER_HOST", &max_idle)?);
+        }
+        if let Some(timeout) = var("PARROTON_HTTP_POOL_IDLE_TIMEOUT_SECS") {
+            self.http_pool_idle_timeout_secs = parse_var("PARROTON_HTTP_POOL_IDLE_TIMEOUT_SECS", &timeout)?;
+        }
+        if let Some(http2) = var("PARROTON_HTTP2") {
+            self.http2 = parse_var("PARROTON_HTTP2", &http2)?;
+        }
+        if let Some(keepalive) = var("PARROTON_HTTP_TCP_KEEPALIVE_SECS") {
+            self.http_tcp_keepalive_secs = Some(parse_var("PARROTON_HTTP_TCP_KEEPALIVE_SECS", &keepalive)?);
+        }
+        if let Some(overrides) = var("PARROTON_HTTP_DNS_OVERRIDES") {
+            self.http_dns_overrides = split_list(&overrides).iter()
+                .map(|entry| match entry.split_once('=') {
+                    Some((host, address)) => Ok((host.trim().to_lowercase(), parse_var("PARROTON_HTTP_DNS_OVERRIDES", address.trim())?)),
+                    None => Err(format!("invalid value for PARROTON_HTTP_DNS_OVERRIDES: {}", entry)),
+                })
+                .collect::<Result<_, String>>()?;
+        }
+        self.plugin_dir = var("PARROTON_PLUGIN_DIR").or(self.plugin_dir);
+        if let Some(fuel) = var("PARROTON_PLUGIN_FUEL") {
+            self.plugin_fuel = parse_var("PARROTON_PLUGIN_FUEL", &fuel)?;
+        }
+        if let Some(log_format) = var("PARROTON_LOG_FORMAT") {
+            self.log_format = match log_format.to_lowercase().as_str() {
+                "text" => LogFormat::Text,
+                "json" => LogFormat::Json,
+                _ => return Err(format!("invalid value for PARROTON_LOG_FORMAT: {}", log_format)),
+            };
+        }
+        Ok(self)
+    }
+
+    pub fn listen_address(&self) -> String {
+        format!("{}:{}", self.bind_address, self.port)
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_var<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value for {}: {}", name, value))
+}
+
+pub fn app_config() -> &'static AppConfig {
+    CONFIG.get_or_init(|| AppConfig::load().unwrap_or_else(|err| panic!("{}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_overrides_file_values() {
+        let file: AppConfig = serde_yaml::from_str("port: 8080\ntable_prefix: staging_\naws_region: eu-west-1").unwrap();
+        let vars = HashMap::from([
+            ("PARROTON_PORT".to_string(), "9090".to_string()),
+            ("PARROTON_CORS_ORIGINS".to_string(), "https://a.xyz, https://b.xyz,".to_string()),
+            ("PARROTON_AWS_PROFILE".to_string(), " ".to_string()),
+            ("PARROTON_LOG_FORMAT".to_string(), "JSON".to_string()),
+            ("PARROTON_ARTIFACT_BUCKET".to_string(), "parroton-artifacts".to_string()),
+            ("PARROTON_ADMIN_USER_IDS".to_string(), "ops1,ops2".to_string()),
+            ("PARROTON_PUBLIC_URL".to_string(), "https://parroton.abc.xyz".to_string()),
+            ("PARROTON_EMAIL_SENDER".to_string(), "parroton@abc.xyz".to_string()),
+            ("PARROTON_HTTP2".to_string(), "false".to_string()),
+            ("PARROTON_HTTP_DNS_OVERRIDES".to_string(), "staging.abc.xyz=10.0.0.5, API.abc.xyz=::1".to_string()),
+            ("PARROTON_PLUGIN_DIR".to_string(), "/opt/parroton/plugins".to_string()),
+            ("PARROTON_SHARE_SECRET".to_string(), "share-secret".to_string()),
+            ("PARROTON_PROPAGATE_TRACEPARENT".to_string(), "true".to_string()),
+        ]);
+
+        let config = file.with_overrides(&vars).unwrap();
+
+        assert_eq!(config.listen_address(), "0.0.0.0:9090");
+        assert_eq!(config.table_prefix, "staging_");
+        assert_eq!(config.aws_region, Some("eu-west-1".to_string()));
+        assert_eq!(config.aws_profile, None);
+        assert_eq!(config.cors_origins, vec!["https://a.xyz", "https://b.xyz"]);
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.body_limit_bytes, AppConfig::default().body_limit_bytes);
+        assert_eq!(config.artifact_bucket, Some("parroton-artifacts".to_string()));
+        assert_eq!(config.artifact_threshold_bytes, 256 * 1024);
+        assert_eq!(config.admin_user_ids, vec!["ops1", "ops2"]);
+        assert_eq!(config.public_url, Some("https://parroton.abc.xyz".to_string()));
+        assert_eq!(config.email_sender, Some("parroton@abc.xyz".to_string()));
+        assert!(!config.http2);
+        assert_eq!(config.http_pool_idle_timeout_secs, 90);
+        assert_eq!(config.http_dns_overrides, HashMap::from([
+            ("staging.abc.xyz".to_string(), "10.0.0.5".parse().unwrap()),
+            ("api.abc.xyz".to_string(), "::1".parse().unwrap()),
+        ]));
+        assert_eq!(config.plugin_dir, Some("/opt/parroton/plugins".to_string()));
+        assert_eq!(config.plugin_fuel, 10_000_000);
+        assert_eq!(config.share_secret, Some("share-secret".to_string()));
+        assert!(config.propagate_traceparent);
+
+        let invalid = HashMap::from([("PARROTON_PORT".to_string(), "http".to_string())]);
+        assert!(AppConfig::default().with_overrides(&invalid).is_err());
+        let invalid = HashMap::from([("PARROTON_HTTP_DNS_OVERRIDES".to_string(), "staging.abc.xyz".to_string())]);
+        assert!(AppConfig::default().with_overrides(&invalid).is_err());
+        let invalid = HashMap::from([("PARROTON_PROPAGATE_TRACEPARENT".to_string(), "yes".to_string())]);
+        assert!(AppConfig::default().with_overrides(&invalid).is_err());
+    }
+}
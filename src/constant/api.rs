@@ -0,0 +1,85 @@
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::constant::model::{Constants, CUSTOMER_CONSTANTS_ID};
+use crate::persistence::repo::Repository;
+use crate::validation::{ValidJson, Validate};
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+const MAX_CONSTANTS_BYTES: usize = 64 * 1024;
+
+pub async fn get_constants(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Constants>, AppError> {
+    let result = repository.constants()
+        .get("eren", CUSTOMER_CONSTANTS_ID)
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn put_constants(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<PutConstantsPayload>,
+) -> Result<ApiResponse<Constants>, AppError> {
+    let result = repository.constants()
+        .put(payload.into_constants(CUSTOMER_CONSTANTS_ID))
+        .await;
+    ApiResponse::from(result)
+}
+
+/// Only the test case's own constants, without the customer-wide ones they override.
+pub async fn get_test_case_constants(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Constants>, AppError> {
+    let result = repository.constants()
+        .get("eren", &test_case_id)
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn put_test_case_constants(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<PutConstantsPayload>,
+) -> Result<ApiResponse<Constants>, AppError> {
+    repository.test_cases()
+        .get("eren".to_string(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let result = repository.constants()
+        .put(payload.into_constants(&test_case_id))
+        .await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutConstantsPayload {
+    #[serde(default)]
+    pub values: Map<String, Value>,
+}
+
+impl PutConstantsPayload {
+    fn into_constants(self, id: &str) -> Constants {
+        Constants::builder()
+            .customer_id("eren".to_string())
+            .id(id.to_string())
+            .values(self.values)
+            .build()
+    }
+}
+
+impl Validate for PutConstantsPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = self.values.keys()
+            .filter(|key| key.is_empty() || key.contains(['.', '[', ']', '/', '$']) || key.contains(char::is_whitespace))
+            .map(|key| FieldError::new(format!("values.{}", key), "cannot be referenced as $.constants.<key>"))
+            .collect();
+        let size = serde_json::to_vec(&self.values).map_or(0, |bytes| bytes.len());
+        if size > MAX_CONSTANTS_BYTES {
+            errors.push(FieldError::new("values", format!("is {} bytes, at most {} are allowed", size, MAX_CONSTANTS_BYTES)));
+        }
+        errors
+    }
+}
@@ -0,0 +1,81 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+/// Context key the constants are merged under, so they are referenced as `$.constants.<key>`.
+pub const CONSTANTS_CONTEXT_KEY: &str = "constants";
+/// Id of the customer-wide constants; test case constants use the test case id.
+pub const CUSTOMER_CONSTANTS_ID: &str = "customer";
+
+/// Non-secret configuration, like tenant ids, that runs read from the context.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Constants {
+    pub customer_id: String,
+    pub id: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub values: Map<String, Value>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl Constants {
+    pub fn empty(customer_id: &str, id: &str) -> Self {
+        Constants::builder()
+            .customer_id(customer_id.to_string())
+            .id(id.to_string())
+            .build()
+    }
+}
+
+/// Test case constants override customer-wide ones with the same key.
+pub fn merge_constants(customer_wide: Constants, test_case: Constants) -> Map<String, Value> {
+    let mut values = customer_wide.values;
+    values.extend(test_case.values);
+    values
+}
+
+/// Adds the constants to the seed context. Constants given in the seed context win, so a run can
+/// override single keys.
+pub fn seed_constants(seed_context: &mut Map<String, Value>, mut constants: Map<String, Value>) {
+    if constants.is_empty() {
+        return;
+    }
+    match seed_context.get(CONSTANTS_CONTEXT_KEY) {
+        Some(Value::Object(seeded)) => constants.extend(seeded.clone()),
+        Some(_) => return,
+        None => {}
+    }
+    seed_context.insert(CONSTANTS_CONTEXT_KEY.to_string(), Value::Object(constants));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn constants(id: &str, values: Value) -> Constants {
+        Constants {
+            values: values.as_object().unwrap().clone(),
+            ..Constants::empty("cust1", id)
+        }
+    }
+
+    #[test]
+    fn test_case_and_seed_constants_take_precedence() {
+        let merged = merge_constants(
+            constants(CUSTOMER_CONSTANTS_ID, json!({"tenant_id": "t-1", "region": "eu"})),
+            constants("tc1", json!({"tenant_id": "t-2"})),
+        );
+        assert_eq!(Value::Object(merged.clone()), json!({"tenant_id": "t-2", "region": "eu"}));
+
+        let mut seed_context = json!({"user": "ayse", "constants": {"region": "us"}}).as_object().unwrap().clone();
+        seed_constants(&mut seed_context, merged.clone());
+        assert_eq!(Value::Object(seed_context), json!({"user": "ayse", "constants": {"tenant_id": "t-2", "region": "us"}}));
+
+        let mut overridden = json!({"constants": "none"}).as_object().unwrap().clone();
+        seed_constants(&mut overridden, merged);
+        assert_eq!(Value::Object(overridden), json!({"constants": "none"}));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::api::AppError;
+use crate::constant::model::{merge_constants, Constants, CUSTOMER_CONSTANTS_ID};
+use crate::persistence::repo::Table;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+pub struct ConstantOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct ConstantsTable();
+
+impl Table<Constants> for ConstantsTable {
+    fn table_name() -> String {
+        "constants".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Constants) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &Constants) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl ConstantOperations {
+    /// Constants that were never stored are empty.
+    pub async fn get(&self, customer_id: &str, id: &str) -> Result<Constants, AppError> {
+        ConstantsTable::get_item(self.client.clone(), customer_id.to_string(), id.to_string())
+            .await
+            .map(|constants| constants.unwrap_or_else(|| Constants::empty(customer_id, id)))
+    }
+
+    pub async fn put(&self, constants: Constants) -> Result<Constants, AppError> {
+        ConstantsTable::put_item(self.client.clone(), constants).await
+    }
+
+    pub async fn delete(&self, customer_id: &str, id: &str) -> Result<Option<Constants>, AppError> {
+        ConstantsTable::delete_item(self.client.clone(), customer_id.to_string(), id.to_string()).await
+    }
+
+    /// Customer-wide constants overridden by those of the test case.
+    pub async fn resolve(&self, customer_id: &str, test_case_id: &str) -> Result<Map<String, Value>, AppError> {
+        let customer_wide = self.get(customer_id, CUSTOMER_CONSTANTS_ID).await?;
+        let test_case = self.get(customer_id, test_case_id).await?;
+        Ok(merge_constants(customer_wide, test_case))
+    }
+}
@@ -0,0 +1,80 @@
+use crate::action::model::Action;
+use crate::api::{ApiResponse, AppError};
+use crate::curl::parser::{parse_curl_commands, to_har_spec};
+use crate::curl::render::render_curl;
+use crate::har_resolver::{append_to_test_case, HarImportOptions};
+use crate::persistence::repo::Repository;
+use crate::revision::history::record_before_change;
+use crate::run::execution::preview_request;
+use crate::validation::AppJson;
+use axum::extract::{Path, Query, State};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Appends the requests of the cURL commands as actions, imported like a HAR recording without
+/// responses: credentials move to auth providers and values are linked to earlier responses.
+pub async fn append_curl(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<AppendCurlPayload>,
+) -> Result<ApiResponse<Vec<Action>>, AppError> {
+    let requests = parse_curl_commands(&payload.commands).map_err(AppError::Validation)?;
+    let spec = to_har_spec(&requests).map_err(AppError::Validation)?;
+    let test_case = repository.test_cases()
+        .get("eren".to_string(), id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    record_before_change(&repository, &"eren".to_string(), &id, "curl import").await;
+    let result = append_to_test_case(
+        &repository,
+        &spec,
+        &test_case,
+        HarImportOptions {
+            excluded_path_parts: vec![],
+            auth_providers: payload.auth_providers,
+            collapse_polling: false,
+        },
+    ).await;
+    ApiResponse::from(result)
+}
+
+/// Renders the request the action sends as a cURL command, resolved with the context of the given
+/// run right before the action, or with an empty context when no run is given.
+pub async fn get_action_curl(
+    Path((test_case_id, action_id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    Query(params): Query<ActionCurlParams>,
+) -> Result<ApiResponse<ActionCurl>, AppError> {
+    let result = preview_request(
+        &repository,
+        &"eren".to_string(),
+        &test_case_id,
+        &action_id,
+        params.run_id.as_ref(),
+        params.include_secrets,
+    ).await
+        .map(|(request, headers)| ActionCurl { command: render_curl(&request, &headers) });
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct AppendCurlPayload {
+    /// One or more `curl ...` commands per item.
+    pub commands: Vec<String>,
+    /// Existing auth providers to link instead of creating new ones for their base URLs.
+    #[serde(default)]
+    pub auth_providers: Vec<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ActionCurlParams {
+    pub run_id: Option<String>,
+    /// Sends sensitive header values as they are instead of masked.
+    #[serde(default)]
+    pub include_secrets: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ActionCurl {
+    pub command: String,
+}
@@ -0,0 +1,333 @@
+use crate::http::HttpMethod;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use har::Spec;
+use serde_json::{json, Value};
+use std::io::Cursor;
+use std::str::FromStr;
+
+/// Options taking a value that do not change the request being made.
+const IGNORED_OPTIONS: [&str; 10] = [
+    "-o", "--output", "-m", "--max-time", "--connect-timeout", "-w", "--write-out", "--retry", "--cacert", "--proxy",
+];
+
+/// Flags that do not change the request being made.
+const IGNORED_FLAGS: [&str; 17] = [
+    "--compressed", "-s", "--silent", "-S", "--show-error", "-k", "--insecure", "-L", "--location",
+    "-v", "--verbose", "-i", "--include", "-f", "--fail", "--http1.1", "--http2",
+];
+
+/// Short options that take a value, which may also be written right after them as in `-XPOST`.
+const SHORT_OPTIONS_WITH_VALUE: [char; 11] = ['X', 'H', 'd', 'b', 'u', 'A', 'e', 'o', 'm', 'w', 'F'];
+
+/// A request as described by one cURL command.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CurlRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub cookies: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl CurlRequest {
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// HAR entry of the request without a recorded response, so it can go through the HAR import.
+    pub fn to_har_entry(&self) -> Value {
+        let pairs = |items: &[(String, String)]| items.iter()
+            .map(|(name, value)| json!({"name": name, "value": value}))
+            .collect::<Vec<Value>>();
+        let query_string: Vec<(String, String)> = self.url.split_once('?')
+            .map(|(_, query)| split_pairs(query.split('#').next().unwrap_or_default()))
+            .unwrap_or_default();
+        let mut request = json!({
+            "method": self.method, "url": self.url, "httpVersion": "HTTP/1.1",
+            "cookies": pairs(&self.cookies), "headers": pairs(&self.headers),
+            "queryString": pairs(&query_string), "headersSize": -1, "bodySize": -1,
+        });
+        if let Some(body) = &self.body {
+            let mime_type = self.content_type().unwrap_or("application/x-www-form-urlencoded").to_string();
+            request["postData"] = if mime_type.contains("form-urlencoded") {
+                json!({"mimeType": mime_type, "text": body, "params": pairs(&split_pairs(body))})
+            } else {
+                json!({"mimeType": mime_type, "text": body})
+            };
+        }
+        json!({
+            "startedDateTime": "1970-01-01T00:00:00.000Z", "time": 0, "request": request,
+            "response": {
+                "status": 0, "statusText": "", "httpVersion": "HTTP/1.1", "cookies": [], "headers": [],
+                "content": {"size": 0}, "redirectURL": "", "headersSize": -1, "bodySize": -1,
+            },
+            "cache": {}, "timings": {"send": 0, "wait": 0, "receive": 0},
+        })
+    }
+}
+
+/// HAR log of the requests in the given order.
+pub fn to_har_spec(requests: &[CurlRequest]) -> Result<Spec, String> {
+    let har = json!({"log": {
+        "version": "1.2",
+        "creator": {"name": "parroton-curl", "version": "1"},
+        "entries": requests.iter().map(CurlRequest::to_har_entry).collect::<Vec<Value>>(),
+    }});
+    har::from_reader(Cursor::new(har.to_string()))
+        .map(|har| har.log)
+        .map_err(|err| format!("Could not convert the curl commands: {}", err))
+}
+
+/// Parses every `curl` command in the given strings; a string may hold several commands, e.g. a
+/// copied shell history.
+pub fn parse_curl_commands(commands: &[String]) -> Result<Vec<CurlRequest>, String> {
+    let mut requests = vec![];
+    for command in commands {
+        let words = split_words(command)?;
+        let mut current: Option<Vec<String>> = None;
+        for word in words {
+            if word == "curl" {
+                if let Some(arguments) = current.replace(vec![]) {
+                    requests.push(parse_arguments(&arguments)?);
+                }
+            } else if let Some(arguments) = current.as_mut() {
+                arguments.push(word);
+            } else {
+                return Err(format!("Expected a curl command but found \"{}\"", word));
+            }
+        }
+        if let Some(arguments) = current {
+            requests.push(parse_arguments(&arguments)?);
+        }
+    }
+    if requests.is_empty() {
+        return Err("No curl command given!".to_string());
+    }
+    Ok(requests)
+}
+
+fn parse_arguments(arguments: &[String]) -> Result<CurlRequest, String> {
+    let mut method = None;
+    let mut url = None;
+    let mut headers = vec![];
+    let mut cookies = vec![];
+    let mut data: Vec<String> = vec![];
+    let mut json_body = false;
+    let mut data_in_query = false;
+    let mut words = arguments.iter();
+    while let Some(word) = words.next() {
+        let (option, attached) = split_short_option(word);
+        let mut value = || attached.clone()
+            .or_else(|| words.next().cloned())
+            .ok_or(format!("Option {} needs a value", option));
+        match option.as_str() {
+            "-X" | "--request" => method = Some(HttpMethod::from_str(&value()?)?.to_string()),
+            "--url" => url = Some(value()?),
+            "-H" | "--header" => {
+                let header = value()?;
+                let (name, header_value) = header.split_once(':')
+                    .ok_or(format!("Invalid header \"{}\"", header))?;
+                headers.push((name.trim().to_string(), header_value.trim().to_string()));
+            }
+            "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" | "--data-urlencode" => data.push(value()?),
+            "--json" => {
+                data.push(value()?);
+                json_body = true;
+            }
+            "-b" | "--cookie" => cookies.extend(value()?.split(';')
+                .filter_map(|cookie| cookie.split_once('='))
+                .map(|(name, cookie_value)| (name.trim().to_string(), cookie_value.trim().to_string()))),
+            "-u" | "--user" => headers.push(("Authorization".to_string(), format!("Basic {}", STANDARD.encode(value()?)))),
+            "-A" | "--user-agent" => headers.push(("User-Agent".to_string(), value()?)),
+            "-e" | "--referer" => headers.push(("Referer".to_string(), value()?)),
+            "-G" | "--get" => data_in_query = true,
+            "-F" | "--form" => return Err("Multipart form bodies (-F) cannot be imported".to_string()),
+            _ if IGNORED_OPTIONS.contains(&option.as_str()) => {
+                value()?;
+            }
+            _ if IGNORED_FLAGS.contains(&option.as_str()) => {}
+            _ if option.starts_with('-') && option.len() > 1 => return Err(format!("Unsupported curl option {}", option)),
+            _ => url = Some(word.clone()),
+        }
+    }
+    let mut url = url.ok_or("A curl command has no URL".to_string())?;
+    if json_body {
+        set_default_header(&mut headers, "Content-Type", "application/json");
+        set_default_header(&mut headers, "Accept", "application/json");
+    }
+    let body = (!data.is_empty()).then(|| data.join("&"));
+    let body = match body {
+        Some(query) if data_in_query => {
+            url = format!("{}{}{}", url, if url.contains('?') { "&" } else { "?" }, query);
+            None
+        }
+        body => body,
+    };
+    let default_method = if body.is_some() { "POST" } else { "GET" };
+    Ok(CurlRequest {
+        method: method.unwrap_or(default_method.to_string()),
+        url,
+        headers,
+        cookies,
+        body,
+    })
+}
+
+fn set_default_header(headers: &mut Vec<(String, String)>, name: &str, value: &str) {
+    if !headers.iter().any(|(existing, _)| existing.eq_ignore_ascii_case(name)) {
+        headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// Separates a value written right after a short option, `-XPOST` being `-X` and `POST`.
+fn split_short_option(word: &str) -> (String, Option<String>) {
+    let mut chars = word.chars();
+    match (chars.next(), chars.next()) {
+        (Some('-'), Some(letter)) if SHORT_OPTIONS_WITH_VALUE.contains(&letter) && word.len() > 2 => {
+            (format!("-{}", letter), Some(word[2..].to_string()))
+        }
+        _ => (word.to_string(), None),
+    }
+}
+
+/// `name=value` pairs of a query string or form body, left encoded.
+fn split_pairs(encoded: &str) -> Vec<(String, String)> {
+    encoded.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+/// Splits a command line into words the way a POSIX shell quotes them, also accepting the `^`
+/// line continuations of commands copied for the Windows command prompt.
+fn split_words(command: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let quoted = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => quoted.push(c),
+                        None => return Err("Unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let quoted = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) if ['"', '\\', '$', '`'].contains(&escaped) => quoted.push(escaped),
+                            Some('\n') => {}
+                            Some(other) => {
+                                quoted.push('\\');
+                                quoted.push(other);
+                            }
+                            None => return Err("Unterminated double quote".to_string()),
+                        },
+                        Some(c) => quoted.push(c),
+                        None => return Err("Unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' | '^' if matches!(chars.peek(), Some('\n') | Some('\r')) => {
+                while matches!(chars.peek(), Some('\n') | Some('\r')) {
+                    chars.next();
+                }
+            }
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    word.get_or_insert_with(String::new).push(escaped);
+                }
+            }
+            c if c.is_whitespace() => {
+                if let Some(finished) = word.take() {
+                    words.push(finished);
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_copied_commands() {
+        let commands = vec![
+            "curl 'https://abc.xyz/api/orders?page=1' \\\n  -H 'Authorization: Bearer abc' \\\n  -H \"Content-Type: application/json\" \\\n  --data-raw '{\"sku\": \"A-1\"}' --compressed".to_string(),
+            "curl -XDELETE https://abc.xyz/api/orders/42 -u ayse:secret curl -G https://abc.xyz/search -d q=shoes -d page=2 -b 'session=s1; theme=dark'".to_string(),
+        ];
+
+        let requests = parse_curl_commands(&commands).unwrap();
+
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0], CurlRequest {
+            method: "POST".to_string(),
+            url: "https://abc.xyz/api/orders?page=1".to_string(),
+            headers: vec![
+                ("Authorization".to_string(), "Bearer abc".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ],
+            cookies: vec![],
+            body: Some("{\"sku\": \"A-1\"}".to_string()),
+        });
+        assert_eq!(requests[1].method, "DELETE");
+        assert_eq!(requests[1].headers, vec![("Authorization".to_string(), "Basic YXlzZTpzZWNyZXQ=".to_string())]);
+        assert_eq!(requests[2].method, "GET");
+        assert_eq!(requests[2].url, "https://abc.xyz/search?q=shoes&page=2");
+        assert_eq!(requests[2].body, None);
+        assert_eq!(requests[2].cookies, vec![
+            ("session".to_string(), "s1".to_string()),
+            ("theme".to_string(), "dark".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn reject_invalid_commands() {
+        assert!(parse_curl_commands(&["wget https://abc.xyz".to_string()]).is_err());
+        assert!(parse_curl_commands(&["curl -H 'X-A: 1'".to_string()]).is_err());
+        assert!(parse_curl_commands(&["curl 'https://abc.xyz".to_string()]).is_err());
+        assert!(parse_curl_commands(&["curl --trace x https://abc.xyz".to_string()]).is_err());
+        assert!(parse_curl_commands(&[]).is_err());
+    }
+
+    #[test]
+    fn reject_unsupported_methods() {
+        assert_eq!(parse_curl_commands(&["curl -X HEAD https://abc.xyz".to_string()]), Err("Invalid HTTP method: HEAD".to_string()));
+        assert!(parse_curl_commands(&["curl -XPOTS https://abc.xyz".to_string()]).is_err());
+        assert_eq!(parse_curl_commands(&["curl --request patch https://abc.xyz".to_string()]).unwrap()[0].method, "PATCH");
+    }
+
+    #[test]
+    fn form_body_becomes_har_params() {
+        let request = parse_curl_commands(&["curl https://abc.xyz/login?next=%2Fhome -d 'user=ayse&scope=read%20write'".to_string()])
+            .unwrap()
+            .remove(0);
+
+        let entry = request.to_har_entry();
+        assert!(to_har_spec(std::slice::from_ref(&request)).is_ok());
+
+        assert_eq!(entry["request"]["queryString"], json!([{"name": "next", "value": "%2Fhome"}]));
+        assert_eq!(entry["request"]["postData"]["mimeType"], json!("application/x-www-form-urlencoded"));
+        assert_eq!(entry["request"]["postData"]["params"], json!([
+            {"name": "user", "value": "ayse"},
+            {"name": "scope", "value": "read%20write"},
+        ]));
+    }
+}
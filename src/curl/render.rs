@@ -0,0 +1,78 @@
+use crate::http::{encode_form_body, HttpMethod, HttpRequest};
+
+/// Renders the request as a cURL command, one option per line, sending the body the way
+/// `ApiClient` does for its content type. `headers` are given separately so they can be masked.
+pub fn render_curl(request: &HttpRequest, headers: &[(String, String)]) -> String {
+    let mut lines = vec![];
+    lines.push(match request.endpoint.method {
+        HttpMethod::GET => format!("curl {}", shell_quote(&request.endpoint.to_url())),
+        _ => format!("curl -X {} {}", request.endpoint.method.to_string(), shell_quote(&request.endpoint.to_url())),
+    });
+    lines.extend(headers.iter().map(|(name, value)| format!("-H {}", shell_quote(&format!("{}: {}", name, value)))));
+    if let Some(body) = &request.req_body.value {
+        let text = if request.content_type.contains("application/x-www-form-urlencoded") {
+            encode_form_body(body)
+        } else if request.content_type.contains("json") {
+            body.to_string()
+        } else {
+            body.as_str().map_or_else(|| body.to_string(), |text| text.to_string())
+        };
+        lines.push(format!("--data-raw {}", shell_quote(&text)));
+    }
+    lines.join(" \\\n  ")
+}
+
+/// Single-quotes the value for POSIX shells; embedded quotes are closed, escaped and reopened.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curl::parser::parse_curl_commands;
+    use crate::http::{Endpoint, ReqBody, ReqParam};
+    use serde_json::json;
+
+    #[test]
+    fn rendered_command_parses_back() {
+        let request = HttpRequest::new(
+            Endpoint::new(
+                HttpMethod::POST,
+                "https://abc.xyz/api/orders".to_string(),
+                vec![],
+                vec![ReqParam::new("note".to_string(), "it's mine".to_string())],
+                vec![],
+            ),
+            ReqBody::new(json!({"sku": "A-1", "label": "Ayşe's"})),
+            "application/json".to_string(),
+        );
+        let headers = vec![
+            ("Authorization".to_string(), "****".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ];
+
+        let command = render_curl(&request, &headers);
+        let parsed = parse_curl_commands(std::slice::from_ref(&command)).unwrap().remove(0);
+
+        assert!(command.starts_with("curl -X POST 'https://abc.xyz/api/orders?note=it%27s%20mine' \\\n  -H 'Authorization: ****'"));
+        assert_eq!(parsed.method, "POST");
+        assert_eq!(parsed.url, request.endpoint.to_url());
+        assert_eq!(parsed.headers, headers);
+        assert_eq!(parsed.body, Some(json!({"sku": "A-1", "label": "Ayşe's"}).to_string()));
+    }
+
+    #[test]
+    fn form_body_is_url_encoded() {
+        let request = HttpRequest::new(
+            Endpoint::new(HttpMethod::PUT, "https://abc.xyz/login".to_string(), vec![], vec![], vec![]),
+            ReqBody::new(json!({"user": "ayse", "scope": ["read", "write all"]})),
+            "application/x-www-form-urlencoded".to_string(),
+        );
+
+        assert_eq!(
+            render_curl(&request, &[]),
+            "curl -X PUT 'https://abc.xyz/login' \\\n  --data-raw 'user=ayse&scope=read&scope=write%20all'",
+        );
+    }
+}
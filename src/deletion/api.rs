@@ -0,0 +1,12 @@
+use crate::api::{ApiResponse, AppError};
+use crate::deletion::model::DeletionJob;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn get_deletion_job(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<DeletionJob>, AppError> {
+    let result = repository.deletion_jobs().get(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
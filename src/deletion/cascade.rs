@@ -0,0 +1,225 @@
+use crate::api::AppError;
+use crate::artifact::store::{artifact_store, test_case_prefix};
+use crate::deletion::model::{DeletionJob, DeletionJobStatus};
+use crate::persistence::repo::Repository;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info};
+
+/// Deletes the test case item right away and cleans up every child table in the
+/// background. Progress is tracked on the returned job so callers can poll it.
+pub async fn delete_test_case(
+    repository: Arc<Repository>,
+    customer_id: String,
+    test_case_id: String,
+) -> Result<DeletionJob, AppError> {
+    repository.test_cases()
+        .get(customer_id.clone(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let deletion_job = repository.deletion_jobs()
+        .create(DeletionJob::builder()
+            .customer_id(customer_id.clone())
+            .test_case_id(test_case_id.clone())
+            .status(DeletionJobStatus::InProgress)
+            .build())
+        .await?;
+    repository.test_cases().delete(&customer_id, &test_case_id).await?;
+
+    let job_id = deletion_job.id.clone();
+    tokio::spawn(async move {
+        let mut deleted_counts = HashMap::new();
+        let result = delete_children(&repository, &customer_id, &test_case_id, &mut deleted_counts).await;
+        let (status, error) = match result {
+            Ok(_) => {
+                info!("deletion job {} completed: {:?}", job_id, deleted_counts);
+                (DeletionJobStatus::Completed, None)
+            }
+            Err(err) => {
+                error!("deletion job {} failed: {:?}", job_id, err);
+                (DeletionJobStatus::Failed, Some(format!("{:?}", err)))
+            }
+        };
+        if let Err(err) = repository.deletion_jobs()
+            .finish(&customer_id, &job_id, status, deleted_counts, error)
+            .await {
+            error!("could not finish deletion job {}: {:?}", job_id, err);
+        }
+    });
+    Ok(deletion_job)
+}
+
+async fn delete_children(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    deleted_counts: &mut HashMap<String, usize>,
+) -> Result<(), AppError> {
+    let actions = repository.actions().delete_all(customer_id.clone(), test_case_id.clone()).await?;
+    deleted_counts.insert("actions".to_string(), actions.len());
+
+    let parameters = repository.parameters().delete_all(customer_id.clone(), test_case_id.clone()).await?;
+    deleted_counts.insert("parameters".to_string(), parameters.len());
+
+    let runs = repository.runs().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("runs".to_string(), runs.len());
+
+    let mut action_executions = 0;
+    for run in runs {
+        action_executions += repository.action_executions()
+            .delete_all(customer_id, test_case_id, &run.id)
+            .await?
+            .len();
+    }
+    deleted_counts.insert("action_executions".to_string(), action_executions);
+
+    let assertions = repository.assertions().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("assertions".to_string(), assertions.len());
+
+    let revisions = repository.revisions().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("revisions".to_string(), revisions.len());
+
+    let action_groups = repository.action_groups().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("action_groups".to_string(), action_groups.len());
+
+    let snapshots = repository.snapshots().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("snapshots".to_string(), snapshots.len());
+
+    let import_report = repository.import_reports().delete(customer_id, test_case_id).await?;
+    deleted_counts.insert("import_reports".to_string(), import_report.iter().count());
+
+    let header_rules = repository.header_rules().delete_all_of_test_case(customer_id, test_case_id).await?;
+    deleted_counts.insert("header_rules".to_string(), header_rules.len());
+
+    let constants = repository.constants().delete(customer_id, test_case_id).await?;
+    deleted_counts.insert("constants".to_string(), constants.iter().count());
+
+    let memberships = repository.memberships().delete_all_of_test_case(customer_id, test_case_id).await?;
+    deleted_counts.insert("memberships".to_string(), memberships.len());
+
+    let run_locks = repository.run_locks().delete_all(customer_id, test_case_id).await?;
+    deleted_counts.insert("run_locks".to_string(), run_locks.len());
+
+    if let Some(store) = artifact_store() {
+        let artifacts = store.delete_prefix(&test_case_prefix(customer_id, test_case_id)).await?;
+        deleted_counts.insert("artifacts".to_string(), artifacts);
+    }
+
+    repository.auth_providers().unlink_test_case(customer_id, test_case_id).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::access::model::{Membership, Role};
+    use crate::action::model::Action;
+    use crate::action_execution::model::ActionExecution;
+    use crate::case::model::TestCase;
+    use crate::constant::model::Constants;
+    use crate::header_rule::model::HeaderRule;
+    use crate::parameter::model::{Parameter, ParameterLocation, ParameterType};
+    use crate::persistence::repo::{current_timestamp, init_logger};
+    use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+    use crate::run::model::{Run, RunStatus};
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn deletes_children_of_every_action_and_run() {
+        init_logger();
+        let repository = Repository::new().await;
+        let test_case = repository.test_cases()
+            .create(TestCase::builder()
+                .customer_id("cust1".to_string())
+                .name("Cascade".to_string())
+                .description("desc".to_string())
+                .build())
+            .await;
+        let (customer_id, test_case_id) = (test_case.customer_id.clone(), test_case.id.clone());
+        let actions: Vec<Action> = ["login", "orders"].iter().enumerate()
+            .map(|(order, name)| Action { test_case_id: test_case_id.clone(), ..test_action(name, order, "get", "/") })
+            .collect();
+        let permit = guard_test_case_write(&repository, &customer_id, &test_case_id, TestCaseWrite { actions: &actions, ..Default::default() })
+            .await
+            .unwrap();
+        repository.actions().batch_create(actions.clone(), permit).await;
+        repository.parameters()
+            .batch_create(actions.iter()
+                .map(|action| Parameter::builder()
+                    .customer_id(customer_id.clone())
+                    .test_case_id(test_case_id.clone())
+                    .action_id(action.id.clone())
+                    .parameter_type(ParameterType::Input)
+                    .location(ParameterLocation::Header("x-trace".to_string()))
+                    .value(Value::Null)
+                    .build())
+                .collect())
+            .await;
+        for run_id in ["r1", "r2"] {
+            repository.runs()
+                .create(Run::builder()
+                    .customer_id(customer_id.clone())
+                    .test_case_id(test_case_id.clone())
+                    .id(run_id.to_string())
+                    .status(RunStatus::Finished)
+                    .started_at(current_timestamp())
+                    .build())
+                .await;
+            repository.action_executions()
+                .create(ActionExecution::builder()
+                    .run_id(run_id.to_string())
+                    .customer_id(customer_id.clone())
+                    .test_case_id(test_case_id.clone())
+                    .action_id(actions[0].id.clone())
+                    .status_code(200)
+                    .query_params(vec![])
+                    .build())
+                .await;
+        }
+        for scope in [Some(test_case_id.clone()), None] {
+            repository.header_rules()
+                .put(HeaderRule::builder()
+                    .customer_id(customer_id.clone())
+                    .maybe_test_case_id(scope.clone())
+                    .name("X-Tenant".to_string())
+                    .value("t1".to_string())
+                    .build())
+                .await
+                .unwrap();
+            repository.memberships()
+                .put(Membership::builder()
+                    .customer_id(customer_id.clone())
+                    .id(Membership::id_of("ayse", scope.as_deref()))
+                    .user_id("ayse".to_string())
+                    .maybe_test_case_id(scope)
+                    .role(Role::Editor)
+                    .build())
+                .await
+                .unwrap();
+        }
+        repository.constants()
+            .put(Constants { values: json!({"tenant": "t1"}).as_object().unwrap().clone(), ..Constants::empty(&customer_id, &test_case_id) })
+            .await
+            .unwrap();
+        assert!(repository.run_locks().acquire(&customer_id, &test_case_id, "r2").await.unwrap());
+
+        let mut deleted_counts = HashMap::new();
+        delete_children(&repository, &customer_id, &test_case_id, &mut deleted_counts).await.unwrap();
+
+        assert_eq!(deleted_counts.get("actions"), Some(&2));
+        assert_eq!(deleted_counts.get("parameters"), Some(&2));
+        assert_eq!(deleted_counts.get("runs"), Some(&2));
+        assert_eq!(deleted_counts.get("action_executions"), Some(&2));
+        assert_eq!(repository.parameters().count(&customer_id, &test_case_id).await.unwrap(), 0);
+        assert!(repository.action_executions().list_with_bodies(&customer_id, &test_case_id, &"r1".to_string()).await.unwrap().is_empty());
+        assert_eq!(deleted_counts.get("header_rules"), Some(&1));
+        assert_eq!(deleted_counts.get("constants"), Some(&1));
+        assert_eq!(deleted_counts.get("memberships"), Some(&1));
+        assert_eq!(deleted_counts.get("run_locks"), Some(&1));
+        assert!(repository.header_rules().list_all(&customer_id).await.unwrap().iter().all(|rule| rule.test_case_id.is_none()));
+        assert!(repository.memberships().list_all(&customer_id).await.unwrap().iter().all(|membership| membership.test_case_id.is_none()));
+        assert!(repository.constants().get(&customer_id, &test_case_id).await.unwrap().values.is_empty());
+        assert!(repository.run_locks().acquire(&customer_id, &test_case_id, "r3").await.unwrap());
+    }
+}
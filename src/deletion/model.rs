@@ -0,0 +1 @@
+pub use parroton_core::deletion::*;
@@ -0,0 +1,68 @@
+use crate::api::AppError;
+use crate::deletion::model::{DeletionJob, DeletionJobStatus};
+use crate::persistence::repo::{current_timestamp, Table};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use serde_dynamo::to_attribute_value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct DeletionJobOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct DeletionJobsTable();
+
+impl Table<DeletionJob> for DeletionJobsTable {
+    fn table_name() -> String {
+        "deletion_jobs".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &DeletionJob) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &DeletionJob) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl DeletionJobOperations {
+    pub async fn create(&self, deletion_job: DeletionJob) -> Result<DeletionJob, AppError> {
+        DeletionJobsTable::put_item(self.client.clone(), deletion_job).await
+    }
+
+    pub async fn get(&self, customer_id: &String, id: &String) -> Result<Option<DeletionJob>, AppError> {
+        DeletionJobsTable::get_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    pub async fn finish(
+        &self,
+        customer_id: &String,
+        id: &String,
+        status: DeletionJobStatus,
+        deleted_counts: HashMap<String, usize>,
+        error: Option<String>,
+    ) -> Result<DeletionJob, AppError> {
+        DeletionJobsTable::update_partial(customer_id.clone(), id.clone(),
+                                          self.client.clone().update_item()
+                                              .expression_attribute_names("#s", "status")
+                                              .expression_attribute_names("#dc", "deleted_counts")
+                                              .expression_attribute_names("#e", "error")
+                                              .expression_attribute_names("#fa", "finished_at")
+                                              .expression_attribute_values(":s", to_attribute_value(status).unwrap())
+                                              .expression_attribute_values(":dc", to_attribute_value(deleted_counts).unwrap())
+                                              .expression_attribute_values(":e", to_attribute_value(error).unwrap())
+                                              .expression_attribute_values(":fa", AttributeValue::N(current_timestamp().to_string()))
+                                              .update_expression("SET #s = :s, #dc = :dc, #e = :e, #fa = :fa"))
+            .await
+    }
+}
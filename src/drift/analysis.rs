@@ -0,0 +1,163 @@
+use crate::action::model::ActionType;
+use crate::api::AppError;
+use crate::drift::model::{ActionDrift, DriftReport, FieldDrift, JsonType};
+use crate::json_path::utils::child_path;
+use crate::parameter::model::{Parameter, ParameterIn};
+use crate::persistence::repo::Repository;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Compares the structure of each live response of the run against the response recorded on import.
+pub async fn analyze_run(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    run_id: &String,
+) -> Result<DriftReport, AppError> {
+    repository.runs()
+        .get(customer_id, test_case_id, run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let mut actions = repository.actions()
+        .list_all(customer_id.clone(), test_case_id.clone())
+        .await?;
+    actions.sort();
+    let executions = repository.action_executions()
+        .list_with_bodies(customer_id, test_case_id, run_id)
+        .await?;
+    let mut action_drifts = vec![];
+    for action in actions.iter().filter(|action| action.action_type == ActionType::Http) {
+        let Some(execution) = executions.iter()
+            .filter(|execution| execution.action_id == action.id)
+            .max_by_key(|execution| execution.started_at) else {
+            continue;
+        };
+        let Some(response_body) = &execution.response_body else {
+            continue;
+        };
+        let outputs = repository.parameters()
+            .list_all_outputs_of_action(customer_id.clone(), test_case_id.clone(), action.id.clone())
+            .await?;
+        if outputs.is_empty() {
+            continue;
+        }
+        action_drifts.push(ActionDrift {
+            action_id: action.id.clone(),
+            action_name: action.name.clone(),
+            execution_id: execution.id.clone(),
+            drifts: compare_structures(&recorded_structure(&outputs), &live_structure(response_body)),
+        });
+    }
+    Ok(DriftReport {
+        run_id: run_id.clone(),
+        test_case_id: test_case_id.clone(),
+        drifted: action_drifts.iter().any(|action_drift| !action_drift.drifts.is_empty()),
+        actions: action_drifts,
+    })
+}
+
+pub fn recorded_structure(outputs: &[Parameter]) -> BTreeMap<String, JsonType> {
+    let index_regex = array_index_regex();
+    outputs.iter()
+        .filter(|parameter| parameter.get_parameter_in() == ParameterIn::Body)
+        .map(|parameter| (index_regex.replace_all(&parameter.get_path(), "[]").to_string(), json_type(&parameter.value)))
+        .collect()
+}
+
+pub fn live_structure(value: &Value) -> BTreeMap<String, JsonType> {
+    let mut structure = BTreeMap::new();
+    collect_leaves(value, "$".to_string(), &mut structure);
+    structure
+}
+
+/// Null on either side is not reported as a type change since recordings often hold empty values.
+pub fn compare_structures(recorded: &BTreeMap<String, JsonType>, live: &BTreeMap<String, JsonType>) -> Vec<FieldDrift> {
+    let mut drifts = vec![];
+    for (path, recorded_type) in recorded {
+        match live.get(path) {
+            None => drifts.push(FieldDrift::Removed { path: path.clone(), recorded: *recorded_type }),
+            Some(live_type) if live_type != recorded_type
+                && *live_type != JsonType::Null
+                && *recorded_type != JsonType::Null => {
+                drifts.push(FieldDrift::TypeChanged { path: path.clone(), recorded: *recorded_type, live: *live_type });
+            }
+            Some(_) => {}
+        }
+    }
+    for (path, live_type) in live {
+        if !recorded.contains_key(path) {
+            drifts.push(FieldDrift::Added { path: path.clone(), live: *live_type });
+        }
+    }
+    drifts
+}
+
+fn collect_leaves(value: &Value, path: String, structure: &mut BTreeMap<String, JsonType>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            map.iter().for_each(|(key, val)| collect_leaves(val, child_path(&path, key), structure));
+        }
+        Value::Array(items) if !items.is_empty() => {
+            items.iter().for_each(|item| collect_leaves(item, format!("{}[]", path), structure));
+        }
+        _ => {
+            structure.insert(path, json_type(value));
+        }
+    }
+}
+
+fn json_type(value: &Value) -> JsonType {
+    match value {
+        Value::Null => JsonType::Null,
+        Value::Bool(_) => JsonType::Bool,
+        Value::Number(_) => JsonType::Number,
+        Value::String(_) => JsonType::String,
+        Value::Array(_) => JsonType::Array,
+        Value::Object(_) => JsonType::Object,
+    }
+}
+
+pub(crate) fn array_index_regex() -> Regex {
+    Regex::new(r"\[\d+\]").unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use serde_json::json;
+
+    #[test]
+    fn detect_structure_drift() {
+        let output = |path: &str, value: Value| Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id("a1".to_string())
+            .parameter_type(ParameterType::Output)
+            .location(ParameterLocation::Body(path.to_string()))
+            .value(value)
+            .build();
+        let recorded = recorded_structure(&[
+            output("$.id", json!(1)),
+            output("$.name", json!("eren")),
+            output("$.items[0].sku", json!("x1")),
+            output("$.items[1].sku", json!("x2")),
+            output("$.deletedAt", json!(null)),
+            output("$.legacy", json!(true)),
+        ]);
+        let live = live_structure(&json!({
+            "id": "1",
+            "name": "eren",
+            "items": [{"sku": "x1", "price": 10}],
+            "deletedAt": "2024-01-01"
+        }));
+
+        assert_eq!(compare_structures(&recorded, &live), vec![
+            FieldDrift::TypeChanged { path: "$.id".to_string(), recorded: JsonType::Number, live: JsonType::String },
+            FieldDrift::Removed { path: "$.legacy".to_string(), recorded: JsonType::Bool },
+            FieldDrift::Added { path: "$.items[].price".to_string(), live: JsonType::Number },
+        ]);
+        assert!(compare_structures(&recorded, &recorded).is_empty());
+    }
+}
@@ -0,0 +1,13 @@
+use crate::api::{ApiResponse, AppError};
+use crate::drift::analysis::analyze_run;
+use crate::drift::model::DriftReport;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn get_drift_report(
+    Path((test_case_id, run_id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<DriftReport>, AppError> {
+    let result = analyze_run(&repository, &"eren".to_string(), &test_case_id, &run_id).await;
+    ApiResponse::from(result)
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, ToSchema)]
+pub enum JsonType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub enum FieldDrift {
+    Added { path: String, live: JsonType },
+    Removed { path: String, recorded: JsonType },
+    TypeChanged { path: String, recorded: JsonType, live: JsonType },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct ActionDrift {
+    pub action_id: String,
+    pub action_name: String,
+    pub execution_id: String,
+    pub drifts: Vec<FieldDrift>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct DriftReport {
+    pub run_id: String,
+    pub test_case_id: String,
+    pub drifted: bool,
+    pub actions: Vec<ActionDrift>,
+}
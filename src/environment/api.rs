@@ -0,0 +1,84 @@
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::environment::model::{Environment, UrlRewrite};
+use crate::persistence::repo::Repository;
+use crate::validation::{ValidJson, Validate, check_not_blank};
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn create_environment(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<EnvironmentPayload>,
+) -> Result<ApiResponse<Environment>, AppError> {
+    let environment = payload.into_environment(uuid::Uuid::new_v4().to_string());
+    let result = repository.environments().put(environment).await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_environment(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<EnvironmentPayload>,
+) -> Result<ApiResponse<Environment>, AppError> {
+    repository.environments()
+        .get(&"eren".to_string(), &id)
+        .await?
+        .ok_or(AppError::NotFound("Environment not found!".to_string()))?;
+    let result = repository.environments().put(payload.into_environment(id)).await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_environment(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Environment>, AppError> {
+    let result = repository.environments().get(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn list_environments(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<Environment>>, AppError> {
+    let result = repository.environments().list_all(&"eren".to_string()).await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_environment(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Environment>, AppError> {
+    let result = repository.environments().delete(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct EnvironmentPayload {
+    pub name: String,
+    #[serde(default)]
+    pub url_rewrites: Vec<UrlRewrite>,
+}
+
+impl EnvironmentPayload {
+    fn into_environment(self, id: String) -> Environment {
+        Environment::builder()
+            .customer_id("eren".to_string())
+            .id(id)
+            .name(self.name)
+            .url_rewrites(self.url_rewrites)
+            .build()
+    }
+}
+
+impl Validate for EnvironmentPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = check_not_blank("name", &self.name).into_iter().collect();
+        for (i, rewrite) in self.url_rewrites.iter().enumerate() {
+            for (field, url) in [("from", &rewrite.from), ("to", &rewrite.to)] {
+                if !url.contains("://") {
+                    errors.push(FieldError::new(format!("url_rewrites[{}].{}", i, field), "must be an absolute URL"));
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,25 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A target deployment a recording can be replayed against, such as another region.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Environment {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub url_rewrites: Vec<UrlRewrite>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+/// Replaces the `from` prefix of a request URL with `to`, e.g. `https://api.prod.example.com`
+/// with `https://api.eu.example.com`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+pub struct UrlRewrite {
+    pub from: String,
+    pub to: String,
+}
@@ -0,0 +1,40 @@
+use crate::environment::model::UrlRewrite;
+
+/// Rewrites the URL with the most specific matching rule. A rule matches when its `from` is the
+/// whole URL or a prefix ending at a path, query or fragment boundary, so `https://api.example.com`
+/// does not match `https://api.example.com.evil.io`.
+pub fn remap_url(url: &str, rewrites: &[UrlRewrite]) -> String {
+    rewrites.iter()
+        .map(|rewrite| (rewrite.from.trim().trim_end_matches('/'), rewrite.to.trim().trim_end_matches('/')))
+        .filter(|(from, _)| !from.is_empty() && matches_prefix(url, from))
+        .max_by_key(|(from, _)| from.len())
+        .map(|(from, to)| format!("{}{}", to, &url[from.len()..]))
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn matches_prefix(url: &str, prefix: &str) -> bool {
+    url.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?', '#']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(from: &str, to: &str) -> UrlRewrite {
+        UrlRewrite { from: from.to_string(), to: to.to_string() }
+    }
+
+    #[test]
+    fn most_specific_rewrite_wins() {
+        let rewrites = vec![
+            rewrite("https://api.prod.example.com/", "https://api.eu.example.com"),
+            rewrite("https://api.prod.example.com/v1", "https://api.eu.example.com/legacy/v1/"),
+        ];
+        assert_eq!(remap_url("https://api.prod.example.com/users?id=1", &rewrites), "https://api.eu.example.com/users?id=1");
+        assert_eq!(remap_url("https://api.prod.example.com/v1/users", &rewrites), "https://api.eu.example.com/legacy/v1/users");
+        assert_eq!(remap_url("https://api.prod.example.com", &rewrites), "https://api.eu.example.com");
+        assert_eq!(remap_url("https://api.prod.example.com.evil.io/users", &rewrites), "https://api.prod.example.com.evil.io/users");
+        assert_eq!(remap_url("https://api.prod.example.com/v10", &rewrites), "https://api.eu.example.com/v10");
+    }
+}
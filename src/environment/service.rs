@@ -0,0 +1,52 @@
+use crate::api::AppError;
+use crate::environment::model::Environment;
+use crate::persistence::repo::Table;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct EnvironmentOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct EnvironmentsTable();
+
+impl Table<Environment> for EnvironmentsTable {
+    fn table_name() -> String {
+        "environments".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Environment) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &Environment) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl EnvironmentOperations {
+    pub async fn put(&self, environment: Environment) -> Result<Environment, AppError> {
+        EnvironmentsTable::put_item(self.client.clone(), environment).await
+    }
+
+    pub async fn get(&self, customer_id: &String, id: &String) -> Result<Option<Environment>, AppError> {
+        EnvironmentsTable::get_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    pub async fn list_all(&self, customer_id: &String) -> Result<Vec<Environment>, AppError> {
+        EnvironmentsTable::list_all_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, id: &String) -> Result<Option<Environment>, AppError> {
+        EnvironmentsTable::delete_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+}
@@ -0,0 +1,154 @@
+use crate::action::model::Action;
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, ComparisonType};
+use crate::graph::model::{GraphEdge, GraphNode, TestCaseGraph};
+use crate::lint::analysis::{expressions_of, parse_reference};
+use crate::parameter::model::{Parameter, ParameterType};
+use crate::persistence::repo::Repository;
+use std::collections::HashMap;
+
+/// Dependency graph of a test case, derived from the expressions wiring its actions together.
+pub async fn build_test_case_graph(repository: &Repository, customer_id: &String, test_case_id: &String) -> Result<TestCaseGraph, AppError> {
+    repository.test_cases()
+        .get(customer_id.clone(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let mut actions = repository.actions().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    actions.sort();
+    let parameters = repository.parameters().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let assertions = repository.assertions().list_all(customer_id, test_case_id).await?;
+    Ok(build_graph(test_case_id, &actions, &parameters, &assertions))
+}
+
+/// References to unknown actions are left out, the lint report covers those.
+pub fn build_graph(test_case_id: &str, actions: &[Action], parameters: &[Parameter], assertions: &[Assertion]) -> TestCaseGraph {
+    let ids_by_name: HashMap<&str, &str> = actions.iter()
+        .map(|action| (action.name.as_str(), action.id.as_str()))
+        .collect();
+    let edge = |expression: &str| {
+        let reference = parse_reference(expression)?;
+        let from_action_id = ids_by_name.get(reference.action_name)?;
+        Some(GraphEdge {
+            from_action_id: from_action_id.to_string(),
+            to_action_id: None,
+            parameter_id: None,
+            assertion_id: None,
+            section: reference.section.to_string(),
+            path: reference.raw_path,
+        })
+    };
+    let mut edges = vec![];
+    for parameter in parameters.iter().filter(|parameter| parameter.parameter_type == ParameterType::Input) {
+        let Some(expression) = &parameter.value_expression else {
+            continue;
+        };
+        if let Some(found) = edge(&expression.value) {
+            edges.push(GraphEdge {
+                to_action_id: Some(parameter.action_id.clone()),
+                parameter_id: Some(parameter.id.clone()),
+                ..found
+            });
+        }
+    }
+    for assertion in assertions {
+        if assertion.comparison_type == ComparisonType::MatchesSnapshot {
+            if let Some(action_id) = assertion.snapshot_action_id.as_ref().filter(|id| actions.iter().any(|action| &&action.id == id)) {
+                edges.push(GraphEdge {
+                    from_action_id: action_id.clone(),
+                    to_action_id: None,
+                    parameter_id: None,
+                    assertion_id: Some(assertion.id.clone()),
+                    section: "output".to_string(),
+                    path: "$".to_string(),
+                });
+            }
+            continue;
+        }
+        edges.extend(expressions_of(&assertion.left).into_iter()
+            .chain(expressions_of(&assertion.right))
+            .filter_map(|expression| edge(expression))
+            .map(|found| GraphEdge { assertion_id: Some(assertion.id.clone()), ..found }));
+    }
+    let nodes = actions.iter()
+        .map(|action| {
+            let mut depends_on: Vec<String> = vec![];
+            edges.iter()
+                .filter(|edge| edge.to_action_id.as_ref() == Some(&action.id) && edge.from_action_id != action.id)
+                .for_each(|edge| {
+                    if !depends_on.contains(&edge.from_action_id) {
+                        depends_on.push(edge.from_action_id.clone());
+                    }
+                });
+            GraphNode {
+                action_id: action.id.clone(),
+                name: action.name.clone(),
+                order: action.order,
+                action_type: action.action_type.clone(),
+                depends_on,
+            }
+        })
+        .collect();
+    TestCaseGraph { test_case_id: test_case_id.to_string(), nodes, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::model::AssertionItem;
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::ParameterLocation;
+    use serde_json::json;
+
+    fn input(id: &str, action_id: &str, expression: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action_id.to_string())
+            .id(id.to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Query("q".to_string()))
+            .value(json!("x"))
+            .value_expression(Expression { value: expression.to_string() })
+            .build()
+    }
+
+    #[test]
+    fn build_edges_from_parameters_and_assertions() {
+        let actions = vec![
+            test_action("login_0", 0, "GET", "https://abc.xyz"),
+            test_action("search_1", 1, "GET", "https://abc.xyz"),
+            test_action("open_2", 2, "GET", "https://abc.xyz"),
+        ];
+        let parameters = vec![
+            input("p1", "search_1_id", "$.login_0.output.token"),
+            input("p2", "open_2_id", "$.search_1.output.items[0].id"),
+            input("p3", "open_2_id", "$.login_0.output.token"),
+            input("p4", "open_2_id", "$.missing.output.id"),
+        ];
+        let assertion = Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("a1".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.open_2.output.status".to_string() }))
+            .right(AssertionItem::from_value(json!("OPEN")))
+            .comparison_type(ComparisonType::EqualTo)
+            .build();
+
+        let graph = build_graph("tc1", &actions, &parameters, &[assertion]);
+
+        let depends_on: Vec<(&str, Vec<String>)> = graph.nodes.iter()
+            .map(|node| (node.name.as_str(), node.depends_on.clone()))
+            .collect();
+        assert_eq!(depends_on, vec![
+            ("login_0", vec![]),
+            ("search_1", vec!["login_0_id".to_string()]),
+            ("open_2", vec!["search_1_id".to_string(), "login_0_id".to_string()]),
+        ]);
+        assert_eq!(graph.edges.len(), 4);
+        assert_eq!(graph.edges[1].path, "$.items[0].id");
+        assert_eq!(graph.edges[3].from_action_id, "open_2_id");
+        assert_eq!(graph.edges[3].assertion_id, Some("a1".to_string()));
+        assert_eq!(graph.edges[3].to_action_id, None);
+    }
+}
@@ -0,0 +1,13 @@
+use crate::api::{ApiResponse, AppError};
+use crate::graph::analysis::build_test_case_graph;
+use crate::graph::model::TestCaseGraph;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn get_test_case_graph(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<TestCaseGraph>, AppError> {
+    let result = build_test_case_graph(&repository, &"eren".to_string(), &id).await;
+    ApiResponse::from(result)
+}
@@ -0,0 +1,36 @@
+use crate::action::model::ActionType;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct GraphNode {
+    pub action_id: String,
+    pub name: String,
+    pub order: usize,
+    pub action_type: ActionType,
+    /// Actions whose output this action consumes through its parameters.
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct GraphEdge {
+    pub from_action_id: String,
+    /// Consuming action, none when the reference comes from an assertion.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_action_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assertion_id: Option<String>,
+    /// Part of the source action that is read, e.g. `output` or `input`.
+    pub section: String,
+    /// Path inside the section, e.g. `$.items[0].id`.
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct TestCaseGraph {
+    pub test_case_id: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
@@ -0,0 +1,123 @@
+use crate::action::model::Action;
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::group::model::{group_actions, ActionGroup, GroupedActions};
+use crate::persistence::repo::Repository;
+use crate::revision::history::{record_before_change, snapshot_before_change};
+use crate::validation::{AppJson, ValidJson, Validate, check_not_blank};
+use crate::change::version::IfMatch;
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn list_grouped_actions(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<GroupedActions>>, AppError> {
+    let groups = repository.action_groups().list_all(&"eren".to_string(), &test_case_id).await?;
+    let result = repository.actions()
+        .list_all("eren".to_string(), test_case_id)
+        .await
+        .map(|actions| group_actions(actions, &groups));
+    ApiResponse::from(result)
+}
+
+pub async fn create_action_group(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<CreateActionGroupPayload>,
+) -> Result<ApiResponse<ActionGroup>, AppError> {
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "action group create").await;
+    let group = repository.action_groups()
+        .create(ActionGroup::builder()
+            .customer_id("eren".to_string())
+            .test_case_id(test_case_id.clone())
+            .name(payload.name)
+            .build())
+        .await?;
+    for action_id in payload.action_ids.unwrap_or_default() {
+        repository.actions()
+            .update_group("eren".to_string(), test_case_id.clone(), action_id, Some(group.id.clone()), None)
+            .await?;
+    }
+    ApiResponse::from(Ok(group))
+}
+
+pub async fn rename_action_group(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<RenameActionGroupPayload>,
+) -> Result<ApiResponse<ActionGroup>, AppError> {
+    let result = repository.action_groups()
+        .rename(&"eren".to_string(), &test_case_id, &id, payload.name)
+        .await;
+    ApiResponse::from(result)
+}
+
+/// Deletes the group only; its actions stay in place and become ungrouped.
+pub async fn delete_action_group(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<ActionGroup>, AppError> {
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "action group delete").await;
+    let members: Vec<Action> = repository.actions()
+        .list_all("eren".to_string(), test_case_id.clone())
+        .await?
+        .into_iter()
+        .filter(|action| action.group_id.as_ref() == Some(&id))
+        .collect();
+    for action in members {
+        repository.actions()
+            .update_group("eren".to_string(), test_case_id.clone(), action.id, None, None)
+            .await?;
+    }
+    let result = repository.action_groups().delete(&"eren".to_string(), &test_case_id, &id).await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn move_action_to_group(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<MoveActionPayload>,
+) -> Result<ApiResponse<Action>, AppError> {
+    if let Some(group_id) = &payload.value {
+        repository.action_groups()
+            .get(&"eren".to_string(), &test_case_id, group_id)
+            .await?
+            .ok_or(AppError::NotFound("Action group not found!".to_string()))?;
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &test_case_id, "action group move").await;
+    let result = repository.actions()
+        .update_group("eren".to_string(), test_case_id, id, payload.value, expected_updated_at)
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CreateActionGroupPayload {
+    pub name: String,
+    pub action_ids: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct RenameActionGroupPayload {
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct MoveActionPayload {
+    pub value: Option<String>,
+}
+
+impl Validate for CreateActionGroupPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter().collect()
+    }
+}
+
+impl Validate for RenameActionGroupPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter().collect()
+    }
+}
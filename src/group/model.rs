@@ -0,0 +1,76 @@
+use crate::action::model::Action;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A named logical step of a test case. Actions join a group through their `group_id`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct ActionGroup {
+    pub customer_id: String,
+    pub test_case_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub name: String,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct GroupedActions {
+    pub group: Option<ActionGroup>,
+    pub actions: Vec<Action>,
+}
+
+/// Splits ordered actions into consecutive runs sharing the same group, so the result reads
+/// like the flow itself. Actions pointing at an unknown group are treated as ungrouped.
+pub fn group_actions(mut actions: Vec<Action>, groups: &[ActionGroup]) -> Vec<GroupedActions> {
+    actions.sort();
+    let mut grouped: Vec<GroupedActions> = vec![];
+    for action in actions {
+        let group = action.group_id.as_ref()
+            .and_then(|group_id| groups.iter().find(|group| group.id == *group_id))
+            .cloned();
+        match grouped.last_mut() {
+            Some(last) if last.group.as_ref().map(|g| &g.id) == group.as_ref().map(|g| &g.id) => {
+                last.actions.push(action);
+            }
+            _ => grouped.push(GroupedActions {
+                group,
+                actions: vec![action],
+            }),
+        }
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+
+    fn action(order: usize, group_id: Option<&str>) -> Action {
+        Action {
+            group_id: group_id.map(|id| id.to_string()),
+            ..test_action(&format!("a_{}", order), order, "GET", "https://abc.xyz")
+        }
+    }
+
+    #[test]
+    fn group_consecutive_actions() {
+        let login = ActionGroup::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("g1".to_string())
+            .name("login".to_string())
+            .build();
+        let actions = vec![action(2, None), action(0, Some("g1")), action(1, Some("g1")), action(3, Some("missing"))];
+
+        let grouped = group_actions(actions, std::slice::from_ref(&login));
+
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].group, Some(login));
+        assert_eq!(grouped[0].actions.iter().map(|a| a.order).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(grouped[1].group, None);
+        assert_eq!(grouped[1].actions.iter().map(|a| a.order).collect::<Vec<_>>(), vec![2, 3]);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::api::AppError;
+use crate::group::model::ActionGroup;
+use crate::persistence::repo::{build_composite_key, Table};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct ActionGroupOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct ActionGroupsTable();
+
+impl Table<ActionGroup> for ActionGroupsTable {
+    fn table_name() -> String {
+        "action_groups".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id#test_case_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &ActionGroup) -> (String, AttributeValue) {
+        Self::partition_key(build_composite_key(vec![entity.customer_id.clone(), entity.test_case_id.clone()]))
+    }
+
+    fn sort_key_from_entity(entity: &ActionGroup) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl ActionGroupOperations {
+    pub async fn create(&self, group: ActionGroup) -> Result<ActionGroup, AppError> {
+        ActionGroupsTable::put_item(self.client.clone(), group).await
+    }
+
+    pub async fn get(&self, customer_id: &String, test_case_id: &String, id: &String) -> Result<Option<ActionGroup>, AppError> {
+        ActionGroupsTable::get_item(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id.clone())
+            .await
+    }
+
+    pub async fn list_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<ActionGroup>, AppError> {
+        ActionGroupsTable::list_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
+    pub async fn rename(&self, customer_id: &String, test_case_id: &String, id: &String, name: String) -> Result<ActionGroup, AppError> {
+        ActionGroupsTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id.clone(),
+                                          self.client.clone().update_item()
+                                              .expression_attribute_names("#name", "name")
+                                              .expression_attribute_values(":value", AttributeValue::S(name))
+                                              .update_expression("SET #name = :value")).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, test_case_id: &String, id: &String) -> Result<Option<ActionGroup>, AppError> {
+        ActionGroupsTable::delete_item(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id.clone())
+            .await
+    }
+
+    pub async fn delete_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<ActionGroup>, AppError> {
+        ActionGroupsTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+}
@@ -1,49 +1,508 @@
 use crate::action::model::Action;
+use crate::api::AppError;
 use crate::assertion::model::{Assertion, AssertionItem, ComparisonType};
 use crate::auth::model::{AuthHeaderValue, AuthenticationProvider};
+use crate::auth::service::SetHeaderRequest;
+use crate::case::fingerprint::fingerprint;
 use crate::case::model::TestCase;
 use crate::har_resolver::FlattenKeyPrefixType::{AssertionExpression, Input, Output};
 use crate::json_path::model::Expression;
-use crate::parameter::model::{Parameter, ParameterLocation, ParameterType};
+use crate::json_path::utils::child_path;
+use crate::http::percent_decode;
+use crate::import_report::model::{ChangedRequest, HarDiff, ImportReport, ImportWarning, RecordedRequest, RefreshReport, RemovedRequest, RequestChange, SkipReason, SkippedEntry};
+use crate::mock::recorded::response_from_outputs;
+use crate::parameter::model::{Parameter, ParameterIn, ParameterLocation, ParameterType, ValueType};
 use crate::persistence::repo::Repository;
+use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+use crate::sensitive_header::model::SensitiveHeaderPolicy;
 use har::v1_2::{Entries, Headers, PostData, Request};
 use har::Spec;
+use indexmap::IndexMap;
 use regex::Regex;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use tracing::{info, warn};
 use uuid::Uuid;
 
+/// Which entries of a recording are imported and the auth providers set on the actions built from them.
+pub struct HarImportOptions {
+    pub excluded_path_parts: Vec<String>,
+    pub auth_providers: Vec<String>,
+    pub collapse_polling: bool,
+}
+
 pub async fn build_test_case(
     repository: &Repository,
     spec: &Spec,
     customer_id: &String,
     test_case_name: &String,
     description: &String,
-    excluded_path_parts: Vec<String>,
-    auth_providers: Vec<String>,
-) {
-    let entries = filter_entries(excluded_path_parts, spec);
-    let response_indexes: Vec<HashMap<String, Value>> = entries
-        .iter()
-        .enumerate()
-        .map(|(i, entry)| build_response_index(i, entry))
-        .collect();
-
-    let request_indexes: Vec<HashMap<String, Value>> = entries
-        .iter()
-        .enumerate()
-        .map(|(i, entry)| build_request_index(i, entry))
-        .collect();
-
+    options: HarImportOptions,
+) -> Result<TestCase, AppError> {
+    let (entries, skipped) = screen_entries(options.excluded_path_parts, spec, options.collapse_polling);
     let case = TestCase::builder()
         .customer_id(customer_id.clone())
         .name(test_case_name.clone())
         .description(description.clone())
+        .maybe_base_url(common_base_url(&entries))
         .build();
+    // imported before the test case is created so a recording over the quota leaves nothing behind
+    let (actions, mut warnings) = import_entries(repository, &case, entries, vec![], vec![], options.auth_providers).await?;
     let created_test_case = repository.test_cases().create(case).await;
+    let (test_case, duplicate_test_case_ids) = record_fingerprint(repository, created_test_case, &actions, &mut warnings).await;
+    save_import_report(repository, ImportReport {
+        duplicate_test_case_ids,
+        ..build_import_report(&test_case, actions.len(), skipped, warnings)
+    }).await;
+    Ok(test_case)
+}
+
+/// Imports a recording after the existing actions of a test case, continuing their order and
+/// resolving expressions against the recorded responses of both.
+pub async fn append_to_test_case(
+    repository: &Repository,
+    spec: &Spec,
+    test_case: &TestCase,
+    options: HarImportOptions,
+) -> Result<Vec<Action>, AppError> {
+    let (entries, skipped) = screen_entries(options.excluded_path_parts, spec, options.collapse_polling);
+    let existing_actions = repository.actions()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    let existing_parameters = repository.parameters()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    let (request_indexes, response_indexes) = build_existing_indexes(&existing_actions, &existing_parameters);
+    let (actions, mut warnings) = import_entries(repository, test_case, entries, request_indexes, response_indexes, options.auth_providers).await?;
+    let all_actions: Vec<Action> = existing_actions.into_iter().chain(actions.iter().cloned()).collect();
+    let (_, duplicate_test_case_ids) = record_fingerprint(repository, test_case.clone(), &all_actions, &mut warnings).await;
+    save_import_report(repository, ImportReport {
+        duplicate_test_case_ids,
+        ..build_import_report(test_case, actions.len(), skipped, warnings)
+    }).await;
+    Ok(actions)
+}
+
+/// Takes a new recording of a test case's flow and updates what was recorded from the old one in
+/// place: the values of its parameters, the outputs the response indexes are rebuilt from, and the
+/// credentials of its linked auth providers. Entries are matched to actions by method and path
+/// template; expressions, assertions and everything else edited on the test case are kept.
+pub async fn refresh_test_case(
+    repository: &Repository,
+    spec: &Spec,
+    test_case: &TestCase,
+    excluded_path_parts: Vec<String>,
+    collapse_polling: bool,
+) -> Result<RefreshReport, AppError> {
+    let (entries, mut skipped) = screen_entries(excluded_path_parts, spec, collapse_polling);
+    let mut actions = repository.actions()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    actions.sort_by_key(|action| action.order);
+    let parameters = repository.parameters()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    let sensitive_headers = sensitive_header_policy(repository, &test_case.customer_id).await;
+    let (matches, unmatched) = match_entries(&actions, entries, test_case.base_url.as_deref());
+    skipped.extend(unmatched.iter()
+        .map(|entry| skipped_entry(&entry.request.method, &entry.request.url, SkipReason::NoMatchingAction)));
+
+    let mut changed_parameters = vec![];
+    let mut updated_parameters = 0;
+    let mut added_outputs = 0;
+    let mut auth_headers_by_base_url: HashMap<String, HashMap<String, AuthHeaderValue>> = HashMap::new();
+    let mut auth_cookies_by_base_url: HashMap<String, HashMap<String, AuthHeaderValue>> = HashMap::new();
+    for (action, entry, path_values) in matches.iter() {
+        info!(action = %action.name, url = %entry.request.url, "refreshing action");
+        let existing: Vec<Parameter> = parameters.iter()
+            .filter(|parameter| parameter.action_id == action.id)
+            .cloned()
+            .collect();
+        let (updated, added) = refreshed_parameters(&existing, recorded_parameters(action, entry, path_values, &sensitive_headers));
+        updated_parameters += updated.len();
+        added_outputs += added.len();
+        changed_parameters.extend(updated.into_iter().chain(added));
+        let base_url = obtain_base_url(&entry.request.url);
+        auth_headers_by_base_url.entry(base_url.clone())
+            .or_default()
+            .extend(build_auth_headers(&entry.request, &sensitive_headers));
+        auth_cookies_by_base_url.entry(base_url)
+            .or_default()
+            .extend(build_auth_cookies(&entry.request, &sensitive_headers));
+    }
+    repository.parameters().batch_create(changed_parameters).await;
+    let updated_auth_provider_ids = refresh_auth_providers(repository, test_case, &auth_headers_by_base_url, &auth_cookies_by_base_url).await?;
+    Ok(RefreshReport {
+        test_case_id: test_case.id.clone(),
+        refreshed_action_ids: matches.iter().map(|(action, _, _)| action.id.clone()).collect(),
+        unmatched_action_ids: actions.iter()
+            .filter(|action| !matches.iter().any(|(matched, _, _)| matched.id == action.id))
+            .map(|action| action.id.clone())
+            .collect(),
+        updated_parameters,
+        added_outputs,
+        updated_auth_provider_ids,
+        skipped,
+    })
+}
+
+/// Compares a new recording of a test case's flow with its actions without changing anything:
+/// entries no action matches are reported as added, actions no entry matches as removed, and
+/// matched ones with the inputs and base URL that differ.
+pub async fn diff_test_case(
+    repository: &Repository,
+    spec: &Spec,
+    test_case: &TestCase,
+    excluded_path_parts: Vec<String>,
+    collapse_polling: bool,
+) -> Result<HarDiff, AppError> {
+    let (entries, skipped) = screen_entries(excluded_path_parts, spec, collapse_polling);
+    let mut actions = repository.actions()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    actions.sort_by_key(|action| action.order);
+    let parameters = repository.parameters()
+        .list_all(test_case.customer_id.clone(), test_case.id.clone())
+        .await?;
+    let sensitive_headers = sensitive_header_policy(repository, &test_case.customer_id).await;
+    Ok(HarDiff {
+        skipped,
+        ..diff_actions(test_case, &actions, &parameters, entries, &sensitive_headers)
+    })
+}
+
+fn diff_actions(
+    test_case: &TestCase,
+    actions: &[Action],
+    parameters: &[Parameter],
+    entries: Vec<&Entries>,
+    sensitive_headers: &SensitiveHeaderPolicy,
+) -> HarDiff {
+    let case_base_url = test_case.base_url.as_deref();
+    let (matches, unmatched) = match_entries(actions, entries, case_base_url);
+    let changed = matches.iter()
+        .filter_map(|(action, entry, path_values)| {
+            let current_base_url = obtain_base_url(&action.absolute_url(case_base_url));
+            let recorded_base_url = obtain_base_url(&entry.request.url);
+            let mut changes = vec![];
+            if current_base_url != recorded_base_url {
+                changes.push(RequestChange::BaseUrl { before: current_base_url, after: recorded_base_url });
+            }
+            let current: Vec<&Parameter> = parameters.iter()
+                .filter(|parameter| parameter.action_id == action.id && parameter.parameter_type == ParameterType::Input)
+                .collect();
+            let recorded: Vec<Parameter> = recorded_parameters(action, entry, path_values, sensitive_headers)
+                .into_iter()
+                .filter(|parameter| parameter.parameter_type == ParameterType::Input)
+                .collect();
+            changes.extend(current.iter().filter_map(|parameter| {
+                let after = recorded.iter().find(|recorded| recorded.location == parameter.location).map(|recorded| &recorded.value);
+                (after != Some(&parameter.value)).then(|| RequestChange::Parameter {
+                    location: parameter.location.clone(),
+                    before: Some(parameter.value.clone()),
+                    after: after.cloned(),
+                })
+            }));
+            changes.extend(recorded.iter()
+                .filter(|parameter| !current.iter().any(|current| current.location == parameter.location))
+                .map(|parameter| RequestChange::Parameter {
+                    location: parameter.location.clone(),
+                    before: None,
+                    after: Some(parameter.value.clone()),
+                }));
+            (!changes.is_empty()).then(|| ChangedRequest {
+                action_id: action.id.clone(),
+                action_name: action.name.clone(),
+                method: entry.request.method.clone(),
+                url: entry.request.url.clone(),
+                changes,
+            })
+        })
+        .collect();
+    HarDiff {
+        test_case_id: test_case.id.clone(),
+        added: unmatched.iter()
+            .map(|entry| RecordedRequest { method: entry.request.method.clone(), url: entry.request.url.clone() })
+            .collect(),
+        removed: actions.iter()
+            .filter(|action| !matches.iter().any(|(matched, _, _)| matched.id == action.id))
+            .map(|action| RemovedRequest {
+                action_id: action.id.clone(),
+                action_name: action.name.clone(),
+                method: action.method.clone(),
+                url: action.absolute_url(case_base_url),
+            })
+            .collect(),
+        changed,
+        skipped: vec![],
+    }
+}
+
+/// The customer's sensitive header policy, the default one when it cannot be loaded.
+async fn sensitive_header_policy(repository: &Repository, customer_id: &String) -> SensitiveHeaderPolicy {
+    repository.sensitive_headers()
+        .get(customer_id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("could not load sensitive header policy, using defaults: {:?}", err);
+            SensitiveHeaderPolicy::default_for(customer_id)
+        })
+}
+
+type EntryMatch<'a> = (&'a Action, &'a Entries, Vec<(String, String)>);
+
+/// Pairs every entry with the first action not matched yet that has its method and path template,
+/// along with the values the recording has for the template's placeholders. Relative action URLs
+/// are resolved against the test case's base URL first, so a base URL with a path still matches.
+fn match_entries<'a>(actions: &'a [Action], entries: Vec<&'a Entries>, case_base_url: Option<&str>) -> (Vec<EntryMatch<'a>>, Vec<&'a Entries>) {
+    let mut matches: Vec<EntryMatch<'a>> = vec![];
+    let mut unmatched = vec![];
+    for entry in entries {
+        let found = actions.iter()
+            .filter(|action| action.method.eq_ignore_ascii_case(&entry.request.method))
+            .filter(|action| !matches.iter().any(|(matched, _, _)| matched.id == action.id))
+            .find_map(|action| match_path_template(&action.absolute_url(case_base_url), &entry.request.url).map(|path_values| (action, path_values)));
+        match found {
+            Some((action, path_values)) => matches.push((action, entry, path_values)),
+            None => unmatched.push(entry),
+        }
+    }
+    (matches, unmatched)
+}
+
+/// Values of the template's `{placeholder}` segments in the URL when its path has the template's
+/// shape. Base URLs and query strings are ignored.
+fn match_path_template(template: &str, url: &str) -> Option<Vec<(String, String)>> {
+    let template_segments = path_segments(template);
+    let segments = path_segments(url);
+    if template_segments.len() != segments.len() {
+        return None;
+    }
+    let mut values = vec![];
+    for (template_segment, segment) in template_segments.into_iter().zip(segments) {
+        match template_segment.strip_prefix('{').and_then(|name| name.strip_suffix('}')) {
+            Some(name) => values.push((name.to_string(), percent_decode(segment))),
+            None if template_segment == segment => {}
+            None => return None,
+        }
+    }
+    Some(values)
+}
+
+fn path_segments(url: &str) -> Vec<&str> {
+    let url = url.split(['?', '#']).next().unwrap_or_default();
+    let path = if url.contains("://") { &url[obtain_base_url(url).len()..] } else { url };
+    path.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
+/// Parameters as the entry records them for the action, expressions left out.
+fn recorded_parameters(action: &Action, entry: &Entries, path_values: &[(String, String)], sensitive_headers: &SensitiveHeaderPolicy) -> Vec<Parameter> {
+    let no_indexes = vec![HashMap::new(); action.order];
+    let mut parameters = build_query_parameters(action, &entry.request, &no_indexes);
+    parameters.extend(build_body_parameters(action, &entry.request, &no_indexes));
+    parameters.extend(build_header_parameters(action, &entry.request, &no_indexes, sensitive_headers));
+    parameters.extend(path_values.iter().map(|(name, value)| build_parameter(
+        action,
+        None,
+        Value::String(value.clone()),
+        ParameterLocation::Path(name.clone()),
+        ParameterType::Input,
+    )));
+    parameters.extend(build_output_parameters(action, entry));
+    parameters
+}
+
+/// Existing parameters whose recorded value changed, with only the value replaced, and the
+/// recorded outputs the action had no parameter for. Inputs are never added, so a request does
+/// not change shape under its user.
+fn refreshed_parameters(existing: &[Parameter], recorded: Vec<Parameter>) -> (Vec<Parameter>, Vec<Parameter>) {
+    let mut updated = vec![];
+    let mut added = vec![];
+    for parameter in recorded {
+        let current = existing.iter().find(|current| current.parameter_type == parameter.parameter_type && current.location == parameter.location);
+        match current {
+            Some(current) if current.value != parameter.value => updated.push(Parameter { value: parameter.value, ..current.clone() }),
+            Some(_) => {}
+            None if parameter.parameter_type == ParameterType::Output => added.push(parameter),
+            None => {}
+        }
+    }
+    (updated, added)
+}
+
+/// Sets the recorded values of the headers and cookies the test case's linked auth providers
+/// already have, returning the ids of the providers that changed.
+async fn refresh_auth_providers(
+    repository: &Repository,
+    test_case: &TestCase,
+    auth_headers_by_base_url: &HashMap<String, HashMap<String, AuthHeaderValue>>,
+    auth_cookies_by_base_url: &HashMap<String, HashMap<String, AuthHeaderValue>>,
+) -> Result<Vec<String>, AppError> {
+    let providers = repository.auth_providers()
+        .list_all(&test_case.customer_id)
+        .await?
+        .into_iter()
+        .filter(|provider| provider.linked_test_case_ids.contains(&test_case.id));
+    let mut updated_ids = vec![];
+    for provider in providers {
+        let request = |name: &String, value: &AuthHeaderValue| SetHeaderRequest {
+            customer_id: provider.customer_id.clone(),
+            id: provider.id.clone(),
+            name: name.clone(),
+            value: value.value.clone(),
+            expires_at: None,
+        };
+        let headers = changed_credentials(&provider.headers_by_name, auth_headers_by_base_url.get(&provider.base_url));
+        let cookies = changed_credentials(&provider.cookies_by_name, auth_cookies_by_base_url.get(&provider.base_url));
+        for (name, value) in headers.iter() {
+            repository.auth_providers().set_header(request(name, value)).await?;
+        }
+        for (name, value) in cookies.iter() {
+            repository.auth_providers().set_cookie(request(name, value)).await?;
+        }
+        if !headers.is_empty() || !cookies.is_empty() {
+            info!(auth_provider = %provider.id, headers = headers.len(), cookies = cookies.len(), "refreshed auth provider");
+            updated_ids.push(provider.id.clone());
+        }
+    }
+    Ok(updated_ids)
+}
+
+/// Recorded credentials the provider has under the same name, ignoring case, with another value.
+/// They are returned under the provider's name.
+fn changed_credentials(current: &HashMap<String, AuthHeaderValue>, recorded: Option<&HashMap<String, AuthHeaderValue>>) -> Vec<(String, AuthHeaderValue)> {
+    let Some(recorded) = recorded else {
+        return vec![];
+    };
+    let mut changed: Vec<(String, AuthHeaderValue)> = current.iter()
+        .filter_map(|(name, value)| recorded.iter()
+            .find(|(recorded_name, _)| recorded_name.eq_ignore_ascii_case(name))
+            .filter(|(_, recorded_value)| recorded_value.value != value.value)
+            .map(|(_, recorded_value)| (name.clone(), recorded_value.clone())))
+        .collect();
+    changed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    changed
+}
+
+/// Stores the fingerprint of the test case's actions and warns about other test cases of the
+/// customer with the same one, returning their ids. Failures only cost the duplicate check.
+async fn record_fingerprint(repository: &Repository, test_case: TestCase, actions: &[Action], warnings: &mut Vec<ImportWarning>) -> (TestCase, Vec<String>) {
+    let fingerprint = fingerprint(actions);
+    let duplicates = match repository.test_cases().find_by_fingerprint(&test_case.customer_id, &fingerprint).await {
+        Ok(test_cases) => test_cases.into_iter().filter(|duplicate| duplicate.id != test_case.id).collect(),
+        Err(err) => {
+            warn!("could not look for duplicate test cases: {:?}", err);
+            vec![]
+        }
+    };
+    warnings.extend(duplicates.iter().map(|duplicate: &TestCase| ImportWarning {
+        url: None,
+        action_name: None,
+        message: format!("Test case {} ({}) has the same action sequence", duplicate.name, duplicate.id),
+    }));
+    let test_case = match repository.test_cases().update_fingerprint(test_case.customer_id.clone(), test_case.id.clone(), fingerprint).await {
+        Ok(updated) => updated,
+        Err(err) => {
+            warn!("could not save the fingerprint of test case {}: {:?}", test_case.id, err);
+            test_case
+        }
+    };
+    (test_case, duplicates.into_iter().map(|duplicate| duplicate.id).collect())
+}
+
+fn build_import_report(test_case: &TestCase, imported: usize, skipped: Vec<SkippedEntry>, warnings: Vec<ImportWarning>) -> ImportReport {
+    ImportReport::builder()
+        .customer_id(test_case.customer_id.clone())
+        .test_case_id(test_case.id.clone())
+        .total_entries(imported + skipped.len())
+        .imported_entries(imported)
+        .skipped_entries(skipped.len())
+        .skipped(skipped)
+        .warnings(warnings)
+        .build()
+}
+
+/// The import itself already succeeded, so a report that cannot be saved is only logged.
+async fn save_import_report(repository: &Repository, report: ImportReport) {
+    if let Err(err) = repository.import_reports().put(report).await {
+        warn!("could not save import report: {:?}", err);
+    }
+}
+
+type PositionalIndexes = Vec<HashMap<String, Value>>;
+
+/// Rebuilds the positional indexes of already imported actions from their stored parameters.
+/// Positions without an action (order gaps, sleeps) get an empty index.
+fn build_existing_indexes(
+    actions: &[Action],
+    parameters: &[Parameter],
+) -> (PositionalIndexes, PositionalIndexes) {
+    let next_order = actions.iter().map(|action| action.order + 1).max().unwrap_or(0);
+    let mut request_indexes: Vec<HashMap<String, Value>> = vec![HashMap::new(); next_order];
+    let mut response_indexes: Vec<HashMap<String, Value>> = vec![HashMap::new(); next_order];
+    actions.iter().for_each(|action| {
+        let of_action = |parameter_type: ParameterType| -> Vec<Parameter> {
+            parameters.iter()
+                .filter(|parameter| parameter.action_id == action.id && parameter.parameter_type == parameter_type)
+                .filter(|parameter| parameter.get_parameter_in() == ParameterIn::Body)
+                .cloned()
+                .collect()
+        };
+        let request_value = response_from_outputs(&of_action(ParameterType::Input));
+        if !request_value.is_null() {
+            request_indexes[action.order] = build_request_index_from_value(&action.name, &request_value);
+        }
+        let response_value = response_from_outputs(&of_action(ParameterType::Output));
+        if !response_value.is_null() {
+            response_indexes[action.order] = build_response_index_from_value(&action.name, &response_value);
+        }
+        parameters.iter()
+            .filter(|parameter| parameter.action_id == action.id && parameter.parameter_type == ParameterType::Output)
+            .filter_map(|parameter| match &parameter.location {
+                ParameterLocation::Header(name) => Some((header_expression(&action.name, name), parameter.value.clone())),
+                _ => None,
+            })
+            .for_each(|(expression, value)| {
+                response_indexes[action.order].insert(expression, value);
+            });
+    });
+    (request_indexes, response_indexes)
+}
 
-    let mut actions = vec![];
+async fn import_entries(
+    repository: &Repository,
+    test_case: &TestCase,
+    entries: Vec<&Entries>,
+    mut request_indexes: Vec<HashMap<String, Value>>,
+    mut response_indexes: Vec<HashMap<String, Value>>,
+    auth_providers: Vec<String>,
+) -> Result<(Vec<Action>, Vec<ImportWarning>), AppError> {
+    let first_order = response_indexes.len();
+    response_indexes.extend(entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| build_response_index(first_order + i, entry)));
+    request_indexes.extend(entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| build_request_index(first_order + i, entry)));
+
+    let customer_id = &test_case.customer_id;
+    let mut warnings = vec![];
+    let sensitive_headers = repository.sensitive_headers()
+        .get(customer_id)
+        .await
+        .unwrap_or_else(|err| {
+            warn!("could not load sensitive header policy, using defaults: {:?}", err);
+            warnings.push(ImportWarning {
+                url: None,
+                action_name: None,
+                message: "Could not load the sensitive header policy, the default one was applied".to_string(),
+            });
+            SensitiveHeaderPolicy::default_for(customer_id)
+        });
+    let (mut actions, mut parameters, mut assertions) = (vec![], vec![], vec![]);
+    let mut linked_auth_provider_ids = HashSet::new();
     let existing_auth_providers = if auth_providers.is_empty() {
         vec![]
     } else {
@@ -54,87 +513,154 @@ pub async fn build_test_case(
     };
     let mut auth_headers_by_base_url: HashMap<String, Vec<HashMap<String, AuthHeaderValue>>> =
         HashMap::new();
-    for i in 0..entries.len() {
-        let current = entries.get(i).unwrap();
-        println!("{:#?}", current.request.url);
-        let action = build_action(i, &created_test_case, current, &response_indexes);
-        let input_parameters = build_action_input(&action, &current.request, &response_indexes);
-        let output_parameters = build_output_parameters(&action, current);
-        let assertions = build_assertions(&action, &request_indexes, &response_indexes);
-        repository.assertions().batch_create(assertions).await;
+    let mut auth_cookies_by_base_url: HashMap<String, HashMap<String, AuthHeaderValue>> = HashMap::new();
+    for (i, current) in entries.iter().enumerate() {
+        info!(url = %current.request.url, order = first_order + i, "importing entry");
+        let action = build_action(first_order + i, test_case, current, &response_indexes);
+        warnings.extend(entry_warnings(&action.name, current));
+        parameters.extend(build_action_input(&action, &current.request, &response_indexes, &sensitive_headers));
+        parameters.extend(build_output_parameters(&action, current));
+        assertions.extend(build_assertions(&action, &request_indexes, &response_indexes));
         actions.push(action);
-        repository.parameters().batch_create(input_parameters).await;
-        repository
-            .parameters()
-            .batch_create(output_parameters)
-            .await;
         let base_url = obtain_base_url(&current.request.url.as_str());
         let matched_provider = existing_auth_providers.iter()
             .find(|auth_provider| { auth_provider.base_url.eq(&base_url) });
 
         match matched_provider {
             None => {
-                let auth_headers = build_auth_headers(&current.request);
+                let auth_headers = build_auth_headers(&current.request, &sensitive_headers);
+                auth_cookies_by_base_url
+                    .entry(base_url.clone())
+                    .or_default()
+                    .extend(build_auth_cookies(&current.request, &sensitive_headers));
                 auth_headers_by_base_url
                     .entry(base_url)
                     .or_insert_with(Vec::new)
                     .push(auth_headers);
             }
             Some(auth_provider) => {
-                repository.auth_providers()
-                    .link(customer_id, &auth_provider.id, &created_test_case.id).await;
+                linked_auth_provider_ids.insert(auth_provider.id.clone());
             }
         }
     }
-    create_auth_providers(repository, created_test_case.clone(), &mut auth_headers_by_base_url).await;
-    repository.actions().batch_create(actions).await;
+    let permit = guard_test_case_write(repository, customer_id, &test_case.id, TestCaseWrite {
+        actions: &actions,
+        parameters: &parameters,
+        assertions: &assertions,
+    }).await?;
+    for auth_provider_id in &linked_auth_provider_ids {
+        repository.auth_providers()
+            .link(customer_id, auth_provider_id, &test_case.id).await;
+    }
+    create_auth_providers(repository, test_case.clone(), &mut auth_headers_by_base_url, &mut auth_cookies_by_base_url).await;
+    repository.assertions().batch_create(assertions).await;
+    repository.parameters().batch_create(parameters).await;
+    repository.actions().batch_create(actions.clone(), permit).await;
+    Ok((actions, warnings))
+}
+
+/// Filters the entries to import, optionally collapsing runs of repeated polling requests.
+pub fn filter_import_entries(excluded_path_parts: Vec<String>, spec: &Spec, collapse_polling: bool) -> Vec<&Entries> {
+    screen_entries(excluded_path_parts, spec, collapse_polling).0
 }
 
-pub fn filter_entries(excluded_path_parts: Vec<String>, spec: &Spec) -> Vec<&Entries> {
+/// Splits the entries into the ones to import and the skipped ones with the reason they were left out.
+pub fn screen_entries(excluded_path_parts: Vec<String>, spec: &Spec, collapse_polling: bool) -> (Vec<&Entries>, Vec<SkippedEntry>) {
     let exclusions: Vec<String> = excluded_path_parts.iter()
         .map(|s| s.trim().to_string())
         .collect();
-    info!("{:?}", excluded_path_parts.clone());
     match spec {
         Spec::V1_2(log_v1) => {
-            let entries: Vec<&Entries> = log_v1
-                .entries
-                .iter()
-                .filter(|entry| {
-                    exclusions.is_empty()
-                        || !exclusions
-                        .iter()
-                        .any(|part| entry.request.url.contains(part))
-                })
-                .filter(|entry| {
-                    let request = &entry.request;
-                    match &request.post_data {
-                        None => {
-                            true
-                        }
-                        Some(post_data) => {
-                            post_data.mime_type.contains("json") || post_data.mime_type.contains("form-urlencoded")
-                        }
-                    }
-                })
-                .filter(|entry| {
-                    let response = &entry.response;
-                    let mime_type_opt = response.content.mime_type.clone();
-                    mime_type_opt.map_or(true, |mime_type| mime_type.contains("json"))
-                })
-                .collect();
-            entries
+            let mut entries = vec![];
+            let mut skipped = vec![];
+            for entry in log_v1.entries.iter() {
+                match skip_reason(entry, &exclusions) {
+                    None => entries.push(entry),
+                    Some(reason) => skipped.push(skipped_entry(&entry.request.method, &entry.request.url, reason)),
+                }
+            }
+            if collapse_polling {
+                let collapsed = collapse_consecutive(entries.clone(), |entry| request_fingerprint(&entry.request));
+                skipped.extend(entries.iter()
+                    .filter(|entry| !collapsed.iter().any(|kept| std::ptr::eq(*kept, **entry)))
+                    .map(|entry| skipped_entry(&entry.request.method, &entry.request.url, SkipReason::RepeatedPolling)));
+                entries = collapsed;
+            }
+            (entries, skipped)
         }
         Spec::V1_3(log_v2) => {
-            vec![]
+            let skipped = log_v2.entries.iter()
+                .map(|entry| skipped_entry(&entry.request.method, &entry.request.url, SkipReason::UnsupportedHarVersion))
+                .collect();
+            (vec![], skipped)
+        }
+    }
+}
+
+fn skipped_entry(method: &str, url: &str, reason: SkipReason) -> SkippedEntry {
+    SkippedEntry { method: method.to_string(), url: url.to_string(), reason }
+}
+
+/// Only JSON and form bodies can be turned into parameters and only JSON responses into outputs.
+fn skip_reason(entry: &Entries, exclusions: &[String]) -> Option<SkipReason> {
+    if let Some(part) = exclusions.iter().find(|part| entry.request.url.contains(part.as_str())) {
+        return Some(SkipReason::ExcludedPath { part: part.clone() });
+    }
+    if let Some(post_data) = &entry.request.post_data {
+        if !post_data.mime_type.contains("json") && !post_data.mime_type.contains("form-urlencoded") {
+            return Some(SkipReason::UnsupportedRequestBody { mime_type: post_data.mime_type.clone() });
+        }
+    }
+    match &entry.response.content.mime_type {
+        Some(mime_type) if !mime_type.contains("json") => Some(SkipReason::UnsupportedResponseBody { mime_type: mime_type.clone() }),
+        _ => None,
+    }
+}
+
+/// Bodies that claim to be JSON but do not parse are imported without the parameters they would yield.
+fn entry_warnings(action_name: &str, entry: &Entries) -> Vec<ImportWarning> {
+    let warning = |message: String| ImportWarning {
+        url: Some(entry.request.url.clone()),
+        action_name: Some(action_name.to_string()),
+        message,
+    };
+    let mut warnings = vec![];
+    let request_text = entry.request.post_data.as_ref()
+        .filter(|post_data| post_data.mime_type.contains("json"))
+        .and_then(|post_data| post_data.text.as_ref());
+    if let Some(Err(err)) = request_text.map(|text| serde_json::from_str::<Value>(text)) {
+        warnings.push(warning(format!("Request body is not valid JSON, no body parameters were created: {}", err)));
+    }
+    let response_text = entry.response.content.text.as_ref().filter(|text| !text.trim().is_empty());
+    if let Some(Err(err)) = response_text.map(|text| serde_json::from_str::<Value>(text)) {
+        warnings.push(warning(format!("Response body is not valid JSON, no output parameters or assertions were created: {}", err)));
+    }
+    warnings
+}
+
+fn request_fingerprint(request: &Request) -> (String, String, Option<String>) {
+    let body = request.post_data.as_ref().and_then(|post_data| post_data.text.clone());
+    (request.method.clone(), request.url.clone(), body)
+}
+
+/// Keeps only the last item of every run of consecutive items sharing a key, so a polled
+/// request is imported once with the response it finally settled on.
+fn collapse_consecutive<T, K: PartialEq>(items: Vec<T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut collapsed: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        if collapsed.last().is_some_and(|last| key(last) == key(&item)) {
+            collapsed.pop();
         }
+        collapsed.push(item);
     }
+    collapsed
 }
 
 async fn create_auth_providers(
     repository: &Repository,
     created_test_case: TestCase,
     auth_headers_by_base_url: &mut HashMap<String, Vec<HashMap<String, AuthHeaderValue>>>,
+    auth_cookies_by_base_url: &mut HashMap<String, HashMap<String, AuthHeaderValue>>,
 ) {
     let auth_providers = auth_headers_by_base_url
         .iter()
@@ -152,6 +678,7 @@ async fn create_auth_providers(
                 .name(build_auth_name_from_url(base_url))
                 .base_url(base_url.clone())
                 .headers_by_name(headers_by_name)
+                .cookies_by_name(auth_cookies_by_base_url.remove(base_url).unwrap_or_default())
                 .linked_test_case_ids(test_case_ids)
                 .build()
         })
@@ -174,6 +701,8 @@ fn build_auth_name_from_url(base_url: &String) -> String {
 
 fn build_action(order: usize, test_case: &TestCase, entry: &Entries, response_indexes: &Vec<HashMap<String, Value>>) -> Action {
     let action_name = build_action_name(order, &entry.request);
+    let url = build_url_template(order, &entry.request.url, response_indexes).url;
+    let (url, base_url) = relative_to_test_case(url, test_case.base_url.as_deref());
     Action::builder()
         .customer_id(test_case.customer_id.clone())
         .test_case_id(test_case.id.clone())
@@ -181,31 +710,116 @@ fn build_action(order: usize, test_case: &TestCase, entry: &Entries, response_in
         .name(action_name.clone())
         .maybe_mime_type(resolve_mime_type(entry))
         .method(entry.request.method.clone())
-        .url(build_url_without_query_params(order, &entry.request.url, response_indexes))
+        .url(url)
+        .maybe_base_url(base_url)
+        .maybe_recorded_status(u16::try_from(entry.response.status).ok().filter(|status| *status > 0))
         .build()
 }
 
-fn build_url_without_query_params(order: usize, url: &String, response_indexes: &Vec<HashMap<String, Value>>) -> String {
+/// The most common base URL of the entries, the first one seen on a tie.
+fn common_base_url(entries: &[&Entries]) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = vec![];
+    for entry in entries {
+        let base_url = obtain_base_url(&entry.request.url);
+        match counts.iter_mut().find(|(seen, _)| *seen == base_url) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((base_url, 1)),
+        }
+    }
+    let max_count = counts.iter().map(|(_, count)| *count).max()?;
+    counts.into_iter()
+        .find(|(_, count)| *count == max_count)
+        .map(|(base_url, _)| base_url)
+}
+
+/// Splits an absolute action URL into its path and, when it differs from the test case's, its own
+/// base URL. Test cases without a base URL keep absolute URLs.
+fn relative_to_test_case(url: String, case_base_url: Option<&str>) -> (String, Option<String>) {
+    let Some(case_base_url) = case_base_url.map(|base_url| base_url.trim_end_matches('/')) else {
+        return (url, None);
+    };
+    let as_path = |path: &str| if path.is_empty() { "/".to_string() } else { path.to_string() };
+    match url.strip_prefix(case_base_url) {
+        Some(path) if path.is_empty() || path.starts_with('/') => (as_path(path), None),
+        _ => {
+            let base_url = obtain_base_url(&url);
+            (as_path(&url[base_url.len()..]), Some(base_url))
+        }
+    }
+}
+
+pub struct UrlTemplate {
+    pub url: String,
+    pub path_parameters: Vec<(String, String, Expression)>,
+}
+
+/// Replaces URL segments that resolve to an earlier response value with named `{placeholder}`s,
+/// returning each placeholder with its recorded value and expression.
+fn build_url_template(order: usize, url: &String, response_indexes: &Vec<HashMap<String, Value>>) -> UrlTemplate {
     let re = Regex::new(r"\?.*$").unwrap();
     let url = re.replace(url, "").to_string();
     let base_url = obtain_base_url(url.as_str());
 
     let path = url.clone().replace(base_url.as_str(), "");
 
-    println!("path: {:#?}", path);
-
-    let path_with_expressions = path.split("/")
-        .map(|s| {
+    let segments: Vec<&str> = path.split("/").collect();
+    let mut path_parameters: Vec<(String, String, Expression)> = vec![];
+    let templated_path = segments.iter()
+        .enumerate()
+        .map(|(i, s)| {
             if s.is_empty() {
-                "".to_string()
-            } else {
-                resolve_value_expression_from_prev(order, &Value::String(s.to_string()), response_indexes)
-                    .map_or(s.to_string(), |expression: Expression| { expression.value })
+                return "".to_string();
+            }
+            let key = if i > 0 { segments[i - 1] } else { "" };
+            match resolve_value_expression_from_prev(order, key, &Value::String(s.to_string()), response_indexes) {
+                None => s.to_string(),
+                Some(expression) => {
+                    let name = unique_placeholder_name(&expression, &path_parameters);
+                    path_parameters.push((name.clone(), s.to_string(), expression));
+                    format!("{{{}}}", name)
+                }
             }
         })
         .collect::<Vec<String>>()
         .join("/");
-    format!("{}{}", base_url, path_with_expressions)
+    UrlTemplate {
+        url: format!("{}{}", base_url, templated_path),
+        path_parameters,
+    }
+}
+
+fn unique_placeholder_name(expression: &Expression, taken: &[(String, String, Expression)]) -> String {
+    let tail = expression.value.rsplit('.').next().unwrap_or_default();
+    let base_name: String = tail.split('[').next().unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_')
+        .collect();
+    let base_name = if base_name.is_empty() { "param".to_string() } else { base_name };
+    let mut name = base_name.clone();
+    let mut suffix = 2;
+    while taken.iter().any(|(taken_name, _, _)| *taken_name == name) {
+        name = format!("{}_{}", base_name, suffix);
+        suffix += 1;
+    }
+    name
+}
+
+fn build_path_parameters(
+    action: &Action,
+    request: &Request,
+    response_indexes: &Vec<HashMap<String, Value>>,
+) -> Vec<Parameter> {
+    build_url_template(action.order, &request.url, response_indexes)
+        .path_parameters
+        .into_iter()
+        .map(|(name, value, expression)| build_parameter(
+            action,
+            Some(expression),
+            Value::String(value),
+            ParameterLocation::Path(name),
+            ParameterType::Input,
+        ))
+        .collect()
 }
 
 fn resolve_mime_type(entry: &Entries) -> Option<String> {
@@ -217,15 +831,23 @@ fn resolve_mime_type(entry: &Entries) -> Option<String> {
 }
 
 fn build_response_index(order: usize, entry: &Entries) -> HashMap<String, Value> {
+    let action_name = build_action_name(order, &entry.request);
+    let mut index = build_response_body_index(&action_name, entry);
+    notable_response_headers(entry).into_iter().for_each(|(name, value)| {
+        index.insert(header_expression(&action_name, &name), Value::String(value));
+    });
+    index
+}
+
+fn build_response_body_index(action_name: &String, entry: &Entries) -> HashMap<String, Value> {
     let response = &entry.response;
     let content = &response.content;
     let option = &content.text;
     option.as_ref().map_or(HashMap::new(), |text| {
-        let action_name = build_action_name(order, &entry.request);
         info!("building response index for: {:?} and mime_type: {:?} content: {:?}", action_name, content.mime_type, text);
         match serde_json::from_str::<Value>(&text) {
             Ok(response_value) => {
-                build_response_index_from_value(&action_name, &response_value)
+                build_response_index_from_value(action_name, &response_value)
             }
             Err(e) => {
                 warn!("Empty index will be created for action: {:?} and mime_type: {:?} content: {:?}", action_name, content.mime_type, e);
@@ -254,7 +876,7 @@ fn build_index_from_value(
     response_value: &Value,
     flatten_key_prefix_type: FlattenKeyPrefixType,
 ) -> HashMap<String, Value> {
-    let mut result = HashMap::<String, Value>::new();
+    let mut result = IndexMap::<String, Value>::new();
     flatten_json_value(
         &action_name,
         &flatten_key_prefix_type,
@@ -262,7 +884,7 @@ fn build_index_from_value(
         "".to_string(),
         &mut result,
     );
-    result
+    result.into_iter().collect()
 }
 
 fn build_output_parameters(action: &Action, entry: &Entries) -> Vec<Parameter> {
@@ -280,9 +902,31 @@ fn build_output_parameters(action: &Action, entry: &Entries) -> Vec<Parameter> {
             }
         }
     });
+    notable_response_headers(entry).into_iter().for_each(|(name, value)| {
+        parameters.push(build_parameter(
+            action,
+            None,
+            Value::String(value),
+            ParameterLocation::Header(name),
+            ParameterType::Output,
+        ));
+    });
     parameters
 }
 
+/// Response headers worth recording as outputs, with lowercased names.
+fn notable_response_headers(entry: &Entries) -> Vec<(String, String)> {
+    entry.response.headers.iter()
+        .map(|header| (resolve_header_name(header).to_lowercase(), header.value.clone()))
+        .filter(|(name, value)| NOTABLE_RESPONSE_HEADERS.contains(&name.as_str()) && !value.is_empty())
+        .collect()
+}
+
+/// Expression of a response header in the run context, e.g. `$.create_order_0.output_headers['location']`.
+pub fn header_expression(action_name: &str, header_name: &str) -> String {
+    format!("$.{}.output_headers['{}']", action_name, header_name.to_lowercase())
+}
+
 pub enum FlattenKeyPrefixType {
     Output,
     Input,
@@ -294,7 +938,7 @@ pub fn build_output_parameters_from_value(
     response_value: &Value,
 ) -> Vec<Parameter> {
     let mut parameters = vec![];
-    let mut result = HashMap::<String, Value>::new();
+    let mut result = IndexMap::<String, Value>::new();
     flatten_json_value(
         &action.name,
         &Input,
@@ -369,12 +1013,15 @@ fn build_action_input(
     action: &Action,
     request: &Request,
     response_indexes: &Vec<HashMap<String, Value>>,
+    sensitive_headers: &SensitiveHeaderPolicy,
 ) -> Vec<Parameter> {
     let mut query_params = build_query_parameters(action, request, response_indexes);
     let body_params = build_body_parameters(action, request, response_indexes);
-    let header_params = build_header_parameters(action, request, response_indexes);
+    let header_params = build_header_parameters(action, request, response_indexes, sensitive_headers);
+    let path_params = build_path_parameters(action, request, response_indexes);
     query_params.extend(body_params);
     query_params.extend(header_params);
+    query_params.extend(path_params);
     query_params
 }
 
@@ -394,7 +1041,7 @@ pub fn build_assertions(
                     let mut slice = request_indexes[0..action.order].to_vec();
                     slice.reverse();
                     let expression_result =
-                        resolve_value_expression_from_slice_index(&res_value, &slice);
+                        resolve_value_expression_from_slice_index(path, &res_value, &slice);
                     if let Some(expression) = expression_result {
                         let assertion = Assertion::builder()
                             .customer_id(action.customer_id.clone())
@@ -443,16 +1090,19 @@ fn build_body_parameters(
                 .iter()
                 .filter(|p| p.value.is_some())
                 .for_each(|param| {
+                    let name = percent_decode(&param.name);
+                    let value = percent_decode(param.value.as_ref().unwrap());
                     let expression_result = resolve_value_expression_from_prev(
                         action.order,
-                        &Value::String(param.value.as_ref().unwrap().clone()),
+                        &name,
+                        &Value::String(value.clone()),
                         response_indexes,
                     );
                     let parameter = build_parameter(
                         action,
                         expression_result,
-                        Value::String(param.value.clone().unwrap()),
-                        ParameterLocation::Body(param.name.clone()),
+                        Value::String(value),
+                        ParameterLocation::Body(name),
                         ParameterType::Input,
                     );
                     parameters.push(parameter);
@@ -468,7 +1118,7 @@ pub fn build_body_parameters_from_value(
     value: &Value,
 ) -> Vec<Parameter> {
     let mut parameters: Vec<Parameter> = vec![];
-    let mut flatten_result: HashMap<String, Value> = HashMap::new();
+    let mut flatten_result: IndexMap<String, Value> = IndexMap::new();
     flatten_json_value(
         &action.name,
         &Input,
@@ -478,7 +1128,7 @@ pub fn build_body_parameters_from_value(
     );
     flatten_result.iter().for_each(|(key, value)| {
         let expression_result =
-            resolve_value_expression_from_prev(action.order, &value, response_indexes);
+            resolve_value_expression_from_prev(action.order, key, value, response_indexes);
         let parameter = build_parameter(
             action,
             expression_result,
@@ -486,7 +1136,8 @@ pub fn build_body_parameters_from_value(
             ParameterLocation::Body(key.to_string()),
             ParameterType::Input,
         );
-        parameters.push(parameter);
+        // keeps the recorded type when an expression later feeds the value, e.g. a string id into a number
+        parameters.push(Parameter { value_type: ValueType::of(value), ..parameter });
     });
     parameters
 }
@@ -498,8 +1149,8 @@ fn build_query_parameters(
 ) -> Vec<Parameter> {
     let mut parameters: Vec<Parameter> = vec![];
     request.query_string.iter().for_each(|query_string| {
-        let query_string_value = &query_string.value;
-        let query_key = &query_string.name;
+        let query_string_value = &percent_decode(&query_string.value);
+        let query_key = &percent_decode(&query_string.name);
         let parameter = build_query_param(action, response_indexes, query_string_value, query_key);
         parameters.push(parameter);
     });
@@ -514,6 +1165,7 @@ pub fn build_query_param(
 ) -> Parameter {
     let expression = resolve_value_expression_from_prev(
         action.order,
+        query_key,
         &Value::String(query_string_value.clone()),
         response_indexes,
     );
@@ -549,6 +1201,7 @@ fn build_header_parameters(
     action: &Action,
     request: &Request,
     response_indexes: &Vec<HashMap<String, Value>>,
+    sensitive_headers: &SensitiveHeaderPolicy,
 ) -> Vec<Parameter> {
     let mut parameters: Vec<Parameter> = vec![];
     request.headers.iter().for_each(|header| {
@@ -557,6 +1210,7 @@ fn build_header_parameters(
             response_indexes,
             &resolve_header_name(header),
             &header.value,
+            sensitive_headers,
         ) {
             parameters.push(parameter);
         }
@@ -569,12 +1223,14 @@ fn build_header_parameter(
     response_indexes: &Vec<HashMap<String, Value>>,
     header_name: &String,
     header_val: &String,
+    sensitive_headers: &SensitiveHeaderPolicy,
 ) -> Option<Parameter> {
-    if is_auth_related_header(&header_name) || must_exclude_header(&header_name) {
+    if sensitive_headers.is_sensitive(header_name) || must_exclude_header(header_name) {
         None
     } else {
         let expression = resolve_value_expression_from_prev(
             action.order,
+            header_name,
             &Value::String(header_val.clone()),
             response_indexes,
         );
@@ -589,12 +1245,14 @@ fn build_header_parameter(
     }
 }
 
-fn build_auth_headers(request: &Request) -> HashMap<String, AuthHeaderValue> {
+/// Sensitive headers of the request except `Cookie`, whose credentials are imported as cookies.
+fn build_auth_headers(request: &Request, sensitive_headers: &SensitiveHeaderPolicy) -> HashMap<String, AuthHeaderValue> {
     let mut auth_headers_by_name: HashMap<String, AuthHeaderValue> = HashMap::new();
     request
         .headers
         .iter()
-        .filter(|header| is_auth_related_header(&header.name))
+        .filter(|header| !header.name.eq_ignore_ascii_case("cookie"))
+        .filter(|header| sensitive_headers.is_sensitive(&header.name))
         .for_each(|header| {
             auth_headers_by_name.insert(
                 resolve_header_name(header),
@@ -603,42 +1261,148 @@ fn build_auth_headers(request: &Request) -> HashMap<String, AuthHeaderValue> {
                     .build(),
             );
         });
-    println!("cookies: {:?}", request.cookies);
-    request.cookies.iter()
-        .filter(|cookie| is_auth_related_header(&cookie.name))
-        .for_each(|cookie| {
-            info!("cookie: {} value: {}", cookie.name, cookie.value);
-            auth_headers_by_name.insert(
-                cookie.name.clone(),
-                AuthHeaderValue::builder()
-                    .value(cookie.value.clone())
-                    .build(),
-            );
-        });
     auth_headers_by_name
 }
 
+/// Cookies with sensitive names, or every cookie when the `Cookie` header itself is sensitive,
+/// as it is by default.
+fn build_auth_cookies(request: &Request, sensitive_headers: &SensitiveHeaderPolicy) -> HashMap<String, AuthHeaderValue> {
+    let cookie_header_sensitive = sensitive_headers.is_sensitive("cookie");
+    recorded_cookies(request)
+        .into_iter()
+        .filter(|(name, _)| cookie_header_sensitive || sensitive_headers.is_sensitive(name))
+        .map(|(name, value)| {
+            info!(cookie = %name, "moving cookie to auth provider");
+            (name, AuthHeaderValue::builder().value(value).build())
+        })
+        .collect()
+}
+
+/// Cookies the HAR lists for the request, read from its `Cookie` header when it lists none.
+fn recorded_cookies(request: &Request) -> Vec<(String, String)> {
+    if !request.cookies.is_empty() {
+        return request.cookies.iter()
+            .map(|cookie| (cookie.name.clone(), cookie.value.clone()))
+            .collect();
+    }
+    request.headers.iter()
+        .filter(|header| header.name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|header| header.value.split(';'))
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
 fn resolve_value_expression_from_prev(
     order: usize,
+    key: &str,
     value: &Value,
     response_indexes: &Vec<HashMap<String, Value>>,
 ) -> Option<Expression> {
     let prev_indexes: &[HashMap<String, Value>] = &response_indexes[0..order];
-    resolve_value_expression_from_slice_index(&value, prev_indexes)
+    resolve_value_expression_from_slice_index(key, &value, prev_indexes)
 }
 
+/// Picks the indexed path whose value matches, preferring paths whose tail resembles the key and
+/// whose value has the same JSON type. Trivially common values are never linked.
 fn resolve_value_expression_from_slice_index(
+    key: &str,
     value: &&Value,
     indexes: &[HashMap<String, Value>],
 ) -> Option<Expression> {
+    if is_trivial_value(value) {
+        return None;
+    }
     indexes
         .iter()
         .rev()
         .enumerate()
-        .flat_map(|(i, indexes)| indexes)
-        .filter(|(_, indexed_value)| indexed_value.eq(value))
-        .map(|(key, value)| Expression { value: key.clone() })
+        .flat_map(|(distance, indexes)| indexes.iter().map(move |(path, indexed_value)| (distance, path, indexed_value)))
+        .filter_map(|(distance, path, indexed_value)| {
+            match_score(key, value, path, indexed_value).map(|score| (score, distance, path))
+        })
+        .max_by(|(score1, distance1, path1), (score2, distance2, path2)| {
+            score1.total_cmp(score2)
+                .then(distance2.cmp(distance1))
+                .then(path2.cmp(path1))
+        })
+        .map(|(_, _, path)| Expression { value: path.clone() })
+}
+
+fn match_score(key: &str, value: &Value, path: &str, indexed_value: &Value) -> Option<f64> {
+    let same_type = if indexed_value.eq(value) {
+        true
+    } else if loosely_equal(value, indexed_value) {
+        false
+    } else {
+        return None;
+    };
+    Some(2.0 * key_similarity(key, path) + if same_type { 1.0 } else { 0.0 })
+}
+
+/// Query strings, headers and URL segments are always strings, while the value they were copied
+/// from is often a number in the response.
+fn loosely_equal(value: &Value, indexed_value: &Value) -> bool {
+    match (value, indexed_value) {
+        (Value::String(text), Value::Number(number)) | (Value::Number(number), Value::String(text)) => {
+            number.to_string() == *text
+        }
+        _ => false,
+    }
+}
+
+fn is_trivial_value(value: &Value) -> bool {
+    match value {
+        Value::Null | Value::Bool(_) => true,
+        Value::Number(number) => number.as_f64().is_some_and(|n| n == 0.0 || n == 1.0),
+        Value::String(text) => {
+            let text = text.trim();
+            text.len() < MIN_LINKABLE_STRING_LENGTH
+                || ["true", "false", "null", "undefined"].contains(&text.to_lowercase().as_str())
+        }
+        Value::Array(items) => items.is_empty(),
+        Value::Object(map) => map.is_empty(),
+    }
+}
+
+/// Scores how well a parameter key resembles the tail of an expression path. A key made of the
+/// source action name and the tail (`issueId` for `$.create_issue_0.output.id`) counts as well.
+pub fn key_similarity(key: &str, expression: &str) -> f64 {
+    let parameter_key = normalized_last_key(key);
+    let source_key = normalized_last_key(expression);
+    if parameter_key.is_empty() || source_key.is_empty() {
+        return 0.0;
+    }
+    if parameter_key == source_key {
+        return 1.0;
+    }
+    let source_action = expression.strip_prefix("$.")
+        .and_then(|path| path.split('.').next())
+        .map(normalize_key)
+        .unwrap_or_default();
+    let key_prefix = parameter_key.strip_suffix(&source_key).unwrap_or_default();
+    if !key_prefix.is_empty() && source_action.contains(key_prefix) {
+        0.9
+    } else if parameter_key.contains(&source_key) || source_key.contains(&parameter_key) {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+fn normalized_last_key(path: &str) -> String {
+    normalize_key(path.rsplit(['.', '/']).next().unwrap_or_default()
+        .split('[')
         .next()
+        .unwrap_or_default())
+}
+
+fn normalize_key(key: &str) -> String {
+    key.chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase()
 }
 
 fn flatten_json_value(
@@ -646,26 +1410,27 @@ fn flatten_json_value(
     prefix_type: &FlattenKeyPrefixType,
     value: &Value,
     prefix_so_far: String,
-    result: &mut HashMap<String, Value>,
+    result: &mut IndexMap<String, Value>,
 ) {
+    let prefix_so_far = if prefix_so_far.is_empty() {
+        match prefix_type {
+            Output => {
+                format!("$.{}.{}", action_name, "output")
+            }
+            Input => {
+                "$".to_string()
+            }
+            AssertionExpression => {
+                format!("$.{}.{}", action_name, "input")
+            }
+        }
+    } else {
+        prefix_so_far
+    };
     match value {
         Value::Object(map) => {
             for (key, val) in map {
-                let new_prefix = if prefix_so_far.is_empty() {
-                    match prefix_type {
-                        Output => {
-                            format!("$.{}.{}.{}", action_name, "output", key)
-                        }
-                        Input => {
-                            format!("$.{}", key)
-                        }
-                        AssertionExpression => {
-                            format!("$.{}.{}.{}", action_name, "input", key)
-                        }
-                    }
-                } else {
-                    format!("{}.{}", prefix_so_far, key)
-                };
+                let new_prefix = child_path(&prefix_so_far, key);
                 flatten_json_value(action_name, prefix_type, val, new_prefix, result);
             }
         }
@@ -681,21 +1446,6 @@ fn flatten_json_value(
     }
 }
 
-fn is_auth_related_header(key: &String) -> bool {
-    vec![
-        "authorization",
-        "token",
-        "session",
-        "csrf",
-        "user",
-        "origin",
-        "cookie",
-        "auth",
-    ]
-        .iter()
-        .any(|x| key.contains(x))
-}
-
 fn must_exclude_header(key: &String) -> bool {
     vec![
         "content-length",
@@ -723,6 +1473,10 @@ fn obtain_base_url(url: &str) -> String {
     url.to_string()
 }
 
+const MIN_LINKABLE_STRING_LENGTH: usize = 3;
+
+const NOTABLE_RESPONSE_HEADERS: [&str; 3] = ["location", "etag", "content-location"];
+
 fn resolve_header_name(header: &Headers) -> String {
     header.name.replace(":", "")
 }
@@ -731,6 +1485,284 @@ fn resolve_header_name(header: &Headers) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::action::fixtures::test_action;
+    use serde_json::json;
+
+    #[test]
+    fn collapse_consecutive_keeps_last_of_each_run() {
+        let polls = vec![("GET", "/jobs/1", 1), ("GET", "/jobs/1", 2), ("GET", "/jobs/1", 3),
+                         ("POST", "/jobs", 4), ("GET", "/jobs/1", 5)];
+
+        let collapsed = collapse_consecutive(polls, |(method, url, _)| (*method, *url));
+
+        assert_eq!(collapsed, vec![("GET", "/jobs/1", 3), ("POST", "/jobs", 4), ("GET", "/jobs/1", 5)]);
+    }
+
+    fn har_entry(method: &str, url: &str, request_mime_type: Option<&str>, response_mime_type: &str, response_text: &str) -> Value {
+        let mut request = json!({
+            "method": method, "url": url, "httpVersion": "HTTP/1.1", "cookies": [], "headers": [],
+            "queryString": [], "headersSize": -1, "bodySize": -1,
+        });
+        if let Some(mime_type) = request_mime_type {
+            request["postData"] = json!({"mimeType": mime_type, "text": "{}"});
+        }
+        json!({
+            "startedDateTime": "2024-01-01T00:00:00.000Z", "time": 1, "request": request,
+            "response": {
+                "status": 200, "statusText": "OK", "httpVersion": "HTTP/1.1", "cookies": [], "headers": [],
+                "content": {"size": 0, "mimeType": response_mime_type, "text": response_text},
+                "redirectURL": "", "headersSize": -1, "bodySize": -1,
+            },
+            "cache": {}, "timings": {"send": 0, "wait": 0, "receive": 0},
+        })
+    }
+
+    #[test]
+    fn cookies_are_imported_apart_from_headers() {
+        let mut entry = har_entry("GET", "https://abc.xyz/me", None, "application/json", "{}");
+        entry["request"]["headers"] = json!([
+            {"name": "Authorization", "value": "Bearer abc"},
+            {"name": "Cookie", "value": "session=s1; theme=dark"},
+        ]);
+        let request: Request = serde_json::from_value(entry["request"].clone()).unwrap();
+        let default_policy = SensitiveHeaderPolicy::default_for("cust1");
+
+        let headers = build_auth_headers(&request, &default_policy);
+        let cookies = build_auth_cookies(&request, &default_policy);
+
+        assert_eq!(headers.keys().collect::<Vec<_>>(), vec!["Authorization"]);
+        assert_eq!(cookies.get("session").map(|cookie| cookie.value.as_str()), Some("s1"));
+        assert_eq!(cookies.get("theme").map(|cookie| cookie.value.as_str()), Some("dark"));
+
+        let cookie_header_excluded = SensitiveHeaderPolicy { exclude: vec!["cookie".to_string()], ..default_policy };
+        let cookies = build_auth_cookies(&request, &cookie_header_excluded);
+        assert_eq!(cookies.keys().collect::<Vec<_>>(), vec!["session"]);
+    }
+
+    #[test]
+    fn refresh_matches_entries_by_path_template() {
+        let har = json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": [
+            har_entry("POST", "https://staging.abc.xyz/v2/orders", None, "application/json", "{\"id\": 7, \"total\": 3}"),
+            har_entry("GET", "https://staging.abc.xyz/v2/orders/7?expand=items", None, "application/json", "{}"),
+            har_entry("GET", "https://staging.abc.xyz/v2/profile", None, "application/json", "{}"),
+        ]}});
+        let spec = har::from_reader(std::io::Cursor::new(har.to_string())).unwrap().log;
+        let entries = screen_entries(vec![], &spec, false).0;
+        let action = |order: usize, method: &str, url: &str| test_action(&format!("orders_{}", order), order, method, url);
+        let actions = vec![action(0, "POST", "/orders"), action(1, "GET", "/orders/{id}"), action(2, "DELETE", "/orders/{id}")];
+
+        let (matches, unmatched) = match_entries(&actions, entries, Some("https://abc.xyz/v2"));
+
+        let matched: Vec<(&str, Vec<(String, String)>)> = matches.iter()
+            .map(|(action, _, path_values)| (action.url.as_str(), path_values.clone()))
+            .collect();
+        assert_eq!(matched, vec![("/orders", vec![]), ("/orders/{id}", vec![("id".to_string(), "7".to_string())])]);
+        assert_eq!(unmatched.iter().map(|entry| entry.request.url.as_str()).collect::<Vec<_>>(), vec!["https://staging.abc.xyz/v2/profile"]);
+
+        let policy = SensitiveHeaderPolicy::default_for("cust1");
+        let parameter = |action: &Action, location: ParameterLocation, parameter_type: ParameterType, value: Value| Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action.id.clone())
+            .parameter_type(parameter_type)
+            .location(location)
+            .value(value)
+            .build();
+        let id_output = parameter(&actions[0], ParameterLocation::Body("$.id".to_string()), ParameterType::Output, json!(42));
+        let id_input = Parameter {
+            value_expression: Some(Expression { value: "$.orders_0.output.id".to_string() }),
+            ..parameter(&actions[1], ParameterLocation::Path("id".to_string()), ParameterType::Input, json!("42"))
+        };
+
+        let (updated, added) = refreshed_parameters(std::slice::from_ref(&id_output), recorded_parameters(matches[0].0, matches[0].1, &matches[0].2, &policy));
+        assert_eq!(updated.iter().map(|parameter| (parameter.id.clone(), parameter.value.clone())).collect::<Vec<_>>(), vec![(id_output.id, json!(7))]);
+        assert_eq!(added.iter().map(Parameter::get_path).collect::<Vec<_>>(), vec!["$.total"]);
+
+        let (updated, added) = refreshed_parameters(std::slice::from_ref(&id_input), recorded_parameters(matches[1].0, matches[1].1, &matches[1].2, &policy));
+        assert_eq!(updated[0].value, json!("7"));
+        assert_eq!(updated[0].value_expression, id_input.value_expression);
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_requests() {
+        let mut order = har_entry("GET", "https://staging.abc.xyz/v2/orders/7?expand=items", None, "application/json", "{}");
+        order["request"]["queryString"] = json!([{"name": "expand", "value": "items"}]);
+        let har = json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": [
+            har_entry("POST", "https://abc.xyz/v2/orders", Some("application/json"), "application/json", "{}"),
+            order,
+            har_entry("GET", "https://abc.xyz/v2/profile", None, "application/json", "{}"),
+        ]}});
+        let spec = har::from_reader(std::io::Cursor::new(har.to_string())).unwrap().log;
+        let entries = screen_entries(vec![], &spec, false).0;
+        let test_case = TestCase::builder()
+            .customer_id("cust1".to_string())
+            .id("tc1".to_string())
+            .name("orders".to_string())
+            .description("".to_string())
+            .base_url("https://abc.xyz/v2".to_string())
+            .build();
+        let action = |order: usize, method: &str, url: &str| test_action(&format!("orders_{}", order), order, method, url);
+        let actions = vec![action(0, "POST", "/orders"), action(1, "GET", "/orders/{id}"), action(2, "DELETE", "/orders/{id}")];
+        let path_parameter = Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(actions[1].id.clone())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Path("id".to_string()))
+            .value(json!("42"))
+            .build();
+
+        let diff = diff_actions(&test_case, &actions, &[path_parameter], entries, &SensitiveHeaderPolicy::default_for("cust1"));
+
+        assert_eq!(diff.added, vec![RecordedRequest { method: "GET".to_string(), url: "https://abc.xyz/v2/profile".to_string() }]);
+        assert_eq!(diff.removed.iter().map(|removed| removed.url.as_str()).collect::<Vec<_>>(), vec!["https://abc.xyz/v2/orders/{id}"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].action_id, actions[1].id);
+        assert_eq!(diff.changed[0].changes, vec![
+            RequestChange::BaseUrl { before: "https://abc.xyz".to_string(), after: "https://staging.abc.xyz".to_string() },
+            RequestChange::Parameter { location: ParameterLocation::Path("id".to_string()), before: Some(json!("42")), after: Some(json!("7")) },
+            RequestChange::Parameter { location: ParameterLocation::Query("expand".to_string()), before: None, after: Some(json!("items")) },
+        ]);
+    }
+
+    #[test]
+    fn refresh_updates_only_known_credentials() {
+        let credential = |value: &str| AuthHeaderValue::builder().value(value.to_string()).build();
+        let current = HashMap::from([("Authorization".to_string(), credential("Bearer old")), ("X-Tenant".to_string(), credential("t1"))]);
+        let recorded = HashMap::from([("authorization".to_string(), credential("Bearer new")), ("X-Tenant".to_string(), credential("t1")),
+                                      ("X-Trace".to_string(), credential("abc"))]);
+
+        let changed = changed_credentials(&current, Some(&recorded));
+
+        assert_eq!(changed.iter().map(|(name, value)| (name.as_str(), value.value.as_str())).collect::<Vec<_>>(), vec![("Authorization", "Bearer new")]);
+        assert!(changed_credentials(&current, None).is_empty());
+    }
+
+    #[test]
+    fn report_skipped_entries_and_unparseable_bodies() {
+        let har = json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": [
+            har_entry("GET", "https://abc.xyz/jobs/1", None, "application/json", "{\"status\": \"running\"}"),
+            har_entry("GET", "https://abc.xyz/jobs/1", None, "application/json", "{\"status\": \"done\"}"),
+            har_entry("GET", "https://abc.xyz/analytics/track", None, "application/json", "{}"),
+            har_entry("POST", "https://abc.xyz/upload", Some("multipart/form-data"), "application/json", "{}"),
+            har_entry("GET", "https://abc.xyz/logo.png", None, "image/png", ""),
+            har_entry("GET", "https://abc.xyz/broken", None, "application/json", "{not json"),
+        ]}});
+        let spec = har::from_reader(std::io::Cursor::new(har.to_string())).unwrap().log;
+
+        let (entries, skipped) = screen_entries(vec!["analytics".to_string()], &spec, true);
+
+        let urls: Vec<&str> = entries.iter().map(|entry| entry.request.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://abc.xyz/jobs/1", "https://abc.xyz/broken"]);
+        assert_eq!(entries[0].response.content.text.as_deref(), Some("{\"status\": \"done\"}"));
+        let reasons: Vec<SkipReason> = skipped.into_iter().map(|entry| entry.reason).collect();
+        assert_eq!(reasons, vec![
+            SkipReason::ExcludedPath { part: "analytics".to_string() },
+            SkipReason::UnsupportedRequestBody { mime_type: "multipart/form-data".to_string() },
+            SkipReason::UnsupportedResponseBody { mime_type: "image/png".to_string() },
+            SkipReason::RepeatedPolling,
+        ]);
+        assert!(entry_warnings("jobs_0", entries[0]).is_empty());
+        assert_eq!(entry_warnings("broken_1", entries[1]).len(), 1);
+    }
+
+    #[test]
+    fn record_notable_response_headers_as_outputs() {
+        let mut entry = har_entry("POST", "https://abc.xyz/orders", None, "application/json", "{\"id\": 42}");
+        entry["response"]["headers"] = json!([
+            {"name": "Location", "value": "/orders/42"},
+            {"name": "ETag", "value": "\"v1\""},
+            {"name": "Content-Type", "value": "application/json"},
+        ]);
+        let har = json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": [entry]}});
+        let spec = har::from_reader(std::io::Cursor::new(har.to_string())).unwrap().log;
+        let entry = screen_entries(vec![], &spec, false).0[0];
+        let action = test_action("orders_0", 0, "POST", "https://abc.xyz/orders");
+
+        let outputs = build_output_parameters(&action, entry);
+        let index = build_response_index(0, entry);
+
+        let header_outputs: Vec<(String, Value)> = outputs.iter()
+            .filter_map(|parameter| match &parameter.location {
+                ParameterLocation::Header(name) => Some((name.clone(), parameter.value.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(header_outputs, vec![
+            ("location".to_string(), json!("/orders/42")),
+            ("etag".to_string(), json!("\"v1\"")),
+        ]);
+        assert_eq!(index.get("$.orders_0.output_headers['location']"), Some(&json!("/orders/42")));
+        assert_eq!(index.get("$.orders_0.output.id"), Some(&json!(42)));
+
+        let (_, rebuilt) = build_existing_indexes(&[action], &outputs);
+        assert_eq!(rebuilt[0].get("$.orders_0.output_headers['etag']"), Some(&json!("\"v1\"")));
+    }
+
+    #[test]
+    fn factor_out_common_base_url() {
+        let har = json!({"log": {"version": "1.2", "creator": {"name": "test", "version": "1"}, "entries": [
+            har_entry("GET", "https://pay.abc.xyz/balance", None, "application/json", "{}"),
+            har_entry("POST", "https://api.abc.xyz/orders", None, "application/json", "{}"),
+            har_entry("GET", "https://api.abc.xyz/orders/42?expand=items", None, "application/json", "{}"),
+        ]}});
+        let spec = har::from_reader(std::io::Cursor::new(har.to_string())).unwrap().log;
+        let entries = screen_entries(vec![], &spec, false).0;
+
+        let base_url = common_base_url(&entries);
+
+        assert_eq!(base_url.as_deref(), Some("https://api.abc.xyz"));
+        assert_eq!(relative_to_test_case("https://api.abc.xyz/orders".to_string(), base_url.as_deref()), ("/orders".to_string(), None));
+        assert_eq!(relative_to_test_case("https://api.abc.xyz".to_string(), base_url.as_deref()), ("/".to_string(), None));
+        assert_eq!(relative_to_test_case("https://pay.abc.xyz/balance".to_string(), base_url.as_deref()),
+                   ("/balance".to_string(), Some("https://pay.abc.xyz".to_string())));
+        assert_eq!(relative_to_test_case("https://api.abc.xyz/v2/orders".to_string(), Some("https://api.abc.xyz/v2/")),
+                   ("/orders".to_string(), None));
+        assert_eq!(relative_to_test_case("https://api.abc.xyz/orders".to_string(), None),
+                   ("https://api.abc.xyz/orders".to_string(), None));
+        assert_eq!(common_base_url(&[]), None);
+    }
+
+    #[test]
+    fn flatten_round_trips_in_order() {
+        let action = test_action("orders_0", 0, "POST", "https://abc.xyz/orders");
+        let body = json!({
+            "zeta": 1,
+            "a.b": {"content-type": "json", "it's": true},
+            "items": [{"id": 2, "tags": [["x", "y"]]}],
+            "alpha": null,
+        });
+
+        let parameters = build_output_parameters_from_value(&action, &body);
+
+        let paths: Vec<String> = parameters.iter().map(|parameter| parameter.get_path()).collect();
+        assert_eq!(paths, vec![
+            "$.zeta", "$['a.b']['content-type']", "$['a.b']['it\\'s']",
+            "$.items[0].id", "$.items[0].tags[0][0]", "$.items[0].tags[0][1]", "$.alpha",
+        ]);
+        let rebuilt = response_from_outputs(&parameters);
+        assert_eq!(rebuilt, body);
+        assert_eq!(serde_json::to_string(&rebuilt).unwrap(), serde_json::to_string(&body).unwrap());
+        let top_level = build_output_parameters_from_value(&action, &json!([{"id": 1}]));
+        assert_eq!(top_level[0].get_path(), "$[0].id");
+    }
+
+    #[test]
+    fn existing_indexes_follow_action_order() {
+        let login = test_action("login_1", 1, "POST", "https://abc.xyz/login");
+        let token = build_output_parameters_from_value(&login, &json!({"token": "secret-token"}));
+
+        let (request_indexes, response_indexes) = build_existing_indexes(&[login], &token);
+
+        assert_eq!(request_indexes.len(), 2);
+        assert!(response_indexes[0].is_empty());
+        assert_eq!(response_indexes[1].get("$.login_1.output.token"), Some(&json!("secret-token")));
+        assert_eq!(
+            resolve_value_expression_from_prev(2, "token", &json!("secret-token"), &response_indexes),
+            Some(Expression { value: "$.login_1.output.token".to_string() })
+        );
+    }
 
     #[tokio::test]
     async fn build_header_name() {
@@ -746,7 +1778,7 @@ mod tests {
             Spec::V1_2(log) => {
                 log.entries.iter()
                     .for_each(|entries: &Entries| {
-                        let map = build_auth_headers(&entries.request);
+                        let map = build_auth_headers(&entries.request, &SensitiveHeaderPolicy::default_for("cust1"));
                         println!("{:#?}", map);
                     })
             }
@@ -758,16 +1790,21 @@ mod tests {
     async fn build_action_url() {
         let action0_index = HashMap::from([(String::from("$.action0.output.issueKey"), Value::String(String::from("TEST-1")))]);
         let response_indexes: Vec<HashMap<String, Value>> = Vec::from([action0_index]);
-        let actual = build_url_without_query_params(1, &"https://abc.xyz/TEST-1/comment".to_string(), &response_indexes);
-        assert_eq!("https://abc.xyz/$.action0.output.issueKey/comment", actual.as_str());
+        let actual = build_url_template(1, &"https://abc.xyz/TEST-1/comment".to_string(), &response_indexes);
+        assert_eq!("https://abc.xyz/{issueKey}/comment", actual.url.as_str());
+        assert_eq!(actual.path_parameters, vec![(
+            "issueKey".to_string(),
+            "TEST-1".to_string(),
+            Expression { value: "$.action0.output.issueKey".to_string() },
+        )]);
     }
 
     #[tokio::test]
     async fn test_build_action_url_with_params() {
         let action0_index = HashMap::from([(String::from("$.action0.output.issueKey"), Value::String(String::from("")))]);
         let response_indexes: Vec<HashMap<String, Value>> = Vec::from([action0_index]);
-        let actual = build_url_without_query_params(1, &"https://layima.atlassian.net/rest/dev-status/1.0/issue/create-branch-targets?issueId=10000".to_string(), &response_indexes);
-        assert_eq!("https://layima.atlassian.net/rest/dev-status/1.0/issue/create-branch-targets", actual.as_str());
+        let actual = build_url_template(1, &"https://layima.atlassian.net/rest/dev-status/1.0/issue/create-branch-targets?issueId=10000".to_string(), &response_indexes);
+        assert_eq!("https://layima.atlassian.net/rest/dev-status/1.0/issue/create-branch-targets", actual.url.as_str());
     }
 
     #[tokio::test]
@@ -775,4 +1812,45 @@ mod tests {
         let actual = build_action_name_from_url(1, &"https://layima.atlassian.net/jsw2/graphql?operation=BoardCardCreate".to_string());
         assert_eq!("graphql_board_card_create_1", actual.as_str());
     }
+
+    #[test]
+    fn prefer_similar_key_and_type() {
+        let create_issue_index = HashMap::from([
+            (String::from("$.create_issue_0.output.id"), json!(10042)),
+            (String::from("$.create_issue_0.output.projectId"), json!(10042)),
+            (String::from("$.create_issue_0.output.fields.sprint"), json!("10042")),
+        ]);
+        let indexes = vec![create_issue_index];
+
+        assert_eq!(resolve_value_expression_from_slice_index("issueId", &&json!("10042"), &indexes).unwrap().value,
+                   "$.create_issue_0.output.id");
+        assert_eq!(resolve_value_expression_from_slice_index("$.projectId", &&json!(10042), &indexes).unwrap().value,
+                   "$.create_issue_0.output.projectId");
+        assert_eq!(resolve_value_expression_from_slice_index("sprintName", &&json!("10042"), &indexes).unwrap().value,
+                   "$.create_issue_0.output.fields.sprint");
+    }
+
+    #[test]
+    fn skip_trivial_values() {
+        let index = HashMap::from([
+            (String::from("$.login_0.output.active"), json!(true)),
+            (String::from("$.login_0.output.count"), json!(1)),
+            (String::from("$.login_0.output.flag"), json!("true")),
+            (String::from("$.login_0.output.code"), json!("ok")),
+        ]);
+        let indexes = vec![index];
+
+        assert!(resolve_value_expression_from_slice_index("active", &&json!(true), &indexes).is_none());
+        assert!(resolve_value_expression_from_slice_index("count", &&json!("1"), &indexes).is_none());
+        assert!(resolve_value_expression_from_slice_index("flag", &&json!("true"), &indexes).is_none());
+        assert!(resolve_value_expression_from_slice_index("code", &&json!("ok"), &indexes).is_none());
+    }
+
+    #[test]
+    fn key_similarity_scores() {
+        assert_eq!(key_similarity("issueId", "$.create_issue_0.output.id"), 0.9);
+        assert_eq!(key_similarity("$.data.id", "$.create_issue_0.output.id"), 1.0);
+        assert_eq!(key_similarity("userIds", "$.create_issue_0.output.id"), 0.5);
+        assert_eq!(key_similarity("name", "$.create_issue_0.output.id"), 0.0);
+    }
 }
@@ -0,0 +1,91 @@
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::header_rule::model::{HeaderRule, HeaderRuleMode};
+use crate::persistence::repo::Repository;
+use crate::validation::{ValidJson, Validate, check_not_blank};
+use axum::extract::{Path, Query, State};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+pub async fn create_header_rule(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<HeaderRulePayload>,
+) -> Result<ApiResponse<HeaderRule>, AppError> {
+    let result = repository.header_rules()
+        .put(payload.into_rule(uuid::Uuid::new_v4().to_string()))
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn update_header_rule(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<HeaderRulePayload>,
+) -> Result<ApiResponse<HeaderRule>, AppError> {
+    repository.header_rules()
+        .get(&"eren".to_string(), &id)
+        .await?
+        .ok_or(AppError::NotFound("Header rule not found!".to_string()))?;
+    let result = repository.header_rules().put(payload.into_rule(id)).await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_header_rule(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<HeaderRule>, AppError> {
+    let result = repository.header_rules().get(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn list_header_rules(
+    State(repository): State<Repository>,
+    Query(params): Query<ListHeaderRuleParams>,
+) -> Result<ApiResponse<Vec<HeaderRule>>, AppError> {
+    let result = match params.test_case_id {
+        None => repository.header_rules().list_all(&"eren".to_string()).await,
+        Some(test_case_id) => repository.header_rules().list_applicable(&"eren".to_string(), &test_case_id).await,
+    };
+    ApiResponse::from(result)
+}
+
+pub async fn delete_header_rule(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<HeaderRule>, AppError> {
+    let result = repository.header_rules().delete(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct HeaderRulePayload {
+    pub test_case_id: Option<String>,
+    pub name: String,
+    pub value: String,
+    pub mode: Option<HeaderRuleMode>,
+    pub disabled: Option<bool>,
+}
+
+impl HeaderRulePayload {
+    fn into_rule(self, id: String) -> HeaderRule {
+        HeaderRule::builder()
+            .customer_id("eren".to_string())
+            .id(id)
+            .maybe_test_case_id(self.test_case_id)
+            .name(self.name)
+            .value(self.value)
+            .mode(self.mode.unwrap_or_default())
+            .disabled(self.disabled.unwrap_or(false))
+            .build()
+    }
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct ListHeaderRuleParams {
+    pub test_case_id: Option<String>,
+}
+
+impl Validate for HeaderRulePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        check_not_blank("name", &self.name).into_iter().collect()
+    }
+}
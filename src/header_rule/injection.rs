@@ -0,0 +1,57 @@
+use crate::header_rule::model::{HeaderRule, HeaderRuleMode};
+use crate::http::ReqParam;
+
+/// Applies enabled rules on top of the given headers. Customer-wide rules go first so that
+/// test case rules win when both target the same header.
+pub fn apply_header_rules(headers: &mut Vec<ReqParam>, rules: &[HeaderRule]) {
+    let ordered = rules.iter()
+        .filter(|rule| !rule.disabled && rule.test_case_id.is_none())
+        .chain(rules.iter().filter(|rule| !rule.disabled && rule.test_case_id.is_some()));
+    for rule in ordered {
+        let present = headers.iter().any(|header| header.key.eq_ignore_ascii_case(&rule.name));
+        match rule.mode {
+            HeaderRuleMode::SetIfMissing if present => {}
+            _ => {
+                headers.retain(|header| !header.key.eq_ignore_ascii_case(&rule.name));
+                headers.push(ReqParam::new(rule.name.clone(), rule.value.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(test_case_id: Option<&str>, name: &str, value: &str, mode: HeaderRuleMode) -> HeaderRule {
+        HeaderRule::builder()
+            .customer_id("cust1".to_string())
+            .maybe_test_case_id(test_case_id.map(|id| id.to_string()))
+            .name(name.to_string())
+            .value(value.to_string())
+            .mode(mode)
+            .build()
+    }
+
+    #[test]
+    fn test_case_rules_win_over_customer_rules() {
+        let mut headers = vec![
+            ReqParam::new("user-agent".to_string(), "Mozilla".to_string()),
+            ReqParam::new("x-trace".to_string(), "abc".to_string()),
+        ];
+        let rules = vec![
+            rule(Some("tc1"), "X-Env", "qa", HeaderRuleMode::Override),
+            rule(None, "X-Env", "staging", HeaderRuleMode::Override),
+            rule(None, "User-Agent", "parroton", HeaderRuleMode::Override),
+            rule(None, "X-Trace", "ignored", HeaderRuleMode::SetIfMissing),
+        ];
+
+        apply_header_rules(&mut headers, &rules);
+
+        assert_eq!(headers, vec![
+            ReqParam::new("x-trace".to_string(), "abc".to_string()),
+            ReqParam::new("User-Agent".to_string(), "parroton".to_string()),
+            ReqParam::new("X-Env".to_string(), "qa".to_string()),
+        ]);
+    }
+}
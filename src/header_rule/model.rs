@@ -0,0 +1,29 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A header the executor injects into every request of a customer, or of a single test case when `test_case_id` is set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct HeaderRule {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub test_case_id: Option<String>,
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub mode: HeaderRuleMode,
+    #[serde(default)]
+    #[builder(default = false)]
+    pub disabled: bool,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, ToSchema)]
+pub enum HeaderRuleMode {
+    #[default]
+    Override,
+    SetIfMissing,
+}
@@ -0,0 +1,72 @@
+use crate::api::AppError;
+use crate::header_rule::model::HeaderRule;
+use crate::persistence::repo::Table;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct HeaderRuleOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct HeaderRulesTable();
+
+impl Table<HeaderRule> for HeaderRulesTable {
+    fn table_name() -> String {
+        "header_rules".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &HeaderRule) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &HeaderRule) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl HeaderRuleOperations {
+    pub async fn put(&self, header_rule: HeaderRule) -> Result<HeaderRule, AppError> {
+        HeaderRulesTable::put_item(self.client.clone(), header_rule).await
+    }
+
+    pub async fn get(&self, customer_id: &String, id: &String) -> Result<Option<HeaderRule>, AppError> {
+        HeaderRulesTable::get_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    pub async fn list_all(&self, customer_id: &String) -> Result<Vec<HeaderRule>, AppError> {
+        HeaderRulesTable::list_all_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    pub async fn list_applicable(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<HeaderRule>, AppError> {
+        Ok(self.list_all(customer_id)
+            .await?
+            .into_iter()
+            .filter(|rule| rule.test_case_id.as_ref().is_none_or(|id| id == test_case_id))
+            .collect())
+    }
+
+    pub async fn delete(&self, customer_id: &String, id: &String) -> Result<Option<HeaderRule>, AppError> {
+        HeaderRulesTable::delete_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    /// Deletes the rules scoped to the test case, customer-wide ones are kept.
+    pub async fn delete_all_of_test_case(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<HeaderRule>, AppError> {
+        let header_rules: Vec<HeaderRule> = self.list_all(customer_id)
+            .await?
+            .into_iter()
+            .filter(|rule| rule.test_case_id.as_ref() == Some(test_case_id))
+            .collect();
+        let keys = header_rules.iter().map(|rule| (rule.customer_id.clone(), rule.id.clone())).collect();
+        HeaderRulesTable::batch_delete_items(self.client.clone(), keys).await?;
+        Ok(header_rules)
+    }
+}
@@ -1,11 +1,20 @@
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::{Client, Method, RequestBuilder, Url};
+use crate::action::model::RedirectPolicy;
+use crate::artifact::store::{artifact_store, store_binary_body};
+use crate::auth::model::RequestSigning;
+use crate::config::AppConfig;
+use crate::signing::sign_request;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION};
+use reqwest::{Client, Method, Request, RequestBuilder, Response, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::net::SocketAddr;
 use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use sha2::{Digest, Sha256};
 use tracing::log::info;
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
 pub struct ReqParam {
     pub key: String,
     pub value: String,
@@ -17,7 +26,7 @@ impl ReqParam {
     }
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ReqBody {
     pub value: Option<Value>,
 }
@@ -68,7 +77,7 @@ impl Endpoint {
             let query = self
                 .query_params
                 .iter()
-                .map(|param| format!("{}={}", param.key, param.value))
+                .map(|param| format!("{}={}", percent_encode(&param.key), percent_encode(&param.value)))
                 .collect::<Vec<String>>()
                 .join("&");
             format!("{}?{}", self.path, query)
@@ -76,10 +85,99 @@ impl Endpoint {
     }
 }
 
+/// Percent-encodes everything but RFC 3986 unreserved characters.
+pub fn percent_encode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+/// Decodes `%XX` sequences and `+` as space; malformed sequences are kept as they are.
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() && bytes[index + 1].is_ascii_hexdigit() && bytes[index + 2].is_ascii_hexdigit() => {
+                let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).unwrap();
+                decoded.push(u8::from_str_radix(hex, 16).unwrap());
+                index += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Serializes a flat object as `application/x-www-form-urlencoded`, repeating the key for array values.
+pub fn encode_form_body(body: &Value) -> String {
+    let fields = match body {
+        Value::Object(fields) => fields,
+        _ => return percent_encode(&body.to_string()),
+    };
+    fields.iter()
+        .flat_map(|(key, value)| match value {
+            Value::Array(items) => items.iter().map(|item| (key, item)).collect::<Vec<(&String, &Value)>>(),
+            value => vec![(key, value)],
+        })
+        .map(|(key, value)| {
+            let text = value.as_str().map_or_else(|| value.to_string(), |text| text.to_string());
+            format!("{}={}", percent_encode(key), percent_encode(&text))
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Describes a response body that is not JSON; only its fingerprint is kept in the run context.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct BinaryBody {
+    pub content_type: Option<String>,
+    pub size: usize,
+    pub sha256: String,
+    /// `s3://bucket/key` of the bytes when an artifact bucket is configured.
+    pub stored_at: Option<String>,
+}
+
+/// Parses JSON bodies as they are and replaces anything else with a [`BinaryBody`] descriptor.
+pub fn parse_response_body(content_type: Option<String>, bytes: &[u8]) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    if let Ok(parsed) = serde_json::from_slice::<Value>(bytes) {
+        return parsed;
+    }
+    let sha256 = Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    serde_json::to_value(BinaryBody {
+        content_type,
+        size: bytes.len(),
+        sha256,
+        stored_at: None,
+    }).unwrap()
+}
+
 pub struct HttpRequest {
     pub endpoint: Endpoint,
     pub req_body: ReqBody,
     pub content_type: String,
+    pub redirect_policy: RedirectPolicy,
+    pub signing: Option<RequestSigning>,
 }
 
 impl HttpRequest {
@@ -88,12 +186,44 @@ impl HttpRequest {
             endpoint,
             req_body,
             content_type,
+            redirect_policy: RedirectPolicy::default(),
+            signing: None,
         }
     }
 
+    pub fn with_signing(mut self, signing: Option<RequestSigning>) -> Self {
+        self.signing = signing;
+        self
+    }
+
+    pub fn with_redirect_policy(mut self, redirect_policy: RedirectPolicy) -> Self {
+        self.redirect_policy = redirect_policy;
+        self
+    }
+
     pub fn get_body(&self) -> Option<Value> {
         self.req_body.value.clone()
     }
+
+    /// The body the way `ApiClient` encodes it for the content type, empty without a body.
+    pub fn body_text(&self) -> String {
+        match &self.req_body.value {
+            None => String::new(),
+            Some(body) if self.content_type.contains("application/x-www-form-urlencoded") => encode_form_body(body),
+            Some(body) if self.content_type.contains("json") => body.to_string(),
+            Some(body) => body.as_str().map_or_else(|| body.to_string(), |text| text.to_string()),
+        }
+    }
+
+    /// The Content-Type `ApiClient` sends for the body, which is `application/json` for JSON
+    /// bodies whatever parameters the content type has, none without a body.
+    pub fn sent_content_type(&self) -> Option<String> {
+        self.req_body.value.as_ref().map(|_| match self.content_type.as_str() {
+            content_type if content_type.contains("application/x-www-form-urlencoded") => content_type.to_string(),
+            content_type if content_type.contains("json") => "application/json".to_string(),
+            content_type => content_type.to_string(),
+        })
+    }
 }
 
 pub struct ResBody<T> {
@@ -109,6 +239,11 @@ impl<T> ResBody<T> {
 pub struct HttpResult<T> {
     pub res_body: ResBody<T>,
     pub status_code: u16,
+    pub redirects: Vec<RedirectHop>,
+    /// Response headers with lowercased names, in the order they were received.
+    pub headers: Vec<(String, String)>,
+    /// Size of the response body as received, unknown for results that did not come over the wire.
+    pub body_bytes: Option<u64>,
 }
 
 impl<T> HttpResult<T> {
@@ -116,8 +251,27 @@ impl<T> HttpResult<T> {
         Self {
             res_body,
             status_code,
+            redirects: vec![],
+            headers: vec![],
+            body_bytes: None,
         }
     }
+
+    pub fn with_headers(self, headers: Vec<(String, String)>) -> Self {
+        Self { headers, ..self }
+    }
+
+    pub fn with_body_bytes(self, body_bytes: usize) -> Self {
+        Self { body_bytes: Some(body_bytes as u64), ..self }
+    }
+}
+
+/// A 3xx response observed while executing a request.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RedirectHop {
+    pub status_code: u16,
+    pub url: String,
+    pub location: String,
 }
 #[derive(Clone)]
 pub enum HttpError {
@@ -185,43 +339,72 @@ impl FromStr for HttpMethod {
 }
 
 impl ApiClient {
-    pub fn new() -> Self {
+    /// One client is shared by every run, so connections to a host are pooled across runs.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut builder = Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .pool_idle_timeout(Duration::from_secs(config.http_pool_idle_timeout_secs))
+            .tcp_keepalive(config.http_tcp_keepalive_secs.map(Duration::from_secs));
+        if let Some(max_idle) = config.http_pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if !config.http2 {
+            builder = builder.http1_only();
+        }
+        for (host, address) in &config.http_dns_overrides {
+            // the port is taken from the URL, not from the override
+            builder = builder.resolve(host, SocketAddr::new(*address, 0));
+        }
         Self {
-            client: Client::new(),
+            client: builder.build().unwrap(),
         }
     }
 
     pub async fn execute(&self, request: HttpRequest) -> Result<HttpResult<Value>, HttpError> {
         info!("will execute http request!");
-        let req = self.build_reqwest(request);
-        let result = req.send().await;
-        match result {
-            Ok(response) => {
-                let status_code = response.status();
-                info!("http request executed, status_code: {}", status_code);
-                if status_code.is_success() {
-                    let response_string = response.text().await.unwrap();
-                    let parsed: Value = serde_json::from_str(&response_string).unwrap();
-                    Ok(HttpResult::new(ResBody::new(parsed), status_code.as_u16()))
-                } else if status_code.is_client_error() {
-                    let text = response.text().await.unwrap();
-                    info!("http request failed: {}", text);
-                    Err(HttpError::Status(
-                        status_code.as_u16(),
-                        StatusError::ClientError(text),
-                    ))
-                } else {
-                    Err(HttpError::Status(
-                        status_code.as_u16(),
-                        StatusError::ServerError(response.text().await.unwrap()),
-                    ))
-                }
-            }
-            Err(error) => {
+        let redirect_policy = request.redirect_policy.clone();
+        let signing = request.signing.clone();
+        let mut current = self.build_reqwest(request)
+            .build()
+            .map_err(|error| HttpError::Io(error.to_string()))?;
+        let signed_host = current.url().host_str().map(|host| host.to_string());
+        if let Some(signing) = &signing {
+            sign_request(&mut current, signing, SystemTime::now());
+        }
+        let mut redirects: Vec<RedirectHop> = vec![];
+        let response = loop {
+            let retry = current.try_clone();
+            let method = current.method().clone();
+            let response = self.client.execute(current).await.map_err(|error| {
                 info!("http request failed: {}", error);
-                Err(HttpError::Io(error.to_string()))
+                HttpError::Io(error.to_string())
+            })?;
+            let Some(location) = redirect_location(&response) else {
+                break response;
+            };
+            redirects.push(RedirectHop {
+                status_code: response.status().as_u16(),
+                url: response.url().to_string(),
+                location: location.to_string(),
+            });
+            let max_hops = match redirect_policy {
+                RedirectPolicy::DontFollow => break response,
+                RedirectPolicy::Follow { max_hops } | RedirectPolicy::Record { max_hops } => max_hops,
+            };
+            if redirects.len() > max_hops {
+                return Err(HttpError::Io(format!("too many redirects, limit is {}", max_hops)));
+            }
+            let retry = retry.ok_or(HttpError::Io("request body can not be replayed for redirect".to_string()))?;
+            current = follow_redirect(retry, &method, response.status().as_u16(), location);
+            if let Some(signing) = signing.as_ref().filter(|_| current.url().host_str() == signed_host.as_deref()) {
+                sign_request(&mut current, signing, SystemTime::now());
             }
+        };
+        let mut result = handle_response(response).await?;
+        if redirect_policy.records_hops() {
+            result.redirects = redirects;
         }
+        Ok(result)
     }
 
     fn build_reqwest(&self, request: HttpRequest) -> RequestBuilder {
@@ -254,15 +437,81 @@ impl ApiClient {
         if let Some(body) = &req_body.value {
             info!("request body: {}", &body.to_string());
             if content_type.contains("application/x-www-form-urlencoded") {
-                req = req.form(&body);
-            } else {
+                req = req.header(CONTENT_TYPE, content_type.as_str())
+                    .body(encode_form_body(body));
+            } else if content_type.contains("json") {
                 req = req.json(&body);
+            } else {
+                let text = body.as_str().map_or_else(|| body.to_string(), |text| text.to_string());
+                req = req.header(CONTENT_TYPE, content_type.as_str()).body(text);
             }
         }
         req
     }
 }
 
+async fn handle_response(response: Response) -> Result<HttpResult<Value>, HttpError> {
+    let status_code = response.status();
+    info!("http request executed, status_code: {}", status_code);
+    if status_code.is_success() || status_code.is_redirection() {
+        let content_type = response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let headers = response.headers()
+            .iter()
+            .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.as_str().to_lowercase(), value.to_string())))
+            .collect();
+        let bytes = response.bytes().await
+            .map_err(|err| HttpError::Io(err.to_string()))?;
+        let mut parsed = parse_response_body(content_type, &bytes);
+        if let Some(store) = artifact_store() {
+            parsed = store_binary_body(store, parsed, &bytes).await;
+        }
+        Ok(HttpResult::new(ResBody::new(parsed), status_code.as_u16())
+            .with_headers(headers)
+            .with_body_bytes(bytes.len()))
+    } else if status_code.is_client_error() {
+        let text = response.text().await.unwrap();
+        info!("http request failed: {}", text);
+        Err(HttpError::Status(
+            status_code.as_u16(),
+            StatusError::ClientError(text),
+        ))
+    } else {
+        Err(HttpError::Status(
+            status_code.as_u16(),
+            StatusError::ServerError(response.text().await.unwrap()),
+        ))
+    }
+}
+
+fn redirect_location(response: &Response) -> Option<Url> {
+    if !response.status().is_redirection() {
+        return None;
+    }
+    response.headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|location| response.url().join(location).ok())
+}
+
+/// Rewrites the request for the next hop the way browsers do: 303, and 301/302 after a POST, become a body-less GET.
+fn follow_redirect(mut request: Request, method: &Method, status_code: u16, location: Url) -> Request {
+    if status_code == 303 || ((status_code == 301 || status_code == 302) && *method == Method::POST) {
+        *request.method_mut() = Method::GET;
+        *request.body_mut() = None;
+        request.headers_mut().remove(CONTENT_TYPE);
+        request.headers_mut().remove(CONTENT_LENGTH);
+    }
+    if request.url().host_str() != location.host_str() {
+        request.headers_mut().remove(AUTHORIZATION);
+        request.headers_mut().remove(COOKIE);
+    }
+    *request.url_mut() = location;
+    request
+}
+
 fn build_cookie_header(cookies: &Vec<ReqParam>) -> HeaderValue {
     let header_value = cookies
         .iter()
@@ -271,3 +520,76 @@ fn build_cookie_header(cookies: &Vec<ReqParam>) -> HeaderValue {
         .join(";");
     HeaderValue::from_str(&header_value).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_url_encodes_repeated_query_params() {
+        let endpoint = Endpoint::new(
+            HttpMethod::GET,
+            "https://abc.xyz/items".to_string(),
+            vec![],
+            vec![
+                ReqParam::new("id".to_string(), "1".to_string()),
+                ReqParam::new("id".to_string(), "2".to_string()),
+                ReqParam::new("q".to_string(), "a b&c=d".to_string()),
+                ReqParam::new("tags[]".to_string(), "x/y".to_string()),
+            ],
+            vec![],
+        );
+        assert_eq!(endpoint.to_url(), "https://abc.xyz/items?id=1&id=2&q=a%20b%26c%3Dd&tags%5B%5D=x%2Fy");
+    }
+
+    #[test]
+    fn decode_query_values() {
+        assert_eq!(percent_decode("a%20b+c%26"), "a b c&");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode(&percent_encode("çay & su")), "çay & su");
+    }
+
+    #[test]
+    fn parse_binary_response_body() {
+        assert_eq!(parse_response_body(Some("application/json".to_string()), b"{\"a\":1}"), serde_json::json!({"a": 1}));
+        assert_eq!(parse_response_body(None, b""), Value::Null);
+        let parsed: BinaryBody = serde_json::from_value(parse_response_body(Some("application/pdf".to_string()), b"%PDF-1.4")).unwrap();
+        assert_eq!(parsed.content_type, Some("application/pdf".to_string()));
+        assert_eq!(parsed.size, 8);
+        assert_eq!(parsed.sha256.len(), 64);
+        assert_eq!(parsed.stored_at, None);
+    }
+
+    #[test]
+    fn post_redirect_becomes_get_without_credentials_across_hosts() {
+        let mut request = Request::new(Method::POST, Url::parse("https://abc.xyz/login").unwrap());
+        request.headers_mut().insert(AUTHORIZATION, HeaderValue::from_static("Bearer t"));
+        request.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        *request.body_mut() = Some("{}".into());
+
+        let next = follow_redirect(request, &Method::POST, 302, Url::parse("https://sso.xyz/callback").unwrap());
+
+        assert_eq!(next.method(), Method::GET);
+        assert!(next.body().is_none());
+        assert!(next.headers().get(AUTHORIZATION).is_none());
+        assert!(next.headers().get(CONTENT_TYPE).is_none());
+        assert_eq!(next.url().as_str(), "https://sso.xyz/callback");
+    }
+
+    #[test]
+    fn temporary_redirect_keeps_method_and_body() {
+        let mut request = Request::new(Method::PUT, Url::parse("https://abc.xyz/items/1").unwrap());
+        *request.body_mut() = Some("{}".into());
+
+        let next = follow_redirect(request, &Method::PUT, 307, Url::parse("https://abc.xyz/items/2").unwrap());
+
+        assert_eq!(next.method(), Method::PUT);
+        assert!(next.body().is_some());
+    }
+
+    #[test]
+    fn encode_form_with_repeated_fields() {
+        let body = serde_json::json!({"user": "a b", "role": ["admin", "dev"], "age": 3});
+        assert_eq!(encode_form_body(&body), "user=a%20b&role=admin&role=dev&age=3");
+    }
+}
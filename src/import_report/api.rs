@@ -0,0 +1,12 @@
+use crate::api::{ApiResponse, AppError};
+use crate::import_report::model::ImportReport;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn get_import_report(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<ImportReport>, AppError> {
+    let result = repository.import_reports().get(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
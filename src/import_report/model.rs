@@ -0,0 +1,112 @@
+use crate::parameter::model::ParameterLocation;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// What happened to the entries of the last HAR imported into a test case.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct ImportReport {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub total_entries: usize,
+    pub imported_entries: usize,
+    pub skipped_entries: usize,
+    #[serde(default)]
+    #[builder(default)]
+    pub skipped: Vec<SkippedEntry>,
+    #[serde(default)]
+    #[builder(default)]
+    pub warnings: Vec<ImportWarning>,
+    /// Test cases of the customer with the same action sequence as the imported one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub duplicate_test_case_ids: Vec<String>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SkippedEntry {
+    pub method: String,
+    pub url: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum SkipReason {
+    ExcludedPath { part: String },
+    UnsupportedRequestBody { mime_type: String },
+    UnsupportedResponseBody { mime_type: String },
+    /// Dropped in favour of the last request of a run of identical polling requests.
+    RepeatedPolling,
+    UnsupportedHarVersion,
+    /// Left out of a refresh because no action of the test case has its method and path.
+    NoMatchingAction,
+}
+
+/// Problem with an imported entry, e.g. a body that is not valid JSON and yielded no parameters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ImportWarning {
+    pub url: Option<String>,
+    pub action_name: Option<String>,
+    pub message: String,
+}
+
+/// What a re-recording of a test case's flow changed in place.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RefreshReport {
+    pub test_case_id: String,
+    pub refreshed_action_ids: Vec<String>,
+    /// Actions no entry of the recording matched, left as they were.
+    pub unmatched_action_ids: Vec<String>,
+    pub updated_parameters: usize,
+    /// Response values the earlier recording did not have, added as outputs.
+    pub added_outputs: usize,
+    pub updated_auth_provider_ids: Vec<String>,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// How a new recording differs from the requests of a test case.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct HarDiff {
+    pub test_case_id: String,
+    /// Recorded requests no action has the method and path of.
+    pub added: Vec<RecordedRequest>,
+    /// Actions no recorded request matched.
+    pub removed: Vec<RemovedRequest>,
+    pub changed: Vec<ChangedRequest>,
+    pub skipped: Vec<SkippedEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RemovedRequest {
+    pub action_id: String,
+    pub action_name: String,
+    pub method: String,
+    pub url: String,
+}
+
+/// An action and the recorded request matching it, with the URL as recorded.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ChangedRequest {
+    pub action_id: String,
+    pub action_name: String,
+    pub method: String,
+    pub url: String,
+    pub changes: Vec<RequestChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RequestChange {
+    BaseUrl { before: String, after: String },
+    /// An input added to the request has no `before`, one no longer sent has no `after`.
+    Parameter { location: ParameterLocation, before: Option<Value>, after: Option<Value> },
+}
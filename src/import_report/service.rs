@@ -0,0 +1,49 @@
+use crate::api::AppError;
+use crate::import_report::model::ImportReport;
+use crate::persistence::repo::Table;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct ImportReportOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct ImportReportsTable();
+
+impl Table<ImportReport> for ImportReportsTable {
+    fn table_name() -> String {
+        "import_reports".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "test_case_id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &ImportReport) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &ImportReport) -> (String, AttributeValue) {
+        Self::sort_key(entity.test_case_id.clone())
+    }
+}
+
+impl ImportReportOperations {
+    /// Replaces the report of the test case's previous import.
+    pub async fn put(&self, report: ImportReport) -> Result<ImportReport, AppError> {
+        ImportReportsTable::put_item(self.client.clone(), report).await
+    }
+
+    pub async fn get(&self, customer_id: &String, test_case_id: &String) -> Result<Option<ImportReport>, AppError> {
+        ImportReportsTable::get_item(self.client.clone(), customer_id.clone(), test_case_id.clone()).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, test_case_id: &String) -> Result<Option<ImportReport>, AppError> {
+        ImportReportsTable::delete_item(self.client.clone(), customer_id.clone(), test_case_id.clone()).await
+    }
+}
@@ -1,19 +1,44 @@
+use crate::api::{ApiResponse, AppError};
+use crate::json_path::model::Expression;
 use crate::json_path::utils;
 use crate::persistence::repo::Repository;
+use crate::validation::AppJson;
 use axum::extract::State;
-use axum::response::IntoResponse;
 use axum::Json;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
 
 pub async fn auto_complete(
     State(repository): State<Repository>,
-    Json(auto_complete_request): Json<AutoCompleteRequest>,
-) -> impl IntoResponse {
+    AppJson(auto_complete_request): AppJson<AutoCompleteRequest>,
+) -> Json<Vec<String>> {
     let result = utils::auto_complete(&repository, auto_complete_request).await;
     Json(result)
 }
 
-#[derive(Deserialize)]
+/// Evaluates an expression against a caller supplied document, without touching any test case.
+pub async fn evaluate(
+    AppJson(request): AppJson<EvaluateRequest>,
+) -> Result<ApiResponse<EvaluateResponse>, AppError> {
+    let values = utils::evaluate_expression(&request.context, &Expression { value: request.expression })
+        .map_err(AppError::Validation)?;
+    ApiResponse::from(Ok(EvaluateResponse { values }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EvaluateRequest {
+    pub expression: String,
+    #[serde(default)]
+    pub context: Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EvaluateResponse {
+    pub values: Vec<Value>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct AutoCompleteRequest {
     pub customer_id: String,
     pub test_case_id: String,
@@ -0,0 +1,84 @@
+use serde_json_path::JsonPath;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Enough for the expressions of a few large test cases running side by side.
+const CAPACITY: usize = 4096;
+
+static COMPILED_PATHS: OnceLock<PathCache> = OnceLock::new();
+
+/// Parses the expression, or takes it from the shared cache when it was parsed before.
+pub fn compile(expression: &str) -> Result<Arc<JsonPath>, String> {
+    COMPILED_PATHS.get_or_init(|| PathCache::new(CAPACITY)).compile(expression)
+}
+
+/// Compiled paths keyed by their expression; the least recently used one makes room when full.
+/// Expressions that fail to parse are not kept.
+pub struct PathCache {
+    capacity: usize,
+    entries: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    tick: u64,
+    paths: HashMap<String, (Arc<JsonPath>, u64)>,
+}
+
+impl PathCache {
+    pub fn new(capacity: usize) -> Self {
+        PathCache {
+            capacity,
+            entries: Mutex::new(CacheEntries::default()),
+        }
+    }
+
+    pub fn compile(&self, expression: &str) -> Result<Arc<JsonPath>, String> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            entries.tick += 1;
+            let tick = entries.tick;
+            if let Some((path, last_used)) = entries.paths.get_mut(expression) {
+                *last_used = tick;
+                return Ok(Arc::clone(path));
+            }
+        }
+        let path = Arc::new(JsonPath::parse(expression).map_err(|err| err.to_string())?);
+        let mut entries = self.entries.lock().unwrap();
+        if entries.paths.len() >= self.capacity && !entries.paths.contains_key(expression) {
+            let least_recent = entries.paths.iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(key) = least_recent {
+                entries.paths.remove(&key);
+            }
+        }
+        let tick = entries.tick;
+        entries.paths.insert(expression.to_string(), (Arc::clone(&path), tick));
+        Ok(path)
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().paths.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_recently_used_paths() {
+        let cache = PathCache::new(2);
+        let first = cache.compile("$.login_0.output.token").unwrap();
+        cache.compile("$.list_1.status").unwrap();
+
+        assert!(Arc::ptr_eq(&first, &cache.compile("$.login_0.output.token").unwrap()));
+        cache.compile("$.get_2.output.id").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(Arc::ptr_eq(&first, &cache.compile("$.login_0.output.token").unwrap()));
+        assert!(cache.compile("$.login_0[").is_err());
+        assert_eq!(cache.len(), 2);
+    }
+}
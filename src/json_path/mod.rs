@@ -1,3 +1,4 @@
 pub mod utils;
 pub mod model;
-pub mod api;
\ No newline at end of file
+pub mod api;
+pub mod cache;
@@ -1,6 +1 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
-pub struct Expression {
-    pub value: String,
-}
\ No newline at end of file
+pub use parroton_core::json_path::*;
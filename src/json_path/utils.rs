@@ -1,10 +1,12 @@
 use crate::parameter::model::{Parameter, ParameterType};
+use crate::persistence::model::SortOrder;
 use crate::persistence::repo::Repository;
 use regex::Regex;
 use serde::Deserialize;
 use serde_json::{Map, Value};
-use serde_json_path::JsonPath;
+use tracing::info;
 use crate::json_path::api::AutoCompleteRequest;
+use crate::json_path::cache;
 use crate::json_path::model::Expression;
 
 #[derive(Debug, PartialEq)]
@@ -16,7 +18,7 @@ enum SuggestionStrategy {
 
 pub async fn auto_complete(repository: &Repository, request: AutoCompleteRequest) -> Vec<String> {
     let strategy_option = crate::json_path::utils::find_matching_suggestion_strategy(&request.latest_input);
-    println!("input: {:?}, stg: {:?}", request.latest_input, strategy_option);
+    info!(input = %request.latest_input, strategy = ?strategy_option, "auto completing expression");
     match strategy_option {
         None => {
             vec![]
@@ -30,6 +32,7 @@ pub async fn auto_complete(repository: &Repository, request: AutoCompleteRequest
                         request.test_case_id.clone(),
                         request.source_action_order.unwrap_or(1000),
                         None,
+                        SortOrder::Asc,
                     )
                     .await
                     .unwrap()
@@ -107,7 +110,7 @@ pub fn evaluate_value(parameter: &Parameter, context: &Value) -> Result<Value, S
             let eval_result = evaluate_expression(context, exp);
             match eval_result {
                 Ok(values) => {
-                    if parameter.value.is_array() {
+                    if parameter.value.is_array() || parameter.get_path().contains("[*]") {
                         Ok(Value::Array(values))
                     } else {
                         match values.iter().next() {
@@ -125,8 +128,10 @@ pub fn evaluate_value(parameter: &Parameter, context: &Value) -> Result<Value, S
     result
 }
 
+/// Compiled paths are shared through the cache, so an expression is parsed once however often
+/// it is evaluated.
 pub fn evaluate_expression(context: &Value, exp: &Expression) -> Result<Vec<Value>, String> {
-    let json_path_result = JsonPath::parse(exp.value.as_str());
+    let json_path_result = cache::compile(exp.value.as_str());
     match json_path_result {
         Ok(json_path) => {
             Ok(json_path.query(context)
@@ -142,76 +147,128 @@ pub fn evaluate_expression(context: &Value, exp: &Expression) -> Result<Vec<Valu
     }
 }
 
+/// Rebuilds a value from flattened paths like `$.items[0].tags[1]` or `$.matrix[0][1]`.
+/// A `[*]` segment spreads an array value into one element per item, so `$.items[*].id`
+/// with `[1, 2]` gives `{"items": [{"id": 1}, {"id": 2}]}`. Holes in arrays are left null.
 pub fn reverse_flatten_all(path_value_pairs: Vec<(String, Value)>) -> Value {
-    let mut root = Map::new();
-    let array_key_regex = Regex::new(r"^([^\[]+)\[(\d+)\](?:\.(.+))?$").unwrap();
+    let mut root = Value::Object(Map::new());
+    for (key, value) in path_value_pairs {
+        let segments = parse_path_segments(&key);
+        insert_at(&mut root, &segments, value);
+    }
+    root
+}
 
-    for (key, mut value) in path_value_pairs {
-        // Remove the leading "$." from the key
-        let key = key.strip_prefix("$.").unwrap_or(&key);
-        let parts: Vec<&str> = key.split('.').collect();
-        let mut current = &mut root;
+/// Appends `key` to `parent` with dot notation when it is a plain name, otherwise with a quoted
+/// bracket so keys like `a.b` or `content-type` survive the way back through `reverse_flatten_all`.
+pub fn child_path(parent: &str, key: &str) -> String {
+    let mut chars = key.chars();
+    let is_plain = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if is_plain {
+        format!("{}.{}", parent, key)
+    } else {
+        format!("{}['{}']", parent, key.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
 
-        for (i, part) in parts.iter().enumerate() {
-            if i == parts.len() - 1 {
-                // Last part of the key
-                if let Some(captures) = array_key_regex.captures(part) {
-                    let array_name = captures.get(1).unwrap().as_str();
-                    let array_index: usize = captures.get(2).unwrap().as_str().parse().unwrap();
-                    let nested_field = captures.get(3).map(|m| m.as_str());
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
 
-                    // Work on the array part
-                    let array = current
-                        .entry(array_name)
-                        .or_insert_with(|| Value::Array(vec![]));
-                    if let Value::Array(ref mut vec) = array {
-                        if vec.len() <= array_index {
-                            vec.resize(array_index + 1, Value::Object(Map::new()));
-                        }
-                        let ref mut current_array_item_val: Value = vec[array_index];
-                        if let Value::Object(ref mut obj) = current_array_item_val {
-                            if let Some(field_name) = nested_field {
-                                obj.insert(field_name.to_string(), value.clone());
-                            } else {
-                                *current_array_item_val = value.clone();
-                            }
+fn parse_path_segments(path: &str) -> Vec<PathSegment> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = vec![];
+    let mut key = String::new();
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+            }
+            '[' => {
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+                if let Some(quote) = chars.next_if(|c| *c == '\'' || *c == '"') {
+                    let mut name = String::new();
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '\\' => name.extend(chars.next()),
+                            c if c == quote => break,
+                            c => name.push(c),
                         }
                     }
-                } else {
-                    current.insert(part.to_string(), value.clone());
+                    chars.by_ref().find(|c| *c == ']');
+                    segments.push(PathSegment::Key(name));
+                    continue;
                 }
-            } else {
-                // Intermediate parts
-                if let Some(captures) = array_key_regex.captures(part) {
-                    let array_name = captures.get(1).unwrap().as_str();
-                    let array_index: usize = captures.get(2).unwrap().as_str().parse().unwrap();
-
-                    // Precompute array entry
-                    let array = current
-                        .entry(array_name)
-                        .or_insert_with(|| Value::Array(vec![]));
-                    current = if let Value::Array(ref mut vec) = array {
-                        if vec.len() <= array_index {
-                            vec.resize(array_index + 1, Value::Object(Map::new()));
-                        }
-                        vec[array_index]
-                            .as_object_mut()
-                            .expect("Expected an object in the array")
-                    } else {
-                        panic!("Expected an array");
-                    };
-                } else {
-                    current = current
-                        .entry(part.to_string())
-                        .or_insert_with(|| Value::Object(Map::new()))
-                        .as_object_mut()
-                        .expect("Expected an object for the intermediate part");
+                let inner: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                let inner = inner.trim();
+                match inner.parse::<usize>() {
+                    Ok(index) => segments.push(PathSegment::Index(index)),
+                    Err(_) if inner == "*" => segments.push(PathSegment::Wildcard),
+                    Err(_) => segments.push(PathSegment::Key(inner.to_string())),
                 }
             }
+            _ => key.push(c),
+        }
+    }
+    if !key.is_empty() {
+        segments.push(PathSegment::Key(key));
+    }
+    segments
+}
+
+/// Later paths win when two paths disagree on whether a node is an object or an array.
+fn insert_at(node: &mut Value, segments: &[PathSegment], value: Value) {
+    let Some((segment, rest)) = segments.split_first() else {
+        *node = value;
+        return;
+    };
+    match segment {
+        PathSegment::Key(key) => {
+            if !node.is_object() {
+                *node = Value::Object(Map::new());
+            }
+            let child = node.as_object_mut().unwrap()
+                .entry(key.clone())
+                .or_insert(Value::Null);
+            insert_at(child, rest, value);
+        }
+        PathSegment::Index(index) => {
+            let items = array_of(node);
+            if items.len() <= *index {
+                items.resize(index + 1, Value::Null);
+            }
+            insert_at(&mut items[*index], rest, value);
+        }
+        PathSegment::Wildcard => {
+            let values = match value {
+                Value::Array(values) => values,
+                other => vec![other],
+            };
+            let items = array_of(node);
+            if items.len() < values.len() {
+                items.resize(values.len(), Value::Null);
+            }
+            for (item, value) in items.iter_mut().zip(values) {
+                insert_at(item, rest, value);
+            }
         }
     }
+}
 
-    Value::Object(root)
+fn array_of(node: &mut Value) -> &mut Vec<Value> {
+    if !node.is_array() {
+        *node = Value::Array(vec![]);
+    }
+    node.as_array_mut().unwrap()
 }
 
 fn remove_prefix(s: &String) -> String {
@@ -243,6 +300,59 @@ fn find_matching_suggestion_strategy(input: &String) -> Option<SuggestionStrateg
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn reverse_flatten_nested_arrays() {
+        let value = reverse_flatten_all(vec![
+            ("$.name".to_string(), json!("order")),
+            ("$.items[1].id".to_string(), json!(2)),
+            ("$.items[0].id".to_string(), json!(1)),
+            ("$.items[0].tags[1]".to_string(), json!("b")),
+            ("$.items[0].tags[0]".to_string(), json!("a")),
+            ("$.matrix[0][1]".to_string(), json!(2)),
+            ("$.matrix[1][0].x".to_string(), json!(3)),
+        ]);
+
+        assert_eq!(value, json!({
+            "name": "order",
+            "items": [{"id": 1, "tags": ["a", "b"]}, {"id": 2}],
+            "matrix": [[null, 2], [{"x": 3}]],
+        }));
+    }
+
+    #[test]
+    fn reverse_flatten_escaped_keys() {
+        let pairs: Vec<(String, Value)> = ["b", "a.b", "content-type", "it's", "[x]", "1st"].iter()
+            .enumerate()
+            .map(|(i, key)| (child_path("$", key), json!(i)))
+            .collect();
+
+        assert_eq!(pairs[1].0, "$['a.b']");
+        assert_eq!(pairs[3].0, "$['it\\'s']");
+        let value = reverse_flatten_all(pairs);
+        let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["b", "a.b", "content-type", "it's", "[x]", "1st"]);
+        let context = json!({"a.b": 1, "content-type": 2});
+        assert_eq!(evaluate_expression(&context, &Expression { value: child_path("$", "a.b") }), Ok(vec![json!(1)]));
+    }
+
+    #[test]
+    fn reverse_flatten_wildcards() {
+        let value = reverse_flatten_all(vec![
+            ("$.items[*].id".to_string(), json!([1, 2, 3])),
+            ("$.items[*].quantity".to_string(), json!([5, 6])),
+            ("$.ids[*]".to_string(), json!(["x", "y"])),
+            ("$.single[*].id".to_string(), json!(7)),
+        ]);
+
+        assert_eq!(value, json!({
+            "items": [{"id": 1, "quantity": 5}, {"id": 2, "quantity": 6}, {"id": 3}],
+            "ids": ["x", "y"],
+            "single": [{"id": 7}],
+        }));
+        assert_eq!(reverse_flatten_all(vec![("[0].id".to_string(), json!(1))]), json!([{"id": 1}]));
+    }
     #[test]
     fn auto_complete_matching_strategy() {
         let input1 = String::from("$.");
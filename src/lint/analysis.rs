@@ -0,0 +1,286 @@
+use crate::action::model::{Action, ActionType};
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, AssertionItem, ComparisonType};
+use crate::drift::analysis::array_index_regex;
+use crate::lint::model::{LintFinding, LintReport, LintRule, LintSeverity};
+use crate::parameter::model::{Parameter, ParameterIn, ParameterType};
+use crate::persistence::repo::Repository;
+use std::collections::{HashMap, HashSet};
+
+/// Checks the wiring of a test case without running it.
+pub async fn lint_test_case(repository: &Repository, customer_id: &String, test_case_id: &String) -> Result<LintReport, AppError> {
+    repository.test_cases()
+        .get(customer_id.clone(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let mut actions = repository.actions().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    actions.sort();
+    let parameters = repository.parameters().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let assertions = repository.assertions().list_all(customer_id, test_case_id).await?;
+    let pinned_provider_ids: Vec<String> = actions.iter()
+        .filter_map(|action| action.auth_provider_id.clone())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+    let existing_provider_ids: HashSet<String> = if pinned_provider_ids.is_empty() {
+        HashSet::new()
+    } else {
+        repository.auth_providers()
+            .batch_get(customer_id, pinned_provider_ids)
+            .await?
+            .into_iter()
+            .map(|provider| provider.id)
+            .collect()
+    };
+    let findings = lint(&actions, &parameters, &assertions, &existing_provider_ids);
+    Ok(LintReport {
+        test_case_id: test_case_id.clone(),
+        broken: findings.iter().any(|finding| finding.severity == LintSeverity::Error),
+        findings,
+    })
+}
+
+pub fn lint(
+    actions: &[Action],
+    parameters: &[Parameter],
+    assertions: &[Assertion],
+    existing_provider_ids: &HashSet<String>,
+) -> Vec<LintFinding> {
+    let linter = Linter::new(actions, parameters);
+    let mut findings = vec![];
+    for action in actions {
+        if let Some(auth_provider_id) = &action.auth_provider_id {
+            if !existing_provider_ids.contains(auth_provider_id) {
+                findings.push(finding(LintRule::MissingAuthProvider, LintSeverity::Error,
+                                      format!("action {} is pinned to auth provider {} which does not exist", action.name, auth_provider_id))
+                    .action(&action.id));
+            }
+        }
+    }
+    for parameter in parameters.iter().filter(|parameter| parameter.parameter_type == ParameterType::Input) {
+        let Some(expression) = &parameter.value_expression else {
+            continue;
+        };
+        let consumer = actions.iter().find(|action| action.id == parameter.action_id);
+        findings.extend(linter.check_expression(&expression.value, consumer)
+            .into_iter()
+            .map(|finding| finding.action(&parameter.action_id).parameter(&parameter.id)));
+    }
+    findings.extend(duplicate_parameters(actions, parameters));
+    for assertion in assertions {
+        if assertion.comparison_type == ComparisonType::MatchesSnapshot {
+            let known = assertion.snapshot_action_id.as_ref()
+                .is_some_and(|action_id| actions.iter().any(|action| &action.id == action_id));
+            if !known {
+                findings.push(finding(LintRule::UnknownAction, LintSeverity::Error,
+                                      "snapshot assertion does not point to an existing action".to_string())
+                    .assertion(&assertion.id));
+            }
+            continue;
+        }
+        let expressions: Vec<&String> = expressions_of(&assertion.left).into_iter()
+            .chain(expressions_of(&assertion.right))
+            .collect();
+        if expressions.is_empty() {
+            findings.push(finding(LintRule::ConstantAssertion, LintSeverity::Warning,
+                                  "assertion compares constants only, its result never changes".to_string())
+                .assertion(&assertion.id));
+        }
+        for expression in expressions {
+            findings.extend(linter.check_expression(expression, None)
+                .into_iter()
+                .map(|finding| finding.assertion(&assertion.id)));
+        }
+    }
+    findings
+}
+
+struct Linter<'a> {
+    actions_by_name: HashMap<&'a str, &'a Action>,
+    output_paths: HashMap<&'a str, HashSet<String>>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(actions: &'a [Action], parameters: &'a [Parameter]) -> Self {
+        let index_regex = array_index_regex();
+        let mut output_paths: HashMap<&str, HashSet<String>> = HashMap::new();
+        for parameter in parameters.iter().filter(|parameter| parameter.parameter_type == ParameterType::Output && parameter.get_parameter_in() == ParameterIn::Body) {
+            output_paths.entry(parameter.action_id.as_str())
+                .or_default()
+                .insert(index_regex.replace_all(&parameter.get_path(), "[]").to_string());
+        }
+        Linter {
+            actions_by_name: actions.iter().map(|action| (action.name.as_str(), action)).collect(),
+            output_paths,
+        }
+    }
+
+    /// `consumer` is the action the expression feeds, none for assertions which run after every action.
+    fn check_expression(&self, expression: &str, consumer: Option<&Action>) -> Vec<LintFinding> {
+        let Some(reference) = parse_reference(expression) else {
+            return vec![];
+        };
+        let Some(source) = self.actions_by_name.get(reference.action_name) else {
+            return vec![finding(LintRule::UnknownAction, LintSeverity::Error,
+                                format!("{} refers to unknown action {}", expression, reference.action_name))];
+        };
+        if source.action_type == ActionType::Sleep {
+            return vec![finding(LintRule::UnreachableOutput, LintSeverity::Error,
+                                format!("{} refers to sleep action {} which has no output", expression, source.name))];
+        }
+        if let Some(consumer) = consumer {
+            if source.order >= consumer.order {
+                return vec![finding(LintRule::UnreachableOutput, LintSeverity::Error,
+                                    format!("{} refers to action {} which has not run yet when {} runs", expression, source.name, consumer.name))];
+            }
+        }
+        if reference.section != "output" {
+            return vec![];
+        }
+        let Some(path) = reference.path else {
+            return vec![];
+        };
+        let known_paths = self.output_paths.get(source.id.as_str());
+        let known = known_paths.is_some_and(|paths| paths.iter().any(|known| covers(known, &path)));
+        if known_paths.is_some() && !known {
+            return vec![finding(LintRule::UnknownPath, LintSeverity::Warning,
+                                format!("{} refers to a path not seen in the recorded response of {}", expression, source.name))];
+        }
+        vec![]
+    }
+}
+
+pub(crate) struct Reference<'e> {
+    pub(crate) action_name: &'e str,
+    pub(crate) section: &'e str,
+    /// Path inside the section in drift notation, none when the expression is too dynamic to check.
+    pub(crate) path: Option<String>,
+    /// Path inside the section exactly as written, e.g. `$.items[0].id`.
+    pub(crate) raw_path: String,
+}
+
+/// Splits `$.create_issue_0.output.fields.id` into the action name, the section and `$.fields.id`.
+pub(crate) fn parse_reference(expression: &str) -> Option<Reference<'_>> {
+    let rest = expression.trim().strip_prefix("$.")?;
+    let name_end = rest.find(['.', '[']).unwrap_or(rest.len());
+    let action_name = &rest[..name_end];
+    if action_name.is_empty() || action_name == "*" {
+        return None;
+    }
+    let after_name = rest[name_end..].strip_prefix('.').unwrap_or("");
+    let section_end = after_name.find(['.', '[']).unwrap_or(after_name.len());
+    let section = &after_name[..section_end];
+    let tail = &after_name[section_end..];
+    let dynamic = ["*", "?", "..", "@", ":"].iter().any(|token| tail.contains(token));
+    let path = (!dynamic).then(|| array_index_regex().replace_all(&format!("${}", tail), "[]").to_string());
+    Some(Reference { action_name, section, path, raw_path: format!("${}", tail) })
+}
+
+fn covers(known: &str, path: &str) -> bool {
+    known == path || path == "$" || known.strip_prefix(path)
+        .is_some_and(|rest| rest.starts_with(['.', '[']))
+}
+
+pub(crate) fn expressions_of(item: &AssertionItem) -> Vec<&String> {
+    let from_provider = item.value_provider.iter()
+        .filter_map(|provider| provider.expression.as_ref());
+    let from_function = item.function.iter()
+        .flat_map(|function| function.parameters.iter())
+        .filter_map(|provider| provider.expression.as_ref());
+    from_provider.chain(from_function).map(|expression| &expression.value).collect()
+}
+
+fn duplicate_parameters(actions: &[Action], parameters: &[Parameter]) -> Vec<LintFinding> {
+    let mut seen = HashSet::new();
+    let mut findings = vec![];
+    for parameter in parameters.iter().filter(|parameter| parameter.parameter_type == ParameterType::Input) {
+        let key = (parameter.action_id.as_str(), format!("{:?}", parameter.location));
+        if !seen.insert(key) {
+            let action_name = actions.iter()
+                .find(|action| action.id == parameter.action_id)
+                .map_or(parameter.action_id.as_str(), |action| action.name.as_str());
+            findings.push(finding(LintRule::DuplicateParameter, LintSeverity::Warning,
+                                  format!("action {} has more than one parameter for {:?}", action_name, parameter.location))
+                .action(&parameter.action_id)
+                .parameter(&parameter.id));
+        }
+    }
+    findings
+}
+
+fn finding(rule: LintRule, severity: LintSeverity, message: String) -> LintFinding {
+    LintFinding { rule, severity, message, action_id: None, parameter_id: None, assertion_id: None }
+}
+
+impl LintFinding {
+    fn action(self, action_id: &str) -> Self {
+        LintFinding { action_id: Some(action_id.to_string()), ..self }
+    }
+
+    fn parameter(self, parameter_id: &str) -> Self {
+        LintFinding { parameter_id: Some(parameter_id.to_string()), ..self }
+    }
+
+    fn assertion(self, assertion_id: &str) -> Self {
+        LintFinding { assertion_id: Some(assertion_id.to_string()), ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::ParameterLocation;
+    use serde_json::json;
+
+    fn parameter(action_id: &str, parameter_type: ParameterType, location: ParameterLocation, expression: Option<&str>) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action_id.to_string())
+            .parameter_type(parameter_type)
+            .location(location)
+            .value(json!("x"))
+            .maybe_value_expression(expression.map(|value| Expression { value: value.to_string() }))
+            .build()
+    }
+
+    #[test]
+    fn detect_broken_wiring() {
+        let actions = vec![
+            test_action("login_0", 0, "GET", "https://abc.xyz"),
+            Action { auth_provider_id: Some("gone".to_string()), ..test_action("profile_1", 1, "GET", "https://abc.xyz") },
+        ];
+        let parameters = vec![
+            parameter("login_0_id", ParameterType::Output, ParameterLocation::Body("$.items[0].token".to_string()), None),
+            parameter("profile_1_id", ParameterType::Input, ParameterLocation::Header("Authorization".to_string()), Some("$.login_0.output.items[1].token")),
+            parameter("profile_1_id", ParameterType::Input, ParameterLocation::Header("Authorization".to_string()), None),
+            parameter("profile_1_id", ParameterType::Input, ParameterLocation::Query("id".to_string()), Some("$.login_0.output.user.id")),
+            parameter("login_0_id", ParameterType::Input, ParameterLocation::Query("next".to_string()), Some("$.profile_1.output.id")),
+            parameter("login_0_id", ParameterType::Input, ParameterLocation::Query("tenant".to_string()), Some("$.signup.output.tenant")),
+        ];
+        let constant = Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("a1".to_string())
+            .left(AssertionItem::from_value(json!(1)))
+            .right(AssertionItem::from_value(json!(1)))
+            .comparison_type(ComparisonType::EqualTo)
+            .build();
+
+        let rules: Vec<(LintRule, LintSeverity)> = lint(&actions, &parameters, &[constant], &HashSet::new())
+            .into_iter()
+            .map(|finding| (finding.rule, finding.severity))
+            .collect();
+
+        assert_eq!(rules, vec![
+            (LintRule::MissingAuthProvider, LintSeverity::Error),
+            (LintRule::UnknownPath, LintSeverity::Warning),
+            (LintRule::UnreachableOutput, LintSeverity::Error),
+            (LintRule::UnknownAction, LintSeverity::Error),
+            (LintRule::DuplicateParameter, LintSeverity::Warning),
+            (LintRule::ConstantAssertion, LintSeverity::Warning),
+        ]);
+    }
+}
@@ -0,0 +1,13 @@
+use crate::api::{ApiResponse, AppError};
+use crate::lint::analysis::lint_test_case;
+use crate::lint::model::LintReport;
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+
+pub async fn get_lint_report(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<LintReport>, AppError> {
+    let result = lint_test_case(&repository, &"eren".to_string(), &id).await;
+    ApiResponse::from(result)
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, ToSchema)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, ToSchema)]
+pub enum LintRule {
+    UnknownAction,
+    UnknownPath,
+    UnreachableOutput,
+    MissingAuthProvider,
+    ConstantAssertion,
+    DuplicateParameter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub severity: LintSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameter_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assertion_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct LintReport {
+    pub test_case_id: String,
+    /// True when any finding is an error, i.e. a run is expected to break.
+    pub broken: bool,
+    pub findings: Vec<LintFinding>,
+}
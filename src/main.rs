@@ -1,5 +1,7 @@
 mod har_resolver;
+mod config;
 mod http;
+mod signing;
 mod api;
 mod proxy;
 mod auth;
@@ -11,13 +13,42 @@ mod action_execution;
 mod action;
 mod persistence;
 mod json_path;
+mod template;
+mod sync;
+mod revision;
+mod deletion;
+mod metrics;
+mod telemetry;
+mod mock;
+mod drift;
+mod header_rule;
+mod group;
+mod quota;
+mod access;
+mod share;
+mod sensitive_header;
+mod snapshot;
+mod environment;
+mod lint;
+mod graph;
+mod validation;
+mod openapi;
+mod change;
+mod import_report;
+mod artifact;
+mod admin;
+mod curl;
+mod notification;
+mod constant;
+mod plugin;
 
 use crate::api::build_api;
+use crate::config::app_config;
 
 #[tokio::main]
 async fn main() {
-    println!("Hello, world!");
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    let router = build_api().await;
+    let config = app_config();
+    let listener = tokio::net::TcpListener::bind(config.listen_address()).await.unwrap();
+    let router = build_api(config).await;
     axum::serve(listener, router).await.unwrap();
 }
@@ -0,0 +1,30 @@
+use crate::metrics::registry::metrics;
+use crate::persistence::repo::Repository;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::Response;
+
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+pub async fn readyz(State(repository): State<Repository>) -> Response {
+    match repository.check_connectivity().await {
+        Ok(_) => Response::builder()
+            .status(StatusCode::OK)
+            .body("ok".into())
+            .unwrap(),
+        Err(_) => Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body("dynamodb unavailable".into())
+            .unwrap(),
+    }
+}
+
+pub async fn get_metrics() -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(metrics().render().into())
+        .unwrap()
+}
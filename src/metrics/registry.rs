@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const DURATION_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+pub struct Metrics {
+    pub runs: CounterVec,
+    pub run_duration: HistogramVec,
+    pub action_http_duration: HistogramVec,
+    pub dynamodb_errors: CounterVec,
+    pub dynamodb_retries: CounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            runs: CounterVec::new("parroton_runs_total", "Number of test case runs by status.", &["status"]),
+            run_duration: HistogramVec::new("parroton_run_duration_seconds", "Duration of finished test case runs.", &[]),
+            action_http_duration: HistogramVec::new("parroton_action_http_duration_seconds", "Latency of upstream HTTP calls per action.", &["test_case_id", "action", "status_code"]),
+            dynamodb_errors: CounterVec::new("parroton_dynamodb_errors_total", "Number of failed DynamoDB calls by table and error code.", &["table", "code"]),
+            dynamodb_retries: CounterVec::new("parroton_dynamodb_retries_total", "Number of retried DynamoDB calls by table and error code.", &["table", "code"]),
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        self.runs.render(&mut output);
+        self.run_duration.render(&mut output);
+        self.action_http_duration.render(&mut output);
+        self.dynamodb_errors.render(&mut output);
+        self.dynamodb_retries.render(&mut output);
+        output
+    }
+}
+
+pub struct CounterVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: Vec<&'static str>,
+    values: Mutex<BTreeMap<Vec<String>, u64>>,
+}
+
+impl CounterVec {
+    fn new(name: &'static str, help: &'static str, label_names: &[&'static str]) -> Self {
+        CounterVec {
+            name,
+            help,
+            label_names: label_names.to_vec(),
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn inc(&self, label_values: &[&str]) {
+        let key = label_values.iter().map(|value| value.to_string()).collect();
+        *self.values.lock().unwrap().entry(key).or_insert(0) += 1;
+    }
+
+    fn render(&self, output: &mut String) {
+        writeln!(output, "# HELP {} {}", self.name, self.help).unwrap();
+        writeln!(output, "# TYPE {} counter", self.name).unwrap();
+        for (label_values, value) in self.values.lock().unwrap().iter() {
+            writeln!(output, "{}{} {}", self.name, format_labels(&self.label_names, label_values, None), value).unwrap();
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+struct HistogramState {
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+pub struct HistogramVec {
+    name: &'static str,
+    help: &'static str,
+    label_names: Vec<&'static str>,
+    values: Mutex<BTreeMap<Vec<String>, HistogramState>>,
+}
+
+impl HistogramVec {
+    fn new(name: &'static str, help: &'static str, label_names: &[&'static str]) -> Self {
+        HistogramVec {
+            name,
+            help,
+            label_names: label_names.to_vec(),
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn observe(&self, label_values: &[&str], duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let key = label_values.iter().map(|value| value.to_string()).collect();
+        let mut values = self.values.lock().unwrap();
+        let state = values.entry(key).or_insert_with(|| HistogramState {
+            bucket_counts: vec![0; DURATION_BUCKETS.len()],
+            ..HistogramState::default()
+        });
+        DURATION_BUCKETS.iter()
+            .zip(state.bucket_counts.iter_mut())
+            .filter(|(bound, _)| seconds <= **bound)
+            .for_each(|(_, count)| *count += 1);
+        state.sum += seconds;
+        state.count += 1;
+    }
+
+    fn render(&self, output: &mut String) {
+        writeln!(output, "# HELP {} {}", self.name, self.help).unwrap();
+        writeln!(output, "# TYPE {} histogram", self.name).unwrap();
+        for (label_values, state) in self.values.lock().unwrap().iter() {
+            for (bound, count) in DURATION_BUCKETS.iter().zip(state.bucket_counts.iter()) {
+                let le = bound.to_string();
+                writeln!(output, "{}_bucket{} {}", self.name, format_labels(&self.label_names, label_values, Some(&le)), count).unwrap();
+            }
+            writeln!(output, "{}_bucket{} {}", self.name, format_labels(&self.label_names, label_values, Some("+Inf")), state.count).unwrap();
+            writeln!(output, "{}_sum{} {}", self.name, format_labels(&self.label_names, label_values, None), state.sum).unwrap();
+            writeln!(output, "{}_count{} {}", self.name, format_labels(&self.label_names, label_values, None), state.count).unwrap();
+        }
+    }
+}
+
+fn format_labels(label_names: &[&str], label_values: &[String], le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = label_names.iter()
+        .zip(label_values.iter())
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_counters_and_histograms() {
+        let metrics = Metrics::new();
+        metrics.runs.inc(&["started"]);
+        metrics.runs.inc(&["started"]);
+        metrics.dynamodb_errors.inc(&["actions", "Throttling\"Exception"]);
+        metrics.action_http_duration.observe(&["tc1", "login_0", "200"], Duration::from_millis(30));
+
+        let rendered = metrics.render();
+
+        assert!(rendered.contains("# TYPE parroton_runs_total counter\n"));
+        assert!(rendered.contains("parroton_runs_total{status=\"started\"} 2\n"));
+        assert!(rendered.contains("parroton_dynamodb_errors_total{table=\"actions\",code=\"Throttling\\\"Exception\"} 1\n"));
+        assert!(rendered.contains("parroton_action_http_duration_seconds_bucket{test_case_id=\"tc1\",action=\"login_0\",status_code=\"200\",le=\"0.025\"} 0\n"));
+        assert!(rendered.contains("parroton_action_http_duration_seconds_bucket{test_case_id=\"tc1\",action=\"login_0\",status_code=\"200\",le=\"0.05\"} 1\n"));
+        assert!(rendered.contains("parroton_action_http_duration_seconds_bucket{test_case_id=\"tc1\",action=\"login_0\",status_code=\"200\",le=\"+Inf\"} 1\n"));
+        assert!(rendered.contains("parroton_action_http_duration_seconds_count{test_case_id=\"tc1\",action=\"login_0\",status_code=\"200\"} 1\n"));
+        assert!(rendered.contains("# TYPE parroton_run_duration_seconds histogram\n"));
+    }
+}
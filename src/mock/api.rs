@@ -0,0 +1,22 @@
+use crate::api::{ApiResponse, AppError};
+use crate::mock::recorded::{find_recorded_action, recorded_response, recorded_status};
+use crate::persistence::repo::Repository;
+use axum::extract::{Path, State};
+use axum::http::{Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+pub async fn serve_mock(
+    Path((test_case_id, path)): Path<(String, String)>,
+    method: Method,
+    State(repository): State<Repository>,
+) -> Result<Response, AppError> {
+    let mut actions = repository.actions()
+        .list_all("eren".to_string(), test_case_id)
+        .await?;
+    actions.sort();
+    let action = find_recorded_action(&actions, method.as_str(), &path)
+        .ok_or(AppError::NotFound(format!("No recorded action for {} /{}", method, path)))?;
+    let body = recorded_response(&repository, action).await?;
+    let status = StatusCode::from_u16(recorded_status(action)).unwrap_or(StatusCode::OK);
+    Ok((status, ApiResponse(body)).into_response())
+}
@@ -0,0 +1,2 @@
+pub mod recorded;
+pub mod api;
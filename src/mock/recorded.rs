@@ -0,0 +1,155 @@
+use crate::action::model::{Action, ActionType};
+use crate::api::AppError;
+use crate::http::{HttpError, HttpResult, ResBody, StatusError};
+use crate::json_path::utils::reverse_flatten_all;
+use crate::parameter::model::{Parameter, ParameterIn, ParameterLocation};
+use crate::persistence::repo::Repository;
+use serde_json::Value;
+
+/// Rebuilds the response body recorded on import from the output parameters of the action.
+pub async fn recorded_response(repository: &Repository, action: &Action) -> Result<Value, AppError> {
+    let outputs = repository.parameters()
+        .list_all_outputs_of_action(action.customer_id.clone(), action.test_case_id.clone(), action.id.clone())
+        .await?;
+    Ok(response_from_outputs(&outputs))
+}
+
+/// Rebuilds the whole recorded result, headers included, with the recorded status. A 4xx or 5xx
+/// one fails the way a live response with that status does.
+pub async fn recorded_result(repository: &Repository, action: &Action) -> Result<HttpResult<Value>, HttpError> {
+    let outputs = repository.parameters()
+        .list_all_outputs_of_action(action.customer_id.clone(), action.test_case_id.clone(), action.id.clone())
+        .await
+        .map_err(|err| HttpError::Io(format!("could not load recorded response: {:?}", err)))?;
+    as_http_result(recorded_status(action), response_from_outputs(&outputs), headers_from_outputs(&outputs))
+}
+
+/// Actions recorded before their status was kept are answered with 200.
+pub fn recorded_status(action: &Action) -> u16 {
+    action.recorded_status.unwrap_or(200)
+}
+
+fn as_http_result(status_code: u16, body: Value, headers: Vec<(String, String)>) -> Result<HttpResult<Value>, HttpError> {
+    let text = || match &body {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    match status_code {
+        400..=499 => Err(HttpError::Status(status_code, StatusError::ClientError(text()))),
+        500.. => Err(HttpError::Status(status_code, StatusError::ServerError(text()))),
+        _ => Ok(HttpResult::new(ResBody::new(body), status_code).with_headers(headers)),
+    }
+}
+
+/// Only body parameters take part, header outputs are served by `headers_from_outputs`.
+pub fn response_from_outputs(outputs: &[Parameter]) -> Value {
+    let body_values: Vec<(String, Value)> = outputs.iter()
+        .filter(|parameter| parameter.get_parameter_in() == ParameterIn::Body)
+        .map(|parameter| (parameter.get_path(), parameter.value.clone()))
+        .collect();
+    if body_values.is_empty() {
+        return Value::Null;
+    }
+    reverse_flatten_all(body_values)
+}
+
+pub fn headers_from_outputs(outputs: &[Parameter]) -> Vec<(String, String)> {
+    outputs.iter()
+        .filter_map(|parameter| match &parameter.location {
+            ParameterLocation::Header(name) => Some((name.to_lowercase(), parameter.value.as_str().unwrap_or_default().to_string())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the first action recorded for the method and path, treating `{placeholder}` and `$.` segments as wildcards.
+pub fn find_recorded_action<'a>(actions: &'a [Action], method: &str, path: &str) -> Option<&'a Action> {
+    let requested_segments = path_segments(path);
+    actions.iter()
+        .filter(|action| action.action_type == ActionType::Http)
+        .filter(|action| action.method.eq_ignore_ascii_case(method))
+        .find(|action| {
+            let recorded_segments = path_segments(path_of_url(&action.url));
+            recorded_segments.len() == requested_segments.len() && recorded_segments.iter()
+                .zip(requested_segments.iter())
+                .all(|(recorded, requested)| is_wildcard_segment(recorded) || recorded == requested)
+        })
+}
+
+fn is_wildcard_segment(segment: &str) -> bool {
+    segment.starts_with("$.") || (segment.starts_with('{') && segment.ends_with('}'))
+}
+
+fn path_of_url(url: &str) -> &str {
+    let without_scheme = url.find("://").map_or(url, |scheme_end| &url[scheme_end + 3..]);
+    let path = without_scheme.find('/').map_or("", |path_start| &without_scheme[path_start..]);
+    path.split(['?', '#']).next().unwrap_or_default()
+}
+
+fn path_segments(path: &str) -> Vec<&str> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use serde_json::json;
+
+    #[test]
+    fn find_action_by_method_and_path() {
+        let actions = vec![
+            test_action("users_0", 0, "GET", "https://abc.xyz/api/users"),
+            test_action("create_user_1", 1, "POST", "https://abc.xyz/api/users"),
+            test_action("user_2", 2, "GET", "https://abc.xyz/api/users/$.create_user_1.output.id"),
+            test_action("roles_3", 3, "GET", "https://abc.xyz/api/users/{id}/roles"),
+        ];
+
+        assert_eq!(find_recorded_action(&actions, "get", "/api/users").unwrap().name, "users_0");
+        assert_eq!(find_recorded_action(&actions, "POST", "api/users/").unwrap().name, "create_user_1");
+        assert_eq!(find_recorded_action(&actions, "GET", "/api/users/42").unwrap().name, "user_2");
+        assert!(find_recorded_action(&actions, "DELETE", "/api/users/42").is_none());
+        assert_eq!(find_recorded_action(&actions, "GET", "/api/users/42/roles").unwrap().name, "roles_3");
+        assert!(find_recorded_action(&actions, "GET", "/api/users/42/groups").is_none());
+    }
+
+    #[test]
+    fn rebuild_response_from_outputs() {
+        let output = |location: ParameterLocation, value: Value| Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id("a1".to_string())
+            .parameter_type(ParameterType::Output)
+            .location(location)
+            .value(value)
+            .build();
+        let outputs = [
+            output(ParameterLocation::Body("$.id".to_string()), json!(42)),
+            output(ParameterLocation::Body("$.profile.name".to_string()), json!("eren")),
+            output(ParameterLocation::Header("location".to_string()), json!("/orders/42")),
+        ];
+
+        assert_eq!(response_from_outputs(&outputs), json!({"id": 42, "profile": {"name": "eren"}}));
+        assert_eq!(headers_from_outputs(&outputs), vec![("location".to_string(), "/orders/42".to_string())]);
+        assert_eq!(response_from_outputs(&outputs[2..]), Value::Null);
+        assert_eq!(response_from_outputs(&[]), Value::Null);
+    }
+
+    #[test]
+    fn answer_with_the_recorded_status() {
+        let mut created = test_action("create_user_1", 1, "POST", "https://abc.xyz/api/users");
+        assert_eq!(recorded_status(&created), 200);
+        created.recorded_status = Some(201);
+        assert_eq!(recorded_status(&created), 201);
+
+        let Ok(result) = as_http_result(201, json!({"id": 42}), vec![]) else { panic!("201 was not answered") };
+        assert_eq!((result.status_code, result.res_body.value), (201, json!({"id": 42})));
+        assert!(matches!(as_http_result(404, json!({"code": "NOT_FOUND"}), vec![]),
+                         Err(HttpError::Status(404, StatusError::ClientError(text))) if text == "{\"code\":\"NOT_FOUND\"}"));
+        assert!(matches!(as_http_result(503, Value::Null, vec![]), Err(HttpError::Status(503, StatusError::ServerError(text))) if text.is_empty()));
+    }
+}
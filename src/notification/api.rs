@@ -0,0 +1,173 @@
+use crate::access::middleware::USER_HEADER;
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::notification::model::{DigestSubscription, NotificationChannel, NotificationSettings, QuietHours, RoutingRule};
+use crate::persistence::repo::Repository;
+use crate::validation::{check_not_blank, ValidJson, Validate};
+use axum::extract::State;
+use axum::http::HeaderMap;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn get_notification_settings(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<NotificationSettings>, AppError> {
+    let result = repository.notification_settings()
+        .get("eren")
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn put_notification_settings(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<PutNotificationSettingsPayload>,
+) -> Result<ApiResponse<NotificationSettings>, AppError> {
+    let result = repository.notification_settings()
+        .put(NotificationSettings::builder()
+            .customer_id("eren".to_string())
+            .rules(payload.rules)
+            .maybe_quiet_hours(payload.quiet_hours)
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_digest_subscription(
+    State(repository): State<Repository>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<DigestSubscription>, AppError> {
+    let user_id = user_id_of(&headers)?;
+    let result = repository.digest_subscriptions()
+        .get("eren", &user_id)
+        .await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn put_digest_subscription(
+    State(repository): State<Repository>,
+    headers: HeaderMap,
+    ValidJson(payload): ValidJson<PutDigestSubscriptionPayload>,
+) -> Result<ApiResponse<DigestSubscription>, AppError> {
+    let user_id = user_id_of(&headers)?;
+    let existing = repository.digest_subscriptions()
+        .get("eren", &user_id)
+        .await?;
+    let result = repository.digest_subscriptions()
+        .put(DigestSubscription::builder()
+            .customer_id("eren".to_string())
+            .id(user_id)
+            .email(payload.email)
+            .test_case_ids(payload.test_case_ids)
+            .send_hour_utc(payload.send_hour_utc)
+            .maybe_last_sent_at(existing.and_then(|subscription| subscription.last_sent_at))
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_digest_subscription(
+    State(repository): State<Repository>,
+    headers: HeaderMap,
+) -> Result<ApiResponse<DigestSubscription>, AppError> {
+    let user_id = user_id_of(&headers)?;
+    let result = repository.digest_subscriptions()
+        .delete("eren", &user_id)
+        .await;
+    ApiResponse::from_option(result)
+}
+
+fn user_id_of(headers: &HeaderMap) -> Result<String, AppError> {
+    headers.get(USER_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .ok_or(AppError::Unauthorized(format!("Missing {} header", USER_HEADER)))
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutDigestSubscriptionPayload {
+    pub email: String,
+    #[serde(default)]
+    pub test_case_ids: Vec<String>,
+    #[serde(default)]
+    pub send_hour_utc: u8,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutNotificationSettingsPayload {
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Validate for PutNotificationSettingsPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = vec![];
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.events.is_empty() {
+                errors.push(FieldError::new(format!("rules[{}].events", i), "must not be empty"));
+            }
+            if rule.channels.is_empty() {
+                errors.push(FieldError::new(format!("rules[{}].channels", i), "must not be empty"));
+            }
+            for (j, channel) in rule.channels.iter().enumerate() {
+                let field = |name: &str| format!("rules[{}].channels[{}].{}", i, j, name);
+                match channel {
+                    NotificationChannel::Webhook { url } => {
+                        errors.extend(check_http_url(&field("url"), url));
+                    }
+                    NotificationChannel::Slack { webhook_url: Some(webhook_url), .. } => {
+                        errors.extend(check_http_url(&field("webhook_url"), webhook_url));
+                    }
+                    NotificationChannel::Email { addresses } => {
+                        if addresses.is_empty() {
+                            errors.push(FieldError::new(field("addresses"), "must not be empty"));
+                        }
+                        errors.extend(addresses.iter().enumerate()
+                            .filter_map(|(k, address)| check_email(&field(&format!("addresses[{}]", k)), address)));
+                    }
+                    NotificationChannel::Slack { webhook_url: None, bot_token, channel } => {
+                        if bot_token.as_ref().is_none_or(|token| token.trim().is_empty()) {
+                            errors.push(FieldError::new(field("bot_token"), "is required without a webhook_url"));
+                        }
+                        if channel.as_ref().is_none_or(|channel| channel.trim().is_empty()) {
+                            errors.push(FieldError::new(field("channel"), "is required without a webhook_url"));
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(quiet_hours) = &self.quiet_hours {
+            for (field, hour) in [("quiet_hours.start_hour", quiet_hours.start_hour), ("quiet_hours.end_hour", quiet_hours.end_hour)] {
+                if hour > 23 {
+                    errors.push(FieldError::new(field, "must be between 0 and 23"));
+                }
+            }
+            if quiet_hours.utc_offset_minutes.abs() > 14 * 60 {
+                errors.push(FieldError::new("quiet_hours.utc_offset_minutes", "must be between -840 and 840"));
+            }
+        }
+        errors
+    }
+}
+
+impl Validate for PutDigestSubscriptionPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = check_email("email", &self.email).into_iter().collect();
+        errors.extend(self.test_case_ids.iter().enumerate()
+            .filter_map(|(i, test_case_id)| check_not_blank(&format!("test_case_ids[{}]", i), test_case_id)));
+        if self.send_hour_utc > 23 {
+            errors.push(FieldError::new("send_hour_utc", "must be between 0 and 23"));
+        }
+        errors
+    }
+}
+
+fn check_email(field: &str, address: &str) -> Option<FieldError> {
+    let valid = address.split_once('@')
+        .is_some_and(|(local, domain)| !local.trim().is_empty() && domain.contains('.') && !address.contains(char::is_whitespace));
+    (!valid).then(|| FieldError::new(field, "must be an email address"))
+}
+
+fn check_http_url(field: &str, url: &str) -> Option<FieldError> {
+    let valid = reqwest::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    (!valid).then(|| FieldError::new(field, "must be an http or https URL"))
+}
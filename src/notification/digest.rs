@@ -0,0 +1,239 @@
+use crate::api::AppError;
+use crate::notification::email::mailer;
+use crate::notification::model::DigestSubscription;
+use crate::persistence::model::View;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::run::model::{Run, RunTrigger, Verdict};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+/// Runs of the day before the digest tell whether a failure is new.
+const LOOKBACK_MILLIS: u64 = 2 * DAY_MILLIS;
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const RUN_PAGE_SIZE: i32 = 100;
+const SLOWEST_ACTIONS: usize = 5;
+
+/// Outcome of a customer's scheduled runs over the last day.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Digest {
+    pub runs: usize,
+    pub passed: usize,
+    /// Test cases whose latest run did not pass while the one before it did.
+    pub new_failures: Vec<String>,
+    pub slowest_actions: Vec<SlowAction>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlowAction {
+    pub test_case_id: String,
+    pub action_name: String,
+    pub duration_ms: u64,
+}
+
+/// Checks for due digests every quarter hour. Nothing is started when no email sender is configured.
+pub fn start_digest_job(repository: Arc<Repository>) {
+    if mailer().is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            send_due_digests(&repository, current_timestamp()).await;
+        }
+    });
+}
+
+pub async fn send_due_digests(repository: &Repository, now: u64) {
+    let subscriptions = match repository.digest_subscriptions().list_all().await {
+        Ok(subscriptions) => subscriptions,
+        Err(err) => {
+            error!("could not list digest subscriptions: {:?}", err);
+            return;
+        }
+    };
+    for subscription in subscriptions.into_iter().filter(|subscription| subscription.is_due(now)) {
+        match repository.digest_subscriptions().claim(&subscription, subscription.last_sent_at, Some(now)).await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("digest of {} is already being sent", subscription.id);
+                continue;
+            }
+            Err(err) => {
+                error!("could not claim the digest of {}: {:?}", subscription.id, err);
+                continue;
+            }
+        }
+        if let Err(err) = send_digest(repository, &subscription, now).await {
+            error!("could not send digest to {}: {:?}", subscription.id, err);
+            // Released so the next check tries again.
+            if let Err(err) = repository.digest_subscriptions().claim(&subscription, Some(now), subscription.last_sent_at).await {
+                error!("could not release the digest of {}: {:?}", subscription.id, err);
+            }
+        }
+    }
+}
+
+/// Days without scheduled runs are skipped.
+async fn send_digest(repository: &Repository, subscription: &DigestSubscription, now: u64) -> Result<(), AppError> {
+    let Some(mailer) = mailer() else {
+        return Ok(());
+    };
+    let since = now.saturating_sub(DAY_MILLIS);
+    let mut runs = scheduled_runs(repository, &subscription.customer_id, now.saturating_sub(LOOKBACK_MILLIS)).await?;
+    if !subscription.test_case_ids.is_empty() {
+        runs.retain(|run| subscription.test_case_ids.contains(&run.test_case_id));
+    }
+    let mut digest = summarize_runs(&runs, since);
+    if digest.runs == 0 {
+        info!("no scheduled runs for the digest of {}", subscription.id);
+        return Ok(());
+    }
+    digest.slowest_actions = slowest_actions(repository, &runs, since).await?;
+    let test_case_names: HashMap<String, String> = repository.test_cases()
+        .list_all(&subscription.customer_id)
+        .await?
+        .into_iter()
+        .map(|test_case| (test_case.id, test_case.name))
+        .collect();
+    mailer.send(std::slice::from_ref(&subscription.email), "[parroton] Daily digest of scheduled runs", &digest_text(&digest, &test_case_names)).await
+}
+
+/// Finished scheduled runs of the customer started after `since`, newest first.
+async fn scheduled_runs(repository: &Repository, customer_id: &String, since: u64) -> Result<Vec<Run>, AppError> {
+    let mut runs = vec![];
+    let mut next_page_key = None;
+    loop {
+        let page = repository.runs()
+            .list_recent(customer_id, None, Some(since), RUN_PAGE_SIZE, next_page_key)
+            .await?;
+        runs.extend(page.items.into_iter()
+            .filter(|run| matches!(run.trigger, RunTrigger::Schedule { .. }) && run.verdict.is_some()));
+        next_page_key = page.next_page_key;
+        if next_page_key.is_none() {
+            break;
+        }
+    }
+    Ok(runs)
+}
+
+/// Pass rate over the runs started after `since`; earlier runs only serve to spot new failures.
+pub fn summarize_runs(runs: &[Run], since: u64) -> Digest {
+    let mut ordered: Vec<&Run> = runs.iter().collect();
+    ordered.sort_by_key(|run| Reverse(run.started_at));
+    let recent: Vec<&&Run> = ordered.iter().filter(|run| run.started_at >= since).collect();
+    let passed = recent.iter().filter(|run| run.verdict == Some(Verdict::Passed)).count();
+    let mut by_test_case: Vec<(&String, Vec<&Run>)> = vec![];
+    for run in &ordered {
+        match by_test_case.iter_mut().find(|(test_case_id, _)| **test_case_id == run.test_case_id) {
+            Some((_, test_case_runs)) => test_case_runs.push(run),
+            None => by_test_case.push((&run.test_case_id, vec![run])),
+        }
+    }
+    let new_failures = by_test_case.into_iter()
+        .filter(|(_, test_case_runs)| match test_case_runs.as_slice() {
+            [latest, previous, ..] => latest.started_at >= since
+                && latest.verdict != Some(Verdict::Passed)
+                && previous.verdict == Some(Verdict::Passed),
+            _ => false,
+        })
+        .map(|(test_case_id, _)| test_case_id.clone())
+        .collect();
+    Digest {
+        runs: recent.len(),
+        passed,
+        new_failures,
+        slowest_actions: vec![],
+    }
+}
+
+/// Slowest action executions among the latest run of every test case started after `since`.
+async fn slowest_actions(repository: &Repository, runs: &[Run], since: u64) -> Result<Vec<SlowAction>, AppError> {
+    let mut latest_runs: HashMap<&String, &Run> = HashMap::new();
+    for run in runs.iter().filter(|run| run.started_at >= since) {
+        let latest = latest_runs.entry(&run.test_case_id).or_insert(run);
+        if run.started_at > latest.started_at {
+            *latest = run;
+        }
+    }
+    let mut slow_actions = vec![];
+    for run in latest_runs.values() {
+        let pairs = repository.action_executions()
+            .list_with_actions(&run.customer_id, &run.test_case_id, &run.id, View::Summary)
+            .await?;
+        slow_actions.extend(pairs.into_iter().filter_map(|pair| {
+            let duration_ms = pair.execution.finished_at?.checked_sub(pair.execution.started_at?)?;
+            Some(SlowAction {
+                test_case_id: run.test_case_id.clone(),
+                action_name: pair.action.map_or(pair.execution.action_id, |action| action.name),
+                duration_ms,
+            })
+        }));
+    }
+    slow_actions.sort_by_key(|slow_action| Reverse(slow_action.duration_ms));
+    slow_actions.truncate(SLOWEST_ACTIONS);
+    Ok(slow_actions)
+}
+
+pub fn digest_text(digest: &Digest, test_case_names: &HashMap<String, String>) -> String {
+    let name_of = |test_case_id: &String| test_case_names.get(test_case_id).unwrap_or(test_case_id).clone();
+    let pass_rate = if digest.runs == 0 { 0.0 } else { digest.passed as f64 * 100.0 / digest.runs as f64 };
+    let mut text = format!("Scheduled runs of the last 24 hours: {} of {} passed ({:.0}%).", digest.passed, digest.runs, pass_rate);
+    if !digest.new_failures.is_empty() {
+        text.push_str("\n\nNew failures:");
+        digest.new_failures.iter().for_each(|test_case_id| text.push_str(&format!("\n- {}", name_of(test_case_id))));
+    }
+    if !digest.slowest_actions.is_empty() {
+        text.push_str("\n\nSlowest actions:");
+        digest.slowest_actions.iter().for_each(|slow_action| text.push_str(&format!(
+            "\n- {} / {}: {} ms", name_of(&slow_action.test_case_id), slow_action.action_name, slow_action.duration_ms)));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::model::RunStatus;
+
+    fn run(test_case_id: &str, started_at: u64, verdict: Verdict) -> Run {
+        Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id(test_case_id.to_string())
+            .status(RunStatus::Finished)
+            .started_at(started_at)
+            .verdict(verdict)
+            .trigger(RunTrigger::Schedule { schedule_id: "nightly".to_string() })
+            .build()
+    }
+
+    #[test]
+    fn summarize_scheduled_runs() {
+        let since = 10 * DAY_MILLIS;
+        let runs = vec![
+            run("checkout", since - 100, Verdict::Passed),
+            run("checkout", since + 100, Verdict::Failed),
+            run("search", since + 100, Verdict::Passed),
+            run("search", since + 200, Verdict::Passed),
+            run("login", since + 100, Verdict::Failed),
+            run("login", since + 200, Verdict::Errored),
+        ];
+
+        let digest = summarize_runs(&runs, since);
+
+        assert_eq!(digest.runs, 5);
+        assert_eq!(digest.passed, 2);
+        assert_eq!(digest.new_failures, vec!["checkout".to_string()]);
+
+        let names = HashMap::from([("checkout".to_string(), "Checkout flow".to_string())]);
+        let text = digest_text(&Digest {
+            slowest_actions: vec![SlowAction { test_case_id: "search".to_string(), action_name: "search_0".to_string(), duration_ms: 1200 }],
+            ..digest
+        }, &names);
+        assert_eq!(text, "Scheduled runs of the last 24 hours: 2 of 5 passed (40%).\n\nNew failures:\n- Checkout flow\n\nSlowest actions:\n- search / search_0: 1200 ms");
+    }
+}
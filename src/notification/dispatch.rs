@@ -0,0 +1,154 @@
+use crate::assertion::model::{AssertionResult, Severity};
+use crate::auth::model::ListAuthProvidersRequest;
+use crate::config::app_config;
+use crate::notification::model::{Notification, NotificationChannel, NotificationEvent, RunSummary};
+use crate::notification::email::{mailer, notification_email};
+use crate::notification::slack::post_to_slack;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::run::model::{Run, Verdict};
+use tracing::{error, info, warn};
+
+/// How long before expiry an auth provider of a finished run is reported.
+pub const AUTH_EXPIRY_NOTICE_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// Delivers the notification to every channel the customer's routing rules select for it.
+/// Delivery failures are logged, never surfaced to the caller.
+pub async fn notify(repository: &Repository, notification: Notification) {
+    let settings = match repository.notification_settings().get(&notification.customer_id).await {
+        Ok(settings) => settings,
+        Err(err) => {
+            error!("could not load notification settings: {:?}", err);
+            return;
+        }
+    };
+    for channel in settings.channels_for(&notification, current_timestamp()) {
+        deliver(&channel, &notification).await;
+    }
+}
+
+async fn deliver(channel: &NotificationChannel, notification: &Notification) {
+    match channel {
+        NotificationChannel::Webhook { url } => {
+            let result = reqwest::Client::new()
+                .post(url)
+                .json(notification)
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    info!("delivered {:?} notification to webhook", notification.event);
+                }
+                Ok(response) => {
+                    error!("notification webhook rejected with status: {}", response.status());
+                }
+                Err(err) => {
+                    error!("notification webhook failed: {}", err);
+                }
+            }
+        }
+        NotificationChannel::Slack { webhook_url, bot_token, channel } => {
+            post_to_slack(webhook_url.as_ref(), bot_token.as_ref(), channel.as_ref(), notification).await;
+        }
+        NotificationChannel::Email { addresses } => {
+            let Some(mailer) = mailer() else {
+                warn!("email channel is configured but no email sender is, skipping notification");
+                return;
+            };
+            let (subject, text) = notification_email(notification);
+            if let Err(err) = mailer.send(addresses, &subject, &text).await {
+                error!("notification email failed: {:?}", err);
+            }
+        }
+    }
+}
+
+/// Notification of a finished run: `RunPassed` as info, or `RunFailed` as critical otherwise.
+pub fn run_notification(run: &Run, verdict: &Verdict, assertion_results: &[AssertionResult], finished_at: u64) -> Notification {
+    let failed_assertions: Vec<String> = assertion_results.iter()
+        .filter(|result| result.is_blocking())
+        .map(|result| result.message.clone().unwrap_or_else(|| format!("assertion {} failed", result.assertion_id)))
+        .collect();
+    let (event, severity, outcome) = match verdict {
+        Verdict::Passed => (NotificationEvent::RunPassed, Severity::Info, "passed"),
+        Verdict::Failed => (NotificationEvent::RunFailed, Severity::Critical, "failed"),
+        Verdict::Errored => (NotificationEvent::RunFailed, Severity::Critical, "errored"),
+        Verdict::TimedOut => (NotificationEvent::RunFailed, Severity::Critical, "timed out"),
+    };
+    let link = app_config().public_url.as_ref()
+        .map(|public_url| format!("{}/test-cases/{}/runs/{}", public_url.trim_end_matches('/'), run.test_case_id, run.id));
+    Notification::builder()
+        .customer_id(run.customer_id.clone())
+        .event(event)
+        .severity(severity)
+        .test_case_id(run.test_case_id.clone())
+        .run_id(run.id.clone())
+        .title(format!("Run {}", outcome))
+        .message(format!("Run {} of test case {} {} with {} failed assertions", run.id, run.test_case_id, outcome, failed_assertions.len()))
+        .run_summary(RunSummary {
+            verdict: verdict.clone(),
+            duration_ms: finished_at.saturating_sub(run.started_at),
+            failed_assertions,
+            link,
+        })
+        .build()
+}
+
+/// Warns about auth providers linked to the run's test case that expire soon.
+pub async fn notify_expiring_auth_providers(repository: &Repository, run: &Run) {
+    let providers = match repository.auth_providers()
+        .list(ListAuthProvidersRequest::builder()
+            .customer_id(run.customer_id.clone())
+            .test_case_id(run.test_case_id.clone())
+            .build())
+        .await {
+        Ok(result) => result.items,
+        Err(err) => {
+            error!("could not list auth providers: {:?}", err);
+            return;
+        }
+    };
+    let now = current_timestamp();
+    for provider in providers.iter().filter(|provider| provider.expires_within(now, AUTH_EXPIRY_NOTICE_MILLIS)) {
+        notify(repository, Notification::builder()
+            .customer_id(run.customer_id.clone())
+            .event(NotificationEvent::AuthExpiring)
+            .severity(Severity::Warning)
+            .test_case_id(run.test_case_id.clone())
+            .run_id(run.id.clone())
+            .title("Auth provider expiring".to_string())
+            .message(format!("Auth provider {} for {} expires within a day", provider.name, provider.base_url))
+            .build()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::model::RunStatus;
+
+    #[test]
+    fn run_outcome_decides_the_event() {
+        let run = Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("run1".to_string())
+            .status(RunStatus::Finished)
+            .started_at(1000)
+            .build();
+        let results = vec![
+            AssertionResult::of_success("a1".to_string()),
+            AssertionResult::from_error("a2".to_string(), "expected: 1, but got: 2".to_string()),
+            AssertionResult::from_error("a3".to_string(), "slow".to_string()).with_severity(Severity::Warning),
+        ];
+
+        let passed = run_notification(&run, &Verdict::Passed, &[], 1500);
+        assert_eq!((passed.event, passed.severity), (NotificationEvent::RunPassed, Severity::Info));
+        let failed = run_notification(&run, &Verdict::Failed, &results, 4000);
+        assert_eq!((failed.event, failed.severity), (NotificationEvent::RunFailed, Severity::Critical));
+        assert_eq!(failed.run_id, Some("run1".to_string()));
+        assert_eq!(failed.message, "Run run1 of test case tc1 failed with 1 failed assertions");
+        let summary = failed.run_summary.unwrap();
+        assert_eq!(summary.duration_ms, 3000);
+        assert_eq!(summary.failed_assertions, vec!["expected: 1, but got: 2"]);
+    }
+}
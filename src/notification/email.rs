@@ -0,0 +1,135 @@
+use crate::api::AppError;
+use crate::auth::model::RequestSigning;
+use crate::config::AppConfig;
+use crate::notification::model::Notification;
+use crate::signing::sign_request;
+use aws_config::SdkConfig;
+use aws_credential_types::provider::{ProvideCredentials, SharedCredentialsProvider};
+use aws_credential_types::Credentials;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Request;
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use tracing::warn;
+
+static MAILER: OnceLock<Mailer> = OnceLock::new();
+
+/// Sends plain text emails through the SES v2 API, signed with the credentials of the AWS config
+/// the repository uses. There is no SES crate among the dependencies, so requests are signed the
+/// way the requests of actions are, see `sign_request`.
+pub struct Mailer {
+    sender: String,
+    region: String,
+    credentials: SharedCredentialsProvider,
+    http: reqwest::Client,
+}
+
+/// Sets the mailer up when `email_sender` is configured; without it no email is sent.
+pub fn init_mailer(sdk_config: &SdkConfig, app_config: &AppConfig) {
+    let Some(sender) = &app_config.email_sender else {
+        return;
+    };
+    let (Some(region), Some(credentials)) = (sdk_config.region(), sdk_config.credentials_provider()) else {
+        warn!("email sender {} is configured without an AWS region or credentials, no email is sent", sender);
+        return;
+    };
+    let _ = MAILER.set(Mailer {
+        sender: sender.clone(),
+        region: region.to_string(),
+        credentials,
+        http: reqwest::Client::new(),
+    });
+}
+
+pub fn mailer() -> Option<&'static Mailer> {
+    MAILER.get()
+}
+
+impl Mailer {
+    pub async fn send(&self, to: &[String], subject: &str, text: &str) -> Result<(), AppError> {
+        let credentials = self.credentials.provide_credentials().await
+            .map_err(|err| AppError::Internal(format!("could not load AWS credentials: {}", err)))?;
+        let url = format!("https://email.{}.amazonaws.com/v2/email/outbound-emails", self.region);
+        let body = ses_email(&self.sender, to, subject, text).to_string();
+        let request = self.http.post(&url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .build()
+            .map_err(|err| AppError::Internal(format!("could not build the email request: {}", err)))?;
+        let request = ses_signed(request, &self.region, &credentials, SystemTime::now());
+        self.http.execute(request).await
+            .and_then(|response| response.error_for_status())
+            .map(|_| ())
+            .map_err(|err| AppError::Internal(format!("could not send email to {}: {}", to.join(", "), err)))
+    }
+}
+
+/// Subject and text of the email for a notification.
+pub fn notification_email(notification: &Notification) -> (String, String) {
+    let mut text = notification.message.clone();
+    if let Some(summary) = &notification.run_summary {
+        text.push_str(&format!("\n\nDuration: {:.1}s", summary.duration_ms as f64 / 1000.0));
+        if !summary.failed_assertions.is_empty() {
+            text.push_str("\n\nFailed assertions:");
+            summary.failed_assertions.iter().for_each(|message| text.push_str(&format!("\n- {}", message)));
+        }
+        if let Some(link) = &summary.link {
+            text.push_str(&format!("\n\nRun report: {}", link));
+        }
+    }
+    (format!("[parroton] {}", notification.title), text)
+}
+
+/// Body of an SES v2 `SendEmail` call with a simple text message.
+fn ses_email(sender: &str, to: &[String], subject: &str, text: &str) -> Value {
+    json!({
+        "FromEmailAddress": sender,
+        "Destination": {"ToAddresses": to},
+        "Content": {
+            "Simple": {
+                "Subject": {"Data": subject, "Charset": "UTF-8"},
+                "Body": {"Text": {"Data": text, "Charset": "UTF-8"}},
+            }
+        }
+    })
+}
+
+fn ses_signed(mut request: Request, region: &str, credentials: &Credentials, time: SystemTime) -> Request {
+    let signing = RequestSigning::SigV4 {
+        access_key_id: credentials.access_key_id().to_string(),
+        secret_access_key: credentials.secret_access_key().to_string(),
+        session_token: credentials.session_token().map(|token| token.to_string()),
+        region: region.to_string(),
+        service: "ses".to_string(),
+    };
+    sign_request(&mut request, &signing, time);
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn ses_request_is_signed() {
+        let credentials = Credentials::new("AKIDEXAMPLE", "secret", None, None, "test");
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let body = ses_email("parroton@abc.xyz", &["ayse@abc.xyz".to_string()], "Digest", "All passed").to_string();
+
+        let request = reqwest::Client::new().post("https://email.eu-west-1.amazonaws.com/v2/email/outbound-emails")
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone())
+            .build()
+            .unwrap();
+
+        let request = ses_signed(request, "eu-west-1", &credentials, time);
+        let header = |name: &str| request.headers().get(name).map(|value| value.to_str().unwrap().to_string());
+
+        assert_eq!(header("content-type"), Some("application/json".to_string()));
+        assert_eq!(header("x-amz-date"), Some("20231114T221320Z".to_string()));
+        assert!(header("authorization").unwrap().starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20231114/eu-west-1/ses/aws4_request"));
+        assert!(body.contains("\"ToAddresses\":[\"ayse@abc.xyz\"]"));
+    }
+}
@@ -0,0 +1,7 @@
+pub mod model;
+pub mod service;
+pub mod dispatch;
+pub mod slack;
+pub mod email;
+pub mod digest;
+pub mod api;
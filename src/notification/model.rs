@@ -0,0 +1,292 @@
+use crate::assertion::model::Severity;
+use crate::run::model::Verdict;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const NOTIFICATION_SETTINGS_ID: &str = "notification-settings";
+const HOUR_MILLIS: i64 = 60 * 60 * 1000;
+const DAY_MILLIS: i64 = 24 * HOUR_MILLIS;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, Hash, ToSchema)]
+pub enum NotificationEvent {
+    RunPassed,
+    RunFailed,
+    ScheduleMissed,
+    AuthExpiring,
+}
+
+/// Where a notification is delivered.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationChannel {
+    /// Receives the notification as a JSON POST.
+    Webhook { url: String },
+    /// Posts a formatted message through an incoming webhook, or with a bot token to `channel`.
+    Slack {
+        webhook_url: Option<String>,
+        bot_token: Option<String>,
+        channel: Option<String>,
+    },
+    /// Sends a plain text email through SES to every address.
+    Email { addresses: Vec<String> },
+}
+
+/// Sends the listed events to the channels when they are at least `min_severity`.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Builder, ToSchema)]
+pub struct RoutingRule {
+    pub events: Vec<NotificationEvent>,
+    #[serde(default = "least_severity")]
+    #[builder(default = least_severity())]
+    pub min_severity: Severity,
+    pub channels: Vec<NotificationChannel>,
+    /// Limits the rule to notifications about these test cases; every test case when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub test_case_ids: Vec<String>,
+}
+
+impl RoutingRule {
+    fn matches(&self, notification: &Notification) -> bool {
+        let test_case_matches = self.test_case_ids.is_empty() || notification.test_case_id.as_ref()
+            .is_some_and(|test_case_id| self.test_case_ids.contains(test_case_id));
+        test_case_matches
+            && self.events.contains(&notification.event)
+            && severity_rank(&notification.severity) >= severity_rank(&self.min_severity)
+    }
+}
+
+fn least_severity() -> Severity {
+    Severity::Info
+}
+
+/// Hours of the day, at `utc_offset_minutes`, in which only critical notifications are sent.
+/// The window wraps midnight when `start_hour` is after `end_hour`, e.g. 22 to 7.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, ToSchema)]
+pub struct QuietHours {
+    pub start_hour: u8,
+    pub end_hour: u8,
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, now_millis: u64) -> bool {
+        let local_millis = now_millis as i64 + self.utc_offset_minutes as i64 * 60 * 1000;
+        let hour = (local_millis.rem_euclid(DAY_MILLIS) / HOUR_MILLIS) as u8;
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Routing of a customer's notifications. Customers without stored settings get no notifications.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct NotificationSettings {
+    pub customer_id: String,
+    #[builder(default = NOTIFICATION_SETTINGS_ID.to_string())]
+    pub id: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub rules: Vec<RoutingRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl NotificationSettings {
+    pub fn default_for(customer_id: &str) -> Self {
+        NotificationSettings::builder()
+            .customer_id(customer_id.to_string())
+            .build()
+    }
+
+    /// Channels of every rule matching the notification, each listed once.
+    pub fn channels_for(&self, notification: &Notification, now_millis: u64) -> Vec<NotificationChannel> {
+        let quiet = self.quiet_hours.as_ref().is_some_and(|quiet_hours| quiet_hours.contains(now_millis));
+        if quiet && notification.severity != Severity::Critical {
+            return vec![];
+        }
+        let mut channels: Vec<NotificationChannel> = vec![];
+        self.rules.iter()
+            .filter(|rule| rule.matches(notification))
+            .flat_map(|rule| rule.channels.iter())
+            .for_each(|channel| {
+                if !channels.contains(channel) {
+                    channels.push(channel.clone());
+                }
+            });
+        channels
+    }
+}
+
+fn severity_rank(severity: &Severity) -> u8 {
+    match severity {
+        Severity::Info => 0,
+        Severity::Warning => 1,
+        Severity::Critical => 2,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Notification {
+    pub customer_id: String,
+    pub event: NotificationEvent,
+    pub severity: Severity,
+    pub test_case_id: Option<String>,
+    pub run_id: Option<String>,
+    pub title: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_summary: Option<RunSummary>,
+}
+
+/// Outcome of the run a notification is about.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RunSummary {
+    pub verdict: Verdict,
+    pub duration_ms: u64,
+    /// Messages of the blocking assertion failures.
+    pub failed_assertions: Vec<String>,
+    /// Run report, when the public URL of the API is configured.
+    pub link: Option<String>,
+}
+
+/// A user's subscription to the daily email digest of scheduled runs, `id` being the user id.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct DigestSubscription {
+    pub customer_id: String,
+    pub id: String,
+    pub email: String,
+    /// Limits the digest to these test cases; every test case when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub test_case_ids: Vec<String>,
+    /// UTC hour of the day the digest is sent at.
+    #[serde(default)]
+    #[builder(default)]
+    pub send_hour_utc: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_sent_at: Option<u64>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl DigestSubscription {
+    /// Due once a day in the subscribed hour.
+    pub fn is_due(&self, now_millis: u64) -> bool {
+        let hour = ((now_millis as i64).rem_euclid(DAY_MILLIS) / HOUR_MILLIS) as u8;
+        let sent_today = self.last_sent_at
+            .is_some_and(|last_sent_at| now_millis.saturating_sub(last_sent_at) < (DAY_MILLIS - HOUR_MILLIS) as u64);
+        hour == self.send_hour_utc && !sent_today
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(event: NotificationEvent, severity: Severity) -> Notification {
+        Notification::builder()
+            .customer_id("cust1".to_string())
+            .event(event)
+            .severity(severity)
+            .title("Run failed".to_string())
+            .message("2 assertions failed".to_string())
+            .build()
+    }
+
+    fn webhook(url: &str) -> NotificationChannel {
+        NotificationChannel::Webhook { url: url.to_string() }
+    }
+
+    #[test]
+    fn route_by_event_and_severity() {
+        let settings = NotificationSettings::builder()
+            .customer_id("cust1".to_string())
+            .rules(vec![
+                RoutingRule::builder()
+                    .events(vec![NotificationEvent::RunFailed, NotificationEvent::AuthExpiring])
+                    .channels(vec![webhook("https://hooks.abc.xyz/all")])
+                    .build(),
+                RoutingRule::builder()
+                    .events(vec![NotificationEvent::RunFailed])
+                    .min_severity(Severity::Critical)
+                    .channels(vec![webhook("https://hooks.abc.xyz/all"), webhook("https://hooks.abc.xyz/pager")])
+                    .build(),
+            ])
+            .build();
+
+        assert_eq!(settings.channels_for(&notification(NotificationEvent::RunFailed, Severity::Critical), 0),
+                   vec![webhook("https://hooks.abc.xyz/all"), webhook("https://hooks.abc.xyz/pager")]);
+        assert_eq!(settings.channels_for(&notification(NotificationEvent::AuthExpiring, Severity::Warning), 0),
+                   vec![webhook("https://hooks.abc.xyz/all")]);
+        assert!(settings.channels_for(&notification(NotificationEvent::ScheduleMissed, Severity::Critical), 0).is_empty());
+        assert!(NotificationSettings::default_for("cust1").channels_for(&notification(NotificationEvent::RunFailed, Severity::Critical), 0).is_empty());
+    }
+
+    #[test]
+    fn route_by_test_case() {
+        let settings = NotificationSettings::builder()
+            .customer_id("cust1".to_string())
+            .rules(vec![RoutingRule::builder()
+                .events(vec![NotificationEvent::RunPassed, NotificationEvent::RunFailed])
+                .channels(vec![webhook("https://hooks.abc.xyz/checkout")])
+                .test_case_ids(vec!["checkout".to_string()])
+                .build()])
+            .build();
+        let about = |test_case_id: Option<&str>| Notification {
+            test_case_id: test_case_id.map(|id| id.to_string()),
+            ..notification(NotificationEvent::RunPassed, Severity::Info)
+        };
+
+        assert_eq!(settings.channels_for(&about(Some("checkout")), 0).len(), 1);
+        assert!(settings.channels_for(&about(Some("search")), 0).is_empty());
+        assert!(settings.channels_for(&about(None), 0).is_empty());
+    }
+
+    #[test]
+    fn quiet_hours_hold_back_non_critical() {
+        let hour = |hour: u64| hour * HOUR_MILLIS as u64;
+        let overnight = QuietHours { start_hour: 22, end_hour: 7, utc_offset_minutes: 0 };
+        assert!(overnight.contains(hour(23)));
+        assert!(overnight.contains(hour(24 + 3)));
+        assert!(!overnight.contains(hour(7)));
+        assert!(!overnight.contains(hour(12)));
+        let shifted = QuietHours { start_hour: 22, end_hour: 7, utc_offset_minutes: 180 };
+        assert!(shifted.contains(hour(20)));
+        assert!(!shifted.contains(hour(5)));
+
+        let settings = NotificationSettings::builder()
+            .customer_id("cust1".to_string())
+            .rules(vec![RoutingRule::builder()
+                .events(vec![NotificationEvent::RunFailed])
+                .channels(vec![webhook("https://hooks.abc.xyz/all")])
+                .build()])
+            .quiet_hours(overnight)
+            .build();
+        assert!(settings.channels_for(&notification(NotificationEvent::RunFailed, Severity::Warning), hour(23)).is_empty());
+        assert_eq!(settings.channels_for(&notification(NotificationEvent::RunFailed, Severity::Critical), hour(23)).len(), 1);
+        assert_eq!(settings.channels_for(&notification(NotificationEvent::RunFailed, Severity::Warning), hour(12)).len(), 1);
+    }
+
+    #[test]
+    fn digest_is_due_once_a_day_in_its_hour() {
+        let hour = |hour: u64| hour * HOUR_MILLIS as u64;
+        let subscription = DigestSubscription::builder()
+            .customer_id("cust1".to_string())
+            .id("ayse".to_string())
+            .email("ayse@abc.xyz".to_string())
+            .send_hour_utc(8)
+            .build();
+
+        assert!(subscription.is_due(hour(24 + 8)));
+        assert!(!subscription.is_due(hour(24 + 9)));
+        let sent = DigestSubscription { last_sent_at: Some(hour(24 + 8)), ..subscription };
+        assert!(!sent.is_due(hour(24 + 8) + 30 * 60 * 1000));
+        assert!(sent.is_due(hour(48 + 8)));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::api::AppError;
+use crate::notification::model::{DigestSubscription, NotificationSettings, NOTIFICATION_SETTINGS_ID};
+use crate::persistence::repo::{from_sdk_error, Table};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct NotificationSettingsOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct NotificationSettingsTable();
+
+impl Table<NotificationSettings> for NotificationSettingsTable {
+    fn table_name() -> String {
+        "notification_settings".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &NotificationSettings) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &NotificationSettings) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl NotificationSettingsOperations {
+    pub async fn get(&self, customer_id: &str) -> Result<NotificationSettings, AppError> {
+        NotificationSettingsTable::get_item(self.client.clone(), customer_id.to_string(), NOTIFICATION_SETTINGS_ID.to_string())
+            .await
+            .map(|settings| settings.unwrap_or_else(|| NotificationSettings::default_for(customer_id)))
+    }
+
+    pub async fn put(&self, settings: NotificationSettings) -> Result<NotificationSettings, AppError> {
+        NotificationSettingsTable::put_item(self.client.clone(), settings).await
+    }
+}
+
+pub struct DigestSubscriptionOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct DigestSubscriptionsTable();
+
+impl Table<DigestSubscription> for DigestSubscriptionsTable {
+    fn table_name() -> String {
+        "digest_subscriptions".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &DigestSubscription) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &DigestSubscription) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl DigestSubscriptionOperations {
+    pub async fn get(&self, customer_id: &str, user_id: &str) -> Result<Option<DigestSubscription>, AppError> {
+        DigestSubscriptionsTable::get_item(self.client.clone(), customer_id.to_string(), user_id.to_string()).await
+    }
+
+    pub async fn put(&self, subscription: DigestSubscription) -> Result<DigestSubscription, AppError> {
+        DigestSubscriptionsTable::put_item(self.client.clone(), subscription).await
+    }
+
+    pub async fn delete(&self, customer_id: &str, user_id: &str) -> Result<Option<DigestSubscription>, AppError> {
+        DigestSubscriptionsTable::delete_item(self.client.clone(), customer_id.to_string(), user_id.to_string()).await
+    }
+
+    /// Moves `last_sent_at` of the subscription from `previous` to `sent_at`, only that field, so
+    /// of the replicas checking for due digests only one gets to send it. False when another one
+    /// already did or the subscription is gone.
+    pub async fn claim(&self, subscription: &DigestSubscription, previous: Option<u64>, sent_at: Option<u64>) -> Result<bool, AppError> {
+        let mut request = self.client.update_item()
+            .table_name(DigestSubscriptionsTable::qualified_table_name())
+            .set_key(Some(DigestSubscriptionsTable::unique_key(subscription.customer_id.clone(), subscription.id.clone())))
+            .expression_attribute_names("#id", "id")
+            .expression_attribute_names("#last_sent_at", "last_sent_at");
+        request = match sent_at {
+            Some(sent_at) => request
+                .update_expression("SET #last_sent_at = :sent_at")
+                .expression_attribute_values(":sent_at", AttributeValue::N(sent_at.to_string())),
+            None => request.update_expression("REMOVE #last_sent_at"),
+        };
+        request = match previous {
+            Some(previous) => request
+                .condition_expression("attribute_exists(#id) AND #last_sent_at = :previous")
+                .expression_attribute_values(":previous", AttributeValue::N(previous.to_string())),
+            None => request.condition_expression("attribute_exists(#id) AND attribute_not_exists(#last_sent_at)"),
+        };
+        match request.send().await {
+            Ok(_) => Ok(true),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) => Ok(false),
+            Err(err) => Err(from_sdk_error(&DigestSubscriptionsTable::qualified_table_name(), err)),
+        }
+    }
+
+    /// Every subscription across customers.
+    pub async fn list_all(&self) -> Result<Vec<DigestSubscription>, AppError> {
+        let mut subscriptions = vec![];
+        for customer_id in DigestSubscriptionsTable::scan_partition_keys(self.client.clone()).await? {
+            subscriptions.extend(DigestSubscriptionsTable::list_all_items(self.client.clone(), customer_id).await?);
+        }
+        Ok(subscriptions)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::notification::model::Notification;
+use crate::run::model::Verdict;
+use serde_json::{json, Value};
+use tracing::{error, info};
+
+const SLACK_POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+/// Failed assertions listed in a message before the rest are summed up.
+const MAX_LISTED_ASSERTIONS: usize = 5;
+
+/// Slack message of the notification; run notifications get their verdict, duration, failed
+/// assertions and a link to the run.
+pub fn slack_message(notification: &Notification) -> Value {
+    let Some(summary) = &notification.run_summary else {
+        return json!({
+            "text": format!("{}: {}", notification.title, notification.message),
+            "blocks": [section(format!("*{}*\n{}", notification.title, notification.message))],
+        });
+    };
+    let icon = match summary.verdict {
+        Verdict::Passed => ":white_check_mark:",
+        _ => ":x:",
+    };
+    let mut text = format!("{} *{}*\n{}\nDuration: {:.1}s", icon, notification.title, notification.message, summary.duration_ms as f64 / 1000.0);
+    if !summary.failed_assertions.is_empty() {
+        text.push_str("\nFailed assertions:");
+        summary.failed_assertions.iter()
+            .take(MAX_LISTED_ASSERTIONS)
+            .for_each(|message| text.push_str(&format!("\n• {}", message)));
+        if summary.failed_assertions.len() > MAX_LISTED_ASSERTIONS {
+            text.push_str(&format!("\n…and {} more", summary.failed_assertions.len() - MAX_LISTED_ASSERTIONS));
+        }
+    }
+    if let Some(link) = &summary.link {
+        text.push_str(&format!("\n<{}|Open run report>", link));
+    }
+    json!({
+        "text": format!("{}: {}", notification.title, notification.message),
+        "blocks": [section(text)],
+    })
+}
+
+fn section(text: String) -> Value {
+    json!({"type": "section", "text": {"type": "mrkdwn", "text": text}})
+}
+
+/// Posts through the incoming webhook when one is set, otherwise with the bot token to `channel`.
+pub async fn post_to_slack(webhook_url: Option<&String>, bot_token: Option<&String>, channel: Option<&String>, notification: &Notification) {
+    let mut message = slack_message(notification);
+    let request = match (webhook_url, bot_token, channel) {
+        (Some(webhook_url), _, _) => reqwest::Client::new().post(webhook_url),
+        (None, Some(bot_token), Some(channel)) => {
+            message["channel"] = json!(channel);
+            reqwest::Client::new().post(SLACK_POST_MESSAGE_URL).bearer_auth(bot_token)
+        }
+        _ => {
+            error!("slack channel needs a webhook URL, or a bot token and a channel");
+            return;
+        }
+    };
+    match request.json(&message).send().await {
+        Ok(response) if response.status().is_success() => {
+            // the Web API answers errors like an unknown channel with 200 and `ok: false`
+            let body: Value = response.json().await.unwrap_or(Value::Null);
+            if body.get("ok") == Some(&Value::Bool(false)) {
+                error!("slack rejected the message: {}", body["error"]);
+            } else {
+                info!("delivered {:?} notification to slack", notification.event);
+            }
+        }
+        Ok(response) => {
+            error!("slack rejected the message with status: {}", response.status());
+        }
+        Err(err) => {
+            error!("slack message failed: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assertion::model::Severity;
+    use crate::notification::model::{NotificationEvent, RunSummary};
+
+    #[test]
+    fn run_message_lists_failures_and_links_the_run() {
+        let notification = Notification::builder()
+            .customer_id("cust1".to_string())
+            .event(NotificationEvent::RunFailed)
+            .severity(Severity::Critical)
+            .title("Run failed".to_string())
+            .message("Run run1 of test case tc1 failed with 7 failed assertions".to_string())
+            .run_summary(RunSummary {
+                verdict: Verdict::Failed,
+                duration_ms: 2360,
+                failed_assertions: (1..=7).map(|i| format!("expected: {}, but got: 0", i)).collect(),
+                link: Some("https://parroton.abc.xyz/test-cases/tc1/runs/run1".to_string()),
+            })
+            .build();
+
+        let text = slack_message(&notification)["blocks"][0]["text"]["text"].as_str().unwrap().to_string();
+
+        assert!(text.starts_with(":x: *Run failed*\n"));
+        assert!(text.contains("Duration: 2.4s"));
+        assert!(text.contains("• expected: 5, but got: 0\n…and 2 more"));
+        assert!(!text.contains("expected: 6"));
+        assert!(text.ends_with("<https://parroton.abc.xyz/test-cases/tc1/runs/run1|Open run report>"));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::api::ApiResponse;
+use crate::change::version::IfMatch;
+use crate::validation::{AppJson, ValidJson};
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::{HeaderMap, Method, StatusCode};
+use axum::response::sse::Sse;
+use axum::response::Response;
+use axum::Json;
+use std::any::type_name;
+use std::future::Future;
+use utoipa::openapi::path::{Parameter, ParameterBuilder, ParameterIn};
+use utoipa::openapi::request_body::{RequestBody, RequestBodyBuilder};
+use utoipa::openapi::{Content, ObjectBuilder, Ref, RefOr, Required, Response as ResponseDoc, ResponseBuilder, Schema, Type};
+use utoipa::{IntoParams, ToSchema};
+
+/// What the extractors and the output of one handler add to its operation in the spec.
+#[derive(Default, Clone)]
+pub struct OperationDoc {
+    pub parameters: Vec<Parameter>,
+    pub request_body: Option<RequestBody>,
+    pub response: Option<ResponseDoc>,
+    /// Schemas referenced from the ones above, to be listed under `components`.
+    pub schemas: Vec<(String, RefOr<Schema>)>,
+}
+
+impl OperationDoc {
+    /// Types of this crate are listed under `components` and referenced by name; generic ones,
+    /// e.g. `QueryResult<Action>`, share their name with other instances and stay inline.
+    fn json_schema<T: ToSchema>(&mut self) -> RefOr<Schema> {
+        T::schemas(&mut self.schemas);
+        let type_name = type_name::<T>();
+        if type_name.starts_with("parroton") && !type_name.contains('<') {
+            self.schemas.push((T::name().to_string(), T::schema()));
+            return RefOr::Ref(Ref::from_schema_name(T::name()));
+        }
+        T::schema()
+    }
+}
+
+/// Extractor of a handler, documented as the parameters or the body it reads.
+pub trait DocumentedInput {
+    fn document(_operation: &mut OperationDoc) {}
+}
+
+/// Return type of a handler, documented as its successful response.
+pub trait DocumentedOutput {
+    fn document(_operation: &mut OperationDoc) {}
+}
+
+/// Implemented for every handler whose extractors and output are documented, so a route cannot be
+/// registered without showing up in `/openapi.json`.
+pub trait DocumentedHandler<T> {
+    fn document(operation: &mut OperationDoc);
+}
+
+impl<F, Fut> DocumentedHandler<((),)> for F
+where
+    F: FnOnce() -> Fut,
+    Fut: Future,
+    Fut::Output: DocumentedOutput,
+{
+    fn document(operation: &mut OperationDoc) {
+        <Fut::Output as DocumentedOutput>::document(operation);
+    }
+}
+
+macro_rules! documented_handler {
+    ($($extractor:ident),+) => {
+        impl<F, Fut, M, $($extractor,)+> DocumentedHandler<(M, $($extractor,)+)> for F
+        where
+            F: FnOnce($($extractor,)+) -> Fut,
+            Fut: Future,
+            Fut::Output: DocumentedOutput,
+            $($extractor: DocumentedInput,)+
+        {
+            fn document(operation: &mut OperationDoc) {
+                $(<$extractor as DocumentedInput>::document(operation);)+
+                <Fut::Output as DocumentedOutput>::document(operation);
+            }
+        }
+    };
+}
+
+documented_handler!(T1);
+documented_handler!(T1, T2);
+documented_handler!(T1, T2, T3);
+documented_handler!(T1, T2, T3, T4);
+documented_handler!(T1, T2, T3, T4, T5);
+documented_handler!(T1, T2, T3, T4, T5, T6);
+documented_handler!(T1, T2, T3, T4, T5, T6, T7);
+documented_handler!(T1, T2, T3, T4, T5, T6, T7, T8);
+
+/// Path parameters are read from the route itself.
+impl<T> DocumentedInput for Path<T> {}
+
+impl<T> DocumentedInput for State<T> {}
+
+impl DocumentedInput for HeaderMap {}
+
+impl DocumentedInput for Method {}
+
+impl DocumentedInput for IfMatch {
+    fn document(operation: &mut OperationDoc) {
+        operation.parameters.push(ParameterBuilder::new()
+            .name("If-Match")
+            .parameter_in(ParameterIn::Header)
+            .required(Required::False)
+            .description(Some("`updated_at` of the entity the change is based on"))
+            .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+            .build());
+    }
+}
+
+impl<T: IntoParams> DocumentedInput for Query<T> {
+    fn document(operation: &mut OperationDoc) {
+        operation.parameters.extend(T::into_params(|| Some(ParameterIn::Query)));
+    }
+}
+
+fn json_body<T: ToSchema>(operation: &mut OperationDoc) {
+    let schema = operation.json_schema::<T>();
+    operation.request_body = Some(RequestBodyBuilder::new()
+        .required(Some(Required::True))
+        .content("application/json", Content::new(Some(schema)))
+        .build());
+}
+
+impl<T: ToSchema> DocumentedInput for AppJson<T> {
+    fn document(operation: &mut OperationDoc) {
+        json_body::<T>(operation);
+    }
+}
+
+impl<T: ToSchema> DocumentedInput for ValidJson<T> {
+    fn document(operation: &mut OperationDoc) {
+        json_body::<T>(operation);
+    }
+}
+
+impl<T: ToSchema> DocumentedInput for Json<T> {
+    fn document(operation: &mut OperationDoc) {
+        json_body::<T>(operation);
+    }
+}
+
+/// Optional bodies are documented like the required ones, marked as not required.
+impl<T: ToSchema> DocumentedInput for Option<Json<T>> {
+    fn document(operation: &mut OperationDoc) {
+        json_body::<T>(operation);
+        if let Some(body) = operation.request_body.as_mut() {
+            body.required = Some(Required::False);
+        }
+    }
+}
+
+/// Raw text bodies, e.g. YAML documents.
+impl DocumentedInput for String {
+    fn document(operation: &mut OperationDoc) {
+        operation.request_body = Some(RequestBodyBuilder::new()
+            .required(Some(Required::True))
+            .content("text/plain", Content::new(Some(ObjectBuilder::new().schema_type(Type::String))))
+            .build());
+    }
+}
+
+impl DocumentedInput for Multipart {
+    fn document(operation: &mut OperationDoc) {
+        operation.request_body = Some(RequestBodyBuilder::new()
+            .required(Some(Required::True))
+            .content("multipart/form-data", Content::new(Some(ObjectBuilder::new().schema_type(Type::Object))))
+            .build());
+    }
+}
+
+fn json_response<T: ToSchema>(operation: &mut OperationDoc) {
+    let schema = operation.json_schema::<T>();
+    operation.response = Some(ResponseBuilder::new()
+        .description("Success")
+        .content("application/json", Content::new(Some(schema)))
+        .build());
+}
+
+impl<T: ToSchema> DocumentedOutput for ApiResponse<T> {
+    fn document(operation: &mut OperationDoc) {
+        json_response::<T>(operation);
+    }
+}
+
+impl<T: ToSchema> DocumentedOutput for Json<T> {
+    fn document(operation: &mut OperationDoc) {
+        json_response::<T>(operation);
+    }
+}
+
+/// Errors are documented once for all operations, see `build_spec`.
+impl<T: DocumentedOutput, E> DocumentedOutput for Result<T, E> {
+    fn document(operation: &mut OperationDoc) {
+        T::document(operation);
+    }
+}
+
+/// Responses built by hand, e.g. file downloads, are only documented by their status.
+impl DocumentedOutput for Response {}
+
+impl DocumentedOutput for StatusCode {}
+
+impl<S> DocumentedOutput for Sse<S> {
+    fn document(operation: &mut OperationDoc) {
+        operation.response = Some(ResponseBuilder::new()
+            .description("Stream of server-sent events")
+            .content("text/event-stream", Content::new(Some(ObjectBuilder::new().schema_type(Type::String))))
+            .build());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::openapi::document::{DocumentedHandler, OperationDoc};
+use crate::openapi::spec::build_spec;
+use axum::handler::Handler;
+use axum::routing::{self, MethodRouter};
+use axum::Router;
+use std::any::type_name;
+use utoipa_swagger_ui::SwaggerUi;
+
+const ANY_METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+/// One method on one path as registered in the router.
+#[derive(Clone)]
+pub struct Operation {
+    pub method: &'static str,
+    pub path: String,
+    /// Fully qualified handler name, e.g. `parroton::action::api::list_actions`.
+    pub handler: &'static str,
+    /// Parameters, body and response documented by the handler's extractors and output.
+    pub doc: OperationDoc,
+}
+
+/// Handlers of one path together with what is needed to document them.
+pub struct Endpoints<S> {
+    router: MethodRouter<S>,
+    operations: Vec<(&'static str, &'static str, OperationDoc)>,
+}
+
+/// `Router` that remembers every operation so `/openapi.json` always matches what is served.
+pub struct DocumentedRouter<S> {
+    router: Router<S>,
+    operations: Vec<Operation>,
+}
+
+impl<S> DocumentedRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        DocumentedRouter { router: Router::new(), operations: vec![] }
+    }
+
+    pub fn route(mut self, path: &str, endpoints: Endpoints<S>) -> Self {
+        self.operations.extend(endpoints.operations.into_iter()
+            .map(|(method, handler, doc)| Operation { method, path: path.to_string(), handler, doc }));
+        self.router = self.router.route(path, endpoints.router);
+        self
+    }
+
+    /// Adds `/openapi.json` and the Swagger UI at `/docs` for the routes registered so far.
+    pub fn into_router(self) -> Router<S> {
+        let spec = build_spec(&self.operations);
+        self.router.merge(SwaggerUi::new("/docs").url("/openapi.json", spec))
+    }
+}
+
+fn operation_of<H: DocumentedHandler<T>, T>(method: &'static str) -> (&'static str, &'static str, OperationDoc) {
+    let mut doc = OperationDoc::default();
+    H::document(&mut doc);
+    (method, type_name::<H>(), doc)
+}
+
+pub fn get<H, T, S>(handler: H) -> Endpoints<S>
+where
+    H: Handler<T, S> + DocumentedHandler<T>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Endpoints { router: routing::get(handler), operations: vec![operation_of::<H, T>("get")] }
+}
+
+pub fn post<H, T, S>(handler: H) -> Endpoints<S>
+where
+    H: Handler<T, S> + DocumentedHandler<T>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Endpoints { router: routing::post(handler), operations: vec![operation_of::<H, T>("post")] }
+}
+
+pub fn patch<H, T, S>(handler: H) -> Endpoints<S>
+where
+    H: Handler<T, S> + DocumentedHandler<T>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Endpoints { router: routing::patch(handler), operations: vec![operation_of::<H, T>("patch")] }
+}
+
+pub fn delete<H, T, S>(handler: H) -> Endpoints<S>
+where
+    H: Handler<T, S> + DocumentedHandler<T>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Endpoints { router: routing::delete(handler), operations: vec![operation_of::<H, T>("delete")] }
+}
+
+pub fn any<H, T, S>(handler: H) -> Endpoints<S>
+where
+    H: Handler<T, S> + DocumentedHandler<T>,
+    T: 'static,
+    S: Clone + Send + Sync + 'static,
+{
+    Endpoints {
+        router: routing::any(handler),
+        operations: ANY_METHODS.iter().map(|method| operation_of::<H, T>(method)).collect(),
+    }
+}
+
+impl<S> Endpoints<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn get<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S> + DocumentedHandler<T>,
+        T: 'static,
+    {
+        self.router = self.router.get(handler);
+        self.operations.push(operation_of::<H, T>("get"));
+        self
+    }
+
+    pub fn post<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S> + DocumentedHandler<T>,
+        T: 'static,
+    {
+        self.router = self.router.post(handler);
+        self.operations.push(operation_of::<H, T>("post"));
+        self
+    }
+
+    pub fn put<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S> + DocumentedHandler<T>,
+        T: 'static,
+    {
+        self.router = self.router.put(handler);
+        self.operations.push(operation_of::<H, T>("put"));
+        self
+    }
+
+    pub fn patch<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S> + DocumentedHandler<T>,
+        T: 'static,
+    {
+        self.router = self.router.patch(handler);
+        self.operations.push(operation_of::<H, T>("patch"));
+        self
+    }
+
+    pub fn delete<H, T>(mut self, handler: H) -> Self
+    where
+        H: Handler<T, S> + DocumentedHandler<T>,
+        T: 'static,
+    {
+        self.router = self.router.delete(handler);
+        self.operations.push(operation_of::<H, T>("delete"));
+        self
+    }
+}
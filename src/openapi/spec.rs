@@ -0,0 +1,131 @@
+use crate::api::{ErrorBody, FieldError};
+use crate::openapi::routing::Operation;
+use utoipa::openapi::path::{HttpMethod, OperationBuilder, ParameterBuilder, ParameterIn};
+use utoipa::openapi::{ComponentsBuilder, Content, InfoBuilder, ObjectBuilder, OpenApi, OpenApiBuilder, Paths, Ref, Required, ResponseBuilder, Type};
+use utoipa::{PartialSchema, ToSchema};
+
+/// OpenAPI 3 document of the registered operations. Path parameters come from the route, query
+/// parameters, bodies and responses from the `ToSchema`/`IntoParams` types of the handlers.
+pub fn build_spec(operations: &[Operation]) -> OpenApi {
+    let mut paths = Paths::new();
+    let mut components = ComponentsBuilder::new()
+        .schema(FieldError::name(), FieldError::schema())
+        .schema(ErrorBody::name(), ErrorBody::schema())
+        .response("Error", ResponseBuilder::new()
+            .description("Error")
+            .content("application/json", Content::new(Some(Ref::from_schema_name(ErrorBody::name())))));
+    for operation in operations {
+        let (path, path_parameters) = openapi_path(&operation.path);
+        let parameters = path_parameters.into_iter()
+            .map(|name| ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Path)
+                .required(Required::True)
+                .schema(Some(ObjectBuilder::new().schema_type(Type::String)))
+                .build())
+            .chain(operation.doc.parameters.iter().cloned());
+        let handler = operation.handler.rsplit("::").next().unwrap_or(operation.handler);
+        let response = operation.doc.response.clone()
+            .unwrap_or_else(|| ResponseBuilder::new().description("Success").build());
+        let entry = OperationBuilder::new()
+            .operation_id(Some(handler))
+            .summary(Some(summary_of(handler)))
+            .tag(tag_of(operation.handler))
+            .parameters(Some(parameters))
+            .request_body(operation.doc.request_body.clone())
+            .response("200", response)
+            .response("default", Ref::new("#/components/responses/Error"))
+            .build();
+        paths.add_path_operation(path, vec![http_method(operation.method)], entry);
+        for (name, schema) in &operation.doc.schemas {
+            components = components.schema(name, schema.clone());
+        }
+    }
+    OpenApiBuilder::new()
+        .info(InfoBuilder::new().title("parroton").version(env!("CARGO_PKG_VERSION")))
+        .paths(paths)
+        .components(Some(components.build()))
+        .build()
+}
+
+fn http_method(method: &str) -> HttpMethod {
+    match method {
+        "post" => HttpMethod::Post,
+        "put" => HttpMethod::Put,
+        "patch" => HttpMethod::Patch,
+        "delete" => HttpMethod::Delete,
+        _ => HttpMethod::Get,
+    }
+}
+
+/// `/test-cases/:id/mock/*path` becomes `/test-cases/{id}/mock/{path}` with both names returned.
+fn openapi_path(path: &str) -> (String, Vec<String>) {
+    let mut names = vec![];
+    let segments: Vec<String> = path.split('/')
+        .map(|segment| match segment.strip_prefix(':').or_else(|| segment.strip_prefix('*')) {
+            Some(name) => {
+                names.push(name.to_string());
+                format!("{{{}}}", name)
+            }
+            None => segment.to_string(),
+        })
+        .collect();
+    (segments.join("/"), names)
+}
+
+/// Module right under the crate, e.g. `action` for `parroton::action::api::list_actions`.
+fn tag_of(handler: &str) -> &str {
+    handler.split("::").nth(1).unwrap_or(handler)
+}
+
+fn summary_of(handler: &str) -> String {
+    let words = handler.replace('_', " ");
+    let mut chars = words.chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AppState;
+    use crate::openapi::document::{DocumentedHandler, OperationDoc};
+    use axum::handler::Handler;
+    use serde_json::json;
+
+    fn operation<H: Handler<T, AppState> + DocumentedHandler<T>, T: 'static>(method: &'static str, path: &str, handler: &'static str, _: H) -> Operation {
+        let mut doc = OperationDoc::default();
+        H::document(&mut doc);
+        Operation { method, path: path.to_string(), handler, doc }
+    }
+
+    #[test]
+    fn document_registered_operations() {
+        let operations = vec![
+            operation("patch", "/test-cases/:test_case_id/actions/:id/name", "parroton::action::api::update_action_name",
+                      crate::action::api::update_action_name),
+            operation("get", "/test-cases/:test_case_id/actions", "parroton::action::api::list_actions",
+                      crate::action::api::list_actions),
+            operation("post", "/test-cases/:id/assertions/batch-get", "parroton::assertion::api::batch_get_assertions",
+                      crate::assertion::api::batch_get_assertions),
+        ];
+
+        let spec = serde_json::to_value(build_spec(&operations)).unwrap();
+
+        let rename = &spec["paths"]["/test-cases/{test_case_id}/actions/{id}/name"]["patch"];
+        assert_eq!(rename["operationId"], "update_action_name");
+        assert_eq!(rename["tags"], json!(["action"]));
+        assert_eq!(rename["parameters"][0]["name"], "test_case_id");
+        assert_eq!(rename["requestBody"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/PatchActionNamePayload");
+        assert_eq!(rename["responses"]["200"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/Action");
+        assert_eq!(spec["components"]["schemas"]["PatchActionNamePayload"]["properties"]["value"]["type"], "string");
+        assert!(spec["components"]["schemas"]["Action"]["properties"]["url"].is_object());
+        let list = &spec["paths"]["/test-cases/{test_case_id}/actions"]["get"];
+        assert!(list["parameters"].as_array().unwrap().iter().any(|parameter| parameter["in"] == "query"));
+        assert!(list.get("requestBody").is_none());
+        let batch = &spec["paths"]["/test-cases/{id}/assertions/batch-get"]["post"];
+        assert_eq!(batch["requestBody"]["content"]["application/json"]["schema"]["type"], "array");
+        assert_eq!(spec["components"]["schemas"]["ErrorBody"]["properties"]["errors"]["items"]["$ref"], "#/components/schemas/FieldError");
+    }
+}
@@ -1,20 +1,33 @@
-use crate::api::{ApiResponse, AppError};
+use crate::api::{ApiResponse, AppError, FieldError};
 use crate::json_path::model::Expression;
-use crate::parameter::model::{Parameter, ParameterIn, ParameterType};
+use crate::parameter::model::{MultiValueMode, Parameter, ParameterIn, ParameterType, ValueType};
+use crate::parameter::suggestion::{list_suggestions, review_suggestions, ExpressionReview, SuggestedExpression};
 use crate::persistence::model::QueryResult;
 use crate::persistence::repo::Repository;
+use crate::revision::history::{record_before_change, snapshot_before_change};
+use crate::validation::{AppJson, ValidJson, Validate};
+use crate::change::version::IfMatch;
 use axum::extract::{Path, Query, State};
-use axum::Json;
 use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Deserialize, Clone)]
+const MAX_BATCH_ACTIONS: usize = 100;
+
+#[derive(Deserialize, Clone, IntoParams)]
 pub struct ParameterQueryParams {
     path: Option<String>,
     parameter_type: ParameterType,
     parameter_in: Option<ParameterIn>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct BatchGetParametersPayload {
+    action_ids: Vec<String>,
+    parameter_type: Option<ParameterType>,
+}
+
+#[derive(Deserialize, ToSchema)]
 pub struct ParametersPathParam {
     test_case_id: String,
     action_id: String,
@@ -61,11 +74,31 @@ pub async fn list_parameters(
     ApiResponse::from(result)
 }
 
+/// Parameters of several actions in one call, keyed by action id. Actions without
+/// parameters are present with an empty list.
+pub async fn batch_get_parameters(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<BatchGetParametersPayload>,
+) -> Result<ApiResponse<HashMap<String, Vec<Parameter>>>, AppError> {
+    let mut seen = HashSet::new();
+    let action_ids: Vec<String> = payload.action_ids.into_iter()
+        .filter(|id| seen.insert(id.clone()))
+        .collect();
+    let result = repository
+        .parameters()
+        .batch_list_by_actions("eren".to_string(), test_case_id, &action_ids, payload.parameter_type)
+        .await;
+    ApiResponse::from(result)
+}
+
 pub async fn update_parameter_expression(
     Path(path_params): Path<ParametersPathParam>,
     State(repository): State<Repository>,
-    Json(expression): Json<Option<Expression>>,
+    IfMatch(expected_updated_at): IfMatch,
+    ValidJson(expression): ValidJson<Option<Expression>>,
 ) -> Result<ApiResponse<Parameter>, AppError> {
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &path_params.test_case_id, "parameter expression update").await;
     let result = repository
         .parameters()
         .update_expression(
@@ -74,7 +107,119 @@ pub async fn update_parameter_expression(
             path_params.action_id,
             path_params.id,
             expression,
+            expected_updated_at,
+        )
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+/// Sets the type body parameter values are sent as. A literal value that does not convert to
+/// the type is rejected; values produced by expressions are converted when the request is built.
+pub async fn update_parameter_value_type(
+    Path(path_params): Path<ParametersPathParam>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchParameterValueTypePayload>,
+) -> Result<ApiResponse<Parameter>, AppError> {
+    let parameter = repository.parameters()
+        .get("eren".to_string(), path_params.test_case_id.clone(), path_params.action_id.clone(), path_params.id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Parameter not found!".to_string()))?;
+    if let Some(value_type) = payload.value {
+        if parameter.parameter_type != ParameterType::Input || parameter.get_parameter_in() != ParameterIn::Body {
+            return Err(AppError::Validation("Only input body parameters have a value type".to_string()));
+        }
+        if parameter.value_expression.is_none() {
+            value_type.coerce(&parameter.value).map_err(AppError::Validation)?;
+        }
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &path_params.test_case_id, "parameter value type update").await;
+    let result = repository
+        .parameters()
+        .update_value_type(
+            "eren".to_string(),
+            path_params.test_case_id,
+            path_params.action_id,
+            path_params.id,
+            payload.value,
+            expected_updated_at,
+        )
+        .await;
+    revision.record_if_ok(&result).await;
+    ApiResponse::from(result)
+}
+
+/// Sets how a query parameter is sent when its expression matches several values.
+pub async fn update_parameter_multi_value_mode(
+    Path(path_params): Path<ParametersPathParam>,
+    State(repository): State<Repository>,
+    IfMatch(expected_updated_at): IfMatch,
+    AppJson(payload): AppJson<PatchParameterMultiValueModePayload>,
+) -> Result<ApiResponse<Parameter>, AppError> {
+    let parameter = repository.parameters()
+        .get("eren".to_string(), path_params.test_case_id.clone(), path_params.action_id.clone(), path_params.id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Parameter not found!".to_string()))?;
+    if payload.value.is_some() && (parameter.parameter_type != ParameterType::Input || parameter.get_parameter_in() != ParameterIn::Query) {
+        return Err(AppError::Validation("Only input query parameters have a multi value mode".to_string()));
+    }
+    let revision = snapshot_before_change(&repository, &"eren".to_string(), &path_params.test_case_id, "parameter multi value mode update").await;
+    let result = repository
+        .parameters()
+        .update_multi_value_mode(
+            "eren".to_string(),
+            path_params.test_case_id,
+            path_params.action_id,
+            path_params.id,
+            payload.value,
+            expected_updated_at,
         )
         .await;
+    revision.record_if_ok(&result).await;
     ApiResponse::from(result)
-}
\ No newline at end of file
+}
+
+pub async fn list_suggested_expressions(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Vec<SuggestedExpression>>, AppError> {
+    let result = list_suggestions(&repository, &"eren".to_string(), &test_case_id).await;
+    ApiResponse::from(result)
+}
+
+pub async fn review_suggested_expressions(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<ReviewSuggestionsPayload>,
+) -> Result<ApiResponse<Vec<Parameter>>, AppError> {
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "suggested expression review").await;
+    let result = review_suggestions(&repository, &"eren".to_string(), &test_case_id, payload.reviews).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchParameterValueTypePayload {
+    pub value: Option<ValueType>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PatchParameterMultiValueModePayload {
+    pub value: Option<MultiValueMode>,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct ReviewSuggestionsPayload {
+    pub reviews: Vec<ExpressionReview>,
+}
+
+impl Validate for BatchGetParametersPayload {
+    fn validate(&self) -> Vec<FieldError> {
+        if self.action_ids.is_empty() {
+            return vec![FieldError::new("action_ids", "must not be empty")];
+        }
+        if self.action_ids.len() > MAX_BATCH_ACTIONS {
+            return vec![FieldError::new("action_ids", format!("must not have more than {} ids", MAX_BATCH_ACTIONS))];
+        }
+        vec![]
+    }
+}
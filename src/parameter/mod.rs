@@ -1,3 +1,4 @@
 pub mod model;
 pub mod service;
-pub mod api;
\ No newline at end of file
+pub mod api;
+pub mod suggestion;
\ No newline at end of file
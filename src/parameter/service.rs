@@ -1,17 +1,21 @@
 use crate::api::AppError;
+use crate::change::model::{EntityChange, EntityType};
 use crate::json_path::model::Expression;
-use crate::parameter::model::{Parameter, ParameterIn, ParameterLocation, ParameterType};
+use crate::parameter::model::{MultiValueMode, Parameter, ParameterIn, ParameterLocation, ParameterType, ValueType};
 use crate::persistence::model::{PageKey, QueryResult};
 use crate::persistence::repo::{build_composite_key, Table};
 use aws_sdk_dynamodb::types::AttributeValue;
 use aws_sdk_dynamodb::Client;
 use serde_dynamo::to_attribute_value;
+use futures::future::try_join_all;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::info;
 
 pub(crate) struct ParametersTable();
 
+const PARALLEL_ACTION_QUERIES: usize = 10;
+
 pub(crate) struct ParameterOperations {
     pub(crate) client: Arc<Client>,
 }
@@ -43,6 +47,17 @@ impl Table<Parameter> for ParametersTable {
         ]))
     }
 
+    fn versioned() -> bool {
+        true
+    }
+
+    fn change_of(entity: &Parameter) -> Option<EntityChange> {
+        Some(EntityChange {
+            action_id: Some(entity.action_id.clone()),
+            ..EntityChange::updated(&entity.customer_id, &entity.test_case_id, EntityType::Parameter, &entity.id, entity.updated_at)
+        })
+    }
+
     fn add_index_key_attributes(entity: &Parameter, item: &mut HashMap<String, AttributeValue>) {
         let parameter_type = parameter_type_to_str(&entity.parameter_type);
         let (location, path) = extract_location_tuple(&entity);
@@ -91,7 +106,7 @@ impl ParameterOperations {
             parameter_type_to_str(&parameter_type),
             path
         );
-        println!("path query sort key: {}", sort_key_value);
+        info!(sort_key = %sort_key_value, "querying parameters by path");
         let result = ParametersTable::query_builder(self.client.clone())
             .index_name("path_index")
             .expression_attribute_names("#pk", partition_key.0)
@@ -108,17 +123,53 @@ impl ParameterOperations {
         ParametersTable::from_query_result(result)
     }
 
-    pub async fn list_all_inputs_of_action(
+    pub async fn delete_all(&self, customer_id: String, test_case_id: String) -> Result<Vec<Parameter>, AppError> {
+        ParametersTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id, test_case_id]))
+            .await
+    }
+
+    pub async fn batch_delete(&self, customer_id: String, test_case_id: String, keys: Vec<(String, String)>) -> Result<(), AppError> {
+        let partition_key = build_composite_key(vec![customer_id, test_case_id]);
+        let key_pairs = keys.into_iter()
+            .map(|(action_id, id)| (partition_key.clone(), build_composite_key(vec![action_id, id])))
+            .collect();
+        ParametersTable::batch_delete_items(self.client.clone(), key_pairs).await
+    }
+
+    pub async fn count(&self, customer_id: &String, test_case_id: &String) -> Result<u64, AppError> {
+        ParametersTable::count_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()])).await
+    }
+
+    pub async fn list_all(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+    ) -> Result<Vec<Parameter>, AppError> {
+        ParametersTable::list_all_items(self.client.clone(), build_composite_key(vec![customer_id, test_case_id]))
+            .await
+    }
+
+    pub async fn list_all_outputs_of_action(
         &self,
         customer_id: String,
         test_case_id: String,
         action_id: String,
+    ) -> Result<Vec<Parameter>, AppError> {
+        self.list_all_of_action(customer_id, test_case_id, action_id, ParameterType::Output).await
+    }
+
+    async fn list_all_of_action(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        action_id: String,
+        parameter_type: ParameterType,
     ) -> Result<Vec<Parameter>, AppError> {
         let mut parameters: Vec<Parameter> = vec![];
         let mut next_page_key: Option<String> = None;
         let mut app_error: Option<AppError> = None;
         loop {
-            let list_result = self.list_by_action(customer_id.clone(), test_case_id.clone(), action_id.clone(), ParameterType::Input, None, next_page_key.clone())
+            let list_result = self.list_by_action(customer_id.clone(), test_case_id.clone(), action_id.clone(), parameter_type.clone(), None, next_page_key.clone())
                 .await;
             match list_result {
                 Ok(query_result) => {
@@ -140,6 +191,56 @@ impl ParameterOperations {
         }
     }
 
+    /// Parameters of several actions grouped by action id, every requested action gets an entry.
+    /// A few actions are read with one sort key prefix query each, in parallel; beyond that a
+    /// single read of the whole test case partition is cheaper.
+    pub async fn batch_list_by_actions(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        action_ids: &[String],
+        parameter_type: Option<ParameterType>,
+    ) -> Result<HashMap<String, Vec<Parameter>>, AppError> {
+        let parameters = if action_ids.len() > PARALLEL_ACTION_QUERIES {
+            self.list_all(customer_id, test_case_id).await?
+        } else {
+            try_join_all(action_ids.iter()
+                .map(|action_id| self.list_all_by_action(customer_id.clone(), test_case_id.clone(), action_id.clone())))
+                .await?
+                .concat()
+        };
+        Ok(group_by_action(parameters, action_ids, parameter_type.as_ref()))
+    }
+
+    async fn list_all_by_action(
+        &self,
+        customer_id: String,
+        test_case_id: String,
+        action_id: String,
+    ) -> Result<Vec<Parameter>, AppError> {
+        let partition_key =
+            ParametersTable::partition_key(build_composite_key(vec![customer_id, test_case_id]));
+        let mut parameters = vec![];
+        let mut next_page_key: Option<String> = None;
+        loop {
+            let result = ParametersTable::query_builder(self.client.clone())
+                .expression_attribute_names("#pk", partition_key.0.clone())
+                .expression_attribute_names("#sk", ParametersTable::sort_key_name())
+                .expression_attribute_values(":pk", partition_key.1.clone())
+                .expression_attribute_values(":sk", AttributeValue::S(format!("{}#", action_id)))
+                .key_condition_expression("#pk = :pk AND begins_with(#sk, :sk)")
+                .set_exclusive_start_key(ParametersTable::build_exclusion_key(next_page_key))
+                .send()
+                .await;
+            let page = ParametersTable::from_query_result(result)?;
+            parameters.extend(page.items);
+            next_page_key = page.next_page_key;
+            if next_page_key.is_none() {
+                return Ok(parameters);
+            }
+        }
+    }
+
     pub async fn list_by_action(
         &self,
         customer_id: String,
@@ -176,14 +277,42 @@ impl ParameterOperations {
         ParametersTable::from_query_result(result)
     }
 
+    pub async fn get(&self, customer_id: String, test_case_id: String, action_id: String, id: String) -> Result<Option<Parameter>, AppError> {
+        ParametersTable::get_item(self.client.clone(),
+                                  build_composite_key(vec![customer_id, test_case_id]),
+                                  build_composite_key(vec![action_id, id])).await
+    }
+
+    pub async fn update_value_type(&self, customer_id: String, test_case_id: String, action_id: String, id: String,
+                                   value_type: Option<ValueType>, expected_updated_at: Option<u64>) -> Result<Parameter, AppError> {
+        ParametersTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]),
+                                        build_composite_key(vec![action_id, id]), expected_updated_at,
+                                        self.client.clone()
+                                            .update_item()
+                                            .update_expression("SET #value_type = :value_type")
+                                            .expression_attribute_names("#value_type", "value_type")
+                                            .expression_attribute_values(":value_type", to_attribute_value(value_type).unwrap())).await
+    }
+
+    pub async fn update_multi_value_mode(&self, customer_id: String, test_case_id: String, action_id: String, id: String,
+                                         multi_value_mode: Option<MultiValueMode>, expected_updated_at: Option<u64>) -> Result<Parameter, AppError> {
+        ParametersTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]),
+                                        build_composite_key(vec![action_id, id]), expected_updated_at,
+                                        self.client.clone()
+                                            .update_item()
+                                            .update_expression("SET #multi_value_mode = :multi_value_mode")
+                                            .expression_attribute_names("#multi_value_mode", "multi_value_mode")
+                                            .expression_attribute_values(":multi_value_mode", to_attribute_value(multi_value_mode).unwrap())).await
+    }
+
     pub async fn update_expression(&self, customer_id: String, test_case_id: String, action_id: String, id: String,
-                                   expression: Option<Expression>) -> Result<Parameter, AppError> {
+                                   expression: Option<Expression>, expected_updated_at: Option<u64>) -> Result<Parameter, AppError> {
         info!("{:?}", expression);
         info!("cid: {}, tid: {}, aid: {}, id: {}", customer_id, test_case_id, action_id, id);
         let attribute_value = expression.map_or(AttributeValue::Null(true), |new_expr| to_attribute_value(new_expr).unwrap());
         info!("attribute_value: {:?}", attribute_value);
-        ParametersTable::update_partial(build_composite_key(vec![customer_id, test_case_id]),
-                                        build_composite_key(vec![action_id, id]),
+        ParametersTable::update_partial_expecting(build_composite_key(vec![customer_id, test_case_id]),
+                                        build_composite_key(vec![action_id, id]), expected_updated_at,
                                         self.client.clone()
                                             .update_item()
                                             .update_expression("SET #expr = :expr")
@@ -206,6 +335,7 @@ fn parameter_in_to_str(parameter_in: &ParameterIn) -> String {
         ParameterIn::Cookie => "cookie".to_string(),
         ParameterIn::Body => "body".to_string(),
         ParameterIn::Query => "query".to_string(),
+        ParameterIn::Path => "path".to_string(),
     };
     parameter_type
 }
@@ -216,6 +346,59 @@ fn extract_location_tuple(entity: &Parameter) -> (String, String) {
         ParameterLocation::Cookie(name) => ("cookie".to_string(), name),
         ParameterLocation::Query(name) => ("query".to_string(), name),
         ParameterLocation::Body(name) => ("body".to_string(), name),
+        ParameterLocation::Path(name) => ("path".to_string(), name),
     };
     (location.clone(), path.clone())
-}
\ No newline at end of file
+}
+
+fn group_by_action(parameters: Vec<Parameter>, action_ids: &[String], parameter_type: Option<&ParameterType>) -> HashMap<String, Vec<Parameter>> {
+    let mut grouped: HashMap<String, Vec<Parameter>> = action_ids.iter()
+        .map(|action_id| (action_id.clone(), vec![]))
+        .collect();
+    parameters.into_iter()
+        .filter(|parameter| parameter_type.is_none_or(|parameter_type| &parameter.parameter_type == parameter_type))
+        .for_each(|parameter| {
+            if let Some(group) = grouped.get_mut(&parameter.action_id) {
+                group.push(parameter);
+            }
+        });
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn parameter(action_id: &str, parameter_type: ParameterType, name: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id("eren".to_string())
+            .test_case_id("tc".to_string())
+            .action_id(action_id.to_string())
+            .parameter_type(parameter_type)
+            .location(ParameterLocation::Header(name.to_string()))
+            .value(Value::Null)
+            .build()
+    }
+
+    #[test]
+    fn group_by_action_keeps_requested_actions_and_type() {
+        let parameters = vec![
+            parameter("a1", ParameterType::Input, "x"),
+            parameter("a1", ParameterType::Output, "y"),
+            parameter("a2", ParameterType::Input, "z"),
+            parameter("other", ParameterType::Input, "w"),
+        ];
+        let action_ids = vec!["a1".to_string(), "a2".to_string(), "a3".to_string()];
+
+        let grouped = group_by_action(parameters.clone(), &action_ids, None);
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped["a1"].len(), 2);
+        assert_eq!(grouped["a2"].len(), 1);
+        assert!(grouped["a3"].is_empty());
+
+        let inputs = group_by_action(parameters, &action_ids, Some(&ParameterType::Input));
+        assert_eq!(inputs["a1"].iter().map(|p| p.get_path()).collect::<Vec<_>>(), vec!["x"]);
+        assert_eq!(inputs["a2"].len(), 1);
+    }
+}
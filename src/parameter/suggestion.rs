@@ -0,0 +1,167 @@
+use crate::api::AppError;
+use crate::har_resolver::key_similarity;
+use crate::parameter::model::{Parameter, ParameterType};
+use crate::change::model::ChangeKind;
+use crate::parameter::service::ParametersTable;
+use crate::persistence::repo::{current_timestamp, Repository, Table};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+const LENGTH_WEIGHT: f64 = 0.4;
+const UNIQUENESS_WEIGHT: f64 = 0.3;
+const KEY_SIMILARITY_WEIGHT: f64 = 0.3;
+const CONFIDENT_VALUE_LENGTH: f64 = 16.0;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SuggestedExpression {
+    pub action_id: String,
+    pub parameter_id: String,
+    pub path: String,
+    pub value: Value,
+    pub expression: String,
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct ExpressionReview {
+    pub action_id: String,
+    pub parameter_id: String,
+    pub accept: bool,
+}
+
+/// Lists the expressions the importer resolved by value equality that nobody has accepted yet,
+/// least confident first.
+pub async fn list_suggestions(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+) -> Result<Vec<SuggestedExpression>, AppError> {
+    let parameters = repository.parameters()
+        .list_all(customer_id.clone(), test_case_id.clone())
+        .await?;
+    Ok(suggestions_of(&parameters))
+}
+
+/// Accepts or drops the suggested expressions together, none of them is changed when one fails.
+/// The last review of a parameter wins when it is reviewed more than once.
+pub async fn review_suggestions(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    reviews: Vec<ExpressionReview>,
+) -> Result<Vec<Parameter>, AppError> {
+    let parameters = repository.parameters()
+        .list_all(customer_id.clone(), test_case_id.clone())
+        .await?;
+    let version = Some(current_timestamp());
+    let mut reviewed: Vec<(Parameter, &Parameter)> = vec![];
+    for review in reviews {
+        let previous = parameters.iter()
+            .find(|parameter| parameter.action_id == review.action_id && parameter.id == review.parameter_id)
+            .ok_or(AppError::NotFound(format!("Parameter {} not found!", review.parameter_id)))?;
+        let parameter = if review.accept {
+            Parameter { expression_accepted: true, updated_at: version, ..previous.clone() }
+        } else {
+            Parameter { value_expression: None, updated_at: version, ..previous.clone() }
+        };
+        reviewed.retain(|(_, other)| other.id != previous.id);
+        reviewed.push((parameter, previous));
+    }
+    let items = reviewed.iter()
+        .map(|(parameter, previous)| ParametersTable::transact_put(parameter, Some(previous)))
+        .collect();
+    repository.transact_write(items).await?;
+    reviewed.iter().for_each(|(parameter, _)| ParametersTable::publish_change(parameter, ChangeKind::Updated));
+    Ok(reviewed.into_iter().map(|(parameter, _)| parameter).collect())
+}
+
+pub fn suggestions_of(parameters: &[Parameter]) -> Vec<SuggestedExpression> {
+    let outputs: Vec<&Parameter> = parameters.iter()
+        .filter(|parameter| parameter.parameter_type == ParameterType::Output)
+        .collect();
+    let mut suggestions: Vec<SuggestedExpression> = parameters.iter()
+        .filter(|parameter| parameter.parameter_type == ParameterType::Input && !parameter.expression_accepted)
+        .filter_map(|parameter| parameter.value_expression.as_ref().map(|expression| (parameter, expression)))
+        .map(|(parameter, expression)| {
+            let same_valued_outputs = outputs.iter()
+                .filter(|output| output.value == parameter.value)
+                .count()
+                .max(1);
+            SuggestedExpression {
+                action_id: parameter.action_id.clone(),
+                parameter_id: parameter.id.clone(),
+                path: parameter.get_path(),
+                value: parameter.value.clone(),
+                expression: expression.value.clone(),
+                confidence: confidence(&parameter.value, same_valued_outputs, &parameter.get_path(), &expression.value),
+            }
+        })
+        .collect();
+    suggestions.sort_by(|s1, s2| s1.confidence.total_cmp(&s2.confidence));
+    suggestions
+}
+
+fn confidence(value: &Value, same_valued_outputs: usize, path: &str, expression: &str) -> f64 {
+    let score = LENGTH_WEIGHT * length_score(value)
+        + UNIQUENESS_WEIGHT / same_valued_outputs as f64
+        + KEY_SIMILARITY_WEIGHT * key_similarity(path, expression);
+    (score * 100.0).round() / 100.0
+}
+
+/// Short values like `true` or `1` collide by accident far more often than long identifiers.
+fn length_score(value: &Value) -> f64 {
+    match value {
+        Value::String(text) => (text.len() as f64 / CONFIDENT_VALUE_LENGTH).min(1.0),
+        Value::Number(number) => (number.to_string().len() as f64 / CONFIDENT_VALUE_LENGTH).min(1.0),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::ParameterLocation;
+    use serde_json::json;
+
+    fn parameter(parameter_type: ParameterType, location: ParameterLocation, value: Value, expression: Option<&str>) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id("a1".to_string())
+            .parameter_type(parameter_type)
+            .location(location)
+            .value(value)
+            .maybe_value_expression(expression.map(|value| Expression { value: value.to_string() }))
+            .build()
+    }
+
+    #[test]
+    fn score_suggestions() {
+        let parameters = vec![
+            parameter(ParameterType::Output, ParameterLocation::Body("$.id".to_string()), json!("7f3c2a9e-51d4-4c0b"), None),
+            parameter(ParameterType::Output, ParameterLocation::Body("$.active".to_string()), json!(true), None),
+            parameter(ParameterType::Output, ParameterLocation::Body("$.verified".to_string()), json!(true), None),
+            parameter(ParameterType::Input, ParameterLocation::Query("userId".to_string()), json!("7f3c2a9e-51d4-4c0b"), Some("$.create_user_0.output.id")),
+            parameter(ParameterType::Input, ParameterLocation::Body("$.enabled".to_string()), json!(true), Some("$.create_user_0.output.active")),
+        ];
+
+        let suggestions = suggestions_of(&parameters);
+
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].path, "$.enabled");
+        assert_eq!(suggestions[0].confidence, 0.15);
+        assert_eq!(suggestions[1].path, "userId");
+        assert_eq!(suggestions[1].confidence, 0.97);
+    }
+
+    #[test]
+    fn accepted_expressions_are_not_suggested() {
+        let accepted = Parameter {
+            expression_accepted: true,
+            ..parameter(ParameterType::Input, ParameterLocation::Header("x-id".to_string()), json!("abc"), Some("$.login_0.output.id"))
+        };
+        assert!(suggestions_of(&[accepted]).is_empty());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod repo;
-pub mod model;
\ No newline at end of file
+pub mod model;
+pub mod retry;
@@ -1,21 +1,26 @@
 use aws_sdk_dynamodb::types::AttributeValue;
 use bon::Builder;
-use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::Deserialize;
+use serde_json::{Number, Value};
 use std::collections::HashMap;
+use utoipa::ToSchema;
 
+/// Last evaluated key of a query. Numeric keys of sorted indexes are kept as JSON numbers so
+/// they go back to DynamoDB as numbers; keys issued before that are all strings and still parse.
 pub struct PageKey {
-    keys: HashMap<String, String>,
+    keys: HashMap<String, Value>,
 }
 
 impl PageKey {
     pub fn from_attribute_values(values: HashMap<String, AttributeValue>) -> Self {
-        let mut keys: HashMap<String, String> = HashMap::new();
+        let mut keys: HashMap<String, Value> = HashMap::new();
         values.iter().for_each(|(k, v)| {
-            keys.insert(
-                k.to_string(),
-                v.as_s().map_or(String::new(), |v| v.to_string()),
-            );
+            let value = match v {
+                AttributeValue::N(number) => serde_json::from_str::<Number>(number)
+                    .map_or(Value::String(number.to_string()), Value::Number),
+                _ => Value::String(v.as_s().map_or(String::new(), |v| v.to_string())),
+            };
+            keys.insert(k.to_string(), value);
         });
         Self { keys }
     }
@@ -23,7 +28,12 @@ impl PageKey {
     pub fn to_attribute_values(&self) -> HashMap<String, AttributeValue> {
         let mut keys: HashMap<String, AttributeValue> = HashMap::new();
         self.keys.iter().for_each(|(k, v)| {
-            keys.insert(k.to_string(), AttributeValue::S(v.to_string()));
+            let value = match v {
+                Value::Number(number) => AttributeValue::N(number.to_string()),
+                Value::String(text) => AttributeValue::S(text.to_string()),
+                other => AttributeValue::S(other.to_string()),
+            };
+            keys.insert(k.to_string(), value);
         });
         keys
     }
@@ -39,14 +49,7 @@ impl PageKey {
     }
 }
 
-#[derive(Clone, Serialize, Debug)]
-pub struct QueryResult<T>
-where
-    T: DeserializeOwned + Serialize + Clone,
-{
-    pub items: Vec<T>,
-    pub next_page_key: Option<String>,
-}
+pub use parroton_core::page::QueryResult;
 
 #[derive(Builder)]
 pub struct ListItemsRequest {
@@ -55,5 +58,46 @@ pub struct ListItemsRequest {
     pub expression_attribute_names: Option<HashMap<String, String>>,
     pub expression_attribute_values: Option<HashMap<String, AttributeValue>>,
     pub filter_expression: Option<String>,
+    /// Attributes to read, every attribute when not set.
+    pub projected_attributes: Option<Vec<String>>,
     pub limit: Option<i32>,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Eq, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn scan_index_forward(&self) -> bool {
+        *self == SortOrder::Asc
+    }
+}
+
+/// How much of each item a list endpoint returns. Summaries leave large stored bodies out of the read.
+#[derive(Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum View {
+    #[default]
+    Full,
+    Summary,
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_key_keeps_numeric_index_keys() {
+        let last_key = HashMap::from([
+            ("customer_id#test_case_id".to_string(), AttributeValue::S("cust1#tc1".to_string())),
+            ("started_at".to_string(), AttributeValue::N("1718000000000".to_string())),
+        ]);
+        let next_page_key = PageKey::from_attribute_values(last_key.clone()).to_next_page_key();
+        assert_eq!(PageKey::from_next_page_key(&next_page_key).to_attribute_values(), last_key);
+
+        let legacy = PageKey::from_next_page_key(&r#"{"id":"a1"}"#.to_string()).to_attribute_values();
+        assert_eq!(legacy, HashMap::from([("id".to_string(), AttributeValue::S("a1".to_string()))]));
+    }
+}
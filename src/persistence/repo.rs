@@ -1,26 +1,47 @@
-use crate::action::model::Action;
+use crate::access::service::{ApiTokenOperations, MembershipOperations};
 use crate::action::service::ActionOperations;
 use crate::action_execution::service::ActionExecutionsOperations;
 use crate::api::AppError;
 use crate::assertion::service::AssertionOperations;
 use crate::auth::service::AuthProviderOperations;
-use crate::case::model::TestCase;
 use crate::case::service::TestCaseOperations;
+use crate::change::feed::change_feed;
+use crate::change::model::{ChangeKind, EntityChange};
+use crate::deletion::service::DeletionJobOperations;
+use crate::constant::service::ConstantOperations;
+use crate::environment::service::EnvironmentOperations;
+use crate::group::service::ActionGroupOperations;
+use crate::header_rule::service::HeaderRuleOperations;
+use crate::import_report::service::ImportReportOperations;
+use crate::metrics::registry::metrics;
 use crate::parameter::service::ParameterOperations;
 use crate::persistence::model::{ListItemsRequest, PageKey, QueryResult};
-use crate::run::model::Run;
+use crate::persistence::retry::{send_conditional_with_retry, send_with_retry, wait_for_unprocessed};
+use crate::quota::service::QuotaOperations;
+use crate::run::lock::RunLockOperations;
+use crate::sensitive_header::service::SensitiveHeaderPolicyOperations;
+use crate::notification::email::init_mailer;
+use crate::notification::service::{DigestSubscriptionOperations, NotificationSettingsOperations};
+use crate::snapshot::service::SnapshotOperations;
 use crate::run::service::RunOperations;
+use crate::revision::service::RevisionOperations;
+use crate::template::service::TemplateOperations;
+use crate::config::{app_config, AppConfig};
+use crate::artifact::store::init_artifact_store;
 use aws_config::meta::region::RegionProviderChain;
+use aws_config::Region;
 use aws_config::{BehaviorVersion, SdkConfig};
 use aws_sdk_dynamodb::config::http::HttpResponse;
+use aws_sdk_dynamodb::config::retry::RetryConfig;
 use aws_sdk_dynamodb::config::{Credentials, ProvideCredentials, SharedCredentialsProvider};
 use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
 use aws_sdk_dynamodb::operation::query::builders::QueryFluentBuilder;
 use aws_sdk_dynamodb::operation::query::{QueryError, QueryOutput};
+use aws_sdk_dynamodb::operation::transact_write_items::TransactWriteItemsError;
 use aws_sdk_dynamodb::operation::update_item::builders::UpdateItemFluentBuilder;
 use aws_sdk_dynamodb::operation::update_item::{UpdateItemError, UpdateItemOutput};
 use aws_sdk_dynamodb::types::builders::UpdateBuilder;
-use aws_sdk_dynamodb::types::{AttributeValue, ComparisonOperator, Condition, DeleteRequest, KeysAndAttributes, PutRequest, ReturnValue, WriteRequest};
+use aws_sdk_dynamodb::types::{AttributeValue, ComparisonOperator, Condition, Delete, DeleteRequest, KeysAndAttributes, Put, PutRequest, ReturnValue, Select, TransactWriteItem, WriteRequest};
 use aws_sdk_dynamodb::Client;
 use futures::future::err;
 use serde::de::DeserializeOwned;
@@ -28,15 +49,17 @@ use serde::{Deserialize, Serialize};
 use serde_dynamo::aws_sdk_dynamodb_1::to_item;
 use serde_dynamo::{from_attribute_value, from_item, to_attribute_value};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Debug;
 use std::sync::{Arc, Once};
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::mpsc::Sender;
 use tracing::{info, Instrument};
 
 pub static INIT: Once = Once::new();
 
+/// DynamoDB rejects transactions with more items than this.
+const MAX_TRANSACTION_ITEMS: usize = 100;
+
 pub fn init_logger() {
     INIT.call_once(|| {
         tracing_subscriber::fmt::init();
@@ -48,6 +71,11 @@ where
     T: DeserializeOwned + Serialize + Clone,
 {
     fn table_name() -> String;
+
+    fn qualified_table_name() -> String {
+        format!("{}{}", app_config().table_prefix, Self::table_name())
+    }
+
     fn partition_key_name() -> String;
     fn sort_key_name() -> String;
 
@@ -103,19 +131,18 @@ where
         partition_key: String,
         sort_key: String,
     ) -> Result<Option<T>, AppError> {
-        let result = client
+        let request = client
             .get_item()
-            .table_name(Self::table_name())
+            .table_name(Self::qualified_table_name())
             .set_key(Some(Self::unique_key(partition_key, sort_key)))
-            .consistent_read(true)
-            .send()
-            .await;
+            .consistent_read(true);
+        let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send()).await;
         match result {
             Ok(output) => match output.item {
                 Some(item_map) => Ok(Some(from_item(item_map).unwrap())),
                 None => Ok(None),
             },
-            Err(e) => Err(from_sdk_error(e)),
+            Err(e) => Err(from_sdk_error(&Self::qualified_table_name(), e)),
         }
     }
 
@@ -123,13 +150,30 @@ where
         partition_key: String,
         sort_key: String,
         update_builder: UpdateItemFluentBuilder,
+    ) -> Result<T, AppError> {
+        Self::update_partial_expecting(partition_key, sort_key, None, update_builder).await
+    }
+
+    /// `update_partial` that only applies while the item's `updated_at` is `expected_updated_at`,
+    /// failing with `Conflict` once someone else has updated it in between.
+    async fn update_partial_expecting(
+        partition_key: String,
+        sort_key: String,
+        expected_updated_at: Option<u64>,
+        update_builder: UpdateItemFluentBuilder,
     ) -> Result<T, AppError> {
         let mut update_expression = update_builder.get_update_expression().clone()
             .unwrap();
         update_expression.push_str(format!("{} #updated_at = :updated_at", if update_expression.contains("SET") { "," } else { " SET" }).as_str());
-        info!("will update partially {}|{} with expr: {:?}, attribute names: {:?}, attributes values: {:?}", partition_key, sort_key, update_expression, update_builder.get_expression_attribute_names(), update_builder.get_expression_attribute_values());
-        let result = update_builder
-            .table_name(Self::table_name())
+        info!(table = %Self::qualified_table_name(), partition_key = %partition_key, sort_key = %sort_key, update_expression = ?update_expression, expected_updated_at = ?expected_updated_at, "will update partially");
+        let update_builder = match expected_updated_at {
+            None => update_builder.condition_expression("attribute_exists(#pk) AND attribute_exists(#sk)"),
+            Some(version) => update_builder
+                .condition_expression("attribute_exists(#pk) AND attribute_exists(#sk) AND #updated_at = :expected_updated_at")
+                .expression_attribute_values(":expected_updated_at", AttributeValue::N(version.to_string())),
+        };
+        let request = update_builder
+            .table_name(Self::qualified_table_name())
             .set_key(Some(Self::unique_key(
                 partition_key,
                 sort_key,
@@ -138,25 +182,43 @@ where
             .expression_attribute_names("#pk", Self::partition_key_name())
             .expression_attribute_names("#sk", Self::sort_key_name())
             .expression_attribute_names("#updated_at", "updated_at")
-            .condition_expression("attribute_exists(#pk) AND attribute_exists(#sk)")
             .expression_attribute_values(":updated_at", to_attribute_value(current_timestamp()).unwrap())
-            .update_expression(update_expression)
-            .send().await;
-        Self::from_update_result(result)
+            .update_expression(update_expression);
+        let result = match expected_updated_at {
+            None => send_with_retry(&Self::qualified_table_name(), || request.clone().send()).await,
+            Some(_) => send_conditional_with_retry(&Self::qualified_table_name(), || request.clone().send()).await,
+        };
+        if let (Some(version), Err(err)) = (expected_updated_at, &result) {
+            if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) {
+                return Err(AppError::Conflict(format!("Item was changed by someone else since version {}!", version)));
+            }
+        }
+        let entity = Self::from_update_result(result)?;
+        Self::publish_change(&entity, ChangeKind::Updated);
+        Ok(entity)
     }
 
     async fn put_item(client: Arc<Client>, entity: T) -> Result<T, AppError> {
         let mut item = to_item(entity.clone()).unwrap();
         Self::add_main_key_attributes(&entity, &mut item);
-        let result = client
+        // a versioned entity gets a new version on every write, read back so the caller sees it
+        let entity = if Self::versioned() {
+            item.insert("updated_at".to_string(), AttributeValue::N(current_timestamp().to_string()));
+            from_item(item.clone()).unwrap()
+        } else {
+            entity
+        };
+        let request = client
             .put_item()
-            .table_name(Self::table_name())
-            .set_item(Some(item))
-            .send()
-            .await;
+            .table_name(Self::qualified_table_name())
+            .set_item(Some(item));
+        let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send()).await;
         match result {
-            Ok(_) => Ok(entity.clone()),
-            Err(err) => Err(from_sdk_error(err)),
+            Ok(_) => {
+                Self::publish_change(&entity, ChangeKind::Updated);
+                Ok(entity)
+            }
+            Err(err) => Err(from_sdk_error(&Self::qualified_table_name(), err)),
         }
     }
 
@@ -165,26 +227,25 @@ where
         partition_key: String,
         sort_key: String,
     ) -> Result<Option<T>, AppError> {
-        info!("{}:will delete: {}|{}", Self::table_name(),  partition_key, sort_key);
-        let result = client
+        info!(table = %Self::qualified_table_name(), partition_key = %partition_key, sort_key = %sort_key, "will delete");
+        let request = client
             .delete_item()
-            .table_name(Self::table_name())
+            .table_name(Self::qualified_table_name())
             .set_key(Some(Self::unique_key(partition_key, sort_key)))
-            .return_values(ReturnValue::AllOld)
-            .send()
-            .await;
+            .return_values(ReturnValue::AllOld);
+        let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send()).await;
         match result {
             Ok(output) => output.attributes.map_or(Ok(None), |item_map| {
-                Ok(Some(
-                    from_attribute_value(AttributeValue::M(item_map)).unwrap(),
-                ))
+                let entity = from_attribute_value(AttributeValue::M(item_map)).unwrap();
+                Self::publish_change(&entity, ChangeKind::Deleted);
+                Ok(Some(entity))
             }),
-            Err(err) => Err(from_sdk_error(err)),
+            Err(err) => Err(from_sdk_error(&Self::qualified_table_name(), err)),
         }
     }
 
     fn query_builder(client: Arc<Client>) -> QueryFluentBuilder {
-        client.query().table_name(Self::table_name())
+        client.query().table_name(Self::qualified_table_name())
             .limit(50)
     }
 
@@ -199,50 +260,60 @@ where
             .iter()
             .map(|key_pair| Self::unique_key(key_pair.0.clone(), key_pair.1.clone()))
             .collect();
-        let table_name = Self::table_name();
-        let result = client
-            .batch_get_item()
-            .request_items(
-                &table_name,
-                KeysAndAttributes::builder()
-                    .consistent_read(true)
-                    .set_keys(Some(keys))
-                    .build()
-                    .unwrap(),
-            )
-            .send()
-            .await;
-        match result {
-            Ok(batch_get_item_output) => {
-                batch_get_item_output
-                    .responses
-                    .map_or(Ok(vec![]), |items_by_table| {
-                        let mut items: Vec<T> = items_by_table
-                            .get(&table_name)
-                            .unwrap()
-                            .iter()
-                            .map(|item| from_item(item.clone()).unwrap())
-                            .collect();
-                        items.sort_by(Self::ordering);
-                        Ok(items)
-                    })
+        let table_name = Self::qualified_table_name();
+        let mut pending = Some(KeysAndAttributes::builder()
+            .consistent_read(true)
+            .set_keys(Some(keys))
+            .build()
+            .unwrap());
+        let mut items: Vec<T> = vec![];
+        let mut attempt = 1;
+        while let Some(keys_and_attributes) = pending.take() {
+            let request = client
+                .batch_get_item()
+                .request_items(&table_name, keys_and_attributes);
+            let output = send_with_retry(&table_name, || request.clone().send())
+                .await
+                .map_err(|err| from_sdk_error(&table_name, err))?;
+            items.extend(output.responses
+                .and_then(|mut items_by_table| items_by_table.remove(&table_name))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|item| from_item(item).unwrap()));
+            // throttled keys come back unprocessed rather than as an error
+            pending = output.unprocessed_keys
+                .and_then(|mut unprocessed| unprocessed.remove(&table_name))
+                .filter(|unprocessed| !unprocessed.keys().is_empty());
+            if let Some(unprocessed) = &pending {
+                wait_for_unprocessed(&table_name, &mut attempt, unprocessed.keys().len()).await?;
             }
-            Err(err) => Err(from_sdk_error(err)),
         }
+        items.sort_by(Self::ordering);
+        Ok(items)
     }
 
     fn from_query_result(
         result: Result<QueryOutput, SdkError<QueryError, HttpResponse>>,
+    ) -> Result<QueryResult<T>, AppError> {
+        Self::from_ordered_query_result(result).map(|mut query_result| {
+            query_result.items.sort_by(Self::ordering);
+            query_result
+        })
+    }
+
+    /// Keeps the order DynamoDB returned, for queries on a sort key index where re-sorting
+    /// a page in memory would disagree with the order of the following pages.
+    fn from_ordered_query_result(
+        result: Result<QueryOutput, SdkError<QueryError, HttpResponse>>,
     ) -> Result<QueryResult<T>, AppError> {
         match result {
             Ok(output) => {
-                let mut items = output.items.map_or(vec![], |items| {
+                let items = output.items.map_or(vec![], |items| {
                     items
                         .iter()
                         .map(|item| from_attribute_value(AttributeValue::M(item.clone())).unwrap())
                         .collect()
                 });
-                items.sort_by(Self::ordering);
                 Ok(QueryResult {
                     items,
                     next_page_key: output.last_evaluated_key.map(|last_key| {
@@ -251,7 +322,7 @@ where
                 })
             }
             Err(err) => {
-                Err(from_sdk_error(err))
+                Err(from_sdk_error(&Self::qualified_table_name(), err))
             }
         }
     }
@@ -269,7 +340,7 @@ where
                     .unwrap())
             }
             Err(err) => {
-                Err(from_sdk_error(err))
+                Err(from_sdk_error(&Self::qualified_table_name(), err))
             }
         }
     }
@@ -286,23 +357,37 @@ where
         request.expression_attribute_values.inspect(|values| {
             expr_attribute_values.extend(values.clone());
         });
-        let result = Self::query_builder(client)
+        let projection_expression = request.projected_attributes.map(|attributes| {
+            let (expression, names) = projection_expression(&attributes);
+            expr_attribute_names.extend(names);
+            expression
+        });
+        let request = Self::query_builder(client)
             .set_expression_attribute_names(Some(expr_attribute_names))
             .set_expression_attribute_values(Some(expr_attribute_values))
             .key_condition_expression("#pk = :pk")
             .set_filter_expression(request.filter_expression)
+            .set_projection_expression(projection_expression)
             .limit(request.limit.map_or(25, |limit| { limit }))
             .set_exclusive_start_key(
                 request.next_page_key.map(|next| PageKey::from_next_page_key(&next).to_attribute_values()),
-            )
-            .send()
-            .await;
+            );
+        let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send()).await;
         Self::from_query_result(result)
     }
 
     async fn list_all_items(
         client: Arc<Client>,
         partition_key: String,
+    ) -> Result<Vec<T>, AppError> {
+        Self::list_all_items_projected(client, partition_key, None).await
+    }
+
+    /// Reads only the given attributes, so `T` must tolerate the left out ones being absent.
+    async fn list_all_items_projected(
+        client: Arc<Client>,
+        partition_key: String,
+        projected_attributes: Option<Vec<String>>,
     ) -> Result<Vec<T>, AppError> {
         let mut app_error = None;
         let mut next_page_key = None;
@@ -312,6 +397,7 @@ where
                 Self::list_items(client.clone(), ListItemsRequest::builder()
                     .partition_key(partition_key.clone())
                     .maybe_next_page_key(next_page_key.clone())
+                    .maybe_projected_attributes(projected_attributes.clone())
                     .build())
                     .await;
             match result {
@@ -337,43 +423,71 @@ where
         }
     }
 
-    async fn delete_all_items(
-        client: Arc<Client>,
-        partition_key: String,
-        sender: &Sender<OnDeleteMessage>,
-    ) {
-        info!("{}: will delete all items for partition {}", Self::table_name(), partition_key);
-        let mut next_page_key: Option<String> = None;
+    /// Counts the items of a partition without reading them.
+    async fn count_items(client: Arc<Client>, partition_key: String) -> Result<u64, AppError> {
+        let mut count = 0;
+        let mut exclusive_start_key = None;
         loop {
-            let result =
-                Self::list_items(client.clone(), ListItemsRequest::builder()
-                    .partition_key(partition_key.clone())
-                    .maybe_next_page_key(next_page_key.clone()).build())
-                    .await;
-            if let Ok(query_result) = result {
-                let mut keys: Vec<(String, String)> = vec![];
-                for item in query_result.items {
-                    let partition_key = Self::partition_key_from_entity(&item).1;
-                    let sort_key = Self::sort_key_from_entity(&item).1;
-                    keys.push((partition_key.as_s().unwrap().clone(), sort_key.as_s().unwrap().clone()));
-                    if let Some(event) = Self::build_deleted_event(item) {
-                        let cloned_sender = sender.clone();
-                        tokio::task::spawn(async move {
-                            cloned_sender.send(event).await.unwrap();
-                        });
-                    }
-                }
+            let request = client.query()
+                .table_name(Self::qualified_table_name())
+                .key_condition_expression("#pk = :pk")
+                .expression_attribute_names("#pk", Self::partition_key_name())
+                .expression_attribute_values(":pk", AttributeValue::S(partition_key.clone()))
+                .select(Select::Count)
+                .set_exclusive_start_key(exclusive_start_key);
+            let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send())
+                .await
+                .map_err(|err| from_sdk_error(&Self::qualified_table_name(), err))?;
+            count += result.count as u64;
+            exclusive_start_key = result.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                return Ok(count);
+            }
+        }
+    }
 
-                Self::batch_delete_items(client.clone(), keys).await;
-                if let Some(page_key) = query_result.next_page_key {
-                    next_page_key = Some(page_key.clone());
-                } else {
-                    break;
-                }
+    /// Distinct partition keys of the whole table. Scans every item, so it is only meant for
+    /// operator endpoints and background jobs.
+    async fn scan_partition_keys(client: Arc<Client>) -> Result<Vec<String>, AppError> {
+        let mut partition_keys = BTreeSet::new();
+        let mut exclusive_start_key = None;
+        loop {
+            let request = client.scan()
+                .table_name(Self::qualified_table_name())
+                .projection_expression("#pk")
+                .expression_attribute_names("#pk", Self::partition_key_name())
+                .set_exclusive_start_key(exclusive_start_key);
+            let result = send_with_retry(&Self::qualified_table_name(), || request.clone().send())
+                .await
+                .map_err(|err| from_sdk_error(&Self::qualified_table_name(), err))?;
+            partition_keys.extend(result.items.unwrap_or_default()
+                .into_iter()
+                .filter_map(|mut item| item.remove(&Self::partition_key_name()))
+                .filter_map(|value| value.as_s().ok().cloned()));
+            exclusive_start_key = result.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                return Ok(partition_keys.into_iter().collect());
             }
         }
     }
 
+    async fn delete_all_items(
+        client: Arc<Client>,
+        partition_key: String,
+    ) -> Result<Vec<T>, AppError> {
+        info!(table = %Self::qualified_table_name(), partition_key = %partition_key, "will delete all items of partition");
+        let items = Self::list_all_items(client.clone(), partition_key).await?;
+        let keys: Vec<(String, String)> = items.iter()
+            .map(|item| {
+                let partition_key = Self::partition_key_from_entity(item).1;
+                let sort_key = Self::sort_key_from_entity(item).1;
+                (partition_key.as_s().unwrap().clone(), sort_key.as_s().unwrap().clone())
+            })
+            .collect();
+        Self::batch_delete_items(client, keys).await?;
+        Ok(items)
+    }
+
     async fn batch_put_item(client: Arc<Client>, entities: Vec<T>) {
         let write_requests: Vec<WriteRequest> = entities
             .iter()
@@ -385,28 +499,96 @@ where
                     .build()
             })
             .collect();
-        batch_write(client, write_requests, &Self::table_name()).await;
+        let _ = batch_write(client, write_requests, &Self::qualified_table_name()).await;
     }
 
-    async fn batch_delete_items(client: Arc<Client>, keys: Vec<(String, String)>) {
-        info!("{}:will batch delete {} items!", Self::table_name(), keys.len());
-        let cloned_client = client.clone();
-        tokio::task::spawn(async move {
-            let write_requests: Vec<WriteRequest> = keys
-                .iter()
-                .map(|key| {
-                    WriteRequest::builder()
-                        .delete_request(
-                            DeleteRequest::builder()
-                                .set_key(Some(Self::unique_key(key.0.clone(), key.1.clone())))
-                                .build()
-                                .unwrap(),
-                        )
-                        .build()
-                })
-                .collect();
-            batch_write(cloned_client, write_requests, &Self::table_name()).await;
-        });
+    async fn batch_delete_items(client: Arc<Client>, keys: Vec<(String, String)>) -> Result<(), AppError> {
+        info!(table = %Self::qualified_table_name(), count = keys.len(), "will batch delete");
+        let write_requests: Vec<WriteRequest> = keys
+            .iter()
+            .map(|key| {
+                WriteRequest::builder()
+                    .delete_request(
+                        DeleteRequest::builder()
+                            .set_key(Some(Self::unique_key(key.0.clone(), key.1.clone())))
+                            .build()
+                            .unwrap(),
+                    )
+                    .build()
+            })
+            .collect();
+        batch_write(client, write_requests, &Self::qualified_table_name()).await
+    }
+
+    /// A put of `entity` to be written together with others through `Repository::transact_write`.
+    /// It only applies while the item is still `previous`, or still missing when it is new, and is
+    /// undone by putting `previous` back or deleting the item, as long as nobody has written it since.
+    fn transact_put(entity: &T, previous: Option<&T>) -> TransactionItem {
+        let written = Self::written_condition(entity);
+        let unchanged = match previous {
+            Some(previous) => Self::written_condition(previous),
+            None => WriteCondition {
+                expression: "attribute_not_exists(#partition_key)".to_string(),
+                names: HashMap::from([("#partition_key".to_string(), Self::partition_key_from_entity(entity).0)]),
+                values: HashMap::new(),
+            },
+        };
+        TransactionItem {
+            write: Self::put_write(entity, Some(unchanged)),
+            undo: match previous {
+                Some(previous) => Self::put_write(previous, Some(written)),
+                None => Self::delete_write(entity, Some(written)),
+            },
+        }
+    }
+
+    /// Holds while the item still has the `updated_at` that writing `entity` gave it.
+    fn written_condition(entity: &T) -> WriteCondition {
+        let names = HashMap::from([("#updated_at".to_string(), "updated_at".to_string())]);
+        match to_item(entity).unwrap().remove("updated_at").filter(|version| !version.is_null()) {
+            Some(version) => WriteCondition {
+                expression: "#updated_at = :written_updated_at".to_string(),
+                names,
+                values: HashMap::from([(":written_updated_at".to_string(), version)]),
+            },
+            None => WriteCondition {
+                expression: "attribute_not_exists(#updated_at) OR attribute_type(#updated_at, :null_type)".to_string(),
+                names,
+                values: HashMap::from([(":null_type".to_string(), AttributeValue::S("NULL".to_string()))]),
+            },
+        }
+    }
+
+    fn put_write(entity: &T, condition: Option<WriteCondition>) -> TransactWriteItem {
+        let mut item = to_item(entity).unwrap();
+        Self::add_main_key_attributes(entity, &mut item);
+        let mut put = Put::builder()
+            .table_name(Self::qualified_table_name())
+            .set_item(Some(item));
+        if let Some(condition) = condition {
+            put = put.condition_expression(condition.expression)
+                .set_expression_attribute_names(Some(condition.names))
+                .set_expression_attribute_values((!condition.values.is_empty()).then_some(condition.values));
+        }
+        TransactWriteItem::builder()
+            .put(put.build().unwrap())
+            .build()
+    }
+
+    fn delete_write(entity: &T, condition: Option<WriteCondition>) -> TransactWriteItem {
+        let (partition_key_name, partition_key) = Self::partition_key_from_entity(entity);
+        let (sort_key_name, sort_key) = Self::sort_key_from_entity(entity);
+        let mut delete = Delete::builder()
+            .table_name(Self::qualified_table_name())
+            .set_key(Some(HashMap::from([(partition_key_name, partition_key), (sort_key_name, sort_key)])));
+        if let Some(condition) = condition {
+            delete = delete.condition_expression(condition.expression)
+                .set_expression_attribute_names(Some(condition.names))
+                .set_expression_attribute_values((!condition.values.is_empty()).then_some(condition.values));
+        }
+        TransactWriteItem::builder()
+            .delete(delete.build().unwrap())
+            .build()
     }
 
     fn add_main_key_attributes(entity: &T, mut item: &mut HashMap<String, AttributeValue>) {
@@ -414,21 +596,64 @@ where
         let sort_key = Self::sort_key_from_entity(&entity);
         item.insert(partition_key.0, partition_key.1);
         item.insert(sort_key.0, sort_key.1);
-        item.insert("created_at".to_string(), AttributeValue::N(current_timestamp().to_string()));
+        // an entity written again keeps the time it was first created
+        if item.get("created_at").is_none_or(|created_at| created_at.is_null()) {
+            item.insert("created_at".to_string(), AttributeValue::N(current_timestamp().to_string()));
+        }
         Self::add_index_key_attributes(&entity, item);
     }
 
     fn add_index_key_attributes(entity: &T, mut item: &mut HashMap<String, AttributeValue>) {}
 
-    fn build_deleted_event(entity: T) -> Option<OnDeleteMessage> {
+    /// Whether puts stamp `updated_at`, keeping it usable as the version `update_partial_expecting` checks.
+    fn versioned() -> bool {
+        false
+    }
+
+    /// Change to announce on the change feed after `entity` was written, `None` to stay silent.
+    fn change_of(_entity: &T) -> Option<EntityChange> {
         None
     }
 
+    fn publish_change(entity: &T, kind: ChangeKind) {
+        if let Some(change) = Self::change_of(entity) {
+            change_feed().publish(EntityChange { kind, ..change });
+        }
+    }
+
     fn ordering(e1: &T, e2: &T) -> Ordering {
         Ordering::Equal
     }
 }
 
+/// Builds a projection over placeholder names since attributes like `status` are reserved words.
+pub fn projection_expression(attributes: &[String]) -> (String, HashMap<String, String>) {
+    let names: Vec<(String, String)> = attributes.iter()
+        .enumerate()
+        .map(|(index, attribute)| (format!("#p{}", index), attribute.clone()))
+        .collect();
+    let expression = names.iter()
+        .map(|(placeholder, _)| placeholder.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    (expression, names.into_iter().collect())
+}
+
+/// One write of a mutation spanning several items, with the write that takes it back.
+#[derive(Clone, Debug)]
+pub struct TransactionItem {
+    pub write: TransactWriteItem,
+    pub undo: TransactWriteItem,
+}
+
+/// Condition expression of a transaction write with the placeholders it uses.
+#[derive(Clone, Debug)]
+pub struct WriteCondition {
+    pub expression: String,
+    pub names: HashMap<String, String>,
+    pub values: HashMap<String, AttributeValue>,
+}
+
 #[derive(Clone)]
 pub struct Repository {
     client: Arc<Client>,
@@ -436,19 +661,98 @@ pub struct Repository {
 
 impl Repository {
     pub async fn new() -> Self {
-        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-        let client = Client::new(&config);
+        Self::from_config(app_config()).await
+    }
+
+    pub async fn from_config(app_config: &AppConfig) -> Self {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = &app_config.aws_region {
+            loader = loader.region(Region::new(region.clone()));
+        }
+        if let Some(profile) = &app_config.aws_profile {
+            loader = loader.profile_name(profile);
+        }
+        let config = loader.load().await;
+        init_artifact_store(&config, app_config);
+        init_mailer(&config, app_config);
+        // calls are retried by `send_with_retry`, which knows which ones are safe to send again
+        let client = Client::from_conf(aws_sdk_dynamodb::config::Builder::from(&config)
+            .retry_config(RetryConfig::disabled())
+            .build());
         Repository {
             client: Arc::new(client),
         }
     }
 
+    pub async fn check_connectivity(&self) -> Result<(), AppError> {
+        self.client.list_tables()
+            .limit(1)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|err| from_sdk_error("list_tables", err))
+    }
+
+    /// Writes items in transactions of up to 100. When one of them fails, the ones written before
+    /// are undone, except for items someone else has written since; those are left as this mutation
+    /// wrote them and the call fails with an error telling how many could not be rolled back.
+    pub async fn transact_write(&self, items: Vec<TransactionItem>) -> Result<(), AppError> {
+        let mut written: Vec<&[TransactionItem]> = vec![];
+        for chunk in items.chunks(MAX_TRANSACTION_ITEMS) {
+            if let Err(err) = self.send_transaction(chunk.iter().map(|item| item.write.clone()).collect()).await {
+                let mut not_undone = 0;
+                for written_chunk in written.into_iter().rev() {
+                    if let Err(undo_err) = self.send_transaction(written_chunk.iter().map(|item| item.undo.clone()).collect()).await {
+                        tracing::error!("could not undo {} transaction items: {:?}", written_chunk.len(), undo_err);
+                        not_undone += written_chunk.len();
+                    }
+                }
+                if not_undone > 0 {
+                    return Err(AppError::Internal(format!(
+                        "Writing {} items failed and {} items written before could not be rolled back: {:?}",
+                        items.len(), not_undone, err)));
+                }
+                return Err(err);
+            }
+            written.push(chunk);
+        }
+        Ok(())
+    }
+
+    async fn send_transaction(&self, items: Vec<TransactWriteItem>) -> Result<(), AppError> {
+        let request = self.client.transact_write_items()
+            .set_transact_items(Some(items));
+        send_conditional_with_retry("transact_write_items", || request.clone().send())
+            .await
+            .map(|_| ())
+            .map_err(|err| {
+                let conflicted = err.as_service_error()
+                    .and_then(|err| match err {
+                        TransactWriteItemsError::TransactionCanceledException(canceled) => Some(canceled),
+                        _ => None,
+                    })
+                    .is_some_and(|canceled| canceled.cancellation_reasons().iter()
+                        .any(|reason| reason.code() == Some("ConditionalCheckFailed")));
+                if conflicted {
+                    AppError::Conflict("Items were changed by someone else, try again!".to_string())
+                } else {
+                    from_sdk_error("transact_write_items", err)
+                }
+            })
+    }
+
     pub fn runs(&self) -> RunOperations {
         RunOperations {
             client: Arc::clone(&self.client),
         }
     }
 
+    pub fn run_locks(&self) -> RunLockOperations {
+        RunLockOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
     pub fn parameters(&self) -> ParameterOperations {
         ParameterOperations {
             client: Arc::clone(&self.client),
@@ -483,57 +787,228 @@ impl Repository {
             client: Arc::clone(&self.client),
         }
     }
+
+    pub fn templates(&self) -> TemplateOperations {
+        TemplateOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn revisions(&self) -> RevisionOperations {
+        RevisionOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn deletion_jobs(&self) -> DeletionJobOperations {
+        DeletionJobOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn action_groups(&self) -> ActionGroupOperations {
+        ActionGroupOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn memberships(&self) -> MembershipOperations {
+        MembershipOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn api_tokens(&self) -> ApiTokenOperations {
+        ApiTokenOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn quotas(&self) -> QuotaOperations {
+        QuotaOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn sensitive_headers(&self) -> SensitiveHeaderPolicyOperations {
+        SensitiveHeaderPolicyOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn notification_settings(&self) -> NotificationSettingsOperations {
+        NotificationSettingsOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn digest_subscriptions(&self) -> DigestSubscriptionOperations {
+        DigestSubscriptionOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn constants(&self) -> ConstantOperations {
+        ConstantOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn environments(&self) -> EnvironmentOperations {
+        EnvironmentOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn snapshots(&self) -> SnapshotOperations {
+        SnapshotOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn header_rules(&self) -> HeaderRuleOperations {
+        HeaderRuleOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
+
+    pub fn import_reports(&self) -> ImportReportOperations {
+        ImportReportOperations {
+            client: Arc::clone(&self.client),
+        }
+    }
 }
 
 pub(crate) fn build_composite_key(keys: Vec<String>) -> String {
     keys.join("#")
 }
 
-async fn batch_write(client: Arc<Client>, write_requests: Vec<WriteRequest>, table_name: &String) {
+async fn batch_write(client: Arc<Client>, write_requests: Vec<WriteRequest>, table_name: &String) -> Result<(), AppError> {
     let chunks: Vec<Vec<WriteRequest>> = write_requests
         .chunks(25)
         .map(|chunk| chunk.to_vec())
         .collect();
+    let mut tasks = vec![];
     for write_chunk in chunks {
         let cloned_client = client.clone();
         let table_name_cloned = table_name.clone();
-        let table_name_cloned2 = table_name.clone();
-        tokio::task::spawn(async move {
-            let result = cloned_client
-                .batch_write_item()
-                .set_request_items(Some(HashMap::from([(table_name_cloned, write_chunk)])))
-                .send()
-                .await;
-            match result {
-                Ok(_) => {
-                    info!("{}:batch_write_item ok", table_name_cloned2);
-                }
-                Err(err) => {
-                    println!("{}: batch write error: {}", table_name_cloned2, err.into_service_error().message().unwrap_or_default());
-                }
+        tasks.push(tokio::task::spawn(async move {
+            write_chunk_until_processed(&cloned_client, write_chunk, &table_name_cloned).await
+        }));
+    }
+    let mut result = Ok(());
+    for task in tasks {
+        match task.await {
+            Ok(Ok(_)) => {
+                info!(table = %table_name, "batch write ok");
             }
-        });
+            Ok(Err(err)) => {
+                tracing::error!(table = %table_name, error = ?err, "batch write failed");
+                result = Err(err);
+            }
+            Err(join_err) => {
+                result = Err(AppError::Internal(join_err.to_string()));
+            }
+        }
     }
+    result
 }
 
-#[derive(Debug)]
-pub(crate) enum OnDeleteMessage {
-    TestCaseDeleted(TestCase),
-    ActionDeleted(Action),
-    RunDeleted(Run),
+/// Writes the chunk again for as long as DynamoDB leaves some of its items unprocessed.
+async fn write_chunk_until_processed(client: &Client, write_chunk: Vec<WriteRequest>, table_name: &String) -> Result<(), AppError> {
+    let mut pending = write_chunk;
+    let mut attempt = 1;
+    loop {
+        let request = client
+            .batch_write_item()
+            .set_request_items(Some(HashMap::from([(table_name.clone(), pending)])));
+        let output = send_with_retry(table_name, || request.clone().send())
+            .await
+            .map_err(|err| from_sdk_error(table_name, err))?;
+        pending = output.unprocessed_items
+            .and_then(|mut unprocessed| unprocessed.remove(table_name))
+            .unwrap_or_default();
+        if pending.is_empty() {
+            return Ok(());
+        }
+        wait_for_unprocessed(table_name, &mut attempt, pending.len()).await?;
+    }
 }
 
 pub fn current_timestamp() -> u64 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
 }
 
-fn from_sdk_error<T>(sdk_err: SdkError<T>) -> AppError
+pub(crate) fn from_sdk_error<T>(table_name: &str, sdk_err: SdkError<T>) -> AppError
 where
     T: Debug,
     T: ProvideErrorMetadata,
 {
     tracing::error!("aws dynamodb sdk error: {:?}", sdk_err);
+    metrics().dynamodb_errors.inc(&[table_name, sdk_err.code().unwrap_or("Unknown")]);
     let message = sdk_err.message()
         .map_or_else(|| sdk_err.to_string(), |err| { err.to_string() });
     AppError::Internal(message)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::action::model::Action;
+    use crate::action::service::ActionsTable;
+
+    fn action(test_case_id: &str, order: usize, updated_at: Option<u64>) -> Action {
+        Action {
+            test_case_id: test_case_id.to_string(),
+            updated_at,
+            ..test_action(&format!("orders_{}", order), order, "get", "/orders")
+        }
+    }
+
+    #[test]
+    fn write_only_applies_to_the_previous_version() {
+        let previous = action("tc1", 0, Some(1));
+        let renamed = Action { name: "renamed".to_string(), updated_at: Some(2), ..previous.clone() };
+        let put = ActionsTable::transact_put(&renamed, Some(&previous)).write.put.unwrap();
+        assert_eq!(put.condition_expression.as_deref(), Some("#updated_at = :written_updated_at"));
+        assert_eq!(put.expression_attribute_values.unwrap().get(":written_updated_at"), Some(&AttributeValue::N("1".to_string())));
+
+        let put = ActionsTable::transact_put(&action("tc1", 1, None), None).write.put.unwrap();
+        assert_eq!(put.condition_expression.as_deref(), Some("attribute_not_exists(#partition_key)"));
+        assert!(put.expression_attribute_values.is_none());
+    }
+
+    #[test]
+    fn undo_only_applies_to_the_written_version() {
+        let previous = action("tc1", 0, Some(1));
+        let renamed = Action { name: "renamed".to_string(), updated_at: Some(2), ..previous.clone() };
+        let undo = ActionsTable::transact_put(&renamed, Some(&previous)).undo;
+        let put = undo.put.unwrap();
+        assert_eq!(put.condition_expression.as_deref(), Some("#updated_at = :written_updated_at"));
+        assert_eq!(put.expression_attribute_values.unwrap().get(":written_updated_at"), Some(&AttributeValue::N("2".to_string())));
+        assert_eq!(put.item.get("name"), Some(&AttributeValue::S("orders_0".to_string())));
+
+        let undo = ActionsTable::transact_put(&action("tc1", 1, None), None).undo;
+        assert!(undo.put.is_none());
+        assert_eq!(undo.delete.unwrap().condition_expression.as_deref(),
+                   Some("attribute_not_exists(#updated_at) OR attribute_type(#updated_at, :null_type)"));
+    }
+
+    #[tokio::test]
+    async fn failed_transaction_undoes_the_ones_written_before() {
+        init_logger();
+        let repository = Repository::new().await;
+        let test_case_id = uuid::Uuid::new_v4().to_string();
+        let actions: Vec<Action> = (0..MAX_TRANSACTION_ITEMS).map(|order| action(&test_case_id, order, Some(1))).collect();
+        let mut items: Vec<TransactionItem> = actions.iter().map(|action| ActionsTable::transact_put(action, None)).collect();
+        // the same item twice makes the second transaction fail
+        let duplicate = action(&test_case_id, MAX_TRANSACTION_ITEMS, Some(1));
+        items.push(ActionsTable::transact_put(&duplicate, None));
+        items.push(ActionsTable::transact_put(&duplicate, None));
+
+        assert!(repository.transact_write(items).await.is_err());
+        let remaining = repository.actions().list_all("cust1".to_string(), test_case_id).await.unwrap();
+        assert!(remaining.is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::api::AppError;
+use crate::metrics::registry::metrics;
+use aws_sdk_dynamodb::config::http::HttpResponse;
+use aws_sdk_dynamodb::error::{ProvideErrorMetadata, SdkError};
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Attempts of one call, the first one included.
+const MAX_ATTEMPTS: u32 = 6;
+const BASE_DELAY: Duration = Duration::from_millis(50);
+const MAX_DELAY: Duration = Duration::from_secs(5);
+/// Retries that may be spent at once before calls fail without retrying.
+const BUDGET_CAPACITY: u32 = 500;
+/// Budget is kept in tenths of a retry, as each successful call earns back one tenth.
+const RETRY_COST: u32 = 10;
+
+/// Error codes DynamoDB answers with when it is throttling; the call was not applied.
+const THROTTLING_CODES: [&str; 4] = [
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+    "LimitExceededException",
+];
+
+/// Error codes DynamoDB answers with when it is briefly unavailable; the call may have been applied.
+const AMBIGUOUS_CODES: [&str; 2] = [
+    "InternalServerError",
+    "ServiceUnavailable",
+];
+
+/// Reported in place of an error code for items a batch call left unprocessed.
+const UNPROCESSED_CODE: &str = "UnprocessedItems";
+
+static RETRY_BUDGET: OnceLock<RetryBudget> = OnceLock::new();
+
+pub fn retry_budget() -> &'static RetryBudget {
+    RETRY_BUDGET.get_or_init(|| RetryBudget::new(BUDGET_CAPACITY))
+}
+
+/// Retries shared by every DynamoDB call, so a table that stays throttled fails fast instead of
+/// every caller waiting out all its attempts.
+pub struct RetryBudget {
+    capacity: u32,
+    tokens: Mutex<u32>,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        RetryBudget {
+            capacity: retries * RETRY_COST,
+            tokens: Mutex::new(retries * RETRY_COST),
+        }
+    }
+
+    /// Takes one retry, `false` when none is left.
+    pub fn withdraw(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens < RETRY_COST {
+            return false;
+        }
+        *tokens -= RETRY_COST;
+        true
+    }
+
+    pub fn deposit(&self) {
+        let mut tokens = self.tokens.lock().unwrap();
+        *tokens = (*tokens + 1).min(self.capacity);
+    }
+}
+
+pub fn is_retryable(code: Option<&str>) -> bool {
+    is_throttling(code) || code.is_some_and(|code| AMBIGUOUS_CODES.contains(&code))
+}
+
+pub fn is_throttling(code: Option<&str>) -> bool {
+    code.is_some_and(|code| THROTTLING_CODES.contains(&code))
+}
+
+/// Exponential backoff with full jitter: a random delay up to `BASE_DELAY * 2^attempt`, capped.
+pub fn backoff(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_DELAY);
+    let jitter = (uuid::Uuid::new_v4().as_u128() % 1000) as u32;
+    ceiling * jitter / 1000
+}
+
+/// Sends the call again while DynamoDB throttles it, as long as attempts and the retry budget last.
+/// Every retry is counted by table and error code.
+pub async fn send_with_retry<T, E, F, Fut>(table_name: &str, send: F) -> Result<T, SdkError<E, HttpResponse>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    E: ProvideErrorMetadata,
+{
+    send_retrying(table_name, is_retryable, send).await
+}
+
+/// `send_with_retry` for conditional writes, which are only sent again when they were throttled: a
+/// write that failed with a server error may have been applied, and sending it again would then
+/// fail its own condition.
+pub async fn send_conditional_with_retry<T, E, F, Fut>(table_name: &str, send: F) -> Result<T, SdkError<E, HttpResponse>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    E: ProvideErrorMetadata,
+{
+    send_retrying(table_name, is_throttling, send).await
+}
+
+async fn send_retrying<T, E, F, Fut>(table_name: &str, retryable: fn(Option<&str>) -> bool, mut send: F) -> Result<T, SdkError<E, HttpResponse>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, SdkError<E, HttpResponse>>>,
+    E: ProvideErrorMetadata,
+{
+    let mut attempt = 1;
+    loop {
+        match send().await {
+            Ok(output) => {
+                retry_budget().deposit();
+                return Ok(output);
+            }
+            Err(err) if attempt < MAX_ATTEMPTS && retryable(err.code()) && retry_budget().withdraw() => {
+                metrics().dynamodb_retries.inc(&[table_name, err.code().unwrap_or("Unknown")]);
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Waits before a batch call is sent again with the items it left unprocessed, failing once the
+/// attempts or the retry budget ran out.
+pub async fn wait_for_unprocessed(table_name: &str, attempt: &mut u32, unprocessed: usize) -> Result<(), AppError> {
+    if *attempt >= MAX_ATTEMPTS || !retry_budget().withdraw() {
+        metrics().dynamodb_errors.inc(&[table_name, UNPROCESSED_CODE]);
+        return Err(AppError::Internal(format!("{} items of {} were left unprocessed", unprocessed, table_name)));
+    }
+    metrics().dynamodb_retries.inc(&[table_name, UNPROCESSED_CODE]);
+    tokio::time::sleep(backoff(*attempt)).await;
+    *attempt += 1;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_up_to_the_cap() {
+        for attempt in 0..10 {
+            let ceiling = BASE_DELAY.saturating_mul(2u32.pow(attempt)).min(MAX_DELAY);
+            assert!(backoff(attempt) <= ceiling);
+        }
+        assert!(backoff(30) <= MAX_DELAY);
+    }
+
+    #[test]
+    fn budget_runs_out_and_refills() {
+        let budget = RetryBudget::new(2);
+        assert!(budget.withdraw());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+        (0..10).for_each(|_| budget.deposit());
+        assert!(budget.withdraw());
+        assert!(!budget.withdraw());
+
+        assert!(is_retryable(Some("ProvisionedThroughputExceededException")));
+        assert!(!is_retryable(Some("ConditionalCheckFailedException")));
+        assert!(!is_retryable(None));
+        assert!(is_retryable(Some("InternalServerError")));
+        assert!(!is_throttling(Some("InternalServerError")));
+        assert!(is_throttling(Some("ThrottlingException")));
+    }
+}
@@ -0,0 +1,7 @@
+use crate::api::{ApiResponse, AppError};
+use crate::plugin::registry::{plugins, PluginNames};
+
+/// Names of the custom functions and comparisons assertions can use.
+pub async fn list_assertion_plugins() -> Result<ApiResponse<PluginNames>, AppError> {
+    ApiResponse::from(Ok(plugins().names()))
+}
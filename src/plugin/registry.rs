@@ -0,0 +1,72 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::warn;
+use utoipa::ToSchema;
+
+static PLUGINS: OnceLock<PluginRegistry> = OnceLock::new();
+
+pub fn plugins() -> &'static PluginRegistry {
+    PLUGINS.get_or_init(PluginRegistry::default)
+}
+
+/// A function assertions can compute values with next to the built-in ones, used as
+/// `Operation::Custom(name)`.
+pub trait AssertionFunction: Send + Sync {
+    /// `arguments` holds what each parameter of the function evaluated to, in order.
+    fn call(&self, arguments: &[Vec<Value>]) -> Result<Vec<Value>, String>;
+}
+
+/// A comparison assertions can check their two sides with, used as `ComparisonType::Custom(name)`.
+pub trait Comparison: Send + Sync {
+    fn compare(&self, left: &[Value], right: &[Value]) -> Result<bool, String>;
+}
+
+/// Custom assertion functions and comparisons by the name assertions refer to them with.
+#[derive(Default)]
+pub struct PluginRegistry {
+    functions: RwLock<HashMap<String, Arc<dyn AssertionFunction>>>,
+    comparisons: RwLock<HashMap<String, Arc<dyn Comparison>>>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct PluginNames {
+    pub functions: Vec<String>,
+    pub comparisons: Vec<String>,
+}
+
+impl PluginRegistry {
+    /// Registers the function, replacing one registered before under the same name.
+    pub fn register_function(&self, name: &str, function: Arc<dyn AssertionFunction>) {
+        if self.functions.write().unwrap().insert(name.to_string(), function).is_some() {
+            warn!(name, "assertion function registered again, the previous one is replaced");
+        }
+    }
+
+    /// Registers the comparison, replacing one registered before under the same name.
+    pub fn register_comparison(&self, name: &str, comparison: Arc<dyn Comparison>) {
+        if self.comparisons.write().unwrap().insert(name.to_string(), comparison).is_some() {
+            warn!(name, "comparison registered again, the previous one is replaced");
+        }
+    }
+
+    pub fn function(&self, name: &str) -> Option<Arc<dyn AssertionFunction>> {
+        self.functions.read().unwrap().get(name).cloned()
+    }
+
+    pub fn comparison(&self, name: &str) -> Option<Arc<dyn Comparison>> {
+        self.comparisons.read().unwrap().get(name).cloned()
+    }
+
+    pub fn names(&self) -> PluginNames {
+        let sorted = |mut names: Vec<String>| {
+            names.sort();
+            names
+        };
+        PluginNames {
+            functions: sorted(self.functions.read().unwrap().keys().cloned().collect()),
+            comparisons: sorted(self.comparisons.read().unwrap().keys().cloned().collect()),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::plugin::registry::{AssertionFunction, Comparison, PluginRegistry};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{error, info};
+use wasmi::{Config, Engine, ExternType, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Exports named `function_<name>` are registered as assertion functions.
+const FUNCTION_PREFIX: &str = "function_";
+/// Exports named `comparison_<name>` are registered as comparisons.
+const COMPARISON_PREFIX: &str = "comparison_";
+/// Linear memory one call may grow to; a plugin asking for more fails the assertion.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// A WebAssembly module exporting assertion functions and comparisons.
+///
+/// Values cross the boundary as JSON. The module exports its `memory` and an `alloc(len) -> ptr`
+/// the input is written through; every plugin export takes `(ptr, len)` of its input and returns
+/// the pointer of its output in the high 32 bits and the length in the low 32 bits. Functions get
+/// the values of their parameters as an array of arrays and answer with an array, comparisons get
+/// `{"left": [...], "right": [...]}` and answer with a boolean. `{"error": "..."}` fails the
+/// assertion with the message.
+///
+/// Every call runs in a fresh instance, so plugins keep no state between calls. Its memory may
+/// grow to `MAX_MEMORY_BYTES`.
+pub struct WasmModule {
+    engine: Engine,
+    module: Module,
+    fuel: u64,
+}
+
+impl WasmModule {
+    /// `fuel` bounds the instructions one call may execute, so a plugin stuck in a loop fails the
+    /// assertion instead of holding up the run.
+    pub fn new(wasm: &[u8], fuel: u64) -> Result<Self, String> {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm).map_err(|err| err.to_string())?;
+        Ok(WasmModule { engine, module, fuel })
+    }
+
+    fn exports_with(&self, prefix: &str) -> Vec<(String, String)> {
+        self.module.exports()
+            .filter(|export| matches!(export.ty(), ExternType::Func(_)))
+            .filter_map(|export| export.name().strip_prefix(prefix)
+                .filter(|name| !name.is_empty())
+                .map(|name| (name.to_string(), export.name().to_string())))
+            .collect()
+    }
+
+    fn call(&self, export: &str, input: &Value) -> Result<Value, String> {
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits: &mut StoreLimits| limits);
+        store.set_fuel(self.fuel).map_err(|err| err.to_string())?;
+        let instance = Linker::<StoreLimits>::new(&self.engine)
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|err| err.to_string())?;
+        let memory = instance.get_memory(&store, "memory").ok_or("module does not export its memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&store, "alloc").map_err(|err| err.to_string())?;
+        let function = instance.get_typed_func::<(i32, i32), i64>(&store, export).map_err(|err| err.to_string())?;
+        let input = serde_json::to_vec(input).unwrap();
+        let input_len = i32::try_from(input.len()).map_err(|_| "input is too large".to_string())?;
+        let input_ptr = alloc.call(&mut store, input_len).map_err(|err| err.to_string())?;
+        memory.write(&mut store, input_ptr as u32 as usize, &input).map_err(|err| err.to_string())?;
+        let packed = function.call(&mut store, (input_ptr, input_len)).map_err(|err| format!("{} failed: {}", export, err))? as u64;
+        let (output_ptr, output_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if output_ptr + output_len > memory.data(&store).len() {
+            return Err(format!("{} returned output outside of its memory", export));
+        }
+        let mut output = vec![0; output_len];
+        memory.read(&store, output_ptr, &mut output).map_err(|err| err.to_string())?;
+        let output: Value = serde_json::from_slice(&output).map_err(|err| format!("{} returned invalid JSON: {}", export, err))?;
+        match output.get("error").and_then(Value::as_str) {
+            Some(message) => Err(message.to_string()),
+            None => Ok(output),
+        }
+    }
+}
+
+struct WasmFunction {
+    module: Arc<WasmModule>,
+    export: String,
+}
+
+impl AssertionFunction for WasmFunction {
+    fn call(&self, arguments: &[Vec<Value>]) -> Result<Vec<Value>, String> {
+        match self.module.call(&self.export, &json!(arguments))? {
+            Value::Array(values) => Ok(values),
+            other => Err(format!("{} returned {} instead of an array", self.export, other)),
+        }
+    }
+}
+
+struct WasmComparison {
+    module: Arc<WasmModule>,
+    export: String,
+}
+
+impl Comparison for WasmComparison {
+    fn compare(&self, left: &[Value], right: &[Value]) -> Result<bool, String> {
+        match self.module.call(&self.export, &json!({"left": left, "right": right}))? {
+            Value::Bool(passed) => Ok(passed),
+            other => Err(format!("{} returned {} instead of a boolean", self.export, other)),
+        }
+    }
+}
+
+/// Registers what the module exports, returning the names it registered.
+pub fn register_module(registry: &PluginRegistry, module: WasmModule) -> Vec<String> {
+    let module = Arc::new(module);
+    let functions = module.exports_with(FUNCTION_PREFIX);
+    let comparisons = module.exports_with(COMPARISON_PREFIX);
+    functions.iter().for_each(|(name, export)| {
+        registry.register_function(name, Arc::new(WasmFunction { module: Arc::clone(&module), export: export.clone() }));
+    });
+    comparisons.iter().for_each(|(name, export)| {
+        registry.register_comparison(name, Arc::new(WasmComparison { module: Arc::clone(&module), export: export.clone() }));
+    });
+    functions.into_iter().chain(comparisons).map(|(name, _)| name).collect()
+}
+
+/// Loads every `.wasm` file of the directory. A module that cannot be read or compiled is
+/// logged and skipped.
+pub fn load_plugins(registry: &PluginRegistry, dir: &Path, fuel: u64) {
+    let mut paths: Vec<_> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+            .collect(),
+        Err(err) => {
+            error!("could not read plugin directory {}: {}", dir.display(), err);
+            return;
+        }
+    };
+    paths.sort();
+    for path in paths {
+        let module = std::fs::read(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|wasm| WasmModule::new(&wasm, fuel));
+        match module {
+            Ok(module) => {
+                let names = register_module(registry, module);
+                info!(plugin = %path.display(), ?names, "loaded assertion plugin");
+            }
+            Err(err) => error!("could not load plugin {}: {}", path.display(), err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `function_first` always answers `[42]`, `comparison_short_input` passes while its input is
+    /// short and fails with an error otherwise, and `function_spin` never returns.
+    /// `function_overflow` points its output past its memory and `function_grow` answers `[42]`
+    /// when growing its memory by 128 MiB is refused.
+    const PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $next (mut i32) (i32.const 1024))
+          (data (i32.const 0) "[42]")
+          (data (i32.const 16) "true")
+          (data (i32.const 32) "{\"error\":\"sides differ\"}")
+          (func (export "alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $next))
+            (global.set $next (i32.add (global.get $next) (local.get $len)))
+            (local.get $ptr))
+          (func (export "function_first") (param i32 i32) (result i64)
+            (i64.const 4))
+          (func (export "comparison_short_input") (param $ptr i32) (param $len i32) (result i64)
+            (if (result i64) (i32.lt_u (local.get $len) (i32.const 30))
+              (then (i64.or (i64.shl (i64.const 16) (i64.const 32)) (i64.const 4)))
+              (else (i64.or (i64.shl (i64.const 32) (i64.const 32)) (i64.const 24)))))
+          (func (export "function_spin") (param i32 i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0))
+          (func (export "function_overflow") (param i32 i32) (result i64)
+            (i64.const 0xffffffff))
+          (func (export "function_grow") (param i32 i32) (result i64)
+            (if (result i64) (i32.eq (memory.grow (i32.const 2048)) (i32.const -1))
+              (then (i64.const 4))
+              (else (i64.or (i64.shl (i64.const 32) (i64.const 32)) (i64.const 24))))))
+    "#;
+
+    #[test]
+    fn registers_and_calls_module_exports() {
+        let registry = PluginRegistry::default();
+        let module = WasmModule::new(&wat::parse_str(PLUGIN).unwrap(), 100_000).unwrap();
+
+        let mut names = register_module(&registry, module);
+        names.sort();
+        assert_eq!(names, vec!["first", "grow", "overflow", "short_input", "spin"]);
+
+        let first = registry.function("first").unwrap();
+        assert_eq!(first.call(&[vec![json!(1)]]), Ok(vec![json!(42)]));
+        let short_input = registry.comparison("short_input").unwrap();
+        assert_eq!(short_input.compare(&[json!(1)], &[json!(1)]), Ok(true));
+        assert_eq!(short_input.compare(&[json!("a long value")], &[json!(1)]), Err("sides differ".to_string()));
+        assert!(registry.function("spin").unwrap().call(&[]).is_err());
+        assert_eq!(registry.function("overflow").unwrap().call(&[]), Err("function_overflow returned output outside of its memory".to_string()));
+        assert_eq!(registry.function("grow").unwrap().call(&[]), Ok(vec![json!(42)]));
+    }
+}
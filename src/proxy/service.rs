@@ -147,17 +147,19 @@ fn build_action_execution(run: &Run, action_id: &String, http_req: &HttpRequest,
         .status_code(response_pair.0)
         .maybe_response_body(response_pair.1)
         .maybe_request_body(http_req.get_body())
+        .request_url(http_req.endpoint.to_url())
         .query_params(http_req.endpoint.query_params.iter()
             .map(|rp| { (rp.key.clone(), rp.value.clone()) })
             .collect())
         .build()
+        .with_canonical_response()
 }
 
 fn update_execution(mut exec: ActionExecution, http_result: HttpResult<Value>) -> ActionExecution {
     let response_pair = (http_result.status_code, http_result.res_body.value);
     exec.status_code = response_pair.0;
     exec.response_body = Some(response_pair.1);
-    exec
+    exec.with_canonical_response()
 }
 
 struct BuildActionParamResult {
@@ -0,0 +1,44 @@
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::repo::Repository;
+use crate::quota::model::{default_max_storage_bytes_per_test_case, Quota, QuotaStatus};
+use crate::validation::AppJson;
+use axum::extract::State;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn get_quota(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<QuotaStatus>, AppError> {
+    let customer_id = "eren".to_string();
+    let result = Ok(QuotaStatus {
+        quota: repository.quotas().get(&customer_id).await?,
+        runs_today: repository.quotas().runs_today(&customer_id).await?,
+        concurrent_runs: repository.runs().count_in_progress(&customer_id).await?,
+    });
+    ApiResponse::from(result)
+}
+
+pub async fn put_quota(
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<PutQuotaPayload>,
+) -> Result<ApiResponse<Quota>, AppError> {
+    let result = repository.quotas()
+        .put(Quota::builder()
+            .customer_id("eren".to_string())
+            .max_runs_per_day(payload.max_runs_per_day)
+            .max_concurrent_runs(payload.max_concurrent_runs)
+            .max_actions_per_test_case(payload.max_actions_per_test_case)
+            .max_storage_bytes_per_test_case(payload.max_storage_bytes_per_test_case)
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutQuotaPayload {
+    pub max_runs_per_day: u64,
+    pub max_concurrent_runs: u64,
+    pub max_actions_per_test_case: u64,
+    #[serde(default = "default_max_storage_bytes_per_test_case")]
+    pub max_storage_bytes_per_test_case: u64,
+}
@@ -0,0 +1,116 @@
+use crate::action::model::Action;
+use crate::api::AppError;
+use crate::assertion::model::Assertion;
+use crate::parameter::model::Parameter;
+use crate::persistence::repo::Repository;
+use crate::quota::model::{Quota, QuotaKind, QuotaViolation};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Items a write stores in a test case. Those sharing a key with a stored item replace it, the
+/// rest are added.
+#[derive(Default)]
+pub struct TestCaseWrite<'a> {
+    pub actions: &'a [Action],
+    pub parameters: &'a [Parameter],
+    pub assertions: &'a [Assertion],
+}
+
+/// Proof that a write passed [`guard_test_case_write`]. The writes adding actions require one, so
+/// no write path can add to a test case without going through the quota checks.
+pub struct QuotaPermit(());
+
+/// How many actions a test case has and how many bytes its actions, parameters and assertions take.
+#[derive(Debug, Default, Eq, PartialEq)]
+struct TestCaseUsage {
+    actions: u64,
+    storage_bytes: u64,
+}
+
+/// The single enforcement point of the per test case quotas: fails with the violated quota when
+/// the write would grow the test case past its action count or storage limit.
+pub async fn guard_test_case_write(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    write: TestCaseWrite<'_>,
+) -> Result<QuotaPermit, AppError> {
+    let (quota_operations, action_operations) = (repository.quotas(), repository.actions());
+    let (parameter_operations, assertion_operations) = (repository.parameters(), repository.assertions());
+    let (quota, actions, parameters, assertions) = tokio::try_join!(
+        quota_operations.get(customer_id),
+        action_operations.list_all(customer_id.clone(), test_case_id.clone()),
+        parameter_operations.list_all(customer_id.clone(), test_case_id.clone()),
+        assertion_operations.list_all(customer_id, test_case_id),
+    )?;
+    let stored = TestCaseWrite { actions: &actions, parameters: &parameters, assertions: &assertions };
+    check_growth(&quota, &usage_of(&stored, None), &usage_of(&stored, Some(&write)))
+        .map_err(AppError::QuotaExceeded)?;
+    Ok(QuotaPermit(()))
+}
+
+/// A write that does not grow the test case passes even when a lowered quota is already exceeded.
+fn check_growth(quota: &Quota, before: &TestCaseUsage, after: &TestCaseUsage) -> Result<(), QuotaViolation> {
+    let grows = |kind: QuotaKind, before: u64, after: u64| match after.checked_sub(before) {
+        Some(adding) if adding > 0 => quota.check_adding(kind, before, adding),
+        _ => Ok(()),
+    };
+    grows(QuotaKind::ActionsPerTestCase, before.actions, after.actions)?;
+    grows(QuotaKind::StoragePerTestCase, before.storage_bytes, after.storage_bytes)
+}
+
+fn usage_of(stored: &TestCaseWrite, write: Option<&TestCaseWrite>) -> TestCaseUsage {
+    let actions = sizes(stored.actions, write.map(|write| write.actions), |action| action.id.clone());
+    let parameters = sizes(stored.parameters, write.map(|write| write.parameters), |parameter| format!("{}#{}", parameter.action_id, parameter.id));
+    let assertions = sizes(stored.assertions, write.map(|write| write.assertions), |assertion| assertion.id.clone());
+    TestCaseUsage {
+        actions: actions.len() as u64,
+        storage_bytes: [actions, parameters, assertions].iter().flat_map(|sizes| sizes.values()).sum(),
+    }
+}
+
+fn sizes<T: Serialize>(stored: &[T], written: Option<&[T]>, key: impl Fn(&T) -> String) -> HashMap<String, u64> {
+    stored.iter()
+        .chain(written.into_iter().flatten())
+        .map(|item| (key(item), serde_json::to_vec(item).map_or(0, |bytes| bytes.len() as u64)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+
+    #[test]
+    fn only_growing_writes_are_checked_against_the_quota() {
+        let stored = vec![test_action("login", 0, "POST", "https://abc.xyz/login")];
+        let renamed = Action { name: "auth".to_string(), ..stored[0].clone() };
+        let added = vec![test_action("list", 1, "GET", "https://abc.xyz/items")];
+        let stored_write = TestCaseWrite { actions: &stored, ..Default::default() };
+        let before = usage_of(&stored_write, None);
+        let after_rename = usage_of(&stored_write, Some(&TestCaseWrite { actions: std::slice::from_ref(&renamed), ..Default::default() }));
+        let after_add = usage_of(&stored_write, Some(&TestCaseWrite { actions: &added, ..Default::default() }));
+
+        assert_eq!(before.actions, 1);
+        assert_eq!(after_rename.actions, 1);
+        assert!(after_rename.storage_bytes < before.storage_bytes);
+        assert_eq!(after_add.actions, 2);
+        assert!(after_add.storage_bytes > before.storage_bytes);
+
+        let quota = Quota::builder()
+            .customer_id("cust1".to_string())
+            .max_runs_per_day(10)
+            .max_concurrent_runs(1)
+            .max_actions_per_test_case(1)
+            .max_storage_bytes_per_test_case(before.storage_bytes)
+            .build();
+        assert!(check_growth(&quota, &before, &after_rename).is_ok());
+        assert_eq!(check_growth(&quota, &before, &after_add).map_err(|violation| violation.quota), Err(QuotaKind::ActionsPerTestCase));
+        let more_actions = Quota { max_actions_per_test_case: 2, ..quota.clone() };
+        assert_eq!(check_growth(&more_actions, &before, &after_add).map_err(|violation| violation.quota), Err(QuotaKind::StoragePerTestCase));
+        let more_storage = Quota { max_storage_bytes_per_test_case: after_add.storage_bytes, ..more_actions };
+        assert!(check_growth(&more_storage, &before, &after_add).is_ok());
+        let lowered = Quota { max_storage_bytes_per_test_case: 0, ..quota };
+        assert!(check_growth(&lowered, &before, &after_rename).is_ok());
+    }
+}
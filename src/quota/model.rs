@@ -0,0 +1,139 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const QUOTA_ID: &str = "quota";
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+pub const DEFAULT_MAX_STORAGE_BYTES_PER_TEST_CASE: u64 = 5 * 1024 * 1024;
+
+/// Limits of a customer. Customers without a stored quota get [`Quota::default_for`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Quota {
+    pub customer_id: String,
+    #[builder(default = QUOTA_ID.to_string())]
+    pub id: String,
+    pub max_runs_per_day: u64,
+    pub max_concurrent_runs: u64,
+    pub max_actions_per_test_case: u64,
+    /// Bytes the actions, parameters and assertions of a test case may take up, measured as JSON.
+    /// Run executions are not counted, retention policies bound those.
+    #[serde(default = "default_max_storage_bytes_per_test_case")]
+    #[builder(default = DEFAULT_MAX_STORAGE_BYTES_PER_TEST_CASE)]
+    pub max_storage_bytes_per_test_case: u64,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl Quota {
+    pub fn default_for(customer_id: &str) -> Self {
+        Quota::builder()
+            .customer_id(customer_id.to_string())
+            .max_runs_per_day(1000)
+            .max_concurrent_runs(10)
+            .max_actions_per_test_case(500)
+            .build()
+    }
+
+    pub fn limit_of(&self, kind: &QuotaKind) -> u64 {
+        match kind {
+            QuotaKind::RunsPerDay => self.max_runs_per_day,
+            QuotaKind::ConcurrentRuns => self.max_concurrent_runs,
+            QuotaKind::ActionsPerTestCase => self.max_actions_per_test_case,
+            QuotaKind::StoragePerTestCase => self.max_storage_bytes_per_test_case,
+        }
+    }
+
+    /// Fails when starting one more unit of `kind` would go over the limit.
+    pub fn check(&self, kind: QuotaKind, used: u64) -> Result<(), QuotaViolation> {
+        self.check_adding(kind, used, 1)
+    }
+
+    /// Fails when adding `adding` units of `kind` to the `used` ones would go over the limit.
+    pub fn check_adding(&self, kind: QuotaKind, used: u64, adding: u64) -> Result<(), QuotaViolation> {
+        let limit = self.limit_of(&kind);
+        if used + adding <= limit {
+            Ok(())
+        } else {
+            Err(QuotaViolation {
+                message: format!("Quota {:?} exceeded: {} of {} used", kind, used, limit),
+                quota: kind,
+                limit,
+                used,
+            })
+        }
+    }
+}
+
+pub fn default_max_storage_bytes_per_test_case() -> u64 {
+    DEFAULT_MAX_STORAGE_BYTES_PER_TEST_CASE
+}
+
+/// Runs started by a customer on a UTC day, `id` being the day number since the epoch.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct QuotaUsage {
+    pub customer_id: String,
+    pub id: String,
+    pub runs: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum QuotaKind {
+    RunsPerDay,
+    ConcurrentRuns,
+    ActionsPerTestCase,
+    StoragePerTestCase,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct QuotaViolation {
+    pub message: String,
+    pub quota: QuotaKind,
+    pub limit: u64,
+    pub used: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct QuotaStatus {
+    pub quota: Quota,
+    pub runs_today: u64,
+    pub concurrent_runs: u64,
+}
+
+pub fn day_of(timestamp_millis: u64) -> String {
+    (timestamp_millis / DAY_MILLIS).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_quota_limits() {
+        let quota = Quota::builder()
+            .customer_id("cust1".to_string())
+            .max_runs_per_day(2)
+            .max_concurrent_runs(1)
+            .max_actions_per_test_case(10)
+            .build();
+
+        assert!(quota.check(QuotaKind::RunsPerDay, 1).is_ok());
+        assert_eq!(quota.check(QuotaKind::ConcurrentRuns, 1), Err(QuotaViolation {
+            message: "Quota ConcurrentRuns exceeded: 1 of 1 used".to_string(),
+            quota: QuotaKind::ConcurrentRuns,
+            limit: 1,
+            used: 1,
+        }));
+        assert!(quota.check_adding(QuotaKind::ActionsPerTestCase, 7, 3).is_ok());
+        assert_eq!(quota.check_adding(QuotaKind::ActionsPerTestCase, 7, 4).map_err(|violation| violation.used), Err(7));
+        let stored: Quota = serde_json::from_value(serde_json::json!({
+            "customer_id": "cust1",
+            "id": QUOTA_ID,
+            "max_runs_per_day": 2,
+            "max_concurrent_runs": 1,
+            "max_actions_per_test_case": 10,
+        })).unwrap();
+        assert_eq!(stored.limit_of(&QuotaKind::StoragePerTestCase), DEFAULT_MAX_STORAGE_BYTES_PER_TEST_CASE);
+        assert_eq!(day_of(0), day_of(DAY_MILLIS - 1));
+        assert_ne!(day_of(DAY_MILLIS - 1), day_of(DAY_MILLIS));
+    }
+}
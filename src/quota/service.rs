@@ -0,0 +1,101 @@
+use crate::api::AppError;
+use crate::persistence::repo::{current_timestamp, from_sdk_error, Table};
+use crate::quota::model::{day_of, Quota, QuotaUsage, QUOTA_ID};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct QuotaOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct QuotasTable();
+
+impl Table<Quota> for QuotasTable {
+    fn table_name() -> String {
+        "quotas".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Quota) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &Quota) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+pub(crate) struct QuotaUsagesTable();
+
+impl Table<QuotaUsage> for QuotaUsagesTable {
+    fn table_name() -> String {
+        "quota_usages".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &QuotaUsage) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &QuotaUsage) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl QuotaOperations {
+    pub async fn get(&self, customer_id: &String) -> Result<Quota, AppError> {
+        QuotasTable::get_item(self.client.clone(), customer_id.clone(), QUOTA_ID.to_string())
+            .await
+            .map(|quota| quota.unwrap_or_else(|| Quota::default_for(customer_id)))
+    }
+
+    pub async fn put(&self, quota: Quota) -> Result<Quota, AppError> {
+        QuotasTable::put_item(self.client.clone(), quota).await
+    }
+
+    /// Daily run counts of the customer, see [`QuotaUsage`].
+    pub async fn list_usages(&self, customer_id: &String) -> Result<Vec<QuotaUsage>, AppError> {
+        QuotaUsagesTable::list_all_items(self.client.clone(), customer_id.clone()).await
+    }
+
+    pub async fn runs_today(&self, customer_id: &String) -> Result<u64, AppError> {
+        QuotaUsagesTable::get_item(self.client.clone(), customer_id.clone(), day_of(current_timestamp()))
+            .await
+            .map(|usage| usage.map_or(0, |usage| usage.runs))
+    }
+
+    /// Counts one more run for today unless the customer already started `limit` runs, creating the
+    /// usage item on the first run of the day. Returns `false` when the daily quota is used up.
+    pub async fn reserve_run(&self, customer_id: &String, limit: u64) -> Result<bool, AppError> {
+        let result = self.client.update_item()
+            .table_name(QuotaUsagesTable::qualified_table_name())
+            .set_key(Some(QuotaUsagesTable::unique_key(customer_id.clone(), day_of(current_timestamp()))))
+            .update_expression("ADD #runs :one")
+            .condition_expression("attribute_not_exists(#runs) OR #runs < :limit")
+            .expression_attribute_names("#runs", "runs")
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .expression_attribute_values(":limit", AttributeValue::N(limit.to_string()))
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) => Ok(false),
+            Err(err) => Err(from_sdk_error(&QuotaUsagesTable::qualified_table_name(), err)),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::model::QueryResult;
+use crate::persistence::repo::Repository;
+use crate::revision::history::{diff_revisions, record_revision, restore_revision};
+use crate::revision::model::Revision;
+use crate::sync::model::SyncPlan;
+use crate::validation::AppJson;
+use axum::extract::{Path, Query, State};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+pub async fn create_revision(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<CreateRevisionPayload>,
+) -> Result<ApiResponse<Option<Revision>>, AppError> {
+    let result = record_revision(&repository, &"eren".to_string(), &test_case_id,
+                                 payload.message.unwrap_or("manual snapshot".to_string())).await;
+    ApiResponse::from(result)
+}
+
+pub async fn list_revisions(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    Query(params): Query<ListRevisionParams>,
+) -> Result<ApiResponse<QueryResult<Revision>>, AppError> {
+    let result = repository.revisions()
+        .list(&"eren".to_string(), &test_case_id, params.next_page_key, None)
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_revision(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Revision>, AppError> {
+    let result = repository.revisions()
+        .get(&"eren".to_string(), &test_case_id, &id)
+        .await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn diff_revision(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    Query(params): Query<DiffRevisionParams>,
+) -> Result<ApiResponse<SyncPlan>, AppError> {
+    let result = diff_revisions(&repository, &"eren".to_string(), &test_case_id, &id, params.to).await;
+    ApiResponse::from(result)
+}
+
+pub async fn restore(
+    Path((test_case_id, id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<SyncPlan>, AppError> {
+    let result = restore_revision(&repository, &"eren".to_string(), &test_case_id, &id).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CreateRevisionPayload {
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct ListRevisionParams {
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct DiffRevisionParams {
+    pub to: Option<String>,
+}
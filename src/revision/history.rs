@@ -0,0 +1,182 @@
+use crate::api::AppError;
+use crate::persistence::repo::Repository;
+use crate::revision::model::Revision;
+use crate::sync::engine::{apply_document, export_test_case, plan};
+use crate::sync::model::{SyncPlan, TestCaseDocument};
+use tracing::warn;
+
+/// Stores the current state of the test case as a new revision unless it is identical
+/// to the latest one.
+pub async fn record_revision(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    message: String,
+) -> Result<Option<Revision>, AppError> {
+    let document = export_test_case(repository, customer_id, test_case_id).await?;
+    store_revision(repository, customer_id, test_case_id, message, document).await
+}
+
+async fn store_revision(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    message: String,
+    document: TestCaseDocument,
+) -> Result<Option<Revision>, AppError> {
+    let latest = repository.revisions().latest(customer_id, test_case_id).await?;
+    if latest.is_some_and(|revision| revision.document == document) {
+        return Ok(None);
+    }
+    repository.revisions()
+        .create(Revision::builder()
+            .customer_id(customer_id.clone())
+            .test_case_id(test_case_id.clone())
+            .message(message)
+            .document(document)
+            .build())
+        .await
+        .map(Some)
+}
+
+/// Snapshot taken right before a mutation, failures are logged and do not block the change.
+pub async fn record_before_change(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    message: &str,
+) {
+    if let Err(err) = record_revision(repository, customer_id, test_case_id, format!("before {}", message)).await {
+        warn!("could not record revision of test case {} before {}: {:?}", test_case_id, message, err);
+    }
+}
+
+/// The test case as it was before a conditional write, stored as a revision by
+/// [`PendingRevision::record_if_ok`] only once the write went through, so a write rejected as stale
+/// leaves no revision behind.
+pub struct PendingRevision {
+    repository: Repository,
+    customer_id: String,
+    test_case_id: String,
+    message: String,
+    document: Option<TestCaseDocument>,
+}
+
+/// `record_before_change` for conditional writes, see [`PendingRevision`].
+pub async fn snapshot_before_change(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    message: &str,
+) -> PendingRevision {
+    let document = match export_test_case(repository, customer_id, test_case_id).await {
+        Ok(document) => Some(document),
+        Err(err) => {
+            warn!("could not export test case {} before {}: {:?}", test_case_id, message, err);
+            None
+        }
+    };
+    PendingRevision {
+        repository: repository.clone(),
+        customer_id: customer_id.clone(),
+        test_case_id: test_case_id.clone(),
+        message: message.to_string(),
+        document,
+    }
+}
+
+impl PendingRevision {
+    pub async fn record_if_ok<T>(self, result: &Result<T, AppError>) {
+        let Some(document) = self.document.filter(|_| result.is_ok()) else {
+            return;
+        };
+        let message = format!("before {}", self.message);
+        if let Err(err) = store_revision(&self.repository, &self.customer_id, &self.test_case_id, message, document).await {
+            warn!("could not record revision of test case {} before {}: {:?}", self.test_case_id, self.message, err);
+        }
+    }
+}
+
+/// Diffs `from_id` against `to_id`, or against the current state when `to_id` is not given.
+pub async fn diff_revisions(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    from_id: &String,
+    to_id: Option<String>,
+) -> Result<SyncPlan, AppError> {
+    let from = get_revision(repository, customer_id, test_case_id, from_id).await?;
+    let to_document = match to_id {
+        None => export_test_case(repository, customer_id, test_case_id).await?,
+        Some(id) => get_revision(repository, customer_id, test_case_id, &id).await?.document,
+    };
+    Ok(plan(&from.document, &to_document))
+}
+
+/// Brings the test case back to the state of the given revision. Output parameters of
+/// actions removed since then are not part of revisions and are not restored.
+pub async fn restore_revision(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    id: &String,
+) -> Result<SyncPlan, AppError> {
+    let revision = get_revision(repository, customer_id, test_case_id, id).await?;
+    record_revision(repository, customer_id, test_case_id, format!("before restoring revision {}", id)).await?;
+    apply_document(repository, customer_id, test_case_id, revision.document, false).await
+}
+
+async fn get_revision(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    id: &String,
+) -> Result<Revision, AppError> {
+    repository.revisions()
+        .get(customer_id, test_case_id, id)
+        .await?
+        .ok_or(AppError::NotFound(format!("Revision {} not found!", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::action::model::Action;
+    use crate::case::model::TestCase;
+    use crate::persistence::repo::init_logger;
+    use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+
+    #[tokio::test]
+    async fn records_diffs_and_restores_revisions() {
+        init_logger();
+        let repository = Repository::new().await;
+        let test_case = repository.test_cases()
+            .create(TestCase::builder()
+                .customer_id("cust1".to_string())
+                .name("Revisions".to_string())
+                .description("desc".to_string())
+                .build())
+            .await;
+        let (customer_id, test_case_id) = (test_case.customer_id.clone(), test_case.id.clone());
+
+        let first = record_revision(&repository, &customer_id, &test_case_id, "initial".to_string()).await.unwrap().unwrap();
+        let unchanged = record_revision(&repository, &customer_id, &test_case_id, "again".to_string()).await.unwrap();
+        assert!(unchanged.is_none());
+
+        let login = vec![Action { test_case_id: test_case_id.clone(), ..test_action("login", 0, "post", "/login") }];
+        let permit = guard_test_case_write(&repository, &customer_id, &test_case_id, TestCaseWrite { actions: &login, ..Default::default() })
+            .await
+            .unwrap();
+        repository.actions().batch_create(login, permit).await;
+        let sync_plan = diff_revisions(&repository, &customer_id, &test_case_id, &first.id, None).await.unwrap();
+        assert_eq!(sync_plan.actions.created.iter().map(|action| action.name.as_str()).collect::<Vec<_>>(), vec!["login"]);
+
+        let restore_plan = restore_revision(&repository, &customer_id, &test_case_id, &first.id).await.unwrap();
+        assert_eq!(restore_plan.actions.deleted.len(), 1);
+        let actions = repository.actions().list_all(customer_id.clone(), test_case_id.clone()).await.unwrap();
+        assert!(actions.is_empty());
+        let latest = repository.revisions().latest(&customer_id, &test_case_id).await.unwrap().unwrap();
+        assert_eq!(latest.message, format!("before restoring revision {}", first.id));
+    }
+}
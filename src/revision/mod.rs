@@ -0,0 +1,4 @@
+pub mod model;
+pub mod service;
+pub mod api;
+pub mod history;
@@ -0,0 +1,36 @@
+use crate::persistence::repo::current_timestamp;
+use crate::sync::model::TestCaseDocument;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Builder, ToSchema)]
+pub struct Revision {
+    pub customer_id: String,
+    pub test_case_id: String,
+    #[builder(default = new_revision_id())]
+    pub id: String,
+    pub message: String,
+    pub document: TestCaseDocument,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+/// Revision ids start with the creation timestamp so that they sort chronologically.
+fn new_revision_id() -> String {
+    format!("{:013}-{}", current_timestamp(), uuid::Uuid::new_v4())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn revision_ids_sort_by_creation_time() {
+        let first = new_revision_id();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = new_revision_id();
+        assert!(first < second);
+        assert_eq!(first.split_once('-').map(|(timestamp, _)| timestamp.len()), Some(13));
+    }
+}
@@ -0,0 +1,92 @@
+use crate::api::AppError;
+use crate::persistence::model::{PageKey, QueryResult};
+use crate::persistence::repo::{build_composite_key, Table};
+use crate::revision::model::Revision;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+pub struct RevisionOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct RevisionsTable();
+
+impl Table<Revision> for RevisionsTable {
+    fn table_name() -> String {
+        "revisions".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id#test_case_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Revision) -> (String, AttributeValue) {
+        Self::partition_key(build_composite_key(vec![
+            entity.customer_id.clone(),
+            entity.test_case_id.clone(),
+        ]))
+    }
+
+    fn sort_key_from_entity(entity: &Revision) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+
+    fn ordering(e1: &Revision, e2: &Revision) -> Ordering {
+        e2.id.cmp(&e1.id)
+    }
+}
+
+impl RevisionOperations {
+    pub async fn create(&self, revision: Revision) -> Result<Revision, AppError> {
+        RevisionsTable::put_item(self.client.clone(), revision).await
+    }
+
+    pub async fn get(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        id: &String,
+    ) -> Result<Option<Revision>, AppError> {
+        RevisionsTable::get_item(
+            self.client.clone(),
+            build_composite_key(vec![customer_id.clone(), test_case_id.clone()]),
+            id.clone(),
+        ).await
+    }
+
+    pub async fn list(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        next_page_key: Option<String>,
+        limit: Option<i32>,
+    ) -> Result<QueryResult<Revision>, AppError> {
+        let result = RevisionsTable::query_builder(self.client.clone())
+            .scan_index_forward(false)
+            .expression_attribute_names("#pk", RevisionsTable::partition_key_name())
+            .expression_attribute_values(":pk", AttributeValue::S(build_composite_key(vec![customer_id.clone(), test_case_id.clone()])))
+            .key_condition_expression("#pk = :pk")
+            .limit(limit.unwrap_or(25))
+            .set_exclusive_start_key(
+                next_page_key.map(|next| PageKey::from_next_page_key(&next).to_attribute_values()),
+            )
+            .send().await;
+        RevisionsTable::from_query_result(result)
+    }
+
+    pub async fn delete_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Revision>, AppError> {
+        RevisionsTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
+    pub async fn latest(&self, customer_id: &String, test_case_id: &String) -> Result<Option<Revision>, AppError> {
+        self.list(customer_id, test_case_id, None, Some(1)).await
+            .map(|mut query_result| query_result.items.pop())
+    }
+}
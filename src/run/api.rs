@@ -1,22 +1,71 @@
 use crate::api::{ApiResponse, AppError, AppState};
-use crate::persistence::model::QueryResult;
-use crate::run::execution::{run_test, RunTestCaseCommand};
-use crate::run::model::Run;
-use axum::extract::{Path, State};
+use crate::config::app_config;
+use crate::persistence::model::{QueryResult, SortOrder, View};
+use crate::run::execution::{inspect_context, resume_run, run_test, run_test_and_wait, RunTestCaseCommand};
+use crate::run::model::{CreatedResource, Run, RunOverview, RunStatus, RunTestCasePayload, RunVerdict, Verdict};
+use crate::run::replay_har::build_run_har;
+use crate::run::resources::{list_resources, teardown_resources};
+use crate::run::service::RunFilter;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use utoipa::IntoParams;
 
 pub async fn run_test_case(
     Path(id): Path<String>,
     State(app_state): State<AppState>,
+    Query(params): Query<RunTestCaseParams>,
+    payload: Option<Json<RunTestCasePayload>>,
+) -> Result<Response, AppError> {
+    let command = RunTestCaseCommand {
+        customer_id: "eren".to_string(),
+        test_case_id: id,
+        mock: params.mock.unwrap_or(false),
+        auth_provider_overrides: payload.as_ref()
+            .map(|Json(payload)| payload.auth_provider_overrides.clone())
+            .unwrap_or_default(),
+        fault_injection: payload.as_ref().and_then(|Json(payload)| payload.fault_injection.clone()),
+        environment_id: payload.as_ref().and_then(|Json(payload)| payload.environment_id.clone()),
+        selection: payload.as_ref().map(|Json(payload)| payload.selection.clone()).unwrap_or_default(),
+        seed_context: payload.as_ref().map(|Json(payload)| payload.seed_context.clone()).unwrap_or_default(),
+        trigger: payload.as_ref().map(|Json(payload)| payload.trigger.clone()).unwrap_or_default(),
+        labels: payload.map(|Json(payload)| payload.labels).unwrap_or_default(),
+    };
+    if !params.wait.unwrap_or(false) {
+        let run = run_test(app_state.repository, app_state.api_client, command).await?;
+        return Ok(ApiResponse(run).into_response());
+    }
+    let timeout = Duration::from_secs(params.timeout
+        .unwrap_or(app_config().default_wait_timeout_secs)
+        .min(app_config().max_wait_timeout_secs));
+    let verdict = run_test_and_wait(app_state.repository, app_state.api_client, command, timeout).await?;
+    Ok(verdict_response(verdict))
+}
+
+fn verdict_response(verdict: RunVerdict) -> Response {
+    let status = match verdict.verdict {
+        Verdict::Passed => StatusCode::OK,
+        Verdict::Failed => StatusCode::UNPROCESSABLE_ENTITY,
+        Verdict::Errored => StatusCode::BAD_GATEWAY,
+        Verdict::TimedOut => StatusCode::GATEWAY_TIMEOUT,
+    };
+    let mut response = ApiResponse(verdict).into_response();
+    if response.status() == StatusCode::OK {
+        *response.status_mut() = status;
+    }
+    response
+}
+
+pub async fn resume(
+    Path(run_id): Path<String>,
+    State(app_state): State<AppState>,
 ) -> Result<ApiResponse<Run>, AppError> {
-    let result = run_test(
-        app_state.repository,
-        app_state.api_client,
-        RunTestCaseCommand {
-            customer_id: "eren".to_string(),
-            test_case_id: id,
-        },
-    )
-        .await;
+    let result = resume_run(app_state.repository, app_state.api_client, &"eren".to_string(), &run_id).await;
     ApiResponse::from(result)
 }
 
@@ -33,14 +82,130 @@ pub async fn get_run(
     ApiResponse::from_option(result)
 }
 
+pub async fn get_run_context(
+    Path(run_id): Path<String>,
+    State(app_state): State<AppState>,
+    Query(params): Query<RunContextParams>,
+) -> Result<ApiResponse<Value>, AppError> {
+    let result = inspect_context(&app_state.repository, &"eren".to_string(), &run_id, params.after_action).await;
+    ApiResponse::from(result)
+}
+
+/// What the run's actions created upstream.
+pub async fn get_run_resources(
+    Path(run_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<ApiResponse<Vec<CreatedResource>>, AppError> {
+    let result = list_resources(&app_state.repository, &"eren".to_string(), &run_id).await;
+    ApiResponse::from(result)
+}
+
+/// Deletes what the run created with the teardown requests of its resources.
+pub async fn delete_run_resources(
+    Path(run_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<ApiResponse<Vec<CreatedResource>>, AppError> {
+    let result = teardown_resources(&app_state.repository, &app_state.api_client, &"eren".to_string(), &run_id).await;
+    ApiResponse::from(result)
+}
+
+/// The run's requests and responses as a HAR file to download.
+pub async fn get_run_har(
+    Path(run_id): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<Response, AppError> {
+    let har = build_run_har(&app_state.repository, &"eren".to_string(), &run_id).await?;
+    let mut response = ApiResponse(har).into_response();
+    if let Ok(disposition) = HeaderValue::from_str(&format!("attachment; filename=\"run-{}.har\"", run_id)) {
+        response.headers_mut().insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    Ok(response)
+}
+
 pub async fn list_runs(
     Path(test_case_id): Path<String>,
     State(app_state): State<AppState>,
+    Query(params): Query<ListRunsParams>,
 ) -> Result<ApiResponse<QueryResult<Run>>, AppError> {
     let result = app_state
         .repository
         .runs()
-        .list(&"eren".to_string(), &test_case_id)
+        .list(&"eren".to_string(), &test_case_id, params.view, params.order.unwrap_or(SortOrder::Desc), params.filter(), params.next_page_key)
         .await;
     ApiResponse::from(result)
-}
\ No newline at end of file
+}
+
+/// Recent runs of every test case of the customer, newest first.
+pub async fn list_recent_runs(
+    State(app_state): State<AppState>,
+    Query(params): Query<RecentRunsParams>,
+) -> Result<ApiResponse<QueryResult<RunOverview>>, AppError> {
+    let customer_id = "eren".to_string();
+    let runs = app_state.repository.runs()
+        .list_recent(&customer_id, params.status, params.since,
+                     params.limit.unwrap_or(DEFAULT_RECENT_RUNS_LIMIT).clamp(1, MAX_RECENT_RUNS_LIMIT), params.next_page_key)
+        .await?;
+    let test_case_ids: HashSet<String> = runs.items.iter().map(|run| run.test_case_id.clone()).collect();
+    let names: HashMap<String, String> = app_state.repository.test_cases()
+        .batch_get(&customer_id, test_case_ids.into_iter().collect())
+        .await?
+        .into_iter()
+        .map(|test_case| (test_case.id, test_case.name))
+        .collect();
+    ApiResponse::from(Ok(QueryResult {
+        items: runs.items.into_iter()
+            .map(|run| RunOverview { test_case_name: names.get(&run.test_case_id).cloned(), run })
+            .collect(),
+        next_page_key: runs.next_page_key,
+    }))
+}
+
+const DEFAULT_RECENT_RUNS_LIMIT: i32 = 25;
+const MAX_RECENT_RUNS_LIMIT: i32 = 100;
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct RecentRunsParams {
+    pub status: Option<RunStatus>,
+    /// Only runs started at or after this timestamp in milliseconds.
+    pub since: Option<u64>,
+    pub limit: Option<i32>,
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct ListRunsParams {
+    #[serde(default)]
+    pub view: View,
+    pub order: Option<SortOrder>,
+    pub next_page_key: Option<String>,
+    /// Trigger type: manual, schedule, ci or api_key.
+    pub trigger: Option<String>,
+    /// Comma separated labels a run must all carry.
+    pub labels: Option<String>,
+}
+
+impl ListRunsParams {
+    fn filter(&self) -> RunFilter {
+        RunFilter {
+            trigger: self.trigger.clone(),
+            labels: self.labels.iter()
+                .flat_map(|labels| labels.split(','))
+                .map(|label| label.trim().to_string())
+                .filter(|label| !label.is_empty())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct RunTestCaseParams {
+    pub wait: Option<bool>,
+    pub mock: Option<bool>,
+    pub timeout: Option<u64>,
+}
+
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct RunContextParams {
+    pub after_action: Option<String>,
+}
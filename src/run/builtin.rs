@@ -0,0 +1,84 @@
+use crate::run::model::Run;
+use aws_sdk_dynamodb::primitives::{DateTime, DateTimeFormat};
+use serde_json::{json, Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Context key of the current time, read as `$.now.iso`, `$.now.epoch_ms` or `$.now.epoch_secs`.
+pub const NOW_CONTEXT_KEY: &str = "now";
+/// Context key of the run itself, read as `$.run.id`, `$.run.started_at` (epoch milliseconds),
+/// `$.run.started_at_iso` and so on.
+pub const RUN_CONTEXT_KEY: &str = "run";
+
+/// Adds the built-in values to the context. Values the run was seeded with under the same keys
+/// are left in place, e.g. a `now` pinned to replay a run at a fixed time.
+pub fn seed_builtins(context: &mut Map<String, Value>, run: &Run, now: SystemTime) {
+    if !run.seed_context.contains_key(RUN_CONTEXT_KEY) {
+        context.insert(RUN_CONTEXT_KEY.to_string(), run_value(run));
+    }
+    refresh_now(context, run, now);
+}
+
+/// Moves `now` forward; the executor calls it before every action and before checking assertions.
+pub fn refresh_now(context: &mut Map<String, Value>, run: &Run, now: SystemTime) {
+    if !run.seed_context.contains_key(NOW_CONTEXT_KEY) {
+        context.insert(NOW_CONTEXT_KEY.to_string(), now_value(now));
+    }
+}
+
+fn now_value(now: SystemTime) -> Value {
+    let epoch_ms = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    json!({
+        "iso": iso_8601(epoch_ms),
+        "epoch_ms": epoch_ms,
+        "epoch_secs": epoch_ms / 1000,
+    })
+}
+
+fn run_value(run: &Run) -> Value {
+    json!({
+        "id": run.id,
+        "test_case_id": run.test_case_id,
+        "started_at": run.started_at,
+        "started_at_iso": iso_8601(run.started_at),
+        "environment_id": run.environment_id,
+        "labels": run.labels,
+    })
+}
+
+/// Formats epoch milliseconds like `2024-03-01T12:30:00.25Z`.
+pub fn iso_8601(epoch_ms: u64) -> String {
+    DateTime::from_millis(epoch_ms as i64)
+        .fmt(DateTimeFormat::DateTime)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::run::model::RunStatus;
+    use std::time::Duration;
+
+    #[test]
+    fn builtins_are_seeded_unless_pinned() {
+        let run = Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("run1".to_string())
+            .status(RunStatus::InProgress)
+            .started_at(1_709_296_200_000)
+            .build();
+        let mut context = Map::new();
+
+        seed_builtins(&mut context, &run, UNIX_EPOCH + Duration::from_millis(1_709_296_201_250));
+
+        assert_eq!(context[RUN_CONTEXT_KEY]["id"], json!("run1"));
+        assert_eq!(context[RUN_CONTEXT_KEY]["started_at_iso"], json!("2024-03-01T12:30:00Z"));
+        assert_eq!(context[NOW_CONTEXT_KEY], json!({"iso": "2024-03-01T12:30:01.25Z", "epoch_ms": 1_709_296_201_250u64, "epoch_secs": 1_709_296_201}));
+
+        let pinned = Run { seed_context: Map::from_iter([(NOW_CONTEXT_KEY.to_string(), json!({"iso": "2024-01-01T00:00:00Z"}))]), ..run };
+        let mut context = pinned.seed_context.clone();
+        seed_builtins(&mut context, &pinned, SystemTime::now());
+        assert_eq!(context[NOW_CONTEXT_KEY], json!({"iso": "2024-01-01T00:00:00Z"}));
+        assert_eq!(context[RUN_CONTEXT_KEY]["test_case_id"], json!("tc1"));
+    }
+}
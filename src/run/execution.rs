@@ -1,30 +1,66 @@
-use crate::action::model::Action;
+use crate::action::model::{Action, ActionType, BudgetViolation};
+use crate::constant::model::seed_constants;
 use crate::action_execution::model::ActionExecution;
 use crate::api::AppError;
+use crate::artifact::store::offload_response_body;
 use crate::assertion::check::check_assertion;
-use crate::assertion::model::AssertionResult;
-use crate::auth::model::ListAuthProvidersRequest;
+use crate::assertion::model::{Assertion, AssertionResult, ComparisonType};
+use crate::auth::model::AuthenticationProvider;
+use crate::case::model::{ConcurrencyPolicy, TestCase};
 use crate::http::{
-    ApiClient, Endpoint, HttpError, HttpMethod, HttpRequest, HttpResult, ReqBody, ReqParam,
+    ApiClient, Endpoint, HttpError, HttpMethod, HttpRequest, HttpResult, percent_encode, RedirectHop, ReqBody, ReqParam, ResBody,
 };
+use crate::environment::remap::remap_url;
+use crate::header_rule::injection::apply_header_rules;
 use crate::json_path::model::Expression;
+use crate::metrics::registry::metrics;
+use crate::mock::recorded::recorded_result;
+use crate::notification::dispatch::{notify, notify_expiring_auth_providers, run_notification};
 use crate::json_path::utils::{evaluate_expression, evaluate_value, reverse_flatten_all};
 use crate::parameter::model::{Parameter, ParameterIn};
 use crate::persistence::repo::Repository;
-use crate::run::model::{Run, RunStatus};
+use crate::quota::model::QuotaKind;
+use crate::run::builtin::{refresh_now, seed_builtins};
+use crate::run::hooks::{apply_request_hooks, apply_response_hooks};
+use crate::run::lock::LOCK_RENEW_INTERVAL;
+use crate::run::prefetch::RunResources;
+use crate::run::resources::track_resource;
+use crate::run::retention::RetentionPlan;
+use crate::run::setup::{resolve_setup, SETUP_CONTEXT_KEY};
+use crate::run::selection::{apply_selection, is_checkable, select_actions, teardown_last, ActionSelection};
+use crate::run::fault::{FaultInjectingClient, FaultInjection};
+use crate::run::model::{compute_verdict, Run, RunStatus, RunTrigger, RunVerdict, Verdict};
+use crate::sensitive_header::model::SensitiveHeaderPolicy;
+use crate::snapshot::compare::check_snapshot_assertion;
+use crate::snapshot::model::Snapshot;
+use crate::telemetry::exporter::{export_spans, telemetry_config};
+use crate::telemetry::model::{SpanContext, SpanKind, SpanRecord};
 use aws_sdk_dynamodb::config::retry::ShouldAttempt::No;
 use aws_sdk_dynamodb::primitives::DateTime;
 use aws_sdk_dynamodb::primitives::DateTimeFormat::DateTimeWithOffset;
 use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{error, info};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tracing::{error, info, instrument, warn, Instrument};
 use uuid::Uuid;
 
+const RUN_LOCK_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_SEED_CONTEXT_BYTES: usize = 64 * 1024;
+
 pub struct RunTestCaseCommand {
     pub customer_id: String,
     pub test_case_id: String,
+    pub mock: bool,
+    pub auth_provider_overrides: HashMap<String, String>,
+    pub fault_injection: Option<FaultInjection>,
+    pub environment_id: Option<String>,
+    pub selection: ActionSelection,
+    pub seed_context: Map<String, Value>,
+    pub trigger: RunTrigger,
+    pub labels: Vec<String>,
 }
 
 pub async fn run_test(
@@ -32,6 +68,30 @@ pub async fn run_test(
     api_client: Arc<ApiClient>,
     command: RunTestCaseCommand,
 ) -> Result<Run, AppError> {
+    start_run(repo, api_client, command)
+        .await
+        .map(|(run, _)| run)
+}
+
+pub async fn run_test_and_wait(
+    repo: Arc<Repository>,
+    api_client: Arc<ApiClient>,
+    command: RunTestCaseCommand,
+    timeout: Duration,
+) -> Result<RunVerdict, AppError> {
+    let (run, handle) = start_run(repo, api_client, command).await?;
+    match tokio::time::timeout(timeout, handle).await {
+        Ok(Ok(finished_run)) => Ok(RunVerdict::from_finished_run(finished_run)),
+        Ok(Err(err)) => Err(AppError::Internal(format!("Run {} could not complete: {}", run.id, err))),
+        Err(_) => Ok(RunVerdict::timed_out(run)),
+    }
+}
+
+async fn start_run(
+    repo: Arc<Repository>,
+    api_client: Arc<ApiClient>,
+    command: RunTestCaseCommand,
+) -> Result<(Run, JoinHandle<Run>), AppError> {
     let get_test_case_result = repo
         .test_cases()
         .get(command.customer_id.clone(), command.test_case_id.clone())
@@ -44,53 +104,88 @@ pub async fn run_test(
                 }
                 Some(test_case) => {
                     info!("Running case {}", test_case.id);
+                    validate_auth_provider_overrides(&repo, &command).await?;
+                    validate_seed_context(&command.seed_context)?;
+                    validate_labels(&command.labels)?;
+                    if let Some(environment_id) = &command.environment_id {
+                        repo.environments()
+                            .get(&command.customer_id, environment_id)
+                            .await?
+                            .ok_or(AppError::NotFound("Environment not found!".to_string()))?;
+                    }
+                    if let Some(fault_injection) = &command.fault_injection {
+                        fault_injection.validate().map_err(AppError::Validation)?;
+                    }
+                    let selected_action_ids = select_run_actions(&repo, &command).await?;
+                    let mut seed_context = command.seed_context.clone();
+                    let constants = repo.constants()
+                        .resolve(&command.customer_id, &command.test_case_id)
+                        .await?;
+                    seed_constants(&mut seed_context, constants);
+                    let setup_run_id = match resolve_setup(&repo, &api_client, &test_case, &command).await? {
+                        Some((setup_run_id, setup_context)) => {
+                            seed_context.insert(SETUP_CONTEXT_KEY.to_string(), Value::Object(setup_context));
+                            Some(setup_run_id)
+                        }
+                        None => None,
+                    };
+                    let run_id = Uuid::new_v4().to_string();
+                    let guarded = test_case.concurrency_policy != ConcurrencyPolicy::Allow;
+                    let status = if guarded {
+                        acquire_run_lock(&repo, &test_case, &run_id).await?
+                    } else {
+                        RunStatus::InProgress
+                    };
+                    if let Err(err) = reserve_run_quota(&repo, &command.customer_id).await {
+                        if guarded && status == RunStatus::InProgress {
+                            release_run_lock(&repo, &test_case.customer_id, &test_case.id, &run_id).await;
+                        }
+                        return Err(err);
+                    }
+                    let run_span_context = SpanContext::root();
                     let run = repo.runs()
                         .create(Run::builder()
                             .customer_id(command.customer_id.clone())
                             .test_case_id(command.test_case_id.clone())
-                            .status(RunStatus::InProgress)
+                            .id(run_id)
+                            .status(status)
                             .started_at(current_timestamp())
+                            .trace_id(run_span_context.trace_id.clone())
+                            .mock(command.mock)
+                            .auth_provider_overrides(command.auth_provider_overrides.clone())
+                            .maybe_fault_injection(command.fault_injection.clone())
+                            .maybe_environment_id(command.environment_id.clone())
+                            .maybe_selection((!command.selection.is_empty()).then(|| command.selection.clone()))
+                            .seed_context(seed_context)
+                            .maybe_setup_run_id(setup_run_id)
+                            .trigger(command.trigger.clone())
+                            .labels(command.labels.clone())
                             .build())
                         .await;
+                    metrics().runs.inc(&["started"]);
 
-                    let cloned_run = run.clone();
                     let repo_cloned = Arc::clone(&repo);
                     let api_client_cloned = Arc::clone(&api_client);
-                    tokio::spawn(async move {
-                        let mut context = Map::new();
-                        let mut actions = &mut repo_cloned
-                            .clone().actions()
-                            .list(test_case.customer_id, test_case.id, None)
+                    let cloned_run = run.clone();
+                    let handle = tokio::spawn(async move {
+                        if cloned_run.status == RunStatus::Queued {
+                            wait_for_run_lock(&repo_cloned, &cloned_run).await;
+                        }
+                        let mut actions = repo_cloned
+                            .actions()
+                            .list_all(test_case.customer_id.clone(), test_case.id.clone())
                             .await
-                            .unwrap().items;
+                            .unwrap();
                         actions.sort();
-                        for action in actions {
-                            execute(
-                                repo_cloned.clone(),
-                                api_client_cloned.clone(),
-                                &cloned_run,
-                                &action,
-                                &mut context)
-                                .await;
+                        if let Some(selected_action_ids) = selected_action_ids {
+                            actions.retain(|action| selected_action_ids.contains(&action.id));
                         }
-                        let assertions = repo_cloned.assertions()
-                            .list(&cloned_run.customer_id, &cloned_run.test_case_id).await
-                            .unwrap().items;
-                        let assertion_context = Value::Object(context.clone());
-                        let assertion_results: Vec<AssertionResult> = assertions.iter()
-                            .map(|assertion| { check_assertion(assertion, &assertion_context) })
-                            .collect();
-                        repo_cloned.runs()
-                            .update(
-                                &cloned_run.customer_id,
-                                &cloned_run.test_case_id,
-                                &cloned_run.id,
-                                &RunStatus::Finished,
-                                assertion_results,
-                            )
-                            .await;
+                        let mut actions = teardown_last(actions);
+                        resolve_action_urls(&mut actions, &test_case);
+                        let seed_context = cloned_run.seed_context.clone();
+                        complete_run(repo_cloned, api_client_cloned, cloned_run, test_case, actions, seed_context, run_span_context).await
                     });
-                    Ok(run)
+                    Ok((run, handle))
                 }
             }
         }
@@ -98,43 +193,623 @@ pub async fn run_test(
     }
 }
 
+pub async fn resume_run(
+    repo: Arc<Repository>,
+    api_client: Arc<ApiClient>,
+    customer_id: &String,
+    run_id: &String,
+) -> Result<Run, AppError> {
+    let run = repo.runs()
+        .find_by_id(customer_id, run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    if run.status != RunStatus::Finished {
+        return Err(AppError::Conflict(format!("Run {} is not finished yet", run.id)));
+    }
+    let test_case = repo.test_cases()
+        .get(run.customer_id.clone(), run.test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let mut actions = repo.actions()
+        .list_all(run.customer_id.clone(), run.test_case_id.clone())
+        .await?;
+    actions.sort();
+    if let Some(selection) = &run.selection {
+        actions = apply_selection(&actions, selection)?;
+    }
+    let mut actions = teardown_last(actions);
+    resolve_action_urls(&mut actions, &test_case);
+    let executions = repo.action_executions()
+        .list_with_bodies(&run.customer_id, &run.test_case_id, &run.id)
+        .await?;
+    let (executed_context, resume_index) = rebuild_context(&actions, &executions);
+    let mut context = run.seed_context.clone();
+    context.extend(executed_context);
+    if resume_index >= actions.len() {
+        return Err(AppError::Validation(format!("Run {} has no failed or unexecuted actions to resume", run.id)));
+    }
+    let guarded = test_case.concurrency_policy != ConcurrencyPolicy::Allow;
+    if guarded && !repo.run_locks().acquire(&run.customer_id, &run.test_case_id, &run.id).await? {
+        return Err(AppError::Conflict(format!("Another run of test case {} is in progress", test_case.id)));
+    }
+    if let Err(err) = reserve_run_quota(&repo, &run.customer_id).await {
+        if guarded {
+            release_run_lock(&repo, &run.customer_id, &run.test_case_id, &run.id).await;
+        }
+        return Err(err);
+    }
+    info!("resuming run {} from action: {}", run.id, actions[resume_index].name);
+    let resumed_run = repo.runs()
+        .update_status(&run.customer_id, &run.test_case_id, &run.id, &RunStatus::InProgress)
+        .await?;
+    metrics().runs.inc(&["resumed"]);
+    let run_span_context = SpanContext::root();
+    let remaining_actions = actions.split_off(resume_index);
+    tokio::spawn(complete_run(repo, api_client, resumed_run.clone(), test_case, remaining_actions, context, run_span_context));
+    Ok(resumed_run)
+}
+
+/// Ids of the actions a partial run executes, none for a full run.
+async fn select_run_actions(repository: &Repository, command: &RunTestCaseCommand) -> Result<Option<HashSet<String>>, AppError> {
+    if command.selection.is_empty() {
+        return Ok(None);
+    }
+    let mut actions = repository.actions()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?;
+    actions.sort();
+    let parameters = repository.parameters()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?;
+    let seeded: HashSet<String> = command.seed_context.keys().cloned().collect();
+    let selected = select_actions(&actions, &parameters, &command.selection, &seeded)?;
+    Ok(Some(selected.into_iter().map(|action| action.id).collect()))
+}
+
+/// Makes relative action URLs absolute up front so requests, auth provider lookups and URL
+/// rewrites all see the URL that is actually called.
+fn resolve_action_urls(actions: &mut [Action], test_case: &TestCase) {
+    for action in actions.iter_mut() {
+        action.url = action.absolute_url(test_case.base_url.as_deref());
+    }
+}
+
+/// Reconstructs the expression context of a run as it was right after `after_action` executed,
+/// or after the whole run when no action is given.
+pub async fn inspect_context(
+    repo: &Repository,
+    customer_id: &String,
+    run_id: &String,
+    after_action: Option<String>,
+) -> Result<Value, AppError> {
+    let run = repo.runs()
+        .find_by_id(customer_id, run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let mut actions = repo.actions()
+        .list_all(run.customer_id.clone(), run.test_case_id.clone())
+        .await?;
+    actions.sort();
+    let actions = teardown_last(actions);
+    let executions = repo.action_executions()
+        .list_with_bodies(&run.customer_id, &run.test_case_id, &run.id)
+        .await?;
+    context_after(&run.seed_context, &actions, &executions, after_action.as_deref())
+        .map(Value::Object)
+        .ok_or(AppError::NotFound(format!("Action {} not found!", after_action.unwrap_or_default())))
+}
+
+/// Builds the request an action sends, fed with the context the run had right before the action
+/// executed. Without a run the action is resolved against the constants alone. Headers are returned
+/// as they go over the wire, masked unless `include_secrets` is set.
+pub async fn preview_request(
+    repo: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    action_id: &String,
+    run_id: Option<&String>,
+    include_secrets: bool,
+) -> Result<(HttpRequest, Vec<(String, String)>), AppError> {
+    let test_case = repo.test_cases()
+        .get(customer_id.clone(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let mut actions = repo.actions()
+        .list_all(customer_id.clone(), test_case_id.clone())
+        .await?;
+    actions.sort();
+    let mut actions = teardown_last(actions);
+    resolve_action_urls(&mut actions, &test_case);
+    let index = actions.iter()
+        .position(|action| &action.id == action_id)
+        .ok_or(AppError::NotFound("Action not found!".to_string()))?;
+    if actions[index].action_type == ActionType::Sleep {
+        return Err(AppError::Validation("Sleep actions do not send a request!".to_string()));
+    }
+    let (run, mut context) = match run_id {
+        Some(run_id) => {
+            let run = repo.runs()
+                .find_by_id(customer_id, run_id)
+                .await?
+                .filter(|run| &run.test_case_id == test_case_id)
+                .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+            let executions = repo.action_executions()
+                .list_with_bodies(&run.customer_id, &run.test_case_id, &run.id)
+                .await?;
+            let context = context_after(&run.seed_context, &actions[..index], &executions, None).unwrap_or_default();
+            (run, context)
+        }
+        None => {
+            let run = Run::builder()
+                .customer_id(customer_id.clone())
+                .test_case_id(test_case_id.clone())
+                .status(RunStatus::Finished)
+                .started_at(current_timestamp())
+                .build();
+            let mut context = Map::new();
+            seed_constants(&mut context, repo.constants().resolve(customer_id, test_case_id).await?);
+            (run, context)
+        }
+    };
+    seed_builtins(&mut context, &run, SystemTime::now());
+    let resources = RunResources::load(repo, &run, std::slice::from_ref(&actions[index])).await?;
+    let (http_request, _) = build_http_request(&resources, &actions[index], &Value::Object(context));
+    let headers = if include_secrets {
+        resolve_wire_headers(&http_request)
+    } else {
+        resolve_request_headers_from_request(&http_request, &resources.sensitive_headers)
+    };
+    Ok((http_request, headers))
+}
+
+/// Replays the latest execution of every action up to `after_action` on top of the seed context
+/// the way `execute` fed the context: recorded bodies go through the post-response hooks again,
+/// failed executions contribute a null output and actions that never ran are absent.
+pub fn context_after(seed_context: &Map<String, Value>, actions: &[Action], executions: &[ActionExecution], after_action: Option<&str>) -> Option<Map<String, Value>> {
+    let mut context = seed_context.clone();
+    for action in actions {
+        let latest_execution = executions.iter()
+            .filter(|execution| execution.action_id == action.id)
+            .max_by_key(|execution| execution.started_at);
+        if let Some(execution) = latest_execution {
+            let output = match execution.error {
+                None => apply_response_hooks(&execution.post_response_hooks, execution.response_body.clone().unwrap_or(Value::Null), &context).0,
+                Some(_) => Value::Null,
+            };
+            context.insert(action.name.clone(), build_action_context(
+                output,
+                execution.request_body.clone().unwrap_or(Value::Null),
+                execution.status_code,
+                &execution.redirects,
+                &execution.response_headers,
+            ));
+        }
+        if after_action == Some(action.name.as_str()) {
+            return Some(context);
+        }
+    }
+    after_action.is_none().then_some(context)
+}
+
+/// Rebuilds the evaluation context from the latest execution of every action that succeeded,
+/// returning it with the index of the first action that failed or was never executed.
+fn rebuild_context(actions: &[Action], executions: &[ActionExecution]) -> (Map<String, Value>, usize) {
+    let mut context = Map::new();
+    for (index, action) in actions.iter().enumerate() {
+        if action.action_type == ActionType::Sleep {
+            continue;
+        }
+        let latest_execution = executions.iter()
+            .filter(|execution| execution.action_id == action.id)
+            .max_by_key(|execution| execution.started_at);
+        match latest_execution {
+            Some(execution) if execution.error.is_none() => {
+                let (output, _) = apply_response_hooks(&execution.post_response_hooks, execution.response_body.clone().unwrap_or(Value::Null), &context);
+                context.insert(action.name.clone(), build_action_context(
+                    output,
+                    execution.request_body.clone().unwrap_or(Value::Null),
+                    execution.status_code,
+                    &execution.redirects,
+                    &execution.response_headers,
+                ));
+            }
+            _ => return (context, index),
+        }
+    }
+    (context, actions.len())
+}
+
+/// Redirect hops are only exposed when recorded, so expressions like `$.login_0.redirects[0].location` can reach them.
+/// Response headers land under `output_headers` by lowercased name; repeated headers are joined with a comma.
+/// The status code is kept under `status`, 0 when no response was received.
+fn build_action_context(output: Value, input: Value, status_code: u16, redirects: &[RedirectHop], headers: &[(String, String)]) -> Value {
+    let mut temp = Map::new();
+    temp.insert("output".to_string(), output);
+    temp.insert("input".to_string(), input);
+    temp.insert("status".to_string(), Value::from(status_code));
+    if !redirects.is_empty() {
+        temp.insert("redirects".to_string(), serde_json::to_value(redirects).unwrap());
+    }
+    if !headers.is_empty() {
+        let mut output_headers: Map<String, Value> = Map::new();
+        for (name, value) in headers {
+            let name = name.to_lowercase();
+            let joined = match output_headers.get(&name).and_then(Value::as_str) {
+                Some(previous) => format!("{}, {}", previous, value),
+                None => value.clone(),
+            };
+            output_headers.insert(name, Value::String(joined));
+        }
+        temp.insert("output_headers".to_string(), Value::Object(output_headers));
+    }
+    Value::Object(temp)
+}
+
+/// Executes the actions in order, which puts teardown actions last; a failing teardown action is
+/// only logged and left out of the verdict. What the actions read from the store is loaded up
+/// front, failing the run as errored when it cannot be, and entries the retention plan no longer needs are dropped from the context as the run
+/// goes. The built-in `run` and `now` values are added to the context, `now` being refreshed
+/// before every action and before assertions are checked. Resources the actions create are added
+/// to the run's manifest.
+#[instrument(skip_all, fields(run_id = %run.id, test_case_id = %run.test_case_id))]
+async fn complete_run(
+    repo: Arc<Repository>,
+    api_client: Arc<ApiClient>,
+    run: Run,
+    test_case: TestCase,
+    actions: Vec<Action>,
+    mut context: Map<String, Value>,
+    run_span_context: SpanContext,
+) -> Run {
+    let run_span = SpanRecord::start("run".to_string(), run_span_context.clone(), None, SpanKind::Internal)
+        .attribute("parroton.test_case_id", run.test_case_id.clone())
+        .attribute("parroton.run_id", run.id.clone());
+    let guarded = test_case.concurrency_policy != ConcurrencyPolicy::Allow;
+    let resources = match RunResources::load(&repo, &run, &actions).await {
+        Ok(resources) => resources,
+        Err(err) => {
+            error!("could not load the resources of run {}: {:?}", run.id, err);
+            export_spans(vec![run_span.finish(true)]).await;
+            return finish_errored_run(&repo, run, guarded).await;
+        }
+    };
+    let heartbeat = guarded.then(|| tokio::spawn(renew_run_lock(Arc::clone(&repo), run.clone())));
+    let retention = RetentionPlan::new(&test_case.context_retention, &actions, &resources.parameters, &resources.assertions);
+    seed_builtins(&mut context, &run, SystemTime::now());
+    let mut spans = vec![];
+    let mut status_codes = vec![];
+    let mut over_budget = false;
+    let mut unexpected_status = false;
+    let mut created_resources = run.resources.clone();
+    let available_names: HashSet<String> = actions.iter()
+        .map(|action| action.name.clone())
+        .chain(context.keys().cloned())
+        .collect();
+    for (index, action) in actions.iter().enumerate() {
+        if let Some(delay) = resolve_delay(action) {
+            info!("waiting {:?} before action: {}", delay, action.name);
+            tokio::time::sleep(delay).await;
+        }
+        if action.action_type == ActionType::Sleep {
+            retention.apply(index, &mut context);
+            continue;
+        }
+        refresh_now(&mut context, &run, SystemTime::now());
+        let (status_code, budget_violations) = execute(
+            repo.clone(),
+            api_client.clone(),
+            &run,
+            action,
+            &resources,
+            &mut context,
+            &run_span_context,
+            &mut spans)
+            .await;
+        if !action.teardown {
+            match action.expected_status {
+                Some(_) if status_code != 0 => unexpected_status |= !action.status_succeeded(status_code),
+                _ => status_codes.push(status_code),
+            }
+            over_budget |= !budget_violations.is_empty() && action.budget.as_ref().is_some_and(|budget| budget.fail_run);
+        } else if !action.status_succeeded(status_code) {
+            warn!(action = %action.name, status_code, "teardown action failed");
+        }
+        if !run.mock && action.status_succeeded(status_code) {
+            let output = context.get(&action.name).map_or(&Value::Null, |entry| &entry["output"]);
+            created_resources.extend(track_resource(action, output, test_case.base_url.as_deref(), &resources.url_rewrites));
+        }
+        retention.apply(index, &mut context);
+    }
+    let mut assertions = resources.assertions.clone();
+    refresh_now(&mut context, &run, SystemTime::now());
+    let assertion_context = Value::Object(context.clone());
+    let snapshot_inputs = load_snapshot_inputs(&repo, &run, &assertions).await;
+    if run.selection.is_some() {
+        assertions.retain(|assertion| is_checkable(assertion, &available_names, &snapshot_inputs.1));
+    }
+    let assertion_results: Vec<AssertionResult> = assertions.iter()
+        .map(|assertion| match assertion.comparison_type {
+            ComparisonType::MatchesSnapshot => check_against_snapshot(assertion, &snapshot_inputs, &assertion_context),
+            _ => check_assertion(assertion, &assertion_context),
+        })
+        .collect();
+    let verdict = match compute_verdict(&assertion_results, &status_codes) {
+        Verdict::Passed if over_budget || unexpected_status => Verdict::Failed,
+        verdict => verdict,
+    };
+    let notification = run_notification(&run, &verdict, &assertion_results, current_timestamp());
+    let notified_repo = Arc::clone(&repo);
+    let notified_run = run.clone();
+    tokio::spawn(async move {
+        notify(&notified_repo, notification).await;
+        notify_expiring_auth_providers(&notified_repo, &notified_run).await;
+    }.in_current_span());
+    repo.runs()
+        .update(
+            &run.customer_id,
+            &run.test_case_id,
+            &run.id,
+            &RunStatus::Finished,
+            assertion_results.clone(),
+            &verdict,
+        )
+        .await;
+    if let Some(heartbeat) = heartbeat {
+        heartbeat.abort();
+        release_run_lock(&repo, &run.customer_id, &run.test_case_id, &run.id).await;
+    }
+    if created_resources.len() > run.resources.len() {
+        if let Err(err) = repo.runs()
+            .update_resources(&run.customer_id, &run.test_case_id, &run.id, &created_resources)
+            .await {
+            error!("could not store the resources run {} created: {:?}", run.id, err);
+        }
+    }
+    spans.push(run_span.finish(false));
+    export_spans(spans).await;
+    metrics().runs.inc(&["finished"]);
+    metrics().run_duration.observe(&[], Duration::from_millis(current_timestamp().saturating_sub(run.started_at)));
+    Run {
+        status: RunStatus::Finished,
+        finished_at: Some(current_timestamp()),
+        assertion_results: Some(assertion_results),
+        verdict: Some(verdict),
+        resources: created_resources,
+        ..run
+    }
+}
+
+/// Finishes a run that could not start executing its actions as errored and lets the next run of
+/// the test case take the lock.
+async fn finish_errored_run(repo: &Repository, run: Run, guarded: bool) -> Run {
+    repo.runs()
+        .update(&run.customer_id, &run.test_case_id, &run.id, &RunStatus::Finished, vec![], &Verdict::Errored)
+        .await;
+    if guarded {
+        release_run_lock(repo, &run.customer_id, &run.test_case_id, &run.id).await;
+    }
+    metrics().runs.inc(&["finished"]);
+    Run {
+        status: RunStatus::Finished,
+        finished_at: Some(current_timestamp()),
+        assertion_results: Some(vec![]),
+        verdict: Some(Verdict::Errored),
+        ..run
+    }
+}
+
+/// Seeds are stored with the run, so they are kept small; keys must be reachable as `$.<key>`.
+fn validate_seed_context(seed_context: &Map<String, Value>) -> Result<(), AppError> {
+    if let Some(key) = seed_context.keys().find(|key| key.is_empty() || key.contains(['.', '[', ']', '/', '$']) || key.contains(char::is_whitespace)) {
+        return Err(AppError::Validation(format!("Seed context key \"{}\" cannot be referenced as $.<key>", key)));
+    }
+    let size = serde_json::to_vec(seed_context).map_or(0, |bytes| bytes.len());
+    if size > MAX_SEED_CONTEXT_BYTES {
+        return Err(AppError::Validation(format!("Seed context is {} bytes, at most {} are allowed", size, MAX_SEED_CONTEXT_BYTES)));
+    }
+    Ok(())
+}
+
+/// Labels are matched with `contains` when listing runs, so they cannot hold the `,` that
+/// separates them in the list filter.
+fn validate_labels(labels: &[String]) -> Result<(), AppError> {
+    match labels.iter().find(|label| label.trim().is_empty() || label.contains(',')) {
+        Some(label) => Err(AppError::Validation(format!("Invalid run label: \"{}\"", label))),
+        None => Ok(()),
+    }
+}
+
+async fn validate_auth_provider_overrides(repository: &Repository, command: &RunTestCaseCommand) -> Result<(), AppError> {
+    let ids: HashSet<String> = command.auth_provider_overrides.values().cloned().collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let found: HashSet<String> = repository.auth_providers()
+        .batch_get(&command.customer_id, ids.iter().cloned().collect())
+        .await?
+        .into_iter()
+        .map(|provider| provider.id)
+        .collect();
+    match ids.difference(&found).next() {
+        Some(missing) => Err(AppError::Validation(format!("Auth provider {} not found!", missing))),
+        None => Ok(()),
+    }
+}
+
+/// Takes the run lock of the test case, returning the status the new run should start with.
+async fn acquire_run_lock(repository: &Repository, test_case: &TestCase, run_id: &str) -> Result<RunStatus, AppError> {
+    if repository.run_locks().acquire(&test_case.customer_id, &test_case.id, run_id).await? {
+        return Ok(RunStatus::InProgress);
+    }
+    match test_case.concurrency_policy {
+        ConcurrencyPolicy::Queue => Ok(RunStatus::Queued),
+        _ => Err(AppError::Conflict(format!("Another run of test case {} is in progress", test_case.id))),
+    }
+}
+
+/// Keeps the run's lock from expiring while it executes; stops once another run took the lock over.
+async fn renew_run_lock(repository: Arc<Repository>, run: Run) {
+    loop {
+        tokio::time::sleep(LOCK_RENEW_INTERVAL).await;
+        match repository.run_locks().renew(&run.customer_id, &run.test_case_id, &run.id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!("run {} lost its lock on test case {}", run.id, run.test_case_id);
+                return;
+            }
+            Err(err) => error!("could not renew run lock of {}: {:?}", run.id, err),
+        }
+    }
+}
+
+async fn release_run_lock(repository: &Repository, customer_id: &String, test_case_id: &String, run_id: &String) {
+    if let Err(err) = repository.run_locks().release(customer_id, test_case_id, run_id).await {
+        error!("could not release run lock of {}: {:?}", run_id, err);
+    }
+}
+
+/// Counts the run against the customer's daily quota once it is known to start, failing when that
+/// or the concurrent run quota is used up.
+async fn reserve_run_quota(repository: &Repository, customer_id: &String) -> Result<(), AppError> {
+    let quota = repository.quotas().get(customer_id).await?;
+    quota.check(QuotaKind::ConcurrentRuns, repository.runs().count_in_progress(customer_id).await?)
+        .map_err(AppError::QuotaExceeded)?;
+    if repository.quotas().reserve_run(customer_id, quota.max_runs_per_day).await? {
+        return Ok(());
+    }
+    let used = repository.quotas().runs_today(customer_id).await?.max(quota.max_runs_per_day);
+    quota.check(QuotaKind::RunsPerDay, used).map_err(AppError::QuotaExceeded)
+}
+
+async fn wait_for_run_lock(repository: &Repository, run: &Run) {
+    loop {
+        match repository.run_locks().acquire(&run.customer_id, &run.test_case_id, &run.id).await {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(err) => error!("could not acquire run lock for {}: {:?}", run.id, err),
+        }
+        tokio::time::sleep(RUN_LOCK_POLL_INTERVAL).await;
+    }
+    info!("run {} acquired the lock after waiting in queue", run.id);
+    if let Err(err) = repository.runs()
+        .update_status(&run.customer_id, &run.test_case_id, &run.id, &RunStatus::InProgress)
+        .await {
+        error!("could not mark queued run {} as started: {:?}", run.id, err);
+    }
+}
+
+/// Baselines and actions needed by snapshot assertions, only loaded when the test case has any.
+async fn load_snapshot_inputs(repo: &Repository, run: &Run, assertions: &[Assertion]) -> (Vec<Snapshot>, Vec<Action>) {
+    if !assertions.iter().any(|assertion| assertion.comparison_type == ComparisonType::MatchesSnapshot) {
+        return (vec![], vec![]);
+    }
+    let snapshots = repo.snapshots()
+        .list_all(&run.customer_id, &run.test_case_id)
+        .await
+        .unwrap_or_else(|err| {
+            error!("could not list snapshots: {:?}", err);
+            vec![]
+        });
+    let actions = repo.actions()
+        .list_all(run.customer_id.clone(), run.test_case_id.clone())
+        .await
+        .unwrap_or_else(|err| {
+            error!("could not list actions for snapshots: {:?}", err);
+            vec![]
+        });
+    (snapshots, actions)
+}
+
+fn check_against_snapshot(assertion: &Assertion, (snapshots, actions): &(Vec<Snapshot>, Vec<Action>), context: &Value) -> AssertionResult {
+    let action = assertion.snapshot_action_id.as_ref()
+        .and_then(|action_id| actions.iter().find(|action| &action.id == action_id));
+    let Some(action) = action else {
+        return AssertionResult::from_error(assertion.id.clone(), "snapshot assertion has no action to compare".to_string())
+            .with_severity(assertion.severity.clone());
+    };
+    let snapshot = snapshots.iter().find(|snapshot| snapshot.action_id == action.id);
+    let live = context.get(&action.name)
+        .and_then(|action_context| action_context.get("output"));
+    check_snapshot_assertion(assertion, snapshot, live, &action.volatile_paths)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(action = %action.name, action_id = %action.id))]
 async fn execute(
     repository: Arc<Repository>,
     client: Arc<ApiClient>,
     run: &Run,
     action: &Action,
+    resources: &RunResources,
     context: &mut Map<String, Value>,
-) {
-    info!(
-        "will execute action: {}, {:?}",
-        action.name.clone(),
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_millis()
-    );
+    parent_span_context: &SpanContext,
+    spans: &mut Vec<SpanRecord>,
+) -> (u16, Vec<BudgetViolation>) {
+    let action_span_context = parent_span_context.child();
+    let action_span = SpanRecord::start(format!("action {}", action.name), action_span_context.clone(), Some(parent_span_context), SpanKind::Internal)
+        .attribute("parroton.action_id", action.id.clone());
+    let http_span_context = action_span_context.child();
+    info!("will execute action");
     let run_cloned = run.clone();
     let action_cloned = action.clone();
     let started_at = current_timestamp();
-    let http_request =
-        build_http_request(&repository, action, &Value::Object(context.clone())).await;
+    let sensitive_headers = &resources.sensitive_headers;
+    let (mut http_request, mut hook_errors) = build_http_request(resources, action, &Value::Object(context.clone()));
+    if telemetry_config().propagate_traceparent {
+        http_request.endpoint.headers.push(ReqParam::new("traceparent".to_string(), http_span_context.traceparent()));
+    }
     let request_body = resolve_request_body_from_request(&http_request);
     let req_params = resolve_request_params_from_request(&http_request);
-    let result = client.execute(http_request).await;
-    info!(
-        "executed action: {}, {:?}",
-        action.name.clone(),
-        SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_millis()
+    let req_headers = resolve_request_headers_from_request(&http_request, sensitive_headers);
+    let request_url = http_request.endpoint.to_url();
+    let http_span = SpanRecord::start(action.method.clone(), http_span_context, Some(&action_span_context), SpanKind::Client)
+        .attribute("http.request.method", action.method.clone())
+        .attribute("url.full", request_url.clone());
+    let http_started_at = Instant::now();
+    let result = if run.mock {
+        recorded_result(&repository, action).await
+    } else {
+        FaultInjectingClient::new(&client, run.fault_injection.as_ref()).execute(http_request).await
+    };
+    let result = result.or_else(|err| accept_expected_error(action, err));
+    let unexpected_status = action.expected_status
+        .filter(|_| result.as_ref().is_ok_and(|http_result| !action.status_succeeded(http_result.status_code)));
+    let failed = result.is_err() || unexpected_status.is_some();
+    spans.push(http_span
+        .attribute("http.response.status_code", resolve_status_code(&result).to_string())
+        .finish(failed));
+    metrics().action_http_duration.observe(
+        &[&action.test_case_id, &action.name, &resolve_status_code(&result).to_string()],
+        http_started_at.elapsed(),
     );
     let finished_at = current_timestamp();
     let arc_repo_clone = Arc::clone(&repository);
     let status_code = resolve_status_code(&result);
-    let error = resolve_error_from_result(&result);
+    let elapsed_ms = http_started_at.elapsed().as_millis() as u64;
+    info!(status_code, elapsed_ms, "executed action");
+    let budget_violations = action.budget.as_ref().map_or(vec![], |budget| budget
+        .violations(elapsed_ms, result.as_ref().ok().and_then(|http_result| http_result.body_bytes)));
+    budget_violations.iter().for_each(|violation| warn!("action {}", violation.message()));
+    let budget_violations_cloned = budget_violations.clone();
+    let error = resolve_error_from_result(&result)
+        .or(unexpected_status.map(|expected| format!("expected status {}, got {}", expected, status_code)));
     let response_body = resolve_response_from_result(&result);
+    let redirects = result.as_ref().map_or(vec![], |http_result| http_result.redirects.clone());
+    let redirects_cloned = redirects.clone();
+    let response_headers = result.as_ref().map_or(vec![], |http_result| http_result.headers.clone());
+    let masked_response_headers = response_headers.iter()
+        .map(|(name, value)| (name.clone(), sensitive_headers.mask(name, value)))
+        .collect();
     let request_body_cloned = request_body.clone();
+    let action_context = match &result {
+        Ok(http_result) if unexpected_status.is_none() => {
+            let (output, errors) = apply_response_hooks(&action.hooks.post_response, http_result.res_body.value.clone(), context);
+            errors.iter().for_each(|err| warn!(error = %err, "could not apply post-response hook"));
+            hook_errors.extend(errors);
+            output
+        }
+        _ => Value::Null,
+    };
     tokio::spawn(async move {
         let action_execution = ActionExecution::builder()
             .run_id(run_cloned.id.clone())
@@ -147,21 +822,31 @@ async fn execute(
             .finished_at(finished_at)
             .maybe_response_body(response_body)
             .maybe_request_body(request_body_cloned)
+            .request_url(request_url)
             .query_params(req_params)
-            .build();
+            .request_headers(req_headers)
+            .redirects(redirects_cloned)
+            .response_headers(masked_response_headers)
+            .budget_violations(budget_violations_cloned)
+            .hook_errors(hook_errors)
+            .post_response_hooks(action_cloned.hooks.post_response.clone())
+            .build()
+            .with_canonical_response();
+        let action_execution = offload_response_body(action_execution).await;
         arc_repo_clone
             .action_executions()
             .create(action_execution)
             .await;
-    });
-    let action_context = match result {
-        Ok(http_result) => http_result.res_body.value,
-        Err(_) => Value::Null,
-    };
-    let mut temp = Map::new();
-    temp.insert("output".to_string(), action_context);
-    temp.insert("input".to_string(), request_body.unwrap_or(Value::Null));
-    context.insert(action.name.clone(), Value::Object(temp));
+    }.in_current_span());
+    context.insert(action.name.clone(), build_action_context(action_context, request_body.unwrap_or(Value::Null), status_code, &redirects, &response_headers));
+    spans.push(action_span.finish(failed));
+    (status_code, budget_violations)
+}
+
+fn resolve_delay(action: &Action) -> Option<Duration> {
+    action.delay_ms
+        .filter(|delay_ms| *delay_ms > 0)
+        .map(Duration::from_millis)
 }
 
 fn resolve_request_body_from_request(http_request: &HttpRequest) -> Option<Value> {
@@ -180,6 +865,48 @@ fn resolve_request_params_from_request(http_request: &HttpRequest) -> Vec<(Strin
         .collect()
 }
 
+/// Headers as they go over the wire, with sensitive values masked. The content type is listed
+/// even when only implied by the body so a wrong one is visible in the execution.
+fn resolve_request_headers_from_request(http_request: &HttpRequest, sensitive_headers: &SensitiveHeaderPolicy) -> Vec<(String, String)> {
+    resolve_wire_headers(http_request)
+        .into_iter()
+        .map(|(key, value)| {
+            let value = sensitive_headers.mask(&key, &value);
+            (key, value)
+        })
+        .collect()
+}
+
+fn resolve_wire_headers(http_request: &HttpRequest) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = http_request
+        .endpoint
+        .headers
+        .iter()
+        .map(|header| (header.key.clone(), header.value.clone()))
+        .collect();
+    let has_content_type = headers.iter().any(|(key, _)| key.eq_ignore_ascii_case("content-type"));
+    if let Some(content_type) = http_request.sent_content_type().filter(|_| !has_content_type) {
+        headers.push(("Content-Type".to_string(), content_type));
+    }
+    headers
+}
+
+/// Turns a 4xx or 5xx response the action expects into a result, its body parsed as JSON when it is JSON.
+fn accept_expected_error(action: &Action, err: HttpError) -> Result<HttpResult<Value>, HttpError> {
+    match err {
+        HttpError::Status(status_code, _) if action.expected_status.is_some() && action.status_succeeded(status_code) => {
+            let text = err.get_message();
+            let body = if text.is_empty() {
+                Value::Null
+            } else {
+                serde_json::from_str(&text).unwrap_or(Value::String(text))
+            };
+            Ok(HttpResult::new(ResBody::new(body), status_code))
+        }
+        err => Err(err),
+    }
+}
+
 fn resolve_status_code(result: &Result<HttpResult<Value>, HttpError>) -> u16 {
     match result {
         Ok(http_result) => http_result.status_code,
@@ -208,92 +935,124 @@ fn resolve_error_from_result(result: &Result<HttpResult<Value>, HttpError>) -> O
     }
 }
 
-async fn build_http_request(
-    repository: &Repository,
-    action: &Action,
-    context: &Value,
-) -> HttpRequest {
-    let parameters = repository.parameters().list_all_inputs_of_action(action.customer_id.clone(), action.test_case_id.clone(), action.id.clone())
-        .await
-        .unwrap();
-    let req_params = build_http_params(&parameters, context, ParameterIn::Query);
-    let mut headers = build_http_params(&parameters, context, ParameterIn::Header);
-    repository.auth_providers()
-        .list(ListAuthProvidersRequest::builder()
-            .customer_id(action.customer_id.clone())
-            .test_case_id(action.test_case_id.clone())
-            .base_url(obtain_base_url(&action.url))
-            .build())
-        .await
-        .unwrap().items
-        .iter()
-        .for_each(|provider| {
-            provider
-                .headers_by_name
-                .iter()
-                .filter(|(_, value)| !value.disabled)
-                .for_each(|(key, value)| {
-                    headers.push(ReqParam::new(key.clone(), value.value.clone()))
-                })
-        });
-    let req_body = build_http_request_body(&parameters, context);
+/// The request of the action with the errors of its pre-request hooks.
+fn build_http_request(resources: &RunResources, action: &Action, context: &Value) -> (HttpRequest, Vec<String>) {
+    let parameters = resources.inputs_of(action);
+    let req_params = build_http_params(parameters, context, ParameterIn::Query);
+    let mut headers = build_http_params(parameters, context, ParameterIn::Header);
+    let auth_providers = resources.auth_providers_of(action);
+    let signing = auth_providers.iter().find_map(|provider| provider.signing.clone());
+    apply_provider_credentials(&mut headers, auth_providers, &resources.sensitive_headers);
+    apply_header_rules(&mut headers, &resources.header_rules);
+    let content_type = action.mime_type.clone().unwrap_or("application/json".to_string());
+    let req_body = build_http_request_body(parameters, context, &content_type);
     let endpoint = Endpoint::new(
         HttpMethod::from_str(&action.method).unwrap(),
-        build_http_url(&action.url, context),
+        remap_url(&build_http_url(&action.url, context, &build_http_params(parameters, context, ParameterIn::Path)), &resources.url_rewrites),
         vec![],
         req_params,
         headers,
     );
-    HttpRequest::new(
-        endpoint,
-        req_body,
-        action
-            .mime_type
-            .clone()
-            .unwrap_or("application/json".to_string()),
-    )
+    let mut http_request = HttpRequest::new(endpoint, req_body, content_type)
+        .with_redirect_policy(action.redirect_policy.clone())
+        .with_signing(signing);
+    let hook_errors = apply_request_hooks(action, &mut http_request, context, SystemTime::now());
+    (http_request, hook_errors)
+}
+
+/// Adds the enabled headers and cookies of the auth providers. Credentials come from the auth
+/// providers then, so recorded ones left on the action, which are stale, are dropped.
+pub(crate) fn apply_provider_credentials(headers: &mut Vec<ReqParam>, auth_providers: &[AuthenticationProvider], sensitive_headers: &SensitiveHeaderPolicy) {
+    let provider_headers: Vec<ReqParam> = auth_providers
+        .iter()
+        .flat_map(|provider| provider
+            .headers_by_name
+            .iter()
+            .filter(|(_, value)| !value.disabled)
+            .map(|(key, value)| ReqParam::new(key.clone(), value.value.clone())))
+        .collect();
+    let provider_cookies: Vec<String> = auth_providers.iter()
+        .filter_map(|provider| provider.cookie_header())
+        .collect();
+    if !provider_headers.is_empty() || !provider_cookies.is_empty() {
+        headers.retain(|header| !sensitive_headers.is_sensitive(&header.key));
+        headers.extend(provider_headers);
+    }
+    if !provider_cookies.is_empty() {
+        add_cookies(headers, &provider_cookies.join("; "));
+    }
+}
+
+/// Appends to the `Cookie` header the action sends, or adds one.
+fn add_cookies(headers: &mut Vec<ReqParam>, cookies: &str) {
+    match headers.iter_mut().find(|header| header.key.eq_ignore_ascii_case("cookie")) {
+        Some(header) => header.value = format!("{}; {}", header.value.trim_end_matches([';', ' ']), cookies),
+        None => headers.push(ReqParam::new("Cookie".to_string(), cookies.to_string())),
+    }
+}
+
+pub(crate) fn auth_provider_override<'a>(overrides: &'a HashMap<String, String>, url: &str) -> Option<&'a String> {
+    let base_url = obtain_base_url(url);
+    overrides.iter()
+        .find(|(override_url, _)| obtain_base_url(override_url.trim_end_matches('/')) == base_url)
+        .map(|(_, auth_provider_id)| auth_provider_id)
 }
 
 fn build_http_params(
-    parameters: &Vec<Parameter>,
+    parameters: &[Parameter],
     context: &Value,
     parameter_in: ParameterIn,
 ) -> Vec<ReqParam> {
     parameters
         .iter()
         .filter(|param| { param.get_parameter_in() == parameter_in })
-        .map(|parameter: &Parameter| (parameter, evaluate_value(parameter, context)))
+        .map(|parameter: &Parameter| (parameter, evaluate_param_values(parameter, context, &parameter_in)))
         .filter(|(parameter, eval_result)| {
             if let Err(err) = eval_result {
-                println!(
-                    "could not eval for param: {:?}, error: {}",
-                    parameter.get_path(),
-                    err
-                );
+                warn!(parameter = ?parameter.get_path(), error = %err, "could not evaluate parameter");
             }
             eval_result.is_ok()
         })
-        .map(|(parameter, eval_result)| {
-            ReqParam::new(
-                parameter.get_path(),
-                eval_result
-                    .clone()
-                    .unwrap()
-                    .to_string()
-                    .trim_matches('"')
-                    .to_string(),
-            )
+        .flat_map(|(parameter, eval_result)| {
+            let values: Vec<String> = eval_result.unwrap().into_iter()
+                .map(|value| value.to_string().trim_matches('"').to_string())
+                .collect();
+            let values = match (&parameter.multi_value_mode, &parameter_in) {
+                (Some(mode), ParameterIn::Query) => mode.apply(values),
+                _ => values,
+            };
+            values.into_iter()
+                .map(|value| ReqParam::new(parameter.get_path(), value))
+                .collect::<Vec<ReqParam>>()
         })
         .collect()
 }
 
+/// Values a parameter is sent with. A query parameter with a multi value mode gets every match of
+/// its expression; otherwise arrays are only spread into repeated query parameters.
+fn evaluate_param_values(parameter: &Parameter, context: &Value, parameter_in: &ParameterIn) -> Result<Vec<Value>, String> {
+    if let (Some(_), Some(expression), ParameterIn::Query) = (&parameter.multi_value_mode, &parameter.value_expression, parameter_in) {
+        return evaluate_expression(context, expression);
+    }
+    Ok(match evaluate_value(parameter, context)? {
+        Value::Array(items) if *parameter_in == ParameterIn::Query => items,
+        value => vec![value],
+    })
+}
+
 fn build_http_url(
     raw_url: &String,
     context: &Value,
+    path_params: &[ReqParam],
 ) -> String {
     raw_url.split("/")
         .map(|part|{
-            if part.starts_with("$.") {
+            let placeholder = part.strip_prefix('{').and_then(|part| part.strip_suffix('}'));
+            if let Some(name) = placeholder {
+                path_params.iter()
+                    .find(|param| param.key == name)
+                    .map_or("".to_string(), |param| percent_encode(&param.value))
+            } else if part.starts_with("$.") {
                 evaluate_expression(context, &Expression {
                     value: part.to_string(),
                 }).map_or("".to_string(), |value| {value.get(0)
@@ -306,8 +1065,9 @@ fn build_http_url(
 }
 
 fn build_http_request_body(
-    parameters: &Vec<Parameter>,
+    parameters: &[Parameter],
     context: &Value,
+    content_type: &str,
 ) -> ReqBody {
     let tuples: Vec<(String, Value)> = parameters
         .iter()
@@ -323,16 +1083,51 @@ fn build_http_request_body(
             }
             eval_result.is_ok()
         })
-        .map(|(parameter, eval_result)| (parameter.get_path(), eval_result.unwrap()))
+        .map(|(parameter, eval_result)| (parameter.get_path(), coerce_value(parameter, eval_result.unwrap())))
         .collect();
     if tuples.is_empty() {
         ReqBody::empty()
+    } else if content_type.contains("application/x-www-form-urlencoded") {
+        ReqBody::new(build_form_fields(tuples))
     } else {
         ReqBody::new(reverse_flatten_all(tuples))
     }
 }
 
-fn obtain_base_url(url: &str) -> String {
+/// Converts the value to the parameter's type; a value that cannot be converted is sent as it is.
+fn coerce_value(parameter: &Parameter, value: Value) -> Value {
+    match parameter.value_type.map(|value_type| value_type.coerce(&value)) {
+        None => value,
+        Some(Ok(coerced)) => coerced,
+        Some(Err(err)) => {
+            warn!("could not coerce param: {:?}, error: {}", parameter.get_path(), err);
+            value
+        }
+    }
+}
+
+/// Form fields are flat, so keys are kept verbatim and repeated keys collect into an array.
+fn build_form_fields(tuples: Vec<(String, Value)>) -> Value {
+    let mut fields = Map::new();
+    tuples.into_iter().for_each(|(key, value)| {
+        let key = key.strip_prefix("$.").unwrap_or(&key).to_string();
+        match fields.remove(&key) {
+            None => {
+                fields.insert(key, value);
+            }
+            Some(Value::Array(mut items)) => {
+                items.push(value);
+                fields.insert(key, Value::Array(items));
+            }
+            Some(existing) => {
+                fields.insert(key, Value::Array(vec![existing, value]));
+            }
+        }
+    });
+    Value::Object(fields)
+}
+
+pub(crate) fn obtain_base_url(url: &str) -> String {
     // Step 1: Find the scheme (http:// or https://)
     if let Some(scheme_end) = url.find("://") {
         // Step 2: Find the part after the scheme and the domain/subdomain
@@ -354,10 +1149,36 @@ fn obtain_base_url(url: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::action::model::{ActionHooks, ExpectedStatus, ResponseHook};
+    use crate::http::StatusError;
     use crate::json_path::model::Expression;
-    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use crate::parameter::model::{MultiValueMode, ParameterLocation, ParameterType, ValueType};
+    use crate::persistence::repo::init_logger;
     use serde_json::json;
 
+    #[test]
+    fn request_headers_are_masked_and_include_content_type() {
+        let endpoint = Endpoint::new(
+            HttpMethod::POST,
+            "https://abc.xyz/items".to_string(),
+            vec![],
+            vec![],
+            vec![
+                ReqParam::new("Authorization".to_string(), "Bearer abc".to_string()),
+                ReqParam::new("X-Tenant".to_string(), "t1".to_string()),
+            ],
+        );
+        let policy = SensitiveHeaderPolicy::default_for("cust1");
+        let request = HttpRequest::new(endpoint, ReqBody::new(json!({"a": 1})), "application/json; charset=utf-8".to_string());
+
+        assert_eq!(resolve_request_headers_from_request(&request, &policy), vec![
+            ("Authorization".to_string(), "****".to_string()),
+            ("X-Tenant".to_string(), "t1".to_string()),
+            ("Content-Type".to_string(), "application/json".to_string()),
+        ]);
+    }
+
     #[test]
     fn test_build_request_body() {
         let param_with_expression = Parameter::builder()
@@ -393,7 +1214,7 @@ mod tests {
                 }
             }
         });
-        let actual = build_http_request_body(&parameters, &context);
+        let actual = build_http_request_body(&parameters, &context, "application/json");
         println!("actual: {:?}", actual.value);
         assert_eq!(actual.value.is_some(), true);
         assert_eq!(actual.value.unwrap(), json!({
@@ -445,6 +1266,278 @@ mod tests {
             value: "header-val1".to_string(),
         }]);
     }
+
+    #[test]
+    fn test_build_repeated_query_params() {
+        let param_with_expression = Parameter::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .action_id("".to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Query(String::from("id")))
+            .value(json!([]))
+            .value_expression(Expression { value: String::from("$.action1.output.items[*].id") })
+            .build();
+        let context = json!({
+            "action1": {
+                "output": {
+                    "items": [{"id": 1}, {"id": "b"}]
+                }
+            }
+        });
+        let actual = build_http_params(&[param_with_expression], &context, ParameterIn::Query);
+        assert_eq!(actual, vec![
+            ReqParam::new("id".to_string(), "1".to_string()),
+            ReqParam::new("id".to_string(), "b".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_build_request_body_coerces_values() {
+        let param = |path: &str, expression: &str, value_type: ValueType| Parameter::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .action_id("".to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Body(path.to_string()))
+            .value(Default::default())
+            .value_expression(Expression { value: expression.to_string() })
+            .value_type(value_type)
+            .build();
+        let parameters = vec![
+            param("$.quantity", "$.seed.quantity", ValueType::Integer),
+            param("$.gift", "$.seed.gift", ValueType::Boolean),
+            param("$.note", "$.seed.note", ValueType::Integer),
+        ];
+        let context = json!({"seed": {"quantity": "3", "gift": "false", "note": "soon"}});
+
+        let actual = build_http_request_body(&parameters, &context, "application/json");
+
+        assert_eq!(actual.value.unwrap(), json!({"quantity": 3, "gift": false, "note": "soon"}));
+    }
+
+    #[test]
+    fn test_build_multi_value_query_params() {
+        let param = |name: &str, mode: MultiValueMode| Parameter::builder()
+            .customer_id("".to_string())
+            .test_case_id("".to_string())
+            .action_id("".to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Query(name.to_string()))
+            .value(json!("1"))
+            .value_expression(Expression { value: String::from("$.action1.output.items[*].id") })
+            .multi_value_mode(mode)
+            .build();
+        let context = json!({"action1": {"output": {"items": [{"id": 1}, {"id": "b"}]}}});
+        let parameters = vec![
+            param("ids", MultiValueMode::Join { delimiter: ",".to_string() }),
+            param("id", MultiValueMode::Repeat),
+            param("first", MultiValueMode::First),
+        ];
+
+        let actual = build_http_params(&parameters, &context, ParameterIn::Query);
+
+        assert_eq!(actual, vec![
+            ReqParam::new("ids".to_string(), "1,b".to_string()),
+            ReqParam::new("id".to_string(), "1".to_string()),
+            ReqParam::new("id".to_string(), "b".to_string()),
+            ReqParam::new("first".to_string(), "1".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_build_form_request_body() {
+        let parameters: Vec<Parameter> = vec![("username", "eren"), ("scope", "read"), ("scope", "write")].into_iter()
+            .map(|(key, value)| Parameter::builder()
+                .customer_id("".to_string())
+                .test_case_id("".to_string())
+                .action_id("".to_string())
+                .parameter_type(ParameterType::Input)
+                .location(ParameterLocation::Body(key.to_string()))
+                .value(json!(value))
+                .build())
+            .collect();
+        let actual = build_http_request_body(&parameters, &json!({}), "application/x-www-form-urlencoded");
+        assert_eq!(actual.value.unwrap(), json!({
+            "username": "eren",
+            "scope": ["read", "write"]
+        }));
+    }
+
+    #[test]
+    fn test_auth_provider_override() {
+        let overrides = HashMap::from([
+            ("https://abc.xyz/".to_string(), "admin".to_string()),
+            ("https://api.abc.xyz".to_string(), "viewer".to_string()),
+        ]);
+
+        assert_eq!(auth_provider_override(&overrides, "https://abc.xyz/issues?page=1"), Some(&"admin".to_string()));
+        assert_eq!(auth_provider_override(&overrides, "https://api.abc.xyz/v1/me"), Some(&"viewer".to_string()));
+        assert_eq!(auth_provider_override(&overrides, "https://other.xyz/"), None);
+    }
+
+    #[test]
+    fn test_add_cookies() {
+        let mut headers = vec![ReqParam::new("accept".to_string(), "*/*".to_string())];
+        add_cookies(&mut headers, "session=abc");
+        assert_eq!(headers[1], ReqParam::new("Cookie".to_string(), "session=abc".to_string()));
+
+        let mut headers = vec![ReqParam::new("cookie".to_string(), "theme=dark;".to_string())];
+        add_cookies(&mut headers, "session=abc; csrf=xyz");
+        assert_eq!(headers, vec![ReqParam::new("cookie".to_string(), "theme=dark; session=abc; csrf=xyz".to_string())]);
+    }
+
+    #[test]
+    fn test_validate_seed_context() {
+        let seed = |value: Value| value.as_object().unwrap().clone();
+
+        assert!(validate_seed_context(&seed(json!({"issue_key": "ABC-1", "login_0": {"output": {"token": "t"}}}))).is_ok());
+        assert!(validate_seed_context(&seed(json!({"tenant.id": "t1"}))).is_err());
+        assert!(validate_seed_context(&seed(json!({"": 1}))).is_err());
+        assert!(validate_seed_context(&seed(json!({"blob": "x".repeat(MAX_SEED_CONTEXT_BYTES)}))).is_err());
+    }
+
+    #[test]
+    fn test_output_headers_in_context() {
+        let headers = vec![
+            ("Location".to_string(), "/orders/42".to_string()),
+            ("set-cookie".to_string(), "a=1".to_string()),
+            ("set-cookie".to_string(), "b=2".to_string()),
+        ];
+        let context = json!({"orders_0": build_action_context(json!({"id": 42}), Value::Null, 201, &[], &headers)});
+
+        let location = evaluate_expression(&context, &Expression { value: "$.orders_0.output_headers['location']".to_string() });
+        assert_eq!(location, Ok(vec![json!("/orders/42")]));
+        assert_eq!(context["orders_0"]["output_headers"]["set-cookie"], json!("a=1, b=2"));
+        assert_eq!(context["orders_0"]["status"], json!(201));
+        assert!(build_action_context(Value::Null, Value::Null, 0, &[], &[]).get("output_headers").is_none());
+    }
+
+    #[test]
+    fn test_resolve_delay() {
+        let action = Action { action_type: ActionType::Sleep, delay_ms: Some(1500), ..test_action("sleep_0", 0, "", "") };
+        assert_eq!(resolve_delay(&action), Some(Duration::from_millis(1500)));
+
+        let no_delay = Action { delay_ms: Some(0), ..action.clone() };
+        assert_eq!(resolve_delay(&no_delay), None);
+    }
+
+    #[test]
+    fn test_accept_expected_error() {
+        let action = Action { expected_status: Some(ExpectedStatus::Class(4)), ..test_action("order_0", 0, "GET", "/orders/1") };
+        let not_found = || HttpError::Status(404, StatusError::ClientError(r#"{"code": "NOT_FOUND"}"#.to_string()));
+
+        let Ok(accepted) = accept_expected_error(&action, not_found()) else { panic!("404 was not accepted") };
+        assert_eq!((accepted.status_code, accepted.res_body.value), (404, json!({"code": "NOT_FOUND"})));
+        let server_error = HttpError::Status(503, StatusError::ServerError("unavailable".to_string()));
+        assert!(accept_expected_error(&action, server_error).is_err());
+        let defaults = Action { expected_status: None, ..action };
+        assert!(accept_expected_error(&defaults, not_found()).is_err());
+        assert!(defaults.status_succeeded(302) && !defaults.status_succeeded(404));
+    }
+
+    #[test]
+    fn test_rebuild_context() {
+        let action = |name: &str, order: usize| test_action(name, order, "GET", "https://abc.xyz");
+        let execution = |action: &Action, started_at: u64, error: Option<String>| ActionExecution::builder()
+            .run_id("r1".to_string())
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action.id.clone())
+            .status_code(if error.is_some() { 500 } else { 200 })
+            .maybe_error(error)
+            .response_body(json!({"id": started_at}))
+            .query_params(vec![])
+            .started_at(started_at)
+            .build();
+        let actions = vec![action("login_0", 0), action("profile_1", 1), action("logout_2", 2)];
+        let executions = vec![
+            execution(&actions[0], 1, Some("timeout".to_string())),
+            execution(&actions[0], 2, None),
+            execution(&actions[1], 3, Some("server error".to_string())),
+        ];
+
+        let (context, resume_index) = rebuild_context(&actions, &executions);
+
+        assert_eq!(resume_index, 1);
+        assert_eq!(Value::Object(context), json!({"login_0": {"output": {"id": 2}, "input": null, "status": 200}}));
+        assert_eq!(rebuild_context(&actions, &[]).1, 0);
+
+        let no_seed = Map::new();
+        let after_login = context_after(&no_seed, &actions, &executions, Some("login_0")).unwrap();
+        assert_eq!(Value::Object(after_login), json!({"login_0": {"output": {"id": 2}, "input": null, "status": 200}}));
+        let after_run = context_after(&no_seed, &actions, &executions, None).unwrap();
+        assert_eq!(Value::Object(after_run), json!({
+            "login_0": {"output": {"id": 2}, "input": null, "status": 200},
+            "profile_1": {"output": null, "input": null, "status": 500}
+        }));
+        assert_eq!(context_after(&no_seed, &actions, &executions, Some("missing")), None);
+        let seed = json!({"tenant_id": "t1"}).as_object().unwrap().clone();
+        let seeded = context_after(&seed, &actions, &executions, Some("login_0")).unwrap();
+        assert_eq!(seeded.get("tenant_id"), Some(&json!("t1")));
+
+        let select_id = ResponseHook::Select { expression: "$.id".to_string() };
+        let changed_hooks = Action {
+            hooks: ActionHooks { pre_request: vec![], post_response: vec![select_id.clone()] },
+            ..actions[0].clone()
+        };
+        let hooked = ActionExecution { post_response_hooks: vec![select_id], ..execution(&actions[0], 4, None) };
+        let replayed = context_after(&no_seed, std::slice::from_ref(&changed_hooks), &executions[1..2], None).unwrap();
+        assert_eq!(replayed["login_0"]["output"], json!({"id": 2}));
+        let replayed = context_after(&no_seed, &[changed_hooks], &[hooked], None).unwrap();
+        assert_eq!(replayed["login_0"]["output"], json!(4));
+    }
+
+    #[test]
+    fn test_build_http_url_with_placeholders() {
+        let context = json!({"login_0": {"output": {"key": "TEST-1"}}});
+        let path_params = vec![ReqParam::new("issueKey".to_string(), "TEST/1 a".to_string())];
+
+        assert_eq!(build_http_url(&"https://abc.xyz/issues/{issueKey}/comment".to_string(), &context, &path_params),
+                   "https://abc.xyz/issues/TEST%2F1%20a/comment");
+        assert_eq!(build_http_url(&"https://abc.xyz/issues/$.login_0.output.key".to_string(), &context, &[]),
+                   "https://abc.xyz/issues/TEST-1");
+    }
+
+    #[tokio::test]
+    async fn concurrency_policies_reject_or_queue_until_the_lock_is_released() {
+        init_logger();
+        let repository = Repository::new().await;
+        let rejecting = TestCase::builder()
+            .customer_id("cust1".to_string())
+            .name("Locked".to_string())
+            .description("desc".to_string())
+            .concurrency_policy(ConcurrencyPolicy::Reject)
+            .build();
+        let queueing = TestCase { concurrency_policy: ConcurrencyPolicy::Queue, ..rejecting.clone() };
+        let (customer_id, test_case_id) = (rejecting.customer_id.clone(), rejecting.id.clone());
+
+        assert_eq!(acquire_run_lock(&repository, &rejecting, "run1").await.unwrap(), RunStatus::InProgress);
+        assert!(matches!(acquire_run_lock(&repository, &rejecting, "run2").await, Err(AppError::Conflict(_))));
+        assert_eq!(acquire_run_lock(&repository, &queueing, "run2").await.unwrap(), RunStatus::Queued);
+
+        let queued = repository.runs()
+            .create(Run::builder()
+                .customer_id(customer_id.clone())
+                .test_case_id(test_case_id.clone())
+                .id("run2".to_string())
+                .status(RunStatus::Queued)
+                .started_at(current_timestamp())
+                .build())
+            .await;
+        release_run_lock(&repository, &customer_id, &test_case_id, &"run1".to_string()).await;
+        tokio::time::timeout(RUN_LOCK_POLL_INTERVAL, wait_for_run_lock(&repository, &queued)).await.unwrap();
+        let started = repository.runs().find_by_id(&customer_id, &queued.id).await.unwrap().unwrap();
+        assert_eq!(started.status, RunStatus::InProgress);
+        assert!(matches!(acquire_run_lock(&repository, &rejecting, "run3").await, Err(AppError::Conflict(_))));
+
+        let finished = finish_errored_run(&repository, started, true).await;
+        assert_eq!(finished.verdict, Some(Verdict::Errored));
+        assert_eq!(acquire_run_lock(&repository, &rejecting, "run3").await.unwrap(), RunStatus::InProgress);
+
+        release_run_lock(&repository, &customer_id, &test_case_id, &"run3".to_string()).await;
+        repository.runs().delete_all(&customer_id, &test_case_id).await.unwrap();
+    }
 }
 
 fn current_timestamp() -> u64 {
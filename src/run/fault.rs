@@ -0,0 +1,100 @@
+use crate::drift::analysis::array_index_regex;
+use crate::http::{ApiClient, HttpError, HttpRequest, HttpResult, StatusError};
+pub use parroton_core::run::FaultInjection;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::info;
+
+/// Wraps the API client for runs started with fault injection; without a config requests pass through untouched.
+pub struct FaultInjectingClient<'a> {
+    client: &'a ApiClient,
+    faults: Option<&'a FaultInjection>,
+}
+
+impl<'a> FaultInjectingClient<'a> {
+    pub fn new(client: &'a ApiClient, faults: Option<&'a FaultInjection>) -> Self {
+        FaultInjectingClient { client, faults }
+    }
+
+    pub async fn execute(&self, request: HttpRequest) -> Result<HttpResult<Value>, HttpError> {
+        let Some(faults) = self.faults else {
+            return self.client.execute(request).await;
+        };
+        if let Some(latency_ms) = faults.latency_ms {
+            tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+        }
+        if injects_failure(faults.failure_rate_percent, roll()) {
+            info!(status_code = faults.failure_status, "injecting request failure");
+            return Err(injected_failure(faults.failure_status));
+        }
+        self.client.execute(request).await.map(|mut result| {
+            corrupt(&mut result.res_body.value, &faults.corrupt_paths);
+            result
+        })
+    }
+}
+
+fn roll() -> u8 {
+    (uuid::Uuid::new_v4().as_u128() % 100) as u8
+}
+
+fn injects_failure(failure_rate_percent: u8, roll: u8) -> bool {
+    roll < failure_rate_percent
+}
+
+fn injected_failure(status: u16) -> HttpError {
+    let message = format!("injected failure with status {}", status);
+    if status >= 500 {
+        HttpError::Status(status, StatusError::ServerError(message))
+    } else {
+        HttpError::Status(status, StatusError::ClientError(message))
+    }
+}
+
+pub fn corrupt(value: &mut Value, corrupt_paths: &[String]) {
+    if corrupt_paths.is_empty() {
+        return;
+    }
+    let index_regex = array_index_regex();
+    let paths: HashSet<String> = corrupt_paths.iter()
+        .map(|path| index_regex.replace_all(path.trim(), "[]").to_string())
+        .collect();
+    corrupt_at(value, "$".to_string(), &paths);
+}
+
+fn corrupt_at(value: &mut Value, path: String, paths: &HashSet<String>) {
+    if paths.contains(&path) {
+        *value = Value::Null;
+        return;
+    }
+    match value {
+        Value::Object(map) => map.iter_mut()
+            .for_each(|(key, val)| corrupt_at(val, format!("{}.{}", path, key), paths)),
+        Value::Array(items) => items.iter_mut()
+            .for_each(|item| corrupt_at(item, format!("{}[]", path), paths)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn corrupt_and_fail_by_rate() {
+        let mut response = json!({"id": 1, "items": [{"sku": "x1", "price": 10}, {"sku": "x2", "price": 20}]});
+        corrupt(&mut response, &["$.id".to_string(), "$.items[0].price".to_string()]);
+        assert_eq!(response, json!({"id": null, "items": [{"sku": "x1", "price": null}, {"sku": "x2", "price": null}]}));
+
+        assert!(!injects_failure(0, 0));
+        assert!(injects_failure(30, 29));
+        assert!(!injects_failure(30, 30));
+        assert!(injects_failure(100, 99));
+
+        let faults: FaultInjection = serde_json::from_value(json!({"failure_rate_percent": 101})).unwrap();
+        assert_eq!(faults.failure_status, 503);
+        assert!(faults.validate().is_err());
+    }
+}
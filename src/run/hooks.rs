@@ -0,0 +1,317 @@
+use crate::action::model::{Action, DigestEncoding, HmacAlgorithm, RequestHook, ResponseHook};
+use crate::http::{HttpRequest, ReqParam};
+use crate::json_path::model::Expression;
+use crate::json_path::utils::evaluate_expression;
+use crate::signing::hex;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::{json, Map, Value};
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Context key the outgoing request is exposed under to pre-request hooks.
+pub const REQUEST_KEY: &str = "request";
+/// Context key the response body is exposed under to post-response hooks.
+pub const RESPONSE_KEY: &str = "response";
+
+/// Runs the pre-request hooks of the action in order, each one seeing what the previous ones did
+/// to the request. A hook that fails is skipped and its error returned, to be recorded on the
+/// execution.
+pub fn apply_request_hooks(action: &Action, request: &mut HttpRequest, context: &Value, now: SystemTime) -> Vec<String> {
+    let mut errors = vec![];
+    for (index, hook) in action.hooks.pre_request.iter().enumerate() {
+        let hook_context = with_entry(context, REQUEST_KEY, describe_request(request, now));
+        if let Err(err) = apply_request_hook(hook, request, &hook_context) {
+            warn!(action = %action.name, error = %err, "could not apply pre-request hook");
+            errors.push(format!("pre-request hook {}: {}", index, err));
+        }
+    }
+    errors
+}
+
+/// Runs post-response hooks over a response body. A hook that fails leaves the body as it was
+/// and its error is returned with the processed body. Takes the hooks rather than the action so
+/// recorded executions are replayed with the hooks they ran with.
+pub fn apply_response_hooks(hooks: &[ResponseHook], body: Value, context: &Map<String, Value>) -> (Value, Vec<String>) {
+    let mut errors = vec![];
+    let processed = hooks.iter().enumerate().fold(body, |body, (index, hook)| {
+        match apply_response_hook(hook, &body, context) {
+            Ok(processed) => processed,
+            Err(err) => {
+                errors.push(format!("post-response hook {}: {}", index, err));
+                body
+            }
+        }
+    });
+    (processed, errors)
+}
+
+fn apply_request_hook(hook: &RequestHook, request: &mut HttpRequest, context: &Value) -> Result<(), String> {
+    match hook {
+        RequestHook::SetHeader { name, value } => {
+            let value = render(value, context)?;
+            set_header(request, name, value)
+        }
+        RequestHook::RemoveHeader { name } => {
+            request.endpoint.headers.retain(|header| !header.key.eq_ignore_ascii_case(name));
+            Ok(())
+        }
+        RequestHook::SetQueryParam { name, value } => {
+            let value = render(value, context)?;
+            request.endpoint.query_params.retain(|param| &param.key != name);
+            request.endpoint.query_params.push(ReqParam::new(name.clone(), value));
+            Ok(())
+        }
+        RequestHook::HmacHeader { name, secret, message, algorithm, encoding } => {
+            let digest = hmac(*algorithm, render(secret, context)?.as_bytes(), render(message, context)?.as_bytes());
+            let signature = match encoding {
+                DigestEncoding::Hex => hex(&digest),
+                DigestEncoding::Base64 => STANDARD.encode(digest),
+            };
+            set_header(request, name, signature)
+        }
+    }
+}
+
+fn apply_response_hook(hook: &ResponseHook, body: &Value, context: &Map<String, Value>) -> Result<Value, String> {
+    let mut processed = body.clone();
+    match hook {
+        ResponseHook::ParseJson { pointer } => {
+            let field = field_at(&mut processed, pointer)?;
+            let parsed = serde_json::from_str(string_at(field, pointer)?).map_err(|err| err.to_string())?;
+            *field = parsed;
+        }
+        ResponseHook::DecodeBase64 { pointer } => {
+            let field = field_at(&mut processed, pointer)?;
+            let decoded = STANDARD.decode(string_at(field, pointer)?.trim()).map_err(|err| err.to_string())?;
+            let decoded = String::from_utf8(decoded).map_err(|_| format!("{} does not decode to text", pointer))?;
+            *field = serde_json::from_str(&decoded).unwrap_or(Value::String(decoded));
+        }
+        ResponseHook::Select { expression } => {
+            return first_match(body, expression);
+        }
+        ResponseHook::SetField { pointer, value } => {
+            let hook_context = with_entry(&Value::Object(context.clone()), RESPONSE_KEY, body.clone());
+            set_field(&mut processed, pointer, render_value(value, &hook_context)?)?;
+        }
+    }
+    Ok(processed)
+}
+
+/// Header names are checked here as the client refuses to send a request with an invalid one.
+fn set_header(request: &mut HttpRequest, name: &str, value: String) -> Result<(), String> {
+    HeaderName::from_bytes(name.as_bytes()).map_err(|_| format!("{} is not a valid header name", name))?;
+    HeaderValue::from_str(&value).map_err(|_| format!("value of header {} is not a valid header value", name))?;
+    request.endpoint.headers.retain(|header| !header.key.eq_ignore_ascii_case(name));
+    request.endpoint.headers.push(ReqParam::new(name.to_string(), value));
+    Ok(())
+}
+
+fn describe_request(request: &HttpRequest, now: SystemTime) -> Value {
+    let url = request.endpoint.to_url();
+    let (path, query) = match reqwest::Url::parse(&url) {
+        Ok(parsed) => (parsed.path().to_string(), parsed.query().unwrap_or_default().to_string()),
+        Err(_) => (String::new(), String::new()),
+    };
+    let headers: Map<String, Value> = request.endpoint.headers.iter()
+        .map(|header| (header.key.to_lowercase(), Value::String(header.value.clone())))
+        .collect();
+    json!({
+        "method": request.endpoint.method.to_string(),
+        "url": url,
+        "path": path,
+        "query": query,
+        "body": request.body_text(),
+        "headers": headers,
+        "timestamp": now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+    })
+}
+
+fn with_entry(context: &Value, key: &str, value: Value) -> Value {
+    let mut extended = context.as_object().cloned().unwrap_or_default();
+    extended.insert(key.to_string(), value);
+    Value::Object(extended)
+}
+
+fn hmac(algorithm: HmacAlgorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+    match algorithm {
+        HmacAlgorithm::Sha256 => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+        HmacAlgorithm::Sha512 => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+            mac.update(message);
+            mac.finalize().into_bytes().to_vec()
+        }
+    }
+}
+
+fn field_at<'a>(body: &'a mut Value, pointer: &str) -> Result<&'a mut Value, String> {
+    body.pointer_mut(pointer).ok_or(format!("nothing found at {}", pointer))
+}
+
+fn string_at<'a>(field: &'a Value, pointer: &str) -> Result<&'a str, String> {
+    field.as_str().ok_or(format!("{} is not a string", pointer))
+}
+
+/// Sets an existing field or adds one to an object; the empty pointer replaces the whole body.
+fn set_field(body: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let Some((parent, token)) = pointer.rsplit_once('/') else {
+        *body = value;
+        return Ok(());
+    };
+    let key = token.replace("~1", "/").replace("~0", "~");
+    match field_at(body, parent)? {
+        Value::Object(fields) => {
+            fields.insert(key, value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let item = key.parse::<usize>().ok()
+                .and_then(|index| items.get_mut(index))
+                .ok_or(format!("nothing found at {}", pointer))?;
+            *item = value;
+            Ok(())
+        }
+        _ => Err(format!("{} is neither an object nor an array", parent)),
+    }
+}
+
+fn first_match(context: &Value, expression: &str) -> Result<Value, String> {
+    evaluate_expression(context, &Expression { value: expression.to_string() })?
+        .into_iter()
+        .next()
+        .ok_or(format!("expression \"{}\" produces empty result", expression))
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Expression(&'a str),
+}
+
+fn parse_template(template: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut segments = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}")
+            .map(|end| start + end)
+            .ok_or(format!("placeholder is not closed in \"{}\"", template))?;
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+        segments.push(Segment::Expression(rest[start + 2..end].trim()));
+        rest = &rest[end + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+/// Expressions of the `{{...}}` placeholders in a template.
+pub fn template_expressions(template: &str) -> Result<Vec<&str>, String> {
+    Ok(parse_template(template)?.into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Expression(expression) => Some(expression),
+            Segment::Text(_) => None,
+        })
+        .collect())
+}
+
+/// Replaces every placeholder with the first match of its expression, strings without quotes.
+pub fn render(template: &str, context: &Value) -> Result<String, String> {
+    parse_template(template)?.into_iter()
+        .map(|segment| match segment {
+            Segment::Text(text) => Ok(text.to_string()),
+            Segment::Expression(expression) => first_match(context, expression)
+                .map(|value| value.as_str().map_or_else(|| value.to_string(), |text| text.to_string())),
+        })
+        .collect()
+}
+
+/// A template that is a single placeholder keeps the type of what it matched.
+fn render_value(template: &str, context: &Value) -> Result<Value, String> {
+    match parse_template(template)?.as_slice() {
+        [Segment::Expression(expression)] => first_match(context, expression),
+        _ => render(template, context).map(Value::String),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::action::model::ActionHooks;
+    use crate::http::{Endpoint, HttpMethod, ReqBody};
+    use std::time::Duration;
+
+    #[test]
+    fn request_hooks_derive_headers_from_the_request() {
+        let action = Action {
+            hooks: ActionHooks {
+                pre_request: vec![
+                    RequestHook::SetHeader { name: "X-Tenant".to_string(), value: "t-{{$.login_0.output.tenant}}".to_string() },
+                    RequestHook::SetQueryParam { name: "ts".to_string(), value: "{{$.request.timestamp}}".to_string() },
+                    RequestHook::HmacHeader {
+                        name: "X-Signature".to_string(),
+                        secret: "{{$.login_0.output.key}}".to_string(),
+                        message: "{{$.request.method}}\n{{$.request.path}}?{{$.request.query}}\n{{$.request.body}}".to_string(),
+                        algorithm: HmacAlgorithm::Sha256,
+                        encoding: DigestEncoding::Hex,
+                    },
+                    RequestHook::RemoveHeader { name: "x-debug".to_string() },
+                    RequestHook::SetHeader { name: "X-Missing".to_string(), value: "{{$.nothing}}".to_string() },
+                ],
+                post_response: vec![],
+            },
+            ..test_action("create_0", 0, "POST", "https://abc.xyz/orders")
+        };
+        let endpoint = Endpoint::new(HttpMethod::POST, "https://abc.xyz/orders".to_string(), vec![], vec![],
+                                     vec![ReqParam::new("X-Debug".to_string(), "1".to_string())]);
+        let mut request = HttpRequest::new(endpoint, ReqBody::new(json!({"qty": 2})), "application/json".to_string());
+        let context = json!({"login_0": {"output": {"tenant": "acme", "key": "s3cr3t"}}});
+
+        let errors = apply_request_hooks(&action, &mut request, &context, UNIX_EPOCH + Duration::from_secs(1700000000));
+
+        let headers: Vec<(&str, &str)> = request.endpoint.headers.iter()
+            .map(|header| (header.key.as_str(), header.value.as_str()))
+            .collect();
+        assert_eq!(headers, vec![
+            ("X-Tenant", "t-acme"),
+            ("X-Signature", "9a0405230532af2dae2df70dd01b61f772b3b650ed8bf432bb72f6952f0cebe9"),
+        ]);
+        assert_eq!(request.endpoint.to_url(), "https://abc.xyz/orders?ts=1700000000");
+        assert_eq!(errors, vec!["pre-request hook 4: expression \"$.nothing\" produces empty result".to_string()]);
+    }
+
+    #[test]
+    fn response_hooks_post_process_the_body() {
+        let action = Action {
+            hooks: ActionHooks {
+                pre_request: vec![],
+                post_response: vec![
+                    ResponseHook::ParseJson { pointer: "/payload".to_string() },
+                    ResponseHook::DecodeBase64 { pointer: "/payload/token".to_string() },
+                    ResponseHook::SetField { pointer: "/payload/user".to_string(), value: "{{$.login_0.output.user}}".to_string() },
+                    ResponseHook::ParseJson { pointer: "/missing".to_string() },
+                    ResponseHook::Select { expression: "$.payload".to_string() },
+                ],
+            },
+            ..test_action("create_0", 0, "POST", "https://abc.xyz/orders")
+        };
+        let body = json!({"payload": "{\"token\": \"eyJzdWIiOiI0MiJ9\"}"});
+        let context: Map<String, Value> = json!({"login_0": {"output": {"user": {"id": 42}}}}).as_object().cloned().unwrap();
+
+        let (processed, errors) = apply_response_hooks(&action.hooks.post_response, body, &context);
+
+        assert_eq!(processed, json!({"token": {"sub": "42"}, "user": {"id": 42}}));
+        assert_eq!(errors, vec!["post-response hook 3: nothing found at /missing".to_string()]);
+        assert_eq!(template_expressions("{{ $.a }}-{{$.b}}").unwrap(), vec!["$.a", "$.b"]);
+        assert!(template_expressions("{{$.a").is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::api::AppError;
+use crate::persistence::repo::{build_composite_key, current_timestamp, from_sdk_error, Table};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use serde::{Deserialize, Serialize};
+use serde_dynamo::aws_sdk_dynamodb_1::to_item;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Locks are renewed by the running executor every [`LOCK_RENEW_INTERVAL`]; one left behind by a
+/// crashed run is considered stale after this long.
+const LOCK_TTL_MILLIS: u64 = 60 * 1000;
+pub const LOCK_RENEW_INTERVAL: Duration = Duration::from_secs(20);
+const LOCK_ID: &str = "run";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunLock {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub id: String,
+    pub run_id: String,
+    pub expires_at: u64,
+}
+
+pub struct RunLockOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct RunLocksTable();
+
+impl Table<RunLock> for RunLocksTable {
+    fn table_name() -> String {
+        "run_locks".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id#test_case_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &RunLock) -> (String, AttributeValue) {
+        Self::partition_key(build_composite_key(vec![entity.customer_id.clone(), entity.test_case_id.clone()]))
+    }
+
+    fn sort_key_from_entity(entity: &RunLock) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl RunLockOperations {
+    /// Returns `false` when another run holds an unexpired lock on the test case.
+    pub async fn acquire(&self, customer_id: &str, test_case_id: &str, run_id: &str) -> Result<bool, AppError> {
+        self.acquire_at(customer_id, test_case_id, run_id, current_timestamp()).await
+    }
+
+    async fn acquire_at(&self, customer_id: &str, test_case_id: &str, run_id: &str, now: u64) -> Result<bool, AppError> {
+        let lock = RunLock {
+            customer_id: customer_id.to_string(),
+            test_case_id: test_case_id.to_string(),
+            id: LOCK_ID.to_string(),
+            run_id: run_id.to_string(),
+            expires_at: now + LOCK_TTL_MILLIS,
+        };
+        let mut item = to_item(lock.clone()).unwrap();
+        RunLocksTable::add_main_key_attributes(&lock, &mut item);
+        let result = self.client.put_item()
+            .table_name(RunLocksTable::qualified_table_name())
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(#pk) OR #expires_at < :now OR #run_id = :run_id")
+            .expression_attribute_names("#pk", RunLocksTable::partition_key_name())
+            .expression_attribute_names("#expires_at", "expires_at")
+            .expression_attribute_names("#run_id", "run_id")
+            .expression_attribute_values(":now", AttributeValue::N(now.to_string()))
+            .expression_attribute_values(":run_id", AttributeValue::S(run_id.to_string()))
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) => Ok(false),
+            Err(err) => Err(from_sdk_error(&RunLocksTable::qualified_table_name(), err)),
+        }
+    }
+
+    /// Extends the lock held by the given run by another TTL. Returns `false` when the run no longer
+    /// holds it, e.g. after it expired and another run took it over.
+    pub async fn renew(&self, customer_id: &str, test_case_id: &str, run_id: &str) -> Result<bool, AppError> {
+        self.renew_at(customer_id, test_case_id, run_id, current_timestamp()).await
+    }
+
+    async fn renew_at(&self, customer_id: &str, test_case_id: &str, run_id: &str, now: u64) -> Result<bool, AppError> {
+        let result = self.client.update_item()
+            .table_name(RunLocksTable::qualified_table_name())
+            .set_key(Some(RunLocksTable::unique_key(
+                build_composite_key(vec![customer_id.to_string(), test_case_id.to_string()]),
+                LOCK_ID.to_string(),
+            )))
+            .update_expression("SET #expires_at = :expires_at")
+            .condition_expression("#run_id = :run_id")
+            .expression_attribute_names("#expires_at", "expires_at")
+            .expression_attribute_names("#run_id", "run_id")
+            .expression_attribute_values(":expires_at", AttributeValue::N((now + LOCK_TTL_MILLIS).to_string()))
+            .expression_attribute_values(":run_id", AttributeValue::S(run_id.to_string()))
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) => Ok(false),
+            Err(err) => Err(from_sdk_error(&RunLocksTable::qualified_table_name(), err)),
+        }
+    }
+
+    /// Releases the lock only if it is still held by the given run.
+    pub async fn release(&self, customer_id: &String, test_case_id: &String, run_id: &String) -> Result<(), AppError> {
+        let result = self.client.delete_item()
+            .table_name(RunLocksTable::qualified_table_name())
+            .set_key(Some(RunLocksTable::unique_key(
+                build_composite_key(vec![customer_id.clone(), test_case_id.clone()]),
+                LOCK_ID.to_string(),
+            )))
+            .condition_expression("#run_id = :run_id")
+            .expression_attribute_names("#run_id", "run_id")
+            .expression_attribute_values(":run_id", AttributeValue::S(run_id.clone()))
+            .send()
+            .await;
+        match result {
+            Ok(_) => Ok(()),
+            Err(err) if err.as_service_error().is_some_and(|err| err.is_conditional_check_failed_exception()) => Ok(()),
+            Err(err) => Err(from_sdk_error(&RunLocksTable::qualified_table_name(), err)),
+        }
+    }
+
+    /// Deletes the lock whichever run holds it, for test cases being deleted.
+    pub async fn delete_all(&self, customer_id: &str, test_case_id: &str) -> Result<Vec<RunLock>, AppError> {
+        RunLocksTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.to_string(), test_case_id.to_string()])).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::repo::{init_logger, Repository};
+
+    #[tokio::test]
+    async fn acquire_renew_expire_and_release() {
+        init_logger();
+        let repository = Repository::new().await;
+        let locks = repository.run_locks();
+        let customer_id = "cust1".to_string();
+        let test_case_id = uuid::Uuid::new_v4().to_string();
+        let (first, second) = ("run1".to_string(), "run2".to_string());
+        let now = current_timestamp();
+
+        assert!(locks.acquire_at(&customer_id, &test_case_id, &first, now).await.unwrap());
+        assert!(locks.acquire_at(&customer_id, &test_case_id, &first, now).await.unwrap());
+        assert!(!locks.acquire_at(&customer_id, &test_case_id, &second, now).await.unwrap());
+        assert!(!locks.renew_at(&customer_id, &test_case_id, &second, now).await.unwrap());
+
+        let after_ttl = now + LOCK_TTL_MILLIS + 1;
+        assert!(locks.renew_at(&customer_id, &test_case_id, &first, now + LOCK_TTL_MILLIS / 2).await.unwrap());
+        assert!(!locks.acquire_at(&customer_id, &test_case_id, &second, after_ttl).await.unwrap());
+
+        let expired = after_ttl + LOCK_TTL_MILLIS;
+        assert!(locks.acquire_at(&customer_id, &test_case_id, &second, expired).await.unwrap());
+        assert!(!locks.renew_at(&customer_id, &test_case_id, &first, expired).await.unwrap());
+
+        locks.release(&customer_id, &test_case_id, &first).await.unwrap();
+        assert!(!locks.acquire_at(&customer_id, &test_case_id, &first, expired).await.unwrap());
+        locks.release(&customer_id, &test_case_id, &second).await.unwrap();
+        assert!(locks.acquire_at(&customer_id, &test_case_id, &first, expired).await.unwrap());
+        locks.release(&customer_id, &test_case_id, &first).await.unwrap();
+    }
+}
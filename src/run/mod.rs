@@ -1,4 +1,14 @@
 pub mod api;
 pub mod model;
 pub mod service;
-pub mod execution;
\ No newline at end of file
+pub mod execution;
+pub mod lock;
+pub mod fault;
+pub mod selection;
+pub mod retention;
+pub mod prefetch;
+pub mod hooks;
+pub mod builtin;
+pub mod setup;
+pub mod resources;
+pub mod replay_har;
@@ -0,0 +1,253 @@
+use crate::action::model::{Action, ActionType};
+use crate::api::AppError;
+use crate::assertion::model::Assertion;
+use crate::auth::model::{for_environment, AuthenticationProvider, ListAuthProvidersRequest};
+use crate::environment::model::UrlRewrite;
+use crate::header_rule::model::HeaderRule;
+use crate::parameter::model::{Parameter, ParameterType};
+use crate::persistence::repo::Repository;
+use crate::run::execution::{auth_provider_override, obtain_base_url};
+use crate::run::model::Run;
+use crate::sensitive_header::model::SensitiveHeaderPolicy;
+use futures::future::try_join_all;
+use std::collections::{HashMap, HashSet};
+use tracing::{error, warn};
+
+/// Everything the actions of a run read from the store, loaded once before the first action so
+/// that executing an action needs no round trips besides its own request.
+pub struct RunResources {
+    /// Every parameter of the test case.
+    pub parameters: Vec<Parameter>,
+    pub assertions: Vec<Assertion>,
+    pub sensitive_headers: SensitiveHeaderPolicy,
+    pub header_rules: Vec<HeaderRule>,
+    pub url_rewrites: Vec<UrlRewrite>,
+    inputs: HashMap<String, Vec<Parameter>>,
+    auth_providers: HashMap<String, Vec<AuthenticationProvider>>,
+}
+
+/// How the auth providers of an action are found, shared by actions on the same base URL.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+enum AuthLookup {
+    Pinned(String),
+    BaseUrl(String),
+}
+
+impl RunResources {
+    /// Runs the queries side by side, one per partition, and one per auth lookup the actions need.
+    pub async fn load(repository: &Repository, run: &Run, actions: &[Action]) -> Result<RunResources, AppError> {
+        let lookups: HashMap<&String, AuthLookup> = actions.iter()
+            .filter(|action| action.action_type != ActionType::Sleep)
+            .map(|action| (&action.id, auth_lookup(run, action)))
+            .collect();
+        let distinct_lookups: HashSet<&AuthLookup> = lookups.values().collect();
+        let (parameter_operations, assertion_operations) = (repository.parameters(), repository.assertions());
+        let (parameters, assertions, sensitive_headers, header_rules, url_rewrites, found_providers) = tokio::join!(
+            parameter_operations.list_all(run.customer_id.clone(), run.test_case_id.clone()),
+            assertion_operations.list_all(&run.customer_id, &run.test_case_id),
+            load_sensitive_headers(repository, &run.customer_id),
+            load_header_rules(repository, run),
+            load_url_rewrites(repository, run),
+            try_join_all(distinct_lookups.into_iter().map(|lookup| async move {
+                find_auth_providers(repository, run, lookup).await.map(|providers| (lookup.clone(), providers))
+            })),
+        );
+        let parameters = parameters?;
+        let found_providers: HashMap<AuthLookup, Vec<AuthenticationProvider>> = found_providers?.into_iter().collect();
+        let mut inputs: HashMap<String, Vec<Parameter>> = HashMap::new();
+        parameters.iter()
+            .filter(|parameter| parameter.parameter_type == ParameterType::Input)
+            .for_each(|parameter| inputs.entry(parameter.action_id.clone()).or_default().push(parameter.clone()));
+        let auth_providers = lookups.into_iter()
+            .map(|(action_id, lookup)| (action_id.clone(), found_providers.get(&lookup).cloned().unwrap_or_default()))
+            .collect();
+        Ok(RunResources {
+            parameters,
+            assertions: assertions?,
+            sensitive_headers,
+            header_rules,
+            url_rewrites,
+            inputs,
+            auth_providers,
+        })
+    }
+
+    pub fn inputs_of(&self, action: &Action) -> &[Parameter] {
+        self.inputs.get(&action.id).map_or(&[], |inputs| inputs.as_slice())
+    }
+
+    pub fn auth_providers_of(&self, action: &Action) -> &[AuthenticationProvider] {
+        self.auth_providers.get(&action.id).map_or(&[], |providers| providers.as_slice())
+    }
+}
+
+/// A run override for the action's base URL wins, then a provider the action is pinned to, so
+/// several identities can share a base URL. Otherwise every provider linked to the test case for
+/// the action's base URL applies. Either way a variant bound to the run's environment replaces
+/// the provider it is a variant of.
+fn auth_lookup(run: &Run, action: &Action) -> AuthLookup {
+    match auth_provider_override(&run.auth_provider_overrides, &action.url).or(action.auth_provider_id.as_ref()) {
+        Some(auth_provider_id) => AuthLookup::Pinned(auth_provider_id.clone()),
+        None => AuthLookup::BaseUrl(obtain_base_url(&action.url)),
+    }
+}
+
+async fn find_auth_providers(repository: &Repository, run: &Run, lookup: &AuthLookup) -> Result<Vec<AuthenticationProvider>, AppError> {
+    match lookup {
+        AuthLookup::Pinned(auth_provider_id) => {
+            let pinned = repository.auth_providers()
+                .get(&run.customer_id, auth_provider_id.clone())
+                .await
+                .unwrap_or_else(|err| {
+                    error!("could not get auth provider {}: {:?}", auth_provider_id, err);
+                    None
+                });
+            let Some(pinned) = pinned else {
+                return Ok(vec![]);
+            };
+            let variants = match &run.environment_id {
+                Some(_) => repository.auth_providers()
+                    .list(ListAuthProvidersRequest::builder()
+                        .customer_id(run.customer_id.clone())
+                        .base_url(pinned.base_url.clone())
+                        .build())
+                    .await?
+                    .items
+                    .into_iter()
+                    .filter(|provider| provider.variant_of.as_ref() == Some(&pinned.id))
+                    .collect(),
+                None => vec![],
+            };
+            Ok(for_environment(std::iter::once(pinned).chain(variants).collect(), run.environment_id.as_deref()))
+        }
+        AuthLookup::BaseUrl(base_url) => Ok(for_environment(repository.auth_providers()
+            .list(ListAuthProvidersRequest::builder()
+                .customer_id(run.customer_id.clone())
+                .test_case_id(run.test_case_id.clone())
+                .base_url(base_url.clone())
+                .build())
+            .await?
+            .items, run.environment_id.as_deref())),
+    }
+}
+
+async fn load_sensitive_headers(repository: &Repository, customer_id: &String) -> SensitiveHeaderPolicy {
+    repository.sensitive_headers()
+        .get(customer_id)
+        .await
+        .unwrap_or_else(|err| {
+            error!("could not load sensitive header policy: {:?}", err);
+            SensitiveHeaderPolicy::default_for(customer_id)
+        })
+}
+
+async fn load_header_rules(repository: &Repository, run: &Run) -> Vec<HeaderRule> {
+    repository.header_rules()
+        .list_applicable(&run.customer_id, &run.test_case_id)
+        .await
+        .unwrap_or_else(|err| {
+            error!("could not list header rules: {:?}", err);
+            vec![]
+        })
+}
+
+async fn load_url_rewrites(repository: &Repository, run: &Run) -> Vec<UrlRewrite> {
+    let Some(environment_id) = &run.environment_id else {
+        return vec![];
+    };
+    match repository.environments().get(&run.customer_id, environment_id).await {
+        Ok(Some(environment)) => environment.url_rewrites,
+        Ok(None) => {
+            warn!(environment_id = %environment_id, "environment of the run no longer exists");
+            vec![]
+        }
+        Err(err) => {
+            error!("could not get environment {}: {:?}", environment_id, err);
+            vec![]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::persistence::repo::init_logger;
+    use crate::run::model::RunStatus;
+
+    #[test]
+    fn auth_lookup_prefers_override_then_pinned_provider_then_base_url() {
+        let run = |overrides: HashMap<String, String>| Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .status(RunStatus::InProgress)
+            .started_at(1000)
+            .auth_provider_overrides(overrides)
+            .build();
+        let unpinned = test_action("list", 0, "GET", "https://abc.xyz/items?page=1");
+        let pinned = Action { auth_provider_id: Some("admin".to_string()), ..unpinned.clone() };
+        let overridden = run(HashMap::from([("https://abc.xyz/".to_string(), "readonly".to_string())]));
+
+        assert_eq!(auth_lookup(&overridden, &pinned), AuthLookup::Pinned("readonly".to_string()));
+        assert_eq!(auth_lookup(&overridden, &unpinned), AuthLookup::Pinned("readonly".to_string()));
+        assert_eq!(auth_lookup(&run(HashMap::new()), &pinned), AuthLookup::Pinned("admin".to_string()));
+        assert_eq!(auth_lookup(&run(HashMap::new()), &unpinned), AuthLookup::BaseUrl("https://abc.xyz".to_string()));
+
+        let other_host = run(HashMap::from([("https://other.xyz".to_string(), "readonly".to_string())]));
+        assert_eq!(auth_lookup(&other_host, &pinned), AuthLookup::Pinned("admin".to_string()));
+        assert_eq!(auth_lookup(&other_host, &unpinned), AuthLookup::BaseUrl("https://abc.xyz".to_string()));
+    }
+
+    #[tokio::test]
+    async fn pinned_providers_resolve_to_the_variant_of_the_run_environment() {
+        init_logger();
+        let repository = Repository::new().await;
+        let customer_id = "cust1".to_string();
+        let base = repository.auth_providers()
+            .create(AuthenticationProvider::builder()
+                .customer_id(customer_id.clone())
+                .name("api".to_string())
+                .base_url("https://variants.xyz".to_string())
+                .headers_by_name(HashMap::new())
+                .linked_test_case_ids(HashSet::from(["tc1".to_string()]))
+                .build())
+            .await
+            .unwrap();
+        let staging = repository.auth_providers()
+            .create(AuthenticationProvider {
+                id: uuid::Uuid::new_v4().to_string(),
+                environment_id: Some("env-staging".to_string()),
+                variant_of: Some(base.id.clone()),
+                ..base.clone()
+            })
+            .await
+            .unwrap();
+        let run = |environment_id: Option<&str>| Run::builder()
+            .customer_id(customer_id.clone())
+            .test_case_id("tc1".to_string())
+            .status(RunStatus::InProgress)
+            .started_at(1000)
+            .maybe_environment_id(environment_id.map(str::to_string))
+            .build();
+        let found = |run: Run, lookup: AuthLookup| {
+            let repository = &repository;
+            async move {
+                find_auth_providers(repository, &run, &lookup).await.unwrap()
+                    .into_iter()
+                    .map(|provider| provider.id)
+                    .collect::<Vec<_>>()
+            }
+        };
+        let pinned = AuthLookup::Pinned(base.id.clone());
+        let by_url = AuthLookup::BaseUrl("https://variants.xyz".to_string());
+
+        assert_eq!(found(run(Some("env-staging")), pinned.clone()).await, vec![staging.id.clone()]);
+        assert_eq!(found(run(Some("env-prod")), pinned.clone()).await, vec![base.id.clone()]);
+        assert_eq!(found(run(None), pinned).await, vec![base.id.clone()]);
+        assert_eq!(found(run(Some("env-staging")), by_url.clone()).await, vec![staging.id.clone()]);
+        assert_eq!(found(run(None), by_url).await, vec![base.id.clone()]);
+
+        repository.auth_providers().delete(&customer_id, staging.id).await.unwrap();
+        repository.auth_providers().delete(&customer_id, base.id).await.unwrap();
+    }
+}
@@ -0,0 +1,184 @@
+use crate::action::model::Action;
+use crate::action_execution::model::ActionExecution;
+use crate::api::AppError;
+use crate::persistence::repo::Repository;
+use crate::run::builtin::iso_8601;
+use har::v1_2::{Content, Creator, Entries, Headers, Log, PostData, QueryString, Request, Response, Timings};
+use har::{Har, Spec};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The run's requests and responses as a HAR, one entry per action execution in the order they
+/// started, to load into browser devtools or hand to the owners of an API. Headers are as masked
+/// as the executions store them; executions of actions deleted since are left out.
+pub async fn build_run_har(repository: &Repository, customer_id: &String, run_id: &String) -> Result<Har, AppError> {
+    let run = repository.runs()
+        .find_by_id(customer_id, run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let case_base_url = repository.test_cases()
+        .get(run.customer_id.clone(), run.test_case_id.clone())
+        .await?
+        .and_then(|test_case| test_case.base_url);
+    let actions: HashMap<String, Action> = repository.actions()
+        .list_all(run.customer_id.clone(), run.test_case_id.clone())
+        .await?
+        .into_iter()
+        .map(|action| (action.id.clone(), action))
+        .collect();
+    let mut executions = repository.action_executions()
+        .list_with_bodies(&run.customer_id, &run.test_case_id, &run.id)
+        .await?;
+    executions.sort_by_key(|execution| execution.started_at);
+    let entries = executions.iter()
+        .filter_map(|execution| actions.get(&execution.action_id)
+            .map(|action| build_entry(action, execution, case_base_url.as_deref())))
+        .collect();
+    Ok(Har {
+        log: Spec::V1_2(Log {
+            creator: Creator {
+                name: "parroton".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                comment: None,
+            },
+            entries,
+            comment: Some(format!("run {} of test case {}", run.id, run.test_case_id)),
+            ..Log::default()
+        }),
+    })
+}
+
+/// Executions stored before the request URL was recorded get the action's URL and query parameters.
+fn build_entry(action: &Action, execution: &ActionExecution, case_base_url: Option<&str>) -> Entries {
+    let started_at = execution.started_at.unwrap_or_default();
+    let time = execution.finished_at.unwrap_or(started_at).saturating_sub(started_at) as f64;
+    let url = execution.request_url.clone().unwrap_or_else(|| {
+        let url = action.absolute_url(case_base_url);
+        let query: Vec<String> = execution.query_params.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+        if query.is_empty() { url } else { format!("{}?{}", url, query.join("&")) }
+    });
+    let request_mime_type = header_value(&execution.request_headers, "content-type")
+        .or(action.mime_type.clone())
+        .unwrap_or("application/json".to_string());
+    let response_text = execution.response_body.as_ref().map(body_text);
+    Entries {
+        started_date_time: iso_8601(started_at),
+        time,
+        request: Request {
+            method: action.method.to_uppercase(),
+            url,
+            http_version: "HTTP/1.1".to_string(),
+            headers: to_headers(&execution.request_headers),
+            query_string: execution.query_params.iter()
+                .map(|(name, value)| QueryString { name: name.clone(), value: value.clone(), comment: None })
+                .collect(),
+            post_data: execution.request_body.as_ref()
+                .filter(|body| !body.is_null())
+                .map(|body| PostData { mime_type: request_mime_type, text: Some(body_text(body)), ..PostData::default() }),
+            headers_size: -1,
+            body_size: -1,
+            ..Request::default()
+        },
+        response: Response {
+            status: execution.status_code as i64,
+            status_text: StatusCode::from_u16(execution.status_code).ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or_default()
+                .to_string(),
+            http_version: "HTTP/1.1".to_string(),
+            headers: to_headers(&execution.response_headers),
+            content: Content {
+                size: response_text.as_ref().map_or(0, |text| text.len() as i64),
+                mime_type: Some(header_value(&execution.response_headers, "content-type").unwrap_or("application/json".to_string())),
+                text: response_text,
+                ..Content::default()
+            },
+            redirect_url: Some(execution.redirects.last().map(|hop| hop.location.clone()).unwrap_or_default()),
+            headers_size: -1,
+            body_size: -1,
+            ..Response::default()
+        },
+        timings: Timings {
+            send: 0.0,
+            wait: time,
+            receive: 0.0,
+            ..Timings::default()
+        },
+        comment: execution.error.clone(),
+        ..Entries::default()
+    }
+}
+
+fn to_headers(headers: &[(String, String)]) -> Vec<Headers> {
+    headers.iter()
+        .map(|(name, value)| Headers { name: name.clone(), value: value.clone(), comment: None })
+        .collect()
+}
+
+fn header_value(headers: &[(String, String)], name: &str) -> Option<String> {
+    headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+/// Strings are sent as they are, anything else as JSON.
+fn body_text(body: &Value) -> String {
+    match body {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::http::RedirectHop;
+    use serde_json::json;
+
+    #[test]
+    fn renders_execution_as_har_entry() {
+        let action = test_action("orders_0", 0, "post", "/orders");
+        let execution = ActionExecution::builder()
+            .run_id("r1".to_string())
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action.id.clone())
+            .status_code(201)
+            .request_body(json!({"item": "book"}))
+            .query_params(vec![("dry_run".to_string(), "true".to_string())])
+            .request_headers(vec![("Authorization".to_string(), "Bear****".to_string())])
+            .response_body(json!({"id": 7}))
+            .response_headers(vec![("Content-Type".to_string(), "application/json; charset=utf-8".to_string())])
+            .redirects(vec![RedirectHop { status_code: 302, url: "https://abc.xyz/orders".to_string(), location: "/orders/7".to_string() }])
+            .started_at(1_709_296_200_000)
+            .finished_at(1_709_296_200_250)
+            .build();
+
+        let entry = build_entry(&action, &execution, Some("https://abc.xyz"));
+
+        assert_eq!(entry.started_date_time, "2024-03-01T12:30:00Z");
+        assert_eq!((entry.time, entry.timings.wait), (250.0, 250.0));
+        assert_eq!((entry.request.method.as_str(), entry.request.url.as_str()), ("POST", "https://abc.xyz/orders?dry_run=true"));
+        assert_eq!(entry.request.headers[0].value, "Bear****");
+        assert_eq!(entry.request.post_data.as_ref().map(|post_data| (post_data.mime_type.as_str(), post_data.text.as_deref())),
+                   Some(("application/json", Some("{\"item\":\"book\"}"))));
+        assert_eq!((entry.response.status, entry.response.status_text.as_str()), (201, "Created"));
+        assert_eq!(entry.response.content.text.as_deref(), Some("{\"id\":7}"));
+        assert_eq!(entry.response.content.mime_type.as_deref(), Some("application/json; charset=utf-8"));
+        assert_eq!(entry.response.redirect_url.as_deref(), Some("/orders/7"));
+
+        let failed = ActionExecution {
+            status_code: 0,
+            error: Some("connection refused".to_string()),
+            request_url: Some("https://staging.abc.xyz/orders".to_string()),
+            response_body: None,
+            ..execution
+        };
+        let entry = build_entry(&action, &failed, Some("https://abc.xyz"));
+        assert_eq!(entry.request.url, "https://staging.abc.xyz/orders");
+        assert_eq!((entry.response.status_text.as_str(), entry.comment.as_deref()), ("", Some("connection refused")));
+        assert_eq!(entry.response.content.text, None);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::action::model::{Action, ResourceRequest};
+use crate::api::AppError;
+use crate::environment::model::UrlRewrite;
+use crate::environment::remap::remap_url;
+use crate::header_rule::injection::apply_header_rules;
+use crate::http::{percent_encode, ApiClient, Endpoint, HttpError, HttpMethod, HttpRequest, ReqBody};
+use crate::json_path::model::Expression;
+use crate::json_path::utils::evaluate_expression;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::run::execution::apply_provider_credentials;
+use crate::run::model::CreatedResource;
+use crate::run::prefetch::RunResources;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tracing::{info, warn};
+
+/// The resource the action created, read from its output. Nothing is recorded for an action that
+/// does not track resources or when its output holds no id.
+pub fn track_resource(action: &Action, output: &Value, case_base_url: Option<&str>, url_rewrites: &[UrlRewrite]) -> Option<CreatedResource> {
+    let tracking = action.creates_resource.as_ref()?;
+    let expression = Expression { value: tracking.id_expression.clone() };
+    let id = match evaluate_expression(output, &expression).ok()?.into_iter().next()? {
+        Value::String(id) => id,
+        Value::Number(id) => id.to_string(),
+        other => {
+            warn!(action = %action.name, "{} is not a resource id", other);
+            return None;
+        }
+    };
+    let teardown = tracking.teardown.as_ref().map(|teardown| ResourceRequest {
+        method: teardown.method.to_uppercase(),
+        url: remap_url(&action.resolve_url(&teardown.url.replace("{id}", &percent_encode(&id)), case_base_url), url_rewrites),
+    });
+    Some(CreatedResource {
+        action_id: action.id.clone(),
+        action_name: action.name.clone(),
+        kind: tracking.kind.clone().unwrap_or(action.name.clone()),
+        id,
+        teardown,
+        deleted_at: None,
+        teardown_error: None,
+    })
+}
+
+pub async fn list_resources(repository: &Repository, customer_id: &String, run_id: &String) -> Result<Vec<CreatedResource>, AppError> {
+    repository.runs()
+        .find_by_id(customer_id, run_id)
+        .await?
+        .map(|run| run.resources)
+        .ok_or(AppError::NotFound("Run not found!".to_string()))
+}
+
+/// Sends the teardown request of every resource not deleted yet, with the credentials of the auth
+/// providers the run would use for the request. A resource that is already gone, answering 404 or
+/// 410, counts as deleted. Returns the manifest as updated.
+pub async fn teardown_resources(repository: &Repository, client: &ApiClient, customer_id: &String, run_id: &String) -> Result<Vec<CreatedResource>, AppError> {
+    let run = repository.runs()
+        .find_by_id(customer_id, run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let actions: HashMap<String, Action> = repository.actions()
+        .list_all(run.customer_id.clone(), run.test_case_id.clone())
+        .await?
+        .into_iter()
+        .map(|action| (action.id.clone(), action))
+        .collect();
+    // auth providers are looked up by the URL the teardown request goes to
+    let mut teardown_actions: HashMap<String, Action> = HashMap::new();
+    run.resources.iter()
+        .filter(|resource| resource.deleted_at.is_none())
+        .filter_map(|resource| resource.teardown.as_ref()
+            .zip(actions.get(&resource.action_id))
+            .map(|(teardown, action)| Action { url: teardown.url.clone(), ..action.clone() }))
+        .for_each(|action| {
+            teardown_actions.entry(action.id.clone()).or_insert(action);
+        });
+    let teardown_actions: Vec<Action> = teardown_actions.into_values().collect();
+    let run_resources = RunResources::load(repository, &run, &teardown_actions).await?;
+    let mut resources = run.resources.clone();
+    for resource in resources.iter_mut().filter(|resource| resource.deleted_at.is_none()) {
+        let Some(teardown) = &resource.teardown else {
+            continue;
+        };
+        // a resource that cannot be torn down does not keep the others from being torn down
+        let method = match HttpMethod::from_str(&teardown.method) {
+            Ok(method) => method,
+            Err(err) => {
+                warn!(kind = %resource.kind, id = %resource.id, "could not delete resource: {}", err);
+                resource.teardown_error = Some(err);
+                continue;
+            }
+        };
+        let action = teardown_actions.iter().find(|action| action.id == resource.action_id);
+        let auth_providers = action.map_or(&[][..], |action| run_resources.auth_providers_of(action));
+        let mut headers = vec![];
+        apply_provider_credentials(&mut headers, auth_providers, &run_resources.sensitive_headers);
+        apply_header_rules(&mut headers, &run_resources.header_rules);
+        let request = HttpRequest::new(Endpoint::new(method, teardown.url.clone(), vec![], vec![], headers), ReqBody::empty(), "application/json".to_string())
+            .with_signing(auth_providers.iter().find_map(|provider| provider.signing.clone()));
+        match client.execute(request).await {
+            Ok(_) | Err(HttpError::Status(404 | 410, _)) => {
+                info!(kind = %resource.kind, id = %resource.id, "deleted resource");
+                resource.deleted_at = Some(current_timestamp());
+                resource.teardown_error = None;
+            }
+            Err(err) => {
+                warn!(kind = %resource.kind, id = %resource.id, "could not delete resource: {}", err.get_message());
+                resource.teardown_error = Some(err.get_message());
+            }
+        }
+    }
+    repository.runs()
+        .update_resources(&run.customer_id, &run.test_case_id, &run.id, &resources)
+        .await?;
+    Ok(resources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::action::model::ResourceTracking;
+    use serde_json::json;
+
+    #[test]
+    fn tracks_created_resource() {
+        let action = Action {
+            creates_resource: Some(ResourceTracking {
+                id_expression: "$.order.id".to_string(),
+                kind: Some("order".to_string()),
+                teardown: Some(ResourceRequest { method: "delete".to_string(), url: "/orders/{id}".to_string() }),
+            }),
+            ..test_action("orders_0", 0, "POST", "https://abc.xyz/orders")
+        };
+        let rewrites = vec![UrlRewrite { from: "https://abc.xyz".to_string(), to: "https://staging.abc.xyz".to_string() }];
+
+        let resource = track_resource(&action, &json!({"order": {"id": "o 1"}}), Some("https://abc.xyz"), &rewrites).unwrap();
+
+        assert_eq!((resource.kind.as_str(), resource.id.as_str()), ("order", "o 1"));
+        assert_eq!(resource.teardown, Some(ResourceRequest { method: "DELETE".to_string(), url: "https://staging.abc.xyz/orders/o%201".to_string() }));
+        assert_eq!(track_resource(&action, &json!({"order": {"id": 42}}), None, &[]).map(|resource| resource.id), Some("42".to_string()));
+        assert!(track_resource(&action, &json!({"error": "invalid"}), None, &[]).is_none());
+        let untracked = Action { creates_resource: None, ..action };
+        assert!(track_resource(&untracked, &json!({"order": {"id": "o1"}}), None, &[]).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::action::model::{Action, ActionType};
+use crate::assertion::model::{Assertion, ComparisonType};
+use crate::case::model::ContextRetention;
+use crate::parameter::model::Parameter;
+use crate::run::selection::dependencies_of;
+use crate::template::library::referenced_action_names_of_assertion;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Action entries to drop from the run context after each action, keyed by the action's position.
+#[derive(Debug, Default, PartialEq)]
+pub struct RetentionPlan {
+    drops: HashMap<usize, Vec<String>>,
+}
+
+impl RetentionPlan {
+    /// Works out when every entry is read for the last time; `actions` are in run order. Entries
+    /// that assertions read are kept, as assertions are checked once all actions ran.
+    pub fn new(retention: &ContextRetention, actions: &[Action], parameters: &[Parameter], assertions: &[Assertion]) -> Self {
+        let keep_for = match retention {
+            ContextRetention::All => return RetentionPlan::default(),
+            ContextRetention::Referenced => 0,
+            ContextRetention::LastActions { count } => *count,
+        };
+        let asserted: HashSet<String> = assertions.iter()
+            .flat_map(|assertion| asserted_names(assertion, actions))
+            .collect();
+        let mut last_use: HashMap<String, usize> = HashMap::new();
+        for (index, action) in actions.iter().enumerate() {
+            dependencies_of(action, parameters).into_iter().for_each(|name| {
+                last_use.insert(name, index);
+            });
+        }
+        let mut drops: HashMap<usize, Vec<String>> = HashMap::new();
+        for (index, action) in actions.iter().enumerate() {
+            if action.action_type == ActionType::Sleep || asserted.contains(&action.name) {
+                continue;
+            }
+            let drop_after = (index + keep_for).max(last_use.get(&action.name).copied().unwrap_or(index));
+            if drop_after < actions.len() {
+                drops.entry(drop_after).or_default().push(action.name.clone());
+            }
+        }
+        RetentionPlan { drops }
+    }
+
+    /// Drops what is no longer needed once the action at `index` executed.
+    pub fn apply(&self, index: usize, context: &mut Map<String, Value>) {
+        self.drops.get(&index).into_iter().flatten().for_each(|name| {
+            context.shift_remove(name);
+        });
+    }
+}
+
+fn asserted_names(assertion: &Assertion, actions: &[Action]) -> HashSet<String> {
+    if assertion.comparison_type == ComparisonType::MatchesSnapshot {
+        return assertion.snapshot_action_id.iter()
+            .filter_map(|id| actions.iter().find(|action| &action.id == id))
+            .map(|action| action.name.clone())
+            .collect();
+    }
+    referenced_action_names_of_assertion(assertion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::model::AssertionItem;
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use serde_json::json;
+
+    fn input(action: &Action, expression: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action.id.clone())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Query("id".to_string()))
+            .value(json!(""))
+            .value_expression(Expression { value: expression.to_string() })
+            .build()
+    }
+
+    fn context_after_each(plan: &RetentionPlan, actions: &[Action]) -> Vec<Vec<String>> {
+        let mut context = Map::new();
+        actions.iter().enumerate()
+            .map(|(index, action)| {
+                context.insert(action.name.clone(), json!({}));
+                plan.apply(index, &mut context);
+                context.keys().cloned().collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn referenced_entries_live_until_their_last_use() {
+        let actions = vec![
+            test_action("login_0", 0, "GET", "https://abc.xyz/login"),
+            test_action("list_1", 1, "GET", "https://abc.xyz/items"),
+            test_action("get_2", 2, "GET", "https://abc.xyz/items/1"),
+            test_action("logout_3", 3, "GET", "https://abc.xyz/logout"),
+        ];
+        let parameters = vec![
+            input(&actions[1], "$.login_0.output.token"),
+            input(&actions[3], "$.login_0.output.token"),
+            input(&actions[2], "$.list_1.output.items[0].id"),
+        ];
+        let assertions = vec![Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.get_2.output.name".to_string() }))
+            .right(AssertionItem::from_value(json!("a")))
+            .comparison_type(ComparisonType::EqualTo)
+            .build()];
+
+        let referenced = RetentionPlan::new(&ContextRetention::Referenced, &actions, &parameters, &assertions);
+        assert_eq!(context_after_each(&referenced, &actions), vec![
+            vec!["login_0"],
+            vec!["login_0", "list_1"],
+            vec!["login_0", "get_2"],
+            vec!["get_2"],
+        ]);
+
+        let last_one = RetentionPlan::new(&ContextRetention::LastActions { count: 1 }, &actions, &parameters, &assertions);
+        assert_eq!(context_after_each(&last_one, &actions)[3], vec!["get_2", "logout_3"]);
+        assert_eq!(RetentionPlan::new(&ContextRetention::All, &actions, &parameters, &assertions), RetentionPlan::default());
+    }
+}
@@ -0,0 +1,170 @@
+use crate::action::model::Action;
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, ComparisonType};
+use crate::parameter::model::{Parameter, ParameterType};
+pub use parroton_core::run::ActionSelection;
+use crate::template::library::{referenced_action_names, referenced_action_names_of_assertion};
+use std::collections::HashSet;
+
+/// Actions of a partial run in order. Unknown names and ids are rejected, and so is a selected
+/// action reading from an action that is neither selected nor in `seeded`. Teardown actions are
+/// kept whenever what they read from is available, as they clean up after any run.
+pub fn select_actions(actions: &[Action], parameters: &[Parameter], selection: &ActionSelection, seeded: &HashSet<String>) -> Result<Vec<Action>, AppError> {
+    let mut selected = apply_selection(actions, selection)?;
+    let selected_names: HashSet<String> = selected.iter()
+        .filter(|action| !action.teardown)
+        .map(|action| action.name.clone())
+        .collect();
+    let known_names: HashSet<&str> = actions.iter().map(|action| action.name.as_str()).collect();
+    selected.retain(|action| !action.teardown || dependencies_of(action, parameters).iter()
+        .all(|name| !known_names.contains(name.as_str()) || selected_names.contains(name) || seeded.contains(name)));
+    let selected_names: HashSet<&str> = selected.iter().map(|action| action.name.as_str()).collect();
+    let mut missing = vec![];
+    for action in &selected {
+        let mut dependencies: Vec<String> = dependencies_of(action, parameters).into_iter()
+            .filter(|name| known_names.contains(name.as_str()))
+            .filter(|name| !selected_names.contains(name.as_str()) && !seeded.contains(name))
+            .collect();
+        dependencies.sort();
+        missing.extend(dependencies.into_iter().map(|name| format!("{} needs {}", action.name, name)));
+    }
+    if !missing.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Actions left out of the run are neither included nor seeded: {}", missing.join(", "))));
+    }
+    Ok(selected)
+}
+
+/// Narrows the actions down to the selection and the teardown actions without looking at
+/// dependencies, as resuming a partial run does. `actions` must be sorted.
+pub fn apply_selection(actions: &[Action], selection: &ActionSelection) -> Result<Vec<Action>, AppError> {
+    if selection.is_empty() {
+        return Ok(actions.to_vec());
+    }
+    let position_of = |name: &String| actions.iter()
+        .position(|action| &action.name == name)
+        .ok_or(AppError::Validation(format!("Unknown action: {}", name)));
+    let in_range = match (&selection.start_action, &selection.end_action) {
+        (None, None) => None,
+        (start, end) => {
+            let start = start.as_ref().map(position_of).transpose()?.unwrap_or(0);
+            let end = end.as_ref().map(position_of).transpose()?.unwrap_or(actions.len().saturating_sub(1));
+            if start > end {
+                return Err(AppError::Validation(format!(
+                    "start_action {} comes after end_action {}", actions[start].name, actions[end].name)));
+            }
+            Some(start..=end)
+        }
+    };
+    if let Some(unknown) = selection.include_action_ids.iter().find(|id| !actions.iter().any(|action| &action.id == *id)) {
+        return Err(AppError::Validation(format!("Unknown action id: {}", unknown)));
+    }
+    Ok(actions.iter()
+        .enumerate()
+        .filter(|(index, action)| in_range.as_ref().is_some_and(|range| range.contains(index))
+            || selection.include_action_ids.contains(&action.id)
+            || action.teardown)
+        .map(|(_, action)| action.clone())
+        .collect())
+}
+
+/// Moves teardown actions behind the others, keeping the order within both phases.
+pub fn teardown_last(actions: Vec<Action>) -> Vec<Action> {
+    let (teardown, mut in_order): (Vec<Action>, Vec<Action>) = actions.into_iter().partition(|action| action.teardown);
+    in_order.extend(teardown);
+    in_order
+}
+
+/// Only assertions whose every referenced action ran or was seeded are checked in a partial run.
+pub fn is_checkable(assertion: &Assertion, available_names: &HashSet<String>, actions: &[Action]) -> bool {
+    if assertion.comparison_type == ComparisonType::MatchesSnapshot {
+        return assertion.snapshot_action_id.as_ref()
+            .and_then(|id| actions.iter().find(|action| &action.id == id))
+            .is_some_and(|action| available_names.contains(&action.name));
+    }
+    referenced_action_names_of_assertion(assertion).is_subset(available_names)
+}
+
+pub(crate) fn dependencies_of(action: &Action, parameters: &[Parameter]) -> HashSet<String> {
+    let from_parameters = parameters.iter()
+        .filter(|parameter| parameter.action_id == action.id && parameter.parameter_type == ParameterType::Input)
+        .filter_map(|parameter| parameter.value_expression.as_ref())
+        .flat_map(|expression| referenced_action_names(&expression.value));
+    from_parameters
+        .chain(referenced_action_names(&action.url))
+        .filter(|name| name != &action.name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::ParameterLocation;
+    use serde_json::json;
+
+    fn input(action_id: &str, expression: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(action_id.to_string())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Header("Authorization".to_string()))
+            .value(json!("Bearer x"))
+            .value_expression(Expression { value: expression.to_string() })
+            .build()
+    }
+
+    #[test]
+    fn select_range_and_included_actions() {
+        let actions = vec![
+            test_action("login_0", 0, "GET", "/login"),
+            test_action("create_1", 1, "GET", "/orders"),
+            test_action("list_2", 2, "GET", "/orders"),
+            test_action("get_3", 3, "GET", "/orders/$.create_1.output.id"),
+        ];
+        let parameters = vec![input("get_3_id", "$.login_0.output.token")];
+        let names = |selected: &[Action]| selected.iter().map(|action| action.name.clone()).collect::<Vec<String>>();
+        let range = ActionSelection {
+            start_action: Some("list_2".to_string()),
+            end_action: None,
+            include_action_ids: vec!["login_0_id".to_string()],
+        };
+
+        let missing = select_actions(&actions, &parameters, &range, &HashSet::new());
+        assert!(matches!(missing, Err(AppError::Validation(message)) if message.contains("get_3 needs create_1")));
+
+        let seeded = HashSet::from(["create_1".to_string()]);
+        let selected = select_actions(&actions, &parameters, &range, &seeded).unwrap();
+        assert_eq!(names(&selected), vec!["login_0", "list_2", "get_3"]);
+
+        let up_to = ActionSelection { end_action: Some("create_1".to_string()), ..Default::default() };
+        assert_eq!(names(&select_actions(&actions, &parameters, &up_to, &HashSet::new()).unwrap()), vec!["login_0", "create_1"]);
+        assert_eq!(select_actions(&actions, &parameters, &ActionSelection::default(), &HashSet::new()).unwrap().len(), 4);
+
+        let reversed = ActionSelection { start_action: Some("get_3".to_string()), end_action: Some("login_0".to_string()), ..Default::default() };
+        assert!(apply_selection(&actions, &reversed).is_err());
+        let unknown = ActionSelection { include_action_ids: vec!["nope".to_string()], ..Default::default() };
+        assert!(apply_selection(&actions, &unknown).is_err());
+    }
+
+    #[test]
+    fn teardown_actions_run_last_when_their_inputs_are_available() {
+        let teardown = |name: &str, order: usize, url: &str| Action { teardown: true, ..test_action(name, order, "GET", url) };
+        let actions = vec![
+            test_action("create_0", 0, "GET", "/orders"),
+            teardown("delete_1", 1, "/orders/$.create_0.output.id"),
+            test_action("list_2", 2, "GET", "/orders"),
+            teardown("logout_3", 3, "/logout"),
+        ];
+        let names = |selected: &[Action]| selected.iter().map(|action| action.name.clone()).collect::<Vec<String>>();
+
+        assert_eq!(names(&teardown_last(actions.clone())), vec!["create_0", "list_2", "delete_1", "logout_3"]);
+
+        let only_list = ActionSelection { start_action: Some("list_2".to_string()), end_action: Some("list_2".to_string()), ..Default::default() };
+        assert_eq!(names(&select_actions(&actions, &[], &only_list, &HashSet::new()).unwrap()), vec!["list_2", "logout_3"]);
+        let seeded = HashSet::from(["create_0".to_string()]);
+        assert_eq!(names(&select_actions(&actions, &[], &only_list, &seeded).unwrap()), vec!["delete_1", "list_2", "logout_3"]);
+    }
+}
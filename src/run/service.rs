@@ -1,10 +1,9 @@
 use crate::api::AppError;
 use crate::assertion::model::AssertionResult;
-use crate::persistence::model::QueryResult;
-use crate::persistence::repo::OnDeleteMessage::RunDeleted;
-use crate::persistence::repo::{build_composite_key, current_timestamp, OnDeleteMessage, Table};
-use crate::run::model::{Run, RunStatus};
-use aws_sdk_dynamodb::types::AttributeValue;
+use crate::persistence::model::{QueryResult, SortOrder, View};
+use crate::persistence::repo::{build_composite_key, current_timestamp, from_sdk_error, projection_expression, Table};
+use crate::run::model::{CreatedResource, Run, RunStatus, Verdict};
+use aws_sdk_dynamodb::types::{AttributeValue, Select};
 use aws_sdk_dynamodb::Client;
 use serde_dynamo::aws_sdk_dynamodb_1::to_attribute_value;
 use std::cmp::Ordering;
@@ -17,6 +16,23 @@ pub struct RunOperations {
 
 pub struct RunTable();
 
+/// Everything but assertion results and the run's request overrides.
+const SUMMARY_ATTRIBUTES: [&str; 14] = [
+    "customer_id", "test_case_id", "id", "status", "started_at", "finished_at", "verdict",
+    "trace_id", "mock", "environment_id", "trigger", "labels", "created_at", "updated_at",
+];
+
+type FilterExpression = (String, HashMap<String, String>, HashMap<String, AttributeValue>);
+
+const TRIGGER_TYPES: [&str; 4] = ["manual", "schedule", "ci", "api_key"];
+
+/// Narrows a run list down to one trigger type and to runs carrying every given label.
+#[derive(Clone, Debug, Default)]
+pub struct RunFilter {
+    pub trigger: Option<String>,
+    pub labels: Vec<String>,
+}
+
 impl Table<Run> for RunTable {
     fn table_name() -> String {
         "runs".to_string()
@@ -48,10 +64,6 @@ impl Table<Run> for RunTable {
         );
     }
 
-    fn build_deleted_event(entity: Run) -> Option<OnDeleteMessage> {
-        Some(RunDeleted(entity))
-    }
-
     fn ordering(e1: &Run, e2: &Run) -> Ordering {
         e2.started_at.cmp(&e1.started_at)
     }
@@ -74,14 +86,135 @@ impl RunOperations {
             id.clone(),
         ).await
     }
-    pub async fn list(&self, customer_id: &String, test_case_id: &String) -> Result<QueryResult<Run>, AppError> {
+    /// Pages through runs on `started_at_index` (customer_id#test_case_id, started_at) so the order
+    /// holds across pages.
+    pub async fn list(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        view: View,
+        order: SortOrder,
+        filter: RunFilter,
+        next_page_key: Option<String>,
+    ) -> Result<QueryResult<Run>, AppError> {
+        let filter = list_filter(filter)?;
+        let (projection, mut names) = match view {
+            View::Full => (None, HashMap::new()),
+            View::Summary => {
+                let (expression, names) = summary_projection();
+                (Some(expression), names)
+            }
+        };
+        let mut values = HashMap::new();
+        let filter_expression = filter.map(|(expression, filter_names, filter_values)| {
+            names.extend(filter_names);
+            values.extend(filter_values);
+            expression
+        });
         let result = RunTable::query_builder(self.client.clone())
-            .scan_index_forward(false)
+            .index_name("started_at_index")
+            .scan_index_forward(order.scan_index_forward())
+            .set_exclusive_start_key(RunTable::build_exclusion_key(next_page_key))
+            .set_projection_expression(projection)
+            .set_filter_expression(filter_expression)
+            .set_expression_attribute_names(Some(names))
+            .set_expression_attribute_values(Some(values))
             .expression_attribute_names("#pk", RunTable::partition_key_name())
             .expression_attribute_values(":pk", AttributeValue::S(build_composite_key(vec![customer_id.clone(), test_case_id.clone()])))
             .key_condition_expression("#pk = :pk")
             .send().await;
-        RunTable::from_query_result(result)
+        RunTable::from_ordered_query_result(result)
+    }
+
+    /// Newest first summaries of the customer's runs across test cases through
+    /// `customer_started_at_index` (customer_id, started_at).
+    pub async fn list_recent(
+        &self,
+        customer_id: &String,
+        status: Option<RunStatus>,
+        since: Option<u64>,
+        limit: i32,
+        next_page_key: Option<String>,
+    ) -> Result<QueryResult<Run>, AppError> {
+        let (projection, names) = summary_projection();
+        let (key_condition, filter_expression, names, values) = recent_runs_condition(customer_id, status, since, names);
+        let result = RunTable::query_builder(self.client.clone())
+            .index_name("customer_started_at_index")
+            .scan_index_forward(false)
+            .limit(limit)
+            .set_exclusive_start_key(RunTable::build_exclusion_key(next_page_key))
+            .projection_expression(projection)
+            .key_condition_expression(key_condition)
+            .set_filter_expression(filter_expression)
+            .set_expression_attribute_names(Some(names))
+            .set_expression_attribute_values(Some(values))
+            .send().await;
+        RunTable::from_ordered_query_result(result)
+    }
+
+    pub async fn count(&self, customer_id: &String, test_case_id: &String) -> Result<u64, AppError> {
+        RunTable::count_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()])).await
+    }
+
+    pub async fn delete_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Run>, AppError> {
+        RunTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
+    /// Looks a run up without its test case through `run_id_index` (customer_id, id).
+    pub async fn find_by_id(&self, customer_id: &String, id: &String) -> Result<Option<Run>, AppError> {
+        let result = RunTable::query_builder(self.client.clone())
+            .index_name("run_id_index")
+            .key_condition_expression("#pk = :pk AND #sk = :sk")
+            .expression_attribute_names("#pk", "customer_id")
+            .expression_attribute_names("#sk", "id")
+            .expression_attribute_values(":pk", AttributeValue::S(customer_id.clone()))
+            .expression_attribute_values(":sk", AttributeValue::S(id.clone()))
+            .send()
+            .await;
+        RunTable::from_query_result(result).map(|query_result| query_result.items.into_iter().next())
+    }
+
+    /// Counts the customer's in progress runs across test cases through `run_id_index`.
+    pub async fn count_in_progress(&self, customer_id: &String) -> Result<u64, AppError> {
+        let mut count = 0;
+        let mut exclusive_start_key = None;
+        loop {
+            let result = self.client.query()
+                .table_name(RunTable::qualified_table_name())
+                .index_name("run_id_index")
+                .key_condition_expression("#pk = :pk")
+                .filter_expression("#s = :s")
+                .expression_attribute_names("#pk", "customer_id")
+                .expression_attribute_names("#s", "status")
+                .expression_attribute_values(":pk", AttributeValue::S(customer_id.clone()))
+                .expression_attribute_values(":s", to_attribute_value(RunStatus::InProgress).unwrap())
+                .select(Select::Count)
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await
+                .map_err(|err| from_sdk_error(&RunTable::qualified_table_name(), err))?;
+            count += result.count as u64;
+            exclusive_start_key = result.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                return Ok(count);
+            }
+        }
+    }
+
+    pub async fn update_status(
+        &self,
+        customer_id: &String,
+        test_case_id: &String,
+        id: &String,
+        status: &RunStatus,
+    ) -> Result<Run, AppError> {
+        RunTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id.clone(),
+                                 self.client.clone().update_item()
+                                     .expression_attribute_names("#s", "status")
+                                     .expression_attribute_values(":s", to_attribute_value(status).unwrap())
+                                     .update_expression("SET #s = :s"))
+            .await
     }
 
     pub async fn update(
@@ -91,19 +224,94 @@ impl RunOperations {
         id: &String,
         status: &RunStatus,
         assertion_results: Vec<AssertionResult>,
+        verdict: &Verdict,
     ) {
         RunTable::update_partial(build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), id.clone(),
                                  self.client.clone().update_item()
                                      .expression_attribute_names("#fa", "finished_at")
                                      .expression_attribute_names("#s", "status")
                                      .expression_attribute_names("#ar", "assertion_results")
+                                     .expression_attribute_names("#v", "verdict")
+                                     .expression_attribute_values(":v", to_attribute_value(verdict).unwrap())
                                      .expression_attribute_values(":s", to_attribute_value(status).unwrap())
                                      .expression_attribute_values(":fa", AttributeValue::N(current_timestamp().to_string()))
                                      .expression_attribute_values(":ar", to_attribute_value(assertion_results).unwrap())
-                                     .update_expression("SET #fa = :fa, #s = :s, #ar = :ar"))
+                                     .update_expression("SET #fa = :fa, #s = :s, #ar = :ar, #v = :v"))
             .await
             .unwrap();
     }
+
+    pub async fn update_resources(
+        &self,
+        customer_id: &str,
+        test_case_id: &str,
+        id: &str,
+        resources: &[CreatedResource],
+    ) -> Result<Run, AppError> {
+        RunTable::update_partial(build_composite_key(vec![customer_id.to_string(), test_case_id.to_string()]), id.to_string(),
+                                 self.client.clone().update_item()
+                                     .expression_attribute_names("#r", "resources")
+                                     .expression_attribute_values(":r", to_attribute_value(resources).unwrap())
+                                     .update_expression("SET #r = :r"))
+            .await
+    }
+}
+
+fn summary_projection() -> (String, HashMap<String, String>) {
+    let attributes: Vec<String> = SUMMARY_ATTRIBUTES.iter().map(|attribute| attribute.to_string()).collect();
+    projection_expression(&attributes)
+}
+
+/// Key condition on `customer_started_at_index` and the status filter of the customer wide run list.
+fn recent_runs_condition(
+    customer_id: &String,
+    status: Option<RunStatus>,
+    since: Option<u64>,
+    mut names: HashMap<String, String>,
+) -> (String, Option<String>, HashMap<String, String>, HashMap<String, AttributeValue>) {
+    names.insert("#pk".to_string(), "customer_id".to_string());
+    let mut values = HashMap::from([(":pk".to_string(), AttributeValue::S(customer_id.clone()))]);
+    let mut key_condition = "#pk = :pk".to_string();
+    if let Some(since) = since {
+        names.insert("#sk".to_string(), "started_at".to_string());
+        values.insert(":since".to_string(), AttributeValue::N(since.to_string()));
+        key_condition.push_str(" AND #sk >= :since");
+    }
+    let filter_expression = status.map(|status| {
+        names.insert("#s".to_string(), "status".to_string());
+        values.insert(":s".to_string(), to_attribute_value(status).unwrap());
+        "#s = :s".to_string()
+    });
+    (key_condition, filter_expression, names, values)
+}
+
+/// Runs stored before triggers were recorded have no `trigger` attribute and count as manual.
+fn list_filter(filter: RunFilter) -> Result<Option<FilterExpression>, AppError> {
+    let mut names = HashMap::new();
+    let mut values = HashMap::new();
+    let mut conditions = vec![];
+    if let Some(trigger) = filter.trigger {
+        if !TRIGGER_TYPES.contains(&trigger.as_str()) {
+            return Err(AppError::Validation(format!("Unknown trigger: {}, expected one of {}", trigger, TRIGGER_TYPES.join(", "))));
+        }
+        names.insert("#trigger".to_string(), "trigger".to_string());
+        names.insert("#trigger_type".to_string(), "type".to_string());
+        conditions.push(if trigger == "manual" {
+            "(attribute_not_exists(#trigger) OR #trigger.#trigger_type = :trigger)".to_string()
+        } else {
+            "#trigger.#trigger_type = :trigger".to_string()
+        });
+        values.insert(":trigger".to_string(), AttributeValue::S(trigger));
+    }
+    for (index, label) in filter.labels.into_iter().enumerate() {
+        names.insert("#labels".to_string(), "labels".to_string());
+        values.insert(format!(":label{}", index), AttributeValue::S(label));
+        conditions.push(format!("contains(#labels, :label{})", index));
+    }
+    if conditions.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((conditions.join(" AND "), names, values)))
 }
 
 #[cfg(test)]
@@ -111,6 +319,39 @@ mod tests {
     use super::*;
     use crate::persistence::repo::{init_logger, Repository};
 
+    #[test]
+    fn list_filter_by_trigger_and_labels() {
+        assert!(list_filter(RunFilter::default()).unwrap().is_none());
+
+        let (expression, names, values) = list_filter(RunFilter {
+            trigger: Some("manual".to_string()),
+            labels: vec!["nightly".to_string(), "main".to_string()],
+        }).unwrap().unwrap();
+        assert_eq!(expression, "(attribute_not_exists(#trigger) OR #trigger.#trigger_type = :trigger) AND contains(#labels, :label0) AND contains(#labels, :label1)");
+        assert_eq!(names.get("#trigger_type"), Some(&"type".to_string()));
+        assert_eq!(values.get(":label1"), Some(&AttributeValue::S("main".to_string())));
+
+        let (expression, _, _) = list_filter(RunFilter { trigger: Some("ci".to_string()), labels: vec![] }).unwrap().unwrap();
+        assert_eq!(expression, "#trigger.#trigger_type = :trigger");
+        assert!(list_filter(RunFilter { trigger: Some("cron".to_string()), labels: vec![] }).is_err());
+    }
+
+    #[test]
+    fn recent_runs_condition_with_since_and_status() {
+        let (key_condition, filter_expression, names, values) =
+            recent_runs_condition(&"cust1".to_string(), None, None, HashMap::new());
+        assert_eq!(key_condition, "#pk = :pk");
+        assert!(filter_expression.is_none());
+        assert_eq!(names.len(), 1);
+        assert_eq!(values.len(), 1);
+
+        let (key_condition, filter_expression, names, values) =
+            recent_runs_condition(&"cust1".to_string(), Some(RunStatus::Finished), Some(1700000000000), HashMap::new());
+        assert_eq!(key_condition, "#pk = :pk AND #sk >= :since");
+        assert_eq!(filter_expression, Some("#s = :s".to_string()));
+        assert_eq!(names.get("#sk"), Some(&"started_at".to_string()));
+        assert_eq!(values.get(":since"), Some(&AttributeValue::N("1700000000000".to_string())));
+    }
 
     #[tokio::test]
     async fn crud() {
@@ -126,7 +367,7 @@ mod tests {
         repository.runs()
             .create(run).await;
         let update_result = repository.runs()
-            .update(&"cust1".to_string(), &"tc1".to_string(), &"r1".to_string(), &RunStatus::Finished, vec![])
+            .update(&"cust1".to_string(), &"tc1".to_string(), &"r1".to_string(), &RunStatus::Finished, vec![], &Verdict::Passed)
             .await;
         let get_result = repository.runs()
             .get(&"cust1".to_string(), &"tc1".to_string(), &"r1".to_string())
@@ -0,0 +1,131 @@
+use crate::api::AppError;
+use crate::case::model::{SetupCase, TestCase};
+use crate::config::app_config;
+use crate::http::ApiClient;
+use crate::persistence::model::{SortOrder, View};
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::run::execution::{context_after, run_test_and_wait, RunTestCaseCommand};
+use crate::run::model::{Run, RunStatus, Verdict};
+use crate::run::selection::teardown_last;
+use crate::run::service::RunFilter;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Context key the setup case's context is seeded under, read as `$.setup.<action>...`.
+pub const SETUP_CONTEXT_KEY: &str = "setup";
+
+/// The setup case must exist and following its own setup chain must not lead back to `test_case_id`.
+pub async fn validate_setup(repository: &Repository, customer_id: &String, test_case_id: &String, setup: &SetupCase) -> Result<(), AppError> {
+    let mut visited = HashSet::from([test_case_id.clone()]);
+    let mut next = Some(setup.test_case_id.clone());
+    while let Some(current) = next {
+        if !visited.insert(current.clone()) {
+            return Err(AppError::Validation(format!("Setup of test case {} would depend on itself through {}", test_case_id, current)));
+        }
+        next = repository.test_cases()
+            .get(customer_id.clone(), current.clone())
+            .await?
+            .ok_or(AppError::NotFound(format!("Setup test case {} not found!", current)))?
+            .setup
+            .map(|setup| setup.test_case_id);
+    }
+    Ok(())
+}
+
+/// Runs the setup case of `test_case`, or picks a recent passed run of it, returning that run's id
+/// and the context it ended with. A setup run that does not pass fails the dependent run up front.
+pub async fn resolve_setup(
+    repo: &Arc<Repository>,
+    api_client: &Arc<ApiClient>,
+    test_case: &TestCase,
+    command: &RunTestCaseCommand,
+) -> Result<Option<(String, Map<String, Value>)>, AppError> {
+    let Some(setup) = &test_case.setup else {
+        return Ok(None);
+    };
+    if command.seed_context.contains_key(SETUP_CONTEXT_KEY) {
+        return Ok(None);
+    }
+    let reused = match setup.reuse_within_secs {
+        Some(within_secs) => {
+            let runs = repo.runs()
+                .list(&command.customer_id, &setup.test_case_id, View::Full, SortOrder::Desc, RunFilter::default(), None)
+                .await?
+                .items;
+            reusable_run(&runs, current_timestamp(), within_secs).cloned()
+        }
+        None => None,
+    };
+    let setup_run = match reused {
+        Some(run) => run,
+        None => {
+            let setup_command = RunTestCaseCommand {
+                customer_id: command.customer_id.clone(),
+                test_case_id: setup.test_case_id.clone(),
+                mock: command.mock,
+                auth_provider_overrides: command.auth_provider_overrides.clone(),
+                fault_injection: None,
+                environment_id: command.environment_id.clone(),
+                selection: Default::default(),
+                seed_context: Map::new(),
+                trigger: command.trigger.clone(),
+                labels: command.labels.clone(),
+            };
+            let timeout = Duration::from_secs(app_config().default_wait_timeout_secs);
+            let verdict = Box::pin(run_test_and_wait(repo.clone(), api_client.clone(), setup_command, timeout)).await?;
+            if verdict.verdict != Verdict::Passed {
+                return Err(AppError::Processing(format!(
+                    "Setup test case {} did not pass, run {} ended {:?}", setup.test_case_id, verdict.run.id, verdict.verdict)));
+            }
+            verdict.run
+        }
+    };
+    let mut actions = repo.actions()
+        .list_all(setup_run.customer_id.clone(), setup_run.test_case_id.clone())
+        .await?;
+    actions.sort();
+    let executions = repo.action_executions()
+        .list_with_bodies(&setup_run.customer_id, &setup_run.test_case_id, &setup_run.id)
+        .await?;
+    let context = context_after(&setup_run.seed_context, &teardown_last(actions), &executions, None).unwrap_or_default();
+    Ok(Some((setup_run.id, context)))
+}
+
+/// Latest passed run that started within the last `within_secs`; `runs` are newest first.
+fn reusable_run(runs: &[Run], now: u64, within_secs: u64) -> Option<&Run> {
+    let earliest = now.saturating_sub(within_secs * 1000);
+    runs.iter()
+        .take_while(|run| run.started_at >= earliest)
+        .find(|run| run.status == RunStatus::Finished && run.verdict == Some(Verdict::Passed) && run.selection.is_none())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, started_at: u64, verdict: Option<Verdict>) -> Run {
+        Run::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("setup".to_string())
+            .id(id.to_string())
+            .status(if verdict.is_some() { RunStatus::Finished } else { RunStatus::InProgress })
+            .started_at(started_at)
+            .maybe_verdict(verdict)
+            .build()
+    }
+
+    #[test]
+    fn reuse_latest_passed_run_within_window() {
+        let runs = vec![
+            run("in_progress", 95_000, None),
+            run("failed", 90_000, Some(Verdict::Failed)),
+            run("passed", 80_000, Some(Verdict::Passed)),
+            run("older", 50_000, Some(Verdict::Passed)),
+        ];
+        assert_eq!(reusable_run(&runs, 100_000, 30).map(|run| run.id.as_str()), Some("passed"));
+        assert_eq!(reusable_run(&runs, 100_000, 15), None);
+        assert_eq!(reusable_run(&[], 100_000, 30), None);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::repo::Repository;
+use crate::sensitive_header::model::SensitiveHeaderPolicy;
+use crate::validation::AppJson;
+use axum::extract::State;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn get_sensitive_header_policy(
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<SensitiveHeaderPolicy>, AppError> {
+    let result = repository.sensitive_headers()
+        .get(&"eren".to_string())
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn put_sensitive_header_policy(
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<PutSensitiveHeaderPolicyPayload>,
+) -> Result<ApiResponse<SensitiveHeaderPolicy>, AppError> {
+    let result = repository.sensitive_headers()
+        .put(SensitiveHeaderPolicy::builder()
+            .customer_id("eren".to_string())
+            .include(payload.include)
+            .exclude(payload.exclude)
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct PutSensitiveHeaderPolicyPayload {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
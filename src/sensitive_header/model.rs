@@ -0,0 +1,93 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const SENSITIVE_HEADER_POLICY_ID: &str = "sensitive-headers";
+
+pub const MASKED_VALUE: &str = "****";
+
+/// Header name fragments treated as credentials unless a customer excludes them.
+const DEFAULT_PATTERNS: [&str; 9] = [
+    "authorization",
+    "token",
+    "session",
+    "csrf",
+    "origin",
+    "cookie",
+    "auth",
+    "api-key",
+    "apikey",
+];
+
+/// Decides which headers and cookies are credentials: those are moved to auth providers on
+/// import instead of being stored as action parameters. Patterns are case-insensitive name
+/// fragments; `exclude` wins over both `include` and the defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct SensitiveHeaderPolicy {
+    pub customer_id: String,
+    #[builder(default = SENSITIVE_HEADER_POLICY_ID.to_string())]
+    pub id: String,
+    #[serde(default)]
+    #[builder(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    #[builder(default)]
+    pub exclude: Vec<String>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+impl SensitiveHeaderPolicy {
+    pub fn default_for(customer_id: &str) -> Self {
+        SensitiveHeaderPolicy::builder()
+            .customer_id(customer_id.to_string())
+            .build()
+    }
+
+    pub fn mask(&self, name: &str, value: &str) -> String {
+        if self.is_sensitive(name) {
+            MASKED_VALUE.to_string()
+        } else {
+            value.to_string()
+        }
+    }
+
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        let matches = |pattern: &String| {
+            let pattern = pattern.trim().to_lowercase();
+            !pattern.is_empty() && name.contains(&pattern)
+        };
+        if self.exclude.iter().any(matches) {
+            return false;
+        }
+        DEFAULT_PATTERNS.iter().any(|pattern| name.contains(pattern))
+            || self.include.iter().any(matches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_headers() {
+        let default_policy = SensitiveHeaderPolicy::default_for("cust1");
+        assert!(default_policy.is_sensitive("Authorization"));
+        assert!(default_policy.is_sensitive("x-api-key-v2"));
+        assert!(!default_policy.is_sensitive("x-user-agent-id"));
+        assert!(!default_policy.is_sensitive("x-tenant-secret"));
+
+        let policy = SensitiveHeaderPolicy {
+            include: vec!["X-Tenant-Secret".to_string(), " ".to_string()],
+            exclude: vec!["x-csrf-nonce".to_string()],
+            ..default_policy
+        };
+        assert!(policy.is_sensitive("x-tenant-secret"));
+        assert!(!policy.is_sensitive("x-csrf-nonce"));
+        assert!(policy.is_sensitive("x-csrf-token"));
+        assert_eq!(policy.mask("Authorization", "Bearer abc"), MASKED_VALUE);
+        assert_eq!(policy.mask("x-csrf-nonce", "n1"), "n1");
+        assert!(!policy.is_sensitive("accept"));
+    }
+}
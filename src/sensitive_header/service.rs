@@ -0,0 +1,46 @@
+use crate::api::AppError;
+use crate::persistence::repo::Table;
+use crate::sensitive_header::model::{SensitiveHeaderPolicy, SENSITIVE_HEADER_POLICY_ID};
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct SensitiveHeaderPolicyOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct SensitiveHeaderPoliciesTable();
+
+impl Table<SensitiveHeaderPolicy> for SensitiveHeaderPoliciesTable {
+    fn table_name() -> String {
+        "sensitive_header_policies".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &SensitiveHeaderPolicy) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &SensitiveHeaderPolicy) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl SensitiveHeaderPolicyOperations {
+    pub async fn get(&self, customer_id: &String) -> Result<SensitiveHeaderPolicy, AppError> {
+        SensitiveHeaderPoliciesTable::get_item(self.client.clone(), customer_id.clone(), SENSITIVE_HEADER_POLICY_ID.to_string())
+            .await
+            .map(|policy| policy.unwrap_or_else(|| SensitiveHeaderPolicy::default_for(customer_id)))
+    }
+
+    pub async fn put(&self, policy: SensitiveHeaderPolicy) -> Result<SensitiveHeaderPolicy, AppError> {
+        SensitiveHeaderPoliciesTable::put_item(self.client.clone(), policy).await
+    }
+}
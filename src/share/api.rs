@@ -0,0 +1,63 @@
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::model::View;
+use crate::persistence::repo::{current_timestamp, Repository};
+use crate::share::model::{ShareClaims, SharedRunLink, SharedRunReport};
+use crate::share::token::{share_secret, sign, verify};
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+const DEFAULT_TTL_SECONDS: u64 = 7 * 24 * 60 * 60;
+const MAX_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+pub async fn share_run(
+    Path(run_id): Path<String>,
+    State(repository): State<Repository>,
+    payload: Option<Json<ShareRunPayload>>,
+) -> Result<ApiResponse<SharedRunLink>, AppError> {
+    let run = repository.runs()
+        .find_by_id(&"eren".to_string(), &run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let ttl_seconds = payload.and_then(|Json(payload)| payload.ttl_seconds)
+        .unwrap_or(DEFAULT_TTL_SECONDS)
+        .min(MAX_TTL_SECONDS);
+    let claims = ShareClaims {
+        customer_id: run.customer_id,
+        test_case_id: run.test_case_id,
+        run_id: run.id,
+        expires_at: current_timestamp() + ttl_seconds * 1000,
+    };
+    let token = sign(&claims, share_secret());
+    ApiResponse::from(Ok(SharedRunLink {
+        path: format!("/shared/runs/{}", token),
+        token,
+        expires_at: claims.expires_at,
+    }))
+}
+
+/// Public route: the token is the only credential and it only ever resolves to its own run.
+pub async fn get_shared_run(
+    Path(token): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<SharedRunReport>, AppError> {
+    let claims = verify(&token, share_secret(), current_timestamp())
+        .ok_or(AppError::Unauthorized("Invalid or expired share link".to_string()))?;
+    let run = repository.runs()
+        .get(&claims.customer_id, &claims.test_case_id, &claims.run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let action_executions = repository.action_executions()
+        .list_with_actions(&claims.customer_id, &claims.test_case_id, &claims.run_id, View::Full)
+        .await?;
+    ApiResponse::from(Ok(SharedRunReport {
+        run,
+        action_executions,
+    }))
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct ShareRunPayload {
+    pub ttl_seconds: Option<u64>,
+}
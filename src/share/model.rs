@@ -0,0 +1,26 @@
+use crate::action_execution::model::ActionExecutionPair;
+use crate::run::model::Run;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// What a share token grants: read access to exactly one run until `expires_at`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ShareClaims {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub run_id: String,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct SharedRunLink {
+    pub token: String,
+    pub path: String,
+    pub expires_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct SharedRunReport {
+    pub run: Run,
+    pub action_executions: Vec<ActionExecutionPair>,
+}
@@ -0,0 +1,68 @@
+use crate::config::app_config;
+use crate::share::model::ShareClaims;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signing key from the configured `share_secret`. Without it a random key is used, so links stop
+/// working when the process restarts.
+pub fn share_secret() -> &'static [u8] {
+    static SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+    SECRET.get_or_init(|| {
+        app_config().share_secret.clone()
+            .map(|secret| secret.into_bytes())
+            .unwrap_or_else(|| format!("{}{}", uuid::Uuid::new_v4(), uuid::Uuid::new_v4()).into_bytes())
+    })
+}
+
+/// Encodes the claims as `<payload>.<signature>`, both base64url without padding.
+pub fn sign(claims: &ShareClaims, secret: &[u8]) -> String {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap());
+    let signature = URL_SAFE_NO_PAD.encode(signature_of(&payload, secret));
+    format!("{}.{}", payload, signature)
+}
+
+/// Returns the claims of a well-signed token that has not expired at `now`.
+pub fn verify(token: &str, secret: &[u8], now: u64) -> Option<ShareClaims> {
+    let (payload, signature) = token.split_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let mut mac = HmacSha256::new_from_slice(secret).unwrap();
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).ok()?;
+    let claims: ShareClaims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+    (claims.expires_at > now).then_some(claims)
+}
+
+fn signature_of(payload: &str, secret: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(secret).unwrap();
+    mac.update(payload.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_signed_token() {
+        let claims = ShareClaims {
+            customer_id: "cust1".to_string(),
+            test_case_id: "tc1".to_string(),
+            run_id: "r1".to_string(),
+            expires_at: 1000,
+        };
+        let token = sign(&claims, b"secret");
+
+        assert_eq!(verify(&token, b"secret", 999), Some(claims.clone()));
+        assert_eq!(verify(&token, b"secret", 1000), None);
+        assert_eq!(verify(&token, b"other", 999), None);
+
+        let forged = sign(&ShareClaims { run_id: "r2".to_string(), ..claims }, b"other");
+        let tampered = format!("{}.{}", forged.split_once('.').unwrap().0, token.split_once('.').unwrap().1);
+        assert_eq!(verify(&tampered, b"secret", 999), None);
+    }
+}
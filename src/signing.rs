@@ -0,0 +1,233 @@
+use crate::auth::model::RequestSigning;
+use crate::http::percent_encode;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Request;
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Adds the signature headers to a request that is about to be sent.
+pub fn sign_request(request: &mut Request, signing: &RequestSigning, now: SystemTime) {
+    match signing {
+        RequestSigning::SigV4 { access_key_id, secret_access_key, session_token, region, service } => {
+            let credentials = SigV4Credentials {
+                access_key_id,
+                secret_access_key,
+                session_token: session_token.as_deref(),
+                region,
+                service,
+            };
+            sign_sigv4(request, &credentials, &amz_date(now));
+        }
+        RequestSigning::Hmac { secret, header } => {
+            let string_to_sign = format!("{}\n{}\n{}",
+                                         request.method().as_str(),
+                                         path_and_query(request),
+                                         String::from_utf8_lossy(body_of(request)));
+            let signature = hex(&hmac_sha256(secret.as_bytes(), string_to_sign.as_bytes()));
+            insert_header(request, header, &signature);
+        }
+    }
+}
+
+struct SigV4Credentials<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    session_token: Option<&'a str>,
+    region: &'a str,
+    service: &'a str,
+}
+
+fn sign_sigv4(request: &mut Request, credentials: &SigV4Credentials, amz_date: &str) {
+    let date = &amz_date[..8];
+    let payload_hash = hex(&Sha256::digest(body_of(request)));
+    insert_header(request, "x-amz-date", amz_date);
+    if credentials.service == "s3" {
+        insert_header(request, "x-amz-content-sha256", &payload_hash);
+    }
+    if let Some(session_token) = credentials.session_token {
+        insert_header(request, "x-amz-security-token", session_token);
+    }
+    let host = match request.url().port() {
+        Some(port) => format!("{}:{}", request.url().host_str().unwrap_or_default(), port),
+        None => request.url().host_str().unwrap_or_default().to_string(),
+    };
+    let mut signed: Vec<(String, String)> = request.headers().iter()
+        .filter(|(name, _)| name.as_str().starts_with("x-amz-"))
+        .map(|(name, value)| (name.as_str().to_string(), value.to_str().unwrap_or_default().trim().to_string()))
+        .collect();
+    signed.push(("host".to_string(), host));
+    signed.sort();
+    let canonical_headers: String = signed.iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value))
+        .collect();
+    let signed_headers = signed.iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<&str>>()
+        .join(";");
+    let canonical_request = format!("{}\n{}\n{}\n{}\n{}\n{}",
+                                    request.method().as_str(),
+                                    canonical_uri(request, credentials.service),
+                                    canonical_query(request),
+                                    canonical_headers,
+                                    signed_headers,
+                                    payload_hash);
+    let scope = format!("{}/{}/{}/aws4_request", date, credentials.region, credentials.service);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                                 amz_date,
+                                 scope,
+                                 hex(&Sha256::digest(canonical_request.as_bytes())));
+    let signing_key = [credentials.region, credentials.service, "aws4_request"].iter()
+        .fold(hmac_sha256(format!("AWS4{}", credentials.secret_access_key).as_bytes(), date.as_bytes()),
+              |key, part| hmac_sha256(&key, part.as_bytes()));
+    let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+    let authorization = format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                                credentials.access_key_id, scope, signed_headers, signature);
+    insert_header(request, AUTHORIZATION.as_str(), &authorization);
+}
+
+/// Services other than S3 expect every path segment to be encoded once more.
+fn canonical_uri(request: &Request, service: &str) -> String {
+    let path = request.url().path();
+    if service == "s3" {
+        return path.to_string();
+    }
+    path.split('/')
+        .map(percent_encode)
+        .collect::<Vec<String>>()
+        .join("/")
+}
+
+fn canonical_query(request: &Request) -> String {
+    let mut pairs: Vec<(String, String)> = request.url().query_pairs()
+        .map(|(key, value)| (percent_encode(&key), percent_encode(&value)))
+        .collect();
+    pairs.sort();
+    pairs.iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn path_and_query(request: &Request) -> String {
+    match request.url().query() {
+        Some(query) => format!("{}?{}", request.url().path(), query),
+        None => request.url().path().to_string(),
+    }
+}
+
+fn body_of(request: &Request) -> &[u8] {
+    request.body()
+        .and_then(|body| body.as_bytes())
+        .unwrap_or_default()
+}
+
+fn insert_header(request: &mut Request, name: &str, value: &str) {
+    if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+        request.headers_mut().insert(name, value);
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Formats a time as `yyyyMMddTHHmmssZ` in UTC.
+fn amz_date(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let (days, seconds_of_day) = ((seconds / 86400) as i64, seconds % 86400);
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}{:02}{:02}T{:02}{:02}{:02}Z", year, month, day,
+            seconds_of_day / 3600, seconds_of_day % 3600 / 60, seconds_of_day % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::{Method, Url};
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn format_amz_date() {
+        assert_eq!(amz_date(at(1440938160)), "20150830T123600Z");
+        assert_eq!(amz_date(at(951782400)), "20000229T000000Z");
+    }
+
+    #[test]
+    fn sign_with_sigv4() {
+        let signing = RequestSigning::SigV4 {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            session_token: None,
+            region: "us-east-1".to_string(),
+            service: "service".to_string(),
+        };
+        let mut request = Request::new(Method::GET, Url::parse("https://example.amazonaws.com/").unwrap());
+
+        sign_request(&mut request, &signing, at(1440938160));
+
+        assert_eq!(request.headers().get("x-amz-date").unwrap(), "20150830T123600Z");
+        assert_eq!(request.headers().get(AUTHORIZATION).unwrap(),
+                   "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                   SignedHeaders=host;x-amz-date, \
+                   Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31");
+    }
+
+    #[test]
+    fn sign_with_sigv4_query_body_and_session_token() {
+        let signing = RequestSigning::SigV4 {
+            access_key_id: "AKID".to_string(),
+            secret_access_key: "SECRET".to_string(),
+            session_token: Some("TOKEN".to_string()),
+            region: "eu-west-1".to_string(),
+            service: "execute-api".to_string(),
+        };
+        let url = "https://abc.execute-api.eu-west-1.amazonaws.com/prod/items/a%20b?b=2&a=x%2Fy&a=1";
+        let mut request = Request::new(Method::POST, Url::parse(url).unwrap());
+        *request.body_mut() = Some("{\"k\":\"v\"}".into());
+
+        sign_request(&mut request, &signing, at(1792112077));
+
+        assert_eq!(request.headers().get("x-amz-security-token").unwrap(), "TOKEN");
+        assert_eq!(request.headers().get(AUTHORIZATION).unwrap(),
+                   "AWS4-HMAC-SHA256 Credential=AKID/20261016/eu-west-1/execute-api/aws4_request, \
+                   SignedHeaders=host;x-amz-date;x-amz-security-token, \
+                   Signature=8ec088cc15806bcb02c05e9fcc2939e202c968432f80cf25fbf5d52164f57189");
+    }
+
+    #[test]
+    fn sign_with_hmac() {
+        let signing = RequestSigning::Hmac {
+            secret: "secret".to_string(),
+            header: "X-Signature".to_string(),
+        };
+        let mut request = Request::new(Method::POST, Url::parse("https://abc.xyz/items?a=1").unwrap());
+        *request.body_mut() = Some("{}".into());
+
+        sign_request(&mut request, &signing, at(0));
+
+        assert_eq!(request.headers().get("x-signature").unwrap().to_str().unwrap(),
+                   "c5ef5a308cb49a0a40ed91e3a18721a52c4b39601fbaec556f8b4961d28ef699");
+    }
+}
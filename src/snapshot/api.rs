@@ -0,0 +1,67 @@
+use crate::action_execution::canonical::canonical_hash;
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::repo::Repository;
+use crate::snapshot::compare::normalize;
+use crate::snapshot::model::Snapshot;
+use crate::validation::AppJson;
+use axum::extract::{Path, State};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+pub async fn get_snapshot(
+    Path((test_case_id, action_id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Snapshot>, AppError> {
+    let result = repository.snapshots().get(&"eren".to_string(), &test_case_id, &action_id).await;
+    ApiResponse::from_option(result)
+}
+
+/// Accepts the action's response in the given run as its new baseline, replacing the previous one.
+pub async fn accept_snapshot(
+    Path((test_case_id, action_id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<AcceptSnapshotPayload>,
+) -> Result<ApiResponse<Snapshot>, AppError> {
+    let customer_id = "eren".to_string();
+    let action = repository.actions()
+        .get(customer_id.clone(), test_case_id.clone(), action_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Action not found!".to_string()))?;
+    repository.runs()
+        .get(&customer_id, &test_case_id, &payload.run_id)
+        .await?
+        .ok_or(AppError::NotFound("Run not found!".to_string()))?;
+    let response_body = repository.action_executions()
+        .list_with_bodies(&customer_id, &test_case_id, &payload.run_id)
+        .await?
+        .into_iter()
+        .filter(|execution| execution.action_id == action_id)
+        .max_by_key(|execution| execution.started_at)
+        .and_then(|execution| execution.response_body)
+        .ok_or(AppError::Validation("The action has no response in this run!".to_string()))?;
+    let baseline = normalize(&response_body, &action.volatile_paths);
+    let result = repository.snapshots()
+        .put(Snapshot::builder()
+            .customer_id(customer_id)
+            .test_case_id(test_case_id)
+            .action_id(action_id)
+            .baseline_hash(canonical_hash(&baseline))
+            .baseline(baseline)
+            .run_id(payload.run_id)
+            .build())
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_snapshot(
+    Path((test_case_id, action_id)): Path<(String, String)>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Snapshot>, AppError> {
+    let result = repository.snapshots().delete(&"eren".to_string(), &test_case_id, &action_id).await;
+    ApiResponse::from_option(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct AcceptSnapshotPayload {
+    pub run_id: String,
+}
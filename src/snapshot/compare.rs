@@ -0,0 +1,148 @@
+use crate::action_execution::canonical::{canonical_hash, canonicalize};
+use crate::assertion::model::{Assertion, AssertionResult};
+use crate::drift::analysis::array_index_regex;
+use crate::snapshot::model::{Snapshot, SnapshotDifference};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+const REPORTED_DIFFERENCES: usize = 5;
+
+/// Drops every field whose path is volatile. Paths use the drift notation, array items are
+/// addressed with `[]` so `$.items[].updatedAt` covers each item; concrete indexes are accepted too.
+pub fn normalize(value: &Value, volatile_paths: &[String]) -> Value {
+    let index_regex = array_index_regex();
+    let volatile: HashSet<String> = volatile_paths.iter()
+        .map(|path| index_regex.replace_all(path.trim(), "[]").to_string())
+        .collect();
+    strip(value, "$".to_string(), &volatile)
+}
+
+fn strip(value: &Value, path: String, volatile: &HashSet<String>) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter()
+            .map(|(key, val)| (format!("{}.{}", path, key), key, val))
+            .filter(|(child_path, _, _)| !volatile.contains(child_path))
+            .map(|(child_path, key, val)| (key.clone(), strip(val, child_path, volatile)))
+            .collect::<Map<String, Value>>()),
+        Value::Array(items) => Value::Array(items.iter()
+            .map(|item| strip(item, format!("{}[]", path), volatile))
+            .collect()),
+        _ => value.clone(),
+    }
+}
+
+pub fn diff(expected: &Value, actual: &Value) -> Vec<SnapshotDifference> {
+    let mut differences = vec![];
+    collect_differences(expected, actual, "$".to_string(), &mut differences);
+    differences
+}
+
+fn collect_differences(expected: &Value, actual: &Value, path: String, differences: &mut Vec<SnapshotDifference>) {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_value) in expected_map {
+                let child_path = format!("{}.{}", path, key);
+                match actual_map.get(key) {
+                    Some(actual_value) => collect_differences(expected_value, actual_value, child_path, differences),
+                    None => differences.push(SnapshotDifference::Removed { path: child_path, expected: expected_value.clone() }),
+                }
+            }
+            for (key, actual_value) in actual_map {
+                if !expected_map.contains_key(key) {
+                    differences.push(SnapshotDifference::Added { path: format!("{}.{}", path, key), actual: actual_value.clone() });
+                }
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            for (index, expected_item) in expected_items.iter().enumerate() {
+                let child_path = format!("{}[{}]", path, index);
+                match actual_items.get(index) {
+                    Some(actual_item) => collect_differences(expected_item, actual_item, child_path, differences),
+                    None => differences.push(SnapshotDifference::Removed { path: child_path, expected: expected_item.clone() }),
+                }
+            }
+            for (index, actual_item) in actual_items.iter().enumerate().skip(expected_items.len()) {
+                differences.push(SnapshotDifference::Added { path: format!("{}[{}]", path, index), actual: actual_item.clone() });
+            }
+        }
+        _ if expected != actual => differences.push(SnapshotDifference::Changed {
+            path,
+            expected: expected.clone(),
+            actual: actual.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Compares the live output of the snapshotted action against its baseline. The volatile paths
+/// are applied to both sides, so paths added after the baseline was accepted are ignored too.
+pub fn check_snapshot_assertion(
+    assertion: &Assertion,
+    snapshot: Option<&Snapshot>,
+    live: Option<&Value>,
+    volatile_paths: &[String],
+) -> AssertionResult {
+    let Some(snapshot) = snapshot else {
+        return AssertionResult::from_error(assertion.id.clone(), "no snapshot baseline accepted for the action".to_string())
+            .with_severity(assertion.severity.clone());
+    };
+    let Some(live) = live else {
+        return AssertionResult::from_error(assertion.id.clone(), "the snapshotted action has no output in this run".to_string())
+            .with_severity(assertion.severity.clone());
+    };
+    let live = canonicalize(&normalize(live, volatile_paths));
+    let unchanged = snapshot.baseline_hash.as_ref().is_some_and(|baseline_hash| baseline_hash == &canonical_hash(&live));
+    let differences = if unchanged { vec![] } else { diff(&normalize(&snapshot.baseline, volatile_paths), &live) };
+    let matches = differences.is_empty();
+    let result = if matches ^ assertion.negate {
+        AssertionResult::of_success(assertion.id.clone())
+    } else if assertion.negate {
+        AssertionResult::from_error(assertion.id.clone(), "response unexpectedly matches the snapshot".to_string())
+    } else {
+        AssertionResult::from_error(assertion.id.clone(), describe(&differences))
+    };
+    result.with_severity(assertion.severity.clone())
+}
+
+fn describe(differences: &[SnapshotDifference]) -> String {
+    let paths: Vec<&str> = differences.iter()
+        .take(REPORTED_DIFFERENCES)
+        .map(|difference| difference.path().as_str())
+        .collect();
+    let remaining = differences.len().saturating_sub(REPORTED_DIFFERENCES);
+    if remaining > 0 {
+        format!("response differs from snapshot at {} and {} more", paths.join(", "), remaining)
+    } else {
+        format!("response differs from snapshot at {}", paths.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_ignores_volatile_paths() {
+        let volatile_paths = vec!["$.requestId".to_string(), "$.items[0].updatedAt".to_string()];
+        let baseline = normalize(&json!({
+            "requestId": "r1",
+            "name": "eren",
+            "items": [{"sku": "x1", "updatedAt": 1}],
+            "legacy": true
+        }), &volatile_paths);
+        let live = normalize(&json!({
+            "requestId": "r2",
+            "name": "kizilay",
+            "items": [{"sku": "x1", "updatedAt": 2}, {"sku": "x2", "updatedAt": 3}]
+        }), &volatile_paths);
+
+        assert_eq!(baseline, json!({"name": "eren", "items": [{"sku": "x1"}], "legacy": true}));
+        assert_eq!(diff(&baseline, &live), vec![
+            SnapshotDifference::Changed { path: "$.name".to_string(), expected: json!("eren"), actual: json!("kizilay") },
+            SnapshotDifference::Added { path: "$.items[1]".to_string(), actual: json!({"sku": "x2"}) },
+            SnapshotDifference::Removed { path: "$.legacy".to_string(), expected: json!(true) },
+        ]);
+        assert!(diff(&baseline, &baseline).is_empty());
+    }
+}
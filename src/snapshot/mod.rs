@@ -0,0 +1,4 @@
+pub mod model;
+pub mod compare;
+pub mod service;
+pub mod api;
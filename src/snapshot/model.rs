@@ -0,0 +1,37 @@
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Accepted response of an action, stored with the action's volatile paths already removed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Builder, ToSchema)]
+pub struct Snapshot {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub action_id: String,
+    pub baseline: Value,
+    /// Canonical hash of the baseline; a live response with the same hash matches without a diff.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline_hash: Option<String>,
+    #[serde(default)]
+    pub run_id: Option<String>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub enum SnapshotDifference {
+    Added { path: String, actual: Value },
+    Removed { path: String, expected: Value },
+    Changed { path: String, expected: Value, actual: Value },
+}
+
+impl SnapshotDifference {
+    pub fn path(&self) -> &String {
+        match self {
+            SnapshotDifference::Added { path, .. } => path,
+            SnapshotDifference::Removed { path, .. } => path,
+            SnapshotDifference::Changed { path, .. } => path,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::api::AppError;
+use crate::persistence::repo::{build_composite_key, Table};
+use crate::snapshot::model::Snapshot;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::sync::Arc;
+
+pub struct SnapshotOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct SnapshotsTable();
+
+impl Table<Snapshot> for SnapshotsTable {
+    fn table_name() -> String {
+        "snapshots".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id#test_case_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "action_id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Snapshot) -> (String, AttributeValue) {
+        Self::partition_key(build_composite_key(vec![entity.customer_id.clone(), entity.test_case_id.clone()]))
+    }
+
+    fn sort_key_from_entity(entity: &Snapshot) -> (String, AttributeValue) {
+        Self::sort_key(entity.action_id.clone())
+    }
+}
+
+impl SnapshotOperations {
+    pub async fn put(&self, snapshot: Snapshot) -> Result<Snapshot, AppError> {
+        SnapshotsTable::put_item(self.client.clone(), snapshot).await
+    }
+
+    pub async fn get(&self, customer_id: &String, test_case_id: &String, action_id: &String) -> Result<Option<Snapshot>, AppError> {
+        SnapshotsTable::get_item(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), action_id.clone())
+            .await
+    }
+
+    pub async fn list_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Snapshot>, AppError> {
+        SnapshotsTable::list_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+
+    pub async fn delete(&self, customer_id: &String, test_case_id: &String, action_id: &String) -> Result<Option<Snapshot>, AppError> {
+        SnapshotsTable::delete_item(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]), action_id.clone())
+            .await
+    }
+
+    pub async fn delete_all(&self, customer_id: &String, test_case_id: &String) -> Result<Vec<Snapshot>, AppError> {
+        SnapshotsTable::delete_all_items(self.client.clone(), build_composite_key(vec![customer_id.clone(), test_case_id.clone()]))
+            .await
+    }
+}
@@ -0,0 +1,45 @@
+use crate::api::{ApiResponse, AppError};
+use crate::persistence::repo::Repository;
+use crate::revision::history::record_before_change;
+use crate::sync::engine::{apply_document, export_test_case};
+use crate::sync::model::{SyncPlan, TestCaseDocument};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+pub async fn get_test_case_yaml(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<Response, AppError> {
+    let document = export_test_case(&repository, &"eren".to_string(), &id).await?;
+    let yaml = serde_yaml::to_string(&document)
+        .map_err(|err| AppError::Internal(err.to_string()))?;
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/yaml")
+        .body(yaml.into())
+        .unwrap())
+}
+
+pub async fn apply_test_case_yaml(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+    Query(params): Query<ApplyYamlParams>,
+    body: String,
+) -> Result<ApiResponse<SyncPlan>, AppError> {
+    let document: TestCaseDocument = serde_yaml::from_str(&body)
+        .map_err(|err| AppError::Validation(err.to_string()))?;
+    let dry_run = params.dry_run.unwrap_or(false);
+    if !dry_run {
+        record_before_change(&repository, &"eren".to_string(), &id, "yaml apply").await;
+    }
+    let result = apply_document(&repository, &"eren".to_string(), &id, document, dry_run).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct ApplyYamlParams {
+    pub dry_run: Option<bool>,
+}
@@ -0,0 +1,364 @@
+use crate::action::model::{Action, ActionType};
+use crate::api::AppError;
+use crate::assertion::model::Assertion;
+use crate::case::model::TestCase;
+use crate::http::HttpMethod;
+use crate::parameter::model::{Parameter, ParameterType};
+use crate::persistence::repo::Repository;
+use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+use crate::sync::model::{ActionDocument, AssertionDocument, Changes, ParameterDocument, ScopedParameterDocument, SyncPlan, TestCaseDocument};
+use crate::validation::check_base_url;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use tracing::info;
+
+pub async fn export_test_case(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+) -> Result<TestCaseDocument, AppError> {
+    let test_case = repository.test_cases()
+        .get(customer_id.clone(), test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let actions = repository.actions().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let parameters = repository.parameters().list_all(customer_id.clone(), test_case_id.clone()).await?;
+    let assertions = repository.assertions().list_all(customer_id, test_case_id).await?;
+    Ok(render(&test_case, &actions, &parameters, &assertions))
+}
+
+pub fn render(
+    test_case: &TestCase,
+    actions: &[Action],
+    parameters: &[Parameter],
+    assertions: &[Assertion],
+) -> TestCaseDocument {
+    let mut sorted_actions = actions.to_vec();
+    sorted_actions.sort_by(|a1, a2| a1.order.cmp(&a2.order).then(a1.name.cmp(&a2.name)));
+    let action_documents = sorted_actions.iter()
+        .map(|action| {
+            let mut parameter_documents: Vec<ParameterDocument> = parameters.iter()
+                .filter(|parameter| parameter.action_id == action.id && parameter.parameter_type == ParameterType::Input)
+                .map(ParameterDocument::from_parameter)
+                .collect();
+            parameter_documents.sort_by_key(parameter_sort_key);
+            ActionDocument::from_action(action, parameter_documents)
+        })
+        .collect();
+    let mut assertion_documents: Vec<AssertionDocument> = assertions.iter()
+        .map(AssertionDocument::from_assertion)
+        .collect();
+    assertion_documents.sort_by(|a1, a2| a1.id.cmp(&a2.id));
+    TestCaseDocument {
+        name: test_case.name.clone(),
+        description: test_case.description.clone(),
+        base_url: test_case.base_url.clone(),
+        actions: action_documents,
+        assertions: assertion_documents,
+    }
+}
+
+fn parameter_sort_key(parameter: &ParameterDocument) -> (String, Option<String>) {
+    (serde_json::to_string(&parameter.location).unwrap_or_default(), parameter.id.clone())
+}
+
+pub fn validate(document: &TestCaseDocument) -> Result<(), AppError> {
+    let mut names = HashSet::new();
+    let mut ids = HashSet::new();
+    let base_urls = document.base_url.iter().chain(document.actions.iter().filter_map(|action| action.base_url.as_ref()));
+    for base_url in base_urls {
+        if check_base_url("base_url", base_url).is_some() {
+            return Err(AppError::Validation(format!("Invalid base URL: {}", base_url)));
+        }
+    }
+    for action in &document.actions {
+        if action.name.trim().is_empty() {
+            return Err(AppError::Validation("Action name must not be empty".to_string()));
+        }
+        if !names.insert(action.name.clone()) {
+            return Err(AppError::Validation(format!("Duplicate action name: {}", action.name)));
+        }
+        if let Some(id) = &action.id {
+            if !ids.insert(id.clone()) {
+                return Err(AppError::Validation(format!("Duplicate action id: {}", id)));
+            }
+        }
+        if action.action_type != ActionType::Sleep && HttpMethod::from_str(&action.method).is_err() {
+            return Err(AppError::Validation(format!("Unsupported method {} of action {}", action.method, action.name)));
+        }
+    }
+    Ok(())
+}
+
+/// Gives an id to every action, parameter and assertion that does not have one yet,
+/// so that new entries can be diffed against the stored state by id.
+pub fn assign_ids(document: &mut TestCaseDocument) {
+    document.actions.iter_mut().for_each(|action| {
+        action.id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+        action.parameters.iter_mut().for_each(|parameter| {
+            parameter.id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+        });
+    });
+    document.assertions.iter_mut().for_each(|assertion| {
+        assertion.id.get_or_insert_with(|| uuid::Uuid::new_v4().to_string());
+    });
+}
+
+pub fn plan(current: &TestCaseDocument, desired: &TestCaseDocument) -> SyncPlan {
+    let strip_parameters = |document: &TestCaseDocument| -> Vec<ActionDocument> {
+        document.actions.iter()
+            .map(|action| ActionDocument { parameters: vec![], ..action.clone() })
+            .collect()
+    };
+    SyncPlan {
+        test_case_changed: current.name != desired.name || current.description != desired.description || current.base_url != desired.base_url,
+        actions: diff(&strip_parameters(current), &strip_parameters(desired), |action| action.id.clone().unwrap_or_default()),
+        parameters: diff(&scoped_parameters(current), &scoped_parameters(desired), |scoped| {
+            format!("{}#{}", scoped.action_id, scoped.parameter.id.clone().unwrap_or_default())
+        }),
+        assertions: diff(&current.assertions, &desired.assertions, |assertion| assertion.id.clone().unwrap_or_default()),
+    }
+}
+
+fn scoped_parameters(document: &TestCaseDocument) -> Vec<ScopedParameterDocument> {
+    document.actions.iter()
+        .flat_map(|action| {
+            action.parameters.iter().map(|parameter| ScopedParameterDocument {
+                action_id: action.id.clone().unwrap_or_default(),
+                parameter: parameter.clone(),
+            })
+        })
+        .collect()
+}
+
+fn diff<T, F>(current: &[T], desired: &[T], key: F) -> Changes<T>
+where
+    T: Clone + PartialEq,
+    F: Fn(&T) -> String,
+{
+    let current_by_key: HashMap<String, &T> = current.iter().map(|item| (key(item), item)).collect();
+    let desired_keys: HashSet<String> = desired.iter().map(&key).collect();
+    let mut changes = Changes {
+        created: vec![],
+        updated: vec![],
+        deleted: vec![],
+    };
+    for item in desired {
+        match current_by_key.get(&key(item)) {
+            None => changes.created.push(item.clone()),
+            Some(existing) => {
+                if *existing != item {
+                    changes.updated.push(item.clone());
+                }
+            }
+        }
+    }
+    changes.deleted = current.iter()
+        .filter(|item| !desired_keys.contains(&key(item)))
+        .cloned()
+        .collect();
+    changes
+}
+
+pub async fn apply_document(
+    repository: &Repository,
+    customer_id: &String,
+    test_case_id: &String,
+    mut desired: TestCaseDocument,
+    dry_run: bool,
+) -> Result<SyncPlan, AppError> {
+    validate(&desired)?;
+    assign_ids(&mut desired);
+    let current = export_test_case(repository, customer_id, test_case_id).await?;
+    let sync_plan = plan(&current, &desired);
+    if dry_run || sync_plan.is_empty() {
+        return Ok(sync_plan);
+    }
+    info!("applying sync plan for test case {}: {:?}", test_case_id, sync_plan);
+    let upserted_actions: Vec<Action> = sync_plan.actions.created.iter()
+        .chain(sync_plan.actions.updated.iter())
+        .map(|action| action.to_action(customer_id, test_case_id))
+        .collect();
+    let upserted_parameters: Vec<Parameter> = sync_plan.parameters.created.iter()
+        .chain(sync_plan.parameters.updated.iter())
+        .map(|scoped| scoped.to_parameter(customer_id, test_case_id))
+        .collect();
+    let upserted_assertions: Vec<Assertion> = sync_plan.assertions.created.iter()
+        .chain(sync_plan.assertions.updated.iter())
+        .map(|assertion| assertion.to_assertion(customer_id, test_case_id))
+        .collect();
+    // checked before anything is written, counting the items the plan deletes as still stored
+    let permit = guard_test_case_write(repository, customer_id, test_case_id, TestCaseWrite {
+        actions: &upserted_actions,
+        parameters: &upserted_parameters,
+        assertions: &upserted_assertions,
+    }).await?;
+    if sync_plan.test_case_changed {
+        repository.test_cases()
+            .update(customer_id.clone(), test_case_id.clone(), desired.name.clone(), desired.description.clone(), None)
+            .await?;
+        if current.base_url != desired.base_url {
+            repository.test_cases()
+                .update_base_url(customer_id.clone(), test_case_id.clone(), desired.base_url.clone(), None)
+                .await?;
+        }
+    }
+
+    let deleted_action_ids: HashSet<String> = sync_plan.actions.deleted.iter()
+        .filter_map(|action| action.id.clone())
+        .collect();
+    for action_id in &deleted_action_ids {
+        repository.actions().delete(customer_id.clone(), test_case_id.clone(), action_id.clone()).await?;
+    }
+    let mut parameter_keys_to_delete: Vec<(String, String)> = sync_plan.parameters.deleted.iter()
+        .filter(|scoped| !deleted_action_ids.contains(&scoped.action_id))
+        .map(|scoped| (scoped.action_id.clone(), scoped.parameter.id.clone().unwrap_or_default()))
+        .collect();
+    if !deleted_action_ids.is_empty() {
+        parameter_keys_to_delete.extend(repository.parameters()
+            .list_all(customer_id.clone(), test_case_id.clone())
+            .await?
+            .iter()
+            .filter(|parameter| deleted_action_ids.contains(&parameter.action_id))
+            .map(|parameter| (parameter.action_id.clone(), parameter.id.clone())));
+    }
+    repository.parameters().batch_delete(customer_id.clone(), test_case_id.clone(), parameter_keys_to_delete).await?;
+    for assertion in &sync_plan.assertions.deleted {
+        repository.assertions()
+            .delete(customer_id.clone(), test_case_id.clone(), assertion.id.clone().unwrap_or_default())
+            .await?;
+    }
+
+    repository.actions().batch_create(upserted_actions, permit).await;
+    repository.parameters().batch_create(upserted_parameters).await;
+    repository.assertions().batch_create(upserted_assertions).await;
+    Ok(sync_plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::model::{AssertionItem, ComparisonType};
+    use crate::json_path::model::Expression;
+    use crate::parameter::model::ParameterLocation;
+    use serde_json::json;
+
+    fn sample() -> (TestCase, Vec<Action>, Vec<Parameter>, Vec<Assertion>) {
+        let test_case = TestCase::builder()
+            .customer_id("cust1".to_string())
+            .id("tc1".to_string())
+            .name("create issue".to_string())
+            .description("".to_string())
+            .build();
+        let actions = vec![
+            test_action("get_1", 1, "GET", "https://abc.xyz/issues/$.create_0.output.key"),
+            test_action("create_0", 0, "POST", "https://abc.xyz/issues"),
+        ];
+        let parameters = vec![
+            Parameter::builder()
+                .customer_id("cust1".to_string())
+                .test_case_id("tc1".to_string())
+                .action_id("create_0_id".to_string())
+                .id("p1".to_string())
+                .parameter_type(ParameterType::Input)
+                .location(ParameterLocation::Body("$.summary".to_string()))
+                .value(json!("hello"))
+                .build(),
+            Parameter::builder()
+                .customer_id("cust1".to_string())
+                .test_case_id("tc1".to_string())
+                .action_id("create_0_id".to_string())
+                .id("p2".to_string())
+                .parameter_type(ParameterType::Output)
+                .location(ParameterLocation::Body("$.key".to_string()))
+                .value(json!("ISSUE-1"))
+                .build(),
+        ];
+        let assertions = vec![Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .id("as1".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.create_0.input.summary".to_string() }))
+            .right(AssertionItem::from_expression(Expression { value: "$.get_1.output.summary".to_string() }))
+            .comparison_type(ComparisonType::EqualTo)
+            .build()];
+        (test_case, actions, parameters, assertions)
+    }
+
+    #[test]
+    fn render_is_canonical_and_round_trips() {
+        let (test_case, actions, parameters, assertions) = sample();
+        let document = render(&test_case, &actions, &parameters, &assertions);
+        assert_eq!(document.actions.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(), vec!["create_0", "get_1"]);
+        assert_eq!(document.actions[0].parameters.len(), 1);
+
+        let yaml = serde_yaml::to_string(&document).unwrap();
+        let parsed: TestCaseDocument = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed, document);
+        assert!(plan(&document, &parsed).is_empty());
+    }
+
+    #[test]
+    fn plan_detects_changes() {
+        let (test_case, actions, parameters, assertions) = sample();
+        let current = render(&test_case, &actions, &parameters, &assertions);
+        let mut desired = current.clone();
+        desired.actions[0].parameters[0].value_expression = Some("$.seed.output.summary".to_string());
+        desired.actions.remove(1);
+        desired.actions.push(ActionDocument {
+            id: None,
+            name: "list_2".to_string(),
+            order: 2,
+            method: "GET".to_string(),
+            url: "https://abc.xyz/issues".to_string(),
+            base_url: None,
+            mime_type: None,
+            action_type: Default::default(),
+            delay_ms: None,
+            redirect_policy: Default::default(),
+            auth_provider_id: None,
+            volatile_paths: vec![],
+            group_id: None,
+            notes: None,
+            owner: None,
+            last_reviewed_at: None,
+            teardown: false,
+            budget: None,
+            hooks: Default::default(),
+            expected_status: None,
+            creates_resource: None,
+            parameters: vec![],
+        });
+        desired.assertions.clear();
+        assign_ids(&mut desired);
+
+        let sync_plan = plan(&current, &desired);
+        assert!(!sync_plan.test_case_changed);
+        assert_eq!(sync_plan.actions.created.len(), 1);
+        assert_eq!(sync_plan.actions.deleted[0].id, Some("get_1_id".to_string()));
+        assert!(sync_plan.actions.updated.is_empty());
+        assert_eq!(sync_plan.parameters.updated[0].parameter.id, Some("p1".to_string()));
+        assert_eq!(sync_plan.assertions.deleted.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_action_names_are_rejected() {
+        let (test_case, mut actions, parameters, assertions) = sample();
+        actions[1].name = "get_1".to_string();
+        let document = render(&test_case, &actions, &parameters, &assertions);
+        assert!(validate(&document).is_err());
+    }
+
+    #[test]
+    fn unsupported_methods_are_rejected() {
+        let (test_case, mut actions, parameters, assertions) = sample();
+        actions[0].method = "HEAD".to_string();
+        let document = render(&test_case, &actions, &parameters, &assertions);
+        assert!(matches!(validate(&document), Err(AppError::Validation(message)) if message == "Unsupported method HEAD of action get_1"));
+
+        actions[0] = Action { action_type: ActionType::Sleep, ..test_action("sleep_1", 1, "", "") };
+        let document = render(&test_case, &actions, &parameters, &assertions);
+        assert!(validate(&document).is_ok());
+    }
+}
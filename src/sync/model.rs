@@ -0,0 +1,267 @@
+use crate::action::model::{Action, ActionBudget, ActionHooks, ActionType, ExpectedStatus, RedirectPolicy, ResourceTracking};
+use crate::assertion::model::{Assertion, AssertionItem, ComparisonType, Severity, Tolerance};
+use crate::json_path::model::Expression;
+use crate::parameter::model::{MultiValueMode, Parameter, ParameterLocation, ParameterType, ValueType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Canonical, review friendly representation of a test case. Timestamps and
+/// output parameters are left out since they are derived from recordings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct TestCaseDocument {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<ActionDocument>,
+    #[serde(default)]
+    pub assertions: Vec<AssertionDocument>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ActionDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub order: usize,
+    #[serde(default)]
+    pub method: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub action_type: ActionType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+    #[serde(default)]
+    pub redirect_policy: RedirectPolicy,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_provider_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub volatile_paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reviewed_at: Option<u64>,
+    #[serde(default)]
+    pub teardown: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<ActionBudget>,
+    #[serde(default, skip_serializing_if = "ActionHooks::is_empty")]
+    pub hooks: ActionHooks,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_status: Option<ExpectedStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creates_resource: Option<ResourceTracking>,
+    #[serde(default)]
+    pub parameters: Vec<ParameterDocument>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ParameterDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub location: ParameterLocation,
+    #[serde(default)]
+    pub value: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_expression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<ValueType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multi_value_mode: Option<MultiValueMode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct AssertionDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub left: AssertionItem,
+    #[serde(default)]
+    pub right: AssertionItem,
+    pub comparison_type: ComparisonType,
+    #[serde(default)]
+    pub negate: bool,
+    #[serde(default)]
+    pub severity: Severity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_action_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tolerance: Option<Tolerance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reviewed_at: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct ScopedParameterDocument {
+    pub action_id: String,
+    pub parameter: ParameterDocument,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct Changes<T> {
+    pub created: Vec<T>,
+    pub updated: Vec<T>,
+    pub deleted: Vec<T>,
+}
+
+impl<T> Changes<T> {
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct SyncPlan {
+    pub test_case_changed: bool,
+    pub actions: Changes<ActionDocument>,
+    pub parameters: Changes<ScopedParameterDocument>,
+    pub assertions: Changes<AssertionDocument>,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        !self.test_case_changed && self.actions.is_empty() && self.parameters.is_empty() && self.assertions.is_empty()
+    }
+}
+
+impl ActionDocument {
+    pub fn from_action(action: &Action, parameters: Vec<ParameterDocument>) -> Self {
+        ActionDocument {
+            id: Some(action.id.clone()),
+            name: action.name.clone(),
+            order: action.order,
+            method: action.method.clone(),
+            url: action.url.clone(),
+            base_url: action.base_url.clone(),
+            mime_type: action.mime_type.clone(),
+            action_type: action.action_type.clone(),
+            delay_ms: action.delay_ms,
+            redirect_policy: action.redirect_policy.clone(),
+            auth_provider_id: action.auth_provider_id.clone(),
+            volatile_paths: action.volatile_paths.clone(),
+            group_id: action.group_id.clone(),
+            notes: action.notes.clone(),
+            owner: action.owner.clone(),
+            last_reviewed_at: action.last_reviewed_at,
+            teardown: action.teardown,
+            budget: action.budget.clone(),
+            hooks: action.hooks.clone(),
+            expected_status: action.expected_status,
+            creates_resource: action.creates_resource.clone(),
+            parameters,
+        }
+    }
+
+    pub fn to_action(&self, customer_id: &str, test_case_id: &str) -> Action {
+        Action::builder()
+            .customer_id(customer_id.to_string())
+            .test_case_id(test_case_id.to_string())
+            .maybe_id(self.id.clone())
+            .order(self.order)
+            .url(self.url.clone())
+            .maybe_base_url(self.base_url.clone())
+            .name(self.name.clone())
+            .maybe_mime_type(self.mime_type.clone())
+            .method(self.method.clone())
+            .action_type(self.action_type.clone())
+            .maybe_delay_ms(self.delay_ms)
+            .redirect_policy(self.redirect_policy.clone())
+            .maybe_auth_provider_id(self.auth_provider_id.clone())
+            .volatile_paths(self.volatile_paths.clone())
+            .maybe_group_id(self.group_id.clone())
+            .maybe_notes(self.notes.clone())
+            .maybe_owner(self.owner.clone())
+            .maybe_last_reviewed_at(self.last_reviewed_at)
+            .teardown(self.teardown)
+            .maybe_budget(self.budget.clone())
+            .hooks(self.hooks.clone())
+            .maybe_expected_status(self.expected_status)
+            .maybe_creates_resource(self.creates_resource.clone())
+            .build()
+    }
+}
+
+impl ParameterDocument {
+    pub fn from_parameter(parameter: &Parameter) -> Self {
+        ParameterDocument {
+            id: Some(parameter.id.clone()),
+            location: parameter.location.clone(),
+            value: parameter.value.clone(),
+            value_expression: parameter.value_expression.as_ref().map(|expression| expression.value.clone()),
+            value_type: parameter.value_type,
+            multi_value_mode: parameter.multi_value_mode.clone(),
+        }
+    }
+}
+
+impl ScopedParameterDocument {
+    pub fn to_parameter(&self, customer_id: &str, test_case_id: &str) -> Parameter {
+        Parameter::builder()
+            .customer_id(customer_id.to_string())
+            .test_case_id(test_case_id.to_string())
+            .action_id(self.action_id.clone())
+            .maybe_id(self.parameter.id.clone())
+            .parameter_type(ParameterType::Input)
+            .location(self.parameter.location.clone())
+            .value(self.parameter.value.clone())
+            .maybe_value_expression(self.parameter.value_expression.clone().map(|value| Expression { value }))
+            .maybe_value_type(self.parameter.value_type)
+            .maybe_multi_value_mode(self.parameter.multi_value_mode.clone())
+            .build()
+    }
+}
+
+impl AssertionDocument {
+    pub fn from_assertion(assertion: &Assertion) -> Self {
+        AssertionDocument {
+            id: Some(assertion.id.clone()),
+            left: assertion.left.clone(),
+            right: assertion.right.clone(),
+            comparison_type: assertion.comparison_type.clone(),
+            negate: assertion.negate,
+            severity: assertion.severity.clone(),
+            snapshot_action_id: assertion.snapshot_action_id.clone(),
+            tolerance: assertion.tolerance,
+            duration_ms: assertion.duration_ms,
+            notes: assertion.notes.clone(),
+            owner: assertion.owner.clone(),
+            last_reviewed_at: assertion.last_reviewed_at,
+        }
+    }
+
+    pub fn to_assertion(&self, customer_id: &str, test_case_id: &str) -> Assertion {
+        Assertion::builder()
+            .customer_id(customer_id.to_string())
+            .test_case_id(test_case_id.to_string())
+            .maybe_id(self.id.clone())
+            .left(self.left.clone())
+            .right(self.right.clone())
+            .comparison_type(self.comparison_type.clone())
+            .negate(self.negate)
+            .severity(self.severity.clone())
+            .maybe_snapshot_action_id(self.snapshot_action_id.clone())
+            .maybe_tolerance(self.tolerance)
+            .maybe_duration_ms(self.duration_ms)
+            .maybe_notes(self.notes.clone())
+            .maybe_owner(self.owner.clone())
+            .maybe_last_reviewed_at(self.last_reviewed_at)
+            .build()
+    }
+}
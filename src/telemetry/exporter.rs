@@ -0,0 +1,131 @@
+use crate::config::app_config;
+use crate::telemetry::model::{SpanKind, SpanRecord};
+use serde_json::{json, Value};
+use std::env;
+use std::sync::OnceLock;
+use tracing::{error, info};
+
+static CONFIG: OnceLock<TelemetryConfig> = OnceLock::new();
+
+pub struct TelemetryConfig {
+    pub endpoint: Option<String>,
+    pub service_name: String,
+    pub propagate_traceparent: bool,
+}
+
+impl TelemetryConfig {
+    fn from_env() -> Self {
+        TelemetryConfig {
+            endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()
+                .filter(|endpoint| !endpoint.is_empty())
+                .map(|endpoint| endpoint.trim_end_matches('/').to_string()),
+            service_name: env::var("OTEL_SERVICE_NAME").unwrap_or("parroton".to_string()),
+            propagate_traceparent: app_config().propagate_traceparent,
+        }
+    }
+}
+
+pub fn telemetry_config() -> &'static TelemetryConfig {
+    CONFIG.get_or_init(TelemetryConfig::from_env)
+}
+
+/// Sends finished spans to the configured OTLP/HTTP collector, e.g. Jaeger or Tempo.
+pub async fn export_spans(spans: Vec<SpanRecord>) {
+    let config = telemetry_config();
+    let Some(endpoint) = &config.endpoint else {
+        return;
+    };
+    let result = reqwest::Client::new()
+        .post(format!("{}/v1/traces", endpoint))
+        .json(&to_otlp_json(&config.service_name, &spans))
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => {
+            info!("exported {} spans", spans.len());
+        }
+        Ok(response) => {
+            error!("span export rejected with status: {}", response.status());
+        }
+        Err(err) => {
+            error!("span export failed: {}", err);
+        }
+    }
+}
+
+pub fn to_otlp_json(service_name: &str, spans: &[SpanRecord]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [string_attribute("service.name", service_name)]
+            },
+            "scopeSpans": [{
+                "scope": { "name": "parroton" },
+                "spans": spans.iter().map(to_otlp_span).collect::<Vec<Value>>()
+            }]
+        }]
+    })
+}
+
+fn to_otlp_span(span: &SpanRecord) -> Value {
+    json!({
+        "traceId": span.context.trace_id,
+        "spanId": span.context.span_id,
+        "parentSpanId": span.parent_span_id.clone().unwrap_or_default(),
+        "name": span.name,
+        "kind": match span.kind {
+            SpanKind::Internal => 1,
+            SpanKind::Client => 3,
+        },
+        "startTimeUnixNano": span.start_time.to_string(),
+        "endTimeUnixNano": span.end_time.to_string(),
+        "attributes": span.attributes.iter()
+            .map(|(key, value)| string_attribute(key, value))
+            .collect::<Vec<Value>>(),
+        "status": { "code": if span.failed { 2 } else { 1 } }
+    })
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::telemetry::model::SpanContext;
+
+    #[test]
+    fn export_payload() {
+        let run_context = SpanContext::root();
+        let http_context = run_context.child();
+        let run_span = SpanRecord::start("run".to_string(), run_context.clone(), None, SpanKind::Internal)
+            .finish(false);
+        let http_span = SpanRecord::start("GET".to_string(), http_context.clone(), Some(&run_context), SpanKind::Client)
+            .attribute("http.response.status_code", "500".to_string())
+            .finish(true);
+
+        let payload = to_otlp_json("parroton", &[run_span, http_span]);
+        let spans = &payload["resourceSpans"][0]["scopeSpans"][0]["spans"];
+
+        assert_eq!(payload["resourceSpans"][0]["resource"]["attributes"][0]["value"]["stringValue"], "parroton");
+        assert_eq!(spans[0]["parentSpanId"], "");
+        assert_eq!(spans[1]["traceId"], run_context.trace_id.as_str());
+        assert_eq!(spans[1]["parentSpanId"], run_context.span_id.as_str());
+        assert_eq!(spans[1]["kind"], 3);
+        assert_eq!(spans[1]["status"]["code"], 2);
+        assert_eq!(spans[1]["attributes"][0]["key"], "http.response.status_code");
+    }
+
+    #[test]
+    fn traceparent_format() {
+        let context = SpanContext::root();
+        let traceparent = context.traceparent();
+        let parts: Vec<&str> = traceparent.split('-').collect();
+
+        assert_eq!(parts, vec!["00", context.trace_id.as_str(), context.span_id.as_str(), "01"]);
+        assert_eq!(parts[1].len(), 32);
+        assert_eq!(parts[2].len(), 16);
+        assert_eq!(context.child().trace_id, context.trace_id);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config::LogFormat;
+use serde_json::{Map, Value};
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::time::{FormatTime, SystemTime};
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+pub fn init_logging(format: &LogFormat) {
+    match format {
+        LogFormat::Text => tracing_subscriber::fmt::init(),
+        LogFormat::Json => tracing_subscriber::registry()
+            .with(SpanFieldsLayer)
+            .with(tracing_subscriber::fmt::layer()
+                .event_format(JsonFormat)
+                .with_filter(LevelFilter::INFO))
+            .init(),
+    }
+}
+
+/// Keeps the fields of every span as JSON, so [`JsonFormat`] can put e.g. the `run_id` of an
+/// enclosing span on each line logged within it.
+pub struct SpanFieldsLayer;
+
+struct SpanFields(Map<String, Value>);
+
+impl<S> Layer<S> for SpanFieldsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let mut fields = Map::new();
+        attrs.record(&mut JsonVisitor(&mut fields));
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields));
+        }
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+                values.record(&mut JsonVisitor(fields));
+            }
+        }
+    }
+}
+
+/// Writes one JSON object per event with the fields of its enclosing spans lifted to the top
+/// level, inner spans and the event itself winning on conflicts.
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(timestamp));
+        line.insert("level".to_string(), Value::String(event.metadata().level().to_string()));
+        line.insert("target".to_string(), Value::String(event.metadata().target().to_string()));
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = vec![];
+            for span in scope.from_root() {
+                spans.push(Value::String(span.name().to_string()));
+                if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                    line.extend(fields.clone());
+                }
+            }
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        event.record(&mut JsonVisitor(&mut line));
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), Value::String(format!("{:?}", value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use std::sync::{Arc, Mutex};
+    use tracing::{info, info_span};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(bytes);
+            Ok(bytes.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_lines_carry_span_fields() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(SpanFieldsLayer)
+            .with(tracing_subscriber::fmt::layer()
+                .event_format(JsonFormat)
+                .with_writer(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let run_span = info_span!("run", run_id = "r1", test_case_id = "tc1");
+            let _entered = run_span.enter();
+            info!(action = "login_0", status_code = 200u64, "executed action");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line: Value = serde_json::from_str(output.trim()).unwrap();
+        assert_eq!(line["message"], "executed action");
+        assert_eq!(line["level"], "INFO");
+        assert_eq!(line["run_id"], "r1");
+        assert_eq!(line["test_case_id"], "tc1");
+        assert_eq!(line["action"], "login_0");
+        assert_eq!(line["status_code"], 200);
+        assert_eq!(line["spans"], serde_json::json!(["run"]));
+    }
+}
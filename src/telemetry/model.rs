@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanContext {
+    pub trace_id: String,
+    pub span_id: String,
+}
+
+impl SpanContext {
+    pub fn root() -> Self {
+        SpanContext {
+            trace_id: uuid::Uuid::new_v4().simple().to_string(),
+            span_id: new_span_id(),
+        }
+    }
+
+    pub fn child(&self) -> Self {
+        SpanContext {
+            trace_id: self.trace_id.clone(),
+            span_id: new_span_id(),
+        }
+    }
+
+    /// W3C trace context header value, always marked as sampled.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+}
+
+fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpanKind {
+    Internal,
+    Client,
+}
+
+#[derive(Clone, Debug)]
+pub struct SpanRecord {
+    pub context: SpanContext,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub kind: SpanKind,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub attributes: Vec<(String, String)>,
+    pub failed: bool,
+}
+
+impl SpanRecord {
+    pub fn start(name: String, context: SpanContext, parent: Option<&SpanContext>, kind: SpanKind) -> Self {
+        SpanRecord {
+            context,
+            parent_span_id: parent.map(|parent| parent.span_id.clone()),
+            name,
+            kind,
+            start_time: now_nanos(),
+            end_time: 0,
+            attributes: vec![],
+            failed: false,
+        }
+    }
+
+    pub fn attribute(mut self, key: &str, value: String) -> Self {
+        self.attributes.push((key.to_string(), value));
+        self
+    }
+
+    pub fn finish(mut self, failed: bool) -> Self {
+        self.end_time = now_nanos();
+        self.failed = failed;
+        self
+    }
+}
+
+fn now_nanos() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos() as u64
+}
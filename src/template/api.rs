@@ -0,0 +1,102 @@
+use crate::action::model::Action;
+use crate::api::{ApiResponse, AppError, FieldError};
+use crate::persistence::model::QueryResult;
+use crate::persistence::repo::Repository;
+use crate::revision::history::record_before_change;
+use crate::template::library::{apply_template, save_template, ApplyTemplateCommand, SaveTemplateCommand};
+use crate::template::model::Template;
+use crate::validation::{AppJson, ValidJson, Validate, check_not_blank};
+use axum::extract::{Path, Query, State};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+pub async fn create_template(
+    State(repository): State<Repository>,
+    ValidJson(payload): ValidJson<CreateTemplatePayload>,
+) -> Result<ApiResponse<Template>, AppError> {
+    let result = save_template(&repository, SaveTemplateCommand {
+        customer_id: "eren".to_string(),
+        test_case_id: payload.test_case_id,
+        name: payload.name,
+        description: payload.description,
+        from_order: payload.from_order,
+        to_order: payload.to_order,
+    }).await;
+    ApiResponse::from(result)
+}
+
+pub async fn get_template(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Template>, AppError> {
+    let result = repository.templates().get(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn list_templates(
+    State(repository): State<Repository>,
+    Query(params): Query<ListTemplateParams>,
+) -> Result<ApiResponse<QueryResult<Template>>, AppError> {
+    let result = repository.templates()
+        .list(&"eren".to_string(), params.next_page_key, params.keyword)
+        .await;
+    ApiResponse::from(result)
+}
+
+pub async fn delete_template(
+    Path(id): Path<String>,
+    State(repository): State<Repository>,
+) -> Result<ApiResponse<Template>, AppError> {
+    let result = repository.templates().delete(&"eren".to_string(), &id).await;
+    ApiResponse::from_option(result)
+}
+
+pub async fn apply_template_to_test_case(
+    Path(test_case_id): Path<String>,
+    State(repository): State<Repository>,
+    AppJson(payload): AppJson<ApplyTemplatePayload>,
+) -> Result<ApiResponse<Vec<Action>>, AppError> {
+    record_before_change(&repository, &"eren".to_string(), &test_case_id, "template apply").await;
+    let result = apply_template(&repository, ApplyTemplateCommand {
+        customer_id: "eren".to_string(),
+        test_case_id,
+        template_id: payload.template_id,
+        prefix: payload.prefix.unwrap_or_default(),
+        order: payload.order,
+    }).await;
+    ApiResponse::from(result)
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct CreateTemplatePayload {
+    pub test_case_id: String,
+    pub name: String,
+    pub description: String,
+    pub from_order: usize,
+    pub to_order: usize,
+}
+
+#[derive(Deserialize, Clone, ToSchema)]
+pub struct ApplyTemplatePayload {
+    pub template_id: String,
+    pub prefix: Option<String>,
+    pub order: usize,
+}
+
+#[derive(Deserialize, Clone, IntoParams)]
+pub struct ListTemplateParams {
+    pub next_page_key: Option<String>,
+    pub keyword: Option<String>,
+}
+
+impl Validate for CreateTemplatePayload {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors: Vec<FieldError> = check_not_blank("name", &self.name).into_iter()
+            .chain(check_not_blank("test_case_id", &self.test_case_id))
+            .collect();
+        if self.from_order > self.to_order {
+            errors.push(FieldError::new("to_order", "must not be less than from_order"));
+        }
+        errors
+    }
+}
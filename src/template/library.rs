@@ -0,0 +1,295 @@
+use crate::action::model::Action;
+use crate::action::service::ActionsTable;
+use crate::api::AppError;
+use crate::assertion::model::{Assertion, AssertionItem};
+use crate::assertion::service::AssertionsTable;
+use crate::change::model::ChangeKind;
+use crate::json_path::model::Expression;
+use crate::parameter::model::Parameter;
+use crate::parameter::service::ParametersTable;
+use crate::persistence::repo::{current_timestamp, Repository, Table, TransactionItem};
+use crate::quota::guard::{guard_test_case_write, TestCaseWrite};
+use crate::template::model::Template;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+
+pub struct SaveTemplateCommand {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub name: String,
+    pub description: String,
+    pub from_order: usize,
+    pub to_order: usize,
+}
+
+pub struct ApplyTemplateCommand {
+    pub customer_id: String,
+    pub test_case_id: String,
+    pub template_id: String,
+    pub prefix: String,
+    pub order: usize,
+}
+
+pub struct TemplateInstance {
+    pub actions: Vec<Action>,
+    pub parameters: Vec<Parameter>,
+    pub assertions: Vec<Assertion>,
+}
+
+pub async fn save_template(repository: &Repository, command: SaveTemplateCommand) -> Result<Template, AppError> {
+    let mut actions: Vec<Action> = repository.actions()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?
+        .into_iter()
+        .filter(|action| action.order >= command.from_order && action.order <= command.to_order)
+        .collect();
+    if actions.is_empty() {
+        return Err(AppError::Validation(format!("No actions found between orders {} and {}", command.from_order, command.to_order)));
+    }
+    actions.sort();
+    let action_ids: HashSet<String> = actions.iter().map(|action| action.id.clone()).collect();
+    let action_names: HashSet<String> = actions.iter().map(|action| action.name.clone()).collect();
+    let parameters: Vec<Parameter> = repository.parameters()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?
+        .into_iter()
+        .filter(|parameter| action_ids.contains(&parameter.action_id))
+        .collect();
+    let assertions: Vec<Assertion> = repository.assertions()
+        .list_all(&command.customer_id, &command.test_case_id)
+        .await?
+        .into_iter()
+        .filter(|assertion| {
+            let referenced = referenced_action_names_of_assertion(assertion);
+            !referenced.is_empty() && referenced.is_subset(&action_names)
+        })
+        .collect();
+    repository.templates()
+        .create(Template::builder()
+            .customer_id(command.customer_id)
+            .name(command.name)
+            .description(command.description)
+            .actions(actions)
+            .parameters(parameters)
+            .assertions(assertions)
+            .build())
+        .await
+}
+
+pub async fn apply_template(repository: &Repository, command: ApplyTemplateCommand) -> Result<Vec<Action>, AppError> {
+    repository.test_cases()
+        .get(command.customer_id.clone(), command.test_case_id.clone())
+        .await?
+        .ok_or(AppError::NotFound("Test case not found!".to_string()))?;
+    let template = repository.templates()
+        .get(&command.customer_id, &command.template_id)
+        .await?
+        .ok_or(AppError::NotFound("Template not found!".to_string()))?;
+    let instance = instantiate_template(&template, &command.test_case_id, &command.prefix, command.order);
+    let permit = guard_test_case_write(repository, &command.customer_id, &command.test_case_id, TestCaseWrite {
+        actions: &instance.actions,
+        parameters: &instance.parameters,
+        assertions: &instance.assertions,
+    }).await?;
+    let existing_actions = repository.actions()
+        .list_all(command.customer_id.clone(), command.test_case_id.clone())
+        .await?;
+    let version = Some(current_timestamp());
+    let shifted: Vec<(Action, &Action)> = existing_actions.iter()
+        .filter(|action| action.order >= command.order)
+        .map(|action| (Action { order: action.order + instance.actions.len(), updated_at: version, ..action.clone() }, action))
+        .collect();
+    let mut items: Vec<TransactionItem> = shifted.iter()
+        .map(|(action, previous)| ActionsTable::transact_put(action, Some(previous)))
+        .collect();
+    items.extend(instance.actions.iter().map(|action| ActionsTable::transact_create(action, &permit)));
+    items.extend(instance.parameters.iter().map(|parameter| ParametersTable::transact_put(parameter, None)));
+    items.extend(instance.assertions.iter().map(|assertion| AssertionsTable::transact_put(assertion, None)));
+    // shifting and inserting together keeps the orders intact when a write fails
+    repository.transact_write(items).await?;
+    shifted.iter().for_each(|(action, _)| ActionsTable::publish_change(action, ChangeKind::Updated));
+    instance.actions.iter().for_each(|action| ActionsTable::publish_change(action, ChangeKind::Created));
+    instance.parameters.iter().for_each(|parameter| ParametersTable::publish_change(parameter, ChangeKind::Created));
+    instance.assertions.iter().for_each(|assertion| AssertionsTable::publish_change(assertion, ChangeKind::Created));
+    Ok(instance.actions)
+}
+
+pub fn instantiate_template(template: &Template, test_case_id: &str, prefix: &str, order: usize) -> TemplateInstance {
+    let renames: HashMap<String, String> = template.actions.iter()
+        .map(|action| (action.name.clone(), prefixed_name(prefix, &action.name)))
+        .collect();
+    let first_order = template.actions.iter().map(|action| action.order).min().unwrap_or(0);
+    let mut action_ids: HashMap<String, String> = HashMap::new();
+    let actions: Vec<Action> = template.actions.iter()
+        .map(|action| {
+            let new_action = Action {
+                test_case_id: test_case_id.to_string(),
+                id: uuid::Uuid::new_v4().to_string(),
+                order: order + action.order - first_order,
+                url: rewrite_expression(&action.url, &renames),
+                name: prefixed_name(prefix, &action.name),
+                group_id: None,
+                created_at: None,
+                updated_at: None,
+                ..action.clone()
+            };
+            action_ids.insert(action.id.clone(), new_action.id.clone());
+            new_action
+        })
+        .collect();
+    let parameters: Vec<Parameter> = template.parameters.iter()
+        .filter_map(|parameter| {
+            action_ids.get(&parameter.action_id).map(|action_id| Parameter {
+                test_case_id: test_case_id.to_string(),
+                action_id: action_id.clone(),
+                id: uuid::Uuid::new_v4().to_string(),
+                value_expression: parameter.value_expression.as_ref()
+                    .map(|expression| rewrite(expression, &renames)),
+                created_at: None,
+                updated_at: None,
+                ..parameter.clone()
+            })
+        })
+        .collect();
+    let assertions: Vec<Assertion> = template.assertions.iter()
+        .map(|assertion| Assertion {
+            test_case_id: test_case_id.to_string(),
+            id: uuid::Uuid::new_v4().to_string(),
+            left: rewrite_assertion_item(&assertion.left, &renames),
+            right: rewrite_assertion_item(&assertion.right, &renames),
+            created_at: None,
+            updated_at: None,
+            ..assertion.clone()
+        })
+        .collect();
+    TemplateInstance {
+        actions,
+        parameters,
+        assertions,
+    }
+}
+
+fn prefixed_name(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}_{}", prefix, name)
+    }
+}
+
+fn action_reference_regex() -> Regex {
+    Regex::new(r"\$\.([^.\[\]\s/]+)").unwrap()
+}
+
+pub fn referenced_action_names(value: &str) -> HashSet<String> {
+    action_reference_regex()
+        .captures_iter(value)
+        .map(|captures| captures.get(1).unwrap().as_str().to_string())
+        .collect()
+}
+
+pub fn rewrite_expression(value: &str, renames: &HashMap<String, String>) -> String {
+    action_reference_regex()
+        .replace_all(value, |captures: &regex::Captures| {
+            let name = captures.get(1).unwrap().as_str();
+            format!("$.{}", renames.get(name).map_or(name, |renamed| renamed.as_str()))
+        })
+        .to_string()
+}
+
+fn rewrite(expression: &Expression, renames: &HashMap<String, String>) -> Expression {
+    Expression {
+        value: rewrite_expression(&expression.value, renames),
+    }
+}
+
+fn rewrite_assertion_item(item: &AssertionItem, renames: &HashMap<String, String>) -> AssertionItem {
+    let mut rewritten = item.clone();
+    if let Some(value_provider) = rewritten.value_provider.as_mut() {
+        value_provider.expression = value_provider.expression.as_ref().map(|expression| rewrite(expression, renames));
+    }
+    if let Some(function) = rewritten.function.as_mut() {
+        function.parameters.iter_mut().for_each(|value_provider| {
+            value_provider.expression = value_provider.expression.as_ref().map(|expression| rewrite(expression, renames));
+        });
+    }
+    rewritten
+}
+
+fn expressions_of_item(item: &AssertionItem) -> Vec<&Expression> {
+    let mut expressions: Vec<&Expression> = vec![];
+    if let Some(expression) = item.value_provider.as_ref().and_then(|vp| vp.expression.as_ref()) {
+        expressions.push(expression);
+    }
+    if let Some(function) = &item.function {
+        expressions.extend(function.parameters.iter().filter_map(|vp| vp.expression.as_ref()));
+    }
+    expressions
+}
+
+pub fn referenced_action_names_of_assertion(assertion: &Assertion) -> HashSet<String> {
+    expressions_of_item(&assertion.left)
+        .into_iter()
+        .chain(expressions_of_item(&assertion.right))
+        .flat_map(|expression| referenced_action_names(&expression.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::fixtures::test_action;
+    use crate::assertion::model::ComparisonType;
+    use crate::parameter::model::{ParameterLocation, ParameterType};
+    use serde_json::json;
+
+    #[test]
+    fn rewrite_action_references() {
+        let renames = HashMap::from([("login_0".to_string(), "auth_login_0".to_string())]);
+        assert_eq!(rewrite_expression("$.login_0.output.token", &renames), "$.auth_login_0.output.token");
+        assert_eq!(rewrite_expression("https://abc.xyz/$.login_0.output.id/items", &renames), "https://abc.xyz/$.auth_login_0.output.id/items");
+        assert_eq!(rewrite_expression("$.other_1.output.token", &renames), "$.other_1.output.token");
+    }
+
+    #[test]
+    fn instantiate_with_prefix() {
+        let login = test_action("login_3", 3, "POST", "https://abc.xyz/login");
+        let profile = test_action("profile_4", 4, "GET", "https://abc.xyz/users/$.login_3.output.userId");
+        let parameter = Parameter::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .action_id(profile.id.clone())
+            .parameter_type(ParameterType::Input)
+            .location(ParameterLocation::Header("x-token".to_string()))
+            .value(json!("abc"))
+            .value_expression(Expression { value: "$.login_3.output.token".to_string() })
+            .build();
+        let assertion = Assertion::builder()
+            .customer_id("cust1".to_string())
+            .test_case_id("tc1".to_string())
+            .left(AssertionItem::from_expression(Expression { value: "$.login_3.output.userId".to_string() }))
+            .right(AssertionItem::from_expression(Expression { value: "$.profile_4.output.id".to_string() }))
+            .comparison_type(ComparisonType::EqualTo)
+            .build();
+        let template = Template::builder()
+            .customer_id("cust1".to_string())
+            .name("login flow".to_string())
+            .description("".to_string())
+            .actions(vec![login, profile.clone()])
+            .parameters(vec![parameter])
+            .assertions(vec![assertion])
+            .build();
+
+        let instance = instantiate_template(&template, "tc2", "auth", 0);
+
+        assert_eq!(instance.actions.iter().map(|a| (a.name.clone(), a.order)).collect::<Vec<_>>(),
+                   vec![("auth_login_3".to_string(), 0), ("auth_profile_4".to_string(), 1)]);
+        assert_eq!(instance.actions[1].url, "https://abc.xyz/users/$.auth_login_3.output.userId");
+        assert!(instance.actions.iter().all(|a| a.test_case_id == "tc2"));
+        assert_eq!(instance.parameters[0].action_id, instance.actions[1].id);
+        assert_ne!(instance.parameters[0].action_id, profile.id);
+        assert_eq!(instance.parameters[0].value_expression, Some(Expression { value: "$.auth_login_3.output.token".to_string() }));
+        assert_eq!(referenced_action_names_of_assertion(&instance.assertions[0]),
+                   HashSet::from(["auth_login_3".to_string(), "auth_profile_4".to_string()]));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::action::model::Action;
+use crate::assertion::model::Assertion;
+use crate::parameter::model::Parameter;
+use bon::Builder;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Clone, Builder, ToSchema)]
+pub struct Template {
+    pub customer_id: String,
+    #[builder(default = uuid::Uuid::new_v4().to_string())]
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub actions: Vec<Action>,
+    pub parameters: Vec<Parameter>,
+    pub assertions: Vec<Assertion>,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+}
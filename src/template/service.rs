@@ -0,0 +1,65 @@
+use crate::api::AppError;
+use crate::persistence::model::{ListItemsRequest, QueryResult};
+use crate::persistence::repo::Table;
+use crate::template::model::Template;
+use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct TemplateOperations {
+    pub(crate) client: Arc<Client>,
+}
+
+pub(crate) struct TemplatesTable();
+
+impl Table<Template> for TemplatesTable {
+    fn table_name() -> String {
+        "templates".to_string()
+    }
+
+    fn partition_key_name() -> String {
+        "customer_id".to_string()
+    }
+
+    fn sort_key_name() -> String {
+        "id".to_string()
+    }
+
+    fn partition_key_from_entity(entity: &Template) -> (String, AttributeValue) {
+        Self::partition_key(entity.customer_id.clone())
+    }
+
+    fn sort_key_from_entity(entity: &Template) -> (String, AttributeValue) {
+        Self::sort_key(entity.id.clone())
+    }
+}
+
+impl TemplateOperations {
+    pub async fn create(&self, template: Template) -> Result<Template, AppError> {
+        TemplatesTable::put_item(self.client.clone(), template).await
+    }
+
+    pub async fn get(&self, customer_id: &String, id: &String) -> Result<Option<Template>, AppError> {
+        TemplatesTable::get_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+
+    pub async fn list(
+        &self,
+        customer_id: &String,
+        next_page_key: Option<String>,
+        keyword: Option<String>,
+    ) -> Result<QueryResult<Template>, AppError> {
+        TemplatesTable::list_items(self.client.clone(), ListItemsRequest::builder()
+            .partition_key(customer_id.clone())
+            .maybe_next_page_key(next_page_key)
+            .maybe_filter_expression(keyword.clone().map(|_| "contains(#name, :keyword)".to_string()))
+            .maybe_expression_attribute_names(keyword.clone().map(|_| HashMap::from([("#name".to_string(), "name".to_string())])))
+            .maybe_expression_attribute_values(keyword.map(|keyword| HashMap::from([(":keyword".to_string(), AttributeValue::S(keyword))])))
+            .build()).await
+    }
+
+    pub async fn delete(&self, customer_id: &String, id: &String) -> Result<Option<Template>, AppError> {
+        TemplatesTable::delete_item(self.client.clone(), customer_id.clone(), id.clone()).await
+    }
+}
@@ -0,0 +1,134 @@
+use crate::api::{AppError, FieldError};
+use crate::json_path::model::Expression;
+use axum::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde_json_path::JsonPath;
+
+const DATA_ERROR_PREFIX: &str = "Failed to deserialize the JSON body into the target type: ";
+
+/// Checks a request payload before it reaches the handler, reporting every offending field at once.
+pub trait Validate {
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+impl Validate for Expression {
+    fn validate(&self) -> Vec<FieldError> {
+        check_expression("value", &self.value).into_iter().collect()
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Vec<FieldError> {
+        self.as_ref().map_or(vec![], Validate::validate)
+    }
+}
+
+/// `Json` whose rejections, e.g. an unknown enum variant, use the common error body.
+pub struct AppJson<T>(pub T);
+
+/// `AppJson` that also runs the payload's `Validate` checks.
+pub struct ValidJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for AppJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(request, state)
+            .await
+            .map(|Json(value)| AppJson(value))
+            .map_err(AppError::from)
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let AppJson(value) = AppJson::<T>::from_request(request, state).await?;
+        let errors = value.validate();
+        if errors.is_empty() {
+            Ok(ValidJson(value))
+        } else {
+            Err(AppError::InvalidFields(errors))
+        }
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(err) => AppError::InvalidFields(vec![data_error_field(&err.body_text())]),
+            other => AppError::Validation(other.body_text()),
+        }
+    }
+}
+
+/// Data errors read `<prefix><path>: <reason>`, the path is left out when the body itself has the wrong shape.
+fn data_error_field(body_text: &str) -> FieldError {
+    let detail = body_text.strip_prefix(DATA_ERROR_PREFIX).unwrap_or(body_text);
+    match detail.split_once(": ") {
+        Some((path, reason)) if !path.is_empty() && !path.contains(char::is_whitespace) => FieldError::new(path, reason),
+        _ => FieldError::new("", detail),
+    }
+}
+
+pub fn check_not_blank(field: &str, value: &str) -> Option<FieldError> {
+    value.trim().is_empty().then(|| FieldError::new(field, "must not be blank"))
+}
+
+/// Base URLs are joined with relative action URLs, so only an http(s) origin with an optional path fits.
+pub fn check_base_url(field: &str, value: &str) -> Option<FieldError> {
+    let valid = reqwest::Url::parse(value)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.query().is_none() && url.fragment().is_none());
+    (!valid).then(|| FieldError::new(field, "must be an http or https URL without query or fragment"))
+}
+
+/// Action names are referred to from expressions, e.g. `$.orders_0.response.body.id`.
+pub fn check_action_name(field: &str, value: &str) -> Option<FieldError> {
+    let valid = !value.is_empty() && value.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    (!valid).then(|| FieldError::new(field, "may only contain lowercase letters, digits and underscores"))
+}
+
+pub fn check_expression(field: &str, expression: &str) -> Option<FieldError> {
+    JsonPath::parse(expression)
+        .err()
+        .map(|err| FieldError::new(field, format!("is not a valid JSONPath expression: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_field_from_data_error() {
+        let error = data_error_field("Failed to deserialize the JSON body into the target type: comparison_type: unknown variant `Equals` at line 1 column 30");
+        assert_eq!(error, FieldError::new("comparison_type", "unknown variant `Equals` at line 1 column 30"));
+
+        let error = data_error_field("Failed to deserialize the JSON body into the target type: invalid type: string \"x\", expected struct Payload");
+        assert_eq!(error.field, "");
+
+        assert!(check_expression("value", "$.login_0.output.token").is_none());
+        assert!(check_base_url("value", "https://staging.abc.xyz/api").is_none());
+        assert!(check_base_url("value", "staging.abc.xyz").is_some());
+        assert!(check_base_url("value", "https://abc.xyz?env=staging").is_some());
+        assert_eq!(check_expression("value", "$.login_0.[").unwrap().field, "value");
+        assert!(check_not_blank("name", "  ").is_some());
+        assert!(check_action_name("value", "create_order_2").is_none());
+        assert!(check_action_name("value", "Create_Order").is_some());
+        assert!(check_action_name("value", " orders ").is_some());
+        assert!(check_action_name("value", "").is_some());
+    }
+}